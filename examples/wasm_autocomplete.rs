@@ -0,0 +1,58 @@
+//! Client-side autocomplete with a letter trie compiled to WebAssembly.
+//!
+//! Build the module for the browser without the file, thread, and timing code:
+//!
+//! ```text
+//! cargo build --example wasm_autocomplete --target wasm32-unknown-unknown --no-default-features --features wasm
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/debug/examples/wasm_autocomplete.wasm
+//! ```
+//!
+//! Then from JavaScript, with the word list fetched as text:
+//!
+//! ```text
+//! const trie = Autocomplete.new_from_words(wordListText);
+//! trie.contains("cross");      // true
+//! trie.suggest("cr", 10);      // ["crate", "cross", ...]
+//! ```
+//!
+//! Running it natively (`cargo run --example wasm_autocomplete --features wasm`) goes through the same functions.
+
+use letter_trie::*;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Autocomplete {
+    trie: NoParentLetterTrie,
+}
+
+#[wasm_bindgen]
+impl Autocomplete {
+    /// Build the trie from a word list with one word per line, which is how the list usually arrives from
+    /// `fetch()`.
+    pub fn new_from_words(words: &str) -> Autocomplete {
+        Autocomplete {
+            trie: NoParentLetterTrie::from_words(words.lines()),
+        }
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.trie.contains(word)
+    }
+
+    pub fn suggest(&self, prefix: &str, max_count: usize) -> Vec<String> {
+        self.trie.suggest(prefix, max_count)
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.trie.to_fixed_node().word_count
+    }
+}
+
+fn main() {
+    let autocomplete =
+        Autocomplete::new_from_words("creature\ncreate\ncross\ncrossing\nan\nand\nazure\n");
+    println!("{} words", autocomplete.word_count());
+    println!("contains(\"cross\") = {}", autocomplete.contains("cross"));
+    println!("contains(\"cros\") = {}", autocomplete.contains("cros"));
+    println!("suggest(\"cr\", 3) = {:?}", autocomplete.suggest("cr", 3));
+}
@@ -0,0 +1,8 @@
+// Arbitrary bytes as a word file. See letter_trie::fuzzing::check_reader_input().
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    letter_trie::fuzzing::check_reader_input(data);
+});
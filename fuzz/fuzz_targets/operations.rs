@@ -0,0 +1,9 @@
+// Arbitrary bytes as a sequence of operations checked against a model. See
+// letter_trie::fuzzing::check_operations().
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    letter_trie::fuzzing::check_operations(data);
+});
@@ -0,0 +1,95 @@
+#ifndef LETTER_TRIE_H
+#define LETTER_TRIE_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The version of the format written by `ConfiguredLetterTrie::write_binary()`, which has the options block.
+#define BINARY_OPTIONS_VERSION 2
+
+// The call succeeded.
+#define LETTER_TRIE_OK 0
+
+// A required pointer argument was null.
+#define LETTER_TRIE_ERR_NULL_POINTER 1
+
+// A string argument was not valid UTF-8.
+#define LETTER_TRIE_ERR_INVALID_UTF8 2
+
+// The word file couldn't be opened or read.
+#define LETTER_TRIE_ERR_IO 3
+
+// A word in the trie contains a NUL character so it can't be returned as a C string.
+#define LETTER_TRIE_ERR_INTERIOR_NUL 4
+
+// The library panicked. The trie passed in, if any, should not be used again except to free it.
+#define LETTER_TRIE_ERR_PANIC 5
+
+typedef struct NoParentLetterTrie NoParentLetterTrie;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create a trie from a word file with one word per line and store it in `*out_trie`.
+//
+// The trie must eventually be released with `letter_trie_free()`. On failure `*out_trie` is set to null.
+//
+// # Safety
+//
+// `path` must be null or point to a NUL-terminated string, and `out_trie` must be null or point to writable
+// memory for one pointer.
+int letter_trie_new_from_file(const char *path,
+                              NoParentLetterTrie **out_trie);
+
+// Set `*out_found` to whether `word` is a whole word in the trie.
+//
+// # Safety
+//
+// `trie` must be null or a pointer returned by `letter_trie_new_from_file()` that hasn't been freed. `word` must
+// be null or point to a NUL-terminated string, and `out_found` must be null or point to writable memory.
+int letter_trie_contains(const NoParentLetterTrie *trie,
+                         const char *word,
+                         bool *out_found);
+
+// Get up to `max_count` completions of `prefix`, shortest first, as a heap-allocated array of C strings.
+//
+// On success `*out_words` points to an array of `*out_count` strings that must be released with a single call to
+// `letter_trie_free_words()`. When there are no completions `*out_words` is null and `*out_count` is zero.
+//
+// # Safety
+//
+// `trie` must be null or a live pointer returned by `letter_trie_new_from_file()`. `prefix` must be null or point
+// to a NUL-terminated string, and `out_words` and `out_count` must be null or point to writable memory.
+int letter_trie_suggest(const NoParentLetterTrie *trie,
+                        const char *prefix,
+                        size_t max_count,
+                        char ***out_words,
+                        size_t *out_count);
+
+// Release an array of words returned by `letter_trie_suggest()`. Passing null does nothing.
+//
+// # Safety
+//
+// `words` and `count` must be exactly the values that `letter_trie_suggest()` produced, and the array must not be
+// used or freed again afterward.
+void letter_trie_free_words(char **words,
+                            size_t count);
+
+// Release a trie created by `letter_trie_new_from_file()`. Passing null does nothing.
+//
+// # Safety
+//
+// `trie` must be null or a pointer returned by `letter_trie_new_from_file()` that hasn't already been freed.
+void letter_trie_free(NoParentLetterTrie *trie);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LETTER_TRIE_H */
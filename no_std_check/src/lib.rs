@@ -0,0 +1,52 @@
+//! The parts of `letter_trie` that work without the standard library, used from a `#![no_std]` crate. Building
+//! this for `thumbv7em-none-eabihf` or any other target without `std` fails if one of them starts to need it.
+//! The final program supplies the global allocator and panic handler, so a library like this one doesn't.
+
+// The tests run on the host with the standard library.
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use letter_trie::{FixedNode, LetterTrie, NoParentLetterTrie, TrieQuery};
+
+/// What `summarize()` found out about a small dictionary.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub root: FixedNode,
+    pub found: Option<FixedNode>,
+    pub contains: bool,
+    pub words: Vec<String>,
+}
+
+/// Build a trie from `words`, add `extra`, and look up `prefix` with each of the queries that has to work without
+/// `std`.
+pub fn summarize(words: &[&str], extra: &str, prefix: &str) -> Summary {
+    let mut trie = NoParentLetterTrie::from_words(words);
+    trie.insert(extra);
+    Summary {
+        root: trie.to_fixed_node(),
+        found: trie.find(prefix),
+        contains: trie.contains(prefix),
+        words: trie
+            .words()
+            .filter(|word| word.starts_with(prefix))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_dictionary() {
+        let summary = summarize(&["cross", "crossing", "an"], "Crossed", "cross");
+        assert_eq!(4, summary.root.word_count);
+        assert_eq!(2, summary.found.unwrap().child_count);
+        assert!(summary.contains);
+        assert_eq!(vec!["cross", "crossed", "crossing"], summary.words);
+    }
+}
@@ -0,0 +1,246 @@
+//! A global allocator for the tests that counts the allocations made on each thread, so that a test can check that
+//! a query doesn't allocate while other tests run on other threads. Reallocations are counted as allocations and
+//! also on their own. The bytes in use on each thread are tracked as well, so that a test can see how much memory a
+//! trie built on that thread holds.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use crate::*;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static REALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    // Wrapping, since memory allocated on one thread can be freed on another.
+    static LIVE_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_one() {
+    // The thread local can already be gone while a thread is shutting down.
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+fn add_live_bytes(added: usize, removed: usize) {
+    let _ = LIVE_BYTES
+        .try_with(|bytes| bytes.set(bytes.get().wrapping_add(added).wrapping_sub(removed)));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_one();
+        add_live_bytes(layout.size(), 0);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_one();
+        add_live_bytes(layout.size(), 0);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_one();
+        let _ = REALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        add_live_bytes(new_size, layout.size());
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        add_live_bytes(0, layout.size());
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// Run f and return what it returns along with the number of allocations it made on this thread.
+pub(crate) fn allocations_during<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+// Run f and return what it returns along with the number of reallocations it made on this thread.
+pub(crate) fn reallocations_during<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = REALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, REALLOCATIONS.with(Cell::get) - before)
+}
+
+// Run f and return what it returns along with the number of bytes it allocated on this thread that are still in
+// use, such as the nodes of a trie it built.
+pub(crate) fn bytes_kept_from<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = LIVE_BYTES.with(Cell::get);
+    let result = f();
+    (result, LIVE_BYTES.with(Cell::get).wrapping_sub(before))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_queries_do_not_allocate<T: LetterTrie>(t: &T, words: &[String]) {
+        let chars: Vec<Vec<char>> = words.iter().map(|word| word.chars().collect()).collect();
+        let (found, allocations) = allocations_during(|| {
+            let mut found = 0;
+            for (word, word_chars) in words.iter().zip(&chars) {
+                // Every word in the medium file is ASCII, so contains() takes the path without to_lowercase().
+                if t.contains(word) && t.contains_prefix(word) {
+                    found += 1;
+                }
+                if t.contains_chars(word_chars.iter().copied())
+                    && t.contains_prefix_chars(word_chars.iter().copied())
+                    && t.contains_chars(word.chars().map(|c| c.to_ascii_uppercase()))
+                {
+                    found += 1;
+                }
+                if t.find_chars(word_chars.iter().copied().chain(Some('#')))
+                    .is_none()
+                    && !t.contains_prefix("#")
+                {
+                    found += 1;
+                }
+                let mut cursor = t.cursor();
+                if cursor.step_chars(word_chars.iter().copied())
+                    && cursor.is_word()
+                    && !cursor.step('#')
+                {
+                    cursor.reset();
+                    if word.chars().all(|c| cursor.step(c)) {
+                        found += 1;
+                    }
+                }
+            }
+            found
+        });
+        assert_eq!(words.len() * 4, found);
+        assert_eq!(0, allocations);
+    }
+
+    #[test]
+    fn medium_queries_do_not_allocate() {
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        assert_queries_do_not_allocate(&BaseLetterTrie::from_words(&words), &words);
+        assert_queries_do_not_allocate(&NoParentLetterTrie::from_words(&words), &words);
+    }
+
+    // A continuous load reads every line into the same buffer and lowercases ASCII on the way into the trie, so it
+    // allocates for the nodes and hardly anything else. Adding words that are already there allocates nothing.
+    fn assert_load_allocates_only_nodes<T: LetterTrie>(content: &str, words: &[String]) {
+        let (_, from_words) = allocations_during(|| T::from_words(words));
+        let (_, loaded) = allocations_during(|| {
+            T::from_reader(content.as_bytes(), false, &LoadMethod::Continuous).unwrap()
+        });
+        assert!(
+            loaded <= from_words + 16,
+            "{} allocations to load but {} to build from the words",
+            loaded,
+            from_words
+        );
+
+        let mut t = T::from_words(words);
+        let ((), allocations) = allocations_during(|| {
+            for word in words {
+                t.insert(word);
+            }
+        });
+        assert_eq!(0, allocations);
+    }
+
+    #[test]
+    fn medium_load_allocates_only_nodes() {
+        let content = std::fs::read_to_string(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        assert_load_allocates_only_nodes::<BaseLetterTrie>(&content, &words);
+        assert_load_allocates_only_nodes::<NoParentLetterTrie>(&content, &words);
+    }
+
+    // Without hints the list of words a vec-fill load reads into doubles its way up to the size of the file, and
+    // a duplicate check's map of the words does the same. With the hints worked out from the file's size each is
+    // allocated once.
+    fn assert_hints_save_reallocations<T: LetterTrie + Send>() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let load = |opt: &DisplayDetailOptions, load_method: &LoadMethod| {
+            let file = std::fs::File::open(dataset.path()).unwrap();
+            reallocations_during(|| {
+                T::from_reader_test(BufReader::new(file), false, load_method, opt, None).unwrap()
+            })
+        };
+        for load_method in [LoadMethod::ReadVecFill, LoadMethod::VecFill] {
+            for policy in [DuplicatePolicy::Ignore, DuplicatePolicy::Count] {
+                let opt = DisplayDetailOptions::make_no_display().with_duplicate_policy(policy);
+                let (t, unhinted) = load(&opt, &load_method);
+                let len = std::fs::metadata(dataset.path()).unwrap().len();
+                let hints = CapacityHints::from_file_len(len);
+                let opt = opt.with_capacity_hints(hints.word_count, hints.avg_word_len);
+                let (t2, hinted) = load(&opt, &load_method);
+                assert_eq!(t.to_fixed_node(), t2.to_fixed_node());
+                assert!(
+                    hinted + 8 <= unhinted,
+                    "{:?} {:?}: {} reallocations with hints and {} without",
+                    load_method,
+                    policy,
+                    hinted,
+                    unhinted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn load_with_hints_reallocates_less() {
+        assert_hints_save_reallocations::<BaseLetterTrie>();
+        assert_hints_save_reallocations::<NoParentLetterTrie>();
+    }
+
+    // A node without children takes no allocation, so building a NoParentLetterTrie makes fewer allocations than
+    // it has nodes, and it keeps exactly the bytes that estimated_bytes() counts for its nodes. When the children
+    // were in a BTreeMap a node took over 500 bytes, though the load made fewer allocations than it does now since
+    // a node with two or more children has also had a box for its first one. See the comment in children.rs.
+    #[test]
+    fn no_parent_nodes_are_small() {
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let ((t, allocations), bytes) =
+            bytes_kept_from(|| allocations_during(|| NoParentLetterTrie::from_words(&words)));
+        let node_count = t.node_count();
+        assert!(
+            allocations < node_count,
+            "{} allocations for {} nodes",
+            allocations,
+            node_count
+        );
+        assert_eq!(
+            t.estimated_bytes() - std::mem::size_of::<NoParentLetterTrie>(),
+            bytes
+        );
+        assert!(
+            bytes < node_count * 100,
+            "{} bytes for {} nodes",
+            bytes,
+            node_count
+        );
+
+        // Taking words out again frees their nodes.
+        let mut t = t;
+        let ((), freed) = bytes_kept_from(|| {
+            for word in words.iter().skip(1) {
+                assert!(t.remove(word));
+            }
+        });
+        assert_eq!(
+            t.estimated_bytes() - std::mem::size_of::<NoParentLetterTrie>(),
+            bytes.wrapping_add(freed)
+        );
+        t.verify_integrity().unwrap();
+    }
+
+    #[test]
+    fn counts_allocations() {
+        let (v, allocations) = allocations_during(|| vec![1, 2, 3]);
+        assert_eq!(vec![1, 2, 3], v);
+        assert_eq!(1, allocations);
+    }
+}
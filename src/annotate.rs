@@ -0,0 +1,417 @@
+//! A bottom-up pass over a trie that works out a value for every node from the node itself and the values already
+//! worked out for its children, behind `LetterTrie::annotate()`, for keeping something derived on each node
+//! without a trie type of its own.
+//!
+//! The values are kept in a `Vec` in `LetterTrie::visit()` order alongside a table of each node's children, so
+//! they can be looked up by prefix as `LetterTrie::find()` would find the node, or by `NodeId`.
+
+use crate::*;
+
+/// The place of a node in an `AnnotatedTrie`, which is its place in `LetterTrie::visit()` order. The root is
+/// `NodeId::root()`, and tries with the same words give their nodes the same ids whatever their type or however
+/// they were built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// The id of the root node.
+    // This is a function since cbindgen would put an associated constant in include/letter_trie.h.
+    pub const fn root() -> NodeId {
+        NodeId(0)
+    }
+
+    /// The node's place in `LetterTrie::visit()` order, which is also the index of its value in
+    /// `AnnotatedTrie::values()`.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// A value for every node of a trie, from `LetterTrie::annotate()`. This is a copy, so it doesn't change when the
+/// trie does.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["an", "and", "at"]);
+/// // The number of words at or below each node.
+/// let counts = trie.annotate(|node, children: &[usize]| node.is_word as usize + children.iter().sum::<usize>());
+/// assert_eq!(3, *counts.root());
+/// assert_eq!(Some(&2), counts.get("AN"));
+/// assert_eq!(None, counts.get("ant"));
+/// assert_eq!(trie.to_fixed_node().node_count, counts.len());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotatedTrie<T> {
+    // The value of each node in visit() order.
+    values: Vec<T>,
+    // The children of node i are children[first_child[i]..first_child[i + 1]], in character order.
+    first_child: Vec<usize>,
+    children: Vec<(char, NodeId)>,
+}
+
+impl<T> AnnotatedTrie<T> {
+    /// The value of the root node.
+    pub fn root(&self) -> &T {
+        &self.values[NodeId::root().0]
+    }
+
+    /// The value of the node for `prefix`, found the way `LetterTrie::find()` finds it, so case and surrounding
+    /// whitespace are ignored and there's no node for an empty prefix.
+    pub fn get(&self, prefix: &str) -> Option<&T> {
+        self.id(prefix).map(|id| &self.values[id.0])
+    }
+
+    /// The id of the node for `prefix`, found as in `get()`.
+    pub fn id(&self, prefix: &str) -> Option<NodeId> {
+        let prefix = prefix.trim();
+        if prefix.is_empty() {
+            return None;
+        }
+        if prefix.is_ascii() {
+            self.id_chars(ascii_lowercase_chars(prefix))
+        } else {
+            self.id_chars(prefix.to_lowercase().chars())
+        }
+    }
+
+    fn id_chars(&self, chars: impl Iterator<Item = char>) -> Option<NodeId> {
+        let mut id = NodeId::root();
+        for c in chars {
+            let children = self.children_of(id);
+            id = children[children.binary_search_by_key(&c, |(c, _)| *c).ok()?].1;
+        }
+        Some(id)
+    }
+
+    /// The value of the node with `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` isn't from this annotation or one of a trie with the same words.
+    pub fn value(&self, id: NodeId) -> &T {
+        &self.values[id.0]
+    }
+
+    /// The letters and ids of the children of the node with `id`, in character order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` isn't from this annotation or one of a trie with the same words.
+    pub fn children_of(&self, id: NodeId) -> &[(char, NodeId)] {
+        &self.children[self.first_child[id.0]..self.first_child[id.0 + 1]]
+    }
+
+    /// The value of every node in the order of `NodeId::index()`, which is `LetterTrie::visit()` order.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// The number of nodes, counting the root.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Always false, since there's a value for the root even in an empty trie.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+// The size of the subtree below a node, counting the node itself, as FixedNode gives it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SubtreeCounts {
+    pub(crate) node_count: usize,
+    pub(crate) word_count: usize,
+    pub(crate) height: usize,
+}
+
+pub(crate) fn subtree_counts<L: LetterTrie + ?Sized>(trie: &L) -> AnnotatedTrie<SubtreeCounts> {
+    annotate(trie, |node, children: &[SubtreeCounts]| {
+        children.iter().fold(
+            SubtreeCounts {
+                node_count: 1,
+                word_count: node.is_word as usize,
+                height: 1,
+            },
+            |counts, child| SubtreeCounts {
+                node_count: counts.node_count + child.node_count,
+                word_count: counts.word_count + child.word_count,
+                height: counts.height.max(child.height + 1),
+            },
+        )
+    })
+}
+
+// One node on the path down to the one being visited, with what's needed to make its NodeView again once all of
+// its children are done.
+struct Frame<T> {
+    id: usize,
+    c: char,
+    prefix_len: usize,
+    is_word: bool,
+    child_count: usize,
+    spelling: Option<String>,
+    score: Option<f64>,
+    max_score: Option<f64>,
+    child_ids: Vec<usize>,
+    child_values: Vec<T>,
+}
+
+// A node's value can only be worked out as the walk leaves it, after its children, while the children's values
+// are still needed until then, so each value goes to its place in visit() order once its parent is done.
+pub(crate) fn annotate<L, T, F>(trie: &L, f: F) -> AnnotatedTrie<T>
+where
+    L: LetterTrie + ?Sized,
+    F: Fn(&NodeView, &[T]) -> T,
+{
+    let mut values: Vec<Option<T>> = vec![];
+    // The letter and parent of each node in visit() order.
+    let mut nodes: Vec<(char, Option<usize>)> = vec![];
+    let mut path: Vec<Frame<T>> = vec![];
+    // The prefix of the last node visited, which starts with the prefix of every node on the path.
+    let mut prefix = String::new();
+    let leave = |path: &mut Vec<Frame<T>>, prefix: &str, values: &mut Vec<Option<T>>| {
+        let Some(frame) = path.pop() else {
+            return;
+        };
+        let node = NodeView {
+            c: frame.c,
+            prefix: &prefix[..frame.prefix_len],
+            depth: path.len(),
+            is_word: frame.is_word,
+            child_count: frame.child_count,
+            spelling: frame.spelling.as_deref(),
+            score: frame.score,
+            max_score: frame.max_score,
+        };
+        let value = f(&node, &frame.child_values);
+        for (id, child_value) in frame.child_ids.into_iter().zip(frame.child_values) {
+            values[id] = Some(child_value);
+        }
+        match path.last_mut() {
+            Some(parent) => parent.child_values.push(value),
+            None => values[frame.id] = Some(value),
+        }
+    };
+    trie.visit("", &mut |node| {
+        while path.len() > node.depth {
+            leave(&mut path, &prefix, &mut values);
+        }
+        let id = nodes.len();
+        let parent = path.last_mut().map(|parent| {
+            parent.child_ids.push(id);
+            parent.id
+        });
+        nodes.push((node.c, parent));
+        values.push(None);
+        prefix.clear();
+        prefix.push_str(node.prefix);
+        path.push(Frame {
+            id,
+            c: node.c,
+            prefix_len: node.prefix.len(),
+            is_word: node.is_word,
+            child_count: node.child_count,
+            spelling: node.spelling.map(str::to_owned),
+            score: node.score,
+            max_score: node.max_score,
+            child_ids: vec![],
+            child_values: vec![],
+        });
+        true
+    });
+    while !path.is_empty() {
+        leave(&mut path, &prefix, &mut values);
+    }
+
+    // Children come after their parent in visit() order and in character order among themselves, so going
+    // through the nodes in order fills in each node's children in order.
+    let mut first_child = vec![0; nodes.len() + 1];
+    for (_, parent) in &nodes {
+        if let Some(parent) = parent {
+            first_child[parent + 1] += 1;
+        }
+    }
+    for i in 1..first_child.len() {
+        first_child[i] += first_child[i - 1];
+    }
+    let mut next_child = first_child.clone();
+    let mut children = vec![(' ', NodeId::root()); nodes.len().saturating_sub(1)];
+    for (id, (c, parent)) in nodes.into_iter().enumerate() {
+        if let Some(parent) = parent {
+            children[next_child[parent]] = (c, NodeId(id));
+            next_child[parent] += 1;
+        }
+    }
+    AnnotatedTrie {
+        values: values.into_iter().map(Option::unwrap).collect(),
+        first_child,
+        children,
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn small<T: LetterTrie>() -> T {
+        T::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap()
+    }
+
+    // Every prefix in the trie in visit() order, leaving out the root's empty one.
+    fn prefixes<T: LetterTrie>(t: &T) -> Vec<String> {
+        let mut prefixes = vec![];
+        t.visit("", &mut |node| {
+            if node.depth > 0 {
+                prefixes.push(node.prefix.to_owned());
+            }
+            true
+        });
+        prefixes
+    }
+
+    fn check_subtree_counts<T: LetterTrie>() {
+        let t = small::<T>();
+        let counts = subtree_counts(&t);
+        let root = t.to_fixed_node();
+        assert_eq!(
+            SubtreeCounts {
+                node_count: root.node_count,
+                word_count: root.word_count,
+                height: root.height,
+            },
+            *counts.root()
+        );
+        assert_eq!(root.node_count, counts.len());
+        for prefix in prefixes(&t) {
+            let node = t.find(&prefix).unwrap();
+            assert_eq!(
+                Some(&SubtreeCounts {
+                    node_count: node.node_count,
+                    word_count: node.word_count,
+                    height: node.height,
+                }),
+                counts.get(&prefix),
+                "{}",
+                prefix
+            );
+        }
+
+        let empty = subtree_counts(&T::from_words(Vec::<String>::new()));
+        assert_eq!(1, empty.len());
+        assert!(!empty.is_empty());
+        assert_eq!(
+            SubtreeCounts {
+                node_count: 1,
+                word_count: 0,
+                height: 1,
+            },
+            *empty.root()
+        );
+    }
+
+    #[test]
+    fn subtree_counts_base() {
+        check_subtree_counts::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn subtree_counts_no_parent() {
+        check_subtree_counts::<NoParentLetterTrie>();
+    }
+
+    // The fewest letters left to type after a prefix to finish a word, as a typing tutor might keep.
+    fn check_keystrokes<T: LetterTrie>() {
+        let t = small::<T>();
+        let keystrokes = t.annotate(|node, children: &[usize]| {
+            if node.is_word {
+                0
+            } else {
+                children.iter().min().map_or(0, |n| n + 1)
+            }
+        });
+        let words: Vec<String> = t.words().collect();
+        for prefix in prefixes(&t) {
+            let expected = words
+                .iter()
+                .filter(|word| word.starts_with(&prefix))
+                .map(|word| word.len() - prefix.len())
+                .min()
+                .unwrap();
+            assert_eq!(Some(&expected), keystrokes.get(&prefix), "{}", prefix);
+        }
+        assert_eq!(Some(&3), keystrokes.get("CRE"));
+        assert_eq!(Some(&2), keystrokes.get("cro"));
+        assert_eq!(Some(&0), keystrokes.get("creature"));
+        assert_eq!(None, keystrokes.get("creatures"));
+        assert_eq!(None, keystrokes.get(""));
+    }
+
+    #[test]
+    fn keystrokes_base() {
+        check_keystrokes::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn keystrokes_no_parent() {
+        check_keystrokes::<NoParentLetterTrie>();
+    }
+
+    // Each node's view is the same one visit() gives, with all of its children's values in character order.
+    #[test]
+    fn values_follow_visit_order() {
+        let mut t = NoParentLetterTrie::from_words(vec!["an", "and", "at", "ñu"]);
+        t.insert_preserving_case("Ant", CasePreference::FirstSeen);
+        t.set_score("at", 0.5);
+        let seen = t.annotate(|node, children: &[String]| {
+            let mut s = format!(
+                "{}:{}:{}:{}:{:?}:{:?}",
+                node.prefix, node.depth, node.is_word, node.child_count, node.spelling, node.score
+            );
+            assert_eq!(node.child_count, children.len());
+            for child in children {
+                s.push_str(&format!("({})", child.split(':').next().unwrap()));
+            }
+            s
+        });
+        assert_eq!(
+            vec![
+                ":0:false:2:None:None(a)(ñ)",
+                "a:1:false:2:None:None(an)(at)",
+                "an:2:true:2:None:None(and)(ant)",
+                "and:3:true:0:None:None",
+                "ant:3:true:0:Some(\"Ant\"):None",
+                "at:2:true:0:None:Some(0.5)",
+                "ñ:1:false:1:None:None(ñu)",
+                "ñu:2:true:0:None:None",
+            ],
+            seen.values()
+        );
+
+        let id = seen.id("AN").unwrap();
+        assert_eq!(2, id.index());
+        assert_eq!(&seen.values()[2], seen.value(id));
+        assert_eq!(
+            vec![('d', NodeId(3)), ('t', NodeId(4))],
+            seen.children_of(id)
+        );
+        assert_eq!(
+            vec![('a', NodeId(1)), ('ñ', NodeId(6))],
+            seen.children_of(NodeId::root())
+        );
+        assert_eq!(Some(NodeId(7)), seen.id("ÑU"));
+        assert_eq!(None, seen.id("aa"));
+
+        // The same words give the same ids in either trie.
+        let base = BaseLetterTrie::from_words(t.words());
+        assert_eq!(
+            t.annotate(|node, _: &[String]| node.prefix.to_owned())
+                .values(),
+            base.annotate(|node, _: &[String]| node.prefix.to_owned())
+                .values()
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Loading a trie inside a tokio runtime without blocking it.
+//!
+//! Lines are read with tokio's async I/O and handed over in chunks to `tokio::task::spawn_blocking()` where the
+//! actual inserts happen, so other tasks on the runtime keep making progress during a load that would take seconds
+//! if done inline.
+
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::panic;
+use std::path::Path;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+use crate::*;
+
+// The number of words inserted per trip to the blocking thread pool. Larger chunks mean less handoff overhead,
+// smaller ones mean the trie reaches its final size more smoothly.
+const ASYNC_CHUNK_WORDS: usize = 10_000;
+
+/// Async constructors for any `LetterTrie` that can be moved between threads.
+///
+/// This is implemented automatically for `BaseLetterTrie` and `NoParentLetterTrie`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+/// Dataset::EmbeddedSmall.materialize().unwrap();
+/// let trie = NoParentLetterTrie::from_file_async(Dataset::EmbeddedSmall.filename())
+///     .await
+///     .unwrap();
+/// assert_eq!(10, trie.to_fixed_node().word_count);
+/// # });
+/// ```
+pub trait AsyncLetterTrie: LetterTrie + Send + Sized + 'static {
+    /// Create a trie from words read from an async source, with the same rules as `LetterTrie::from_reader()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if reading fails or a line isn't valid UTF-8.
+    fn from_async_reader<R>(reader: R) -> impl Future<Output = Result<Self>> + Send
+    where
+        R: AsyncBufRead + Unpin + Send,
+    {
+        async move {
+            Self::from_async_reader_with_options(reader, TrieOptions::new())
+                .await
+                .map(|(trie, _)| trie)
+        }
+    }
+
+    /// Create a trie from words read from an async source as `from_async_reader()` does, enforcing
+    /// `TrieOptions::max_word_len` and `TrieOptions::max_words` and treating lines that aren't valid UTF-8 as
+    /// `TrieOptions::utf8_policy` says. Only as much of a line is kept as `max_word_len` allows for, so one very
+    /// long line doesn't have to fit in memory. The other options don't apply to a plain trie and are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if reading fails or a line isn't valid UTF-8 under `Utf8Policy::Strict`, and
+    /// `LetterTrieError::LimitExceeded` if a word goes over a limit whose policy is `LimitPolicy::Abort`, in which
+    /// case the load stops there.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+    /// let source = "an\ncreature\ncross\n";
+    /// let options = TrieOptions::new().with_max_word_len(5, LimitPolicy::Skip);
+    /// let (trie, report) = NoParentLetterTrie::from_async_reader_with_options(source.as_bytes(), options)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(vec!["an", "cross"], trie.words_with_prefix(""));
+    /// assert_eq!(1, report.skipped_too_long);
+    /// # });
+    /// ```
+    fn from_async_reader_with_options<R>(
+        mut reader: R,
+        options: TrieOptions,
+    ) -> impl Future<Output = Result<(Self, LoadReport)>> + Send
+    where
+        R: AsyncBufRead + Unpin + Send,
+    {
+        async move {
+            let mut trie = Self::from_words(std::iter::empty::<&str>());
+            let mut report = LoadReport::default();
+            let max_line_bytes = options.max_line_bytes();
+            let mut buf: Vec<u8> = vec![];
+            let mut index = 0;
+            let mut chunk: Vec<String> = Vec::with_capacity(ASYNC_CHUNK_WORDS);
+            while let Some(is_cut) = read_line(&mut reader, &mut buf, max_line_bytes).await? {
+                index += 1;
+                if is_cut {
+                    options.skip_cut_line(index, &mut report)?;
+                    continue;
+                }
+                let line = match options.decode_line(&buf, index, &mut report)? {
+                    Some(line) => line,
+                    None => continue,
+                };
+                let word = line.trim();
+                if word.is_empty() || !options.check_limits(word, index, &mut report)? {
+                    continue;
+                }
+                chunk.push(word.to_owned());
+                if chunk.len() == ASYNC_CHUNK_WORDS {
+                    trie = insert_chunk(trie, mem::take(&mut chunk)).await?;
+                }
+            }
+            if !chunk.is_empty() {
+                trie = insert_chunk(trie, chunk).await?;
+            }
+            Ok((trie, report))
+        }
+    }
+
+    /// Create a trie from a word file opened with `tokio::fs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the path if the file can't be opened or read.
+    fn from_file_async<P>(path: P) -> impl Future<Output = Result<Self>> + Send
+    where
+        P: AsRef<Path> + Send,
+    {
+        async move {
+            let path = path.as_ref();
+            let file = tokio::fs::File::open(path).await.map_err(in_file(path))?;
+            Self::from_async_reader(BufReader::new(file))
+                .await
+                .map_err(in_file(path))
+        }
+    }
+}
+
+impl<T: LetterTrie + Send + 'static> AsyncLetterTrie for T {}
+
+// Move the trie to a blocking thread, add the words there, and move it back.
+async fn insert_chunk<T>(mut trie: T, chunk: Vec<String>) -> Result<T>
+where
+    T: LetterTrie + Send + 'static,
+{
+    let result = tokio::task::spawn_blocking(move || {
+        for word in &chunk {
+            trie.insert(word);
+        }
+        trie
+    })
+    .await;
+    match result {
+        Ok(trie) => Ok(trie),
+        Err(err) if err.is_panic() => panic::resume_unwind(err.into_panic()),
+        Err(err) => Err(io::Error::new(io::ErrorKind::Interrupted, err).into()),
+    }
+}
+
+// Read the next line into buf without its line ending, keeping at most max_bytes of it. Gives None at the end of
+// the source, or whether the line was cut short. This is the async version of the one `ConfiguredLetterTrie` uses.
+async fn read_line<R>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    max_bytes: Option<usize>,
+) -> io::Result<Option<bool>>
+where
+    R: AsyncBufRead + Unpin + Send,
+{
+    let max_bytes = max_bytes.unwrap_or(usize::MAX);
+    buf.clear();
+    let mut read_any = false;
+    let mut is_cut = false;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            break;
+        }
+        read_any = true;
+        let newline = available.iter().position(|byte| *byte == b'\n');
+        let line_part = &available[..newline.unwrap_or(available.len())];
+        let room = max_bytes - buf.len().min(max_bytes);
+        if line_part.len() > room {
+            is_cut = true;
+        }
+        buf.extend_from_slice(&line_part[..line_part.len().min(room)]);
+        let used = newline.map(|i| i + 1).unwrap_or(available.len());
+        reader.consume(used);
+        if newline.is_some() {
+            break;
+        }
+    }
+    if !read_any {
+        return Ok(None);
+    }
+    if !is_cut && buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+    Ok(Some(is_cut))
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn matches_sync_load() {
+        // More words than fit in one chunk.
+        let dataset = Dataset::Generated {
+            count: 25_000,
+            seed: 3,
+        };
+        dataset.materialize().unwrap();
+        let sync_trie =
+            NoParentLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        let async_trie = NoParentLetterTrie::from_file_async(dataset.filename())
+            .await
+            .unwrap();
+        assert_eq!(sync_trie.to_fixed_node(), async_trie.to_fixed_node());
+        assert_eq!(
+            sync_trie.words_with_prefix(""),
+            async_trie.words_with_prefix("")
+        );
+
+        let base_trie = BaseLetterTrie::from_file_async(dataset.filename())
+            .await
+            .unwrap();
+        assert_eq!(sync_trie.to_fixed_node(), base_trie.to_fixed_node());
+    }
+
+    #[tokio::test]
+    async fn missing_file_is_an_error() {
+        match NoParentLetterTrie::from_file_async("no_such_file.txt").await {
+            Err(err @ LetterTrieError::File { .. }) => {
+                assert!(err.to_string().contains("no_such_file.txt"), "{}", err)
+            }
+            other => panic!("{:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn limits() {
+        let long_word = "x".repeat(1_000_000);
+        let source = format!("an\n{}\n\ncross\ncrease\nazure\n", long_word);
+        let options = TrieOptions::new()
+            .with_max_word_len(20, LimitPolicy::Skip)
+            .with_max_words(3, LimitPolicy::Skip);
+        let (trie, report) =
+            BaseLetterTrie::from_async_reader_with_options(source.as_bytes(), options)
+                .await
+                .unwrap();
+        assert_eq!(vec!["an", "crease", "cross"], trie.words_with_prefix(""));
+        assert_eq!(3, report.words_added);
+        assert_eq!(1, report.skipped_too_long);
+        assert_eq!(1, report.skipped_over_max_words);
+
+        let options = TrieOptions::new().with_max_word_len(20, LimitPolicy::Abort);
+        match NoParentLetterTrie::from_async_reader_with_options(source.as_bytes(), options).await {
+            Err(LetterTrieError::LimitExceeded {
+                limit: "max_word_len",
+                max: 20,
+                line: 2,
+                word: None,
+            }) => {}
+            other => panic!("{:?}", other.map(|(_, report)| report)),
+        }
+
+        // Without a limit the long word is read whole.
+        let (trie, report) = NoParentLetterTrie::from_async_reader_with_options(
+            source.as_bytes(),
+            TrieOptions::new(),
+        )
+        .await
+        .unwrap();
+        assert!(trie.contains(&long_word));
+        assert_eq!(5, report.words_added);
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8() {
+        let source: &[u8] = b"an\ncr\xffss\ncross\n";
+        let options = TrieOptions::new().with_utf8_policy(Utf8Policy::Skip);
+        let (trie, report) = NoParentLetterTrie::from_async_reader_with_options(source, options)
+            .await
+            .unwrap();
+        assert_eq!(vec!["an", "cross"], trie.words_with_prefix(""));
+        assert_eq!(1, report.invalid_utf8_lines);
+
+        match NoParentLetterTrie::from_async_reader(source).await {
+            Err(LetterTrieError::Io(err)) => assert_eq!(io::ErrorKind::InvalidData, err.kind()),
+            other => panic!("{:?}", other.err()),
+        }
+    }
+
+    // The default test runtime has a single thread so the ticker only advances if the load gives the thread back.
+    #[tokio::test]
+    async fn other_tasks_progress_during_load() {
+        let dataset = Dataset::Generated {
+            count: 100_000,
+            seed: 5,
+        };
+        dataset.materialize().unwrap();
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker_ticks = Arc::clone(&ticks);
+        let ticker = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(1));
+            loop {
+                interval.tick().await;
+                ticker_ticks.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        let trie = NoParentLetterTrie::from_file_async(dataset.filename())
+            .await
+            .unwrap();
+        ticker.abort();
+
+        let sync_trie =
+            NoParentLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        assert_eq!(sync_trie.to_fixed_node(), trie.to_fixed_node());
+        assert!(ticks.load(Ordering::Relaxed) > 1);
+    }
+}
@@ -1,17 +1,22 @@
-extern crate test;
-
-use std::cell::RefCell;
-use std::cmp;
-use std::collections::BTreeMap;
-use std::fmt::{self, Debug};
-use std::fs;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::rc::{Rc, Weak};
+use alloc::collections::BTreeMap;
+use alloc::rc::{Rc, Weak};
+use core::cell::RefCell;
+use core::cmp;
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+#[cfg(feature = "parallel")]
+use core::mem;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+#[cfg(feature = "parallel")]
 use std::sync::mpsc;
+#[cfg(feature = "parallel")]
 use std::thread;
-use std::time::Instant;
 
+#[cfg(feature = "std")]
+use crate::prefix_cache::PrefixCache;
+use crate::trace::TraceOp;
+use crate::util::format_indent;
 use crate::*;
 
 // The Rc pointing to a node should always have a count of one except in special cases where additional references are
@@ -20,6 +25,15 @@ use crate::*;
 type ChildLink = Rc<RefCell<Node>>;
 // The weak count of the pointer to a node should always equal that node's number of child nodes.
 type ParentLink = Weak<RefCell<Node>>;
+// What a worker thread of a parallel load sends back: its part of the trie, the counts it made while building it,
+// and how long it took along with when it finished so the loading thread can tell how long the part was queued.
+#[cfg(feature = "parallel")]
+struct WorkerPart {
+    trie: BaseLetterTrie,
+    counts: CharGetCounts,
+    stats: LetterLoadStats,
+    finished: Instant,
+}
 
 /// The baseline implementation of a [letter trie]: https://www.geeksforgeeks.org/trie-insert-and-search/ with added
 /// references from nodes to their parents to experiment with Rc and RefCell. Other trees use different approaches
@@ -27,6 +41,27 @@ type ParentLink = Weak<RefCell<Node>>;
 pub struct BaseLetterTrie {
     // The root node's character is a single space which doesn't count toward the words represented by the trie.
     root: ChildLink,
+    // The tags from insert_with_tag() keyed by word, which is empty unless tags are used. It's in a RefCell like the
+    // nodes so that merge() can take the other trie's tags through a shared reference.
+    tags: RefCell<BTreeMap<String, u64>>,
+    // The sources from insert_with_source() keyed by word, which is empty unless sources are used. Unlike the tags
+    // it's not in a RefCell so that sources() can lend out the list, which means merge() can't take them.
+    sources: BTreeMap<String, Vec<u16>>,
+    // The spellings from insert_preserving_case() keyed by word, which like the sources is empty unless they're
+    // used and isn't in a RefCell so that it can lend them out.
+    spellings: BTreeMap<String, String>,
+    // The scores from set_scores() keyed by word, which is empty unless scores are used. It's in a RefCell like the
+    // tags so that merge() can take the other trie's scores.
+    scores: RefCell<BTreeMap<String, f64>>,
+    // The highest score at or below each prefix that has a scored word at or below it, as kept by
+    // scores::update_max_scores(). It goes along with the scores in merge().
+    max_scores: RefCell<BTreeMap<String, f64>>,
+    // The ranks from set_rank() keyed by word, which is empty unless ranks are used. They belong to the list the
+    // words were loaded from, so like the sources they aren't taken by merge().
+    ranks: BTreeMap<String, u32>,
+    // The cache from enable_prefix_cache(), if it's on.
+    #[cfg(feature = "std")]
+    prefix_cache: Option<PrefixCache>,
 }
 
 impl BaseLetterTrie {
@@ -44,17 +79,27 @@ impl BaseLetterTrie {
         let is_word = false;
         let root = BaseLetterTrie::make_child_node_and_link(c, parent, depth, is_word);
         debug_assert!(Self::child_link_has_normal_ref_counts(&root));
-        BaseLetterTrie { root }
+        BaseLetterTrie {
+            root,
+            tags: RefCell::new(BTreeMap::new()),
+            sources: BTreeMap::new(),
+            spellings: BTreeMap::new(),
+            scores: RefCell::new(BTreeMap::new()),
+            max_scores: RefCell::new(BTreeMap::new()),
+            ranks: BTreeMap::new(),
+            #[cfg(feature = "std")]
+            prefix_cache: None,
+        }
     }
 
-    // Create an Rc<RefCell<Node>> for a given character.
+    // Create an Rc<RefCell<Node>> for a given character. The caller checks the counts on the parent link since
+    // it may be holding a temporary reference to the parent.
     fn make_child_node_and_link(
         c: char,
         parent: Option<ParentLink>,
         depth: usize,
         is_word: bool,
     ) -> ChildLink {
-        debug_assert!(Self::opt_parent_link_has_normal_ref_counts(&parent));
         let children = BTreeMap::new();
         Rc::new(RefCell::new(Node {
             c,
@@ -63,80 +108,442 @@ impl BaseLetterTrie {
             children,
             is_word,
             is_frozen: false,
+            longest_word_len: if is_word { depth } else { 0 },
             node_count: None,
             word_count: None,
             height: None,
         }))
     }
 
+    // An ASCII word is lowercased a character at a time on the way into the trie, so only a word with other
+    // characters allocates before any new nodes are made.
     fn add_word(&self, s: &str) {
         let s = s.trim();
         if !s.is_empty() {
             debug_assert!(!self.is_frozen());
-            let v: Vec<char> = s.to_lowercase().chars().collect();
-            let v_len = v.len();
-            self.add_from_vec_chars(&v, v_len, 0);
+            self.clear_prefix_cache();
+            if s.is_ascii() {
+                let chars = s.bytes().map(|b| char::from(b.to_ascii_lowercase()));
+                Self::add_chars(&self.root, chars, 0, s.len());
+            } else {
+                let s = s.to_lowercase();
+                Self::add_chars(&self.root, s.chars(), 0, s.chars().count());
+            }
         }
     }
 
     // This is called once for every word, and should be called only on the root.
-    pub fn add_from_vec_chars(&self, v: &[char], v_len: usize, char_index: usize) {
+    pub fn add_from_vec_chars(&mut self, v: &[char], v_len: usize, char_index: usize) {
         debug_assert!(!self.is_frozen());
         debug_assert!(self.root.borrow().c == ' ');
         if v_len > 0 {
+            self.clear_prefix_cache();
             BaseLetterTrie::add_from_vec_chars_one_char(&self.root, v, v_len, char_index);
         }
     }
 
-    // This is called once for every character in every word.
     fn add_from_vec_chars_one_char(rc: &ChildLink, v: &[char], v_len: usize, char_index: usize) {
-        debug_assert!(Self::child_link_has_normal_ref_counts(&rc));
-        if char_index < v_len {
-            let c = v[char_index];
-            let is_word = char_index == v_len - 1;
-            let mut root = rc.borrow_mut();
-            let child_node_opt = root.children.get(&c);
-
-            if USE_CHAR_GET_COUNTER {
-                CharGetCounter::record(child_node_opt.is_some());
+        Self::add_chars(
+            rc,
+            v.iter().take(v_len).skip(char_index).copied(),
+            char_index,
+            v_len.saturating_sub(char_index),
+        );
+    }
+
+    // Add the letters in chars below rc, with the last one ending a word. There are char_count of them, and the
+    // first is at char_index in the whole word, which is where CharGetCounter records it.
+    //
+    // This is called once for every word and goes through the word's characters in a loop rather than recursing
+    // so that a very long line can't overflow the stack. The loop holds an extra strong reference to the node it's
+    // on, which is the kind of temporary reference allowed by the comment on ChildLink.
+    fn add_chars<I: Iterator<Item = char>>(
+        rc: &ChildLink,
+        chars: I,
+        char_index: usize,
+        char_count: usize,
+    ) {
+        debug_assert!(Self::child_link_has_normal_ref_counts(rc));
+        let mut rc = Rc::clone(rc);
+        let word_len = rc.borrow().depth + char_count;
+        let v_len = char_index + char_count;
+        #[cfg(feature = "std")]
+        let counting = CharGetCounter::is_enabled();
+        for (i, c) in (char_index..).zip(chars) {
+            let is_word = i == v_len - 1;
+            let mut node = rc.borrow_mut();
+            node.longest_word_len = cmp::max(node.longest_word_len, word_len);
+            let child_node_opt = node.children.get(&c);
+
+            #[cfg(feature = "std")]
+            if counting {
+                CharGetCounter::record_at(i, child_node_opt.is_some());
             }
 
-            if let Some(child_node_link) = child_node_opt {
-                debug_assert!(Self::child_link_has_normal_ref_counts(&child_node_link));
+            let next_rc = if let Some(child_node_link) = child_node_opt {
+                debug_assert!(Self::child_link_has_normal_ref_counts(child_node_link));
                 if is_word {
                     let mut child_node = child_node_link.borrow_mut();
                     child_node.is_word = true;
+                    child_node.longest_word_len = cmp::max(child_node.longest_word_len, word_len);
                 }
-                BaseLetterTrie::add_from_vec_chars_one_char(
-                    &child_node_link,
-                    v,
-                    v_len,
-                    char_index + 1,
-                );
+                Rc::clone(child_node_link)
             } else {
-                debug_assert!(Self::child_link_has_normal_ref_counts(&rc));
                 let parent: ParentLink = Rc::downgrade(&rc);
-                debug_assert!(Self::parent_link_has_normal_ref_counts(&parent));
+                // The link from the node's own parent (or from the BaseLetterTrie) plus the one held by the loop.
+                debug_assert_eq!(2, Weak::strong_count(&parent));
                 let new_child_link: ChildLink = BaseLetterTrie::make_child_node_and_link(
                     c,
                     Some(parent),
-                    root.depth + 1,
+                    node.depth + 1,
                     is_word,
                 );
-                BaseLetterTrie::add_from_vec_chars_one_char(
-                    &new_child_link,
-                    v,
-                    v_len,
-                    char_index + 1,
-                );
-                root.children.insert(c, new_child_link);
+                node.children.insert(c, Rc::clone(&new_child_link));
+                new_child_link
+            };
+            drop(node);
+            rc = next_rc;
+        }
+    }
+
+    // Remove a word and any nodes that only led to it. Like add_word() this is only called on an unfrozen trie.
+    fn remove_word(&self, s: &str) -> bool {
+        let s = s.trim();
+        if s.is_empty() {
+            return false;
+        }
+        debug_assert!(!self.is_frozen());
+        self.clear_prefix_cache();
+        let v: Vec<char> = s.to_lowercase().chars().collect();
+        // The nodes from the root down to the word's node. These are extra strong references that are all dropped
+        // before returning.
+        let mut path: Vec<ChildLink> = vec![Rc::clone(&self.root)];
+        // The index in path of the deepest node that has to stay after the word is removed.
+        let mut keep_index = 0;
+        for (i, c) in v.iter().enumerate() {
+            let rc_opt = {
+                let node = path[i].borrow();
+                if i > 0 && (node.is_word || node.children.len() > 1) {
+                    keep_index = i;
+                }
+                node.children.get(c).map(Rc::clone)
+            };
+            match rc_opt {
+                Some(rc) => path.push(rc),
+                None => return false,
+            }
+        }
+        let mut node = path[v.len()].borrow_mut();
+        if !node.is_word {
+            return false;
+        }
+        // The deepest node on the path that's still in the trie.
+        let end = if !node.children.is_empty() {
+            // Longer words still go through this node.
+            node.is_word = false;
+            drop(node);
+            v.len()
+        } else {
+            drop(node);
+            path[keep_index]
+                .borrow_mut()
+                .children
+                .remove(&v[keep_index]);
+            keep_index
+        };
+        // Only the nodes on the path can have a different longest word now. Going up from the deepest one, each
+        // node's children already have the right lengths.
+        for rc in path[..=end].iter().rev() {
+            let mut node = rc.borrow_mut();
+            node.longest_word_len = node.own_longest_word_len();
+        }
+        true
+    }
+
+    // Work out the highest scores again along the path to the word key after the word's score has changed or the
+    // word has gone.
+    fn update_max_scores(&self, key: &str) {
+        let mut path = vec![];
+        let mut rc_opt = Some(Rc::clone(&self.root));
+        let mut chars = key.chars();
+        while let Some(rc) = rc_opt {
+            let node = rc.borrow();
+            path.push((node.is_word, node.children.keys().copied().collect()));
+            rc_opt = chars
+                .next()
+                .and_then(|c| node.children.get(&c).map(Rc::clone));
+        }
+        scores::update_max_scores(
+            &mut self.max_scores.borrow_mut(),
+            &self.scores.borrow(),
+            key,
+            &path,
+        );
+    }
+
+    // Empty the cache from enable_prefix_cache(), if it's on, before a change to the words.
+    fn clear_prefix_cache(&self) {
+        #[cfg(feature = "std")]
+        if let Some(cache) = &self.prefix_cache {
+            cache.clear();
+        }
+    }
+
+    // find() without the prefix cache.
+    fn find_query(&self, prefix: &str) -> Option<FixedNode> {
+        self.find_link_query(prefix)
+            .filter(|rc| rc.borrow().depth > 0)
+            .map(|rc| rc.borrow().to_fixed_node())
+    }
+
+    // Follow the letters of an already-lowercased prefix down from the root.
+    fn find_link(&self, prefix: &str) -> Option<ChildLink> {
+        let mut rc = Rc::clone(&self.root);
+        for c in prefix.chars() {
+            let rc_next = rc.borrow().children.get(&c).map(Rc::clone)?;
+            rc = rc_next;
+        }
+        Some(rc)
+    }
+
+    // Like find_link() but lowercasing each character on the way. Cloning the links only changes their counts, so
+    // this doesn't allocate.
+    fn find_link_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> Option<ChildLink> {
+        self.find_link_lowered(chars.into_iter().flat_map(char::to_lowercase))
+    }
+
+    // The link for a query as it was passed to one of the str methods.
+    fn find_link_query(&self, query: &str) -> Option<ChildLink> {
+        if query.is_ascii() {
+            self.find_link_lowered(ascii_lowercase_chars(query))
+        } else {
+            with_lowercase_chars(query, |chars| self.find_link_chars(chars))
+        }
+    }
+
+    // Follow characters that are already lowercase down from the root.
+    fn find_link_lowered(&self, chars: impl Iterator<Item = char>) -> Option<ChildLink> {
+        #[cfg(feature = "std")]
+        let counting = CharGetCounter::is_enabled();
+        let mut rc = Rc::clone(&self.root);
+        for (depth, c) in chars.enumerate() {
+            let rc_next = rc.borrow().children.get(&c).map(Rc::clone);
+            #[cfg(feature = "std")]
+            if counting {
+                CharGetCounter::record_at(depth, rc_next.is_some());
             }
+            #[cfg(not(feature = "std"))]
+            let _ = depth;
+            rc = rc_next?;
         }
+        Some(rc)
+    }
+
+    // find_link_query() counting what it does, for find_with_metrics() and contains_with_metrics(). The children
+    // are a BTreeMap, which doesn't say how many keys it compared, so they're counted as the scan in order that
+    // the map makes through each of its nodes.
+    fn find_link_with_metrics(&self, query: &str, metrics: &mut FindMetrics) -> Option<ChildLink> {
+        let mut walk = |chars: &mut dyn Iterator<Item = char>| {
+            let mut rc = Rc::clone(&self.root);
+            for c in chars {
+                let node = rc.borrow();
+                let comparisons = node
+                    .children
+                    .keys()
+                    .position(|key| *key >= c)
+                    .map_or(node.children.len(), |index| index + 1);
+                let rc_next = node.children.get(&c).map(Rc::clone);
+                drop(node);
+                metrics.step(comparisons, rc_next.is_some());
+                rc = rc_next?;
+            }
+            Some(rc)
+        };
+        if query.is_ascii() {
+            walk(&mut ascii_lowercase_chars(query))
+        } else {
+            with_lowercase_chars(query, |chars| walk(&mut chars.flat_map(char::to_lowercase)))
+        }
+    }
+
+    // For remove_sorted_words(), finish the last node on the path and drop it from the path, cutting it off its
+    // parent if there's nothing left at or below it.
+    fn leave_node(&self, path: &mut Vec<ChildLink>, report: &mut RemovalReport) {
+        let Some(rc) = path.pop() else {
+            return;
+        };
+        let mut node = rc.borrow_mut();
+        if !node.is_word && node.children.is_empty() {
+            path.last()
+                .unwrap_or(&self.root)
+                .borrow_mut()
+                .children
+                .remove(&node.c);
+            report.nodes_pruned += 1;
+        } else {
+            node.longest_word_len = node.own_longest_word_len();
+        }
+    }
+
+    // Add the words below start with exactly total_len letters to words, skipping any branch whose longest word is
+    // too short. The prefix buffer holds the starting node's letters.
+    fn push_completions_of_exact_length(
+        start: ChildLink,
+        prefix: &mut String,
+        total_len: usize,
+        words: &mut Vec<String>,
+    ) {
+        let start_len = prefix.len();
+        let mut stack: Vec<(ChildLink, Option<usize>)> = vec![(start, None)];
+        while let Some((rc, parent_len)) = stack.pop() {
+            let node = rc.borrow();
+            if node.longest_word_len < total_len {
+                continue;
+            }
+            if let Some(parent_len) = parent_len {
+                prefix.truncate(parent_len);
+                prefix.push(node.c);
+            }
+            if node.depth == total_len {
+                if node.is_word {
+                    words.push(prefix.clone());
+                }
+                continue;
+            }
+            let len = prefix.len();
+            stack.extend(
+                node.children
+                    .values()
+                    .rev()
+                    .map(|child_rc| (node.checked_child(child_rc), Some(len))),
+            );
+        }
+        prefix.truncate(start_len);
     }
 
-    pub fn merge(&self, other: BaseLetterTrie) {
+    // Add the words with as many letters as target that differ from it in at most max_mismatches places, skipping
+    // any branch whose longest word is too short or that already differs in too many places.
+    fn push_hamming_matches(
+        &self,
+        target: &[char],
+        max_mismatches: usize,
+        words: &mut Vec<String>,
+    ) {
+        let mut prefix = String::new();
+        // Each node waits with the length of its parent's letters and how many places differ down to it.
+        let mut stack: Vec<(ChildLink, Option<usize>, usize)> =
+            vec![(Rc::clone(&self.root), None, 0)];
+        while let Some((rc, parent_len, mismatches)) = stack.pop() {
+            let node = rc.borrow();
+            if node.longest_word_len < target.len() {
+                continue;
+            }
+            if let Some(parent_len) = parent_len {
+                prefix.truncate(parent_len);
+                prefix.push(node.c);
+            }
+            if node.depth == target.len() {
+                if node.is_word {
+                    words.push(prefix.clone());
+                }
+                continue;
+            }
+            let len = prefix.len();
+            let target_c = target[node.depth];
+            for (c, child_rc) in node.children.iter().rev() {
+                let mismatches = mismatches + (*c != target_c) as usize;
+                if mismatches <= max_mismatches {
+                    stack.push((node.checked_child(child_rc), Some(len), mismatches));
+                }
+            }
+        }
+    }
+
+    // The prefix buffer holds the starting node's letters on entry and is restored to that before returning. The
+    // walk keeps its own stack rather than recursing so that a very deep trie can't overflow the call stack. The
+    // spellings and scores are only looked up for words, and not at all if there aren't any.
+    fn visit_from(
+        start: ChildLink,
+        prefix: &mut String,
+        spellings: &BTreeMap<String, String>,
+        scores: &RefCell<BTreeMap<String, f64>>,
+        max_scores: &RefCell<BTreeMap<String, f64>>,
+        reverse: bool,
+        f: &mut dyn FnMut(&NodeView) -> bool,
+    ) {
+        let scores = scores.borrow();
+        let max_scores = max_scores.borrow();
+        let start_len = prefix.len();
+        // Each entry is a node still to be visited and the length of its parent's prefix, or None for the starting
+        // node since its letters are already in the buffer.
+        let mut stack: Vec<(ChildLink, Option<usize>)> = vec![(start, None)];
+        while let Some((rc, parent_len)) = stack.pop() {
+            let node = rc.borrow();
+            if let Some(parent_len) = parent_len {
+                prefix.truncate(parent_len);
+                prefix.push(node.c);
+            }
+            let node_view = NodeView {
+                c: node.c,
+                prefix,
+                depth: node.depth,
+                is_word: node.is_word,
+                child_count: node.children.len(),
+                spelling: if node.is_word && !spellings.is_empty() {
+                    spellings.get(prefix.as_str()).map(String::as_str)
+                } else {
+                    None
+                },
+                score: if node.is_word && !scores.is_empty() {
+                    scores.get(prefix.as_str()).copied()
+                } else {
+                    None
+                },
+                max_score: if max_scores.is_empty() {
+                    None
+                } else {
+                    max_scores.get(prefix.as_str()).copied()
+                },
+            };
+            if f(&node_view) {
+                let len = prefix.len();
+                // The stack is popped from the end, so the children are pushed in the opposite order to the one
+                // they're visited in.
+                let child = |child_rc| (node.checked_child(child_rc), Some(len));
+                if reverse {
+                    stack.extend(node.children.values().map(child));
+                } else {
+                    stack.extend(node.children.values().rev().map(child));
+                }
+            }
+        }
+        prefix.truncate(start_len);
+    }
+
+    // The other trie's tags and scores come along with its words, but not its sources, spellings or ranks. Use
+    // merge_tagged() for those.
+    pub fn merge(&mut self, other: BaseLetterTrie) {
+        self.clear_prefix_cache();
+        self.tags.borrow_mut().append(&mut other.tags.borrow_mut());
+        self.scores
+            .borrow_mut()
+            .append(&mut other.scores.borrow_mut());
+        // The other trie's prefixes start with letters this one doesn't have, apart from the root's.
+        let mut max_scores = self.max_scores.borrow_mut();
+        let root_max = [&*max_scores, &*other.max_scores.borrow()]
+            .iter()
+            .filter_map(|max_scores| max_scores.get("").copied())
+            .reduce(f64::max);
+        max_scores.append(&mut other.max_scores.borrow_mut());
+        if let Some(root_max) = root_max {
+            max_scores.insert(String::new(), root_max);
+        }
+        drop(max_scores);
         let mut this_node = self.root.borrow_mut();
-        for other_child_node_link in other.root.borrow().children.values() {
+        // Take the children away from the other root rather than cloning the links so that each child node still
+        // has exactly one strong reference once it's been moved over.
+        let other_children = core::mem::take(&mut other.root.borrow_mut().children);
+        for (c, other_child_node_link) in other_children {
             debug_assert!(Self::child_link_has_normal_ref_counts(
                 &other_child_node_link
             ));
@@ -146,16 +553,16 @@ impl BaseLetterTrie {
             debug_assert!(Self::opt_parent_link_has_normal_ref_counts(
                 &other_child_node.parent
             ));
-            let c = other_child_node.c;
-            this_node
-                .children
-                .insert(c, Rc::clone(other_child_node_link));
-            debug_assert!(Self::child_link_has_normal_ref_counts(
-                &other_child_node_link
-            ));
+            this_node.longest_word_len = cmp::max(
+                this_node.longest_word_len,
+                other_child_node.longest_word_len,
+            );
+            drop(other_child_node);
+            this_node.children.insert(c, other_child_node_link);
         }
     }
 
+    #[cfg(feature = "std")]
     pub fn print_prefixes(&self, prefix_count: usize) -> usize {
         self.root.borrow().print_prefixes(prefix_count)
     }
@@ -166,6 +573,7 @@ impl BaseLetterTrie {
         v
     }
 
+    #[cfg(feature = "std")]
     pub fn print_words(&self, word_count: usize) {
         let v = self.get_words(word_count);
         for word in v {
@@ -177,13 +585,63 @@ impl BaseLetterTrie {
         self.root.borrow().is_frozen
     }
 
-    pub fn iter_breadth_first(&self) -> BaseLetterTrieIteratorBreadthFirst {
+    /// Iterate over the nodes breadth first starting with the root.
+    ///
+    /// Unlike `words()`, which collects the words when it's called, this walks the nodes as it goes and so
+    /// borrows the trie until it's dropped. The trie can't be changed in the meantime:
+    ///
+    /// ```compile_fail,E0502
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["cross", "an"]);
+    /// for node in trie.iter_breadth_first() {
+    ///     trie.insert("crossed");
+    /// }
+    /// ```
+    ///
+    /// The same goes for merging another trie into this one or adding a word with `add_from_vec_chars()`:
+    ///
+    /// ```compile_fail,E0502
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["cross", "an"]);
+    /// for node in trie.iter_breadth_first() {
+    ///     trie.merge(BaseLetterTrie::from_words(vec!["crossed"]));
+    /// }
+    /// ```
+    ///
+    /// ```compile_fail,E0502
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["cross", "an"]);
+    /// let mut nodes = trie.iter_prefix("cross");
+    /// nodes.next();
+    /// trie.add_from_vec_chars(&['c', 'r', 'y'], 3, 0);
+    /// nodes.next();
+    /// ```
+    pub fn iter_breadth_first(&self) -> BaseLetterTrieIteratorBreadthFirst<'_> {
         BaseLetterTrieIteratorBreadthFirst {
             stack: vec![Rc::clone(&self.root)],
+            trie: PhantomData,
         }
     }
 
-    pub fn iter_prefix(&self, prefix: &str) -> BaseLetterTrieIteratorPrefix {
+    /// Iterate over the nodes along `prefix` starting with the root, stopping early if `prefix` isn't in the
+    /// trie.
+    ///
+    /// As with `iter_breadth_first()` this borrows the trie until it's dropped, so the trie can't be changed in
+    /// the meantime:
+    ///
+    /// ```compile_fail,E0502
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["cross", "an"]);
+    /// let mut nodes = trie.iter_prefix("cross");
+    /// nodes.next();
+    /// trie.remove("cross");
+    /// nodes.next();
+    /// ```
+    pub fn iter_prefix(&self, prefix: &str) -> BaseLetterTrieIteratorPrefix<'_> {
         let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
         let prefix_len = prefix.len();
         BaseLetterTrieIteratorPrefix {
@@ -191,6 +649,7 @@ impl BaseLetterTrie {
             prefix_len,
             prefix_index: 0,
             rc: Rc::clone(&self.root),
+            trie: PhantomData,
         }
     }
 
@@ -202,151 +661,175 @@ impl BaseLetterTrie {
         self.root.borrow_mut().unfreeze();
     }
 
-    fn print(&self, detail_level: usize) {
-        match detail_level {
-            1 => println!("{:?}", self.to_fixed_node()),
-            2 => println!("{:#?}", self.to_fixed_node()),
-            _ => (),
+    // The load behind from_reader_test() once the reader has been wrapped for the duplicate policy if need be.
+    #[cfg(feature = "std")]
+    fn load_from_reader<R: BufRead>(
+        reader: R,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+    ) -> Result<Self> {
+        let mut t = Self::new();
+        let timer = Timer::overall(opt);
+        #[cfg(feature = "parallel")]
+        if uses_read_ahead(load_method, opt) {
+            let t = load_with_read_ahead(reader, opt, expected_word_count)?;
+            timer.stop();
+            return Ok(t);
         }
-    }
-
-    fn load_read_vec_fill(
-        &self,
-        filename: &str,
+        match load_method {
+            LoadMethod::ReadVecFill => t.load_read_vec_fill(reader, opt, expected_word_count),
+            LoadMethod::VecFill => t.load_vec_fill(reader, opt, expected_word_count),
+            LoadMethod::Continuous => t.load_continuous(reader, expected_word_count),
+            #[cfg(feature = "parallel")]
+            LoadMethod::ContinuousParallel => {
+                if is_sorted {
+                    t.load_continuous_parallel_sorted(reader, opt, expected_word_count)
+                } else {
+                    t.load_parallel_unsorted(reader, opt, expected_word_count)
+                }
+            }
+            // Without threads the parallel load is the same as the continuous one.
+            #[cfg(not(feature = "parallel"))]
+            LoadMethod::ContinuousParallel => {
+                let _ = is_sorted;
+                t.load_continuous(reader, expected_word_count)
+            }
+            #[cfg(feature = "fs")]
+            LoadMethod::External {
+                temp_dir,
+                chunk_words,
+            } => external::load_external(reader, temp_dir, *chunk_words, opt, expected_word_count)
+                .map(|other| t.merge(other)),
+        }?;
+        timer.stop();
+        Ok(t)
+    }
+
+    #[cfg(feature = "std")]
+    fn load_read_vec_fill<R: BufRead>(
+        &mut self,
+        mut reader: R,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
-    ) {
-        println!("{}", filename);
-        let start = Instant::now();
-        let content = fs::read_to_string(filename).expect("Error reading file.");
-        print_elapsed_from_start(opt.print_step_time, &opt.label, LABEL_STEP_READ_FILE, start);
-
-        let start = Instant::now();
-        let words: Vec<&str> = content
-            .split('\n')
-            .map(|x| x.trim())
-            .filter(|x| !x.is_empty())
-            .collect();
+    ) -> Result<()> {
+        let mut timer = Timer::new(LABEL_STEP_READ_FILE, opt);
+        let hints = opt.capacity_hints();
+        let mut content = String::with_capacity(hints.map_or(0, |hints| hints.bytes()));
+        reader.read_to_string(&mut content)?;
+        timer.set_intermediate_bytes(content.capacity());
+        timer.stop();
+
+        let mut timer = Timer::new(LABEL_STEP_MAKE_VECTOR, opt);
+        // Every word but the last takes at least two bytes with its newline.
+        let mut words: Vec<&str> =
+            Vec::with_capacity(hints.map_or(0, |hints| hints.words().min(content.len() / 2 + 1)));
+        words.extend(
+            content
+                .split('\n')
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty()),
+        );
         if let Some(exp_word_count) = expected_word_count {
             assert_eq!(words.len(), exp_word_count);
         }
-        print_elapsed_from_start(
-            opt.print_step_time,
-            &opt.label,
-            LABEL_STEP_MAKE_VECTOR,
-            start,
-        );
+        timer.set_intermediate_bytes(str_vec_bytes(&content, &words));
+        timer.stop();
 
-        if opt.object_detail_level >= 1 {
-            println!("\nWord count = {}", words.len());
-        }
+        opt.print_word_count(words.len());
 
-        let start = Instant::now();
+        let timer = Timer::new(LABEL_STEP_LOAD_FROM_VEC, opt);
         for word in words {
             self.add_word(word);
         }
-        print_elapsed_from_start(
-            opt.print_step_time,
-            &opt.label,
-            LABEL_STEP_LOAD_FROM_VEC,
-            start,
-        );
+        timer.stop();
 
-        self.print(opt.object_detail_level);
+        opt.print_trie(self);
+        Ok(())
     }
 
-    fn load_vec_fill(
-        &self,
-        filename: &str,
+    #[cfg(feature = "std")]
+    fn load_vec_fill<R: BufRead>(
+        &mut self,
+        reader: R,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
-    ) {
-        let start = Instant::now();
-        let v = make_vec_char_test(filename, opt, expected_word_count);
+    ) -> Result<()> {
+        let timer = Timer::new(LABEL_STEP_LOAD_FROM_VEC, opt);
+        let v = make_vec_char_from_reader_test(reader, opt, expected_word_count)?;
         for vec_char in v {
             let v_len = vec_char.len();
             self.add_from_vec_chars(&vec_char, v_len, 0);
         }
-        print_elapsed_from_start(
-            opt.print_step_time,
-            &opt.label,
-            LABEL_STEP_LOAD_FROM_VEC,
-            start,
-        );
-        self.print(opt.object_detail_level);
+        timer.stop();
+        opt.print_trie(self);
+        Ok(())
     }
 
-    fn load_continuous(&self, filename: &str, expected_word_count: Option<usize>) {
-        let file = File::open(filename).unwrap();
-        let lines = BufReader::new(file)
-            .lines()
-            .map(|x| x.unwrap().trim().to_owned())
-            .filter(|x| !x.is_empty())
-            .collect::<Vec<String>>();
+    #[cfg(feature = "std")]
+    fn load_continuous<R: BufRead>(
+        &mut self,
+        reader: R,
+        expected_word_count: Option<usize>,
+    ) -> Result<()> {
+        let word_count = for_each_trimmed_line(reader, |line| self.add_word(line))?;
         if let Some(exp_word_count) = expected_word_count {
-            assert_eq!(lines.len(), exp_word_count);
-        }
-
-        for line in lines {
-            let vec_char: Vec<char> = line.to_lowercase().chars().collect();
-            let v_len = vec_char.len();
-            self.add_from_vec_chars(&vec_char, v_len, 0);
+            assert_eq!(word_count, exp_word_count);
         }
+        Ok(())
     }
 
-    fn load_continuous_parallel_sorted(&self, filename: &str, expected_word_count: Option<usize>) {
+    #[cfg(feature = "parallel")]
+    fn load_continuous_parallel_sorted<R: BufRead>(
+        &mut self,
+        reader: R,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+    ) -> Result<()> {
+        let dispatch_start = Instant::now();
         let (tx, rx) = mpsc::channel();
 
-        let file = File::open(filename).unwrap();
-        let lines = BufReader::new(file)
-            .lines()
-            .map(|x| x.unwrap().trim().to_owned())
-            .filter(|x| !x.is_empty())
-            .collect::<Vec<String>>();
-        if let Some(exp_word_count) = expected_word_count {
-            assert_eq!(lines.len(), exp_word_count);
-        }
-
         let mut thread_count = 0;
         let mut prev_c = ' ';
         let mut this_vec: Vec<Vec<char>> = vec![];
-        for line in lines {
-            let vec_char: Vec<char> = line.to_lowercase().chars().collect();
+        let word_count = for_each_trimmed_line(reader, |line| {
+            let vec_char = lowercase_vec_chars(line);
             let this_c = vec_char[0];
             if this_c != prev_c {
-                thread_count +=
-                    Self::create_thread_for_part_of_vec(this_vec, mpsc::Sender::clone(&tx));
-                this_vec = vec![];
+                let part = mem::take(&mut this_vec);
+                thread_count += Self::create_thread_for_part_of_vec(part, mpsc::Sender::clone(&tx));
                 prev_c = this_c;
             }
-            this_vec.push(vec_char.clone());
+            this_vec.push(vec_char);
+        })?;
+        if let Some(exp_word_count) = expected_word_count {
+            assert_eq!(word_count, exp_word_count);
         }
 
+        // The words of the last letter are only sent once the input has run out, whether or not the last line ended
+        // in a newline. A group is never empty unless there were no words at all, and then no thread is started.
         thread_count += Self::create_thread_for_part_of_vec(this_vec, mpsc::Sender::clone(&tx));
+        drop(tx);
 
-        for (received_index, received) in rx.iter().enumerate() {
-            self.merge(received);
-            if received_index == thread_count - 1 {
-                break;
-            }
-        }
+        self.merge_parts(&rx, thread_count, dispatch_start.elapsed(), opt)
     }
 
-    fn load_parallel_unsorted(
-        &self,
-        filename: &str,
+    #[cfg(feature = "parallel")]
+    fn load_parallel_unsorted<R: BufRead>(
+        &mut self,
+        reader: R,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
-    ) {
-        let mut v = make_vec_char_test(filename, opt, expected_word_count);
+    ) -> Result<()> {
+        let mut v = make_vec_char_from_reader_test(reader, opt, expected_word_count)?;
 
-        print_elapsed(
-            opt.print_step_time,
-            &opt.label,
-            LABEL_STEP_SORT_VECTOR,
-            || v.sort_unstable_by(|a, b| a[0].cmp(&b[0])),
-        );
+        let mut timer = Timer::new(LABEL_STEP_SORT_VECTOR, opt);
+        sort_by_first_char(&mut v);
+        timer.set_intermediate_bytes(vec_char_bytes(&v));
+        timer.stop();
 
+        let dispatch_start = Instant::now();
         let (tx, rx) = mpsc::channel();
 
         let mut thread_count = 0;
@@ -360,29 +843,77 @@ impl BaseLetterTrie {
                 this_vec = vec![];
                 prev_c = this_c;
             }
-            this_vec.push(vec_char.clone());
+            this_vec.push(vec_char);
         }
 
         thread_count += Self::create_thread_for_part_of_vec(this_vec, mpsc::Sender::clone(&tx));
+        drop(tx);
 
-        for (received_index, received) in rx.iter().enumerate() {
-            self.merge(received);
-            if received_index == thread_count - 1 {
-                break;
-            }
+        self.merge_parts(&rx, thread_count, dispatch_start.elapsed(), opt)
+    }
+
+    // Merge the part from each of the worker threads as it arrives, timing the waits and the merges for
+    // DisplayDetailOptions::parallel_load_stats(). The loading thread drops its own sender first, so if a worker
+    // panics the channel closes once the others are done and the load fails rather than waiting forever.
+    #[cfg(feature = "parallel")]
+    fn merge_parts(
+        &mut self,
+        rx: &mpsc::Receiver<WorkerPart>,
+        thread_count: usize,
+        dispatch_time: Duration,
+        opt: &DisplayDetailOptions,
+    ) -> Result<()> {
+        let mut stats = ParallelLoadStats {
+            dispatch_time,
+            ..Default::default()
+        };
+        for _ in 0..thread_count {
+            let wait_start = Instant::now();
+            let part = rx.recv().map_err(|_| worker_panicked())?;
+            stats.worker_wait += wait_start.elapsed();
+            let mut letter = part.stats;
+            letter.queue_wait = part.finished.elapsed();
+            let merge_start = Instant::now();
+            self.merge(part.trie);
+            letter.merge_time = merge_start.elapsed();
+            CharGetCounter::add(&part.counts);
+            stats.letters.push(letter);
         }
+        stats.letters.sort_by_key(|letter| letter.letter);
+        opt.report_parallel_load(stats);
+        Ok(())
     }
 
     // Returns the number of threads spawned, which will be 1 if there are items in the vector, otherwise 0.
-    fn create_thread_for_part_of_vec(v: Vec<Vec<char>>, tx: mpsc::Sender<BaseLetterTrie>) -> usize {
+    #[cfg(feature = "parallel")]
+    fn create_thread_for_part_of_vec(v: Vec<Vec<char>>, tx: mpsc::Sender<WorkerPart>) -> usize {
         if !v.is_empty() {
+            // The worker counts for itself if the loading thread is counting, and sends its counts back with its
+            // part of the trie.
+            let counting = CharGetCounter::is_enabled();
             thread::spawn(move || {
-                let t = BaseLetterTrie::new();
+                CharGetCounter::set_enabled(counting);
+                let start = Instant::now();
+                let letter = v[0][0];
+                let word_count = v.len();
+                let mut t = BaseLetterTrie::new();
                 for vec_char in v {
                     let v_len = vec_char.len();
                     t.add_from_vec_chars(&vec_char, v_len, 0);
                 }
-                tx.send(t).unwrap();
+                let stats = LetterLoadStats {
+                    letter,
+                    word_count,
+                    build_time: start.elapsed(),
+                    ..Default::default()
+                };
+                // The loading thread only stops listening once the load has failed, and then the part isn't wanted.
+                let _ = tx.send(WorkerPart {
+                    trie: t,
+                    counts: CharGetCounter::take(),
+                    stats,
+                    finished: Instant::now(),
+                });
             });
             1
         } else {
@@ -391,9 +922,7 @@ impl BaseLetterTrie {
     }
 
     pub fn find(&self, prefix: &str) -> Option<FixedNode> {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
-        let prefix_len = prefix.len();
-        self.root.borrow().find_child(prefix, prefix_len, 0)
+        TrieQuery::find(self, prefix)
     }
 
     pub fn find_loop(&self, prefix: &str) -> Option<FixedNode> {
@@ -413,17 +942,71 @@ impl BaseLetterTrie {
                     };
                 }
                 let c = prefix[prefix_index];
-                let rc_opt = rc.borrow().children.get(&c).map(|x| Rc::clone(x));
-                if let Some(rc_next) = rc_opt {
+                let rc_opt = rc.borrow().children.get(&c).map(Rc::clone);
+                {
+                    let rc_next = rc_opt?;
                     rc = rc_next;
                     prefix_index += 1;
-                } else {
-                    return None;
                 }
             }
         }
     }
 
+    /// Returns the node at the end of the prefix followed by each node above it up to and including the root,
+    /// found by following the parent links rather than by walking down again from the root. The result is None if
+    /// no word starts with the prefix. An empty prefix gives just the root.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed", "an"]);
+    /// let ancestors = trie.ancestors("Cros").unwrap();
+    /// let letters: String = ancestors.iter().map(|node| node.c).collect();
+    /// assert_eq!("sorc ", letters);
+    /// assert_eq!(2, ancestors[0].word_count);
+    /// assert_eq!(3, ancestors.last().unwrap().word_count);
+    /// assert!(trie.ancestors("crow").is_none());
+    /// ```
+    pub fn ancestors(&self, prefix: &str) -> Option<Vec<FixedNode>> {
+        let mut rc = self.find_link_query(prefix)?;
+        let mut ancestors: Vec<FixedNode> = vec![];
+        loop {
+            let parent_opt = {
+                let node = rc.borrow();
+                ancestors.push(node.to_fixed_node());
+                node.parent
+                    .as_ref()
+                    .and_then(|parent_weak| parent_weak.upgrade())
+            };
+            match parent_opt {
+                Some(parent_rc) => rc = parent_rc,
+                None => break,
+            }
+        }
+        debug_assert!(Rc::ptr_eq(&rc, &self.root));
+        Some(ancestors)
+    }
+
+    /// Returns the prefix of the node reached by the given prefix as rebuilt from the characters above it by
+    /// following the parent links, which is the lowercased prefix unless the links are broken. The result is None
+    /// if no word starts with the prefix.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed", "an"]);
+    /// assert_eq!(Some("crosse".to_owned()), trie.prefix_from_parents("CROSSE"));
+    /// assert_eq!(Some(String::new()), trie.prefix_from_parents(""));
+    /// assert_eq!(None, trie.prefix_from_parents("crow"));
+    /// ```
+    pub fn prefix_from_parents(&self, prefix: &str) -> Option<String> {
+        let rc = self.find_link_query(prefix)?;
+        let rebuilt = rc.borrow().prefix();
+        Some(rebuilt)
+    }
+
     pub fn is_word_recursive(&self, prefix: &str) -> bool {
         let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
         let prefix_len = prefix.len();
@@ -443,7 +1026,7 @@ impl BaseLetterTrie {
                     return rc.borrow().is_word;
                 }
                 let c = prefix[prefix_index];
-                let rc_opt = rc.borrow().children.get(&c).map(|x| Rc::clone(x));
+                let rc_opt = rc.borrow().children.get(&c).map(Rc::clone);
                 if let Some(rc_next) = rc_opt {
                     rc = rc_next;
                     prefix_index += 1;
@@ -454,6 +1037,136 @@ impl BaseLetterTrie {
         }
     }
 
+    /// Report the `Rc` strong count and the `Weak` count of each node down to `max_depth`, where the root is at
+    /// depth 0, and flag the nodes where they aren't what they should be. This is for tracking down a bug in the
+    /// way the nodes are linked; see the `rc_report` module for what the counts mean.
+    ///
+    /// The counts are taken as they are when the report is made, so a node that a live iterator from
+    /// `iter_breadth_first()` or `iter_prefix()` is holding on to shows up as shared.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// let report = trie.rc_report(usize::MAX);
+    /// assert_eq!(8, report.nodes.len());
+    /// assert!(report.is_clean());
+    /// ```
+    pub fn rc_report(&self, max_depth: usize) -> RcReport {
+        let mut report = RcReport {
+            max_depth,
+            ..RcReport::default()
+        };
+        // The prefixes come from the walk rather than from the parent links, which may be what's broken.
+        let mut stack: Vec<(String, ChildLink)> = vec![(String::new(), Rc::clone(&self.root))];
+        while let Some((prefix, rc)) = stack.pop() {
+            let node = rc.borrow();
+            let counts = RcNodeCounts {
+                prefix,
+                depth: node.depth,
+                // Less the clone on the stack.
+                strong_count: Rc::strong_count(&rc) - 1,
+                weak_count: Rc::weak_count(&rc),
+                child_count: node.children.len(),
+            };
+            if node.depth < max_depth {
+                for (c, child_rc) in node.children.iter().rev() {
+                    // A link that doesn't go one level down, as a link back up the trie from a bug wouldn't, isn't
+                    // followed so that the walk can't go around a cycle. The node it goes to is still reported
+                    // under its own parent, as shared.
+                    let child_depth = child_rc.try_borrow().ok().map(|child| child.depth);
+                    if child_depth == Some(node.depth + 1) {
+                        let mut child_prefix = counts.prefix.clone();
+                        child_prefix.push(*c);
+                        stack.push((child_prefix, Rc::clone(child_rc)));
+                    }
+                }
+            }
+            if let Some(anomaly) = counts.anomaly() {
+                report.anomalies.push(anomaly);
+            }
+            report.nodes.push(counts);
+        }
+        report
+    }
+
+    // Check the parts of the trie that verify_structure() can't see: the parent links, the reference counts
+    // described on ChildLink and ParentLink as rc_report() checks them, and the character stored in each node
+    // compared to its key.
+    fn verify_links(&self) -> Result<(), String> {
+        if self.root.borrow().parent.is_some() {
+            return Err("the root has a parent".to_owned());
+        }
+        let mut stack: Vec<ChildLink> = vec![Rc::clone(&self.root)];
+        while let Some(rc) = stack.pop() {
+            let node = rc.borrow();
+            // Less the clone on the stack, as in rc_report().
+            let anomaly = RcAnomaly::find(
+                Rc::strong_count(&rc) - 1,
+                Rc::weak_count(&rc),
+                node.children.len(),
+                || node.prefix(),
+            );
+            if let Some(anomaly) = anomaly {
+                return Err(format!("{}", anomaly));
+            }
+            for (c, child_rc) in &node.children {
+                let child_node = match child_rc.try_borrow() {
+                    Ok(child_node) => child_node,
+                    Err(_) => {
+                        return Err(format!(
+                            "the child {:?} of \"{}\" links back to the node itself",
+                            c,
+                            node.prefix()
+                        ))
+                    }
+                };
+                if child_node.depth != node.depth + 1 {
+                    return Err(format!(
+                        "the child {:?} of \"{}\" has a depth of {} instead of {}, which a link back up the trie \
+                        would cause",
+                        c,
+                        node.prefix(),
+                        child_node.depth,
+                        node.depth + 1
+                    ));
+                }
+                if child_node.c != *c {
+                    return Err(format!(
+                        "the child {:?} of \"{}\" holds {:?}",
+                        c,
+                        node.prefix(),
+                        child_node.c
+                    ));
+                }
+                let links_back = child_node
+                    .parent
+                    .as_ref()
+                    .and_then(|parent_weak| parent_weak.upgrade())
+                    .map(|parent_rc| Rc::ptr_eq(&parent_rc, &rc))
+                    .unwrap_or(false);
+                if !links_back {
+                    return Err(format!(
+                        "the child {:?} of \"{}\" doesn't link back to it",
+                        c,
+                        node.prefix()
+                    ));
+                }
+                if child_node.is_frozen != node.is_frozen {
+                    return Err(format!(
+                        "the child {:?} of \"{}\" isn't frozen the same way as its parent",
+                        c,
+                        node.prefix()
+                    ));
+                }
+                stack.push(Rc::clone(child_rc));
+            }
+        }
+        Ok(())
+    }
+
     fn child_link_has_normal_ref_counts(rc: &ChildLink) -> bool {
         // The Rc pointing to a node will normally have a count of one, either from the BaseLetterTrie to the root
         // node or from a parent node to a child node.
@@ -497,79 +1210,489 @@ impl BaseLetterTrie {
 
     fn opt_parent_link_has_normal_ref_counts(weak_opt: &Option<ParentLink>) -> bool {
         if let Some(weak) = weak_opt {
-            Self::parent_link_has_normal_ref_counts(&weak)
+            Self::parent_link_has_normal_ref_counts(weak)
         } else {
             true
         }
     }
 }
 
-impl LetterTrie for BaseLetterTrie {
-    fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self {
-        let opt = DisplayDetailOptions::make_no_display();
-        Self::from_file_test(filename, is_sorted, load_method, &opt, None)
+impl view::sealed::Sealed for BaseLetterTrie {}
+
+impl TrieQuery for BaseLetterTrie {
+    fn sources(&self, word: &str) -> Option<&[u16]> {
+        if self.sources.is_empty() {
+            return None;
+        }
+        self.sources.get(&word_key(word)?).map(Vec::as_slice)
+    }
+
+    fn spelling(&self, word: &str) -> Option<&str> {
+        if self.spellings.is_empty() {
+            return None;
+        }
+        self.spellings.get(&word_key(word)?).map(String::as_str)
+    }
+
+    fn tag(&self, word: &str) -> Option<u64> {
+        let tags = self.tags.borrow();
+        if tags.is_empty() {
+            return None;
+        }
+        tags.get(&word_key(word)?).copied()
+    }
+
+    fn score(&self, word: &str) -> Option<f64> {
+        let scores = self.scores.borrow();
+        if scores.is_empty() {
+            return None;
+        }
+        scores.get(&word_key(word)?).copied()
+    }
+
+    fn rank_of(&self, word: &str) -> Option<u32> {
+        if self.ranks.is_empty() {
+            return None;
+        }
+        self.ranks.get(&word_key(word)?).copied()
+    }
+
+    fn find(&self, prefix: &str) -> Option<FixedNode> {
+        let prefix = prefix.trim();
+        trace::traced(
+            || TraceOp::Find(prefix.to_owned()),
+            || {
+                #[cfg(feature = "std")]
+                if let Some(cache) = &self.prefix_cache {
+                    return cache.find(prefix, || self.find_query(prefix));
+                }
+                self.find_query(prefix)
+            },
+        )
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        let word = word.trim();
+        // An ASCII word has one letter per byte, so one longer than any stored word can't be in the trie.
+        if word.is_ascii() && word.len() > self.root.borrow().longest_word_len {
+            return false;
+        }
+        self.find_link_query(word)
+            .is_some_and(|rc| rc.borrow().is_word)
+    }
+
+    fn contains_prefix(&self, prefix: &str) -> bool {
+        let prefix = prefix.trim();
+        self.find_link_query(prefix).is_some()
+    }
+
+    fn to_fixed_node(&self) -> FixedNode {
+        self.root.borrow().to_fixed_node()
+    }
+
+    fn visit(&self, prefix: &str, f: &mut dyn FnMut(&NodeView) -> bool) {
+        let prefix = prefix.trim();
+        let mut prefix = prefix.to_lowercase();
+        if let Some(rc) = self.find_link(&prefix) {
+            Self::visit_from(
+                rc,
+                &mut prefix,
+                &self.spellings,
+                &self.scores,
+                &self.max_scores,
+                false,
+                f,
+            );
+        }
     }
 
-    fn from_file_test(
-        filename: &str,
+    fn visit_rev(&self, prefix: &str, f: &mut dyn FnMut(&NodeView) -> bool) {
+        let prefix = prefix.trim();
+        let mut prefix = prefix.to_lowercase();
+        if let Some(rc) = self.find_link(&prefix) {
+            Self::visit_from(
+                rc,
+                &mut prefix,
+                &self.spellings,
+                &self.scores,
+                &self.max_scores,
+                true,
+                f,
+            );
+        }
+    }
+
+    fn cursor(&self) -> TrieCursor<'_> {
+        TrieCursor::new(cursor::Position::Base(
+            CursorNode(Rc::clone(&self.root)),
+            PhantomData,
+        ))
+    }
+
+    // The children are a BTreeMap so they're in character order whatever the load method, including the merges
+    // done by the parallel loads.
+    fn children(&self, prefix: &str) -> Vec<char> {
+        let prefix = prefix.trim();
+        self.find_link(&prefix.to_lowercase())
+            .map(|rc| rc.borrow().children.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    fn next_letters(&self, prefix: &str) -> Vec<(char, bool)> {
+        let prefix = prefix.trim();
+        self.find_link(&prefix.to_lowercase())
+            .map(|rc| {
+                rc.borrow()
+                    .children
+                    .iter()
+                    .map(|(c, child)| (*c, child.borrow().is_word))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl LetterTrie for BaseLetterTrie {
+    #[cfg(feature = "std")]
+    fn from_reader_test<R: BufRead>(
+        reader: R,
         is_sorted: bool,
         load_method: &LoadMethod,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
-    ) -> Self {
-        let t = Self::new();
-        print_elapsed(
-            opt.print_overall_time,
-            &opt.label,
-            LABEL_STEP_OVERALL,
+    ) -> Result<Self> {
+        let _span = opt.enter_load_span(load_method, LetterTrieType::Base);
+        match opt.duplicate_policy {
+            DuplicatePolicy::Ignore => {
+                Self::load_from_reader(reader, is_sorted, load_method, opt, expected_word_count)
+            }
+            policy => duplicates::load_checking_duplicates(reader, policy, opt, |reader| {
+                Self::load_from_reader(reader, is_sorted, load_method, opt, expected_word_count)
+            }),
+        }
+    }
+
+    fn from_words<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        trace::traced_with(
             || {
-                match load_method {
-                    LoadMethod::ReadVecFill => {
-                        t.load_read_vec_fill(filename, opt, expected_word_count);
-                    }
-                    LoadMethod::VecFill => {
-                        t.load_vec_fill(filename, opt, expected_word_count);
-                    }
-                    LoadMethod::Continuous => {
-                        t.load_continuous(filename, expected_word_count);
+                let t = Self::new();
+                for word in words {
+                    t.add_word(word.as_ref());
+                }
+                t
+            },
+            |t| TraceOp::FromWords(t.words_with_prefix("")),
+        )
+    }
+
+    // Each group goes to a worker thread of its own and the parts are merged as they arrive, as in a
+    // LoadMethod::ContinuousParallel load of a sorted file.
+    fn from_letter_groups<I>(groups: I, parallel: bool) -> Result<Self>
+    where
+        I: IntoIterator<Item = (char, Vec<String>)>,
+    {
+        let groups = check_letter_groups(groups)?;
+        let mut t = Self::new();
+        #[cfg(feature = "parallel")]
+        if parallel {
+            let dispatch_start = Instant::now();
+            let (tx, rx) = mpsc::channel();
+            let mut thread_count = 0;
+            for (_, words) in groups {
+                let part = words.iter().map(|word| lowercase_vec_chars(word)).collect();
+                thread_count += Self::create_thread_for_part_of_vec(part, mpsc::Sender::clone(&tx));
+            }
+            drop(tx);
+            let opt = DisplayDetailOptions::make_no_display();
+            t.merge_parts(&rx, thread_count, dispatch_start.elapsed(), &opt)?;
+            return Ok(t);
+        }
+        #[cfg(not(feature = "parallel"))]
+        let _ = parallel;
+        for (_, words) in groups {
+            let part = Self::new();
+            for word in words {
+                part.add_word(&word);
+            }
+            t.merge(part);
+        }
+        Ok(t)
+    }
+
+    fn insert(&mut self, word: &str) {
+        trace::traced(
+            || TraceOp::Insert(word.to_owned()),
+            || {
+                if self.is_frozen() {
+                    self.unfreeze();
+                }
+                self.add_word(word);
+            },
+        )
+    }
+
+    fn remove(&mut self, word: &str) -> bool {
+        trace::traced(
+            || TraceOp::Remove(word.to_owned()),
+            || {
+                if self.is_frozen() {
+                    self.unfreeze();
+                }
+                let removed = self.remove_word(word);
+                if let (true, Some(key)) = (removed, word_key(word)) {
+                    self.tags.borrow_mut().remove(&key);
+                    self.sources.remove(&key);
+                    self.spellings.remove(&key);
+                    self.ranks.remove(&key);
+                    if self.scores.borrow_mut().remove(&key).is_some() {
+                        self.update_max_scores(&key);
                     }
-                    LoadMethod::ContinuousParallel => {
-                        if is_sorted {
-                            t.load_continuous_parallel_sorted(filename, expected_word_count);
-                        } else {
-                            t.load_parallel_unsorted(filename, opt, expected_word_count);
-                        }
+                }
+                removed
+            },
+        )
+    }
+
+    fn remove_sorted_words(&mut self, words: &[Vec<char>]) -> RemovalReport {
+        if self.is_frozen() {
+            self.unfreeze();
+        }
+        self.clear_prefix_cache();
+        let mut report = RemovalReport::default();
+        let mut removed_keys: Vec<String> = vec![];
+        // The nodes below the root on the way to the current word.
+        let mut path: Vec<ChildLink> = vec![];
+        for word in words {
+            let shared = removal::shared_len(path.iter().map(|rc| rc.borrow().c), word);
+            while path.len() > shared {
+                self.leave_node(&mut path, &mut report);
+            }
+            for c in &word[shared..] {
+                let rc_next = path
+                    .last()
+                    .unwrap_or(&self.root)
+                    .borrow()
+                    .children
+                    .get(c)
+                    .map(Rc::clone);
+                match rc_next {
+                    Some(rc) => path.push(rc),
+                    None => break,
+                }
+            }
+            let node_rc = path.last().filter(|_| path.len() == word.len());
+            match node_rc {
+                Some(rc) if rc.borrow().is_word => {
+                    rc.borrow_mut().is_word = false;
+                    report.removed += 1;
+                    removed_keys.push(word.iter().collect());
+                }
+                _ => report.not_found += 1,
+            }
+        }
+        while !path.is_empty() {
+            self.leave_node(&mut path, &mut report);
+        }
+        let mut root = self.root.borrow_mut();
+        root.longest_word_len = root.own_longest_word_len();
+        drop(root);
+        let mut tags = self.tags.borrow_mut();
+        let mut scores = self.scores.borrow_mut();
+        // The words that had scores, whose paths need their highest scores worked out again.
+        let mut scored_keys: Vec<String> = vec![];
+        for key in removed_keys {
+            tags.remove(&key);
+            self.sources.remove(&key);
+            self.spellings.remove(&key);
+            self.ranks.remove(&key);
+            if scores.remove(&key).is_some() {
+                scored_keys.push(key);
+            }
+        }
+        drop(scores);
+        for key in scored_keys {
+            self.update_max_scores(&key);
+        }
+        report
+    }
+
+    fn insert_with_tag(&mut self, word: &str, tag: u64) {
+        trace::traced(
+            || TraceOp::InsertWithTag(word.to_owned(), tag),
+            || {
+                if let Some(key) = word_key(word) {
+                    if self.is_frozen() {
+                        self.unfreeze();
                     }
+                    self.add_word(&key);
+                    self.tags.borrow_mut().insert(key, tag);
+                }
+            },
+        )
+    }
+
+    fn insert_with_source(&mut self, word: &str, source_id: u16) {
+        if let Some(key) = word_key(word) {
+            if self.is_frozen() {
+                self.unfreeze();
+            }
+            self.add_word(&key);
+            add_source(self.sources.entry(key).or_default(), source_id);
+        }
+    }
+
+    fn insert_with_spelling(&mut self, word: &str, spelling: &str, preference: CasePreference) {
+        if let Some(key) = word_key(word) {
+            if self.is_frozen() {
+                self.unfreeze();
+            }
+            self.add_word(&key);
+            set_spelling(&mut self.spellings, key, spelling, preference);
+        }
+    }
+
+    fn set_score(&mut self, word: &str, score: f64) -> bool {
+        trace::traced(
+            || TraceOp::SetScore(word.to_owned(), score),
+            || {
+                let key = match word_key(word) {
+                    Some(key) if scores::is_valid_score(score) && self.contains(&key) => key,
+                    _ => return false,
                 };
+                self.scores.borrow_mut().insert(key.clone(), score);
+                self.update_max_scores(&key);
+                true
             },
-        );
-        t
+        )
     }
 
-    fn find(&self, prefix: &str) -> Option<FixedNode> {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
-        let prefix_len = prefix.len();
-        self.root.borrow().find_child(prefix, prefix_len, 0)
+    fn set_rank(&mut self, word: &str, rank: u32) -> bool {
+        let key = match word_key(word) {
+            Some(key) if self.contains(&key) => key,
+            _ => return false,
+        };
+        self.ranks.insert(key, rank);
+        true
     }
 
-    fn to_fixed_node(&self) -> FixedNode {
-        self.root.borrow().to_fixed_node()
+    #[cfg(feature = "std")]
+    fn enable_prefix_cache(&mut self, max_entries: usize) {
+        self.prefix_cache = (max_entries > 0).then(|| PrefixCache::new(max_entries));
+    }
+
+    #[cfg(feature = "std")]
+    fn cache_stats(&self) -> CacheStats {
+        self.prefix_cache
+            .as_ref()
+            .map(PrefixCache::stats)
+            .unwrap_or_default()
+    }
+
+    fn find_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> Option<FixedNode> {
+        self.find_link_chars(chars)
+            .filter(|rc| rc.borrow().depth > 0)
+            .map(|rc| rc.borrow().to_fixed_node())
+    }
+
+    fn find_with_metrics(&self, prefix: &str) -> (Option<FixedNode>, FindMetrics) {
+        let prefix = prefix.trim();
+        let mut metrics = FindMetrics::at_root();
+        let node = self
+            .find_link_with_metrics(prefix, &mut metrics)
+            .filter(|rc| rc.borrow().depth > 0)
+            .map(|rc| rc.borrow().to_fixed_node());
+        metrics.finish(node.as_ref().map(|node| node.is_word));
+        (node, metrics)
+    }
+
+    fn contains_with_metrics(&self, word: &str) -> (bool, FindMetrics) {
+        let word = word.trim();
+        let mut metrics = FindMetrics::at_root();
+        if word.is_ascii() && word.len() > self.root.borrow().longest_word_len {
+            return (false, metrics);
+        }
+        let is_word = self
+            .find_link_with_metrics(word, &mut metrics)
+            .filter(|rc| rc.borrow().depth > 0)
+            .map(|rc| rc.borrow().is_word);
+        metrics.finish(is_word);
+        (is_word == Some(true), metrics)
+    }
+
+    fn contains_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> bool {
+        self.find_link_chars(chars)
+            .map(|rc| rc.borrow().is_word)
+            .unwrap_or(false)
+    }
+
+    fn contains_prefix_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> bool {
+        self.find_link_chars(chars).is_some()
+    }
+
+    // The links are checked first since a link back up the trie would send the walk in verify_structure() around
+    // in a cycle.
+    fn verify_integrity(&self) -> Result<()> {
+        self.verify_links().map_err(LetterTrieError::Inconsistent)?;
+        verify_structure(self)
+    }
+
+    fn has_completion_of_length(&self, prefix: &str, min_total_len: usize) -> bool {
+        let prefix = prefix.trim();
+        self.find_link(&prefix.to_lowercase()).is_some_and(|rc| {
+            let longest_word_len = rc.borrow().longest_word_len;
+            longest_word_len > 0 && longest_word_len >= min_total_len
+        })
+    }
+
+    fn completions_of_exact_length(&self, prefix: &str, total_len: usize) -> Vec<String> {
+        let prefix = prefix.trim();
+        let mut prefix = prefix.to_lowercase();
+        let mut words = vec![];
+        if let Some(rc) = self.find_link(&prefix) {
+            Self::push_completions_of_exact_length(rc, &mut prefix, total_len, &mut words);
+        }
+        words
+    }
+
+    fn find_hamming(&self, word: &str, k: usize) -> Vec<String> {
+        let word = word.trim();
+        let target: Vec<char> = word.to_lowercase().chars().collect();
+        let mut words = vec![];
+        self.push_hamming_matches(&target, k, &mut words);
+        words
+    }
+}
+
+// A short summary rather than the whole tree, which could be hundreds of thousands of nodes. For the tree itself
+// use describe_deep() on the root node.
+impl Debug for BaseLetterTrie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_debug_summary(self, "BaseLetterTrie", f)
     }
 }
 
-impl Debug for BaseLetterTrie {
+impl fmt::Display for BaseLetterTrie {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.root.borrow().fmt(f)
+        fmt_display_summary(self, f)
     }
 }
 
 unsafe impl Send for BaseLetterTrie {}
 
-pub struct BaseLetterTrieIteratorBreadthFirst {
+/// An iterator over the nodes of a trie breadth first, returned by `BaseLetterTrie::iter_breadth_first()`.
+pub struct BaseLetterTrieIteratorBreadthFirst<'a> {
     stack: Vec<ChildLink>,
+    // The nodes are reached through their links rather than through the trie, so this is what keeps the trie
+    // borrowed. Otherwise the links would let the iterator see an insert or a removal partway through, or keep a
+    // removed subtree alive.
+    trie: PhantomData<&'a BaseLetterTrie>,
 }
 
-impl Iterator for BaseLetterTrieIteratorBreadthFirst {
+impl Iterator for BaseLetterTrieIteratorBreadthFirst<'_> {
     type Item = FixedNode;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -579,25 +1702,29 @@ impl Iterator for BaseLetterTrieIteratorBreadthFirst {
             let this_rc = self.stack.remove(0);
             let this_node = this_rc.borrow();
             let fixed_char_node = this_node.to_fixed_node();
-            for (_, child_node_rc) in this_node.children.iter() {
-                self.stack.push(Rc::clone(&child_node_rc));
+            for child_node_rc in this_node.children.values() {
+                self.stack.push(Rc::clone(child_node_rc));
             }
             Some(fixed_char_node)
         }
     }
 }
 
-pub struct BaseLetterTrieIteratorPrefix {
+/// An iterator over the nodes along a prefix, returned by `BaseLetterTrie::iter_prefix()`.
+pub struct BaseLetterTrieIteratorPrefix<'a> {
     prefix: Vec<char>,
     prefix_len: usize,
     prefix_index: usize,
     rc: ChildLink,
+    // As in BaseLetterTrieIteratorBreadthFirst.
+    trie: PhantomData<&'a BaseLetterTrie>,
 }
 
-impl Iterator for BaseLetterTrieIteratorPrefix {
+impl Iterator for BaseLetterTrieIteratorPrefix<'_> {
     type Item = FixedNode;
 
     fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "std")]
         println!("BaseLetterTrieIteratorPrefix.next():\n{:#?}", self);
         if self.prefix_index > self.prefix_len {
             None
@@ -608,7 +1735,7 @@ impl Iterator for BaseLetterTrieIteratorPrefix {
                 Some(fixed_char_node)
             } else {
                 let c = self.prefix[self.prefix_index];
-                let rc_opt = self.rc.borrow().children.get(&c).map(|x| Rc::clone(x));
+                let rc_opt = self.rc.borrow().children.get(&c).map(Rc::clone);
                 if let Some(rc_next) = rc_opt {
                     self.rc = rc_next;
                     self.prefix_index += 1;
@@ -621,25 +1748,53 @@ impl Iterator for BaseLetterTrieIteratorPrefix {
     }
 }
 
-impl Debug for BaseLetterTrieIteratorPrefix {
+impl Debug for BaseLetterTrieIteratorPrefix<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let rc_string = self.rc.borrow().describe_one_line();
         if f.alternate() {
             write!(
                 f,
                 "BaseLetterTrieIteratorPrefix:\n\tprefix_len = {}\n\tprefix_index = {}\n\trc = {}",
-                self.prefix_len, self.prefix_index, &rc_string
+                self.prefix_len, self.prefix_index, rc_string
             )
         } else {
             write!(
                 f,
                 "BaseLetterTrieIteratorPrefix: prefix_len = {}, prefix_index = {}, rc = {}",
-                self.prefix_len, self.prefix_index, &rc_string
+                self.prefix_len, self.prefix_index, rc_string
             )
         }
     }
 }
 
+// The node a TrieCursor is at. It's a clone of the node's link, which the cursor can only hold while it borrows
+// the trie, so the node can't be changed or dropped in the meantime.
+#[derive(Clone)]
+pub(crate) struct CursorNode(ChildLink);
+
+impl CursorNode {
+    // The child for a letter that's already lowercase.
+    pub(crate) fn child(&self, c: char) -> Option<Self> {
+        self.0
+            .borrow()
+            .children
+            .get(&c)
+            .map(|rc| CursorNode(Rc::clone(rc)))
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.0.borrow().depth
+    }
+
+    pub(crate) fn is_word(&self) -> bool {
+        self.0.borrow().is_word
+    }
+
+    pub(crate) fn child_count(&self) -> usize {
+        self.0.borrow().children.len()
+    }
+}
+
 struct Node {
     c: char,
     depth: usize,
@@ -647,6 +1802,9 @@ struct Node {
     children: BTreeMap<char, ChildLink>,
     is_word: bool,
     is_frozen: bool,
+    // The length of the longest word that ends at or below this node, or 0 if there isn't one. Unlike the counts
+    // below it's kept up to date as words are added and removed, frozen or not.
+    longest_word_len: usize,
     node_count: Option<usize>,
     word_count: Option<usize>,
     height: Option<usize>,
@@ -654,99 +1812,132 @@ struct Node {
 
 impl Node {
     pub fn node_count(&self) -> usize {
-        if self.is_frozen {
-            self.node_count.unwrap()
-        } else {
-            let this_count = 1;
-            let child_count: usize = self
-                .children
-                .values()
-                .map(|rc| rc.borrow().node_count())
-                .sum();
-            this_count + child_count
+        match self.node_count.filter(|_| self.is_frozen) {
+            Some(node_count) => node_count,
+            None => self.subtree_counts().0,
         }
     }
 
     pub fn word_count(&self) -> usize {
-        if self.is_frozen {
-            self.word_count.unwrap()
-        } else {
-            let this_count = if self.is_word { 1 } else { 0 };
-            let child_count: usize = self
-                .children
-                .values()
-                .map(|rc| rc.borrow().word_count())
-                .sum();
-            this_count + child_count
+        match self.word_count.filter(|_| self.is_frozen) {
+            Some(word_count) => word_count,
+            None => self.subtree_counts().1,
         }
     }
 
     pub fn height(&self) -> usize {
-        if self.is_frozen {
-            self.height.unwrap()
-        } else {
-            let max_child_height: usize = self
-                .children
-                .values()
-                .map(|rc| rc.borrow().height())
-                .max()
-                .unwrap_or(0);
-            max_child_height + 1
+        match self.height.filter(|_| self.is_frozen) {
+            Some(height) => height,
+            None => self.subtree_counts().2,
+        }
+    }
+
+    // Calculate the node count, word count and height of an unfrozen subtree. This keeps its own stack of nodes
+    // instead of recursing so that a very deep trie can't overflow the call stack.
+    fn subtree_counts(&self) -> (usize, usize, usize) {
+        let mut node_count: usize = 1;
+        let mut word_count: usize = if self.is_word { 1 } else { 0 };
+        let mut max_depth = self.depth;
+        let mut stack: Vec<ChildLink> = self.checked_children().collect();
+        while let Some(rc) = stack.pop() {
+            let node = rc.borrow();
+            node_count = node_count.saturating_add(1);
+            if node.is_word {
+                word_count = word_count.saturating_add(1);
+            }
+            max_depth = cmp::max(max_depth, node.depth);
+            stack.extend(node.checked_children());
+        }
+        (node_count, word_count, max_depth - self.depth + 1)
+    }
+
+    // The longest_word_len for this node from its own flag and its children's lengths.
+    fn own_longest_word_len(&self) -> usize {
+        let own = if self.is_word { self.depth } else { 0 };
+        let below = self
+            .children
+            .values()
+            .map(|child_rc| child_rc.borrow().longest_word_len)
+            .max()
+            .unwrap_or(0);
+        cmp::max(own, below)
+    }
+
+    // All of the nodes below this one in depth-first order, so every node comes before its descendants.
+    fn descendants(&self) -> Vec<ChildLink> {
+        let mut v: Vec<ChildLink> = vec![];
+        let mut stack: Vec<ChildLink> = self.checked_children().collect();
+        while let Some(rc) = stack.pop() {
+            stack.extend(rc.borrow().checked_children());
+            v.push(rc);
+        }
+        v
+    }
+
+    // Get a link to a child after checking that the child's depth follows from this node's. A link back up the
+    // trie, which only a bug could make, has to break that somewhere around the cycle, so the walks that check it
+    // panic instead of going around forever.
+    fn checked_child(&self, child_rc: &ChildLink) -> ChildLink {
+        // A child that's already borrowed mutably is a node on the path down to here.
+        let child_depth = child_rc.try_borrow().ok().map(|child| child.depth);
+        if child_depth != Some(self.depth + 1) {
+            panic!(
+                "the trie has a cycle: a child of \"{}\" at depth {} links to a node at depth {:?}",
+                self.prefix(),
+                self.depth,
+                child_depth
+            );
         }
+        Rc::clone(child_rc)
+    }
+
+    fn checked_children(&self) -> impl Iterator<Item = ChildLink> + '_ {
+        self.children
+            .values()
+            .map(move |child_rc| self.checked_child(child_rc))
     }
 
     pub fn freeze(&mut self) {
         if !self.is_frozen {
-            let mut node_count = 1;
-            let mut word_count = if self.is_word { 1 } else { 0 };
-            let mut max_child_height = 0;
-            for mut child_node in self.children.values().map(|x| x.borrow_mut()) {
-                child_node.freeze();
-                node_count += child_node.node_count.unwrap();
-                word_count += child_node.word_count.unwrap();
-                max_child_height = cmp::max(max_child_height, child_node.height.unwrap());
+            // Freeze the deepest nodes first so that each node can add up the counts already stored in its
+            // children.
+            for rc in self.descendants().iter().rev() {
+                rc.borrow_mut().freeze_from_children();
             }
-            self.node_count = Some(node_count);
-            self.word_count = Some(word_count);
-            self.height = Some(max_child_height + 1);
-            self.is_frozen = true;
+            self.freeze_from_children();
         }
     }
 
+    // Store the counts for this node, assuming that its children are already frozen.
+    fn freeze_from_children(&mut self) {
+        let mut node_count = 1;
+        let mut word_count = if self.is_word { 1 } else { 0 };
+        let mut max_child_height = 0;
+        for child_node in self.children.values().map(|x| x.borrow()) {
+            node_count = child_node.node_count().saturating_add(node_count);
+            word_count = child_node.word_count().saturating_add(word_count);
+            max_child_height = cmp::max(max_child_height, child_node.height());
+        }
+        self.node_count = Some(node_count);
+        self.word_count = Some(word_count);
+        self.height = Some(max_child_height + 1);
+        self.is_frozen = true;
+    }
+
     pub fn unfreeze(&mut self) {
         if self.is_frozen {
-            for mut child_node in self.children.values().map(|x| x.borrow_mut()) {
-                child_node.unfreeze();
+            for rc in self.descendants() {
+                rc.borrow_mut().clear_frozen_counts();
             }
-            self.node_count = None;
-            self.word_count = None;
-            self.height = None;
-            self.is_frozen = false;
+            self.clear_frozen_counts();
         }
     }
 
-    fn find_child(
-        &self,
-        prefix: Vec<char>,
-        prefix_len: usize,
-        prefix_index: usize,
-    ) -> Option<FixedNode> {
-        if prefix_index >= prefix_len {
-            None
-        } else {
-            let c = prefix[prefix_index];
-            if let Some(child_rc) = self.children.get(&c) {
-                let child_node = child_rc.borrow();
-                if prefix_index == prefix_len - 1 {
-                    // We've found the root.
-                    Some(child_node.to_fixed_node())
-                } else {
-                    child_node.find_child(prefix, prefix_len, prefix_index + 1)
-                }
-            } else {
-                None
-            }
-        }
+    fn clear_frozen_counts(&mut self) {
+        self.node_count = None;
+        self.word_count = None;
+        self.height = None;
+        self.is_frozen = false;
     }
 
     fn is_word_child(&self, prefix: Vec<char>, prefix_len: usize, prefix_index: usize) -> bool {
@@ -819,16 +2010,31 @@ impl Node {
         }
     }
 
+    // Build the prefix by following the parent links up to the root in a loop rather than recursing.
     pub fn prefix(&self) -> String {
-        if let Some(parent_weak) = &self.parent {
-            if let Some(parent_rc) = parent_weak.upgrade() {
-                let parent_prefix = parent_rc.borrow().prefix();
-                return format!("{}{}", parent_prefix, self.c);
+        let mut chars: Vec<char> = vec![];
+        let mut c = self.c;
+        let mut parent_opt = self
+            .parent
+            .as_ref()
+            .and_then(|parent_weak| parent_weak.upgrade());
+        while let Some(parent_rc) = parent_opt {
+            // There are only as many parents as the depth unless the parent links go around in a cycle.
+            if chars.len() > self.depth {
+                break;
             }
+            chars.push(c);
+            let parent = parent_rc.borrow();
+            c = parent.c;
+            parent_opt = parent
+                .parent
+                .as_ref()
+                .and_then(|parent_weak| parent_weak.upgrade());
         }
-        String::from("")
+        chars.iter().rev().collect()
     }
 
+    #[cfg(feature = "std")]
     pub fn print_prefixes(&self, prefix_count: usize) -> usize {
         let mut remaining_prefix_count = prefix_count;
         let mut prefixes_printed = 0;
@@ -855,13 +2061,28 @@ impl Node {
             v.push(self.prefix());
         }
         if !self.children.is_empty() {
-            for (_, child_node_rc) in self.children.iter() {
+            for child_node_rc in self.children.values() {
                 child_node_rc.borrow().get_words(v, word_count);
             }
         }
     }
 }
 
+// Dropping the nodes one at a time instead of letting each node drop its children keeps a very deep trie from
+// overflowing the call stack. A node with another strong reference, such as one held by an iterator, is left for
+// whoever drops that reference, which will come back here.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack: Vec<ChildLink> = core::mem::take(&mut self.children).into_values().collect();
+        while let Some(rc) = stack.pop() {
+            if let Ok(cell) = Rc::try_unwrap(rc) {
+                let mut node = cell.into_inner();
+                stack.extend(core::mem::take(&mut node.children).into_values());
+            }
+        }
+    }
+}
+
 impl Debug for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if f.alternate() {
@@ -875,30 +2096,26 @@ impl Debug for Node {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "fs"))]
 mod tests {
     use super::*;
+    use alloc::collections::BTreeSet;
+    use std::fs;
     use test::Bencher;
 
+    crate::letter_trie_conformance_tests!(BaseLetterTrie);
+
     #[test]
     fn small_root() {
         let dataset = Dataset::TestSmallUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::Continuous,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         assert_small_root(&t.to_fixed_node());
     }
 
     #[test]
     fn small_prefix_cross() {
         let dataset = Dataset::TestSmallUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::Continuous,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         assert_eq!(
             t.find("cross"),
             Some(FixedNode {
@@ -917,11 +2134,7 @@ mod tests {
     #[test]
     fn small_prefix_creatu() {
         let dataset = Dataset::TestSmallUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::Continuous,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         assert_eq!(
             t.find("creatu"),
             Some(FixedNode {
@@ -940,11 +2153,7 @@ mod tests {
     #[test]
     fn small_prefix_an() {
         let dataset = Dataset::TestSmallUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::Continuous,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         assert_eq!(
             t.find("an"),
             Some(FixedNode {
@@ -963,11 +2172,7 @@ mod tests {
     #[test]
     fn small_prefix_c() {
         let dataset = Dataset::TestSmallUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::Continuous,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         assert_eq!(
             t.find("c"),
             Some(FixedNode {
@@ -986,123 +2191,790 @@ mod tests {
     #[test]
     fn small_prefix_not_found() {
         let dataset = Dataset::TestSmallUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::Continuous,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         assert_eq!(t.find("casoun"), None);
     }
 
     #[test]
+    fn small_from_reader_all_load_methods() {
+        let content = fs::read_to_string(Dataset::TestSmallUnsorted.filename()).unwrap();
+        for load_method in &[
+            LoadMethod::ReadVecFill,
+            LoadMethod::VecFill,
+            LoadMethod::Continuous,
+            LoadMethod::ContinuousParallel,
+        ] {
+            let t = BaseLetterTrie::from_reader(content.as_bytes(), false, load_method).unwrap();
+            assert_small_root(&t.to_fixed_node());
+        }
+    }
+
+    #[test]
+    fn small_contains() {
+        let t = BaseLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+            .unwrap();
+        assert!(t.contains("an"));
+        assert!(t.contains("Creature"));
+        assert!(!t.contains("cre"));
+        assert!(!t.contains("notfound"));
+        assert!(!t.contains(""));
+    }
+
+    #[test]
+    fn small_words_with_prefix() {
+        let t = BaseLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+            .unwrap();
+        assert_eq!(
+            t.words_with_prefix("cre"),
+            vec!["crease", "creative", "creator", "creature"]
+        );
+        assert_eq!(t.words_with_prefix("an"), vec!["an", "and"]);
+        assert!(t.words_with_prefix("q").is_empty());
+        assert_eq!(t.words_with_prefix("").len(), 10);
+    }
+
+    #[test]
+    fn small_suggest() {
+        let t = BaseLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+            .unwrap();
+        assert_eq!(t.suggest("", 3), vec!["a", "an", "and"]);
+        assert_eq!(t.suggest("cr", 3), vec!["cross", "crease", "creator"]);
+        assert_eq!(t.suggest("cross", 5), vec!["cross", "crossed"]);
+        assert!(t.suggest("cr", 0).is_empty());
+        assert!(t.suggest("q", 3).is_empty());
+    }
+
+    #[test]
+    fn small_remove() {
+        let mut t =
+            BaseLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+                .unwrap();
+        t.freeze();
+        assert!(t.remove("Cross"));
+        assert!(!t.remove("cross"));
+        assert!(!t.remove("cre"));
+        assert!(t.contains("crossed"));
+        assert_eq!(9, t.to_fixed_node().word_count);
+        // With "cross" gone, "crossed" was the only word below "cr" so removing it takes five nodes with it.
+        assert!(t.remove("crossed"));
+        assert_eq!(23, t.to_fixed_node().node_count);
+        t.verify_integrity().unwrap();
+        for word in t.words_with_prefix("") {
+            assert!(t.remove(&word));
+        }
+        assert_eq!(1, t.to_fixed_node().node_count);
+        t.verify_integrity().unwrap();
+    }
+
+    #[test]
+    fn small_same_for_every_load_method() {
+        let mut renderings: Vec<(String, String)> = vec![];
+        for dataset in &[Dataset::TestSmallUnsorted, Dataset::TestSmallSorted] {
+            for load_method in &ALL_LOAD_METHODS {
+                let t = BaseLetterTrie::from_dataset(dataset, load_method).unwrap();
+                t.verify_integrity().unwrap();
+                assert_eq!(vec!['a', 'c'], t.children(""));
+                assert_eq!(vec!['e', 'o'], t.children("Cr"));
+                assert!(t.children("crossed").is_empty());
+                assert!(t.children("q").is_empty());
+                let deep = format!("{:#?}", t.root.borrow());
+                renderings.push((format!("{:#?}", t), deep));
+            }
+        }
+        for rendering in &renderings[1..] {
+            assert_eq!(&renderings[0], rendering);
+        }
+    }
+
+    #[test]
+    fn small_next_letters() {
+        let t = BaseLetterTrie::from_words(
+            words_from_file(Dataset::TestSmallUnsorted.filename()).unwrap(),
+        );
+        assert_eq!(vec![('a', true), ('c', false)], t.next_letters(""));
+        assert_eq!(vec![('s', false), ('t', false)], t.next_letters("crea"));
+        assert_eq!(vec![('e', false)], t.next_letters("Cross"));
+        assert!(t.next_letters("crossed").is_empty());
+        assert!(t.next_letters("b").is_empty());
+        for prefix in &["", "a", "crea", "cross", "crossed", "b"] {
+            let letters: Vec<char> = t.next_letters(prefix).into_iter().map(|(c, _)| c).collect();
+            assert_eq!(t.children(prefix), letters);
+        }
+    }
+
+    #[test]
+    fn small_lookup() {
+        let t = BaseLetterTrie::from_words(
+            words_from_file(Dataset::TestSmallUnsorted.filename()).unwrap(),
+        );
+        // "cross" is a word and the start of "crossed".
+        match t.lookup("Cross") {
+            Lookup::Word(node) => {
+                assert_eq!(1, node.child_count);
+                assert_eq!(2, node.word_count);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(t.contains("cross"));
+        assert!(t.contains_prefix("cross"));
+        // "cre" is only the start of "crease", "creative" and the others.
+        match t.lookup("cre") {
+            Lookup::PrefixOnly(node) => assert_eq!(4, node.word_count),
+            other => panic!("{:?}", other),
+        }
+        assert!(!t.contains("cre"));
+        assert!(t.contains_prefix("CRE"));
+        for absent in &["q", "crossedx", "azurea", "b"] {
+            assert_eq!(Lookup::NotFound, t.lookup(absent));
+            assert!(!t.contains(absent));
+            assert!(!t.contains_prefix(absent));
+        }
+        assert_eq!(Lookup::PrefixOnly(t.to_fixed_node()), t.lookup(""));
+        assert!(t.contains_prefix(""));
+        assert!(!t.contains(""));
+    }
+
+    #[test]
+    fn small_find_chars() {
+        let words = words_from_file(Dataset::TestSmallUnsorted.filename()).unwrap();
+        let t = BaseLetterTrie::from_words(&words);
+        for word in &words {
+            let upper: Vec<char> = word.to_uppercase().chars().collect();
+            for len in 0..=upper.len() {
+                let prefix: String = upper[..len].iter().collect();
+                assert_eq!(t.find(&prefix), t.find_chars(upper[..len].iter().copied()));
+                assert_eq!(t.contains(&prefix), t.contains_chars(prefix.chars()));
+                assert!(t.contains_prefix_chars(prefix.chars()));
+            }
+        }
+        assert_eq!(None, t.find_chars("".chars()));
+        assert!(!t.contains_chars("crossedx".chars()));
+        assert!(!t.contains_prefix_chars(vec!['c', 'x']));
+
+        // Lowercasing one character at a time doesn't give a final sigma, but find() lowercases the whole string.
+        let t = BaseLetterTrie::from_words(vec!["ΟΔΟΣ"]);
+        assert!(t.contains("ΟΔΟΣ"));
+        assert!(t.contains_chars("οδος".chars()));
+        assert!(!t.contains_chars("ΟΔΟΣ".chars()));
+    }
+
+    #[test]
+    fn small_tags() {
+        let mut t = BaseLetterTrie::from_words(vec!["an", "cross"]);
+        t.insert_with_tag("and", 1);
+        t.freeze();
+        t.insert_with_tag("Crossed", 2);
+        assert_eq!(Some(2), t.tag("crossed "));
+        assert_eq!(None, t.tag("cross"));
+        assert!(t.remove("and"));
+        assert_eq!(None, t.tag("and"));
+        t.insert("and");
+        assert_eq!(None, t.tag("and"));
+
+        // merge() brings the other trie's tags with its nodes.
+        let mut other = BaseLetterTrie::from_words(vec!["zoo"]);
+        other.insert_with_tag("zebra", 3);
+        t.merge(other);
+        assert_eq!(
+            vec![
+                ("an".to_owned(), None),
+                ("and".to_owned(), None),
+                ("cross".to_owned(), None),
+                ("crossed".to_owned(), Some(2)),
+                ("zebra".to_owned(), Some(3)),
+                ("zoo".to_owned(), None)
+            ],
+            t.words_with_tags().collect::<Vec<_>>()
+        );
+        t.verify_integrity().unwrap();
+    }
+
+    #[test]
+    fn debug_and_display() {
+        let t = BaseLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+            .unwrap();
+        assert_eq!(
+            "BaseLetterTrie { word_count: 10, node_count: 28, height: 9, first_words: [\"a\", \"an\", \"and\", \"azure\", \"crease\"] }",
+            format!("{:?}", t)
+        );
+        assert_eq!("LetterTrie(10 words, height 9)", t.to_string());
+        assert_eq!(
+            "LetterTrie(0 words, height 1)",
+            BaseLetterTrie::new().to_string()
+        );
+
+        let t = BaseLetterTrie::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous)
+            .unwrap();
+        assert!(format!("{:?}", t).len() < 200);
+        assert!(format!("{:#?}", t).len() < 300);
+        assert!(t.to_string().len() < 50);
+    }
+
+    #[test]
+    fn verify_integrity_finds_broken_parent_link() {
+        let t = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+        t.verify_integrity().unwrap();
+        let c_node = t.find_link("c").unwrap();
+        let r_node = c_node.borrow().children.get(&'r').map(Rc::clone).unwrap();
+        r_node.borrow_mut().parent = Some(Rc::downgrade(&t.root));
+        drop(r_node);
+        drop(c_node);
+        assert!(matches!(
+            t.verify_integrity(),
+            Err(LetterTrieError::Inconsistent(_))
+        ));
+    }
+
+    // Make the node for `from` link back to the node for `to` as its child `c`, as a bug in a merge or clone
+    // could.
+    fn add_cycle(t: &BaseLetterTrie, from: &str, c: char, to: &str) {
+        let to_rc = t.find_link(to).unwrap();
+        t.find_link(from)
+            .unwrap()
+            .borrow_mut()
+            .children
+            .insert(c, to_rc);
+    }
+
+    #[test]
+    fn cycle_is_an_error_not_a_hang() {
+        let t = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+        add_cycle(&t, "crossed", 'x', "cr");
+        // The node the cycle goes back to has a second strong reference.
+        match t.verify_integrity() {
+            Err(LetterTrieError::Inconsistent(msg)) => assert!(msg.contains("\"cr\""), "{}", msg),
+            other => panic!("{:?}", other),
+        }
+        // "crossed" also has a child with no parent link back to it.
+        assert_eq!(
+            vec![
+                RcAnomaly::Shared {
+                    prefix: "cr".to_owned(),
+                    strong_count: 2
+                },
+                RcAnomaly::ParentLinks {
+                    prefix: "crossed".to_owned(),
+                    weak_count: 0,
+                    child_count: 1
+                }
+            ],
+            t.rc_report(usize::MAX).anomalies
+        );
+        let panic_message = |f: &dyn Fn()| {
+            let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_err();
+            *err.downcast::<String>().unwrap()
+        };
+        let msg = panic_message(&|| {
+            t.to_fixed_node();
+        });
+        assert!(
+            msg.contains("cycle") && msg.contains("\"crossed\""),
+            "{}",
+            msg
+        );
+        let msg = panic_message(&|| t.visit("", &mut |_| true));
+        assert!(msg.contains("\"crossed\""), "{}", msg);
+
+        // A node that links to itself.
+        let mut t = BaseLetterTrie::from_words(vec!["an"]);
+        add_cycle(&t, "a", 'a', "a");
+        assert!(matches!(
+            t.verify_integrity(),
+            Err(LetterTrieError::Inconsistent(_))
+        ));
+        assert!(panic_message(&|| {
+            t.to_fixed_node();
+        })
+        .contains("\"a\""));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| t.freeze()));
+        assert!(result.is_err());
+    }
+
+    // Hold on to an extra Rc for the node for `prefix`, as a link cloned by mistake and kept would.
+    fn extra_link(t: &BaseLetterTrie, prefix: &str) -> ChildLink {
+        t.find_link(prefix).unwrap()
+    }
+
+    #[test]
+    fn rc_report_small() {
+        let t = BaseLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+            .unwrap();
+        let counts = |prefix: &str, weak_count: usize| RcNodeCounts {
+            prefix: prefix.to_owned(),
+            depth: prefix.len(),
+            strong_count: 1,
+            weak_count,
+            child_count: weak_count,
+        };
+        let report = t.rc_report(2);
+        assert_eq!(
+            RcReport {
+                max_depth: 2,
+                nodes: vec![
+                    counts("", 2),
+                    counts("a", 2),
+                    counts("an", 1),
+                    counts("az", 1),
+                    counts("c", 1),
+                    counts("cr", 2),
+                ],
+                anomalies: vec![],
+            },
+            report
+        );
+        assert!(report.to_string().starts_with(
+            "6 nodes down to depth 2, 0 anomalies\n\"\": strong 1, weak 2, children 2\n  \"a\": strong 1, \
+            weak 2, children 2\n"
+        ));
+        assert_eq!(vec![counts("", 2)], t.rc_report(0).nodes);
+        let report = t.rc_report(usize::MAX);
+        assert_eq!(28, report.nodes.len());
+        assert_eq!(Some(&counts("crossed", 0)), report.nodes.last());
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn rc_report_flags_extra_clone() {
+        let t = BaseLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+            .unwrap();
+        let extra = extra_link(&t, "cre");
+        let report = t.rc_report(3);
+        assert_eq!(
+            vec![RcAnomaly::Shared {
+                prefix: "cre".to_owned(),
+                strong_count: 2
+            }],
+            report.anomalies
+        );
+        assert!(report
+            .to_string()
+            .ends_with("anomaly: \"cre\" has 2 strong references instead of 1\n"));
+        // Too deep to be reported.
+        assert!(t.rc_report(2).is_clean());
+        match t.verify_integrity() {
+            Err(LetterTrieError::Inconsistent(msg)) => {
+                assert_eq!("\"cre\" has 2 strong references instead of 1", msg)
+            }
+            other => panic!("{:?}", other),
+        }
+        drop(extra);
+        assert!(t.rc_report(3).is_clean());
+        t.verify_integrity().unwrap();
+
+        // A child whose parent link goes to the root instead of to its parent.
+        let r_node = extra_link(&t, "cr");
+        r_node.borrow_mut().parent = Some(Rc::downgrade(&t.root));
+        drop(r_node);
+        assert_eq!(
+            vec![
+                RcAnomaly::ParentLinks {
+                    prefix: "".to_owned(),
+                    weak_count: 3,
+                    child_count: 2
+                },
+                RcAnomaly::ParentLinks {
+                    prefix: "c".to_owned(),
+                    weak_count: 0,
+                    child_count: 1
+                }
+            ],
+            t.rc_report(1).anomalies
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the large dataset files"]
     fn large_read_vec_fill_root() {
         let dataset = Dataset::TestLargeUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::ReadVecFill,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::ReadVecFill).unwrap();
         assert_large_root(&t.to_fixed_node());
     }
 
     #[test]
+    #[ignore = "needs the large dataset files"]
     fn large_vec_fill_root() {
         let dataset = Dataset::TestLargeUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::VecFill,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::VecFill).unwrap();
         assert_large_root(&t.to_fixed_node());
     }
 
     #[test]
+    #[ignore = "needs the large dataset files"]
     fn large_continuous_root() {
         let dataset = Dataset::TestLargeUnsorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::Continuous,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         assert_large_root(&t.to_fixed_node());
     }
 
     #[test]
+    #[ignore = "needs the large dataset files"]
     fn large_continuous_parallel_root() {
         let dataset = Dataset::TestLargeSorted;
-        let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
-            dataset.is_sorted(),
-            &LoadMethod::ContinuousParallel,
-        );
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::ContinuousParallel).unwrap();
         assert_large_root(&t.to_fixed_node());
     }
 
     #[test]
+    fn parallel_load_stats() {
+        let dataset = Dataset::TestMediumSorted;
+        let opt = DisplayDetailOptions::make_no_display();
+        BaseLetterTrie::from_dataset_with_options(&dataset, &LoadMethod::ContinuousParallel, &opt)
+            .unwrap();
+        let stats = opt.parallel_load_stats().unwrap();
+        assert_eq!(dataset.word_count(), stats.word_count());
+
+        let letters: Vec<char> = stats.letters.iter().map(|letter| letter.letter).collect();
+        let expected: BTreeSet<char> = words_from_file(dataset.filename())
+            .unwrap()
+            .iter()
+            .filter_map(|word| word.to_lowercase().chars().next())
+            .collect();
+        assert_eq!(expected.into_iter().collect::<Vec<char>>(), letters);
+        assert!(stats.letters.iter().all(|letter| letter.word_count > 0));
+        let total = format_count_with(dataset.word_count(), NumberStyle::Comma);
+        assert!(stats.table(NumberStyle::Comma).contains(&total));
+
+        // Other load methods don't leave any.
+        let opt = DisplayDetailOptions::make_no_display();
+        BaseLetterTrie::from_dataset_with_options(&dataset, &LoadMethod::Continuous, &opt).unwrap();
+        assert_eq!(None, opt.parallel_load_stats());
+    }
+
+    // The workers of a parallel load count on their own threads, and their counts come back with their parts.
+    #[test]
+    fn char_get_counts_match_for_parallel_load() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let counts_for = |load_method: &LoadMethod| {
+            CharGetCounter::reset();
+            BaseLetterTrie::from_dataset(&dataset, load_method).unwrap();
+            CharGetCounter::snapshot()
+        };
+        assert_eq!(
+            CharGetCounterSnapshot::default(),
+            counts_for(&LoadMethod::Continuous)
+        );
+
+        CharGetCounter::set_enabled(true);
+        let sequential = counts_for(&LoadMethod::Continuous);
+        let parallel = counts_for(&LoadMethod::ContinuousParallel);
+        CharGetCounter::set_enabled(false);
+        assert_eq!(
+            sequential.hit_count + sequential.miss_count,
+            parallel.hit_count + parallel.miss_count
+        );
+        assert_eq!(sequential, parallel);
+        // Every node but the root is made by a miss.
+        assert_eq!(99_851 - 1, sequential.miss_count);
+        assert!(sequential.hit_pct() > 0.5 && sequential.hit_pct() < 1.0);
+        assert_eq!(0.0, CharGetCounterSnapshot::default().hit_pct());
+    }
+
+    // The histogram shows where lookups leave the trie. Each verification non-word is a word with a q on the end, so
+    // it shares every letter but the last with a word and misses at its last letter, deeper on average than the
+    // letters of the good words are looked up. The lookups go through contains_prefix() since contains() turns away a
+    // word longer than any in the trie without walking it.
+    #[test]
+    #[ignore = "needs the large dataset files"]
+    fn char_get_histogram_for_lookups() {
+        let t = large_tree();
+        let good_words = verification_words(VerificationSet::GoodWords, None).unwrap();
+        let non_words = verification_words(VerificationSet::NonWords, None).unwrap();
+        let lookup_histogram = |words: &[String]| {
+            CharGetCounter::reset();
+            for word in words {
+                t.contains_prefix(word);
+            }
+            CharGetCounter::histogram().unwrap()
+        };
+        let mean_depth = |histogram: &[DepthCounts], count_of: fn(&DepthCounts) -> usize| {
+            let (sum, count) = histogram.iter().fold((0, 0), |(sum, count), counts| {
+                let n = count_of(counts);
+                (sum + counts.depth * n, count + n)
+            });
+            sum as f64 / count as f64
+        };
+        CharGetCounter::reset();
+        assert_eq!(None, CharGetCounter::histogram());
+
+        CharGetCounter::set_enabled(true);
+        let good = lookup_histogram(&good_words);
+        let non = lookup_histogram(&non_words);
+        CharGetCounter::set_enabled(false);
+
+        assert!(good.iter().all(|counts| counts.miss_count == 0));
+        let letter_count: usize = good_words.iter().map(|word| word.chars().count()).sum();
+        assert_eq!(
+            letter_count,
+            good.iter().map(|counts| counts.hit_count).sum::<usize>()
+        );
+        for (depth, counts) in non.iter().enumerate() {
+            assert_eq!(depth, counts.depth);
+            let ending_here = non_words
+                .iter()
+                .filter(|word| word.chars().count() - 1 == depth)
+                .count();
+            assert!(counts.miss_count <= ending_here);
+        }
+        assert_eq!(
+            non_words.len(),
+            non.iter().map(|counts| counts.miss_count).sum::<usize>()
+        );
+        let mean_lookup_depth = mean_depth(&good, |counts| counts.hit_count);
+        let mean_miss_depth = mean_depth(&non, |counts| counts.miss_count);
+        assert!(mean_miss_depth > mean_lookup_depth + 3.0);
+
+        // Lookups deeper than the last slot are all counted in it.
+        let long = "a".repeat(CHAR_GET_COUNTER_MAX_DEPTH + 10);
+        let deep = BaseLetterTrie::from_words(vec![long.as_str()]);
+        CharGetCounter::set_enabled(true);
+        CharGetCounter::reset();
+        deep.contains(&long);
+        let histogram = CharGetCounter::histogram().unwrap();
+        CharGetCounter::set_enabled(false);
+        assert_eq!(CHAR_GET_COUNTER_MAX_DEPTH + 1, histogram.len());
+        assert_eq!(10, histogram[CHAR_GET_COUNTER_MAX_DEPTH].hit_count);
+    }
+
+    #[test]
+    #[ignore = "needs the large dataset files"]
     fn is_word_recursive_good_words() {
         let t = large_tree();
-        let words = good_words();
+        let words = verification_words(VerificationSet::GoodWords, None).unwrap();
         for word in words {
-            assert_eq!(true, t.is_word_recursive(&word));
+            assert!(t.is_word_recursive(&word));
         }
     }
 
     #[test]
+    #[ignore = "needs the large dataset files"]
     fn is_word_loop_good_words() {
         let t = large_tree();
-        let words = good_words();
+        let words = verification_words(VerificationSet::GoodWords, None).unwrap();
         for word in words {
-            assert_eq!(true, t.is_word_loop(&word));
+            assert!(t.is_word_loop(&word));
         }
     }
 
     #[test]
+    #[ignore = "needs the large dataset files"]
     fn is_word_recursive_non_words() {
         let t = large_tree();
-        let words = non_words();
+        let words = verification_words(VerificationSet::NonWords, None).unwrap();
         for word in words {
-            assert_eq!(false, t.is_word_recursive(&word));
+            assert!(!t.is_word_recursive(&word));
         }
     }
 
     #[test]
+    #[ignore = "needs the large dataset files"]
     fn is_word_loop_non_words() {
         let t = large_tree();
-        let words = non_words();
+        let words = verification_words(VerificationSet::NonWords, None).unwrap();
         for word in words {
-            assert_eq!(false, t.is_word_loop(&word));
+            assert!(!t.is_word_loop(&word));
+        }
+    }
+
+    // The chain from the parent links is the same as finding each shorter prefix from the root, and the prefix
+    // rebuilt from them is the word itself.
+    #[test]
+    fn ancestors_match_find() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let t = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        let words: Vec<String> = t.words().step_by(97).collect();
+        for word in &words {
+            assert_eq!(Some(word.clone()), t.prefix_from_parents(word));
+            let ancestors = t.ancestors(word).unwrap();
+            let chars: Vec<char> = word.chars().collect();
+            assert_eq!(chars.len() + 1, ancestors.len());
+            for (i, node) in ancestors[..chars.len()].iter().enumerate() {
+                let prefix: String = chars[..chars.len() - i].iter().collect();
+                assert_eq!(t.find(&prefix).as_ref(), Some(node));
+            }
+            assert_eq!(&t.to_fixed_node(), ancestors.last().unwrap());
         }
+        assert_eq!(vec![t.to_fixed_node()], t.ancestors("").unwrap());
+        assert_eq!(None, t.ancestors("qqqq"));
+        assert_eq!(None, t.prefix_from_parents("qqqq"));
+    }
+
+    // Parent links are set on the nodes brought in by merge() and removal keeps the rest of the chain whole.
+    #[test]
+    fn ancestors_after_merge_and_remove() {
+        let mut t = BaseLetterTrie::from_words(vec!["cross", "crossing"]);
+        t.merge(BaseLetterTrie::from_words(vec!["an", "and"]));
+        assert_eq!(Some("and".to_owned()), t.prefix_from_parents("and"));
+        let letters: String = t
+            .ancestors("and")
+            .unwrap()
+            .iter()
+            .map(|node| node.c)
+            .collect();
+        assert_eq!("dna ", letters);
+
+        let mut t = t;
+        assert!(t.remove("crossing"));
+        assert_eq!(None, t.ancestors("crossi"));
+        let ancestors = t.ancestors("cross").unwrap();
+        assert_eq!(6, ancestors.len());
+        assert_eq!(
+            vec![1, 1, 1, 1, 1, 3],
+            ancestors
+                .iter()
+                .map(|node| node.word_count)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn ancestors_deep_chain() {
+        let word = "ab".repeat(1_000);
+        let t = BaseLetterTrie::from_words(vec![word.as_str()]);
+        assert_eq!(Some(word.clone()), t.prefix_from_parents(&word));
+        assert_eq!(word.len() + 1, t.ancestors(&word[..]).unwrap().len());
     }
 
     #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
     fn bench_is_word_hash_set(b: &mut Bencher) {
-        let words = good_words();
-        let hash_set = large_dataset_words_hash_set();
+        let words = verification_words(VerificationSet::GoodWords, None).unwrap();
+        let hash_set = words_hash_set(&Dataset::TestLargeSorted).unwrap();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, hash_set.contains(&word));
+                assert!(hash_set.contains(&word));
             }
         });
     }
 
     #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
     fn bench_is_word_recursive(b: &mut Bencher) {
-        let words = good_words();
+        let words = verification_words(VerificationSet::GoodWords, None).unwrap();
         let t = large_tree();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, t.is_word_recursive(&word));
+                assert!(t.is_word_recursive(&word));
             }
         });
     }
 
     #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
     fn bench_is_word_loop(b: &mut Bencher) {
-        let words = good_words();
+        let words = verification_words(VerificationSet::GoodWords, None).unwrap();
         let t = large_tree();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, t.is_word_loop(&word));
+                assert!(t.is_word_loop(&word));
+            }
+        });
+    }
+
+    // Misses that share every letter but the last with a word, against the verification non-words which mostly
+    // go wrong within a few letters.
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_contains_shared_prefix_misses(b: &mut Bencher) {
+        let probes = shared_prefix_probes(&Dataset::TestLargeSorted, 12, 1_000).unwrap();
+        let t = large_tree();
+        b.iter(|| {
+            for probe in &probes {
+                assert!(!t.contains(probe));
+            }
+        });
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_contains_non_words(b: &mut Bencher) {
+        let words = verification_words(VerificationSet::NonWords, None).unwrap();
+        let t = large_tree();
+        b.iter(|| {
+            for word in &words {
+                assert!(!t.contains(word));
+            }
+        });
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_find_shared_prefix_misses(b: &mut Bencher) {
+        let probes = shared_prefix_probes(&Dataset::TestLargeSorted, 12, 1_000).unwrap();
+        let t = large_tree();
+        b.iter(|| {
+            for probe in &probes {
+                assert!(t.find(probe).is_none());
+            }
+        });
+    }
+
+    // The words of the same length within one or two letters of 100 of the verification words, where only the
+    // branches that still match closely enough are walked.
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_find_hamming_1(b: &mut Bencher) {
+        let words: Vec<String> = verification_words(VerificationSet::GoodWords, None)
+            .unwrap()
+            .into_iter()
+            .take(100)
+            .collect();
+        let t = large_tree();
+        b.iter(|| {
+            words
+                .iter()
+                .map(|word| t.find_hamming(word, 1).len())
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_find_hamming_2(b: &mut Bencher) {
+        let words: Vec<String> = verification_words(VerificationSet::GoodWords, None)
+            .unwrap()
+            .into_iter()
+            .take(100)
+            .collect();
+        let t = large_tree();
+        b.iter(|| {
+            words
+                .iter()
+                .map(|word| t.find_hamming(word, 2).len())
+                .sum::<usize>()
+        });
+    }
+
+    // The chain of nodes above each word from the parent links, against finding each shorter prefix of the word
+    // again from the root. The tree is frozen so that neither one is counting subtrees. In a release build the
+    // parent links took about 1.2 ms for the good words against 1.9 ms going down from the root, which is what
+    // they buy for the memory they take on every node.
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_ancestors(b: &mut Bencher) {
+        let words = verification_words(VerificationSet::GoodWords, None).unwrap();
+        let mut t = large_tree();
+        t.freeze();
+        b.iter(|| {
+            for word in &words {
+                assert!(t.ancestors(word).is_some());
+            }
+        });
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_ancestors_by_find(b: &mut Bencher) {
+        let words = verification_words(VerificationSet::GoodWords, None).unwrap();
+        let mut t = large_tree();
+        t.freeze();
+        b.iter(|| {
+            for word in &words {
+                let mut ancestors = vec![t.to_fixed_node()];
+                for (i, _) in word.char_indices().skip(1) {
+                    ancestors.push(t.find(&word[..i]).unwrap());
+                }
+                ancestors.push(t.find(word).unwrap());
+                assert!(!ancestors.is_empty());
             }
         });
     }
@@ -1111,11 +2983,7 @@ mod tests {
     fn bench_load_read_vec_fill(b: &mut Bencher) {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
-            BaseLetterTrie::from_file(
-                &dataset.filename(),
-                dataset.is_sorted(),
-                &LoadMethod::ReadVecFill,
-            );
+            BaseLetterTrie::from_dataset(&dataset, &LoadMethod::ReadVecFill).unwrap();
         });
     }
 
@@ -1123,11 +2991,7 @@ mod tests {
     fn bench_load_vec_fill(b: &mut Bencher) {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
-            BaseLetterTrie::from_file(
-                &dataset.filename(),
-                dataset.is_sorted(),
-                &LoadMethod::VecFill,
-            );
+            BaseLetterTrie::from_dataset(&dataset, &LoadMethod::VecFill).unwrap();
         });
     }
 
@@ -1135,11 +2999,7 @@ mod tests {
     fn bench_load_continuous(b: &mut Bencher) {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
-            BaseLetterTrie::from_file(
-                &dataset.filename(),
-                dataset.is_sorted(),
-                &LoadMethod::Continuous,
-            );
+            BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
         });
     }
 
@@ -1147,19 +3007,12 @@ mod tests {
     fn bench_load_continuous_parallel(b: &mut Bencher) {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
-            BaseLetterTrie::from_file(
-                &dataset.filename(),
-                dataset.is_sorted(),
-                &LoadMethod::ContinuousParallel,
-            );
+            BaseLetterTrie::from_dataset(&dataset, &LoadMethod::ContinuousParallel).unwrap();
         });
     }
 
     fn large_tree() -> BaseLetterTrie {
-        BaseLetterTrie::from_file(
-            Dataset::TestLargeSorted.filename(),
-            true,
-            &LoadMethod::ContinuousParallel,
-        )
+        BaseLetterTrie::from_dataset(&Dataset::TestLargeSorted, &LoadMethod::ContinuousParallel)
+            .unwrap()
     }
 }
@@ -0,0 +1,481 @@
+//! A command line tool for building, querying and benchmarking letter tries.
+//!
+//! ```text
+//! letter-trie build <wordfile> -o <trie.bin>
+//! letter-trie lookup <trie.bin|wordfile> <word>
+//! letter-trie complete <trie.bin|wordfile> <prefix> [-n <count>]
+//! letter-trie stats <trie.bin|wordfile>
+//! letter-trie bench [--dataset small|medium|large|generated|realistic] [--unsorted] [--count <n>] [--seed <n>]
+//!     [--method <method>] [--type base|no-parent]
+//! letter-trie shootout [--dataset small|medium|large|generated|realistic] [--unsorted] [--count <n>] [--seed <n>]
+//!     [--probes <n>]
+//! ```
+//!
+//! Wherever a trie is read, the file can be either a trie saved by `build` or a word file with one word per line.
+//! `lookup` exits with 0 if the word is in the trie and 1 if it isn't. `shootout` prints the table from
+//! `membership_shootout()`, using the verification words as probes for the large dataset and `--probes` of each kind
+//! sampled from the words for the others. Every command exits with 2 on an error.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+use letter_trie::*;
+
+const EXIT_FOUND: i32 = 0;
+const EXIT_NOT_FOUND: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+const DEFAULT_COMPLETE_COUNT: usize = 10;
+const DEFAULT_GENERATED_COUNT: usize = 100_000;
+const DEFAULT_PROBE_COUNT: usize = 1_000;
+
+const USAGE: &str = "Usage:
+    letter-trie build <wordfile> -o <trie.bin>
+    letter-trie lookup <trie.bin|wordfile> <word>
+    letter-trie complete <trie.bin|wordfile> <prefix> [-n <count>]
+    letter-trie stats <trie.bin|wordfile>
+    letter-trie bench [--dataset small|medium|large|generated|realistic] [--unsorted] [--count <n>] [--seed <n>]
+        [--method <method>] [--type base|no-parent]
+    letter-trie shootout [--dataset small|medium|large|generated|realistic] [--unsorted] [--count <n>] [--seed <n>]
+        [--probes <n>]
+
+Generated and realistic datasets have --count words (default 100000) made from --seed (default 0).
+Load methods: read-vec-fill, vec-fill, continuous, continuous-parallel, external";
+
+#[derive(Debug)]
+enum Command {
+    Build {
+        word_file: String,
+        output: String,
+    },
+    Lookup {
+        source: String,
+        word: String,
+    },
+    Complete {
+        source: String,
+        prefix: String,
+        max_count: usize,
+    },
+    Stats {
+        source: String,
+    },
+    Bench {
+        dataset: Dataset,
+        load_method: LoadMethod,
+        letter_trie_type: LetterTrieType,
+    },
+    Shootout {
+        dataset: Dataset,
+        probe_count: usize,
+    },
+}
+
+// The arguments after the subcommand, split into positional arguments and options. Every option except the ones
+// in `flags` takes a value.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse(args: &[String], flags: &[&str]) -> Result<Self, String> {
+        let mut positional = vec![];
+        let mut options = vec![];
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg.starts_with('-') && arg.len() > 1 {
+                if flags.contains(&arg.as_str()) {
+                    options.push((arg.clone(), None));
+                } else {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| format!("Missing value for {}", arg))?;
+                    options.push((arg.clone(), Some(value.clone())));
+                }
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn positional(&self, count: usize) -> Result<&[String], String> {
+        if self.positional.len() == count {
+            Ok(&self.positional)
+        } else {
+            Err(format!(
+                "Expected {} argument(s) but found {}",
+                count,
+                self.positional.len()
+            ))
+        }
+    }
+
+    fn value(&self, names: &[&str]) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(name, _)| names.contains(&name.as_str()))
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn has_flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn check_options(&self, known: &[&str]) -> Result<(), String> {
+        match self
+            .options
+            .iter()
+            .find(|(name, _)| !known.contains(&name.as_str()))
+        {
+            Some((name, _)) => Err(format!("Unknown option {}", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    let (subcommand, rest) = args.split_first().ok_or("Missing command")?;
+    let args = Args::parse(rest, &["--unsorted"])?;
+    match subcommand.as_str() {
+        "build" => {
+            args.check_options(&["-o", "--output"])?;
+            let positional = args.positional(1)?;
+            let output = args
+                .value(&["-o", "--output"])
+                .ok_or("build needs an output file given with -o")?;
+            Ok(Command::Build {
+                word_file: positional[0].clone(),
+                output: output.to_owned(),
+            })
+        }
+        "lookup" => {
+            args.check_options(&[])?;
+            let positional = args.positional(2)?;
+            Ok(Command::Lookup {
+                source: positional[0].clone(),
+                word: positional[1].clone(),
+            })
+        }
+        "complete" => {
+            args.check_options(&["-n"])?;
+            let positional = args.positional(2)?;
+            let max_count = number_option(&args, "-n", DEFAULT_COMPLETE_COUNT)?;
+            Ok(Command::Complete {
+                source: positional[0].clone(),
+                prefix: positional[1].clone(),
+                max_count,
+            })
+        }
+        "stats" => {
+            args.check_options(&[])?;
+            let positional = args.positional(1)?;
+            Ok(Command::Stats {
+                source: positional[0].clone(),
+            })
+        }
+        "bench" => {
+            args.check_options(&[
+                "--dataset",
+                "--unsorted",
+                "--count",
+                "--seed",
+                "--method",
+                "--type",
+            ])?;
+            args.positional(0)?;
+            let dataset = dataset_option(&args)?;
+            let load_method: LoadMethod = args
+                .value(&["--method"])
+                .unwrap_or("continuous")
+                .parse()
+                .map_err(|err: UnknownNameError| err.to_string())?;
+            let letter_trie_type: LetterTrieType = args
+                .value(&["--type"])
+                .unwrap_or("no-parent")
+                .parse()
+                .map_err(|err: UnknownNameError| err.to_string())?;
+            Ok(Command::Bench {
+                dataset,
+                load_method,
+                letter_trie_type,
+            })
+        }
+        "shootout" => {
+            args.check_options(&["--dataset", "--unsorted", "--count", "--seed", "--probes"])?;
+            args.positional(0)?;
+            Ok(Command::Shootout {
+                dataset: dataset_option(&args)?,
+                probe_count: number_option(&args, "--probes", DEFAULT_PROBE_COUNT)?,
+            })
+        }
+        other => Err(format!("Unknown command {}", other)),
+    }
+}
+
+// The dataset named by --dataset, --unsorted, --count and --seed.
+fn dataset_option(args: &Args) -> Result<Dataset, String> {
+    let sorted = !args.has_flag("--unsorted");
+    Ok(
+        match (args.value(&["--dataset"]).unwrap_or("medium"), sorted) {
+            ("small", true) => Dataset::TestSmallSorted,
+            ("small", false) => Dataset::TestSmallUnsorted,
+            ("medium", true) => Dataset::TestMediumSorted,
+            ("medium", false) => Dataset::TestMediumUnsorted,
+            ("large", true) => Dataset::TestLargeSorted,
+            ("large", false) => Dataset::TestLargeUnsorted,
+            ("generated", _) => Dataset::Generated {
+                count: number_option(args, "--count", DEFAULT_GENERATED_COUNT)?,
+                seed: number_option(args, "--seed", 0)?,
+            },
+            ("realistic", _) => Dataset::Realistic {
+                count: number_option(args, "--count", DEFAULT_GENERATED_COUNT)?,
+                seed: number_option(args, "--seed", 0)?,
+            },
+            (other, _) => return Err(format!("Unknown dataset {}", other)),
+        },
+    )
+}
+
+fn number_option<T: std::str::FromStr>(args: &Args, name: &str, default: T) -> Result<T, String> {
+    match args.value(&[name]) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("{} must be a number, not {}", name, value)),
+        None => Ok(default),
+    }
+}
+
+// For map_err() on opening, reading or creating the file at path, so that the message says which file it was.
+fn in_file(path: &str) -> impl FnOnce(io::Error) -> LetterTrieError + '_ {
+    move |source| LetterTrieError::File {
+        path: path.into(),
+        source,
+    }
+}
+
+// Load a trie from either a file written by `build` or a word file.
+fn load_trie(source: &str) -> Result<NoParentLetterTrie, LetterTrieError> {
+    let bytes = fs::read(source).map_err(in_file(source))?;
+    if binary::is_binary_trie(&bytes) {
+        NoParentLetterTrie::read_binary(bytes.as_slice())
+    } else {
+        NoParentLetterTrie::from_reader(bytes.as_slice(), false, &LoadMethod::Continuous)
+    }
+}
+
+fn run(command: Command) -> Result<i32, LetterTrieError> {
+    match command {
+        Command::Build { word_file, output } => {
+            let trie = load_trie(&word_file)?;
+            trie.write_binary(fs::File::create(&output).map_err(in_file(&output))?)?;
+            println!(
+                "Saved {} words to {}",
+                format_count(trie.to_fixed_node().word_count),
+                output
+            );
+            Ok(EXIT_FOUND)
+        }
+        Command::Lookup { source, word } => {
+            if load_trie(&source)?.contains(&word) {
+                println!("{}: found", word);
+                Ok(EXIT_FOUND)
+            } else {
+                println!("{}: not found", word);
+                Ok(EXIT_NOT_FOUND)
+            }
+        }
+        Command::Complete {
+            source,
+            prefix,
+            max_count,
+        } => {
+            for word in load_trie(&source)?.suggest(&prefix, max_count) {
+                println!("{}", word);
+            }
+            Ok(EXIT_FOUND)
+        }
+        Command::Stats { source } => {
+            let root = load_trie(&source)?.to_fixed_node();
+            println!("words: {}", format_count(root.word_count));
+            println!("nodes: {}", format_count(root.node_count));
+            println!("height: {}", root.height);
+            println!("first letters: {}", root.child_count);
+            Ok(EXIT_FOUND)
+        }
+        Command::Bench {
+            dataset,
+            load_method,
+            letter_trie_type,
+        } => {
+            let opt =
+                DisplayDetailOptions::make_moderate(&dataset, &load_method, &letter_trie_type);
+            dataset.materialize()?;
+            let filename = dataset.filename();
+            let expected_word_count = Some(dataset.word_count());
+            match letter_trie_type {
+                LetterTrieType::Base => {
+                    BaseLetterTrie::from_file_test(
+                        filename,
+                        dataset.is_sorted(),
+                        &load_method,
+                        &opt,
+                        expected_word_count,
+                    )?;
+                }
+                LetterTrieType::NoParent => {
+                    NoParentLetterTrie::from_file_test(
+                        filename,
+                        dataset.is_sorted(),
+                        &load_method,
+                        &opt,
+                        expected_word_count,
+                    )?;
+                }
+                other => {
+                    eprintln!("bench doesn't support the {} trie type yet", other);
+                    return Ok(EXIT_ERROR);
+                }
+            }
+            Ok(EXIT_FOUND)
+        }
+        Command::Shootout {
+            dataset,
+            probe_count,
+        } => {
+            let probes = match dataset {
+                Dataset::TestLargeSorted | Dataset::TestLargeUnsorted => ProbeSet::verification()?,
+                _ => ProbeSet::sampled(&dataset, probe_count)?,
+            };
+            print!("{}", membership_shootout(&dataset, &probes)?);
+            Ok(EXIT_FOUND)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match parse_command(&args) {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            process::exit(EXIT_ERROR);
+        }
+    };
+    match run(command) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|x| x.to_owned()).collect()
+    }
+
+    #[test]
+    fn parse() {
+        assert!(matches!(
+            parse_command(&args("complete words.txt cr -n 3")),
+            Ok(Command::Complete { max_count: 3, .. })
+        ));
+        assert!(matches!(
+            parse_command(&args(
+                "bench --dataset large --method continuous-parallel --type base"
+            )),
+            Ok(Command::Bench {
+                dataset: Dataset::TestLargeSorted,
+                load_method: LoadMethod::ContinuousParallel,
+                letter_trie_type: LetterTrieType::Base,
+            })
+        ));
+        assert!(matches!(
+            parse_command(&args("bench --dataset generated --count 500 --seed 9")),
+            Ok(Command::Bench {
+                dataset: Dataset::Generated {
+                    count: 500,
+                    seed: 9
+                },
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_command(&args("shootout --dataset realistic --count 500")),
+            Ok(Command::Shootout {
+                dataset: Dataset::Realistic {
+                    count: 500,
+                    seed: 0
+                },
+                ..
+            })
+        ));
+        assert!(parse_command(&args("bench --dataset generated --count many")).is_err());
+        assert!(parse_command(&args("build words.txt")).is_err());
+        assert!(parse_command(&args("lookup words.txt")).is_err());
+        assert!(parse_command(&args("stats words.txt -x 1")).is_err());
+        assert!(parse_command(&args("bench --dataset huge")).is_err());
+        assert!(matches!(
+            parse_command(&args("shootout --dataset small --unsorted --probes 5")),
+            Ok(Command::Shootout {
+                dataset: Dataset::TestSmallUnsorted,
+                probe_count: 5,
+            })
+        ));
+        assert!(parse_command(&args("shootout --method continuous")).is_err());
+        assert!(parse_command(&[]).is_err());
+    }
+
+    #[test]
+    fn build_then_lookup() {
+        let output = env::temp_dir().join(format!("letter_trie_cli_{}.bin", process::id()));
+        let output = output.to_str().unwrap().to_owned();
+        let build = Command::Build {
+            word_file: Dataset::TestSmallUnsorted.filename().to_owned(),
+            output: output.clone(),
+        };
+        assert_eq!(EXIT_FOUND, run(build).unwrap());
+        assert!(binary::is_binary_trie(&fs::read(&output).unwrap()));
+
+        for (source, word, expected) in &[
+            (output.as_str(), "creature", EXIT_FOUND),
+            (output.as_str(), "creatur", EXIT_NOT_FOUND),
+            (Dataset::TestSmallSorted.filename(), "azure", EXIT_FOUND),
+        ] {
+            let lookup = Command::Lookup {
+                source: source.to_string(),
+                word: word.to_string(),
+            };
+            assert_eq!(*expected, run(lookup).unwrap());
+        }
+        fs::remove_file(&output).unwrap();
+
+        let missing = Command::Stats {
+            source: output.clone(),
+        };
+        let err = run(missing).unwrap_err();
+        assert!(matches!(err, LetterTrieError::File { .. }));
+        assert!(err.to_string().contains(&output));
+    }
+
+    #[test]
+    fn shootout() {
+        let shootout = Command::Shootout {
+            dataset: Dataset::TestSmallSorted,
+            probe_count: 3,
+        };
+        assert_eq!(EXIT_FOUND, run(shootout).unwrap());
+    }
+}
@@ -0,0 +1,864 @@
+//! A compact binary format for saving a trie and loading it again without going back to the word file.
+//!
+//! The file starts with a header:
+//! - The six bytes `LTRIE\0`.
+//! - A format version byte, which is 1 for a trie on its own and 2 for a `ConfiguredLetterTrie`.
+//! - The number of nodes including the root as a little-endian u64.
+//! - For version 2 only, the options block: its length in bytes as a little-endian u64, then the
+//!   `TrieOptions` the words were stored under and the original spellings kept for them. A reader skips any bytes
+//!   at the end of the block that it doesn't know about.
+//!
+//! Then every node follows in the same depth-first, character order used by `LetterTrie::visit()`, starting with
+//! the root. Each node is its character as a little-endian u32, a flags byte where bit 0 means the node ends a
+//! word, bit 1 means it has a tag, bit 2 means it has sources and bit 3 means it has a spelling, and its number of
+//! children as a little-endian u32. If the node has a tag it follows as a little-endian u64, then if it has sources
+//! their number follows as a little-endian u16 and each source as another, and then if it has a spelling its
+//! length in bytes follows as a little-endian u32 and the spelling as UTF-8. A trie without tags, sources or
+//! spellings takes no more space than it did before them. A node's children come right after it, so the shape of
+//! the trie can be rebuilt without storing any links.
+//!
+//! The options block starts with a flags byte where bit 0 means diacritics are folded and bit 1 means original
+//! spellings are kept, then one byte each for the case preference (0 for none, then 1 to 3 for `FirstSeen`,
+//! `LastSeen` and `SortsFirst`), the duplicate policy (0 to 2 for `Ignore`, `Count` and `Error`) and the UTF-8
+//! policy (0 to 2 for `Strict`, `Lossy` and `Skip`). Then come the tag separator as a little-endian u32, and the
+//! maximum word length and the maximum number of words, each as a little-endian u64 followed by a byte for its
+//! policy, 0 for `Skip` and 1 for `Abort`. `u32::MAX` or `u64::MAX` means the option isn't set. Last are the
+//! original spellings: the number of words as a little-endian u32, and for each word the stored word, the number
+//! of its spellings as a little-endian u32 and each spelling, where each string is its length in bytes as a
+//! little-endian u32 followed by its UTF-8.
+//!
+//! The format doesn't depend on the implementation, so a file written from a `BaseLetterTrie` can be read into a
+//! `NoParentLetterTrie` and vice versa. `LetterTrie::read_binary()` reads either version and leaves out the
+//! options, and `ConfiguredLetterTrie::read_binary()` reads a version 1 file as having the default options.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+#[cfg(feature = "fs")]
+use std::io::{Seek, SeekFrom};
+
+use crate::*;
+
+/// The bytes at the start of every file in this format.
+pub const BINARY_MAGIC: &[u8; 6] = b"LTRIE\0";
+
+/// The version of the format written by `LetterTrie::write_binary()`.
+pub const BINARY_VERSION: u8 = 1;
+
+/// The version of the format written by `ConfiguredLetterTrie::write_binary()`, which has the options block.
+pub const BINARY_OPTIONS_VERSION: u8 = 2;
+
+const FLAG_IS_WORD: u8 = 1;
+const FLAG_HAS_TAG: u8 = 2;
+const FLAG_HAS_SOURCES: u8 = 4;
+const FLAG_HAS_SPELLING: u8 = 8;
+
+const OPTION_FOLD_DIACRITICS: u8 = 1;
+const OPTION_KEEP_ORIGINAL_SPELLING: u8 = 2;
+
+// Stands for an option that isn't set in the options block.
+const NOT_SET_U32: u32 = u32::MAX;
+const NOT_SET_U64: u64 = u64::MAX;
+
+// The options a ConfiguredLetterTrie was saved with, and the original spellings it kept under
+// TrieOptions::keep_original_spelling keyed by the stored word.
+pub(crate) struct SavedOptions {
+    pub(crate) options: TrieOptions,
+    pub(crate) spellings: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Returns true if `bytes` starts with the header of this format, which is a quick way to tell a saved trie from
+/// a word file.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// assert!(!binary::is_binary_trie(b"cross\ncrossed\n"));
+/// ```
+pub fn is_binary_trie(bytes: &[u8]) -> bool {
+    bytes.starts_with(BINARY_MAGIC)
+}
+
+pub(crate) fn write_trie<T, W>(trie: &T, writer: W) -> Result<()>
+where
+    T: LetterTrie + ?Sized,
+    W: Write,
+{
+    write_trie_with_options(trie, writer, None)
+}
+
+// Write the trie as version 2 with the options block if there are options, and as version 1 otherwise.
+pub(crate) fn write_trie_with_options<T, W>(
+    trie: &T,
+    writer: W,
+    options: Option<(&TrieOptions, &BTreeMap<String, BTreeSet<String>>)>,
+) -> Result<()>
+where
+    T: LetterTrie + ?Sized,
+    W: Write,
+{
+    let mut writer = io::BufWriter::new(writer);
+    let mut node_count: u64 = 0;
+    trie.visit("", &mut |_| {
+        node_count += 1;
+        true
+    });
+    writer.write_all(BINARY_MAGIC)?;
+    match options {
+        Some((options, spellings)) => {
+            writer.write_all(&[BINARY_OPTIONS_VERSION])?;
+            writer.write_all(&node_count.to_le_bytes())?;
+            let mut block: Vec<u8> = vec![];
+            write_options(&mut block, options, spellings)?;
+            writer.write_all(&(block.len() as u64).to_le_bytes())?;
+            writer.write_all(&block)?;
+        }
+        None => {
+            writer.write_all(&[BINARY_VERSION])?;
+            writer.write_all(&node_count.to_le_bytes())?;
+        }
+    }
+
+    // visit() can't return an error so keep the first one and skip the rest of the walk.
+    let mut result: io::Result<()> = Ok(());
+    trie.visit("", &mut |node| {
+        if result.is_ok() {
+            let (tag, sources) = if node.is_word {
+                (trie.tag(node.prefix), trie.sources(node.prefix))
+            } else {
+                (None, None)
+            };
+            result = write_node(&mut writer, node, tag, sources.unwrap_or_default());
+        }
+        result.is_ok()
+    });
+    result?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Write the options block described at the top of the module, without its length.
+fn write_options<W: Write>(
+    writer: &mut W,
+    options: &TrieOptions,
+    spellings: &BTreeMap<String, BTreeSet<String>>,
+) -> io::Result<()> {
+    let mut flags = 0;
+    if options.fold_diacritics {
+        flags |= OPTION_FOLD_DIACRITICS;
+    }
+    if options.keep_original_spelling {
+        flags |= OPTION_KEEP_ORIGINAL_SPELLING;
+    }
+    let case_preference = match options.case_preference {
+        None => 0,
+        Some(CasePreference::FirstSeen) => 1,
+        Some(CasePreference::LastSeen) => 2,
+        Some(CasePreference::SortsFirst) => 3,
+    };
+    let duplicate_policy = match options.duplicate_policy {
+        DuplicatePolicy::Ignore => 0,
+        DuplicatePolicy::Count => 1,
+        DuplicatePolicy::Error => 2,
+    };
+    let utf8_policy = match options.utf8_policy {
+        Utf8Policy::Strict => 0,
+        Utf8Policy::Lossy => 1,
+        Utf8Policy::Skip => 2,
+    };
+    writer.write_all(&[flags, case_preference, duplicate_policy, utf8_policy])?;
+    let tag_separator = options.tag_separator.map_or(NOT_SET_U32, |c| c as u32);
+    writer.write_all(&tag_separator.to_le_bytes())?;
+    for (max, policy) in [
+        (options.max_word_len, options.word_len_policy),
+        (options.max_words, options.max_words_policy),
+    ] {
+        let max = max.map_or(NOT_SET_U64, |max| max as u64);
+        writer.write_all(&max.to_le_bytes())?;
+        writer.write_all(&[limit_policy_byte(policy)])?;
+    }
+    writer.write_all(&u32_len(spellings.len())?.to_le_bytes())?;
+    for (word, word_spellings) in spellings {
+        write_string(writer, word)?;
+        writer.write_all(&u32_len(word_spellings.len())?.to_le_bytes())?;
+        for spelling in word_spellings {
+            write_string(writer, spelling)?;
+        }
+    }
+    Ok(())
+}
+
+fn limit_policy_byte(policy: LimitPolicy) -> u8 {
+    match policy {
+        LimitPolicy::Skip => 0,
+        LimitPolicy::Abort => 1,
+    }
+}
+
+fn u32_len(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is too many to save", len),
+        )
+    })
+}
+
+fn write_string<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    writer.write_all(&u32_len(s.len())?.to_le_bytes())?;
+    writer.write_all(s.as_bytes())
+}
+
+// Read the options block written by write_options(), ignoring anything after what it knows about.
+fn read_options<R: Read>(reader: &mut R) -> Result<SavedOptions> {
+    let len = read_u64(reader)?;
+    let mut block = vec![];
+    // Read through take() so that a corrupt length can't make this allocate more than the file holds.
+    reader.take(len).read_to_end(&mut block)?;
+    if block.len() as u64 != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let reader = &mut block.as_slice();
+    let flags = read_u8(reader)?;
+    let invalid = |name: &str, value: u64| {
+        LetterTrieError::InvalidFormat(format!("{} is not a valid {}", value, name))
+    };
+    let case_preference = match read_u8(reader)? {
+        0 => None,
+        1 => Some(CasePreference::FirstSeen),
+        2 => Some(CasePreference::LastSeen),
+        3 => Some(CasePreference::SortsFirst),
+        value => return Err(invalid("case preference", value.into())),
+    };
+    let duplicate_policy = match read_u8(reader)? {
+        0 => DuplicatePolicy::Ignore,
+        1 => DuplicatePolicy::Count,
+        2 => DuplicatePolicy::Error,
+        value => return Err(invalid("duplicate policy", value.into())),
+    };
+    let utf8_policy = match read_u8(reader)? {
+        0 => Utf8Policy::Strict,
+        1 => Utf8Policy::Lossy,
+        2 => Utf8Policy::Skip,
+        value => return Err(invalid("UTF-8 policy", value.into())),
+    };
+    let tag_separator = match read_u32(reader)? {
+        NOT_SET_U32 => None,
+        value => {
+            Some(std::char::from_u32(value).ok_or_else(|| invalid("tag separator", value.into()))?)
+        }
+    };
+    let mut limits = [(None, LimitPolicy::Skip); 2];
+    for limit in &mut limits {
+        let max = match read_u64(reader)? {
+            NOT_SET_U64 => None,
+            value => Some(usize::try_from(value).map_err(|_| invalid("limit", value))?),
+        };
+        let policy = match read_u8(reader)? {
+            0 => LimitPolicy::Skip,
+            1 => LimitPolicy::Abort,
+            value => return Err(invalid("limit policy", value.into())),
+        };
+        *limit = (max, policy);
+    }
+    let mut spellings: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for _ in 0..read_u32(reader)? {
+        let word = read_string(reader)?;
+        let word_spellings = spellings.entry(word).or_default();
+        for _ in 0..read_u32(reader)? {
+            word_spellings.insert(read_string(reader)?);
+        }
+    }
+    let [(max_word_len, word_len_policy), (max_words, max_words_policy)] = limits;
+    let options = TrieOptions {
+        fold_diacritics: flags & OPTION_FOLD_DIACRITICS != 0,
+        keep_original_spelling: flags & OPTION_KEEP_ORIGINAL_SPELLING != 0,
+        tag_separator,
+        max_word_len,
+        word_len_policy,
+        max_words,
+        max_words_policy,
+        duplicate_policy,
+        case_preference,
+        utf8_policy,
+    };
+    Ok(SavedOptions { options, spellings })
+}
+
+// Read a string written by write_string(), which isn't allowed to be longer than the rest of the block.
+fn read_string(reader: &mut &[u8]) -> Result<String> {
+    let len = read_u32(reader)? as usize;
+    if len > reader.len() {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    String::from_utf8(bytes.to_vec()).map_err(|_| {
+        LetterTrieError::InvalidFormat("a spelling in the options block isn't UTF-8".to_owned())
+    })
+}
+
+fn write_node<W: Write>(
+    writer: &mut W,
+    node: &NodeView,
+    tag: Option<u64>,
+    sources: &[u16],
+) -> io::Result<()> {
+    let mut flags = if node.is_word { FLAG_IS_WORD } else { 0 };
+    if tag.is_some() {
+        flags |= FLAG_HAS_TAG;
+    }
+    if !sources.is_empty() {
+        flags |= FLAG_HAS_SOURCES;
+    }
+    if node.spelling.is_some() {
+        flags |= FLAG_HAS_SPELLING;
+    }
+    writer.write_all(&(node.c as u32).to_le_bytes())?;
+    writer.write_all(&[flags])?;
+    writer.write_all(&(node.child_count as u32).to_le_bytes())?;
+    if let Some(tag) = tag {
+        writer.write_all(&tag.to_le_bytes())?;
+    }
+    if !sources.is_empty() {
+        // A word can only have as many sources as there are u16 values, but the count needs one more than that.
+        let count = u16::try_from(sources.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?} has too many sources", node.prefix),
+            )
+        })?;
+        writer.write_all(&count.to_le_bytes())?;
+        for source_id in sources {
+            writer.write_all(&source_id.to_le_bytes())?;
+        }
+    }
+    if let Some(spelling) = node.spelling {
+        writer.write_all(&(spelling.len() as u32).to_le_bytes())?;
+        writer.write_all(spelling.as_bytes())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_trie<T, R>(reader: R) -> Result<T>
+where
+    T: LetterTrie,
+    R: Read,
+{
+    read_trie_with_options(reader).map(|(trie, _)| trie)
+}
+
+// Read a trie of either version, along with its options if it was saved with them.
+pub(crate) fn read_trie_with_options<T, R>(reader: R) -> Result<(T, Option<SavedOptions>)>
+where
+    T: LetterTrie,
+    R: Read,
+{
+    let mut words: Vec<String> = vec![];
+    let mut sourced_words: Vec<SourcedWord> = vec![];
+    let mut binary_words = BinaryWords::new(io::BufReader::new(reader))?;
+    let saved = binary_words.saved_options.take();
+    for word in binary_words {
+        let word = word?;
+        if word.tag.is_none() && word.sources.is_empty() && word.spelling.is_none() {
+            words.push(word.word);
+        } else {
+            sourced_words.push(word);
+        }
+    }
+    let mut trie = T::from_words(words);
+    for word in sourced_words {
+        trie.insert_sourced_word(&word);
+    }
+    Ok((trie, saved))
+}
+
+// The words of a saved trie in alphabetical order along with their tags, sources and spellings, read one at a time so that
+// a big file can be gone through without holding all of its words. The iterator ends after the first error.
+pub(crate) struct BinaryWords<R: Read> {
+    reader: R,
+    node_count: u64,
+    nodes_read: u64,
+    // Each entry is the number of children still to be read for a node on the current path.
+    pending: Vec<u32>,
+    prefix: String,
+    // The options block of a version 2 file.
+    pub(crate) saved_options: Option<SavedOptions>,
+}
+
+impl<R: Read> BinaryWords<R> {
+    // Read the header and the root.
+    pub(crate) fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0u8; 6];
+        reader.read_exact(&mut magic)?;
+        if &magic != BINARY_MAGIC {
+            return Err(LetterTrieError::InvalidFormat(
+                "not a letter trie file".to_owned(),
+            ));
+        }
+        let version = read_u8(&mut reader)?;
+        if version != BINARY_VERSION && version != BINARY_OPTIONS_VERSION {
+            return Err(LetterTrieError::InvalidFormat(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+        let node_count = read_u64(&mut reader)?;
+        if node_count == 0 {
+            return Err(LetterTrieError::InvalidFormat("no root node".to_owned()));
+        }
+        let saved_options = if version == BINARY_OPTIONS_VERSION {
+            Some(read_options(&mut reader)?)
+        } else {
+            None
+        };
+        // The first node is the root, whose character isn't part of any word.
+        let root = read_node(&mut reader)?;
+        if root.is_word {
+            return Err(LetterTrieError::InvalidFormat(
+                "the root can't be a word".to_owned(),
+            ));
+        }
+        Ok(Self {
+            reader,
+            node_count,
+            nodes_read: 1,
+            pending: vec![root.child_count],
+            prefix: String::new(),
+            saved_options,
+        })
+    }
+
+    fn next_word(&mut self) -> Result<Option<SourcedWord>> {
+        while let Some(remaining) = self.pending.last_mut() {
+            if *remaining == 0 {
+                self.pending.pop();
+                self.prefix.pop();
+                continue;
+            }
+            *remaining -= 1;
+            if self.nodes_read == self.node_count {
+                return Err(LetterTrieError::InvalidFormat(format!(
+                    "more than the {} nodes in the header",
+                    self.node_count
+                )));
+            }
+            let node = read_node(&mut self.reader)?;
+            self.nodes_read += 1;
+            self.prefix.push(node.c);
+            self.pending.push(node.child_count);
+            if node.is_word {
+                return Ok(Some(SourcedWord {
+                    word: self.prefix.clone(),
+                    tag: node.tag,
+                    sources: node.sources,
+                    spelling: node.spelling,
+                }));
+            }
+            if node.tag.is_some() || !node.sources.is_empty() || node.spelling.is_some() {
+                return Err(LetterTrieError::InvalidFormat(format!(
+                    "{:?} has a tag, sources or a spelling but isn't a word",
+                    self.prefix
+                )));
+            }
+        }
+        if self.nodes_read != self.node_count {
+            return Err(LetterTrieError::InvalidFormat(format!(
+                "{} nodes in the header but {} in the trie",
+                self.node_count, self.nodes_read
+            )));
+        }
+        Ok(None)
+    }
+}
+
+impl<R: Read> Iterator for BinaryWords<R> {
+    type Item = Result<SourcedWord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_word() {
+            Ok(word) => word.map(Ok),
+            Err(err) => {
+                // Nothing after an error can be trusted.
+                self.pending.clear();
+                self.nodes_read = self.node_count;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+// The size of the buffer in SortedWordsWriter. A node's number of children is filled in once all of its children
+// have been written, which is usually while the node is still in the buffer.
+#[cfg(feature = "fs")]
+const SORTED_WORDS_BUFFER_LEN: usize = 1 << 20;
+
+// Writes a trie in this format from words that come in alphabetical order without repeats, without building the
+// trie. Each node is written with no children, and the count is filled in when the words move past it. Tags aren't
+// written.
+#[cfg(feature = "fs")]
+pub(crate) struct SortedWordsWriter<W: Write + Seek> {
+    writer: W,
+    buffer: Vec<u8>,
+    // The number of bytes already written out of the buffer.
+    flushed: u64,
+    node_count: u64,
+    // The letters of the last word, with the position of the child count and the number of children so far for
+    // each node on its path, starting with the root.
+    letters: Vec<char>,
+    path: Vec<(u64, u32)>,
+}
+
+#[cfg(feature = "fs")]
+impl<W: Write + Seek> SortedWordsWriter<W> {
+    pub(crate) fn new(writer: W) -> io::Result<Self> {
+        let mut this = Self {
+            writer,
+            buffer: Vec::with_capacity(SORTED_WORDS_BUFFER_LEN),
+            flushed: 0,
+            node_count: 0,
+            letters: vec![],
+            path: vec![],
+        };
+        this.write(BINARY_MAGIC)?;
+        this.write(&[BINARY_VERSION])?;
+        this.write(&0u64.to_le_bytes())?;
+        this.write_node(' ', false)?;
+        Ok(this)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.buffer.extend_from_slice(bytes);
+        if self.buffer.len() >= SORTED_WORDS_BUFFER_LEN {
+            self.writer.write_all(&self.buffer)?;
+            self.flushed += self.buffer.len() as u64;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    // Write over bytes that were written earlier, in the buffer if they're still there.
+    fn patch(&mut self, position: u64, bytes: &[u8]) -> io::Result<()> {
+        if position >= self.flushed {
+            let start = (position - self.flushed) as usize;
+            self.buffer[start..start + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        } else {
+            self.writer.seek(SeekFrom::Start(position))?;
+            self.writer.write_all(bytes)?;
+            self.writer.seek(SeekFrom::End(0))?;
+            Ok(())
+        }
+    }
+
+    fn write_node(&mut self, c: char, is_word: bool) -> io::Result<()> {
+        if let Some((_, child_count)) = self.path.last_mut() {
+            *child_count += 1;
+        }
+        let position = self.flushed + self.buffer.len() as u64;
+        self.write(&(c as u32).to_le_bytes())?;
+        self.write(&[if is_word { FLAG_IS_WORD } else { 0 }])?;
+        self.write(&0u32.to_le_bytes())?;
+        self.node_count += 1;
+        self.path.push((position + 5, 0));
+        Ok(())
+    }
+
+    // Leave the deepest node on the path, now that all of its children have been written.
+    fn close_node(&mut self) -> io::Result<()> {
+        let Some((position, child_count)) = self.path.pop() else {
+            return Ok(());
+        };
+        self.patch(position, &child_count.to_le_bytes())
+    }
+
+    // Add a word, which has to come after the last one in alphabetical order.
+    pub(crate) fn add(&mut self, word: &str) -> io::Result<()> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.is_empty() || chars <= self.letters {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("\"{}\" is out of order or repeated", word),
+            ));
+        }
+        let shared = chars
+            .iter()
+            .zip(&self.letters)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while self.path.len() > shared + 1 {
+            self.close_node()?;
+        }
+        for (i, c) in chars.iter().enumerate().skip(shared) {
+            self.write_node(*c, i == chars.len() - 1)?;
+        }
+        self.letters = chars;
+        Ok(())
+    }
+
+    // Fill in the remaining counts and the number of nodes in the header, and return the writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        while !self.path.is_empty() {
+            self.close_node()?;
+        }
+        let node_count = self.node_count;
+        self.patch(BINARY_MAGIC.len() as u64 + 1, &node_count.to_le_bytes())?;
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+// One node as read from the file.
+struct RawNode {
+    c: char,
+    is_word: bool,
+    child_count: u32,
+    tag: Option<u64>,
+    // Empty if the node has no sources.
+    sources: Vec<u16>,
+    spelling: Option<String>,
+}
+
+// Read one node along with its tag, sources and spelling if it has them.
+fn read_node<R: Read>(reader: &mut R) -> Result<RawNode> {
+    let c = read_u32(reader)?;
+    let c = std::char::from_u32(c).ok_or_else(|| {
+        LetterTrieError::InvalidFormat(format!("{:#x} is not a valid character", c))
+    })?;
+    let flags = read_u8(reader)?;
+    let child_count = read_u32(reader)?;
+    let tag = if flags & FLAG_HAS_TAG != 0 {
+        Some(read_u64(reader)?)
+    } else {
+        None
+    };
+    let mut sources = vec![];
+    if flags & FLAG_HAS_SOURCES != 0 {
+        let count = read_u16(reader)?;
+        for _ in 0..count {
+            add_source(&mut sources, read_u16(reader)?);
+        }
+    }
+    let spelling = if flags & FLAG_HAS_SPELLING != 0 {
+        let len = read_u32(reader)? as usize;
+        let mut bytes = vec![];
+        // Read through take() so that a corrupt length can't make this allocate more than the file holds.
+        reader.take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let spelling = String::from_utf8(bytes).map_err(|_| {
+            LetterTrieError::InvalidFormat(format!("the spelling of {:?} isn't UTF-8", c))
+        })?;
+        Some(spelling)
+    } else {
+        None
+    };
+    Ok(RawNode {
+        c,
+        is_word: flags & FLAG_IS_WORD != 0,
+        child_count,
+        tag,
+        sources,
+        spelling,
+    })
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_round_trip_between_types() {
+        let t = BaseLetterTrie::from_words(
+            words_from_file(Dataset::TestSmallUnsorted.filename()).unwrap(),
+        );
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        assert!(is_binary_trie(&bytes));
+        // 15 bytes of header plus 9 bytes for each of the 28 nodes.
+        assert_eq!(15 + 9 * 28, bytes.len());
+
+        let t2 = NoParentLetterTrie::read_binary(bytes.as_slice()).unwrap();
+        assert_small_root(&t2.to_fixed_node());
+        assert_eq!(t.words_with_prefix(""), t2.words_with_prefix(""));
+    }
+
+    #[test]
+    fn medium_round_trip() {
+        let dataset = Dataset::TestMediumSorted;
+        let t = NoParentLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        let t2 = NoParentLetterTrie::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(t.to_fixed_node(), t2.to_fixed_node());
+    }
+
+    #[test]
+    fn tags_round_trip() {
+        let mut t = BaseLetterTrie::from_words(vec!["an", "and", "cross"]);
+        t.insert_with_tag("and", 3);
+        t.insert_with_tag("cross", u64::MAX);
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        // 15 bytes of header, 9 bytes for each of the 9 nodes and 8 for each of the 2 tags.
+        assert_eq!(15 + 9 * 9 + 8 * 2, bytes.len());
+
+        let t2 = NoParentLetterTrie::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(
+            t.words_with_tags().collect::<Vec<_>>(),
+            t2.words_with_tags().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(u64::MAX), t2.tag("cross"));
+
+        // A tag on the node for "a", which isn't a word.
+        let t = NoParentLetterTrie::from_words(vec!["an", "and"]);
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        bytes[15 + 9 + 4] = FLAG_HAS_TAG;
+        bytes.splice(15 + 9 * 2..15 + 9 * 2, 0u64.to_le_bytes().iter().copied());
+        let result = NoParentLetterTrie::read_binary(bytes.as_slice());
+        assert!(matches!(result, Err(LetterTrieError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn sources_round_trip() {
+        let mut t = NoParentLetterTrie::from_words(vec!["an", "and"]);
+        t.insert_with_source("and", 1);
+        t.insert_with_source("and", 300);
+        t.insert_with_tag("cross", 5);
+        t.insert_with_source("cross", 2);
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        // 15 bytes of header, 9 bytes for each of the 9 nodes, 8 for the tag and 2 for each source count and source.
+        assert_eq!(15 + 9 * 9 + 8 + 2 * 5, bytes.len());
+
+        let t2 = BaseLetterTrie::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(
+            t.words_with_sources().collect::<Vec<_>>(),
+            t2.words_with_sources().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&[1, 300][..]), t2.sources("and"));
+        assert_eq!(Some(5), t2.tag("cross"));
+        assert_eq!(None, t2.sources("an"));
+    }
+
+    #[test]
+    fn spellings_round_trip() {
+        let mut t = BaseLetterTrie::from_words(vec!["mac"]);
+        t.insert_preserving_case("MacDonald", CasePreference::FirstSeen);
+        t.insert_preserving_case("Ébène", CasePreference::FirstSeen);
+        t.insert_with_tag("ébène", 4);
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        // 15 bytes of header, 9 bytes for each of the 15 nodes, 8 for the tag, and 4 for each spelling's length
+        // along with its bytes.
+        assert_eq!(15 + 9 * 15 + 8 + 4 + 9 + 4 + 7, bytes.len());
+
+        let t2 = NoParentLetterTrie::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(
+            t.words_with_sources().collect::<Vec<_>>(),
+            t2.words_with_sources().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["mac", "MacDonald", "Ébène"],
+            t2.words().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(4), t2.tag("ÉBÈNE"));
+
+        // A spelling that isn't UTF-8.
+        let spelling_start = bytes.len() - 7;
+        bytes[spelling_start] = 0xff;
+        let result = NoParentLetterTrie::read_binary(bytes.as_slice());
+        assert!(matches!(result, Err(LetterTrieError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn bad_input_is_an_error() {
+        let t = NoParentLetterTrie::from_words(vec!["an", "and"]);
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+
+        let result = NoParentLetterTrie::read_binary(&b"an\nand\n"[..]);
+        assert!(matches!(result, Err(LetterTrieError::InvalidFormat(_))));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[6] = 99;
+        let result = NoParentLetterTrie::read_binary(wrong_version.as_slice());
+        assert!(matches!(result, Err(LetterTrieError::InvalidFormat(_))));
+
+        let result = NoParentLetterTrie::read_binary(&bytes[..bytes.len() - 1]);
+        assert!(matches!(result, Err(LetterTrieError::Io(_))));
+
+        let mut wrong_count = bytes.clone();
+        wrong_count[7] += 1;
+        let result = NoParentLetterTrie::read_binary(wrong_count.as_slice());
+        assert!(matches!(result, Err(LetterTrieError::InvalidFormat(_))));
+    }
+
+    // Writing sorted words gives the same bytes as writing the trie built from them, and they read back in order.
+    #[test]
+    fn sorted_words_writer() {
+        let t = NoParentLetterTrie::from_words(
+            words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap(),
+        );
+        let mut expected: Vec<u8> = vec![];
+        t.write_binary(&mut expected).unwrap();
+
+        let words: Vec<String> = t.words().collect();
+        let mut writer = SortedWordsWriter::new(io::Cursor::new(vec![])).unwrap();
+        for word in &words {
+            writer.add(word).unwrap();
+        }
+        assert!(writer.add(&words[0]).is_err());
+        assert!(writer.add(words.last().unwrap()).is_err());
+        let bytes = writer.finish().unwrap().into_inner();
+        assert_eq!(expected, bytes);
+
+        let read: Vec<String> = BinaryWords::new(bytes.as_slice())
+            .unwrap()
+            .map(|word| word.unwrap().word)
+            .collect();
+        assert_eq!(words, read);
+
+        let empty = SortedWordsWriter::new(io::Cursor::new(vec![]))
+            .unwrap()
+            .finish()
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            0,
+            NoParentLetterTrie::read_binary(empty.as_slice())
+                .unwrap()
+                .to_fixed_node()
+                .word_count
+        );
+    }
+}
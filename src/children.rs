@@ -0,0 +1,310 @@
+// The children of a NoParentLetterTrie node. Most nodes in a trie of words have no children or one, so a node
+// without children allocates nothing and a node with one keeps it in a single box. A node with more keeps them in a
+// vector sorted by letter and finds one by binary search. The vector starts with room for a few children, which is
+// all most of these nodes ever get, and grows as usual from there.
+//
+// The methods are the ones the trie used from the BTreeMap this replaced, with the same signatures, and everything
+// that walks the children gets them in character order.
+//
+// Against the BTreeMap this cuts the bytes a from_words() load keeps by about 85%, but it doesn't cut the number of
+// allocations, which goes up by about a fifth: from 779,104 to 959,383 on the large dataset and from 80,856 to
+// 93,225 on the medium one. A BTreeMap took one allocation for a node's first eleven children. Here a node that gets
+// a second child swaps its box for a vector, so it has taken two allocations, and a vector that grows past four
+// children takes another each time it grows. Every node with children needs an allocation of its own either way,
+// so the count can't go below the BTreeMap's without keeping the nodes somewhere else, such as an arena.
+
+use core::iter::FusedIterator;
+use core::mem;
+use core::slice;
+
+use crate::*;
+
+// The room a node's vector starts with when its second child is added.
+const MANY_INITIAL_CAPACITY: usize = 4;
+
+#[derive(Default)]
+pub(crate) enum Children<T> {
+    #[default]
+    None,
+    One(char, Box<T>),
+    // Never fewer than two.
+    Many(Vec<(char, T)>),
+}
+
+impl<T> Children<T> {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Children::None => 0,
+            Children::One(_, _) => 1,
+            Children::Many(v) => v.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        matches!(self, Children::None)
+    }
+
+    pub(crate) fn get(&self, c: &char) -> Option<&T> {
+        match self {
+            Children::None => None,
+            Children::One(one_c, child) => (one_c == c).then_some(&**child),
+            Children::Many(v) => v
+                .binary_search_by_key(c, |(c, _)| *c)
+                .ok()
+                .map(|index| &v[index].1),
+        }
+    }
+
+    // get(), adding the number of letters compared along the way to comparisons.
+    pub(crate) fn get_counting(&self, c: &char, comparisons: &mut usize) -> Option<&T> {
+        match self {
+            Children::None => None,
+            Children::One(one_c, child) => {
+                *comparisons += 1;
+                (one_c == c).then_some(&**child)
+            }
+            Children::Many(v) => v
+                .binary_search_by(|(one_c, _)| {
+                    *comparisons += 1;
+                    one_c.cmp(c)
+                })
+                .ok()
+                .map(|index| &v[index].1),
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, c: &char) -> Option<&mut T> {
+        match self {
+            Children::None => None,
+            Children::One(one_c, child) => (one_c == c).then_some(&mut **child),
+            Children::Many(v) => match v.binary_search_by_key(c, |(c, _)| *c) {
+                Ok(index) => Some(&mut v[index].1),
+                Err(_) => None,
+            },
+        }
+    }
+
+    // The child for c, first adding the one from f if there isn't one, as with BTreeMap::entry().or_insert_with().
+    pub(crate) fn get_or_insert_with(&mut self, c: char, f: impl FnOnce() -> T) -> &mut T {
+        let mut f = Some(f);
+        // Only one of the paths below adds a child, so make() is called at most once.
+        #[allow(clippy::unwrap_used)]
+        let mut make = || (f.take().unwrap())();
+        match self {
+            Children::None => *self = Children::One(c, Box::new(make())),
+            Children::One(one_c, _) if *one_c != c => {
+                let one_c = *one_c;
+                let one = match mem::take(self) {
+                    Children::One(_, child) => *child,
+                    _ => unreachable!(),
+                };
+                let mut v = Vec::with_capacity(MANY_INITIAL_CAPACITY);
+                if c < one_c {
+                    v.push((c, make()));
+                    v.push((one_c, one));
+                } else {
+                    v.push((one_c, one));
+                    v.push((c, make()));
+                }
+                *self = Children::Many(v);
+            }
+            _ => {}
+        }
+        match self {
+            Children::None => unreachable!(),
+            Children::One(_, child) => child,
+            Children::Many(v) => {
+                let index = match v.binary_search_by_key(&c, |(c, _)| *c) {
+                    Ok(index) => index,
+                    Err(index) => {
+                        v.insert(index, (c, make()));
+                        index
+                    }
+                };
+                &mut v[index].1
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, c: &char) -> Option<T> {
+        match self {
+            Children::None => None,
+            Children::One(one_c, _) if one_c != c => None,
+            Children::One(_, _) => match mem::take(self) {
+                Children::One(_, child) => Some(*child),
+                _ => unreachable!(),
+            },
+            Children::Many(v) => {
+                let index = v.binary_search_by_key(c, |(c, _)| *c).ok()?;
+                let (_, child) = v.remove(index);
+                if v.len() == 1 {
+                    if let Some((last_c, last)) = v.pop() {
+                        *self = Children::One(last_c, Box::new(last));
+                    }
+                }
+                Some(child)
+            }
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_, T> {
+        match self {
+            Children::None => Iter::One(None),
+            Children::One(c, child) => Iter::One(Some((c, child))),
+            Children::Many(v) => Iter::Many(v.iter()),
+        }
+    }
+
+    pub(crate) fn keys(&self) -> impl DoubleEndedIterator<Item = &char> + ExactSizeIterator {
+        self.iter().map(|(c, _)| c)
+    }
+
+    pub(crate) fn values(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.iter().map(|(_, child)| child)
+    }
+
+    pub(crate) fn into_values(self) -> IntoValues<T> {
+        match self {
+            Children::None => IntoValues::One(None),
+            Children::One(_, child) => IntoValues::One(Some(*child)),
+            Children::Many(v) => IntoValues::Many(v.into_iter()),
+        }
+    }
+
+    // The bytes allocated for the children themselves, not counting anything below them.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        match self {
+            Children::None => 0,
+            Children::One(_, _) => mem::size_of::<T>(),
+            Children::Many(v) => v.capacity() * mem::size_of::<(char, T)>(),
+        }
+    }
+}
+
+// The children and their letters in character order.
+pub(crate) enum Iter<'a, T> {
+    One(Option<(&'a char, &'a T)>),
+    Many(slice::Iter<'a, (char, T)>),
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a char, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::One(one) => one.take(),
+            Iter::Many(iter) => iter.next().map(|(c, child)| (c, child)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self {
+            Iter::One(one) => one.is_some() as usize,
+            Iter::Many(iter) => iter.len(),
+        };
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::One(one) => one.take(),
+            Iter::Many(iter) => iter.next_back().map(|(c, child)| (c, child)),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+pub(crate) enum IntoValues<T> {
+    One(Option<T>),
+    Many(alloc::vec::IntoIter<(char, T)>),
+}
+
+impl<T> Iterator for IntoValues<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            IntoValues::One(one) => one.take(),
+            IntoValues::Many(iter) => iter.next().map(|(_, child)| child),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self {
+            IntoValues::One(one) => one.is_some() as usize,
+            IntoValues::Many(iter) => iter.len(),
+        };
+        (len, Some(len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(children: &Children<u32>) -> Vec<char> {
+        children.keys().copied().collect()
+    }
+
+    #[test]
+    fn insert_get_and_remove_in_order() {
+        let mut children: Children<u32> = Children::default();
+        assert!(children.is_empty());
+        assert_eq!(0, children.heap_bytes());
+        *children.get_or_insert_with('m', || 1) += 10;
+        assert!(matches!(children, Children::One('m', _)));
+        assert_eq!(Some(&11), children.get(&'m'));
+        assert_eq!(None, children.get(&'a'));
+        for (value, c) in "zamqb".chars().enumerate() {
+            children.get_or_insert_with(c, || value as u32);
+        }
+        assert_eq!(vec!['a', 'b', 'm', 'q', 'z'], keys(&children));
+        assert_eq!(
+            vec![1, 4, 11, 3, 0],
+            children.values().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!['z', 'q'],
+            children.keys().rev().take(2).copied().collect::<Vec<_>>()
+        );
+        assert_eq!(5, children.len());
+        assert_eq!(5, children.iter().len());
+        *children.get_mut(&'q').unwrap() = 30;
+        assert_eq!(Some(&30), children.get(&'q'));
+        let mut comparisons = 0;
+        assert_eq!(Some(&30), children.get_counting(&'q', &mut comparisons));
+        assert_eq!(None, children.get_counting(&'c', &mut comparisons));
+        // A binary search of five takes no more than four comparisons.
+        assert!((2..=8).contains(&comparisons));
+        assert_eq!(None, children.get_mut(&'c'));
+
+        assert_eq!(None, children.remove(&'c'));
+        for c in ['a', 'q', 'z', 'm'] {
+            assert!(children.remove(&c).is_some());
+        }
+        // The last one goes back into a box of its own.
+        assert!(matches!(children, Children::One('b', _)));
+        assert_eq!(None, children.remove(&'a'));
+        assert_eq!(Some(4), children.remove(&'b'));
+        assert!(children.is_empty());
+        assert_eq!(0, children.iter().count());
+    }
+
+    #[test]
+    fn into_values_in_order() {
+        let mut children: Children<String> = Children::default();
+        assert_eq!(0, mem::take(&mut children).into_values().count());
+        for c in "dbca".chars() {
+            children.get_or_insert_with(c, || c.to_string());
+        }
+        let values: Vec<String> = mem::take(&mut children).into_values().collect();
+        assert_eq!(vec!["a", "b", "c", "d"], values);
+        children.get_or_insert_with('x', || "x".to_owned());
+        assert_eq!(vec!["x"], children.into_values().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,145 @@
+//! Writing a list of words out as Rust source for a `static` table of nodes, for a build script that compiles a
+//! dictionary into a program. The table is queried through `static_trie::StaticLetterTrie`.
+//!
+//! A build script would do something like this, with `letter_trie` as both a dependency and a build dependency:
+//!
+//! ```rust,ignore
+//! // build.rs
+//! let words: Vec<String> = fs::read_to_string("words.txt")?.lines().map(str::to_owned).collect();
+//! let path = Path::new(&env::var("OUT_DIR")?).join("words.rs");
+//! letter_trie::codegen::generate_static_trie(&words, &mut File::create(path)?)?;
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/words.rs"));
+//! pub static WORDS: StaticLetterTrie = StaticLetterTrie::new(&LETTER_TRIE_NODES);
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+use crate::static_trie::PackedNode;
+use crate::*;
+
+/// Write Rust source declaring `pub static LETTER_TRIE_NODES: [letter_trie::static_trie::PackedNode; N]` for the
+/// trie holding `words`. The words are trimmed and lowercased the same way as for any other trie. The source
+/// refers to the crate as `letter_trie`, so the code that includes it needs that as a dependency.
+///
+/// # Errors
+///
+/// Returns any error from writing to `out`.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut source: Vec<u8> = vec![];
+/// letter_trie::codegen::generate_static_trie(&["an".to_owned(), "at".to_owned()], &mut source).unwrap();
+/// let source = String::from_utf8(source).unwrap();
+/// assert!(source.contains("[letter_trie::static_trie::PackedNode; 4]"));
+/// assert!(source.contains("PackedNode::new('t', true, 4, 0),"));
+/// ```
+pub fn generate_static_trie<W: Write>(words: &[String], out: &mut W) -> io::Result<()> {
+    let nodes = pack(words);
+    writeln!(
+        out,
+        "// Written by letter_trie::codegen::generate_static_trie() for {} words.",
+        words.len()
+    )?;
+    writeln!(
+        out,
+        "pub static LETTER_TRIE_NODES: [letter_trie::static_trie::PackedNode; {}] = [",
+        nodes.len()
+    )?;
+    for node in &nodes {
+        writeln!(
+            out,
+            "    letter_trie::static_trie::PackedNode::new({:?}, {}, {}, {}),",
+            node.c, node.is_word, node.first_child, node.child_count
+        )?;
+    }
+    writeln!(out, "];")
+}
+
+// Lay the trie out breadth first so that the children of each node are next to each other.
+fn pack(words: &[String]) -> Vec<PackedNode> {
+    let t = NoParentLetterTrie::from_words(words);
+    // Each node in visit() order, with the visit() order index of each of its children.
+    let mut nodes: Vec<(char, bool, Vec<usize>)> = vec![];
+    let mut path: Vec<usize> = vec![];
+    t.visit("", &mut |node| {
+        path.truncate(node.depth);
+        let index = nodes.len();
+        if let Some(parent) = path.last() {
+            nodes[*parent].2.push(index);
+        }
+        nodes.push((node.c, node.is_word, vec![]));
+        path.push(index);
+        true
+    });
+
+    let mut packed: Vec<PackedNode> = Vec::with_capacity(nodes.len());
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    queue.push_back(0);
+    // The children of the nodes queued so far will start here.
+    let mut next_index = 1;
+    while let Some(index) = queue.pop_front() {
+        let (c, is_word, children) = &nodes[index];
+        packed.push(PackedNode::new(
+            *c,
+            *is_word,
+            next_index as u32,
+            children.len() as u32,
+        ));
+        next_index += children.len();
+        queue.extend(children);
+    }
+    packed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::static_trie::StaticLetterTrie;
+
+    fn static_trie(words: &[String]) -> StaticLetterTrie {
+        StaticLetterTrie::new(Box::leak(pack(words).into_boxed_slice()))
+    }
+
+    #[test]
+    fn matches_base_trie() {
+        let words: Vec<String> = generator::generate_words(2_000, 1, 9, 3)
+            .into_iter()
+            .chain(vec![
+                "Ünïcödé".to_owned(),
+                "  padded  ".to_owned(),
+                "ΟΔΟΣ".to_owned(),
+            ])
+            .collect();
+        let t = BaseLetterTrie::from_words(&words);
+        let s = static_trie(&words);
+        assert_eq!(t.to_fixed_node(), s.to_fixed_node());
+        assert_eq!(t.to_fixed_node().node_count, s.node_count());
+        assert_eq!(t.words_with_prefix(""), s.words_with_prefix(""));
+        for prefix in &["a", "bc", "Pad", "ü", "zzzzzzzzzz", ""] {
+            assert_eq!(t.find(prefix), s.find(prefix), "{:?}", prefix);
+            assert_eq!(t.words_with_prefix(prefix), s.words_with_prefix(prefix));
+        }
+        for word in words.iter().chain(&["nope".to_owned(), "οδος".to_owned()]) {
+            assert_eq!(t.contains(word), s.contains(word), "{:?}", word);
+            assert_eq!(t.contains(word.trim()), s.contains(word.trim()));
+        }
+    }
+
+    #[test]
+    fn empty_and_escaped() {
+        let s = static_trie(&[]);
+        assert_eq!(1, s.node_count());
+        assert!(s.words_with_prefix("").is_empty());
+        assert!(!s.contains("a"));
+
+        let mut source: Vec<u8> = vec![];
+        generate_static_trie(&["it's".to_owned()], &mut source).unwrap();
+        let source = String::from_utf8(source).unwrap();
+        assert!(source.contains("PackedNode::new('\\'', false, 4, 1),"));
+        assert!(source.contains("[letter_trie::static_trie::PackedNode; 5]"));
+    }
+}
@@ -0,0 +1,426 @@
+// The splitting behind LetterTrie::split_points() and LetterTrie::compound_decompositions(), for compound words
+// like the German "Arbeitsplatz" or the Dutch "fietspad".
+//
+// The words that start at some position of the token are found with one walk down the trie along the token's
+// letters, noting each depth where a word ends. For two parts the rest of the token after each of those is
+// checked with contains(). For more, the walk is made once from each position that a part can start at, and the
+// decompositions are the ways of getting from the start of the token to its end through those words.
+//
+// The token is lowercased one character at a time to walk the trie, and a split can only go between two of its
+// original characters, so that the indices and the parts always line up with the token as it was given.
+
+use crate::*;
+
+// The fewest characters in a part when there's no other limit.
+pub(crate) const DEFAULT_MIN_PART_LEN: usize = 2;
+
+struct Token {
+    // The token lowercased, one entry for each character of the lowercase form.
+    lower: Vec<char>,
+    // For each position in lower, including the end, the byte index in word and the number of characters of word
+    // before it, or None if it's partway through the lowercase form of one character.
+    boundaries: Vec<Option<(usize, usize)>>,
+    char_count: usize,
+}
+
+impl Token {
+    fn new(word: &str) -> Self {
+        let mut lower = vec![];
+        let mut boundaries = vec![];
+        let mut char_count = 0;
+        for (byte_index, c) in word.char_indices() {
+            boundaries.push(Some((byte_index, char_count)));
+            for (i, lower_c) in c.to_lowercase().enumerate() {
+                if i > 0 {
+                    boundaries.push(None);
+                }
+                lower.push(lower_c);
+            }
+            char_count += 1;
+        }
+        boundaries.push(Some((word.len(), char_count)));
+        Self {
+            lower,
+            boundaries,
+            char_count,
+        }
+    }
+
+    // The positions in lower where a word that starts at start ends, in order, leaving out any that aren't on a
+    // boundary or that make a part shorter than min_part_len characters.
+    fn word_ends_from<T: LetterTrie + ?Sized>(
+        &self,
+        trie: &T,
+        start: usize,
+        min_part_len: usize,
+    ) -> Vec<usize> {
+        let mut ends = vec![];
+        let start_chars = match self.boundaries[start] {
+            Some((_, chars)) => chars,
+            None => return ends,
+        };
+        let letters = &self.lower[start..];
+        trie.visit("", &mut |node| {
+            if node.depth == 0 {
+                return true;
+            }
+            if node.depth > letters.len() || node.c != letters[node.depth - 1] {
+                return false;
+            }
+            if node.is_word {
+                let end = start + node.depth;
+                if let Some((_, chars)) = self.boundaries[end] {
+                    if chars - start_chars >= min_part_len {
+                        ends.push(end);
+                    }
+                }
+            }
+            true
+        });
+        ends
+    }
+
+    // The positions passed to these come from word_ends_from() or are 0, so they're always on a boundary.
+    #[allow(clippy::unwrap_used)]
+    fn byte_index(&self, position: usize) -> usize {
+        self.boundaries[position].unwrap().0
+    }
+
+    #[allow(clippy::unwrap_used)]
+    fn chars_after(&self, position: usize) -> usize {
+        self.char_count - self.boundaries[position].unwrap().1
+    }
+}
+
+pub(crate) fn split_points<T: LetterTrie + ?Sized>(
+    trie: &T,
+    word: &str,
+    min_part_len: usize,
+) -> Vec<usize> {
+    let min_part_len = min_part_len.max(1);
+    let token = Token::new(word);
+    if token.char_count < 2 * min_part_len {
+        return vec![];
+    }
+    token
+        .word_ends_from(trie, 0, min_part_len)
+        .into_iter()
+        .filter(|end| *end < token.lower.len() && token.chars_after(*end) >= min_part_len)
+        .map(|end| token.byte_index(end))
+        .filter(|byte_index| trie.contains(&word[*byte_index..]))
+        .collect()
+}
+
+pub(crate) fn compound_decompositions<T: LetterTrie + ?Sized>(
+    trie: &T,
+    word: &str,
+    max_parts: usize,
+    min_part_len: usize,
+) -> Vec<Vec<String>> {
+    let min_part_len = min_part_len.max(1);
+    let token = Token::new(word);
+    let mut decompositions = vec![];
+    if max_parts < 2 || token.char_count < 2 * min_part_len {
+        return decompositions;
+    }
+    // The ends of the words from each position, filled in the first time a part starts there.
+    let mut ends_from: Vec<Option<Vec<usize>>> = vec![None; token.lower.len()];
+    // The end of each part so far, with the index of the next end to try after it.
+    let mut stack: Vec<(usize, usize)> = vec![];
+    let mut start = 0;
+    let mut next_index = 0;
+    loop {
+        let ends =
+            ends_from[start].get_or_insert_with(|| token.word_ends_from(trie, start, min_part_len));
+        match ends.get(next_index) {
+            Some(&end) => {
+                next_index += 1;
+                let part_count = stack.len() + 1;
+                if end == token.lower.len() {
+                    if part_count >= 2 {
+                        let mut parts = vec![];
+                        let mut part_start = 0;
+                        for &(part_end, _) in stack.iter().chain(Some(&(end, 0))) {
+                            parts.push(
+                                word[token.byte_index(part_start)..token.byte_index(part_end)]
+                                    .to_owned(),
+                            );
+                            part_start = part_end;
+                        }
+                        decompositions.push(parts);
+                    }
+                } else if part_count < max_parts && token.chars_after(end) >= min_part_len {
+                    stack.push((end, next_index));
+                    start = end;
+                    next_index = 0;
+                }
+            }
+            None => match stack.pop() {
+                Some((_, index)) => {
+                    start = stack.last().map(|(end, _)| *end).unwrap_or(0);
+                    next_index = index;
+                }
+                None => break,
+            },
+        }
+    }
+    decompositions
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use crate::generator::SplitMix64;
+    use test::Bencher;
+
+    fn fixture<T: LetterTrie>() -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/compounds/words.txt");
+        T::from_words(words_from_file(path.to_str().unwrap()).unwrap())
+    }
+
+    // Every split of word into two stored words at a character boundary, found by trying each one.
+    fn brute_force_split_points<T: LetterTrie>(
+        t: &T,
+        word: &str,
+        min_part_len: usize,
+    ) -> Vec<usize> {
+        let char_count = word.chars().count();
+        word.char_indices()
+            .enumerate()
+            .filter(|(chars, _)| *chars >= min_part_len && char_count - chars >= min_part_len)
+            .map(|(_, (byte_index, _))| byte_index)
+            .filter(|i| t.contains(&word[..*i]) && t.contains(&word[*i..]))
+            .collect()
+    }
+
+    fn brute_force_decompositions<T: LetterTrie>(
+        t: &T,
+        word: &str,
+        max_parts: usize,
+        min_part_len: usize,
+    ) -> Vec<Vec<String>> {
+        if max_parts < 2 {
+            return vec![];
+        }
+        let mut decompositions = vec![];
+        for (i, _) in word.char_indices().skip(min_part_len) {
+            let rest = &word[i..];
+            if rest.chars().count() < min_part_len || !t.contains(&word[..i]) {
+                continue;
+            }
+            if t.contains(rest) {
+                decompositions.push(vec![word[..i].to_owned(), rest.to_owned()]);
+            }
+            for mut tail in brute_force_decompositions(t, rest, max_parts - 1, min_part_len) {
+                tail.insert(0, word[..i].to_owned());
+                decompositions.push(tail);
+            }
+        }
+        decompositions.sort_by_key(|parts| parts.iter().map(|part| part.len()).collect::<Vec<_>>());
+        decompositions
+    }
+
+    fn parts(decompositions: &[&[&str]]) -> Vec<Vec<String>> {
+        decompositions
+            .iter()
+            .map(|parts| parts.iter().map(|part| part.to_string()).collect())
+            .collect()
+    }
+
+    fn check_fixture<T: LetterTrie>() {
+        let t: T = fixture();
+
+        // The word is in the dictionary itself, but that isn't a split.
+        assert_eq!(vec![7], t.split_points("Arbeitsplatz"));
+        assert_eq!(
+            parts(&[&["Arbeits", "platz"]]),
+            t.compound_decompositions("Arbeitsplatz", 3)
+        );
+        // The indices are bytes, and "tür" takes four.
+        assert_eq!(vec![4], t.split_points("Haustür"));
+        assert_eq!(vec![4], t.split_points("türschlüssel"));
+        assert_eq!("schlüssel", &"türschlüssel"[4..]);
+        assert_eq!(
+            parts(&[&["haus", "tür", "schlüssel"]]),
+            t.compound_decompositions("haustürschlüssel", 3)
+        );
+        assert!(t.compound_decompositions("haustürschlüssel", 2).is_empty());
+        assert_eq!(vec![5], t.split_points("fietspad"));
+
+        // "en" is only long enough with the default of two characters.
+        assert_eq!(vec![6], t.split_points("ziekenhuis"));
+        assert_eq!(
+            parts(&[&["ziek", "en", "huis"], &["zieken", "huis"]]),
+            t.compound_decompositions("ziekenhuis", 3)
+        );
+        assert_eq!(
+            parts(&[&["zieken", "huis"]]),
+            t.compound_decompositions_with_min_len("ziekenhuis", 3, 3)
+        );
+        assert_eq!(vec![6], t.split_points_with_min_len("ziekenhuis", 3));
+
+        // A letter that could go with either part.
+        assert_eq!(vec![6], t.split_points("kaffeeersatz"));
+        assert_eq!(vec![3], t.split_points("teeei"));
+        assert_eq!(vec![3], t.split_points("bettuch"));
+        assert_eq!(
+            parts(&[&["bet", "tuch"]]),
+            t.compound_decompositions("bettuch", 2)
+        );
+
+        for word in ["", "a", "haus", "hausx", "xhaus", "zeit"] {
+            assert!(t.split_points(word).is_empty(), "{}", word);
+            assert!(t.compound_decompositions(word, 4).is_empty(), "{}", word);
+        }
+        assert!(t.compound_decompositions("haustür", 1).is_empty());
+        assert!(t.compound_decompositions("haustür", 0).is_empty());
+    }
+
+    #[test]
+    fn fixture_compounds() {
+        check_fixture::<BaseLetterTrie>();
+        check_fixture::<NoParentLetterTrie>();
+    }
+
+    fn check_repeated_letters<T: LetterTrie>() {
+        let t = T::from_words(vec!["a", "aa", "aaa", "aaaa", "aaaaa"]);
+        assert_eq!(
+            vec![1, 2, 3, 4, 5],
+            t.split_points_with_min_len("aaaaaa", 1)
+        );
+        assert_eq!(vec![3], t.split_points_with_min_len("aaaaaa", 3));
+        assert_eq!(vec![2, 3, 4], t.split_points("aaaaaa"));
+        let decompositions = t.compound_decompositions_with_min_len("aaaaaa", 6, 1);
+        // Every way of writing 6 as a sum except 6 itself.
+        assert_eq!(31, decompositions.len());
+        assert!(decompositions
+            .iter()
+            .all(|parts| parts.concat() == "aaaaaa" && parts.len() >= 2));
+        assert_eq!(
+            parts(&[&["a", "a", "a", "a", "a", "a"], &["a", "a", "a", "a", "aa"]]),
+            decompositions[..2].to_vec()
+        );
+        assert_eq!(
+            parts(&[
+                &["aa", "aa", "aa"],
+                &["aa", "aaaa"],
+                &["aaa", "aaa"],
+                &["aaaa", "aa"]
+            ]),
+            t.compound_decompositions("aaaaaa", 6)
+        );
+    }
+
+    #[test]
+    fn repeated_letters() {
+        check_repeated_letters::<BaseLetterTrie>();
+        check_repeated_letters::<NoParentLetterTrie>();
+    }
+
+    // A character whose lowercase form is longer than one character can't have a split in the middle of it.
+    fn check_long_lowercase<T: LetterTrie>() {
+        // "İ" lowercases to "i" and a combining dot.
+        let t = T::from_words(vec!["i", "\u{307}xab", "i\u{307}x", "cd"]);
+        assert!(t.split_points_with_min_len("İxab", 1).is_empty());
+        assert_eq!(vec![3], t.split_points_with_min_len("İxcd", 1));
+        assert_eq!(
+            parts(&[&["İx", "cd"]]),
+            t.compound_decompositions_with_min_len("İxcd", 2, 1)
+        );
+    }
+
+    #[test]
+    fn long_lowercase() {
+        check_long_lowercase::<BaseLetterTrie>();
+        check_long_lowercase::<NoParentLetterTrie>();
+    }
+
+    // Tokens made of one to three words from the dataset, sometimes with a letter more or less.
+    fn tokens(words: &[String], count: usize, seed: u64) -> Vec<String> {
+        let mut rng = SplitMix64(seed);
+        (0..count)
+            .map(|_| {
+                let part_count = 1 + rng.below(3);
+                let mut token: String = (0..part_count)
+                    .map(|_| words[rng.below(words.len() as u64) as usize].as_str())
+                    .collect();
+                match rng.below(4) {
+                    0 => token.push('s'),
+                    1 => {
+                        token.pop();
+                    }
+                    _ => {}
+                }
+                token
+            })
+            .collect()
+    }
+
+    fn check_brute_force<T: LetterTrie>() {
+        let words: Vec<String> = words_from_file(Dataset::TestMediumUnsorted.filename())
+            .unwrap()
+            .into_iter()
+            .filter(|word| word.chars().count() <= 6)
+            .collect();
+        let t = T::from_words(&words);
+        for token in tokens(&words, 300, 23) {
+            for min_part_len in [1, 2, 4] {
+                assert_eq!(
+                    brute_force_split_points(&t, &token, min_part_len),
+                    t.split_points_with_min_len(&token, min_part_len),
+                    "{} {}",
+                    token,
+                    min_part_len
+                );
+                assert_eq!(
+                    brute_force_decompositions(&t, &token, 3, min_part_len),
+                    t.compound_decompositions_with_min_len(&token, 3, min_part_len),
+                    "{} {}",
+                    token,
+                    min_part_len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        check_brute_force::<BaseLetterTrie>();
+        check_brute_force::<NoParentLetterTrie>();
+    }
+
+    fn large_tokens() -> (NoParentLetterTrie, Vec<String>) {
+        let t = NoParentLetterTrie::from_dataset(
+            &Dataset::TestLargeSorted,
+            &LoadMethod::ContinuousParallel,
+        )
+        .unwrap();
+        let words: Vec<String> = t.words().filter(|word| word.len() <= 8).collect();
+        let tokens = tokens(&words, 10_000, 5);
+        (t, tokens)
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_split_points_large(b: &mut Bencher) {
+        let (t, tokens) = large_tokens();
+        b.iter(|| {
+            tokens
+                .iter()
+                .map(|token| t.split_points(token).len())
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_compound_decompositions_large(b: &mut Bencher) {
+        let (t, tokens) = large_tokens();
+        b.iter(|| {
+            tokens
+                .iter()
+                .map(|token| t.compound_decompositions(token, 3).len())
+                .sum::<usize>()
+        });
+    }
+}
@@ -0,0 +1,924 @@
+//! A shared set of checks that every implementation of `LetterTrie` should pass, so that a new implementation or
+//! load method gets the same coverage as the existing ones without another hand-written test matrix.
+//!
+//! Call `run_conformance()` directly or generate a set of `#[test]` functions with
+//! `letter_trie_conformance_tests!`. `run_byte_conformance()` runs the same checks for a `ByteLetterTrie`.
+//!
+//! `compare_load_methods()` builds one word file every way there is and reports the first node where the builds
+//! differ, for finding out which load method gets an unusual file wrong.
+
+// Like a test, a check here panics to report what it found, so unwrapping is the point rather than a risk.
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use std::any;
+use std::collections::BTreeSet;
+use std::fs;
+use std::iter;
+
+use crate::*;
+
+/// Build tries of type `T` from `dataset` and panic with a description of the problem if any of these fail:
+/// - Every word in the dataset's file is found, with `is_word` true.
+/// - Every entry of `VerificationSet::NonWords` that isn't in the file is not a word in the trie.
+/// - Every node, as `find()` gives it, matches a node worked out directly from the file, field for field as
+///   described on `FixedNode`, and the trie has no other nodes.
+/// - Every `LoadMethod` produces a trie with the same fingerprint.
+/// - The same words sorted and in reverse order produce that same fingerprint with every `LoadMethod`, with and
+///   without read-ahead, whether the last word is followed by a newline, by blank or whitespace-only lines, or by
+///   nothing at all.
+///
+/// The dataset is checked with `Dataset::verify()` first so that a damaged file isn't taken for a broken trie.
+///
+/// # Panics
+///
+/// Panics if a check fails, or if the dataset's file can't be read or doesn't pass `Dataset::verify()`. The
+/// non-words are the copy built into the crate.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// conformance::run_conformance::<NoParentLetterTrie>(&Dataset::EmbeddedSmall);
+/// ```
+pub fn run_conformance<T: LetterTrie>(dataset: &Dataset) {
+    let type_name = any::type_name::<T>();
+    verify_dataset(dataset);
+    let lines = read_lines(dataset);
+    let words: BTreeSet<&str> = lines.iter().map(|line| line.as_str()).collect();
+    let expected = reference_nodes(&words);
+
+    let mut fingerprint: Option<u64> = None;
+    for load_method in &ALL_LOAD_METHODS {
+        let t = T::from_dataset(dataset, load_method)
+            .unwrap_or_else(|err| panic!("{}: error loading {:?}: {}", type_name, dataset, err));
+        let description = format!(
+            "{} loaded from {:?} with {:?}",
+            type_name, dataset, load_method
+        );
+        check_nodes(&t, &expected, &description);
+
+        match fingerprint {
+            None => {
+                check_words(&t, &words, type_name);
+                fingerprint = Some(t.fingerprint());
+            }
+            Some(fingerprint) => assert_eq!(
+                fingerprint,
+                t.fingerprint(),
+                "{} loaded from {:?}: {:?} produced a different trie than {:?}.",
+                type_name,
+                dataset,
+                load_method,
+                ALL_LOAD_METHODS[0]
+            ),
+        }
+    }
+    let fingerprint = fingerprint.unwrap();
+
+    let mut sorted_lines = lines.clone();
+    sorted_lines.sort_unstable();
+    let mut reversed_lines = sorted_lines.clone();
+    reversed_lines.reverse();
+    for (lines, is_sorted) in &[(sorted_lines, true), (reversed_lines, false)] {
+        for ending in &FILE_ENDINGS {
+            let content = format!("{}{}", lines.join("\n"), ending);
+            for load_method in &ALL_LOAD_METHODS {
+                for read_ahead_lines in [0, 2] {
+                    let opt = DisplayDetailOptions::make_no_display()
+                        .with_read_ahead_lines(read_ahead_lines);
+                    // Only the continuous load methods read ahead.
+                    let is_continuous = matches!(
+                        load_method,
+                        LoadMethod::Continuous | LoadMethod::ContinuousParallel
+                    );
+                    if read_ahead_lines > 0 && !is_continuous {
+                        continue;
+                    }
+                    let t = T::from_reader_test(
+                        content.as_bytes(),
+                        *is_sorted,
+                        load_method,
+                        &opt,
+                        None,
+                    )
+                    .expect("Error reading words from memory.");
+                    assert_eq!(
+                        fingerprint,
+                        t.fingerprint(),
+                        "{} from the words in {:?} ending in {:?} with is_sorted = {}, {:?} and {} \
+                        lines of read-ahead doesn't match the file.",
+                        type_name,
+                        dataset,
+                        ending,
+                        is_sorted,
+                        load_method,
+                        read_ahead_lines
+                    );
+                }
+            }
+        }
+    }
+}
+
+// The ways the words of a file might be followed at the end of the input. The last word has to be loaded the same
+// way after each of them, including by the load methods that have a group of words left to finish when the input
+// runs out.
+const FILE_ENDINGS: [&str; 5] = ["", "\n", "\n\n", "\n  \n\t\n   ", " \t"];
+
+/// Build a `ByteLetterTrie` from the words in `dataset`, which must all be ASCII, and panic with a description of
+/// the problem if it doesn't pass the checks in `run_conformance()` or doesn't agree with a `BaseLetterTrie` of
+/// the same words node for node. The words are trimmed and lowercased first, since the byte trie keeps them as they
+/// are.
+///
+/// # Panics
+///
+/// Panics if a check fails, if a word isn't ASCII, or if the dataset's file can't be read or doesn't pass
+/// `Dataset::verify()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// conformance::run_byte_conformance(&Dataset::EmbeddedSmall);
+/// ```
+pub fn run_byte_conformance(dataset: &Dataset) {
+    verify_dataset(dataset);
+    let lines = read_lines(dataset);
+    if let Some(line) = lines.iter().find(|line| !line.is_ascii()) {
+        panic!("{:?} has a word that isn't ASCII: \"{}\"", dataset, line);
+    }
+    let words: BTreeSet<&str> = lines.iter().map(|line| line.as_str()).collect();
+    let expected = reference_root(&words);
+    let letters = BaseLetterTrie::from_dataset(dataset, &LoadMethod::Continuous)
+        .unwrap_or_else(|err| panic!("Error loading {:?}: {}", dataset, err));
+    let expected_nodes = letter_nodes(&letters);
+
+    let mut sorted_lines = lines.clone();
+    sorted_lines.sort_unstable();
+    let mut reversed_lines = sorted_lines.clone();
+    reversed_lines.reverse();
+    for (order, lines) in &[
+        ("file", &lines),
+        ("sorted", &sorted_lines),
+        ("reversed", &reversed_lines),
+    ] {
+        let t = ByteLetterTrie::from_reader(lines.join("\n").as_bytes())
+            .expect("Error reading words from memory.");
+        assert_eq!(
+            expected,
+            t.to_fixed_node().to_letter_node(),
+            "ByteLetterTrie from the words in {:?} in {} order: the root doesn't match the file.",
+            dataset,
+            order
+        );
+        assert_eq!(
+            expected_nodes,
+            byte_nodes(&t),
+            "ByteLetterTrie from the words in {:?} in {} order: the nodes don't match BaseLetterTrie.",
+            dataset,
+            order
+        );
+    }
+
+    let t = ByteLetterTrie::from_bytes_iter(&lines);
+    for word in &words {
+        assert!(
+            t.contains(word.as_bytes()),
+            "ByteLetterTrie: contains(b\"{}\") is false.",
+            word
+        );
+        assert_eq!(
+            letters.find(word),
+            t.find(word.as_bytes()).map(|node| node.to_letter_node()),
+            "ByteLetterTrie: find(b\"{}\") doesn't match BaseLetterTrie.",
+            word
+        );
+    }
+    let non_words = verification_words_embedded(VerificationSet::NonWords);
+    for non_word in non_words {
+        let non_word = non_word.to_lowercase();
+        assert_eq!(
+            letters.contains(&non_word),
+            t.contains(non_word.as_bytes()),
+            "ByteLetterTrie: contains(b\"{}\") doesn't match BaseLetterTrie.",
+            non_word
+        );
+    }
+}
+
+/// The result of `compare_load_methods()`: what each build of a file came out as, and where the first one that
+/// differs from the rest parts ways with them.
+///
+/// The `Display` output is one line saying the builds agree, or the prefix where they differ followed by one line
+/// for each build with its node there, which is meant to be enough to start looking for the bug.
+#[derive(Debug, PartialEq)]
+pub struct ComparisonReport {
+    pub path: PathBuf,
+    /// Whether the words in the file are sorted by their first letter, which is passed to every build as
+    /// `is_sorted` so that the sorted paths are checked when they're allowed.
+    pub is_sorted: bool,
+    /// Each build in the order of the load methods given, with `LetterTrieType::all()` for each method.
+    pub builds: Vec<LoadBuild>,
+    /// None if every build has the same nodes.
+    pub mismatch: Option<LoadMismatch>,
+}
+
+/// One build of the file in a `ComparisonReport`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadBuild {
+    /// The `Display` name of the load method such as "continuous-parallel".
+    pub load_method: String,
+    pub letter_trie_type: LetterTrieType,
+    pub fingerprint: u64,
+    pub word_count: usize,
+    pub node_count: usize,
+}
+
+/// Where the builds in a `ComparisonReport` first differ.
+#[derive(Debug, PartialEq)]
+pub struct LoadMismatch {
+    /// The first prefix in the order of `visit()` whose node isn't the same in every build.
+    pub prefix: String,
+    /// The node for `prefix` in each build, in the order of `ComparisonReport::builds`, or None for a build that
+    /// doesn't have the prefix.
+    pub nodes: Vec<Option<FixedNode>>,
+}
+
+impl ComparisonReport {
+    /// Returns true if every build has the same nodes.
+    pub fn is_consistent(&self) -> bool {
+        self.mismatch.is_none()
+    }
+
+    /// Panic with the report if the builds don't all have the same nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `is_consistent()` is false.
+    pub fn assert_consistent(&self) {
+        assert!(self.is_consistent(), "{}", self);
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.mismatch {
+            None => {
+                write!(
+                    f,
+                    "{} builds of {} agree",
+                    self.builds.len(),
+                    self.path.display()
+                )?;
+                if let Some(build) = self.builds.first() {
+                    write!(
+                        f,
+                        ": {} words, {} nodes, fingerprint {:016x}",
+                        build.word_count, build.node_count, build.fingerprint
+                    )?;
+                }
+                writeln!(f)
+            }
+            Some(mismatch) => {
+                writeln!(
+                    f,
+                    "The builds of {} (is_sorted = {}) differ first at the prefix {:?}:",
+                    self.path.display(),
+                    self.is_sorted,
+                    mismatch.prefix
+                )?;
+                for (build, node) in self.builds.iter().zip(&mismatch.nodes) {
+                    write!(
+                        f,
+                        "    {} with {}: {} words, {} nodes, fingerprint {:016x}, ",
+                        build.letter_trie_type,
+                        build.load_method,
+                        build.word_count,
+                        build.node_count,
+                        build.fingerprint
+                    )?;
+                    match node {
+                        Some(node) => writeln!(f, "{:?}", node)?,
+                        None => writeln!(f, "no node")?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Build a trie from the word file at `path` with each of `load_methods` and each implementation, and compare
+/// every node of every build, as `find()` gives it, with the same node in the others. This is for tracking down a
+/// load method that builds something different from the rest on an unusual file, such as one with CRLF line
+/// endings, blank lines at the end, or no newline after the last word.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::File` with the path if the file can't be opened or read, or any other error from a
+/// build.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// Dataset::EmbeddedSmall.materialize()?;
+/// let report = conformance::compare_load_methods(&Dataset::EmbeddedSmall.path(), LoadMethod::all())?;
+/// assert!(report.is_consistent());
+/// assert_eq!(8, report.builds.len());
+/// assert!(report.builds.iter().all(|build| build.word_count == 10));
+/// # Ok::<(), LetterTrieError>(())
+/// ```
+pub fn compare_load_methods(path: &Path, load_methods: &[LoadMethod]) -> Result<ComparisonReport> {
+    let is_sorted = is_sorted_by_first_letter(&fs::read(path).map_err(in_file(path))?);
+    let mut builds = vec![];
+    let mut snapshots = vec![];
+    for load_method in load_methods {
+        for letter_trie_type in LetterTrieType::all() {
+            let (build, nodes) = match letter_trie_type {
+                LetterTrieType::Base => build_from_path::<BaseLetterTrie>(
+                    path,
+                    is_sorted,
+                    load_method,
+                    letter_trie_type,
+                )?,
+                LetterTrieType::NoParent => build_from_path::<NoParentLetterTrie>(
+                    path,
+                    is_sorted,
+                    load_method,
+                    letter_trie_type,
+                )?,
+            };
+            builds.push(build);
+            snapshots.push(nodes);
+        }
+    }
+    Ok(ComparisonReport {
+        path: path.to_owned(),
+        is_sorted,
+        builds,
+        mismatch: first_mismatch(snapshots),
+    })
+}
+
+fn build_from_path<T: LetterTrie>(
+    path: &Path,
+    is_sorted: bool,
+    load_method: &LoadMethod,
+    letter_trie_type: &LetterTrieType,
+) -> Result<(LoadBuild, Vec<FixedNode>)> {
+    let opt = DisplayDetailOptions::make_no_display();
+    let file = File::open(path).map_err(in_file(path))?;
+    let t = opt
+        .with_file_hints(&file, || {
+            T::from_reader_test(BufReader::new(&file), is_sorted, load_method, &opt, None)
+        })
+        .map_err(in_file(path))?;
+    let root = t.to_fixed_node();
+    let build = LoadBuild {
+        load_method: load_method.to_string(),
+        letter_trie_type: *letter_trie_type,
+        fingerprint: t.fingerprint(),
+        word_count: root.word_count,
+        node_count: root.node_count,
+    };
+    Ok((build, node_snapshots(&t)))
+}
+
+// Whether the first letters of the words in the file never go backward, as the loaders see the words. A file that
+// isn't UTF-8 is taken as unsorted and left for the builds to report.
+fn is_sorted_by_first_letter(content: &[u8]) -> bool {
+    let content = match std::str::from_utf8(content) {
+        Ok(content) => content,
+        Err(_) => return false,
+    };
+    let first_letters: Vec<char> = content
+        .lines()
+        .filter_map(|line| line.trim().to_lowercase().chars().next())
+        .collect();
+    first_letters.windows(2).all(|pair| pair[0] <= pair[1])
+}
+
+// The first prefix in visit() order where the node lists don't all agree, with each list's node for it. Each list
+// is sorted by prefix, so the smallest prefix among the nodes at the first index that differs is the first one
+// that some list has differently or not at all.
+fn first_mismatch(snapshots: Vec<Vec<FixedNode>>) -> Option<LoadMismatch> {
+    let first = snapshots.first()?;
+    let len = snapshots.iter().map(Vec::len).max().unwrap_or(0);
+    let index = (0..len).find(|index| {
+        snapshots
+            .iter()
+            .any(|nodes| nodes.get(*index) != first.get(*index))
+    })?;
+    let prefix = snapshots
+        .iter()
+        .filter_map(|nodes| nodes.get(index))
+        .map(|node| node.prefix.as_str())
+        .min()
+        .unwrap()
+        .to_owned();
+    let nodes = snapshots
+        .into_iter()
+        .map(|nodes| nodes.into_iter().find(|node| node.prefix == prefix))
+        .collect();
+    Some(LoadMismatch { prefix, nodes })
+}
+
+// Each node in visit() order as its letter, depth and word flag.
+fn letter_nodes<T: LetterTrie>(t: &T) -> Vec<(char, usize, bool)> {
+    let mut nodes = vec![];
+    t.visit("", &mut |node| {
+        nodes.push((node.c, node.depth, node.is_word));
+        true
+    });
+    nodes
+}
+
+fn byte_nodes(t: &ByteLetterTrie) -> Vec<(char, usize, bool)> {
+    let mut nodes = vec![];
+    t.visit(b"", &mut |node| {
+        nodes.push((
+            node.symbol.map(char::from).unwrap_or(' '),
+            node.depth,
+            node.is_word,
+        ));
+        true
+    });
+    nodes
+}
+
+fn verify_dataset(dataset: &Dataset) {
+    if let Err(err) = dataset.verify() {
+        panic!(
+            "{:?} can't be used for the conformance checks. {}",
+            dataset, err
+        );
+    }
+}
+
+// The words in the file the way the loaders see them: trimmed, lowercase, and skipping empty lines.
+fn read_lines(dataset: &Dataset) -> Vec<String> {
+    read_dataset_words(dataset)
+        .expect("Error reading file.")
+        .into_iter()
+        .map(|line| line.to_lowercase())
+        .collect()
+}
+
+// The root that a correct trie would have.
+fn reference_root(words: &BTreeSet<&str>) -> FixedNode {
+    reference_nodes(words).swap_remove(0)
+}
+
+// The nodes that a correct trie would have in visit() order, worked out from the set of prefixes of the words.
+// Sorting the prefixes puts each one right before the prefixes that extend it, which are the rest of its subtree.
+fn reference_nodes(words: &BTreeSet<&str>) -> Vec<FixedNode> {
+    let mut prefixes: BTreeSet<&str> = BTreeSet::new();
+    for word in words {
+        for (i, c) in word.char_indices() {
+            prefixes.insert(&word[..i + c.len_utf8()]);
+        }
+    }
+    // The root is a node too, with the empty prefix.
+    let prefixes: Vec<(&str, usize)> = iter::once("")
+        .chain(prefixes)
+        .map(|prefix| (prefix, prefix.chars().count()))
+        .collect();
+    prefixes
+        .iter()
+        .enumerate()
+        .map(|(i, (prefix, depth))| {
+            let subtree = prefixes[i..]
+                .iter()
+                .take_while(|(other, _)| other.starts_with(prefix));
+            let mut node = FixedNode {
+                c: prefix.chars().last().unwrap_or(' '),
+                prefix: (*prefix).to_owned(),
+                depth: *depth,
+                is_word: words.contains(prefix),
+                child_count: 0,
+                node_count: 0,
+                word_count: 0,
+                height: 0,
+            };
+            for (other, other_depth) in subtree {
+                if *other_depth == depth + 1 {
+                    node.child_count += 1;
+                }
+                node.node_count += 1;
+                if words.contains(other) {
+                    node.word_count += 1;
+                }
+                // The height counts this node's level as well as each level below it.
+                node.height = node.height.max(other_depth - depth + 1);
+            }
+            node
+        })
+        .collect()
+}
+
+// Every node of the trie in visit() order: the root from to_fixed_node() and the rest from find().
+fn node_snapshots<T: LetterTrie>(t: &T) -> Vec<FixedNode> {
+    let mut prefixes: Vec<String> = vec![];
+    t.visit("", &mut |node| {
+        prefixes.push(node.prefix.to_owned());
+        true
+    });
+    let mut nodes = vec![t.to_fixed_node()];
+    nodes.extend(prefixes.iter().skip(1).map(|prefix| {
+        t.find(prefix)
+            .unwrap_or_else(|| panic!("find(\"{}\") is None.", prefix))
+    }));
+    nodes
+}
+
+fn check_nodes<T: LetterTrie>(t: &T, expected: &[FixedNode], description: &str) {
+    let nodes = node_snapshots(t);
+    if let Some((node, expected_node)) = nodes.iter().zip(expected).find(|(a, b)| a != b) {
+        panic!(
+            "{}: the node for \"{}\" doesn't match the file.\nExpected: {:?}\nActual: {:?}",
+            description, expected_node.prefix, expected_node, node
+        );
+    }
+    assert_eq!(
+        expected.len(),
+        nodes.len(),
+        "{}: the number of nodes doesn't match the file.",
+        description
+    );
+}
+
+fn check_words<T: LetterTrie>(t: &T, words: &BTreeSet<&str>, type_name: &str) {
+    for word in words {
+        let found = t.find(word);
+        assert!(
+            found.as_ref().map(|node| node.is_word).unwrap_or(false),
+            "{}: \"{}\" should be a word but find() returned {:?}.",
+            type_name,
+            word,
+            found
+        );
+        assert!(
+            t.contains(word),
+            "{}: contains(\"{}\") is false.",
+            type_name,
+            word
+        );
+    }
+    let non_words = verification_words_embedded(VerificationSet::NonWords);
+    for non_word in non_words {
+        let non_word = non_word.to_lowercase();
+        if !words.contains(non_word.as_str()) {
+            assert!(
+                !t.contains(&non_word),
+                "{}: \"{}\" isn't in the file but contains() is true.",
+                type_name,
+                non_word
+            );
+        }
+    }
+}
+
+/// Generate `#[test]` functions that run `conformance::run_conformance()` for a `LetterTrie` implementation on the
+/// small and medium datasets, sorted and unsorted. The first of them, on `Dataset::EmbeddedSmall`, doesn't need
+/// the word files.
+///
+/// Use it inside a test module:
+///
+/// ```rust,ignore
+/// #[cfg(test)]
+/// mod tests {
+///     use super::*;
+///
+///     letter_trie::letter_trie_conformance_tests!(MyLetterTrie);
+/// }
+/// ```
+#[macro_export]
+macro_rules! letter_trie_conformance_tests {
+    ($t:ty) => {
+        #[test]
+        fn conformance_embedded_small() {
+            $crate::conformance::run_conformance::<$t>(&$crate::Dataset::EmbeddedSmall);
+        }
+
+        #[test]
+        fn conformance_small_sorted() {
+            $crate::conformance::run_conformance::<$t>(&$crate::Dataset::TestSmallSorted);
+        }
+
+        #[test]
+        fn conformance_small_unsorted() {
+            $crate::conformance::run_conformance::<$t>(&$crate::Dataset::TestSmallUnsorted);
+        }
+
+        #[test]
+        fn conformance_medium_sorted() {
+            $crate::conformance::run_conformance::<$t>(&$crate::Dataset::TestMediumSorted);
+        }
+
+        #[test]
+        fn conformance_medium_unsorted() {
+            $crate::conformance::run_conformance::<$t>(&$crate::Dataset::TestMediumUnsorted);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_matches_known_small_root() {
+        let lines = read_lines(&Dataset::TestSmallSorted);
+        let words: BTreeSet<&str> = lines.iter().map(|line| line.as_str()).collect();
+        assert_small_root(&reference_root(&words));
+    }
+
+    // Every field of every node for a trie where each word is a prefix of the next.
+    #[test]
+    fn nested_words_snapshot() {
+        let node = |prefix: &str, is_word, child_count, node_count, word_count, height| FixedNode {
+            c: prefix.chars().last().unwrap_or(' '),
+            prefix: prefix.to_owned(),
+            depth: prefix.len(),
+            is_word,
+            child_count,
+            node_count,
+            word_count,
+            height,
+        };
+        let expected = vec![
+            node("", false, 1, 4, 3, 4),
+            node("a", true, 1, 3, 3, 3),
+            node("ab", true, 1, 2, 2, 2),
+            node("abc", true, 0, 1, 1, 1),
+        ];
+        let words: BTreeSet<&str> = vec!["a", "ab", "abc"].into_iter().collect();
+        assert_eq!(expected, reference_nodes(&words));
+
+        check_nodes(
+            &BaseLetterTrie::from_words(words.iter()),
+            &expected,
+            "BaseLetterTrie",
+        );
+        check_nodes(
+            &NoParentLetterTrie::from_words(words.iter()),
+            &expected,
+            "NoParentLetterTrie",
+        );
+        for load_method in &ALL_LOAD_METHODS {
+            for content in &["a\nab\nabc\n", "abc\nab\na"] {
+                let t =
+                    BaseLetterTrie::from_reader(content.as_bytes(), false, load_method).unwrap();
+                check_nodes(&t, &expected, "BaseLetterTrie");
+                let t = NoParentLetterTrie::from_reader(content.as_bytes(), false, load_method)
+                    .unwrap();
+                check_nodes(&t, &expected, "NoParentLetterTrie");
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the node for \"ab\" doesn't match")]
+    fn wrong_node_is_reported() {
+        let t = NoParentLetterTrie::from_words(vec!["a", "ab", "abc"]);
+        let words: BTreeSet<&str> = vec!["a", "ab", "abc"].into_iter().collect();
+        let mut expected = reference_nodes(&words);
+        expected[2].height += 1;
+        check_nodes(&t, &expected, "NoParentLetterTrie");
+    }
+
+    #[test]
+    fn byte_trie_small() {
+        run_byte_conformance(&Dataset::TestSmallSorted);
+        run_byte_conformance(&Dataset::TestSmallUnsorted);
+    }
+
+    #[test]
+    fn byte_trie_medium() {
+        run_byte_conformance(&Dataset::TestMediumSorted);
+        run_byte_conformance(&Dataset::TestMediumUnsorted);
+    }
+
+    #[test]
+    #[should_panic(expected = "should be a word")]
+    fn missing_word_is_reported() {
+        let t = NoParentLetterTrie::from_words(vec!["cross"]);
+        let words: BTreeSet<&str> = vec!["cross", "crossed"].into_iter().collect();
+        check_words(&t, &words, "NoParentLetterTrie");
+    }
+
+    // The inputs that have tripped up a load method, or could, with the words each one should give.
+    const LOAD_METHOD_FIXTURES: [(&str, &[&str]); 14] = [
+        (
+            "no_trailing_newline.txt",
+            &["an", "and", "cross", "crossed"],
+        ),
+        ("crlf.txt", &["an", "and", "cross", "crossed"]),
+        (
+            "crlf_no_trailing_newline.txt",
+            &["azure", "creature", "cross"],
+        ),
+        (
+            "duplicate_last_word.txt",
+            &["an", "and", "cross", "crossed"],
+        ),
+        (
+            "adjacent_duplicates.txt",
+            &["an", "and", "cross", "crossed"],
+        ),
+        ("trailing_blank_lines.txt", &["an", "and", "cross"]),
+        (
+            "blank_lines_and_whitespace.txt",
+            &["an", "and", "cross", "crossed"],
+        ),
+        (
+            "unsorted_with_prefixes.txt",
+            &["a", "an", "and", "cross", "crossed"],
+        ),
+        ("last_letter_with_newline.txt", &LAST_LETTER_WORDS),
+        ("last_letter_no_newline.txt", &LAST_LETTER_WORDS),
+        ("last_letter_blank_line.txt", &LAST_LETTER_WORDS),
+        ("last_letter_whitespace_lines.txt", &LAST_LETTER_WORDS),
+        ("last_letter_trailing_spaces.txt", &LAST_LETTER_WORDS),
+        ("single_word_no_newline.txt", &["zebra"]),
+    ];
+
+    // The words of the last_letter fixtures, which only differ in how they end. The last word is the only one with
+    // its first letter, so it's a group of its own for LoadMethod::ContinuousParallel to finish at the end.
+    const LAST_LETTER_WORDS: [&str; 5] = ["an", "and", "cross", "crossed", "zebra"];
+
+    fn fixture_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/load_methods")
+            .join(name)
+    }
+
+    #[test]
+    fn load_methods_agree_on_tricky_files() {
+        for (name, words) in &LOAD_METHOD_FIXTURES {
+            let path = fixture_path(name);
+            let report = compare_load_methods(&path, LoadMethod::all()).unwrap();
+            report.assert_consistent();
+            assert_eq!(2 * LoadMethod::all().len(), report.builds.len());
+            // They also have to agree on the right trie.
+            let expected: BTreeSet<&str> = words.iter().copied().collect();
+            assert_eq!(
+                reference_nodes(&expected).len(),
+                report.builds[0].node_count,
+                "{}",
+                name
+            );
+            let t = NoParentLetterTrie::from_file(
+                path.to_str().unwrap(),
+                report.is_sorted,
+                &LoadMethod::Continuous,
+            )
+            .unwrap();
+            assert_eq!(words.to_vec(), t.words().collect::<Vec<_>>(), "{}", name);
+        }
+        let is_sorted = |name: &str| {
+            compare_load_methods(&fixture_path(name), &[LoadMethod::Continuous])
+                .unwrap()
+                .is_sorted
+        };
+        assert!(is_sorted("crlf.txt"));
+        assert!(!is_sorted("unsorted_with_prefixes.txt"));
+    }
+
+    #[test]
+    fn every_ending_gives_the_same_trie() {
+        let fingerprint = NoParentLetterTrie::from_words(LAST_LETTER_WORDS).fingerprint();
+        let temp_dir = temp_path("conformance_endings");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let load_methods = [
+            LoadMethod::ReadVecFill,
+            LoadMethod::VecFill,
+            LoadMethod::Continuous,
+            LoadMethod::ContinuousParallel,
+            LoadMethod::External {
+                temp_dir: temp_dir.to_path_buf(),
+                chunk_words: 2,
+            },
+        ];
+        for (name, words) in &LOAD_METHOD_FIXTURES {
+            if *words != LAST_LETTER_WORDS {
+                continue;
+            }
+            let path = fixture_path(name);
+            let report = compare_load_methods(&path, &load_methods).unwrap();
+            report.assert_consistent();
+            assert!(report.is_sorted);
+            for build in &report.builds {
+                assert_eq!(fingerprint, build.fingerprint, "{} {:?}", name, build);
+            }
+            for load_method in &[LoadMethod::Continuous, LoadMethod::ContinuousParallel] {
+                let opt = DisplayDetailOptions::make_no_display().with_read_ahead_lines(1);
+                let reader = BufReader::new(File::open(&path).unwrap());
+                let t = BaseLetterTrie::from_reader_test(reader, true, load_method, &opt, None)
+                    .unwrap();
+                assert_eq!(fingerprint, t.fingerprint(), "{} {:?}", name, load_method);
+            }
+            let t: ConfiguredLetterTrie<NoParentLetterTrie> =
+                ConfiguredLetterTrie::from_file(path.to_str().unwrap(), TrieOptions::new())
+                    .unwrap();
+            assert_eq!(fingerprint, t.trie().fingerprint(), "{}", name);
+            assert_eq!(
+                LAST_LETTER_WORDS.len(),
+                t.load_report().words_added,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let err =
+            compare_load_methods(&fixture_path("missing.txt"), LoadMethod::all()).unwrap_err();
+        assert!(matches!(err, LetterTrieError::File { .. }));
+        assert!(err.to_string().contains("missing.txt"), "{}", err);
+    }
+
+    fn build_of(t: &NoParentLetterTrie, load_method: &str) -> LoadBuild {
+        let root = t.to_fixed_node();
+        LoadBuild {
+            load_method: load_method.to_owned(),
+            letter_trie_type: LetterTrieType::NoParent,
+            fingerprint: t.fingerprint(),
+            word_count: root.word_count,
+            node_count: root.node_count,
+        }
+    }
+
+    #[test]
+    fn mismatch_is_reported_at_the_first_prefix() {
+        let same = NoParentLetterTrie::from_words(vec!["an", "and", "cross"]);
+        let different = NoParentLetterTrie::from_words(vec!["an", "ant", "cross"]);
+        let mismatch = first_mismatch(vec![
+            node_snapshots(&same),
+            node_snapshots(&same),
+            node_snapshots(&different),
+        ])
+        .unwrap();
+        // The nodes down to "an" have the same fields in all three, so the first difference is that the last build
+        // has no "and".
+        assert_eq!("and", mismatch.prefix);
+        assert_eq!(
+            vec![same.find("and"), same.find("and"), None],
+            mismatch.nodes
+        );
+        assert_eq!(
+            None,
+            first_mismatch(vec![node_snapshots(&same), node_snapshots(&same)])
+        );
+        assert_eq!(None, first_mismatch(vec![]));
+
+        // Missing words change the counts all the way up to the root.
+        let shorter = NoParentLetterTrie::from_words(vec!["an", "and"]);
+        let mismatch =
+            first_mismatch(vec![node_snapshots(&same), node_snapshots(&shorter)]).unwrap();
+        assert_eq!("", mismatch.prefix);
+
+        let report = ComparisonReport {
+            path: PathBuf::from("words.txt"),
+            is_sorted: true,
+            builds: vec![
+                build_of(&same, "continuous"),
+                build_of(&different, "vec-fill"),
+            ],
+            mismatch: first_mismatch(vec![node_snapshots(&same), node_snapshots(&different)]),
+        };
+        assert!(!report.is_consistent());
+        let s = report.to_string();
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!(3, lines.len());
+        assert_eq!(
+            "The builds of words.txt (is_sorted = true) differ first at the prefix \"and\":",
+            lines[0]
+        );
+        assert!(
+            lines[1].starts_with("    no-parent with continuous: 3 words, 9 nodes, fingerprint ")
+        );
+        assert!(lines[1].ends_with(&format!("{:?}", same.find("and").unwrap())));
+        assert!(lines[2].starts_with("    no-parent with vec-fill: "));
+        assert!(lines[2].ends_with(", no node"));
+    }
+
+    #[test]
+    #[should_panic(expected = "differ first at the prefix")]
+    fn inconsistent_report_panics() {
+        let a = NoParentLetterTrie::from_words(vec!["an"]);
+        let b = NoParentLetterTrie::from_words(vec!["and"]);
+        ComparisonReport {
+            path: PathBuf::from("words.txt"),
+            is_sorted: false,
+            builds: vec![build_of(&a, "continuous"), build_of(&b, "continuous")],
+            mismatch: first_mismatch(vec![node_snapshots(&a), node_snapshots(&b)]),
+        }
+        .assert_consistent();
+    }
+}
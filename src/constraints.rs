@@ -0,0 +1,624 @@
+//! Finding the words that fit what's known from the guesses in a game like hangman or Wordle, behind
+//! `LetterTrie::filter_candidates()` and `LetterTrie::count_candidates()`.
+//!
+//! The constraints are checked as the trie is walked rather than on whole words. A branch is cut off as soon as
+//! its letter doesn't fit its position, a letter shows up more often than it can, or there aren't enough
+//! positions left for the letters that are still needed, and nothing below the target length is visited.
+
+use core::cmp;
+
+use crate::limits::Budget;
+use crate::*;
+
+/// What a game like Wordle tells about one letter of a guess.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LetterFeedback {
+    /// The letter is in the answer in this position.
+    Green,
+    /// The letter is in the answer but not in this position.
+    Yellow,
+    /// The letter isn't in the answer, or not as many times as it's in the guess.
+    Gray,
+}
+
+impl LetterFeedback {
+    /// Score `guess` against `answer` the way Wordle does. Greens are marked first, and then each other letter of
+    /// the guess is yellow if the answer has one of that letter left over that isn't already green or yellow, and
+    /// gray if not. So guessing "speed" for "crepe" gives a green e and a yellow one, since the answer has two,
+    /// but guessing "eerie" for "crepe" gives a yellow e, a gray one and a green one. Both words are compared as
+    /// they're given, one position to each character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the words aren't the same number of characters long.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use letter_trie::LetterFeedback::*;
+    ///
+    /// assert_eq!(vec![Gray, Yellow, Green, Yellow, Gray], LetterFeedback::score("speed", "crepe"));
+    /// assert_eq!(vec![Yellow, Gray, Yellow, Gray, Green], LetterFeedback::score("eerie", "crepe"));
+    /// ```
+    pub fn score(guess: &str, answer: &str) -> Vec<LetterFeedback> {
+        let guess: Vec<char> = guess.chars().collect();
+        let answer: Vec<char> = answer.chars().collect();
+        assert_eq!(
+            guess.len(),
+            answer.len(),
+            "the guess and answer have to be the same length"
+        );
+        let mut feedback = vec![LetterFeedback::Gray; guess.len()];
+        // The letters of the answer that aren't matched by a green, to be handed out as yellows.
+        let mut left_over: Vec<char> = vec![];
+        for (position, (g, a)) in guess.iter().zip(answer.iter()).enumerate() {
+            if g == a {
+                feedback[position] = LetterFeedback::Green;
+            } else {
+                left_over.push(*a);
+            }
+        }
+        for (position, g) in guess.iter().enumerate() {
+            if feedback[position] == LetterFeedback::Green {
+                continue;
+            }
+            if let Some(index) = left_over.iter().position(|a| a == g) {
+                left_over.swap_remove(index);
+                feedback[position] = LetterFeedback::Yellow;
+            }
+        }
+        feedback
+    }
+}
+
+// What's known about how many times one letter is in the word.
+#[derive(Clone, Debug)]
+struct LetterCount {
+    c: char,
+    // The fewest times it can be in the word from misplaced letters and guesses. Fixed positions are counted
+    // separately in LetterConstraints::min_count().
+    at_least: usize,
+    // The most times it can be in the word, or None if there's no limit.
+    at_most: Option<usize>,
+    // Set by with_excluded(), which means no more than it's needed.
+    excluded: bool,
+}
+
+/// The known letters of a word to look for with `LetterTrie::filter_candidates()` and
+/// `LetterTrie::count_candidates()`, built up one clue at a time. Positions count from 0 in characters, and the
+/// letters are lowercased since that's how the words are stored.
+///
+/// The clues can be given one at a time, or a whole Wordle guess at once with `with_guess()`, which gets right
+/// the case of a letter that's in the guess more often than in the answer.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["crane", "crate", "trace", "react", "cater", "crated"]);
+/// let constraints = LetterConstraints::new(5)
+///     .with_fixed(0, 'c')
+///     .with_misplaced('t', 4)
+///     .with_excluded('n');
+/// assert_eq!(vec!["cater", "crate"], trie.filter_candidates(&constraints));
+/// assert_eq!(2, trie.count_candidates(&constraints));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LetterConstraints {
+    // The letter known to be at each position.
+    fixed: Vec<Option<char>>,
+    // The letters known not to be at each position.
+    not_at: Vec<Vec<char>>,
+    // The counts for every letter there's a count or exclusion for, in the order they were first given.
+    counts: Vec<LetterCount>,
+}
+
+impl LetterConstraints {
+    /// Start with nothing known about a word other than that it's `len` letters long.
+    pub fn new(len: usize) -> Self {
+        Self {
+            fixed: vec![None; len],
+            not_at: vec![vec![]; len],
+            counts: vec![],
+        }
+    }
+
+    /// The length of the words to look for.
+    pub fn len(&self) -> usize {
+        self.fixed.len()
+    }
+
+    /// Returns true if the words to look for have no letters, in which case nothing is found.
+    pub fn is_empty(&self) -> bool {
+        self.fixed.is_empty()
+    }
+
+    /// The letter at `position` is `c`, like a green letter in Wordle. A position past the end means no word
+    /// fits.
+    pub fn with_fixed(mut self, position: usize, c: char) -> Self {
+        let c = lowercase_letter(c);
+        match self.fixed.get_mut(position) {
+            Some(fixed) if fixed.is_none() || *fixed == Some(c) => *fixed = Some(c),
+            // A second letter for the same position can't be satisfied, and neither can one past the end.
+            _ => self.make_impossible(),
+        }
+        self
+    }
+
+    /// The word has `c` in it, but not at `position`, like a yellow letter in Wordle.
+    pub fn with_misplaced(mut self, c: char, position: usize) -> Self {
+        let c = lowercase_letter(c);
+        if let Some(not_at) = self.not_at.get_mut(position) {
+            not_at.push(c);
+        }
+        let count = self.count_mut(c);
+        count.at_least = cmp::max(count.at_least, 1);
+        self
+    }
+
+    /// The word has no `c` in it other than the ones that `with_fixed()`, `with_misplaced()` and
+    /// `with_guess()` call for, like a gray letter in Wordle.
+    pub fn with_excluded(mut self, c: char) -> Self {
+        let c = lowercase_letter(c);
+        self.count_mut(c).excluded = true;
+        self
+    }
+
+    /// Add everything that Wordle's feedback for `guess` tells about the word. A green letter is fixed and a
+    /// yellow one is misplaced. If the guess has a letter more times than it's green or yellow, the word has that
+    /// letter exactly as many times as it's green or yellow, and not at any of the positions where it's gray.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `feedback` doesn't have one entry for each character of `guess`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use letter_trie::LetterFeedback::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["crepe", "creep", "where", "erect", "there"]);
+    /// // Guessing "speed" when the answer is "crepe" says there's a p but not at position 1, an e at position 2 and
+    /// // another e but not at position 3.
+    /// let constraints = LetterConstraints::new(5).with_guess("speed", &[Gray, Yellow, Green, Yellow, Gray]);
+    /// assert_eq!(vec!["crepe"], trie.filter_candidates(&constraints));
+    /// ```
+    pub fn with_guess(mut self, guess: &str, feedback: &[LetterFeedback]) -> Self {
+        let guess: Vec<char> = guess.chars().map(lowercase_letter).collect();
+        assert_eq!(
+            guess.len(),
+            feedback.len(),
+            "each letter of the guess needs feedback"
+        );
+        // The letters of the guess with how many times each is green or yellow and whether any is gray.
+        let mut letters: Vec<(char, usize, bool)> = vec![];
+        for (position, (c, feedback)) in guess.iter().zip(feedback.iter()).enumerate() {
+            match feedback {
+                LetterFeedback::Green => self = self.with_fixed(position, *c),
+                LetterFeedback::Yellow | LetterFeedback::Gray => {
+                    if let Some(not_at) = self.not_at.get_mut(position) {
+                        not_at.push(*c);
+                    }
+                }
+            }
+            let index = match letters.iter().position(|(letter, _, _)| letter == c) {
+                Some(index) => index,
+                None => {
+                    letters.push((*c, 0, false));
+                    letters.len() - 1
+                }
+            };
+            match feedback {
+                LetterFeedback::Green | LetterFeedback::Yellow => letters[index].1 += 1,
+                LetterFeedback::Gray => letters[index].2 = true,
+            }
+        }
+        for (c, found, has_gray) in letters {
+            let count = self.count_mut(c);
+            count.at_least = cmp::max(count.at_least, found);
+            if has_gray {
+                count.at_most = Some(
+                    count
+                        .at_most
+                        .map_or(found, |at_most| cmp::min(at_most, found)),
+                );
+            }
+        }
+        self
+    }
+
+    fn count_mut(&mut self, c: char) -> &mut LetterCount {
+        let index = match self.counts.iter().position(|count| count.c == c) {
+            Some(index) => index,
+            None => {
+                self.counts.push(LetterCount {
+                    c,
+                    at_least: 0,
+                    at_most: None,
+                    excluded: false,
+                });
+                self.counts.len() - 1
+            }
+        };
+        &mut self.counts[index]
+    }
+
+    // Leave the constraints so that no word can fit them.
+    fn make_impossible(&mut self) {
+        // No letter can be both at least once and at most no times in a word.
+        let count = self.count_mut(' ');
+        count.at_least = 1;
+        count.at_most = Some(0);
+    }
+
+    // The fewest times the letter of a count can be in the word.
+    fn min_count(&self, count: &LetterCount) -> usize {
+        let fixed = self.fixed.iter().filter(|&&c| c == Some(count.c)).count();
+        cmp::max(count.at_least, fixed)
+    }
+
+    // The limits on each letter of self.counts as (letter, fewest, most), along with the letters that are fixed
+    // but not otherwise limited, since those still have to be counted to know how many positions are left.
+    fn letter_limits(&self) -> Vec<(char, usize, Option<usize>)> {
+        let mut limits: Vec<(char, usize, Option<usize>)> = self
+            .counts
+            .iter()
+            .map(|count| {
+                let min = self.min_count(count);
+                let max = if count.excluded {
+                    Some(count.at_most.map_or(min, |at_most| cmp::min(at_most, min)))
+                } else {
+                    count.at_most
+                };
+                (count.c, min, max)
+            })
+            .collect();
+        for c in self.fixed.iter().flatten() {
+            if !limits.iter().any(|(letter, _, _)| letter == c) {
+                let min = self
+                    .fixed
+                    .iter()
+                    .filter(|&&fixed| fixed == Some(*c))
+                    .count();
+                limits.push((*c, min, None));
+            }
+        }
+        limits
+    }
+}
+
+fn lowercase_letter(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// Call f with each word that fits, in alphabetical order, until the budget is spent.
+pub(crate) fn for_each_candidate<T, F>(
+    trie: &T,
+    constraints: &LetterConstraints,
+    budget: &mut Budget,
+    mut f: F,
+) where
+    T: TrieQuery + ?Sized,
+    F: FnMut(&str),
+{
+    let len = constraints.len();
+    if len == 0 {
+        return;
+    }
+    let limits = constraints.letter_limits();
+    if limits
+        .iter()
+        .any(|(_, min, max)| max.is_some_and(|max| max < *min))
+    {
+        return;
+    }
+    // How many times each letter of `limits` is on the path to the current node, and the index in `limits` of
+    // each node's letter on the path if it has one.
+    let mut on_path = vec![0usize; limits.len()];
+    let mut path: Vec<Option<usize>> = vec![];
+    trie.visit("", &mut |node| {
+        if !budget.visit_node() {
+            return false;
+        }
+        if node.depth == 0 {
+            return true;
+        }
+        while path.len() >= node.depth {
+            if let Some(Some(index)) = path.pop() {
+                on_path[index] -= 1;
+            }
+        }
+        let position = node.depth - 1;
+        if constraints.fixed[position].is_some_and(|c| c != node.c)
+            || constraints.not_at[position].contains(&node.c)
+        {
+            return false;
+        }
+        let index = limits.iter().position(|(c, _, _)| *c == node.c);
+        if let Some(index) = index {
+            if limits[index].2.is_some_and(|max| on_path[index] >= max) {
+                return false;
+            }
+            on_path[index] += 1;
+        }
+        path.push(index);
+        // The letters still needed have to fit in the positions that are left.
+        let still_needed: usize = limits
+            .iter()
+            .zip(on_path.iter())
+            .map(|((_, min, _), n)| min.saturating_sub(*n))
+            .sum();
+        if still_needed > len - node.depth {
+            return false;
+        }
+        if node.depth == len {
+            if node.is_word {
+                f(node.prefix);
+                budget.add_result();
+            }
+            return false;
+        }
+        true
+    });
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use LetterFeedback::*;
+
+    // Parse feedback written as g for green, y for yellow and anything else for gray.
+    fn feedback(s: &str) -> Vec<LetterFeedback> {
+        s.chars()
+            .map(|c| match c {
+                'g' => Green,
+                'y' => Yellow,
+                _ => Gray,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn score() {
+        assert_eq!(feedback("ggggg"), LetterFeedback::score("crane", "crane"));
+        // The answer has one e, which goes to the first e of the guess.
+        assert_eq!(feedback("..y.y"), LetterFeedback::score("speed", "abide"));
+        // The answer has one e, which goes to the green rather than the earlier e's of the guess.
+        assert_eq!(feedback("...gg"), LetterFeedback::score("eerie", "abcie"));
+        assert_eq!(feedback("..g.g"), LetterFeedback::score("eerie", "xxrxe"));
+        // Two e's in the answer, so both yellows are given out.
+        assert_eq!(feedback("yy..."), LetterFeedback::score("eexxx", "abeee"));
+    }
+
+    // Whether word fits constraints given clue by clue, checked on the whole word without the trie.
+    struct Clues<'a> {
+        len: usize,
+        fixed: &'a [(usize, char)],
+        misplaced: &'a [(char, usize)],
+        excluded: &'a [char],
+    }
+
+    impl Clues<'_> {
+        fn constraints(&self) -> LetterConstraints {
+            let mut constraints = LetterConstraints::new(self.len);
+            for (position, c) in self.fixed {
+                constraints = constraints.with_fixed(*position, *c);
+            }
+            for (c, position) in self.misplaced {
+                constraints = constraints.with_misplaced(*c, *position);
+            }
+            for c in self.excluded {
+                constraints = constraints.with_excluded(*c);
+            }
+            constraints
+        }
+
+        fn fits(&self, word: &str) -> bool {
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() != self.len {
+                return false;
+            }
+            if !self
+                .fixed
+                .iter()
+                .all(|(position, c)| chars[*position] == *c)
+            {
+                return false;
+            }
+            if !self
+                .misplaced
+                .iter()
+                .all(|(c, position)| chars.contains(c) && chars[*position] != *c)
+            {
+                return false;
+            }
+            self.excluded.iter().all(|c| {
+                let needed_fixed = self.fixed.iter().filter(|(_, f)| f == c).count();
+                let needed_misplaced = self.misplaced.iter().any(|(m, _)| m == c) as usize;
+                chars.iter().filter(|x| *x == c).count() <= cmp::max(needed_fixed, needed_misplaced)
+            })
+        }
+    }
+
+    fn check_clues<T: LetterTrie>(words: &[String]) {
+        let t = T::from_words(words);
+        let clue_sets = [
+            Clues {
+                len: 5,
+                fixed: &[],
+                misplaced: &[],
+                excluded: &[],
+            },
+            Clues {
+                len: 7,
+                fixed: &[(0, 'b'), (6, 'a')],
+                misplaced: &[],
+                excluded: &[],
+            },
+            Clues {
+                len: 6,
+                fixed: &[(1, 'a')],
+                misplaced: &[('e', 0), ('r', 3)],
+                excluded: &['s', 't', 'o'],
+            },
+            Clues {
+                len: 8,
+                fixed: &[],
+                misplaced: &[('a', 1), ('a', 4), ('n', 2)],
+                excluded: &['e', 'i', 'o', 'u'],
+            },
+            // An excluded letter that's also fixed can only be in the fixed position.
+            Clues {
+                len: 6,
+                fixed: &[(2, 'a')],
+                misplaced: &[],
+                excluded: &['a', 'e'],
+            },
+            Clues {
+                len: 4,
+                fixed: &[(0, 'q'), (1, 'q')],
+                misplaced: &[],
+                excluded: &[],
+            },
+            Clues {
+                len: 20,
+                fixed: &[],
+                misplaced: &[],
+                excluded: &[],
+            },
+        ];
+        for clues in &clue_sets {
+            let expected: Vec<&String> = words.iter().filter(|word| clues.fits(word)).collect();
+            let constraints = clues.constraints();
+            assert_eq!(
+                expected,
+                t.filter_candidates(&constraints).iter().collect::<Vec<_>>()
+            );
+            assert_eq!(expected.len(), t.count_candidates(&constraints));
+        }
+    }
+
+    fn medium_words() -> Vec<String> {
+        let mut words = words_from_file(Dataset::TestMediumSorted.filename()).unwrap();
+        words.sort();
+        words.dedup();
+        words
+    }
+
+    #[test]
+    fn medium_clues() {
+        let words = medium_words();
+        check_clues::<BaseLetterTrie>(&words);
+        check_clues::<NoParentLetterTrie>(&words);
+    }
+
+    // Every word of the right length that would have given the same feedback to each guess.
+    fn brute_force<'a>(words: &'a [String], answer: &str, guesses: &[&str]) -> Vec<&'a String> {
+        let len = answer.chars().count();
+        words
+            .iter()
+            .filter(|word| word.chars().count() == len)
+            .filter(|word| {
+                guesses.iter().all(|guess| {
+                    LetterFeedback::score(guess, word) == LetterFeedback::score(guess, answer)
+                })
+            })
+            .collect()
+    }
+
+    fn check_guesses<T: LetterTrie>(words: &[String]) {
+        let t = T::from_words(words);
+        let five: Vec<&String> = words
+            .iter()
+            .filter(|word| word.chars().count() == 5)
+            .collect();
+        // Words with a letter more than once, which is where the feedback is easiest to get wrong.
+        let repeated: Vec<&String> = five
+            .iter()
+            .copied()
+            .filter(|word| {
+                let chars: Vec<char> = word.chars().collect();
+                chars
+                    .iter()
+                    .any(|c| chars.iter().filter(|x| *x == c).count() > 1)
+            })
+            .collect();
+        assert!(repeated.len() > 20);
+        let mut checked = 0;
+        for (i, answer) in five.iter().step_by(4).take(40).enumerate() {
+            let guesses: Vec<&str> = vec![
+                repeated[i % repeated.len()].as_str(),
+                repeated[(i * 7 + 3) % repeated.len()].as_str(),
+                five[(i * 13 + 5) % five.len()].as_str(),
+            ];
+            for guess_count in 1..=guesses.len() {
+                let guesses = &guesses[..guess_count];
+                let constraints =
+                    guesses
+                        .iter()
+                        .fold(LetterConstraints::new(5), |constraints, guess| {
+                            constraints.with_guess(guess, &LetterFeedback::score(guess, answer))
+                        });
+                let expected = brute_force(words, answer, guesses);
+                assert!(expected.contains(answer));
+                let found = t.filter_candidates(&constraints);
+                assert_eq!(
+                    expected,
+                    found.iter().collect::<Vec<_>>(),
+                    "{} after {:?}",
+                    answer,
+                    guesses
+                );
+                assert_eq!(expected.len(), t.count_candidates(&constraints));
+                checked += 1;
+            }
+        }
+        assert_eq!(120, checked);
+    }
+
+    #[test]
+    fn medium_guesses() {
+        let words = medium_words();
+        check_guesses::<BaseLetterTrie>(&words);
+        check_guesses::<NoParentLetterTrie>(&words);
+    }
+
+    #[test]
+    fn repeated_letters() {
+        let words = [
+            "crepe", "creep", "erect", "eerie", "there", "where", "speed", "spend", "abbey",
+        ];
+        let t = NoParentLetterTrie::from_words(words);
+        let filter = |constraints: &LetterConstraints| t.filter_candidates(constraints);
+        // Two yellow e's mean at least two e's, neither at positions 2 or 3.
+        let constraints = LetterConstraints::new(5).with_guess("speed", &feedback("..yy."));
+        assert_eq!(vec!["eerie"], filter(&constraints));
+        // A green e and a gray e mean exactly one e.
+        let constraints = LetterConstraints::new(5).with_guess("eerie", &feedback("....g"));
+        assert_eq!(Vec::<String>::new(), filter(&constraints));
+        let constraints = LetterConstraints::new(5).with_guess("speed", &feedback("ggg.."));
+        assert_eq!(Vec::<String>::new(), filter(&constraints));
+        let constraints = LetterConstraints::new(5).with_guess("speed", &feedback("ggg.g"));
+        assert_eq!(vec!["spend"], filter(&constraints));
+        // Excluding a fixed letter only rules out more of it.
+        let constraints = LetterConstraints::new(5)
+            .with_fixed(1, 'b')
+            .with_excluded('b');
+        assert_eq!(Vec::<String>::new(), filter(&constraints));
+        let constraints = LetterConstraints::new(5)
+            .with_fixed(1, 'B')
+            .with_fixed(2, 'b');
+        assert_eq!(vec!["abbey"], filter(&constraints));
+        // Two letters for one position, or a position past the end, can't be met.
+        let constraints = LetterConstraints::new(5)
+            .with_fixed(0, 'c')
+            .with_fixed(0, 'e');
+        assert_eq!(0, t.count_candidates(&constraints));
+        let constraints = LetterConstraints::new(5).with_fixed(5, 'c');
+        assert_eq!(0, t.count_candidates(&constraints));
+        assert_eq!(0, t.count_candidates(&LetterConstraints::new(0)));
+    }
+}
@@ -0,0 +1,275 @@
+//! Walking down a trie one letter at a time with a `TrieCursor` from `LetterTrie::cursor()`, as when scanning text
+//! for words or trying the tiles of a word game, where asking `contains_prefix()` again for each longer prefix
+//! would go back over the letters already matched.
+//!
+//! A cursor is at one node of the trie, starting at the root, and each step moves it to a child. It keeps nothing
+//! but the node it's at and the root, so stepping never allocates.
+//!
+//! As with the node iterators, a cursor borrows the trie for as long as it's alive, so the trie can't be changed
+//! under it. For a `NoParentLetterTrie` that's what any reference into the trie does, and for a `BaseLetterTrie`,
+//! whose nodes could otherwise be changed through the `RefCell`s the cursor can reach, it's the same borrow that
+//! `insert()`, `remove()`, `merge()` and the rest of the methods that change the trie need:
+//!
+//! ```compile_fail,E0502
+//! use letter_trie::*;
+//!
+//! let mut trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+//! let mut cursor = trie.cursor();
+//! cursor.step_chars("cross".chars());
+//! trie.merge(BaseLetterTrie::from_words(vec!["crossing"]));
+//! cursor.step('e');
+//! ```
+//!
+//! ```compile_fail,E0502
+//! use letter_trie::*;
+//!
+//! let mut trie = NoParentLetterTrie::from_words(vec!["cross", "crossed"]);
+//! let mut cursor = trie.cursor();
+//! cursor.step_chars("cross".chars());
+//! trie.remove("crossed");
+//! cursor.step('e');
+//! ```
+
+use core::marker::PhantomData;
+
+use crate::base_letter_trie::CursorNode;
+use crate::*;
+
+/// A position in a trie from `LetterTrie::cursor()`. See the `cursor` module.
+///
+/// Letters are lowercased before they're looked for, each on its own as in `LetterTrie::find_chars()`, so a
+/// letter such as "İ" whose lowercase form is two characters takes two steps down, both in one call. A capital
+/// sigma becomes a plain sigma, since a step can't know whether it ends the word, so step with a final sigma
+/// where it does. A step that can't be taken leaves the cursor where it was, so after a failed step it can still
+/// be asked about the prefix it has. `depth()` counts `char`s, not bytes.
+///
+/// A cursor on a `BaseLetterTrie` holds references to the root and to the node it's at, which
+/// `verify_integrity()` and `rc_report()` see as an extra strong count on each until the cursor is dropped.
+///
+/// # Examples
+///
+/// Finding the words that start at the beginning of some text:
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["a", "an", "and", "cross"]);
+/// let mut cursor = trie.cursor();
+/// let mut found = vec![];
+/// for (i, c) in "Andromeda".char_indices() {
+///     if !cursor.step(c) {
+///         break;
+///     }
+///     if cursor.is_word() {
+///         found.push(&"andromeda"[..i + c.len_utf8()]);
+///     }
+/// }
+/// assert_eq!(vec!["a", "an", "and"], found);
+///
+/// cursor.reset();
+/// assert!(cursor.step_chars("CROSS".chars()));
+/// assert!(cursor.is_word());
+/// assert!(!cursor.step_chars("ed".chars()));
+/// assert_eq!(5, cursor.depth());
+/// ```
+#[derive(Clone)]
+pub struct TrieCursor<'a> {
+    root: Position<'a>,
+    node: Position<'a>,
+}
+
+// The node of one of the tries that a cursor is at. A BaseLetterTrie's nodes are behind an Rc, so the cursor keeps
+// a clone of the link rather than a reference, and the trie's lifetime is kept by the NoParent variant's reference
+// or the PhantomData.
+#[derive(Clone)]
+pub(crate) enum Position<'a> {
+    Base(CursorNode, PhantomData<&'a BaseLetterTrie>),
+    NoParent(&'a no_parent_letter_trie::Node),
+}
+
+impl<'a> Position<'a> {
+    fn child(&self, c: char) -> Option<Self> {
+        match self {
+            Position::Base(node, _) => node
+                .child(c)
+                .map(|child| Position::Base(child, PhantomData)),
+            Position::NoParent(node) => node.child_node(c).map(Position::NoParent),
+        }
+    }
+}
+
+impl<'a> TrieCursor<'a> {
+    pub(crate) fn new(root: Position<'a>) -> Self {
+        Self {
+            node: root.clone(),
+            root,
+        }
+    }
+
+    /// Move down to the child for `c`, returning false and staying put if there isn't one.
+    pub fn step(&mut self, c: char) -> bool {
+        self.step_chars(Some(c))
+    }
+
+    /// Move down through each of `chars` in turn, returning true if all of them are there. If one isn't, the
+    /// cursor stays where it was before the call rather than stopping part of the way down. Like `find_chars()`
+    /// this doesn't allocate, however the letters are given.
+    pub fn step_chars<I: IntoIterator<Item = char>>(&mut self, chars: I) -> bool {
+        let mut node = self.node.clone();
+        for c in chars.into_iter().flat_map(char::to_lowercase) {
+            match node.child(c) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        self.node = node;
+        true
+    }
+
+    /// Go back to the root.
+    pub fn reset(&mut self) {
+        self.node = self.root.clone();
+    }
+
+    /// The number of letters stepped through from the root, counting `char`s rather than bytes.
+    pub fn depth(&self) -> usize {
+        match &self.node {
+            Position::Base(node, _) => node.depth(),
+            Position::NoParent(node) => node.depth(),
+        }
+    }
+
+    /// Returns true if the letters stepped through so far are a word, which is never true at the root.
+    pub fn is_word(&self) -> bool {
+        match &self.node {
+            Position::Base(node, _) => node.is_word(),
+            Position::NoParent(node) => node.is_word(),
+        }
+    }
+
+    /// The number of letters that can follow the ones stepped through so far. If it's 0 no step can be taken.
+    pub fn child_count(&self) -> usize {
+        match &self.node {
+            Position::Base(node, _) => node.child_count(),
+            Position::NoParent(node) => node.child_count(),
+        }
+    }
+}
+
+impl Debug for TrieCursor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrieCursor")
+            .field("depth", &self.depth())
+            .field("is_word", &self.is_word())
+            .field("child_count", &self.child_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_cursor<T: LetterTrie>() {
+        let t = T::from_words(vec!["a", "an", "and", "cross", "crossed", "日本", "日本語"]);
+        let mut cursor = t.cursor();
+        assert_eq!(0, cursor.depth());
+        assert!(!cursor.is_word());
+        assert_eq!(3, cursor.child_count());
+        assert!(!cursor.step('x'));
+        assert_eq!(0, cursor.depth());
+
+        assert!(cursor.step('A'));
+        assert!(cursor.is_word());
+        assert!(cursor.step_chars(vec!['n', 'd']));
+        assert!(cursor.is_word());
+        assert_eq!(3, cursor.depth());
+        assert_eq!(0, cursor.child_count());
+
+        // A failed bulk step leaves the cursor where it was, not at the last letter it found.
+        cursor.reset();
+        assert!(cursor.step_chars("cro".chars()));
+        assert!(!cursor.step_chars("ssing".chars()));
+        assert_eq!(3, cursor.depth());
+        assert!(cursor.step_chars("ss".chars()));
+        assert!(cursor.is_word());
+        let copy = cursor.clone();
+        assert!(cursor.step_chars("ED".chars()));
+        assert_eq!(7, cursor.depth());
+        assert_eq!(5, copy.depth());
+
+        cursor.reset();
+        assert!(cursor.step_chars("日本".chars()));
+        assert!(cursor.is_word());
+        assert_eq!(2, cursor.depth());
+        assert!(cursor.step('語'));
+        assert_eq!(3, cursor.depth());
+
+        // The same answers as the str queries, for every prefix of every word and a letter past each.
+        for word in t.words() {
+            let mut cursor = t.cursor();
+            let mut prefix = String::new();
+            for c in word.chars().chain(Some('#')) {
+                prefix.push(c);
+                assert_eq!(t.contains_prefix(&prefix), cursor.step(c), "{}", prefix);
+                if t.contains_prefix(&prefix) {
+                    assert_eq!(t.contains(&prefix), cursor.is_word(), "{}", prefix);
+                    assert_eq!(t.children(&prefix).len(), cursor.child_count());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_base() {
+        check_cursor::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn cursor_no_parent() {
+        check_cursor::<NoParentLetterTrie>();
+    }
+
+    // A cursor's hold on the trie ends when it's dropped, and a BaseLetterTrie's reference counts are back to
+    // normal then.
+    fn check_mutation_after_cursor<T: LetterTrie>() {
+        let mut t = T::from_words(vec!["cross", "crossed"]);
+        let mut cursor = t.cursor();
+        assert!(cursor.step_chars("crosse".chars()));
+        drop(cursor);
+        t.verify_integrity().unwrap();
+        assert!(t.remove("crossed"));
+        t.insert("crossing");
+        let mut cursor = t.cursor();
+        assert!(!cursor.step_chars("crosse".chars()));
+        assert!(cursor.step_chars("crossing".chars()));
+        assert!(cursor.is_word());
+    }
+
+    #[test]
+    fn mutation_after_cursor() {
+        check_mutation_after_cursor::<BaseLetterTrie>();
+        check_mutation_after_cursor::<NoParentLetterTrie>();
+    }
+
+    // The extra count that the docs for TrieCursor mention.
+    #[test]
+    fn base_cursor_holds_a_count() {
+        let t = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+        let mut cursor = t.cursor();
+        assert!(cursor.step_chars("cro".chars()));
+        // The root and the node for "cro".
+        assert_eq!(2, t.rc_report(usize::MAX).anomalies.len());
+        drop(cursor);
+        assert!(t.rc_report(usize::MAX).is_clean());
+    }
+
+    #[test]
+    fn cursor_on_empty_trie() {
+        let t = NoParentLetterTrie::new();
+        let mut cursor = t.cursor();
+        assert!(cursor.step_chars("".chars()));
+        assert!(!cursor.step('a'));
+        assert_eq!(0, cursor.child_count());
+    }
+}
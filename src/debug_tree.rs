@@ -0,0 +1,228 @@
+//! The tree rendering behind `LetterTrie::describe_tree()`, with optional limits on how deep it goes and how many
+//! children of each node it shows so that even a big trie can be looked at.
+//!
+//! Anything left out is marked where it would have been: a node whose children were cut off at the depth limit is
+//! followed by the number of words below it, and a node with more children than the limit is followed by the
+//! number of children not shown.
+
+use crate::util::format_indent;
+use crate::*;
+
+// A node on the path from the root, with the number of its children seen so far.
+struct PathNode {
+    child_count: usize,
+    children_seen: usize,
+}
+
+fn plural<'a>(count: usize, one: &'a str, many: &'a str) -> &'a str {
+    if count == 1 {
+        one
+    } else {
+        many
+    }
+}
+
+// The number of words below a node, not counting the node itself.
+fn words_below<T: LetterTrie + ?Sized>(trie: &T, node: &NodeView) -> usize {
+    let word_count = if node.depth == 0 {
+        trie.to_fixed_node().word_count
+    } else {
+        trie.find(node.prefix).map_or(0, |found| found.word_count)
+    };
+    word_count - node.is_word as usize
+}
+
+// Leave the node at the end of the path, noting any of its children that weren't shown.
+fn leave(path: &mut Vec<PathNode>, max_children: Option<usize>, s: &mut String) {
+    let Some(node) = path.pop() else {
+        return;
+    };
+    if let Some(max_children) = max_children {
+        if node.child_count > max_children {
+            let hidden = node.child_count - max_children;
+            let line = format!(
+                "… {} more {}",
+                format_count(hidden),
+                plural(hidden, "child", "children")
+            );
+            s.push_str(&format_indent(path.len(), &line));
+            s.push('\n');
+        }
+    }
+}
+
+pub(crate) fn describe_tree<T: LetterTrie + ?Sized>(
+    trie: &T,
+    max_depth: Option<usize>,
+    max_children: Option<usize>,
+) -> String {
+    let mut s = String::new();
+    let mut path: Vec<PathNode> = vec![];
+    trie.visit("", &mut |node| {
+        while path.len() > node.depth {
+            leave(&mut path, max_children, &mut s);
+        }
+        if let Some(parent) = path.last_mut() {
+            parent.children_seen += 1;
+            if max_children.is_some_and(|max_children| parent.children_seen > max_children) {
+                return false;
+            }
+        }
+        if node.depth > 0 {
+            let word_desc = if node.is_word { " (word)" } else { "" };
+            let line = format!("{}{}", node.c, word_desc);
+            s.push_str(&format_indent(node.depth - 1, &line));
+            s.push('\n');
+        }
+        if node.child_count > 0 && max_depth.is_some_and(|max_depth| node.depth >= max_depth) {
+            let below = words_below(trie, node);
+            let line = format!(
+                "… subtree of {} {}",
+                format_count(below),
+                plural(below, "word", "words")
+            );
+            s.push_str(&format_indent(node.depth, &line));
+            s.push('\n');
+            return false;
+        }
+        path.push(PathNode {
+            child_count: node.child_count,
+            children_seen: 0,
+        });
+        true
+    });
+    while !path.is_empty() {
+        leave(&mut path, max_children, &mut s);
+    }
+    s
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    const SMALL_TREE: &str = "\
+a (word)
+    n (word)
+        d (word)
+    z
+        u
+            r
+                e (word)
+c
+    r
+        e
+            a
+                s
+                    e (word)
+                t
+                    i
+                        v
+                            e (word)
+                    o
+                        r (word)
+                    u
+                        r
+                            e (word)
+        o
+            s
+                s (word)
+                    e
+                        d (word)
+";
+
+    #[test]
+    fn small_untruncated() {
+        for t in [
+            Box::new(
+                BaseLetterTrie::from_dataset(&Dataset::TestSmallUnsorted, &LoadMethod::Continuous)
+                    .unwrap(),
+            ) as Box<dyn LetterTrie>,
+            Box::new(
+                NoParentLetterTrie::from_dataset(
+                    &Dataset::TestSmallSorted,
+                    &LoadMethod::Continuous,
+                )
+                .unwrap(),
+            ),
+        ] {
+            assert_eq!(SMALL_TREE, t.describe_tree(None, None));
+        }
+    }
+
+    #[test]
+    fn small_truncated() {
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+                .unwrap();
+        let expected = "\
+a (word)
+    … subtree of 3 words
+c
+    … subtree of 6 words
+";
+        assert_eq!(expected, t.describe_tree(Some(1), None));
+        let expected = "\
+a (word)
+    n (word)
+        d (word)
+    … 1 more child
+… 1 more child
+";
+        assert_eq!(expected, t.describe_tree(None, Some(1)));
+        assert_eq!("… 2 more children\n", t.describe_tree(None, Some(0)));
+        assert_eq!("… subtree of 10 words\n", t.describe_tree(Some(0), None));
+        assert!(NoParentLetterTrie::new()
+            .describe_tree(Some(2), Some(3))
+            .is_empty());
+    }
+
+    const MEDIUM_TREE: &str = "\
+a
+    b
+        … subtree of 113 words
+    c
+        … subtree of 182 words
+    d
+        … subtree of 201 words
+    … 14 more children
+b
+    a
+        … subtree of 134 words
+    e
+        … subtree of 243 words
+    i
+        … subtree of 58 words
+    … 4 more children
+c
+    a
+        … subtree of 229 words
+    e
+        … subtree of 72 words
+    h
+        … subtree of 282 words
+    … 6 more children
+… 22 more children
+";
+
+    // The same rendering however the trie was built.
+    #[test]
+    fn medium_truncated() {
+        for dataset in &[Dataset::TestMediumSorted, Dataset::TestMediumUnsorted] {
+            for load_method in &ALL_LOAD_METHODS {
+                for letter_trie_type in &[LetterTrieType::Base, LetterTrieType::NoParent] {
+                    let t = build_trie(
+                        letter_trie_type,
+                        TrieSource::File {
+                            filename: dataset.filename(),
+                            is_sorted: dataset.is_sorted(),
+                        },
+                        load_method,
+                    )
+                    .unwrap();
+                    assert_eq!(MEDIUM_TREE, t.describe_tree(Some(2), Some(3)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Folding letters with diacritics onto their base letters, so that a search for "jalapeno" can find "jalapeño".
+//!
+//! This uses a small built-in table of the letters in Latin-1 and Latin Extended-A that have a canonical
+//! decomposition into a base letter and a combining mark, rather than depending on a full Unicode normalization
+//! library. Letters without such a decomposition, such as "ø", "ł" and "ß", are left alone.
+
+use alloc::string::String;
+
+// Each letter with its base letter and combining mark, ordered by the letter so that it can be binary searched.
+const DECOMPOSITIONS: [(char, char, char); 161] = [
+    ('À', 'A', '\u{300}'),
+    ('Á', 'A', '\u{301}'),
+    ('Â', 'A', '\u{302}'),
+    ('Ã', 'A', '\u{303}'),
+    ('Ä', 'A', '\u{308}'),
+    ('Å', 'A', '\u{30a}'),
+    ('Ç', 'C', '\u{327}'),
+    ('È', 'E', '\u{300}'),
+    ('É', 'E', '\u{301}'),
+    ('Ê', 'E', '\u{302}'),
+    ('Ë', 'E', '\u{308}'),
+    ('Ì', 'I', '\u{300}'),
+    ('Í', 'I', '\u{301}'),
+    ('Î', 'I', '\u{302}'),
+    ('Ï', 'I', '\u{308}'),
+    ('Ñ', 'N', '\u{303}'),
+    ('Ò', 'O', '\u{300}'),
+    ('Ó', 'O', '\u{301}'),
+    ('Ô', 'O', '\u{302}'),
+    ('Õ', 'O', '\u{303}'),
+    ('Ö', 'O', '\u{308}'),
+    ('Ù', 'U', '\u{300}'),
+    ('Ú', 'U', '\u{301}'),
+    ('Û', 'U', '\u{302}'),
+    ('Ü', 'U', '\u{308}'),
+    ('Ý', 'Y', '\u{301}'),
+    ('à', 'a', '\u{300}'),
+    ('á', 'a', '\u{301}'),
+    ('â', 'a', '\u{302}'),
+    ('ã', 'a', '\u{303}'),
+    ('ä', 'a', '\u{308}'),
+    ('å', 'a', '\u{30a}'),
+    ('ç', 'c', '\u{327}'),
+    ('è', 'e', '\u{300}'),
+    ('é', 'e', '\u{301}'),
+    ('ê', 'e', '\u{302}'),
+    ('ë', 'e', '\u{308}'),
+    ('ì', 'i', '\u{300}'),
+    ('í', 'i', '\u{301}'),
+    ('î', 'i', '\u{302}'),
+    ('ï', 'i', '\u{308}'),
+    ('ñ', 'n', '\u{303}'),
+    ('ò', 'o', '\u{300}'),
+    ('ó', 'o', '\u{301}'),
+    ('ô', 'o', '\u{302}'),
+    ('õ', 'o', '\u{303}'),
+    ('ö', 'o', '\u{308}'),
+    ('ù', 'u', '\u{300}'),
+    ('ú', 'u', '\u{301}'),
+    ('û', 'u', '\u{302}'),
+    ('ü', 'u', '\u{308}'),
+    ('ý', 'y', '\u{301}'),
+    ('ÿ', 'y', '\u{308}'),
+    ('Ā', 'A', '\u{304}'),
+    ('ā', 'a', '\u{304}'),
+    ('Ă', 'A', '\u{306}'),
+    ('ă', 'a', '\u{306}'),
+    ('Ą', 'A', '\u{328}'),
+    ('ą', 'a', '\u{328}'),
+    ('Ć', 'C', '\u{301}'),
+    ('ć', 'c', '\u{301}'),
+    ('Ĉ', 'C', '\u{302}'),
+    ('ĉ', 'c', '\u{302}'),
+    ('Ċ', 'C', '\u{307}'),
+    ('ċ', 'c', '\u{307}'),
+    ('Č', 'C', '\u{30c}'),
+    ('č', 'c', '\u{30c}'),
+    ('Ď', 'D', '\u{30c}'),
+    ('ď', 'd', '\u{30c}'),
+    ('Ē', 'E', '\u{304}'),
+    ('ē', 'e', '\u{304}'),
+    ('Ĕ', 'E', '\u{306}'),
+    ('ĕ', 'e', '\u{306}'),
+    ('Ė', 'E', '\u{307}'),
+    ('ė', 'e', '\u{307}'),
+    ('Ę', 'E', '\u{328}'),
+    ('ę', 'e', '\u{328}'),
+    ('Ě', 'E', '\u{30c}'),
+    ('ě', 'e', '\u{30c}'),
+    ('Ĝ', 'G', '\u{302}'),
+    ('ĝ', 'g', '\u{302}'),
+    ('Ğ', 'G', '\u{306}'),
+    ('ğ', 'g', '\u{306}'),
+    ('Ġ', 'G', '\u{307}'),
+    ('ġ', 'g', '\u{307}'),
+    ('Ģ', 'G', '\u{327}'),
+    ('ģ', 'g', '\u{327}'),
+    ('Ĥ', 'H', '\u{302}'),
+    ('ĥ', 'h', '\u{302}'),
+    ('Ĩ', 'I', '\u{303}'),
+    ('ĩ', 'i', '\u{303}'),
+    ('Ī', 'I', '\u{304}'),
+    ('ī', 'i', '\u{304}'),
+    ('Ĭ', 'I', '\u{306}'),
+    ('ĭ', 'i', '\u{306}'),
+    ('Į', 'I', '\u{328}'),
+    ('į', 'i', '\u{328}'),
+    ('İ', 'I', '\u{307}'),
+    ('Ĵ', 'J', '\u{302}'),
+    ('ĵ', 'j', '\u{302}'),
+    ('Ķ', 'K', '\u{327}'),
+    ('ķ', 'k', '\u{327}'),
+    ('Ĺ', 'L', '\u{301}'),
+    ('ĺ', 'l', '\u{301}'),
+    ('Ļ', 'L', '\u{327}'),
+    ('ļ', 'l', '\u{327}'),
+    ('Ľ', 'L', '\u{30c}'),
+    ('ľ', 'l', '\u{30c}'),
+    ('Ń', 'N', '\u{301}'),
+    ('ń', 'n', '\u{301}'),
+    ('Ņ', 'N', '\u{327}'),
+    ('ņ', 'n', '\u{327}'),
+    ('Ň', 'N', '\u{30c}'),
+    ('ň', 'n', '\u{30c}'),
+    ('Ō', 'O', '\u{304}'),
+    ('ō', 'o', '\u{304}'),
+    ('Ŏ', 'O', '\u{306}'),
+    ('ŏ', 'o', '\u{306}'),
+    ('Ő', 'O', '\u{30b}'),
+    ('ő', 'o', '\u{30b}'),
+    ('Ŕ', 'R', '\u{301}'),
+    ('ŕ', 'r', '\u{301}'),
+    ('Ŗ', 'R', '\u{327}'),
+    ('ŗ', 'r', '\u{327}'),
+    ('Ř', 'R', '\u{30c}'),
+    ('ř', 'r', '\u{30c}'),
+    ('Ś', 'S', '\u{301}'),
+    ('ś', 's', '\u{301}'),
+    ('Ŝ', 'S', '\u{302}'),
+    ('ŝ', 's', '\u{302}'),
+    ('Ş', 'S', '\u{327}'),
+    ('ş', 's', '\u{327}'),
+    ('Š', 'S', '\u{30c}'),
+    ('š', 's', '\u{30c}'),
+    ('Ţ', 'T', '\u{327}'),
+    ('ţ', 't', '\u{327}'),
+    ('Ť', 'T', '\u{30c}'),
+    ('ť', 't', '\u{30c}'),
+    ('Ũ', 'U', '\u{303}'),
+    ('ũ', 'u', '\u{303}'),
+    ('Ū', 'U', '\u{304}'),
+    ('ū', 'u', '\u{304}'),
+    ('Ŭ', 'U', '\u{306}'),
+    ('ŭ', 'u', '\u{306}'),
+    ('Ů', 'U', '\u{30a}'),
+    ('ů', 'u', '\u{30a}'),
+    ('Ű', 'U', '\u{30b}'),
+    ('ű', 'u', '\u{30b}'),
+    ('Ų', 'U', '\u{328}'),
+    ('ų', 'u', '\u{328}'),
+    ('Ŵ', 'W', '\u{302}'),
+    ('ŵ', 'w', '\u{302}'),
+    ('Ŷ', 'Y', '\u{302}'),
+    ('ŷ', 'y', '\u{302}'),
+    ('Ÿ', 'Y', '\u{308}'),
+    ('Ź', 'Z', '\u{301}'),
+    ('ź', 'z', '\u{301}'),
+    ('Ż', 'Z', '\u{307}'),
+    ('ż', 'z', '\u{307}'),
+    ('Ž', 'Z', '\u{30c}'),
+    ('ž', 'z', '\u{30c}'),
+];
+
+// The combining diacritical marks block, which holds the marks used by decomposed (NFD) text.
+fn is_combining_mark(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+fn decompose(c: char) -> Option<(char, char)> {
+    DECOMPOSITIONS
+        .binary_search_by_key(&c, |(letter, _, _)| *letter)
+        .ok()
+        .map(|i| (DECOMPOSITIONS[i].1, DECOMPOSITIONS[i].2))
+}
+
+/// Replace each letter in the built-in table with its base letter and remove every combining mark, so that the
+/// composed (NFC) and decomposed (NFD) forms of a word fold to the same string. Case is kept.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::diacritics::fold_diacritics;
+///
+/// assert_eq!("jalapeno", fold_diacritics("jalape\u{f1}o"));
+/// assert_eq!("jalapeno", fold_diacritics("jalapen\u{303}o"));
+/// assert_eq!("Ecole", fold_diacritics("\u{c9}cole"));
+/// assert_eq!("stra\u{df}e", fold_diacritics("stra\u{df}e"));
+/// ```
+pub fn fold_diacritics(text: &str) -> String {
+    text.chars()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| decompose(c).map(|(base, _)| base).unwrap_or(c))
+        .collect()
+}
+
+/// Replace each base letter followed by a combining mark with the single letter from the built-in table, if there
+/// is one, so that decomposed (NFD) text comes out the same as composed (NFC) text. Marks that don't combine with
+/// the letter before them are kept as they are.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::diacritics::compose_diacritics;
+///
+/// assert_eq!("ni\u{f1}o", compose_diacritics("nin\u{303}o"));
+/// assert_eq!("ni\u{f1}o", compose_diacritics("ni\u{f1}o"));
+/// ```
+pub fn compose_diacritics(text: &str) -> String {
+    let mut composed = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let letter = chars.peek().and_then(|mark| {
+            DECOMPOSITIONS
+                .iter()
+                .find(|(_, base, m)| *base == c && m == mark)
+                .map(|(letter, _, _)| *letter)
+        });
+        match letter {
+            Some(letter) => {
+                composed.push(letter);
+                chars.next();
+            }
+            None => composed.push(c),
+        }
+    }
+    composed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(DECOMPOSITIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(DECOMPOSITIONS
+            .iter()
+            .all(|(_, base, mark)| base.is_ascii_alphabetic() && is_combining_mark(*mark)));
+    }
+
+    #[test]
+    fn composed_and_decomposed_agree() {
+        for (composed, decomposed) in &[
+            ("ni\u{f1}o", "nin\u{303}o"),
+            ("ca\u{f1}\u{f3}n", "can\u{303}o\u{301}n"),
+            ("\u{c5}ngstr\u{f6}m", "A\u{30a}ngstro\u{308}m"),
+            ("\u{10c}esk\u{fd}", "C\u{30c}esky\u{301}"),
+            ("\u{130}stanbul", "I\u{307}stanbul"),
+        ] {
+            assert_eq!(fold_diacritics(composed), fold_diacritics(decomposed));
+            assert_eq!(*composed, compose_diacritics(decomposed));
+        }
+        assert_eq!("Angstrom", fold_diacritics("\u{c5}ngstr\u{f6}m"));
+    }
+
+    #[test]
+    fn letters_without_decompositions_are_kept() {
+        assert_eq!(
+            "\u{f8}\u{142}\u{df}\u{e6}",
+            fold_diacritics("\u{f8}\u{142}\u{df}\u{e6}")
+        );
+        // A mark after a letter it doesn't combine with is kept by compose_diacritics() but removed by folding.
+        assert_eq!("q\u{303}", compose_diacritics("q\u{303}"));
+        assert_eq!("q", fold_diacritics("q\u{303}"));
+        assert_eq!("", fold_diacritics(""));
+    }
+}
@@ -0,0 +1,297 @@
+//! The comparison behind `LetterTrie::diff()`.
+//!
+//! Each trie is first flattened into its nodes in `visit()` order, with a hash of the subtree under each node
+//! worked out as the walk leaves it. The two lists of nodes are then walked side by side, and any subtree with the
+//! same hash and size in both is stepped over without looking inside, so two versions of a dictionary that only
+//! differ in a few places are compared in about the time it takes to walk each of them once.
+
+use core::cmp::Ordering;
+
+use crate::*;
+
+/// The words added and removed between two tries, from `LetterTrie::diff()`. Both lists are in alphabetical order.
+///
+/// The `Display` output lists every change in alphabetical order, one to a line, with `+` before an added word and
+/// `-` before a removed one.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let old = NoParentLetterTrie::from_words(vec!["cross", "crossed", "an"]);
+/// let new = BaseLetterTrie::from_words(vec!["cross", "crossing", "an", "and"]);
+/// let diff = old.diff(&new);
+/// assert_eq!(vec!["and", "crossing"], diff.added);
+/// assert_eq!(vec!["crossed"], diff.removed);
+/// assert_eq!("+and\n-crossed\n+crossing\n", diff.to_string());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrieDiff {
+    /// The words in the other trie that aren't in this one.
+    pub added: Vec<String>,
+    /// The words in this trie that aren't in the other one.
+    pub removed: Vec<String>,
+}
+
+impl TrieDiff {
+    /// Returns true if the two tries have the same words.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for TrieDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut added = self.added.iter().peekable();
+        let mut removed = self.removed.iter().peekable();
+        loop {
+            let take_added = match (added.peek(), removed.peek()) {
+                (None, None) => return Ok(()),
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (Some(a), Some(r)) => a <= r,
+            };
+            let (sign, word) = if take_added {
+                ('+', added.next())
+            } else {
+                ('-', removed.next())
+            };
+            if let Some(word) = word {
+                writeln!(f, "{}{}", sign, word)?;
+            }
+        }
+    }
+}
+
+// One node of a flattened trie.
+struct FlatNode {
+    c: char,
+    is_word: bool,
+    depth: usize,
+    // The number of nodes in the subtree, including this one, so the next sibling is this many places on.
+    len: usize,
+    hash: u64,
+}
+
+fn fnv(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+fn flatten<T: LetterTrie + ?Sized>(trie: &T) -> Vec<FlatNode> {
+    let mut nodes: Vec<FlatNode> = vec![];
+    // The index of each node on the path from the root, whose hash is still taking in its children's hashes.
+    let mut path: Vec<usize> = vec![];
+    let leave = |nodes: &mut Vec<FlatNode>, path: &mut Vec<usize>| {
+        let Some(index) = path.pop() else {
+            return;
+        };
+        let end = nodes.len();
+        let node = &mut nodes[index];
+        node.len = end - index;
+        let hash = node.hash;
+        if let Some(parent) = path.last() {
+            nodes[*parent].hash = fnv(nodes[*parent].hash, &hash.to_le_bytes());
+        }
+    };
+    trie.visit("", &mut |node| {
+        while path.len() > node.depth {
+            leave(&mut nodes, &mut path);
+        }
+        let mut bytes = [0u8; 5];
+        bytes[..4].copy_from_slice(&(node.c as u32).to_le_bytes());
+        bytes[4] = node.is_word as u8;
+        path.push(nodes.len());
+        nodes.push(FlatNode {
+            c: node.c,
+            is_word: node.is_word,
+            depth: node.depth,
+            len: 1,
+            hash: fnv(FNV_OFFSET_BASIS, &bytes),
+        });
+        true
+    });
+    while !path.is_empty() {
+        leave(&mut nodes, &mut path);
+    }
+    nodes
+}
+
+// Add every word in the subtree of nodes[start] to words, where prefix holds the letters above that node.
+fn push_words(nodes: &[FlatNode], start: usize, prefix: &mut String, words: &mut Vec<String>) {
+    let start_len = prefix.len();
+    let start_depth = nodes[start].depth;
+    // The length of the prefix after each node on the path from the start.
+    let mut lens: Vec<usize> = vec![];
+    for node in &nodes[start..start + nodes[start].len] {
+        lens.truncate(node.depth - start_depth);
+        prefix.truncate(lens.last().copied().unwrap_or(start_len));
+        prefix.push(node.c);
+        lens.push(prefix.len());
+        if node.is_word {
+            words.push(prefix.clone());
+        }
+    }
+    prefix.truncate(start_len);
+}
+
+pub(crate) fn diff<A, B>(this: &A, other: &B) -> TrieDiff
+where
+    A: LetterTrie + ?Sized,
+    B: LetterTrie + ?Sized,
+{
+    let a = flatten(this);
+    let b = flatten(other);
+    let mut diff = TrieDiff::default();
+    let mut prefix = String::new();
+    // Each entry is the next child to compare on each side, the end of the parent's subtree on each side, and the
+    // length of the parent's prefix.
+    let mut stack: Vec<(usize, usize, usize, usize, usize)> = vec![];
+    if a[0].hash != b[0].hash || a[0].len != b[0].len {
+        stack.push((1, a[0].len, 1, b[0].len, 0));
+    }
+    while let Some(top) = stack.last_mut() {
+        let (a_next, a_end, b_next, b_end, prefix_len) = *top;
+        prefix.truncate(prefix_len);
+        let order = match (a_next < a_end, b_next < b_end) {
+            (false, false) => {
+                stack.pop();
+                continue;
+            }
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (true, true) => a[a_next].c.cmp(&b[b_next].c),
+        };
+        match order {
+            Ordering::Less => {
+                top.0 += a[a_next].len;
+                push_words(&a, a_next, &mut prefix, &mut diff.removed);
+            }
+            Ordering::Greater => {
+                top.2 += b[b_next].len;
+                push_words(&b, b_next, &mut prefix, &mut diff.added);
+            }
+            Ordering::Equal => {
+                let (a_node, b_node) = (&a[a_next], &b[b_next]);
+                top.0 += a_node.len;
+                top.2 += b_node.len;
+                if a_node.hash == b_node.hash && a_node.len == b_node.len {
+                    continue;
+                }
+                prefix.push(a_node.c);
+                match (a_node.is_word, b_node.is_word) {
+                    (true, false) => diff.removed.push(prefix.clone()),
+                    (false, true) => diff.added.push(prefix.clone()),
+                    _ => {}
+                }
+                stack.push((
+                    a_next + 1,
+                    a_next + a_node.len,
+                    b_next + 1,
+                    b_next + b_node.len,
+                    prefix.len(),
+                ));
+            }
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn medium_changes() {
+        let filename = Dataset::TestMediumSorted.filename();
+        let old = NoParentLetterTrie::from_file(filename, true, &LoadMethod::Continuous).unwrap();
+        let mut new = BaseLetterTrie::from_file(filename, true, &LoadMethod::Continuous).unwrap();
+        assert!(old.diff(&new).is_empty());
+
+        let words: Vec<String> = old.words().collect();
+        let removed: Vec<String> = vec![
+            words[0].clone(),
+            words[1_234].clone(),
+            words[10_000].clone(),
+            words[words.len() - 1].clone(),
+        ];
+        for word in &removed {
+            assert!(new.remove(word));
+        }
+        let mut added: Vec<String> = vec![
+            "zzzzzzzz".to_owned(),
+            format!("{}q", words[500]),
+            "ñandú".to_owned(),
+            "a".to_owned(),
+        ];
+        added.retain(|word| !old.contains(word));
+        assert_eq!(4, added.len());
+        for word in &added {
+            new.insert(word);
+        }
+        added.sort();
+
+        let diff = old.diff(&new);
+        assert_eq!(added, diff.added);
+        assert_eq!(removed, diff.removed);
+
+        let reverse = new.diff(&old);
+        assert_eq!(diff.added, reverse.removed);
+        assert_eq!(diff.removed, reverse.added);
+
+        let lines: Vec<String> = diff.to_string().lines().map(str::to_owned).collect();
+        assert_eq!(8, lines.len());
+        let mut sorted = lines.clone();
+        sorted.sort_by(|a, b| a[1..].cmp(&b[1..]));
+        assert_eq!(sorted, lines);
+        assert!(lines.contains(&format!("-{}", removed[1])));
+        assert!(lines.contains(&"+zzzzzzzz".to_owned()));
+    }
+
+    #[test]
+    fn empty_and_prefixes() {
+        let empty = NoParentLetterTrie::new();
+        let t = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crossing"]);
+        assert_eq!(vec!["cross", "crossed", "crossing"], empty.diff(&t).added);
+        assert_eq!(t.words().collect::<Vec<_>>(), t.diff(&empty).removed);
+        assert!(empty.diff(&empty).to_string().is_empty());
+
+        // The same nodes with a different word flag.
+        let other = NoParentLetterTrie::from_words(vec!["crossed", "crossing", "cros"]);
+        let diff = t.diff(&other);
+        assert_eq!(vec!["cros"], diff.added);
+        assert_eq!(vec!["cross"], diff.removed);
+    }
+
+    // The walk keeps its own stack, so a very long word doesn't overflow the call stack.
+    #[test]
+    fn deep_chain() {
+        let word = "ab".repeat(50_000);
+        let t = BaseLetterTrie::from_words(vec![word.as_str()]);
+        let other = BaseLetterTrie::from_words(vec![word.as_str(), "abc"]);
+        let diff = t.diff(&other);
+        assert_eq!(vec!["abc"], diff.added);
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            vec![word, "abc".to_owned()],
+            other.diff(&BaseLetterTrie::new()).removed
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let diff = TrieDiff {
+            added: vec!["and".to_owned()],
+            removed: vec!["crossed".to_owned()],
+        };
+        let json = serde_json::to_string(&diff).unwrap();
+        assert_eq!(r#"{"added":["and"],"removed":["crossed"]}"#, json);
+        assert_eq!(diff, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,310 @@
+//! The check behind `DisplayDetailOptions::duplicate_policy`.
+//!
+//! The source is wrapped in a reader that looks at each line as the load method reads past it, so every load
+//! method is checked the same way without knowing about it: the lines are seen on the thread that reads them
+//! before they're split up by first letter, sorted, or written to chunk files. When a word turns up a second time
+//! under `DuplicatePolicy::Error` the next read fails, and the error from the load method is swapped for the
+//! `LetterTrieError::DuplicateWord` that caused it.
+
+use std::collections::HashMap;
+
+use crate::*;
+
+// What's been seen of the lines so far.
+struct LineState {
+    policy: DuplicatePolicy,
+    // The part of the current line read so far.
+    line: Vec<u8>,
+    line_number: usize,
+    // The line each word was first read on and the number of times it's been read.
+    words: HashMap<String, (usize, u64)>,
+    error: Option<LetterTrieError>,
+}
+
+impl LineState {
+    fn end_line(&mut self) {
+        self.line_number += 1;
+        let line = core::mem::take(&mut self.line);
+        if self.error.is_some() {
+            return;
+        }
+        // A line that isn't UTF-8 is left to the load method, which fails on it.
+        let Ok(word) = core::str::from_utf8(&line) else {
+            return;
+        };
+        let word = word.trim();
+        if word.is_empty() {
+            return;
+        }
+        let word = word.to_lowercase();
+        let line_number = self.line_number;
+        match self.words.get_mut(&word) {
+            Some((first_line, count)) => {
+                if self.policy == DuplicatePolicy::Error {
+                    self.error = Some(LetterTrieError::DuplicateWord {
+                        word,
+                        first_line: *first_line,
+                        second_line: line_number,
+                    });
+                } else {
+                    *count += 1;
+                }
+            }
+            None => {
+                self.words.insert(word, (line_number, 1));
+            }
+        }
+    }
+
+    fn stopped(&self) -> io::Result<()> {
+        match self.error {
+            Some(_) => Err(io::Error::other("stopped at a duplicate word")),
+            None => Ok(()),
+        }
+    }
+}
+
+pub(crate) struct DuplicateCheck<R> {
+    reader: R,
+    state: LineState,
+}
+
+impl<R: BufRead> DuplicateCheck<R> {
+    fn new(reader: R, policy: DuplicatePolicy, word_capacity: usize) -> Self {
+        Self {
+            reader,
+            state: LineState {
+                policy,
+                line: vec![],
+                line_number: 0,
+                words: HashMap::with_capacity(word_capacity),
+                error: None,
+            },
+        }
+    }
+}
+
+impl<R: BufRead> io::Read for DuplicateCheck<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for DuplicateCheck<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.state.stopped()?;
+        if self.reader.fill_buf()?.is_empty() && !self.state.line.is_empty() {
+            // The last line didn't end with a newline.
+            self.state.end_line();
+            self.state.stopped()?;
+        }
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // The bytes being consumed are the start of what the last fill_buf() gave, which the inner reader gives
+        // again until they're consumed.
+        if let Ok(available) = self.reader.fill_buf() {
+            for part in available[..amt].split_inclusive(|byte| *byte == b'\n') {
+                self.state.line.extend_from_slice(part);
+                if part.last() == Some(&b'\n') {
+                    self.state.end_line();
+                }
+            }
+        }
+        self.reader.consume(amt);
+    }
+}
+
+// Run a load through a DuplicateCheck under any policy but Ignore. Under Count each word's tag is set to the
+// number of times it was read once the load has finished.
+pub(crate) fn load_checking_duplicates<T, R, F>(
+    reader: R,
+    policy: DuplicatePolicy,
+    opt: &DisplayDetailOptions,
+    load: F,
+) -> Result<T>
+where
+    T: LetterTrie,
+    R: BufRead,
+    F: FnOnce(&mut DuplicateCheck<R>) -> Result<T>,
+{
+    let word_capacity = opt.capacity_hints().map_or(0, |hints| hints.words());
+    let mut check = DuplicateCheck::new(reader, policy, word_capacity);
+    let result = load(&mut check);
+    if let Some(err) = check.state.error {
+        return Err(err);
+    }
+    let mut t = result?;
+    if policy == DuplicatePolicy::Count {
+        for (word, (_, count)) in check.state.words {
+            t.insert_with_tag(&word, count);
+        }
+    }
+    Ok(t)
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // The fixture has "cross" on lines 2 and 6, "an" on lines 1, 5 and 10, and a blank line 4 and 8.
+    const FIXTURE: &str = "an\ncross\ncrossed\n\nAn\n  CROSS  \ncrossing\n\nand\nan";
+
+    fn all_methods() -> Vec<LoadMethod> {
+        let mut methods = Vec::from(ALL_LOAD_METHODS);
+        methods.push(LoadMethod::External {
+            temp_dir: env::temp_dir(),
+            chunk_words: 2,
+        });
+        methods
+    }
+
+    fn load<T: LetterTrie>(
+        source: &str,
+        load_method: &LoadMethod,
+        policy: DuplicatePolicy,
+        read_ahead_lines: usize,
+    ) -> Result<T> {
+        let opt = DisplayDetailOptions::make_no_display()
+            .with_duplicate_policy(policy)
+            .with_read_ahead_lines(read_ahead_lines);
+        T::from_reader_test(source.as_bytes(), false, load_method, &opt, None)
+    }
+
+    fn check_policies<T: LetterTrie>() {
+        let expected = vec!["an", "and", "cross", "crossed", "crossing"];
+        for load_method in &all_methods() {
+            for read_ahead_lines in [0, 3] {
+                let t: T = load(
+                    FIXTURE,
+                    load_method,
+                    DuplicatePolicy::Ignore,
+                    read_ahead_lines,
+                )
+                .unwrap();
+                assert_eq!(expected, t.words().collect::<Vec<_>>());
+                assert_eq!(None, t.tag("an"));
+
+                let t: T = load(
+                    FIXTURE,
+                    load_method,
+                    DuplicatePolicy::Count,
+                    read_ahead_lines,
+                )
+                .unwrap();
+                assert_eq!(expected, t.words().collect::<Vec<_>>());
+                let counts: Vec<Option<u64>> = expected.iter().map(|word| t.tag(word)).collect();
+                assert_eq!(vec![Some(3), Some(1), Some(2), Some(1), Some(1)], counts);
+
+                let result: Result<T, _> = load(
+                    FIXTURE,
+                    load_method,
+                    DuplicatePolicy::Error,
+                    read_ahead_lines,
+                );
+                match result {
+                    Err(LetterTrieError::DuplicateWord {
+                        word,
+                        first_line,
+                        second_line,
+                    }) => assert_eq!(("an", 1, 5), (word.as_str(), first_line, second_line)),
+                    _ => panic!("{:?} didn't find the duplicate", load_method),
+                }
+
+                // The last line is checked even without a newline after it.
+                let result: Result<T, _> = load(
+                    "cross\nan\ncross",
+                    load_method,
+                    DuplicatePolicy::Error,
+                    read_ahead_lines,
+                );
+                assert!(matches!(
+                    result,
+                    Err(LetterTrieError::DuplicateWord {
+                        first_line: 1,
+                        second_line: 3,
+                        ..
+                    })
+                ));
+                let t: T = load(
+                    "cross\r\ncrossed\r\n",
+                    load_method,
+                    DuplicatePolicy::Error,
+                    read_ahead_lines,
+                )
+                .unwrap();
+                assert_eq!(vec!["cross", "crossed"], t.words().collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn policies_base() {
+        check_policies::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn policies_no_parent() {
+        check_policies::<NoParentLetterTrie>();
+    }
+
+    // A file with no repeats loads the same under each policy, and a repeat near the end of a large unsorted file
+    // is still found when the parallel load splits the words up by first letter.
+    #[test]
+    #[ignore = "needs the large dataset files"]
+    fn large_file() {
+        let dataset = Dataset::TestLargeUnsorted;
+        let opt =
+            DisplayDetailOptions::make_no_display().with_duplicate_policy(DuplicatePolicy::Error);
+        let t = BaseLetterTrie::from_dataset_with_options(
+            &dataset,
+            &LoadMethod::ContinuousParallel,
+            &opt,
+        )
+        .unwrap();
+        assert_eq!(dataset.expected_word_count(), t.to_fixed_node().word_count);
+
+        let mut content = fs::read_to_string(dataset.filename()).unwrap();
+        let first_word = content.lines().next().unwrap().trim().to_owned();
+        let line_count = content.lines().count();
+        content.push_str(&first_word.to_uppercase());
+        content.push('\n');
+        let result = BaseLetterTrie::from_reader_test(
+            content.as_bytes(),
+            dataset.is_sorted(),
+            &LoadMethod::ContinuousParallel,
+            &opt,
+            None,
+        );
+        match result {
+            Err(LetterTrieError::DuplicateWord {
+                word,
+                first_line,
+                second_line,
+            }) => assert_eq!(
+                (first_word, 1, line_count + 1),
+                (word, first_line, second_line)
+            ),
+            _ => panic!("the duplicate wasn't found"),
+        }
+    }
+
+    #[test]
+    fn display() {
+        let err = LetterTrieError::DuplicateWord {
+            word: "cross".to_owned(),
+            first_line: 2,
+            second_line: 6,
+        };
+        assert_eq!(
+            "Duplicate word: \"cross\" on line 2 and again on line 6",
+            err.to_string()
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! `LoadMethod::External`, which loads a word list that's too big to hold alongside the trie.
+//!
+//! The file is read a chunk at a time. Each chunk is built into a small trie and saved in the binary format to a
+//! file in the temporary directory, so only one chunk's trie is in memory at once. The chunk files are then merged
+//! two at a time by reading both as streams of words in alphabetical order and writing the union straight back out
+//! without building anything, until one file is left. That file is read into the trie. Each file is deleted as soon
+//! as it has been merged, and whatever is left is deleted if the load fails part of the way through.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::binary::{self, BinaryWords, SortedWordsWriter};
+use crate::*;
+
+// Tells the files of loads running at the same time apart.
+static LOAD_ID: AtomicUsize = AtomicUsize::new(0);
+
+// The chunk files of one load, which are deleted when this is dropped whether or not the load finished.
+struct TempFiles {
+    dir: PathBuf,
+    load_id: usize,
+    files_made: usize,
+    paths: Vec<PathBuf>,
+}
+
+impl TempFiles {
+    fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_owned(),
+            load_id: LOAD_ID.fetch_add(1, Ordering::Relaxed),
+            files_made: 0,
+            paths: vec![],
+        }
+    }
+
+    // Create the next file. It's tracked before it's created so that even a partly written file is cleaned up.
+    fn create(&mut self) -> Result<(PathBuf, File)> {
+        let path = self.dir.join(format!(
+            "letter_trie_chunk_{}_{}_{}.ltrie",
+            process::id(),
+            self.load_id,
+            self.files_made
+        ));
+        self.files_made += 1;
+        self.paths.push(path.clone());
+        let file = File::create(&path).map_err(in_file(&path))?;
+        Ok((path, file))
+    }
+
+    fn remove(&mut self, path: &Path) -> Result<()> {
+        self.paths.retain(|p| p != path);
+        fs::remove_file(path).map_err(in_file(path))?;
+        Ok(())
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn open_words(path: &Path) -> Result<BinaryWords<BufReader<File>>> {
+    BinaryWords::new(BufReader::new(File::open(path).map_err(in_file(path))?))
+        .map_err(in_file(path))
+}
+
+// Write the words that are in either file to a new file, each once.
+fn merge_files(temp_files: &mut TempFiles, a: &Path, b: &Path) -> Result<PathBuf> {
+    let mut a_words = open_words(a)?;
+    let mut b_words = open_words(b)?;
+    let (path, file) = temp_files.create()?;
+    let mut writer = SortedWordsWriter::new(BufWriter::new(file))?;
+    let mut a_word = a_words.next().transpose()?;
+    let mut b_word = b_words.next().transpose()?;
+    loop {
+        let word = match (a_word.take(), b_word.take()) {
+            (None, None) => break,
+            (Some(a), Some(b)) if a.word == b.word => {
+                a_word = a_words.next().transpose()?;
+                b_word = b_words.next().transpose()?;
+                a.word
+            }
+            (Some(a), Some(b)) if a.word > b.word => {
+                a_word = Some(a);
+                b_word = b_words.next().transpose()?;
+                b.word
+            }
+            (Some(a), b) => {
+                a_word = a_words.next().transpose()?;
+                b_word = b;
+                a.word
+            }
+            (None, Some(b)) => {
+                b_word = b_words.next().transpose()?;
+                b.word
+            }
+        };
+        writer.add(&word)?;
+    }
+    writer.finish()?;
+    Ok(path)
+}
+
+pub(crate) fn load_external<T: LetterTrie, R: BufRead>(
+    reader: R,
+    temp_dir: &Path,
+    chunk_words: usize,
+    opt: &DisplayDetailOptions,
+    expected_word_count: Option<usize>,
+) -> Result<T> {
+    let chunk_words = chunk_words.max(1);
+    let mut temp_files = TempFiles::new(temp_dir);
+    let mut chunk_paths: Vec<PathBuf> = vec![];
+    let mut word_count = 0;
+
+    let timer = Timer::new(LABEL_STEP_WRITE_CHUNKS, opt);
+    let mut write_chunk = |chunk: &mut Vec<String>| -> Result<()> {
+        let t = T::from_words(chunk.drain(..));
+        let (path, file) = temp_files.create()?;
+        binary::write_trie(&t, file).map_err(in_file(&path))?;
+        chunk_paths.push(path);
+        Ok(())
+    };
+    let mut chunk: Vec<String> = Vec::with_capacity(chunk_words);
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        word_count += 1;
+        chunk.push(line.to_lowercase());
+        if chunk.len() == chunk_words {
+            write_chunk(&mut chunk)?;
+        }
+    }
+    if !chunk.is_empty() {
+        write_chunk(&mut chunk)?;
+    }
+    timer.stop();
+    if let Some(exp_word_count) = expected_word_count {
+        assert_eq!(word_count, exp_word_count);
+    }
+
+    // Merge the oldest two files each time so that the files being merged stay about the same size.
+    let timer = Timer::new(LABEL_STEP_MERGE_CHUNKS, opt);
+    let mut next = 0;
+    while chunk_paths.len() - next > 1 {
+        let path = merge_files(&mut temp_files, &chunk_paths[next], &chunk_paths[next + 1])?;
+        temp_files.remove(&chunk_paths[next])?;
+        temp_files.remove(&chunk_paths[next + 1])?;
+        next += 2;
+        chunk_paths.push(path);
+    }
+    timer.stop();
+
+    let timer = Timer::new(LABEL_STEP_LOAD_MERGED_CHUNKS, opt);
+    let mut t = T::from_words(core::iter::empty::<&str>());
+    if let Some(path) = chunk_paths.get(next) {
+        for word in open_words(path)? {
+            t.insert(&word.map_err(in_file(path))?.word);
+        }
+        temp_files.remove(path)?;
+    }
+    timer.stop();
+    opt.print_trie(&t);
+    Ok(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Read};
+
+    fn is_empty_dir(dir: &Path) -> bool {
+        fs::read_dir(dir).unwrap().next().is_none()
+    }
+
+    #[test]
+    fn matches_continuous() {
+        // An empty directory of its own, so that the test can check that nothing was left behind.
+        let dir = temp_path("external_matches");
+        fs::create_dir_all(&dir).unwrap();
+        let dataset = Dataset::TestMediumUnsorted;
+        let load_method = LoadMethod::External {
+            temp_dir: dir.to_path_buf(),
+            chunk_words: 1_000,
+        };
+        let expected = NoParentLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        let expected = words_fingerprint(expected.words());
+        let t = BaseLetterTrie::from_dataset(&dataset, &load_method).unwrap();
+        assert_eq!(expected, words_fingerprint(t.words()));
+        assert_eq!(dataset.expected_word_count(), t.to_fixed_node().word_count);
+        let t = NoParentLetterTrie::from_dataset(&dataset, &load_method).unwrap();
+        assert_eq!(expected, words_fingerprint(t.words()));
+        assert!(is_empty_dir(&dir));
+    }
+
+    #[test]
+    fn repeats_across_chunks() {
+        let dir = temp_path("external_repeats");
+        fs::create_dir_all(&dir).unwrap();
+        let text = "cross\nAn\n\ncrossed\n an\ncross\ncrossing\nand\ncrossed\n";
+        for chunk_words in 0..10 {
+            let load_method = LoadMethod::External {
+                temp_dir: dir.to_path_buf(),
+                chunk_words,
+            };
+            let t = NoParentLetterTrie::from_reader(text.as_bytes(), false, &load_method).unwrap();
+            assert_eq!(
+                vec!["an", "and", "cross", "crossed", "crossing"],
+                t.words().collect::<Vec<_>>()
+            );
+            assert!(is_empty_dir(&dir));
+        }
+        let load_method = LoadMethod::External {
+            temp_dir: dir.to_path_buf(),
+            chunk_words: 2,
+        };
+        let t = BaseLetterTrie::from_reader("\n \n".as_bytes(), false, &load_method).unwrap();
+        assert_eq!(0, t.to_fixed_node().word_count);
+        assert!(is_empty_dir(&dir));
+    }
+
+    // A reader that fails after giving out some lines.
+    struct FailingReader {
+        text: &'static [u8],
+    }
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.text.is_empty() {
+                return Err(io::Error::other("the disk went away"));
+            }
+            let len = buf.len().min(self.text.len());
+            buf[..len].copy_from_slice(&self.text[..len]);
+            self.text = &self.text[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn error_removes_files() {
+        let dir = temp_path("external_error");
+        fs::create_dir_all(&dir).unwrap();
+        let load_method = LoadMethod::External {
+            temp_dir: dir.to_path_buf(),
+            chunk_words: 1,
+        };
+        let reader = BufReader::new(FailingReader {
+            text: b"cross\ncrossed\ncrossing\nan\n",
+        });
+        let result = BaseLetterTrie::from_reader(reader, false, &load_method);
+        assert!(matches!(result, Err(LetterTrieError::Io(_))));
+        assert!(is_empty_dir(&dir));
+
+        // A directory that isn't there fails at the first chunk.
+        let missing = dir.join("missing");
+        let load_method = LoadMethod::External {
+            temp_dir: missing,
+            chunk_words: 1,
+        };
+        let result = NoParentLetterTrie::from_reader("cross\n".as_bytes(), false, &load_method);
+        match result {
+            Err(LetterTrieError::File { path, .. }) => assert!(path.starts_with(&dir)),
+            other => panic!("{:?}", other.err()),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! A C interface to `NoParentLetterTrie` for embedding the trie in applications written in other languages.
+//!
+//! Every function returns one of the `LETTER_TRIE_*` status codes and passes results back through out-parameters.
+//! The trie itself is an opaque pointer created by `letter_trie_new_from_file()` and released with
+//! `letter_trie_free()`. Strings coming in must be NUL-terminated UTF-8; anything else is reported with
+//! `LETTER_TRIE_ERR_INVALID_UTF8` rather than a crash, and a panic inside the library is caught and reported as
+//! `LETTER_TRIE_ERR_PANIC` so it never unwinds into the caller.
+//!
+//! The header in `include/letter_trie.h` is generated with cbindgen from this file. To build the shared library:
+//!
+//! ```text
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::BufReader;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::*;
+
+/// The call succeeded.
+pub const LETTER_TRIE_OK: c_int = 0;
+/// A required pointer argument was null.
+pub const LETTER_TRIE_ERR_NULL_POINTER: c_int = 1;
+/// A string argument was not valid UTF-8.
+pub const LETTER_TRIE_ERR_INVALID_UTF8: c_int = 2;
+/// The word file couldn't be opened or read.
+pub const LETTER_TRIE_ERR_IO: c_int = 3;
+/// A word in the trie contains a NUL character so it can't be returned as a C string.
+pub const LETTER_TRIE_ERR_INTERIOR_NUL: c_int = 4;
+/// The library panicked. The trie passed in, if any, should not be used again except to free it.
+pub const LETTER_TRIE_ERR_PANIC: c_int = 5;
+
+// Run the body of an exported function, turning a panic into an error code instead of unwinding across the FFI
+// boundary.
+fn catch_panic<F: FnOnce() -> c_int>(f: F) -> c_int {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(LETTER_TRIE_ERR_PANIC)
+}
+
+// Borrow a C string argument as a &str.
+unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str, c_int> {
+    if s.is_null() {
+        return Err(LETTER_TRIE_ERR_NULL_POINTER);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| LETTER_TRIE_ERR_INVALID_UTF8)
+}
+
+/// Create a trie from a word file with one word per line and store it in `*out_trie`.
+///
+/// The trie must eventually be released with `letter_trie_free()`. On failure `*out_trie` is set to null.
+///
+/// # Safety
+///
+/// `path` must be null or point to a NUL-terminated string, and `out_trie` must be null or point to writable
+/// memory for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn letter_trie_new_from_file(
+    path: *const c_char,
+    out_trie: *mut *mut NoParentLetterTrie,
+) -> c_int {
+    catch_panic(|| {
+        if out_trie.is_null() {
+            return LETTER_TRIE_ERR_NULL_POINTER;
+        }
+        *out_trie = ptr::null_mut();
+        let path = match str_arg(path) {
+            Ok(path) => path,
+            Err(code) => return code,
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return LETTER_TRIE_ERR_IO,
+        };
+        match NoParentLetterTrie::from_reader(BufReader::new(file), false, &LoadMethod::Continuous)
+        {
+            Ok(trie) => {
+                *out_trie = Box::into_raw(Box::new(trie));
+                LETTER_TRIE_OK
+            }
+            Err(_) => LETTER_TRIE_ERR_IO,
+        }
+    })
+}
+
+/// Set `*out_found` to whether `word` is a whole word in the trie.
+///
+/// # Safety
+///
+/// `trie` must be null or a pointer returned by `letter_trie_new_from_file()` that hasn't been freed. `word` must
+/// be null or point to a NUL-terminated string, and `out_found` must be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn letter_trie_contains(
+    trie: *const NoParentLetterTrie,
+    word: *const c_char,
+    out_found: *mut bool,
+) -> c_int {
+    catch_panic(|| {
+        if trie.is_null() || out_found.is_null() {
+            return LETTER_TRIE_ERR_NULL_POINTER;
+        }
+        let word = match str_arg(word) {
+            Ok(word) => word,
+            Err(code) => return code,
+        };
+        *out_found = (*trie).contains(word);
+        LETTER_TRIE_OK
+    })
+}
+
+/// Get up to `max_count` completions of `prefix`, shortest first, as a heap-allocated array of C strings.
+///
+/// On success `*out_words` points to an array of `*out_count` strings that must be released with a single call to
+/// `letter_trie_free_words()`. When there are no completions `*out_words` is null and `*out_count` is zero.
+///
+/// # Safety
+///
+/// `trie` must be null or a live pointer returned by `letter_trie_new_from_file()`. `prefix` must be null or point
+/// to a NUL-terminated string, and `out_words` and `out_count` must be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn letter_trie_suggest(
+    trie: *const NoParentLetterTrie,
+    prefix: *const c_char,
+    max_count: usize,
+    out_words: *mut *mut *mut c_char,
+    out_count: *mut usize,
+) -> c_int {
+    catch_panic(|| {
+        if trie.is_null() || out_words.is_null() || out_count.is_null() {
+            return LETTER_TRIE_ERR_NULL_POINTER;
+        }
+        *out_words = ptr::null_mut();
+        *out_count = 0;
+        let prefix = match str_arg(prefix) {
+            Ok(prefix) => prefix,
+            Err(code) => return code,
+        };
+        let mut c_words: Vec<*mut c_char> = vec![];
+        for word in (*trie).suggest(prefix, max_count) {
+            match CString::new(word) {
+                Ok(c_word) => c_words.push(c_word.into_raw()),
+                Err(_) => {
+                    free_c_words(c_words);
+                    return LETTER_TRIE_ERR_INTERIOR_NUL;
+                }
+            }
+        }
+        if !c_words.is_empty() {
+            *out_count = c_words.len();
+            *out_words = Box::into_raw(c_words.into_boxed_slice()) as *mut *mut c_char;
+        }
+        LETTER_TRIE_OK
+    })
+}
+
+/// Release an array of words returned by `letter_trie_suggest()`. Passing null does nothing.
+///
+/// # Safety
+///
+/// `words` and `count` must be exactly the values that `letter_trie_suggest()` produced, and the array must not be
+/// used or freed again afterward.
+#[no_mangle]
+pub unsafe extern "C" fn letter_trie_free_words(words: *mut *mut c_char, count: usize) {
+    if words.is_null() {
+        return;
+    }
+    let words: Box<[*mut c_char]> = Box::from_raw(ptr::slice_from_raw_parts_mut(words, count));
+    free_c_words(words.into_vec());
+}
+
+/// Release a trie created by `letter_trie_new_from_file()`. Passing null does nothing.
+///
+/// # Safety
+///
+/// `trie` must be null or a pointer returned by `letter_trie_new_from_file()` that hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn letter_trie_free(trie: *mut NoParentLetterTrie) {
+    if !trie.is_null() {
+        // Dropping a trie doesn't panic but the result is ignored anyway so nothing can unwind into C.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(trie))));
+    }
+}
+
+fn free_c_words(c_words: Vec<*mut c_char>) {
+    for c_word in c_words {
+        // Safety: every entry came from CString::into_raw().
+        unsafe { drop(CString::from_raw(c_word)) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    // Call the functions the way a C program would: raw NUL-terminated strings and out-parameters.
+    unsafe fn new_from_file(path: &str) -> *mut NoParentLetterTrie {
+        let path = CString::new(path).unwrap();
+        let mut trie: *mut NoParentLetterTrie = ptr::null_mut();
+        assert_eq!(
+            LETTER_TRIE_OK,
+            letter_trie_new_from_file(path.as_ptr(), &mut trie)
+        );
+        assert!(!trie.is_null());
+        trie
+    }
+
+    unsafe fn suggest(
+        trie: *const NoParentLetterTrie,
+        prefix: &str,
+        max_count: usize,
+    ) -> Vec<String> {
+        let prefix = CString::new(prefix).unwrap();
+        let mut words: *mut *mut c_char = ptr::null_mut();
+        let mut count: usize = 0;
+        assert_eq!(
+            LETTER_TRIE_OK,
+            letter_trie_suggest(trie, prefix.as_ptr(), max_count, &mut words, &mut count)
+        );
+        let v = (0..count)
+            .map(|i| CStr::from_ptr(*words.add(i)).to_str().unwrap().to_owned())
+            .collect();
+        letter_trie_free_words(words, count);
+        v
+    }
+
+    #[test]
+    fn drive_like_c() {
+        unsafe {
+            let trie = new_from_file(Dataset::TestSmallUnsorted.filename());
+
+            let mut found = false;
+            let word = CString::new("creature").unwrap();
+            assert_eq!(
+                LETTER_TRIE_OK,
+                letter_trie_contains(trie, word.as_ptr(), &mut found)
+            );
+            assert!(found);
+            let word = CString::new("creatur").unwrap();
+            assert_eq!(
+                LETTER_TRIE_OK,
+                letter_trie_contains(trie, word.as_ptr(), &mut found)
+            );
+            assert!(!found);
+
+            assert_eq!(suggest(trie, "cr", 3), vec!["cross", "crease", "creator"]);
+            assert!(suggest(trie, "zz", 3).is_empty());
+
+            letter_trie_free(trie);
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        unsafe {
+            let trie = new_from_file(Dataset::TestSmallSorted.filename());
+            let bad: &[u8] = b"cr\xffoss\0";
+            let mut found = true;
+            assert_eq!(
+                LETTER_TRIE_ERR_INVALID_UTF8,
+                letter_trie_contains(trie, bad.as_ptr() as *const c_char, &mut found)
+            );
+            let mut words: *mut *mut c_char = ptr::null_mut();
+            let mut count: usize = 0;
+            assert_eq!(
+                LETTER_TRIE_ERR_INVALID_UTF8,
+                letter_trie_suggest(
+                    trie,
+                    bad.as_ptr() as *const c_char,
+                    3,
+                    &mut words,
+                    &mut count
+                )
+            );
+            assert!(words.is_null());
+            letter_trie_free(trie);
+        }
+    }
+
+    #[test]
+    fn null_and_missing_file() {
+        unsafe {
+            let mut trie: *mut NoParentLetterTrie = ptr::null_mut();
+            assert_eq!(
+                LETTER_TRIE_ERR_NULL_POINTER,
+                letter_trie_new_from_file(ptr::null(), &mut trie)
+            );
+            let path = CString::new("no_such_file.txt").unwrap();
+            assert_eq!(
+                LETTER_TRIE_ERR_IO,
+                letter_trie_new_from_file(path.as_ptr(), &mut trie)
+            );
+            assert!(trie.is_null());
+            let mut found = false;
+            assert_eq!(
+                LETTER_TRIE_ERR_NULL_POINTER,
+                letter_trie_contains(ptr::null(), path.as_ptr(), &mut found)
+            );
+            letter_trie_free(ptr::null_mut());
+            letter_trie_free_words(ptr::null_mut(), 0);
+        }
+    }
+
+    #[test]
+    fn header_is_up_to_date() {
+        let crate_dir = env!("CARGO_MANIFEST_DIR");
+        let config =
+            cbindgen::Config::from_file(Path::new(crate_dir).join("cbindgen.toml")).unwrap();
+        let mut generated: Vec<u8> = vec![];
+        cbindgen::Builder::new()
+            .with_crate(crate_dir)
+            .with_config(config)
+            .generate()
+            .unwrap()
+            .write(&mut generated);
+        let committed = std::fs::read(Path::new(crate_dir).join("include/letter_trie.h")).unwrap();
+        assert!(
+            generated == committed,
+            "include/letter_trie.h is out of date; regenerate it with `cbindgen --output include/letter_trie.h`"
+        );
+    }
+}
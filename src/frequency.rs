@@ -0,0 +1,331 @@
+//! The letter counts behind `LetterTrie::letter_frequencies()` and the related methods, for word game solvers.
+//!
+//! Only the letters a to z are counted. Any other character, including an accented letter, still takes up its
+//! position in a word but isn't counted anywhere.
+
+use core::cmp;
+
+use crate::limits::Budget;
+use crate::*;
+
+/// The character that matches any letter in a pattern for `LetterTrie::best_guess()`.
+pub const WILDCARD: char = '?';
+
+// The slot for c in a count of letters, or None if it isn't one of the letters a to z.
+fn letter_index(c: char) -> Option<usize> {
+    if c.is_ascii_lowercase() {
+        Some((c as u8 - b'a') as usize)
+    } else {
+        None
+    }
+}
+
+// Each letter is counted once for every word below its node, found by adding up the words below each node as the
+// walk leaves it so that every node is only looked at once.
+pub(crate) fn letter_frequencies<T: LetterTrie + ?Sized>(trie: &T) -> [usize; 26] {
+    let mut counts = [0; 26];
+    // The letter and the number of words so far in the subtree of each node on the path from the root.
+    let mut path: Vec<(char, usize)> = vec![];
+    let leave = |path: &mut Vec<(char, usize)>, counts: &mut [usize; 26]| {
+        let Some((c, word_count)) = path.pop() else {
+            return;
+        };
+        if let Some(index) = letter_index(c) {
+            counts[index] += word_count;
+        }
+        if let Some((_, parent_word_count)) = path.last_mut() {
+            *parent_word_count += word_count;
+        }
+    };
+    trie.visit("", &mut |node| {
+        // The root is at depth 0, so the path should be as long as the depth before this node goes on.
+        while path.len() > node.depth {
+            leave(&mut path, &mut counts);
+        }
+        path.push((node.c, node.is_word as usize));
+        true
+    });
+    // The root's letter is a space, so leaving it doesn't count anything.
+    while !path.is_empty() {
+        leave(&mut path, &mut counts);
+    }
+    counts
+}
+
+pub(crate) fn letter_frequencies_by_word<T: LetterTrie + ?Sized>(trie: &T) -> [usize; 26] {
+    let mut counts = [0; 26];
+    // How many times each letter is on the path to the current node, and the letter slot of each node on it.
+    let mut on_path = [0usize; 26];
+    let mut path: Vec<Option<usize>> = vec![];
+    trie.visit("", &mut |node| {
+        while path.len() > node.depth {
+            if let Some(Some(index)) = path.pop() {
+                on_path[index] -= 1;
+            }
+        }
+        let index = letter_index(node.c);
+        if let Some(index) = index {
+            on_path[index] += 1;
+        }
+        path.push(index);
+        if node.is_word {
+            for (count, n) in counts.iter_mut().zip(on_path.iter()) {
+                if *n > 0 {
+                    *count += 1;
+                }
+            }
+        }
+        true
+    });
+    counts
+}
+
+pub(crate) fn positional_frequencies<T: LetterTrie + ?Sized>(
+    trie: &T,
+    word_len: usize,
+) -> Vec<[usize; 26]> {
+    let mut counts = vec![[0; 26]; word_len];
+    if word_len == 0 {
+        return counts;
+    }
+    trie.visit("", &mut |node| {
+        if node.depth == word_len {
+            if node.is_word {
+                for (position, c) in node.prefix.chars().enumerate() {
+                    if let Some(index) = letter_index(c) {
+                        counts[position][index] += 1;
+                    }
+                }
+            }
+            // Nothing below here has the right length.
+            return false;
+        }
+        true
+    });
+    counts
+}
+
+// The words that fit the pattern without any of the excluded letters, in alphabetical order, until the budget is
+// spent. The metrics, if they're wanted, count each node the walk reaches and one comparison for each letter
+// checked against the pattern.
+pub(crate) fn pattern_candidates<T: TrieQuery + ?Sized>(
+    trie: &T,
+    pattern: &str,
+    excluded: &str,
+    budget: &mut Budget,
+    mut metrics: Option<&mut FindMetrics>,
+) -> Vec<String> {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let mut candidates: Vec<String> = vec![];
+    if pattern.is_empty() {
+        return candidates;
+    }
+    let excluded = excluded.to_lowercase();
+    trie.visit("", &mut |node| {
+        if !budget.visit_node() {
+            return false;
+        }
+        if let Some(metrics) = metrics.as_deref_mut() {
+            metrics.nodes_visited += 1;
+            metrics.comparisons += (node.depth > 0) as usize;
+        }
+        if node.depth == 0 {
+            return true;
+        }
+        let expected = pattern[node.depth - 1];
+        if (expected != WILDCARD && expected != node.c) || excluded.contains(node.c) {
+            return false;
+        }
+        if let Some(metrics) = metrics.as_deref_mut() {
+            metrics.terminal_depth = cmp::max(metrics.terminal_depth, node.depth);
+        }
+        if node.depth == pattern.len() {
+            if node.is_word {
+                candidates.push(node.prefix.to_owned());
+                budget.add_result();
+            }
+            return false;
+        }
+        true
+    });
+    if let Some(metrics) = metrics {
+        let matched_any = metrics.terminal_depth > 0;
+        metrics.finish(matched_any.then_some(!candidates.is_empty()));
+    }
+    candidates
+}
+
+// The candidate that tells the most about the rest, or None if there aren't any.
+pub(crate) fn best_guess(candidates: Vec<String>) -> Option<String> {
+    // Score each candidate by how many of the candidates share each of its letters, so that the guess is the one
+    // that tells the most about the rest.
+    let mut shared = [0usize; 26];
+    let distinct_letters = |word: &str| {
+        let mut letters = [false; 26];
+        for index in word.chars().filter_map(letter_index) {
+            letters[index] = true;
+        }
+        letters
+    };
+    for candidate in &candidates {
+        for (count, has) in shared.iter_mut().zip(distinct_letters(candidate).iter()) {
+            if *has {
+                *count += 1;
+            }
+        }
+    }
+    let score = |word: &str| -> usize {
+        distinct_letters(word)
+            .iter()
+            .zip(shared.iter())
+            .filter(|(has, _)| **has)
+            .map(|(_, count)| *count)
+            .sum()
+    };
+    // The candidates are in alphabetical order and max_by_key() keeps the last of equal scores, so go backward
+    // to have ties go to the first word.
+    candidates
+        .into_iter()
+        .rev()
+        .max_by_key(|candidate| score(candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The counts for a to z.
+    fn letters(counts: &[(char, usize)]) -> [usize; 26] {
+        let mut expected = [0; 26];
+        for (c, count) in counts {
+            expected[letter_index(*c).unwrap()] = *count;
+        }
+        expected
+    }
+
+    fn small<T: LetterTrie>() -> T {
+        T::from_words(words_from_file(Dataset::TestSmallUnsorted.filename()).unwrap())
+    }
+
+    fn check_small<T: LetterTrie>() {
+        // a, an, and, azure, crease, creative, creator, creature, cross, crossed
+        let t: T = small();
+        assert_eq!(
+            letters(&[
+                ('a', 8),
+                ('c', 6),
+                ('d', 2),
+                ('e', 9),
+                ('i', 1),
+                ('n', 2),
+                ('o', 3),
+                ('r', 9),
+                ('s', 5),
+                ('t', 3),
+                ('u', 2),
+                ('v', 1),
+                ('z', 1)
+            ]),
+            t.letter_frequencies()
+        );
+        assert_eq!(
+            letters(&[
+                ('a', 8),
+                ('c', 6),
+                ('d', 2),
+                ('e', 6),
+                ('i', 1),
+                ('n', 2),
+                ('o', 3),
+                ('r', 7),
+                ('s', 3),
+                ('t', 3),
+                ('u', 2),
+                ('v', 1),
+                ('z', 1)
+            ]),
+            t.letter_frequencies_by_word()
+        );
+
+        // azure and cross
+        assert_eq!(
+            vec![
+                letters(&[('a', 1), ('c', 1)]),
+                letters(&[('r', 1), ('z', 1)]),
+                letters(&[('o', 1), ('u', 1)]),
+                letters(&[('r', 1), ('s', 1)]),
+                letters(&[('e', 1), ('s', 1)]),
+            ],
+            t.positional_frequencies(5)
+        );
+        // creative and creature
+        let eight = t.positional_frequencies(8);
+        assert_eq!(letters(&[('c', 2)]), eight[0]);
+        assert_eq!(letters(&[('i', 1), ('u', 1)]), eight[5]);
+        assert_eq!(letters(&[('e', 2)]), eight[7]);
+        assert_eq!(vec![[0; 26]; 4], t.positional_frequencies(4));
+        assert!(t.positional_frequencies(0).is_empty());
+
+        // creative scores 12 against 11 for creature because only it has an i and a v.
+        assert_eq!(Some("creative".to_owned()), t.best_guess("CRE?????", ""));
+        assert_eq!(Some("creature".to_owned()), t.best_guess("cre?????", "v"));
+        // azure scores 6 and cross scores 5 since both have an r.
+        assert_eq!(Some("azure".to_owned()), t.best_guess("?????", ""));
+        assert_eq!(Some("cross".to_owned()), t.best_guess("?????", "Z"));
+        assert_eq!(Some("and".to_owned()), t.best_guess("a?d", ""));
+        assert_eq!(None, t.best_guess("?????", "rz"));
+        assert_eq!(None, t.best_guess("q", ""));
+        assert_eq!(None, t.best_guess("", ""));
+    }
+
+    #[test]
+    fn small_base() {
+        check_small::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn small_no_parent() {
+        check_small::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn other_characters() {
+        let t = NoParentLetterTrie::from_words(vec!["café", "it's", "über", "cafe"]);
+        assert_eq!(
+            letters(&[
+                ('a', 2),
+                ('b', 1),
+                ('c', 2),
+                ('e', 2),
+                ('f', 2),
+                ('i', 1),
+                ('r', 1),
+                ('s', 1),
+                ('t', 1)
+            ]),
+            t.letter_frequencies()
+        );
+        assert_eq!(t.letter_frequencies(), t.letter_frequencies_by_word());
+        let four = t.positional_frequencies(4);
+        assert_eq!(letters(&[('c', 2), ('i', 1)]), four[0]);
+        assert_eq!(letters(&[('a', 2), ('b', 1), ('t', 1)]), four[1]);
+        assert_eq!(letters(&[('e', 1), ('r', 1), ('s', 1)]), four[3]);
+        assert_eq!(Some("cafe".to_owned()), t.best_guess("caf?", ""));
+        assert_eq!(Some("café".to_owned()), t.best_guess("caf?", "e"));
+    }
+
+    // A long chain is still one pass through the nodes.
+    #[test]
+    fn deep_chain() {
+        let word = "ab".repeat(50_000);
+        let t = NoParentLetterTrie::from_words(vec![word.as_str()]);
+        assert_eq!(
+            letters(&[('a', 50_000), ('b', 50_000)]),
+            t.letter_frequencies()
+        );
+        assert_eq!(
+            letters(&[('a', 1), ('b', 1)]),
+            t.letter_frequencies_by_word()
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! A front-coded word list, for handing a trie's words to code that doesn't know this crate's node layout.
+//!
+//! The file starts with a header:
+//! - The five bytes `LTFC\0`.
+//! - A format version byte, currently 1.
+//!
+//! Then every word follows in alphabetical order as it's stored in the trie, lowercased. Each word is one byte
+//! giving how many of its leading bytes are the same as the word before it, then the rest of its bytes as UTF-8.
+//! The count is always below 0x20, and a word can't have a control character below 0x20 in it, so the rest of a
+//! word runs up to the next such byte or the end of the file without needing a separator. The first word shares
+//! nothing with the one before it, and a word that shares more than 31 bytes with the word before it is written as
+//! sharing 31. Tags, sources and spellings aren't kept.
+//!
+//! Reading it back only needs a buffer holding the last word: read the count, cut the buffer down to that many
+//! bytes, and add the bytes up to the next one below 0x20.
+
+use std::io::{self, BufRead, Read, Write};
+
+use crate::*;
+
+/// The bytes at the start of every file in this format.
+pub const FRONT_CODED_MAGIC: &[u8; 5] = b"LTFC\0";
+
+/// The version of the format written by `LetterTrie::save_front_coded()`.
+pub const FRONT_CODED_VERSION: u8 = 1;
+
+// Every byte below this one is a shared byte count rather than part of a word.
+const FIRST_WORD_BYTE: u8 = 0x20;
+
+/// Returns true if `bytes` starts with the header of this format.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+/// let mut bytes: Vec<u8> = vec![];
+/// trie.save_front_coded(&mut bytes).unwrap();
+/// assert!(front_coded::is_front_coded(&bytes));
+/// assert!(!front_coded::is_front_coded(b"cross\ncrossed\n"));
+/// ```
+pub fn is_front_coded(bytes: &[u8]) -> bool {
+    bytes.starts_with(FRONT_CODED_MAGIC)
+}
+
+pub(crate) fn write_words<T, W>(trie: &T, writer: W) -> Result<()>
+where
+    T: LetterTrie + ?Sized,
+    W: Write,
+{
+    let mut writer = io::BufWriter::new(writer);
+    writer.write_all(FRONT_CODED_MAGIC)?;
+    writer.write_all(&[FRONT_CODED_VERSION])?;
+
+    // visit() can't return an error so keep the first one and skip the rest of the walk.
+    let mut result: io::Result<()> = Ok(());
+    let mut previous = String::new();
+    trie.visit("", &mut |node| {
+        if result.is_ok() && node.is_word {
+            result = write_word(&mut writer, &previous, node.prefix);
+            previous.clear();
+            previous.push_str(node.prefix);
+        }
+        result.is_ok()
+    });
+    result?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_word<W: Write>(writer: &mut W, previous: &str, word: &str) -> io::Result<()> {
+    if word.bytes().any(|b| b < FIRST_WORD_BYTE) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} has a control character", word),
+        ));
+    }
+    let shared = previous
+        .bytes()
+        .zip(word.bytes())
+        .take(usize::from(FIRST_WORD_BYTE - 1))
+        .take_while(|(a, b)| a == b)
+        .count();
+    writer.write_all(&[shared as u8])?;
+    writer.write_all(&word.as_bytes()[shared..])
+}
+
+pub(crate) fn read_trie<T, R>(reader: R, load_method: &LoadMethod) -> Result<T>
+where
+    T: LetterTrie,
+    R: Read,
+{
+    let mut reader = io::BufReader::new(reader);
+    let mut header = [0u8; 6];
+    reader.read_exact(&mut header)?;
+    if !is_front_coded(&header) {
+        return Err(LetterTrieError::InvalidFormat(
+            "not a front-coded word list".to_owned(),
+        ));
+    }
+    if header[5] != FRONT_CODED_VERSION {
+        return Err(LetterTrieError::InvalidFormat(format!(
+            "unsupported version {}",
+            header[5]
+        )));
+    }
+    // The words come out in order, so a load method that relies on them being sorted can use its fast path.
+    let opt = DisplayDetailOptions::make_no_display();
+    T::from_reader_test(FrontCodedLines::new(reader), true, load_method, &opt, None)
+}
+
+// The words of a front-coded list after its header, decoded back into lines so that they can go through the same
+// loaders as a word file. A count longer than the word before it comes out as an io::ErrorKind::InvalidData error
+// from the reader.
+struct FrontCodedLines<R: BufRead> {
+    reader: R,
+    // The last word decoded followed by a newline, and how much of it has been consumed.
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: BufRead> FrontCodedLines<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: vec![],
+            pos: 0,
+        }
+    }
+
+    // Replace the line with the next word and a newline, leaving it empty at the end of the list.
+    fn next_line(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        let mut shared = [0u8; 1];
+        if self.reader.read(&mut shared)? == 0 {
+            self.line.clear();
+            return Ok(());
+        }
+        let shared = usize::from(shared[0]);
+        // The newline goes along with any bytes the next word doesn't share.
+        let previous_len = self.line.len().saturating_sub(1);
+        if shared > previous_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "a word shares {} bytes with a word of {} bytes",
+                    shared, previous_len
+                ),
+            ));
+        }
+        self.line.truncate(shared);
+        loop {
+            let available = self.reader.fill_buf()?;
+            let len = available
+                .iter()
+                .position(|b| *b < FIRST_WORD_BYTE)
+                .unwrap_or(available.len());
+            self.line.extend_from_slice(&available[..len]);
+            let at_end = len < available.len() || available.is_empty();
+            self.reader.consume(len);
+            if at_end {
+                break;
+            }
+        }
+        self.line.push(b'\n');
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for FrontCodedLines<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for FrontCodedLines<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.line.len() {
+            self.next_line()?;
+        }
+        Ok(&self.line[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.line.len());
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_round_trip() {
+        let t = BaseLetterTrie::from_words(vec!["an", "and", "cross", "crossed", "Ébène"]);
+        let mut bytes: Vec<u8> = vec![];
+        t.save_front_coded(&mut bytes).unwrap();
+        let mut expected: Vec<u8> = FRONT_CODED_MAGIC.to_vec();
+        expected.push(FRONT_CODED_VERSION);
+        expected.extend_from_slice(b"\x00an\x02d\x00cross\x05ed\x00\xc3\xa9b\xc3\xa8ne");
+        assert_eq!(expected, bytes);
+
+        for load_method in ALL_LOAD_METHODS.iter() {
+            let t2 = NoParentLetterTrie::from_front_coded(bytes.as_slice(), load_method).unwrap();
+            assert_eq!(t.fingerprint(), t2.fingerprint());
+        }
+
+        let empty = NoParentLetterTrie::from_words(Vec::<&str>::new());
+        let mut bytes: Vec<u8> = vec![];
+        empty.save_front_coded(&mut bytes).unwrap();
+        assert_eq!(6, bytes.len());
+        let t2 =
+            BaseLetterTrie::from_front_coded(bytes.as_slice(), &LoadMethod::Continuous).unwrap();
+        assert_eq!(0, t2.to_fixed_node().word_count);
+    }
+
+    #[test]
+    fn long_shared_prefix() {
+        let long = "a".repeat(300);
+        let longer = format!("{}b", long);
+        let t = NoParentLetterTrie::from_words(vec![long.as_str(), longer.as_str()]);
+        let mut bytes: Vec<u8> = vec![];
+        t.save_front_coded(&mut bytes).unwrap();
+        // The second word shares 31 bytes and writes the other 270.
+        assert_eq!(6 + 1 + 300 + 1 + 270, bytes.len());
+        let t2 =
+            BaseLetterTrie::from_front_coded(bytes.as_slice(), &LoadMethod::Continuous).unwrap();
+        assert_eq!(t.fingerprint(), t2.fingerprint());
+    }
+
+    #[test]
+    fn invalid_data() {
+        let read = |bytes: &[u8]| BaseLetterTrie::from_front_coded(bytes, &LoadMethod::Continuous);
+        assert!(matches!(
+            read(b"cross\ncrossed\n"),
+            Err(LetterTrieError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            read(b"LTFC\0\x02\x00an"),
+            Err(LetterTrieError::InvalidFormat(_))
+        ));
+        // The second word claims to share more bytes than the first has.
+        match read(b"LTFC\0\x01\x00an\x03d") {
+            Err(LetterTrieError::Io(err)) => assert_eq!(io::ErrorKind::InvalidData, err.kind()),
+            other => panic!("unexpected result {:?}", other.map(|t| t.to_fixed_node())),
+        }
+
+        // A control character in a word would be read as the start of the next word.
+        let t = NoParentLetterTrie::from_words(vec!["cross\tcrossed"]);
+        match t.save_front_coded(io::sink()) {
+            Err(LetterTrieError::Io(err)) => assert_eq!(io::ErrorKind::InvalidInput, err.kind()),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    // A sorted word list front-codes to several times smaller than the plain text. The large dataset's words have
+    // about 2.7 bytes each that they don't share with the word before, against 11.5 a line as text.
+    #[test]
+    #[ignore = "needs the large dataset files"]
+    fn large_round_trip() {
+        let dataset = Dataset::TestLargeSorted;
+        let t = NoParentLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        let mut bytes: Vec<u8> = vec![];
+        t.save_front_coded(&mut bytes).unwrap();
+        let text_len = std::fs::metadata(dataset.path()).unwrap().len() as usize;
+        assert!(
+            bytes.len() * 3 < text_len,
+            "{} bytes front-coded against {} as text",
+            bytes.len(),
+            text_len
+        );
+
+        let t2 =
+            BaseLetterTrie::from_front_coded(bytes.as_slice(), &LoadMethod::ContinuousParallel)
+                .unwrap();
+        assert_eq!(t.fingerprint(), t2.fingerprint());
+        assert_eq!(t.to_fixed_node(), t2.to_fixed_node());
+    }
+}
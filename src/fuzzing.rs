@@ -0,0 +1,311 @@
+//! The checks run by the fuzz targets in the `fuzz` directory. They live in the crate so that the targets stay a
+//! few lines each and so that the same checks run as ordinary tests on a fixed set of inputs.
+//!
+//! Each function panics when it finds a problem, which is how a fuzzer recognizes a failing input. The threads
+//! spawned by `LoadMethod::ContinuousParallel` aren't joined, so the leak check has to be turned off for
+//! `from_reader` or it reports their thread-local storage:
+//!
+//! ```text
+//! cargo +nightly fuzz run from_reader -- -detect_leaks=0
+//! cargo +nightly fuzz run operations
+//! ```
+
+// A check here panics when it finds a problem, as the module documentation says, so unwrapping is how it reports one.
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use std::collections::BTreeSet;
+use std::str;
+
+use crate::*;
+
+// The characters that the bytes of an operation's word are mapped to. There are few enough to make shared
+// prefixes and repeated words common, with upper case that folds onto other entries (including the Kelvin sign
+// for "k"), characters that are more than one byte in UTF-8 or that lowercase to more than one character, and
+// whitespace and control characters both inside words and at the ends where they're trimmed.
+const OPERATION_CHARS: [char; 16] = [
+    'a', 'b', 'c', 'k', 'A', 'B', 'é', 'É', 'ß', '\u{130}', '\u{212a}', ' ', '\t', '\r', '\0', 'z',
+];
+
+/// Build both kinds of trie from `data` as a word file with every load method and check that:
+/// - Loading fails with `LetterTrieError::Io` exactly when `data` isn't valid UTF-8.
+/// - Otherwise every trie passes `verify_integrity()` and holds the trimmed, lowercase, non-empty lines of
+///   `data` and nothing else.
+/// - The same words sorted and loaded with `is_sorted` true give the same trie.
+///
+/// # Panics
+///
+/// Panics if any of these checks fail.
+pub fn check_reader_input(data: &[u8]) {
+    check_reader_input_for::<BaseLetterTrie>(data);
+    check_reader_input_for::<NoParentLetterTrie>(data);
+}
+
+fn check_reader_input_for<T: LetterTrie>(data: &[u8]) {
+    let expected: Option<BTreeSet<String>> = str::from_utf8(data).ok().map(|text| {
+        text.split('\n')
+            .map(|line| line.trim().to_lowercase())
+            .filter(|line| !line.is_empty())
+            .collect()
+    });
+    let expected = match expected {
+        Some(expected) => expected,
+        None => {
+            for load_method in &ALL_LOAD_METHODS {
+                let result = T::from_reader(data, false, load_method);
+                assert!(
+                    matches!(result, Err(LetterTrieError::Io(_))),
+                    "{:?} accepted invalid UTF-8.",
+                    load_method
+                );
+            }
+            return;
+        }
+    };
+    let expected_words: Vec<String> = expected.iter().cloned().collect();
+    let sorted_content = expected_words.join("\n");
+
+    let mut fingerprint: Option<u64> = None;
+    for (content, is_sorted) in &[(data, false), (sorted_content.as_bytes(), true)] {
+        for load_method in &ALL_LOAD_METHODS {
+            let t = T::from_reader(*content, *is_sorted, load_method).unwrap_or_else(|err| {
+                panic!(
+                    "{:?} with is_sorted = {} failed on valid UTF-8: {}",
+                    load_method, is_sorted, err
+                )
+            });
+            if let Err(err) = t.verify_integrity() {
+                panic!(
+                    "{:?} with is_sorted = {} built a bad trie: {}",
+                    load_method, is_sorted, err
+                );
+            }
+            assert_eq!(
+                expected_words,
+                t.words_with_prefix(""),
+                "{:?} with is_sorted = {} has the wrong words.",
+                load_method,
+                is_sorted
+            );
+            let this_fingerprint = t.fingerprint();
+            assert_eq!(
+                *fingerprint.get_or_insert(this_fingerprint),
+                this_fingerprint,
+                "{:?} with is_sorted = {} built a different trie.",
+                load_method,
+                is_sorted
+            );
+        }
+    }
+}
+
+/// Treat `data` as a sequence of operations and apply each one to a `BaseLetterTrie`, a `NoParentLetterTrie`
+/// and a `BTreeSet<String>` model, checking that the tries give the same answers as the model and stay
+/// consistent.
+///
+/// Each operation is an opcode byte, a length byte, and then up to that many bytes which each pick a character
+/// for the operation's word. The operations are insert, remove, contains, find, `words_with_prefix()` along with
+/// `suggest()`, and a round trip through the binary format.
+///
+/// # Panics
+///
+/// Panics if a trie and the model disagree or if `verify_integrity()` finds a problem.
+pub fn check_operations(data: &[u8]) {
+    let mut base = BaseLetterTrie::new();
+    let mut no_parent = NoParentLetterTrie::new();
+    let mut model: BTreeSet<String> = BTreeSet::new();
+
+    let mut bytes = data.iter();
+    while let (Some(opcode), Some(len)) = (bytes.next(), bytes.next()) {
+        let word: String = bytes
+            .by_ref()
+            .take(usize::from(*len % 16))
+            .map(|b| OPERATION_CHARS[usize::from(*b) % OPERATION_CHARS.len()])
+            .collect();
+        match opcode % 7 {
+            0 | 1 => {
+                base.insert(&word);
+                no_parent.insert(&word);
+                let word = word.trim().to_lowercase();
+                if !word.is_empty() {
+                    model.insert(word);
+                }
+            }
+            2 => {
+                let expected = model.remove(&word.trim().to_lowercase());
+                assert_eq!(expected, base.remove(&word), "remove({:?})", word);
+                assert_eq!(expected, no_parent.remove(&word), "remove({:?})", word);
+            }
+            3 => {
+                let expected = model.contains(&word.trim().to_lowercase());
+                assert_eq!(expected, base.contains(&word), "contains({:?})", word);
+                assert_eq!(expected, no_parent.contains(&word), "contains({:?})", word);
+            }
+            4 => {
+                let prefix = word.trim().to_lowercase();
+                let word_count = model_words_with_prefix(&model, &prefix).len();
+                // There's no node for the empty prefix or for one that no word starts with.
+                let expected = if prefix.is_empty() || word_count == 0 {
+                    None
+                } else {
+                    Some((model.contains(&prefix), word_count))
+                };
+                for found in &[base.find(&word), no_parent.find(&word)] {
+                    let found = found.as_ref().map(|node| (node.is_word, node.word_count));
+                    assert_eq!(expected, found, "find({:?})", word);
+                }
+            }
+            5 => {
+                let prefix = word.trim().to_lowercase();
+                let expected = model_words_with_prefix(&model, &prefix);
+                assert_eq!(
+                    expected,
+                    base.words_with_prefix(&word),
+                    "words_with_prefix({:?})",
+                    word
+                );
+                assert_eq!(
+                    expected,
+                    no_parent.words_with_prefix(&word),
+                    "words_with_prefix({:?})",
+                    word
+                );
+
+                let max_count = usize::from(*len % 5);
+                let mut expected = expected;
+                expected.sort_by_key(|word| word.chars().count());
+                expected.truncate(max_count);
+                assert_eq!(
+                    expected,
+                    base.suggest(&word, max_count),
+                    "suggest({:?})",
+                    word
+                );
+                assert_eq!(
+                    expected,
+                    no_parent.suggest(&word, max_count),
+                    "suggest({:?})",
+                    word
+                );
+            }
+            _ => {
+                let mut saved: Vec<u8> = vec![];
+                base.write_binary(&mut saved)
+                    .expect("Error saving the trie.");
+                let copy = NoParentLetterTrie::read_binary(saved.as_slice())
+                    .expect("Error reading the saved trie.");
+                assert_eq!(base.fingerprint(), copy.fingerprint());
+                assert_eq!(no_parent.fingerprint(), copy.fingerprint());
+                verify(&base, &no_parent, &model);
+            }
+        }
+    }
+    verify(&base, &no_parent, &model);
+}
+
+// The words in the model that start with prefix, in the same alphabetical order used by the tries.
+fn model_words_with_prefix(model: &BTreeSet<String>, prefix: &str) -> Vec<String> {
+    model
+        .range(prefix.to_owned()..)
+        .take_while(|word| word.starts_with(prefix))
+        .cloned()
+        .collect()
+}
+
+fn verify(base: &BaseLetterTrie, no_parent: &NoParentLetterTrie, model: &BTreeSet<String>) {
+    if let Err(err) = base.verify_integrity() {
+        panic!("BaseLetterTrie: {}", err);
+    }
+    if let Err(err) = no_parent.verify_integrity() {
+        panic!("NoParentLetterTrie: {}", err);
+    }
+    let expected: Vec<String> = model.iter().cloned().collect();
+    assert_eq!(expected, base.words_with_prefix(""));
+    assert_eq!(expected, no_parent.words_with_prefix(""));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small xorshift generator so that the tests can make lots of arbitrary inputs without a dependency.
+    fn arbitrary_inputs(count: usize, max_len: usize, seed: u64) -> Vec<Vec<u8>> {
+        let mut x = seed;
+        let mut next = move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        (0..count)
+            .map(|_| {
+                let len = (next() % max_len as u64) as usize;
+                (0..len).map(|_| next() as u8).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reader_edge_cases() {
+        let long_line = "Ab".repeat(50_000);
+        let inputs: Vec<&[u8]> = vec![
+            b"",
+            b"\n\n\r\n",
+            b"cross\rcrossed\rcrossing",
+            b"a\0b\nA\0B\n\0\n",
+            b"cross\ncross\nCROSS  \n  cro ss\n",
+            b"\xef\xbb\xbfbom\n",
+            "\u{130}stanbul\n\u{212a}elvin\nSTRASSE\nstra\u{df}e\n".as_bytes(),
+            b"ok\nbad \xff byte\n",
+            b"\xc3",
+            long_line.as_bytes(),
+        ];
+        for input in inputs {
+            check_reader_input(input);
+        }
+    }
+
+    #[test]
+    fn reader_arbitrary_bytes() {
+        for input in arbitrary_inputs(100, 200, 1) {
+            check_reader_input(&input);
+            // Most arbitrary bytes aren't UTF-8 so also try them as text made of a few letters and line endings.
+            let text: Vec<u8> = input
+                .iter()
+                .map(|b| b"abAB \n\r\t\0z"[usize::from(*b) % 10])
+                .collect();
+            check_reader_input(&text);
+        }
+    }
+
+    #[test]
+    fn operations_arbitrary_bytes() {
+        for input in arbitrary_inputs(300, 600, 2) {
+            check_operations(&input);
+        }
+    }
+
+    #[test]
+    fn long_word_operations() {
+        // Far deeper than recursion could go on a test thread's stack.
+        let word = "ab".repeat(100_000);
+        let mut base = BaseLetterTrie::from_words(vec![word.as_str(), "ab"]);
+        let mut no_parent = NoParentLetterTrie::from_words(vec![word.as_str(), "ab"]);
+        let mut model: BTreeSet<String> = vec![word.clone(), "ab".to_owned()].into_iter().collect();
+        verify(&base, &no_parent, &model);
+        assert_eq!(
+            Some(1),
+            base.find(&word[..5_000]).map(|node| node.word_count)
+        );
+        assert_eq!(
+            Some(1),
+            no_parent.find(&word[..5_000]).map(|node| node.word_count)
+        );
+
+        assert!(base.remove(&word));
+        assert!(no_parent.remove(&word));
+        model.remove(&word);
+        verify(&base, &no_parent, &model);
+        assert_eq!(3, base.to_fixed_node().node_count);
+        assert_eq!(3, no_parent.to_fixed_node().node_count);
+    }
+}
@@ -0,0 +1,434 @@
+//! Synthetic word lists of any size for tests and benchmarks.
+//!
+//! The words are made of the letters a to z chosen uniformly at random by a small SplitMix64 generator, so a given
+//! seed produces exactly the same words on every platform and with every version of the crate's dependencies.
+//!
+//! Uniformly random words hardly share any prefixes past the first two or three letters, so they make a trie that's
+//! much bushier near the root than one of real words, with a chain of single children under each word's third
+//! letter. `Dataset::Realistic` instead makes
+//! up words with `TransitionModel::generate()` from the letter pairs of the medium dataset.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::process;
+#[cfg(feature = "fs")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+use crate::{Dataset, LetterTrie, NoParentLetterTrie};
+
+// The word lengths used for Dataset::Generated and Dataset::Realistic.
+const GENERATED_MIN_LEN: usize = 2;
+const GENERATED_MAX_LEN: usize = 12;
+
+// The files for Dataset::Generated, Dataset::Realistic and Dataset::EmbeddedSmall, keyed by the name of the file
+// without the extension.
+static DATASET_FILES: OnceLock<Mutex<HashMap<String, DatasetFile>>> = OnceLock::new();
+
+// The file for a dataset that isn't in the repository. The name is leaked once for each dataset so that
+// Dataset::filename() can return a &str like it does for the fixed datasets.
+struct DatasetFile {
+    filename: &'static str,
+    // Whether materialize() has checked or written the file in this process.
+    #[cfg(feature = "fs")]
+    is_ready: bool,
+}
+
+/// Generate `count` words with lengths from `min_len` to `max_len` inclusive, the same words for the same seed.
+///
+/// Duplicates are possible but only happen by chance, which is rare for longer words. Use
+/// `generate_words_with_duplicates()` to control how often words repeat.
+///
+/// # Panics
+///
+/// Panics if `min_len` is zero or greater than `max_len`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::generator::generate_words;
+///
+/// let words = generate_words(1_000, 3, 8, 42);
+/// assert_eq!(1_000, words.len());
+/// assert_eq!(words, generate_words(1_000, 3, 8, 42));
+/// ```
+pub fn generate_words(count: usize, min_len: usize, max_len: usize, seed: u64) -> Vec<String> {
+    generate_words_with_duplicates(count, min_len, max_len, seed, 0.0)
+}
+
+/// Like `generate_words()` except that each word after the first has a chance of `duplicate_rate` (from 0.0 to
+/// 1.0) of being a copy of an earlier word instead of a new one, for testing how loaders handle repeated lines.
+///
+/// # Panics
+///
+/// Panics if `min_len` is zero or greater than `max_len`, or if `duplicate_rate` is outside 0.0 to 1.0.
+pub fn generate_words_with_duplicates(
+    count: usize,
+    min_len: usize,
+    max_len: usize,
+    seed: u64,
+    duplicate_rate: f64,
+) -> Vec<String> {
+    assert!(min_len > 0, "min_len must be at least 1.");
+    assert!(min_len <= max_len, "min_len must not be more than max_len.");
+    assert!(
+        (0.0..=1.0).contains(&duplicate_rate),
+        "duplicate_rate must be from 0.0 to 1.0."
+    );
+    let mut rng = SplitMix64(seed);
+    let mut words: Vec<String> = Vec::with_capacity(count);
+    for _ in 0..count {
+        if !words.is_empty() && duplicate_rate > 0.0 && rng.next_f64() < duplicate_rate {
+            let i = rng.below(words.len() as u64) as usize;
+            words.push(words[i].clone());
+        } else {
+            let len = min_len + rng.below((max_len - min_len + 1) as u64) as usize;
+            let word: String = (0..len)
+                .map(|_| (b'a' + rng.below(26) as u8) as char)
+                .collect();
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Write a word file with one word per line, sorting a copy of the words first if `sorted` is true.
+///
+/// # Errors
+///
+/// Returns any error from creating or writing the file.
+pub fn write_word_file<P: AsRef<Path>>(path: P, words: &[String], sorted: bool) -> io::Result<()> {
+    let mut sorted_words: Vec<&String>;
+    let words: Vec<&String> = if sorted {
+        sorted_words = words.iter().collect();
+        sorted_words.sort_unstable();
+        sorted_words
+    } else {
+        words.iter().collect()
+    };
+    let mut writer = BufWriter::new(File::create(path)?);
+    for word in words {
+        writeln!(writer, "{}", word)?;
+    }
+    writer.flush()
+}
+
+// The words written to the file for Dataset::Generated.
+pub(crate) fn generated_dataset_words(count: usize, seed: u64) -> Vec<String> {
+    generate_words(count, GENERATED_MIN_LEN, GENERATED_MAX_LEN, seed)
+}
+
+// The words written to the file for Dataset::Realistic, made up from a model of the medium dataset's words by
+// position.
+pub(crate) fn realistic_dataset_words(count: usize, seed: u64) -> io::Result<Vec<String>> {
+    let text = fs::read_to_string(Dataset::TestMediumUnsorted.filename())?;
+    let trie = NoParentLetterTrie::from_words(text.lines());
+    Ok(trie.transition_model_by_position().generate(
+        seed,
+        count,
+        GENERATED_MIN_LEN,
+        GENERATED_MAX_LEN,
+    ))
+}
+
+pub(crate) fn generated_dataset_filename(count: usize, seed: u64) -> &'static str {
+    dataset_filename(&generated_name(count, seed))
+}
+
+pub(crate) fn realistic_dataset_filename(count: usize, seed: u64) -> &'static str {
+    dataset_filename(&realistic_name(count, seed))
+}
+
+pub(crate) fn embedded_dataset_filename() -> &'static str {
+    dataset_filename(EMBEDDED_NAME)
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn materialize_generated(count: usize, seed: u64) -> io::Result<()> {
+    let dataset = Dataset::Generated { count, seed };
+    materialize(&generated_name(count, seed), &dataset, |path| {
+        write_word_file(path, &generated_dataset_words(count, seed), false)
+    })
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn materialize_realistic(count: usize, seed: u64) -> io::Result<()> {
+    let dataset = Dataset::Realistic { count, seed };
+    materialize(&realistic_name(count, seed), &dataset, |path| {
+        write_word_file(path, &realistic_dataset_words(count, seed)?, false)
+    })
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn materialize_embedded() -> io::Result<()> {
+    materialize(EMBEDDED_NAME, &Dataset::EmbeddedSmall, |path| {
+        fs::write(path, crate::EMBEDDED_SMALL_UNSORTED)
+    })
+}
+
+const EMBEDDED_NAME: &str = "embedded_small";
+
+fn generated_name(count: usize, seed: u64) -> String {
+    format!("generated_{}_{}", count, seed)
+}
+
+fn realistic_name(count: usize, seed: u64) -> String {
+    format!("realistic_{}_{}", count, seed)
+}
+
+// The map is only ever changed a whole entry at a time, so it's still good after a panic elsewhere while it was
+// locked.
+fn dataset_files() -> MutexGuard<'static, HashMap<String, DatasetFile>> {
+    DATASET_FILES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+fn dataset_file<'a>(
+    files: &'a mut HashMap<String, DatasetFile>,
+    name: &str,
+) -> &'a mut DatasetFile {
+    files.entry(name.to_owned()).or_insert_with(|| {
+        let path = env::temp_dir().join(format!("letter_trie_{}.txt", name));
+        DatasetFile {
+            filename: Box::leak(path.to_string_lossy().into_owned().into_boxed_str()),
+            #[cfg(feature = "fs")]
+            is_ready: false,
+        }
+    })
+}
+
+// The name of the file in the temp directory for a dataset that isn't in the repository, whether or not it's been
+// written yet.
+fn dataset_filename(name: &str) -> &'static str {
+    dataset_file(&mut dataset_files(), name).filename
+}
+
+// Make sure the file for a dataset that isn't in the repository is in the temp directory with the right words. A
+// file that's already there, perhaps from an earlier run, is only used if it has the dataset's word count and
+// fingerprint. Otherwise the words are written to a file with a name no other writer uses and then renamed into
+// place, so no process ever sees part of a file. Once the file is ready it isn't checked again in this process.
+#[cfg(feature = "fs")]
+fn materialize(
+    name: &str,
+    dataset: &Dataset,
+    write: impl FnOnce(&Path) -> io::Result<()>,
+) -> io::Result<()> {
+    // Each file written by this process gets its own number as well as the process ID.
+    static NEXT_TEMP_FILE: AtomicUsize = AtomicUsize::new(0);
+    let mut files = dataset_files();
+    let file = dataset_file(&mut files, name);
+    if file.is_ready {
+        return Ok(());
+    }
+    let path = Path::new(file.filename);
+    let is_good = crate::verify_word_file(
+        file.filename,
+        dataset.expected_word_count(),
+        dataset.expected_fingerprint(),
+    )
+    .is_ok();
+    if !is_good {
+        let temp_path = path.with_extension(format!(
+            "{}.{}.tmp",
+            process::id(),
+            NEXT_TEMP_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = write(&temp_path).and_then(|_| fs::rename(&temp_path, path));
+        if let Err(err) = written {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err);
+        }
+    }
+    file.is_ready = true;
+    Ok(())
+}
+
+// SplitMix64 from https://prng.di.unimi.it/splitmix64.c. It's not suitable for anything needing real randomness
+// but it's fast, tiny, and its output for a given seed will never change.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // A number from 0 to n - 1. The modulo bias is far too small to matter for picking letters.
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    // A number from 0.0 up to but not including 1.0.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_path;
+    use std::collections::HashSet;
+
+    #[test]
+    fn deterministic() {
+        let words = generate_words(5_000, 1, 10, 7);
+        assert_eq!(words, generate_words(5_000, 1, 10, 7));
+        assert_ne!(words, generate_words(5_000, 1, 10, 8));
+        assert!(words
+            .iter()
+            .all(|word| (1..=10).contains(&word.len())
+                && word.bytes().all(|b| b.is_ascii_lowercase())));
+        // Make sure the words don't change between versions since benchmark results are compared across commits.
+        assert_eq!(generate_words(3, 4, 4, 0), vec!["abmr", "xila", "srdr"]);
+    }
+
+    #[test]
+    fn duplicate_rate() {
+        let words = generate_words_with_duplicates(10_000, 8, 12, 1, 0.25);
+        let unique: HashSet<&String> = words.iter().collect();
+        let duplicates = words.len() - unique.len();
+        assert!(duplicates > 2_000 && duplicates < 3_000, "{}", duplicates);
+
+        let words = generate_words_with_duplicates(10_000, 8, 12, 1, 0.0);
+        let unique: HashSet<&String> = words.iter().collect();
+        assert_eq!(words.len(), unique.len());
+    }
+
+    #[test]
+    fn word_file_sortedness() {
+        let words = generate_words(1_000, 2, 6, 3);
+        let sorted_path = temp_path("generator_sorted.txt");
+        let unsorted_path = temp_path("generator_unsorted.txt");
+        write_word_file(&sorted_path, &words, true).unwrap();
+        write_word_file(&unsorted_path, &words, false).unwrap();
+
+        let sorted_lines: Vec<String> = fs::read_to_string(&sorted_path)
+            .unwrap()
+            .lines()
+            .map(|line| line.to_owned())
+            .collect();
+        let unsorted_lines: Vec<String> = fs::read_to_string(&unsorted_path)
+            .unwrap()
+            .lines()
+            .map(|line| line.to_owned())
+            .collect();
+
+        assert_eq!(words, unsorted_lines);
+        assert!(sorted_lines.windows(2).all(|pair| pair[0] <= pair[1]));
+        let mut expected = words;
+        expected.sort_unstable();
+        assert_eq!(expected, sorted_lines);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn generated_dataset() {
+        use crate::{
+            Dataset, DisplayDetailOptions, LetterTrie, LoadMethod, NoParentLetterTrie, TrieQuery,
+        };
+
+        let dataset = Dataset::Generated {
+            count: 2_000,
+            seed: 11,
+        };
+        assert_eq!(dataset.filename(), dataset.filename());
+        dataset.materialize().unwrap();
+        for load_method in &[LoadMethod::VecFill, LoadMethod::ContinuousParallel] {
+            let t = NoParentLetterTrie::from_file_test(
+                dataset.filename(),
+                dataset.is_sorted(),
+                load_method,
+                &DisplayDetailOptions::make_no_display(),
+                Some(dataset.word_count()),
+            )
+            .unwrap();
+            let unique: HashSet<String> =
+                generate_words(2_000, GENERATED_MIN_LEN, GENERATED_MAX_LEN, 11)
+                    .into_iter()
+                    .collect();
+            assert_eq!(unique.len(), t.to_fixed_node().word_count);
+        }
+    }
+
+    // Naming the file doesn't write it, and a file that's already there is only kept if it has the right words.
+    #[cfg(feature = "fs")]
+    #[test]
+    fn materialize_checks_the_file() {
+        use crate::{words_from_file, Dataset};
+
+        let dataset = Dataset::Generated {
+            count: 30,
+            seed: 2_024,
+        };
+        let path = Path::new(dataset.filename());
+        let _ = fs::remove_file(path);
+        assert_eq!(path, Path::new(dataset.filename()));
+        assert!(!path.exists());
+        let words = generated_dataset_words(30, 2_024);
+        dataset.materialize().unwrap();
+        assert_eq!(words, words_from_file(dataset.filename()).unwrap());
+
+        // As if the file had been left cut short by another run before this process looked at it.
+        fs::write(path, "abc\n").unwrap();
+        dataset_file(&mut dataset_files(), &generated_name(30, 2_024)).is_ready = false;
+        dataset.materialize().unwrap();
+        assert_eq!(words, words_from_file(dataset.filename()).unwrap());
+        dataset.verify().unwrap();
+    }
+
+    // Words made up from the medium dataset only use its letters, and share more of their prefixes than uniformly
+    // random words, which is the point of them.
+    #[cfg(feature = "fs")]
+    #[test]
+    fn realistic_dataset() {
+        use crate::{words_from_file, Dataset, LetterTrie, NoParentLetterTrie};
+
+        let words = realistic_dataset_words(5_000, 3).unwrap();
+        assert_eq!(5_000, words.len());
+        assert_eq!(words, realistic_dataset_words(5_000, 3).unwrap());
+        let source = NoParentLetterTrie::from_words(
+            words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap(),
+        );
+        let letters = source.transition_model().letters();
+        assert!(words.iter().all(|word| {
+            (GENERATED_MIN_LEN..=GENERATED_MAX_LEN).contains(&word.chars().count())
+                && word.chars().all(|c| letters.contains(&c))
+        }));
+
+        // Uniformly random words have gone their separate ways by the third letter.
+        let three_letter_prefixes = |words: &[String]| {
+            words
+                .iter()
+                .filter(|word| word.len() >= 3)
+                .map(|word| &word[..3])
+                .collect::<HashSet<&str>>()
+                .len()
+        };
+        let uniform = generated_dataset_words(5_000, 3);
+        assert!(three_letter_prefixes(&words) * 2 < three_letter_prefixes(&uniform));
+        let dataset = Dataset::Realistic {
+            count: 5_000,
+            seed: 3,
+        };
+        assert_ne!(
+            Dataset::Generated {
+                count: 5_000,
+                seed: 3
+            }
+            .filename(),
+            dataset.filename()
+        );
+        dataset.materialize().unwrap();
+        assert_eq!(words, words_from_file(dataset.filename()).unwrap());
+    }
+}
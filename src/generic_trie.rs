@@ -0,0 +1,508 @@
+//! A trie over any ordered symbol type rather than `char`, for sequences that aren't text: DNA as bytes such as
+//! `b"ACGT"`, or the token IDs from a tokenizer as `u32`s.
+//!
+//! `GenericTrie` has the shape of `NoParentLetterTrie`, with each node owning its children in symbol order, but a
+//! sequence is stored exactly as given. There's no lowercasing or trimming except in
+//! `ByteLetterTrie::from_reader()`, which reads lines the way the letter tries do. A node found with `find()` is a
+//! `GenericFixedNode`, and one whose symbols convert to `char`, as bytes do, can be turned into a `FixedNode` to
+//! compare with a letter trie.
+
+use alloc::collections::BTreeMap;
+use core::fmt::{self, Debug};
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+use crate::*;
+
+/// A type that a `GenericTrie` can be built from sequences of.
+pub trait Symbol: Copy + Ord + Debug {
+    /// Write a sequence of symbols for people to read, as in the `Display` output of a `GenericFixedNode`.
+    fn fmt_symbols(symbols: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+impl Symbol for char {
+    fn fmt_symbols(symbols: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        symbols.iter().try_for_each(|c| write!(f, "{}", c))
+    }
+}
+
+// Printable ASCII as it is and anything else escaped, so that b"ACGT" reads as ACGT.
+impl Symbol for u8 {
+    fn fmt_symbols(symbols: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", symbols.escape_ascii())
+    }
+}
+
+impl Symbol for u16 {
+    fn fmt_symbols(symbols: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_numbers(symbols, f)
+    }
+}
+
+impl Symbol for u32 {
+    fn fmt_symbols(symbols: &[Self], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_numbers(symbols, f)
+    }
+}
+
+// Numbers such as token IDs separated by spaces.
+fn fmt_numbers<S: fmt::Display>(symbols: &[S], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, symbol) in symbols.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", symbol)?;
+    }
+    Ok(())
+}
+
+/// A trie of byte sequences, such as DNA or ASCII words.
+pub type ByteLetterTrie = GenericTrie<u8>;
+
+/// A trie of token ID sequences.
+pub type TokenTrie = GenericTrie<u32>;
+
+/// A trie over sequences of `S`, described in the module documentation.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = ByteLetterTrie::from_bytes_iter(vec![&b"GATTACA"[..], b"GATT", b"CAT"]);
+/// assert!(trie.contains(b"GATT"));
+/// assert!(!trie.contains(b"GAT"));
+/// assert!(trie.contains_prefix(b"GAT"));
+/// assert_eq!(vec![b"GATT".to_vec(), b"GATTACA".to_vec()], trie.sequences_with_prefix(b"GA"));
+/// assert_eq!("GATT", trie.find(b"GATT").unwrap().to_string());
+///
+/// let tokens = TokenTrie::from_sequences(vec![vec![101, 7592, 102], vec![101, 2088]]);
+/// assert_eq!(2, tokens.count_with_prefix(&[101]));
+/// assert_eq!("101 7592", tokens.find(&[101, 7592]).unwrap().to_string());
+/// ```
+pub struct GenericTrie<S: Symbol> {
+    root: Node<S>,
+}
+
+struct Node<S: Symbol> {
+    children: BTreeMap<S, Node<S>>,
+    is_word: bool,
+}
+
+impl<S: Symbol> Node<S> {
+    fn new() -> Self {
+        Self {
+            children: BTreeMap::new(),
+            is_word: false,
+        }
+    }
+}
+
+// Dropping the nodes one at a time instead of letting each node drop its children keeps a very deep trie from
+// overflowing the call stack.
+impl<S: Symbol> Drop for Node<S> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Self> = vec![];
+        stack.extend(core::mem::take(&mut self.children).into_values());
+        while let Some(mut node) = stack.pop() {
+            stack.extend(core::mem::take(&mut node.children).into_values());
+        }
+    }
+}
+
+/// A concrete copy of a node in a `GenericTrie`, the counterpart of `FixedNode`. The `Display` output is the
+/// node's prefix as written by `Symbol::fmt_symbols()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenericFixedNode<S: Symbol> {
+    /// The node's symbol, or None for the root.
+    pub symbol: Option<S>,
+    pub prefix: Vec<S>,
+    pub depth: usize,
+    pub is_word: bool,
+    pub child_count: usize,
+    pub node_count: usize,
+    pub word_count: usize,
+    pub height: usize,
+}
+
+impl<S: Symbol + Into<char>> GenericFixedNode<S> {
+    /// The same node described as a `FixedNode`, with a space for the root's character as in the letter tries, so
+    /// that a trie of bytes can be compared with a letter trie of the same ASCII words.
+    pub fn to_letter_node(&self) -> FixedNode {
+        FixedNode {
+            c: self.symbol.map(Into::into).unwrap_or(' '),
+            prefix: self.prefix.iter().map(|symbol| (*symbol).into()).collect(),
+            depth: self.depth,
+            is_word: self.is_word,
+            child_count: self.child_count,
+            node_count: self.node_count,
+            word_count: self.word_count,
+            height: self.height,
+        }
+    }
+}
+
+impl<S: Symbol> fmt::Display for GenericFixedNode<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        S::fmt_symbols(&self.prefix, f)
+    }
+}
+
+/// A borrowed description of one node passed to the callback in `GenericTrie::visit()`, the counterpart of
+/// `NodeView`.
+#[derive(Debug)]
+pub struct GenericNodeView<'a, S: Symbol> {
+    /// The node's symbol, or None for the root.
+    pub symbol: Option<S>,
+    pub prefix: &'a [S],
+    pub depth: usize,
+    pub is_word: bool,
+    pub child_count: usize,
+}
+
+impl<S: Symbol> GenericTrie<S> {
+    pub fn new() -> Self {
+        Self { root: Node::new() }
+    }
+
+    /// Create a trie from sequences of symbols. Empty sequences and duplicates are ignored.
+    pub fn from_sequences<I, W>(sequences: I) -> Self
+    where
+        I: IntoIterator<Item = W>,
+        W: AsRef<[S]>,
+    {
+        let mut trie = Self::new();
+        for sequence in sequences {
+            trie.insert(sequence.as_ref());
+        }
+        trie
+    }
+
+    /// Add a sequence. An empty sequence is ignored, as an empty word is in the letter tries.
+    pub fn insert(&mut self, sequence: &[S]) {
+        if sequence.is_empty() {
+            return;
+        }
+        let mut node = &mut self.root;
+        for symbol in sequence {
+            node = node.children.entry(*symbol).or_insert_with(Node::new);
+        }
+        node.is_word = true;
+    }
+
+    /// Remove a sequence, along with any nodes that no other sequence goes through. Returns true if the sequence
+    /// was in the trie.
+    pub fn remove(&mut self, sequence: &[S]) -> bool {
+        // The length of the deepest prefix on the path that has to stay, as in NoParentLetterTrie.
+        let mut keep_len = 0;
+        let mut node = &self.root;
+        for (i, symbol) in sequence.iter().enumerate() {
+            if i > 0 && (node.is_word || node.children.len() > 1) {
+                keep_len = i;
+            }
+            match node.children.get(symbol) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        if !node.is_word || sequence.is_empty() {
+            return false;
+        }
+        if !node.children.is_empty() {
+            // Longer sequences still go through this node.
+            if let Some(node) = self.find_node_mut(sequence) {
+                node.is_word = false;
+            }
+        } else if let Some(node) = self.find_node_mut(&sequence[..keep_len]) {
+            node.children.remove(&sequence[keep_len]);
+        }
+        true
+    }
+
+    fn find_node(&self, prefix: &[S]) -> Option<&Node<S>> {
+        let mut node = &self.root;
+        for symbol in prefix {
+            node = node.children.get(symbol)?;
+        }
+        Some(node)
+    }
+
+    fn find_node_mut(&mut self, prefix: &[S]) -> Option<&mut Node<S>> {
+        let mut node = &mut self.root;
+        for symbol in prefix {
+            node = node.children.get_mut(symbol)?;
+        }
+        Some(node)
+    }
+
+    /// Returns true if `sequence` was added to the trie.
+    pub fn contains(&self, sequence: &[S]) -> bool {
+        self.find_node(sequence)
+            .map(|node| node.is_word)
+            .unwrap_or(false)
+    }
+
+    /// Returns true if `prefix` is a sequence in the trie or the start of one. The empty prefix starts every
+    /// sequence.
+    pub fn contains_prefix(&self, prefix: &[S]) -> bool {
+        self.find_node(prefix).is_some()
+    }
+
+    /// Find the node for `prefix`, or None if no sequence starts with it. As with `LetterTrie::find()` there's no
+    /// node for an empty prefix; use `to_fixed_node()` for the root.
+    pub fn find(&self, prefix: &[S]) -> Option<GenericFixedNode<S>> {
+        if prefix.is_empty() {
+            return None;
+        }
+        self.find_node(prefix)
+            .map(|node| fixed_node(node, prefix.to_vec()))
+    }
+
+    /// Describe the root node.
+    pub fn to_fixed_node(&self) -> GenericFixedNode<S> {
+        fixed_node(&self.root, vec![])
+    }
+
+    /// Walk the subtree starting at the node for `prefix`, depth-first and in symbol order, as in
+    /// `LetterTrie::visit()`. If `f` returns false the children of that node are skipped.
+    pub fn visit(&self, prefix: &[S], f: &mut dyn FnMut(&GenericNodeView<S>) -> bool) {
+        let start = match self.find_node(prefix) {
+            Some(node) => node,
+            None => return,
+        };
+        let mut buffer: Vec<S> = prefix.to_vec();
+        let mut view = |node: &Node<S>, buffer: &[S]| -> bool {
+            f(&GenericNodeView {
+                symbol: buffer.last().copied(),
+                prefix: buffer,
+                depth: buffer.len(),
+                is_word: node.is_word,
+                child_count: node.children.len(),
+            })
+        };
+        // Each entry is a node still to be visited with its symbol and the length of its parent's prefix.
+        let mut stack: Vec<(&Node<S>, S, usize)> = vec![];
+        if view(start, &buffer) {
+            push_children(&mut stack, start, buffer.len());
+        }
+        while let Some((node, symbol, parent_len)) = stack.pop() {
+            buffer.truncate(parent_len);
+            buffer.push(symbol);
+            if view(node, &buffer) {
+                push_children(&mut stack, node, buffer.len());
+            }
+        }
+    }
+
+    /// Get every sequence that starts with `prefix` in symbol order, including `prefix` itself if it's in the trie.
+    pub fn sequences_with_prefix(&self, prefix: &[S]) -> Vec<Vec<S>> {
+        let mut sequences = vec![];
+        self.visit(prefix, &mut |node| {
+            if node.is_word {
+                sequences.push(node.prefix.to_vec());
+            }
+            true
+        });
+        sequences
+    }
+
+    /// Get the number of sequences that start with `prefix`.
+    pub fn count_with_prefix(&self, prefix: &[S]) -> usize {
+        self.find_node(prefix)
+            .map(|node| subtree_counts(node).1)
+            .unwrap_or(0)
+    }
+}
+
+impl ByteLetterTrie {
+    /// Create a trie of byte sequences, such as DNA reads. This is `from_sequences()` under the name that reads
+    /// best for bytes.
+    pub fn from_bytes_iter<I, W>(sequences: I) -> Self
+    where
+        I: IntoIterator<Item = W>,
+        W: AsRef<[u8]>,
+    {
+        Self::from_sequences(sequences)
+    }
+
+    /// Create a trie with one sequence for each line of `reader`, with ASCII whitespace trimmed from both ends and
+    /// blank lines skipped as in `LetterTrie::from_reader()`. Case is kept, and the lines don't have to be UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if reading fails.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: BufRead>(mut reader: R) -> Result<Self> {
+        let mut trie = Self::new();
+        let mut line: Vec<u8> = vec![];
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            trie.insert(line.trim_ascii());
+            line.clear();
+        }
+        Ok(trie)
+    }
+}
+
+impl<S: Symbol> Default for GenericTrie<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A short summary of the counts, like the Debug output of the letter tries.
+impl<S: Symbol> Debug for GenericTrie<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (node_count, word_count, height) = subtree_counts(&self.root);
+        f.debug_struct("GenericTrie")
+            .field("word_count", &word_count)
+            .field("node_count", &node_count)
+            .field("height", &height)
+            .finish()
+    }
+}
+
+// Push the children of node in reverse so that they come off the stack in symbol order.
+fn push_children<'a, S: Symbol>(
+    stack: &mut Vec<(&'a Node<S>, S, usize)>,
+    node: &'a Node<S>,
+    len: usize,
+) {
+    stack.extend(
+        node.children
+            .iter()
+            .rev()
+            .map(|(symbol, child)| (child, *symbol, len)),
+    );
+}
+
+fn fixed_node<S: Symbol>(node: &Node<S>, prefix: Vec<S>) -> GenericFixedNode<S> {
+    let (node_count, word_count, height) = subtree_counts(node);
+    GenericFixedNode {
+        symbol: prefix.last().copied(),
+        depth: prefix.len(),
+        prefix,
+        is_word: node.is_word,
+        child_count: node.children.len(),
+        node_count,
+        word_count,
+        height,
+    }
+}
+
+// The number of nodes, the number of sequences and the height of the subtree under node, counting the node's own
+// level in the height as the letter tries do. The walk keeps its own stack so a very deep trie can't overflow the
+// call stack.
+fn subtree_counts<S: Symbol>(node: &Node<S>) -> (usize, usize, usize) {
+    let (mut node_count, mut word_count, mut height) = (0, 0, 0);
+    let mut stack: Vec<(&Node<S>, usize)> = vec![(node, 1)];
+    while let Some((node, level)) = stack.pop() {
+        node_count += 1;
+        word_count += node.is_word as usize;
+        height = height.max(level);
+        stack.extend(node.children.values().map(|child| (child, level + 1)));
+    }
+    (node_count, word_count, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dna() {
+        let mut t = ByteLetterTrie::from_bytes_iter(vec!["GATTACA", "GATT", "CAT", "", "GATT"]);
+        let root = t.to_fixed_node();
+        assert_eq!(
+            (None, 3, 3 + 8, 8),
+            (root.symbol, root.word_count, root.node_count, root.height)
+        );
+        assert_eq!(vec![b'C', b'G'], {
+            let mut children = vec![];
+            t.visit(b"", &mut |node| {
+                if node.depth == 1 {
+                    children.push(node.symbol.unwrap());
+                }
+                node.depth == 0
+            });
+            children
+        });
+        let gatt = t.find(b"GATT").unwrap();
+        assert_eq!(
+            GenericFixedNode {
+                symbol: Some(b'T'),
+                prefix: b"GATT".to_vec(),
+                depth: 4,
+                is_word: true,
+                child_count: 1,
+                node_count: 4,
+                word_count: 2,
+                height: 4,
+            },
+            gatt
+        );
+        assert_eq!(None, t.find(b"gatt"));
+        assert_eq!(None, t.find(b""));
+        assert!(!t.contains(b""));
+
+        assert!(!t.remove(b"GAT"));
+        assert!(t.remove(b"GATT"));
+        assert!(t.contains(b"GATTACA"));
+        assert!(t.remove(b"GATTACA"));
+        assert!(!t.contains_prefix(b"G"));
+        assert_eq!(vec![b"CAT".to_vec()], t.sequences_with_prefix(b""));
+        assert_eq!(4, t.to_fixed_node().node_count);
+    }
+
+    #[test]
+    fn display_and_letter_nodes() {
+        let t = ByteLetterTrie::from_bytes_iter(vec![&b"a\x00b"[..]]);
+        assert_eq!("a\\x00b", t.find(b"a\x00b").unwrap().to_string());
+        let tokens = TokenTrie::from_sequences(vec![vec![u32::MAX, 0]]);
+        assert_eq!(
+            "4294967295 0",
+            tokens.find(&[u32::MAX, 0]).unwrap().to_string()
+        );
+
+        let bytes = ByteLetterTrie::from_bytes_iter(vec!["cross", "crossed", "an"]);
+        let letters = BaseLetterTrie::from_words(vec!["cross", "crossed", "an"]);
+        assert_eq!(
+            letters.to_fixed_node(),
+            bytes.to_fixed_node().to_letter_node()
+        );
+        assert_eq!(
+            letters.find("cro"),
+            bytes.find(b"cro").map(|node| node.to_letter_node())
+        );
+        let chars = GenericTrie::<char>::from_sequences(vec![vec!['ñ', 'a']]);
+        assert_eq!("ña", chars.find(&['ñ', 'a']).unwrap().to_string());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_reader_keeps_case_and_bytes() {
+        let content: &[u8] = b"  ACGT\r\n\nacgt\n\xff\xfe\nACG";
+        let t = ByteLetterTrie::from_reader(content).unwrap();
+        assert_eq!(
+            vec![
+                b"ACG".to_vec(),
+                b"ACGT".to_vec(),
+                b"acgt".to_vec(),
+                vec![0xff, 0xfe]
+            ],
+            t.sequences_with_prefix(b"")
+        );
+    }
+
+    // The walks and the drop keep their own stacks, so a very long sequence doesn't overflow the call stack.
+    #[test]
+    fn deep_chain() {
+        let sequence: Vec<u32> = (0..100_000).collect();
+        let t = TokenTrie::from_sequences(vec![&sequence[..], &sequence[..10]]);
+        assert_eq!(100_001, t.to_fixed_node().height);
+        assert_eq!(2, t.count_with_prefix(&[0, 1]));
+        assert_eq!(
+            vec![sequence[..10].to_vec(), sequence.clone()],
+            t.sequences_with_prefix(&[])
+        );
+    }
+}
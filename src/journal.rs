@@ -0,0 +1,403 @@
+//! An append-only log of the words added to and removed from a trie, so that changes made while a program runs
+//! survive a restart without saving the whole trie again.
+//!
+//! The journal is a text file with one change per line: `+` and the word for a word that was added, or `-` and the
+//! word for one that was removed. A typical program loads a snapshot such as a file from
+//! `LetterTrie::write_binary()`, replays the journal into it, and appends each change as it's made. Once the
+//! journal gets long, `Journal::compact()` replaces it with the trie's current words.
+//!
+//! Each change is written with a single write that ends in the line break and is synced to the disk before
+//! `Journal::append()` returns. If the program stops partway through a write, the last line is left without its
+//! line break. That line is a change that was never confirmed, so it's dropped when the journal is opened and
+//! reported by `Journal::replay_into()`, and any other malformed line is an error.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::*;
+
+/// One change recorded in a `Journal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WordOp {
+    Add(String),
+    Remove(String),
+}
+
+impl WordOp {
+    fn to_line(&self) -> Result<String> {
+        let (marker, word) = match self {
+            WordOp::Add(word) => ('+', word),
+            WordOp::Remove(word) => ('-', word),
+        };
+        if word.contains(['\n', '\r']) {
+            return Err(LetterTrieError::InvalidFormat(format!(
+                "{:?} can't be written to a journal because it has a line break",
+                word
+            )));
+        }
+        Ok(format!("{}{}\n", marker, word))
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut chars = line.chars();
+        match chars.next() {
+            Some('+') => Some(WordOp::Add(chars.as_str().to_owned())),
+            Some('-') => Some(WordOp::Remove(chars.as_str().to_owned())),
+            _ => None,
+        }
+    }
+}
+
+/// What `Journal::replay_into()` did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// The number of `WordOp::Add` lines applied.
+    pub added: usize,
+    /// The number of `WordOp::Remove` lines applied, whether or not the word was in the trie.
+    pub removed: usize,
+    /// The text of an unfinished last line that was skipped, left by a write that didn't complete.
+    pub torn_line: Option<String>,
+}
+
+/// An append-only file of `WordOp`s, described in the module documentation.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    // The unfinished last line found when the journal was opened, which has been cut off the file.
+    torn_line: Option<String>,
+}
+
+/// Open the journal at `path`, creating an empty one if there's no file there. This is the same as
+/// `Journal::open()`.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::File` with the path if the file can't be opened, read or created.
+pub fn open_journal(path: impl AsRef<Path>) -> Result<Journal> {
+    Journal::open(path)
+}
+
+impl Journal {
+    /// Open the journal at `path`, creating an empty one if there's no file there. An unfinished last line is cut
+    /// off so that the next change starts on a line of its own.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the path if the file can't be opened, read or created.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let path = std::env::temp_dir().join(format!("letter_trie_journal_doc_{}.txt", std::process::id()));
+    /// let mut journal = open_journal(&path).unwrap();
+    /// journal.append(WordOp::Add("crossed".to_owned())).unwrap();
+    /// journal.append(WordOp::Remove("cross".to_owned())).unwrap();
+    /// drop(journal);
+    ///
+    /// // After a restart, replay the changes into the trie loaded from the snapshot.
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["an", "cross"]);
+    /// let report = open_journal(&path).unwrap().replay_into(&mut trie).unwrap();
+    /// assert_eq!(vec!["an", "crossed"], trie.words_with_prefix(""));
+    /// assert_eq!((1, 1), (report.added, report.removed));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(in_file(&path))?;
+        let torn_line = cut_torn_line(&mut file).map_err(in_file(&path))?;
+        Ok(Self {
+            path,
+            file,
+            torn_line,
+        })
+    }
+
+    /// The path of the journal's file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add a change to the end of the journal. It's on the disk when this returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::InvalidFormat` if the word has a line break, which the format can't hold, and
+    /// `LetterTrieError::File` if writing fails.
+    pub fn append(&mut self, op: WordOp) -> Result<()> {
+        let line = op.to_line()?;
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.sync_data())
+            .map_err(in_file(&self.path))?;
+        Ok(())
+    }
+
+    /// Apply every change in the journal to `trie` in order, adding and removing words as `LetterTrie::insert()`
+    /// and `LetterTrie::remove()` do.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::InvalidLine` with the path and line for a complete line that isn't a change,
+    /// including when it isn't valid UTF-8, and `LetterTrieError::File` if reading fails. The changes before a bad line have already been
+    /// applied.
+    pub fn replay_into<T: LetterTrie>(&self, trie: &mut T) -> Result<ReplayReport> {
+        let mut report = ReplayReport {
+            torn_line: self.torn_line.clone(),
+            ..ReplayReport::default()
+        };
+        let mut reader = BufReader::new(File::open(&self.path).map_err(in_file(&self.path))?);
+        let mut buf: Vec<u8> = vec![];
+        let mut index = 0;
+        loop {
+            buf.clear();
+            if reader
+                .read_until(b'\n', &mut buf)
+                .map_err(in_file(&self.path))?
+                == 0
+            {
+                break;
+            }
+            index += 1;
+            if buf.pop() != Some(b'\n') {
+                // Only another program writing the file at the same time could leave this now.
+                report.torn_line = Some(String::from_utf8_lossy(&buf).into_owned());
+                break;
+            }
+            let op = core::str::from_utf8(&buf).ok().and_then(WordOp::from_line);
+            match op {
+                Some(WordOp::Add(word)) => {
+                    trie.insert(&word);
+                    report.added += 1;
+                }
+                Some(WordOp::Remove(word)) => {
+                    trie.remove(&word);
+                    report.removed += 1;
+                }
+                None => {
+                    return Err(LetterTrieError::InvalidLine {
+                        path: Some(self.path.clone()),
+                        line: index,
+                        message: format!("{:?} isn't a change", String::from_utf8_lossy(&buf)),
+                    })
+                }
+            }
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(torn_line) = &report.torn_line {
+            tracing::warn!(journal = ?self.path, torn_line = %torn_line, "skipped an unfinished journal line");
+        }
+        Ok(report)
+    }
+
+    /// Replace the journal with one `WordOp::Add` for each word in `trie`, so that replaying it into an empty trie
+    /// gives the same words. The new journal is written beside the old one and renamed over it, so a crash leaves
+    /// one or the other.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the path of the file that failed if writing or renaming fails, in which
+    /// case the old journal is still in place.
+    pub fn compact<T: LetterTrie + ?Sized>(&mut self, trie: &T) -> Result<()> {
+        let mut temp_name = self.path.as_os_str().to_owned();
+        temp_name.push(".compact");
+        let temp_path = PathBuf::from(temp_name);
+        {
+            let mut writer =
+                io::BufWriter::new(File::create(&temp_path).map_err(in_file(&temp_path))?);
+            for word in trie.words() {
+                let line = WordOp::Add(word).to_line()?;
+                writer
+                    .write_all(line.as_bytes())
+                    .map_err(in_file(&temp_path))?;
+            }
+            writer
+                .into_inner()
+                .map_err(|err| err.into_error())
+                .and_then(|file| file.sync_all())
+                .map_err(in_file(&temp_path))?;
+        }
+        fs::rename(&temp_path, &self.path).map_err(in_file(&temp_path))?;
+        self.file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(in_file(&self.path))?;
+        self.torn_line = None;
+        Ok(())
+    }
+}
+
+// Cut anything after the last line break off the end of the file and return it.
+fn cut_torn_line(file: &mut File) -> io::Result<Option<String>> {
+    // Only the end of the file needs to be read to find the last line break.
+    const CHUNK: u64 = 4_096;
+    let len = file.metadata()?.len();
+    let mut end = len;
+    let mut tail: Vec<u8> = vec![];
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK);
+        let mut chunk = vec![0u8; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        io::Read::read_exact(file, &mut chunk)?;
+        match chunk.iter().rposition(|byte| *byte == b'\n') {
+            Some(i) => {
+                tail.splice(0..0, chunk[i + 1..].iter().copied());
+                end = start + i as u64 + 1;
+                break;
+            }
+            None => {
+                tail.splice(0..0, chunk);
+                end = start;
+            }
+        }
+    }
+    if end == len {
+        return Ok(None);
+    }
+    file.set_len(end)?;
+    file.sync_data()?;
+    Ok(Some(String::from_utf8_lossy(&tail).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_with_snapshot() {
+        let snapshot_path = temp_path("journal_snapshot.txt");
+        let journal_path = temp_path("journal_restart.txt");
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+
+        let mut t = BaseLetterTrie::from_words(&words);
+        t.write_binary(File::create(&snapshot_path).unwrap())
+            .unwrap();
+        let mut journal = open_journal(&journal_path).unwrap();
+        let ops = vec![
+            WordOp::Add("zzyzx".to_owned()),
+            WordOp::Remove(words[0].clone()),
+            WordOp::Add("Ñandú".to_owned()),
+            WordOp::Remove("not there".to_owned()),
+            WordOp::Remove("zzyzx".to_owned()),
+            WordOp::Add("zzyzx".to_owned()),
+        ];
+        for op in &ops {
+            match op {
+                WordOp::Add(word) => t.insert(word),
+                WordOp::Remove(word) => {
+                    t.remove(word);
+                }
+            }
+            journal.append(op.clone()).unwrap();
+        }
+        drop(journal);
+
+        let mut restarted =
+            NoParentLetterTrie::read_binary(File::open(&snapshot_path).unwrap()).unwrap();
+        let journal = open_journal(&journal_path).unwrap();
+        let report = journal.replay_into(&mut restarted).unwrap();
+        assert_eq!(
+            ReplayReport {
+                added: 3,
+                removed: 3,
+                torn_line: None
+            },
+            report
+        );
+        assert!(t.diff(&restarted).is_empty());
+        assert!(restarted.contains("ñandú"));
+        assert!(!restarted.contains(&words[0]));
+    }
+
+    #[test]
+    fn torn_last_line() {
+        let path = temp_path("journal_torn.txt");
+        fs::write(&path, "+cross\n-an\n+cros").unwrap();
+        let mut journal = open_journal(&path).unwrap();
+        // The next change starts on its own line rather than finishing the torn one.
+        journal.append(WordOp::Add("crossed".to_owned())).unwrap();
+        assert_eq!(
+            "+cross\n-an\n+crossed\n",
+            fs::read_to_string(&path).unwrap()
+        );
+
+        let mut t = NoParentLetterTrie::from_words(vec!["an"]);
+        let report = journal.replay_into(&mut t).unwrap();
+        assert_eq!(Some("+cros".to_owned()), report.torn_line);
+        assert_eq!((2, 1), (report.added, report.removed));
+        assert_eq!(vec!["cross", "crossed"], t.words_with_prefix(""));
+
+        // A whole journal that's one unfinished line, longer than the chunk read from the end.
+        let long = format!("+{}", "a".repeat(10_000));
+        fs::write(&path, &long).unwrap();
+        let journal = open_journal(&path).unwrap();
+        assert!(fs::read(&path).unwrap().is_empty());
+        let report = journal.replay_into(&mut t).unwrap();
+        assert_eq!(Some(long), report.torn_line);
+    }
+
+    #[test]
+    fn bad_lines() {
+        let path = temp_path("journal_bad.txt");
+        fs::write(&path, "+cross\ncrossed\n+an\n").unwrap();
+        let journal = open_journal(&path).unwrap();
+        let mut t = BaseLetterTrie::new();
+        match journal.replay_into(&mut t) {
+            Err(err @ LetterTrieError::InvalidLine { line: 2, .. }) => {
+                let msg = err.to_string();
+                assert!(msg.starts_with("Invalid line 2 of \""), "{}", msg);
+                assert!(msg.contains(path.to_str().unwrap()), "{}", msg);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(vec!["cross"], t.words_with_prefix(""));
+
+        let mut journal = open_journal(&path).unwrap();
+        assert!(matches!(
+            journal.append(WordOp::Add("two\nwords".to_owned())),
+            Err(LetterTrieError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn compaction() {
+        let path = temp_path("journal_compact.txt");
+        let mut journal = open_journal(&path).unwrap();
+        let mut t = NoParentLetterTrie::new();
+        for word in &["cross", "an", "crossed", "azure"] {
+            journal.append(WordOp::Add((*word).to_owned())).unwrap();
+            t.insert(word);
+        }
+        journal.append(WordOp::Remove("an".to_owned())).unwrap();
+        t.remove("an");
+
+        journal.compact(&t).unwrap();
+        assert_eq!(
+            "+azure\n+cross\n+crossed\n",
+            fs::read_to_string(&path).unwrap()
+        );
+        journal.append(WordOp::Remove("cross".to_owned())).unwrap();
+        drop(journal);
+
+        let mut replayed = BaseLetterTrie::new();
+        let report = open_journal(&path)
+            .unwrap()
+            .replay_into(&mut replayed)
+            .unwrap();
+        assert_eq!((3, 1), (report.added, report.removed));
+        assert_eq!(vec!["azure", "crossed"], replayed.words_with_prefix(""));
+
+        let mut journal = open_journal(&path).unwrap();
+        journal.compact(&NoParentLetterTrie::new()).unwrap();
+        assert!(fs::read(&path).unwrap().is_empty());
+    }
+}
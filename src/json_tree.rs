@@ -0,0 +1,292 @@
+//! The structure of a trie as nested JSON objects, for drawing it with a tree viewer such as D3's.
+//!
+//! Each node is an object like `{"c":"a","word":true,"children":[...],"n":4,"nodes":6,"height":3}` with these keys:
+//!
+//! - `c`: the node's letter, or an empty string for the root.
+//! - `word`: whether the letters down to the node are a word.
+//! - `children`: the nodes below it in character order.
+//! - `n`, `nodes` and `height`: the node's `FixedNode::word_count`, `FixedNode::node_count` and
+//!   `FixedNode::height`, which cover the whole subtree whether or not all of it is shown.
+//! - `truncated`: the number of the node's children left out of `children` because of `max_depth` or
+//!   `max_children`. It's only there if some were.
+//!
+//! The counts come after `children` so that the whole tree can be written in one walk with nothing kept but the
+//! path down to the current node. JSON doesn't care about the order of keys. Quotes, backslashes and control
+//! characters are escaped, and so is anything outside ASCII, so the output is plain ASCII however it's served.
+
+use core::fmt::{self, Write};
+
+use crate::*;
+
+// One node on the path down to the one being visited.
+struct Frame {
+    is_shown: bool,
+    children_shown: usize,
+    children_left_out: usize,
+    word_count: usize,
+    node_count: usize,
+    height: usize,
+}
+
+// Write the tree to out as described in the module documentation. A node is shown if its depth is at most
+// max_depth and it's one of the first max_children children of a node that's shown.
+pub(crate) fn write_tree<T: LetterTrie + ?Sized>(
+    trie: &T,
+    max_depth: Option<usize>,
+    max_children: Option<usize>,
+    out: &mut dyn Write,
+) -> fmt::Result {
+    let max_depth = max_depth.unwrap_or(usize::MAX);
+    let max_children = max_children.unwrap_or(usize::MAX);
+    let mut path: Vec<Frame> = vec![];
+    let mut result = Ok(());
+    // Every node is visited, even the ones that aren't shown, since they count toward the nodes above them.
+    trie.visit("", &mut |node| {
+        if result.is_err() {
+            return false;
+        }
+        while path.len() > node.depth {
+            result = close(&mut path, out);
+        }
+        let is_shown = match path.last_mut() {
+            None => true,
+            Some(parent) if !parent.is_shown => false,
+            Some(parent) if node.depth > max_depth || parent.children_shown == max_children => {
+                parent.children_left_out += 1;
+                false
+            }
+            Some(parent) => {
+                if parent.children_shown > 0 {
+                    result = result.and_then(|_| out.write_char(','));
+                }
+                parent.children_shown += 1;
+                true
+            }
+        };
+        if is_shown {
+            result = result
+                .and_then(|_| out.write_str("{\"c\":"))
+                .and_then(|_| write_letter(node, out))
+                .and_then(|_| write!(out, ",\"word\":{},\"children\":[", node.is_word));
+        }
+        path.push(Frame {
+            is_shown,
+            children_shown: 0,
+            children_left_out: 0,
+            word_count: node.is_word as usize,
+            node_count: 1,
+            height: 1,
+        });
+        result.is_ok()
+    });
+    while result.is_ok() && !path.is_empty() {
+        result = close(&mut path, out);
+    }
+    result
+}
+
+// Finish the last node on the path and add its counts to its parent's.
+fn close(path: &mut Vec<Frame>, out: &mut dyn Write) -> fmt::Result {
+    let Some(frame) = path.pop() else {
+        return Ok(());
+    };
+    if frame.is_shown {
+        write!(
+            out,
+            "],\"n\":{},\"nodes\":{},\"height\":{}",
+            frame.word_count, frame.node_count, frame.height
+        )?;
+        if frame.children_left_out > 0 {
+            write!(out, ",\"truncated\":{}", frame.children_left_out)?;
+        }
+        out.write_char('}')?;
+    }
+    if let Some(parent) = path.last_mut() {
+        parent.word_count += frame.word_count;
+        parent.node_count += frame.node_count;
+        parent.height = parent.height.max(frame.height + 1);
+    }
+    Ok(())
+}
+
+// Write the node's letter as a JSON string, or an empty one for the root. Anything outside printable ASCII is
+// written as UTF-16, with a pair of escapes for a character past the Basic Multilingual Plane.
+fn write_letter(node: &NodeView, out: &mut dyn Write) -> fmt::Result {
+    out.write_char('"')?;
+    if node.depth > 0 {
+        let c = node.c;
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            ' '..='~' => out.write_char(c)?,
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+        }
+    }
+    out.write_char('"')
+}
+
+// A fmt::Write that writes to an io::Write and keeps the first error, which fmt::Error can't carry.
+#[cfg(feature = "std")]
+pub(crate) struct IoWriter<W> {
+    pub(crate) inner: W,
+    pub(crate) error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        io::Write::write_all(&mut self.inner, s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn child<'a>(node: &'a Value, c: &str) -> &'a Value {
+        node["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|child| child["c"] == c)
+            .unwrap_or_else(|| panic!("no child {:?} in {}", c, node))
+    }
+
+    // Check each node shown against find() for its prefix and return the number shown.
+    fn check_against_find<T: LetterTrie>(t: &T, node: &Value, prefix: &str) -> usize {
+        let expected = if prefix.is_empty() {
+            t.to_fixed_node()
+        } else {
+            t.find(prefix).unwrap()
+        };
+        assert_eq!(expected.is_word, node["word"], "{}", prefix);
+        assert_eq!(expected.word_count, node["n"], "{}", prefix);
+        assert_eq!(expected.node_count, node["nodes"], "{}", prefix);
+        assert_eq!(expected.height, node["height"], "{}", prefix);
+        let children = node["children"].as_array().unwrap();
+        let left_out = node
+            .get("truncated")
+            .map_or(0, |n| n.as_u64().unwrap() as usize);
+        assert_eq!(
+            expected.child_count,
+            children.len() + left_out,
+            "{}",
+            prefix
+        );
+        let letters: Vec<String> = children
+            .iter()
+            .map(|child| child["c"].as_str().unwrap().to_owned())
+            .collect();
+        let all_letters: Vec<String> = t.children(prefix).iter().map(|c| c.to_string()).collect();
+        assert_eq!(all_letters[..letters.len()], letters[..], "{}", prefix);
+        1 + children
+            .iter()
+            .map(|child| {
+                let child_prefix = format!("{}{}", prefix, child["c"].as_str().unwrap());
+                check_against_find(t, child, &child_prefix)
+            })
+            .sum::<usize>()
+    }
+
+    fn check_small<T: LetterTrie>() {
+        let t = T::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous).unwrap();
+        let root: Value = serde_json::from_str(&t.to_json_tree(None, None)).unwrap();
+        assert_eq!("", root["c"]);
+        assert_eq!(28, root["nodes"]);
+        assert_eq!(10, root["n"]);
+        assert!(root.get("truncated").is_none());
+        let and = child(child(child(&root, "a"), "n"), "d");
+        assert_eq!(true, and["word"]);
+        assert_eq!(1, and["nodes"]);
+        assert_eq!(28, check_against_find(&t, &root, ""));
+
+        let mut bytes: Vec<u8> = vec![];
+        t.write_json_tree(&mut bytes, None, None).unwrap();
+        assert_eq!(t.to_json_tree(None, None).into_bytes(), bytes);
+    }
+
+    #[test]
+    fn small() {
+        check_small::<BaseLetterTrie>();
+        check_small::<NoParentLetterTrie>();
+    }
+
+    fn check_limits<T: LetterTrie>() {
+        let t = T::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous).unwrap();
+        let fixed_node = t.to_fixed_node();
+        let root: Value = serde_json::from_str(&t.to_json_tree(Some(0), None)).unwrap();
+        assert_eq!(0, root["children"].as_array().unwrap().len());
+        assert_eq!(fixed_node.child_count, root["truncated"]);
+        assert_eq!(fixed_node.node_count, root["nodes"]);
+
+        let root: Value = serde_json::from_str(&t.to_json_tree(Some(3), Some(2))).unwrap();
+        // The root, two children, four grandchildren and at most eight below those.
+        let shown = check_against_find(&t, &root, "");
+        assert!((7..=15).contains(&shown), "{}", shown);
+        assert_eq!(fixed_node.child_count - 2, root["truncated"]);
+        assert_eq!(fixed_node.word_count, root["n"]);
+    }
+
+    #[test]
+    fn limits() {
+        check_limits::<BaseLetterTrie>();
+        check_limits::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn escapes() {
+        let t = NoParentLetterTrie::from_words(vec!["a\"b", "a\\", "a\tç", "ü😀", "a/<"]);
+        let json = t.to_json_tree(None, None);
+        assert!(json.is_ascii());
+        assert!(json.contains(r#""c":"\"""#));
+        assert!(json.contains(r#""c":"\\""#));
+        assert!(json.contains(r#""c":"\t""#));
+        assert!(json.contains(r#""c":"\ud83d\ude00""#));
+        assert!(json.contains(r#""c":"\u00fc""#));
+        let root: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(true, child(child(&root, "a"), "\"")["children"][0]["word"]);
+        assert_eq!(4, child(&root, "a")["children"].as_array().unwrap().len());
+        assert_eq!(true, child(child(&root, "ü"), "😀")["word"]);
+        assert_eq!(true, child(child(child(&root, "a"), "\t"), "ç")["word"]);
+        assert_eq!(5, root["n"]);
+    }
+
+    #[test]
+    fn empty_trie() {
+        let t = BaseLetterTrie::new();
+        assert_eq!(
+            r#"{"c":"","word":false,"children":[],"n":0,"nodes":1,"height":1}"#,
+            t.to_json_tree(None, None)
+        );
+    }
+
+    #[test]
+    fn write_errors_are_kept() {
+        struct Full;
+        impl io::Write for Full {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let t = NoParentLetterTrie::from_words(vec!["cross"]);
+        match t.write_json_tree(Full, None, None) {
+            Err(LetterTrieError::Io(err)) => assert_eq!("full", err.to_string()),
+            other => panic!("{:?}", other),
+        }
+    }
+}
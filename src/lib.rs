@@ -1,351 +1,4187 @@
-// Enables the use of Weak::strong_count() and Weak::weak_count().
-#![feature(weak_counts)]
 #![allow(clippy::new_without_default)]
-#![feature(test)]
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+// Without the std feature only core and alloc are used, so the tries can be built for a target with an allocator
+// but no operating system.
+#![cfg_attr(not(feature = "std"), no_std)]
+// The benchmarks use the unstable test crate so only test builds need a nightly compiler.
+#![cfg_attr(test, feature(test))]
 
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "fs")]
 extern crate regex;
+#[cfg(test)]
 extern crate test;
 
-#[macro_use]
-extern crate lazy_static;
-
+// These are in the std prelude but have to be imported under no_std. The modules that use crate::* get them from
+// here.
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cell::{Cell, RefCell};
+use core::fmt::{self, Debug};
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
 use std::collections::HashSet;
-use std::fmt::Debug;
+#[cfg(feature = "fs")]
+use std::env;
+#[cfg(feature = "std")]
+use std::error;
+#[cfg(feature = "fs")]
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::sync::Mutex;
-use std::time::Instant;
+#[cfg(feature = "fs")]
+use std::io::BufReader;
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
+#[cfg(feature = "async")]
+pub mod async_load;
+#[cfg(feature = "async")]
+pub use async_load::AsyncLetterTrie;
+#[cfg(all(test, feature = "std"))]
+mod alloc_count;
+pub mod annotate;
+pub use annotate::{AnnotatedTrie, NodeId};
 pub mod base_letter_trie;
+#[cfg(feature = "std")]
+pub mod binary;
+mod children;
+#[cfg(feature = "std")]
+pub mod codegen;
+mod compounds;
+#[cfg(feature = "fs")]
+pub mod conformance;
+pub mod constraints;
+pub use constraints::{LetterConstraints, LetterFeedback};
+pub mod cursor;
+pub use cursor::TrieCursor;
+mod debug_tree;
+pub mod diacritics;
+pub mod diff;
+pub use diff::TrieDiff;
+#[cfg(feature = "std")]
+mod duplicates;
+#[cfg(feature = "fs")]
+mod external;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod frequency;
+#[cfg(feature = "std")]
+pub mod front_coded;
+#[cfg(feature = "std")]
+pub mod fuzzing;
+#[cfg(feature = "std")]
+pub mod generator;
+pub mod generic_trie;
+pub use generic_trie::{
+    ByteLetterTrie, GenericFixedNode, GenericNodeView, GenericTrie, Symbol, TokenTrie,
+};
+#[cfg(feature = "fs")]
+pub mod journal;
+#[cfg(feature = "fs")]
+pub use journal::{open_journal, Journal, ReplayReport, WordOp};
+pub mod json_tree;
+#[cfg(test)]
+mod properties;
 pub use base_letter_trie::BaseLetterTrie;
+pub mod limits;
+pub use limits::{Completeness, Limited, LimitedQuery, QueryLimits};
+#[cfg(feature = "std")]
+pub mod line_parser;
+#[cfg(feature = "std")]
+pub use line_parser::ParsedWord;
+pub mod metrics;
+pub use metrics::{FindMetrics, FindOutcome};
+#[cfg(feature = "std")]
+pub mod load_stats;
+#[cfg(feature = "std")]
+pub use load_stats::*;
+pub mod matcher;
+pub mod neighbors;
+pub use neighbors::Neighbors;
 pub mod no_parent_letter_trie;
 pub use no_parent_letter_trie::NoParentLetterTrie;
+pub mod options;
+pub use options::{
+    CasePreference, ConfiguredLetterTrie, DuplicatePolicy, LimitPolicy, LoadReport, TrieOptions,
+    Utf8Policy,
+};
+pub mod overlay;
+pub use overlay::OverlayTrie;
+pub mod phonetic;
+#[cfg(feature = "std")]
+pub mod prefix_cache;
+#[cfg(feature = "std")]
+pub use prefix_cache::CacheStats;
+pub mod prelude;
+pub mod rc_report;
+pub use rc_report::{RcAnomaly, RcNodeCounts, RcReport};
+#[cfg(feature = "regex")]
+mod regex_search;
+#[cfg(feature = "regex")]
+pub use regex_search::RegexQuery;
+pub mod removal;
+pub use removal::RemovalReport;
+pub mod scores;
+pub use scores::ScoredQuery;
+pub mod self_test;
+pub use self_test::{SelfTestFailure, SelfTestReport, SelfTestSpec};
+#[cfg(feature = "std")]
+pub mod sharded_trie;
+#[cfg(feature = "std")]
+pub use sharded_trie::ShardedLetterTrie;
+#[cfg(feature = "fs")]
+pub mod sharding;
+#[cfg(all(test, feature = "fs"))]
+mod snapshots;
+#[cfg(feature = "fs")]
+pub use sharding::{Shard, ShardBy, ShardManifest};
+#[cfg(feature = "fs")]
+pub mod shootout;
+#[cfg(feature = "fs")]
+pub use shootout::{membership_shootout, Contender, ContenderResult, ProbeSet, ShootoutReport};
+#[cfg(feature = "std")]
+pub mod source_order;
+#[cfg(feature = "std")]
+pub use source_order::SourceOrder;
+pub mod static_trie;
+#[cfg(feature = "parallel")]
+pub mod streaming;
+#[cfg(feature = "parallel")]
+pub use streaming::WordSender;
+#[cfg(feature = "std")]
+pub mod suggestions;
+#[cfg(feature = "std")]
+pub use suggestions::{Suggestion, SuggestionConfig};
+pub mod suffix_sharing;
+pub use suffix_sharing::{SharedSubtree, SuffixReport};
+pub mod tags;
+pub use tags::{SourcedWord, TaggedLetterTrie, WordsWithSources, WordsWithTags};
+pub mod trace;
+#[cfg(feature = "fs")]
+pub use trace::{record_trace, replay_trace, OperationStats, ReplayStats, TraceGuard};
+#[cfg(feature = "std")]
+pub mod transitions;
+#[cfg(feature = "std")]
+pub use transitions::TransitionModel;
 pub mod util;
-pub use util::*;
+pub use util::{format_bytes, format_count, format_count_with, format_duration, NumberStyle};
+#[cfg(feature = "std")]
+pub use util::{
+    print_duration, print_elapsed, print_elapsed_from_start, resident_memory_bytes, Instant, Timer,
+};
+#[cfg(feature = "fs")]
+pub use util::{validate_word_file, LineProblem, OutOfOrder, ValidationPolicy, ValidationReport};
+pub mod view;
+pub use view::TrieView;
+#[cfg(feature = "fs")]
 pub mod text_util;
+#[cfg(feature = "fs")]
 pub use text_util::*;
 
+#[cfg(feature = "std")]
 const USE_CHAR_GET_COUNTER: bool = false;
 
 const DEBUG_TRIE_MAX_DEPTH: usize = 1000;
 const DEBUG_TRIE_MAX_CHILDREN: usize = 1000;
+// The number of words shown in the Debug output of a trie.
+const DEBUG_TRIE_FIRST_WORD_COUNT: usize = 5;
+// The number of words shown for each first letter when a trie is printed with an object_detail_level of 2 or more.
+#[cfg(feature = "std")]
+const DEBUG_BRANCH_SAMPLE_COUNT: usize = 3;
 
+#[cfg(feature = "std")]
 const WORD_COUNT_SMALL: usize = 10;
+#[cfg(feature = "std")]
 const WORD_COUNT_MEDIUM: usize = 20_000;
+#[cfg(feature = "std")]
 const WORD_COUNT_LARGE: usize = 400_000;
+#[cfg(feature = "fs")]
 const WORD_COUNT_GOOD: usize = 1_000;
+#[cfg(feature = "fs")]
 const WORD_COUNT_NON: usize = 1_000;
 
+#[cfg(feature = "std")]
+const FINGERPRINT_SMALL_SORTED: u64 = 0xbc7f_be21_fb21_6df7;
+#[cfg(feature = "std")]
+const FINGERPRINT_SMALL_UNSORTED: u64 = 0x991a_992b_a98f_03af;
+#[cfg(feature = "std")]
+const FINGERPRINT_MEDIUM_SORTED: u64 = 0x4f1d_952f_4478_4fa4;
+#[cfg(feature = "std")]
+const FINGERPRINT_MEDIUM_UNSORTED: u64 = 0xfdfd_7e4d_d543_6fc2;
+#[cfg(feature = "std")]
+const FINGERPRINT_LARGE_SORTED: u64 = 0x47f5_5dc3_e4f6_bfc3;
+#[cfg(feature = "std")]
+const FINGERPRINT_LARGE_UNSORTED: u64 = 0x4644_ecfa_8a5a_e4ff;
+
+#[cfg(feature = "std")]
 const FILENAME_SMALL_SORTED: &str = "english_words_10_sorted.txt";
+#[cfg(feature = "std")]
 const FILENAME_SMALL_UNSORTED: &str = "english_words_10_unsorted.txt";
+#[cfg(feature = "std")]
 const FILENAME_MEDIUM_SORTED: &str = "fake_words_20_000_sorted.txt";
+#[cfg(feature = "std")]
 const FILENAME_MEDIUM_UNSORTED: &str = "fake_words_20_000_unsorted.txt";
+#[cfg(feature = "std")]
 const FILENAME_LARGE_SORTED: &str = "fake_words_400_000_sorted.txt";
+#[cfg(feature = "std")]
 const FILENAME_LARGE_UNSORTED: &str = "fake_words_400_000_unsorted.txt";
+#[cfg(feature = "fs")]
 const FILENAME_GOOD_WORDS: &str = "test_good_words.txt";
+#[cfg(feature = "fs")]
 const FILENAME_NON_WORDS: &str = "test_non_words.txt";
 
+// The small dataset and the verification lists are built into the crate as well, so that the doctests and the
+// smallest conformance checks don't need the word files.
+#[cfg(feature = "fs")]
+const EMBEDDED_SMALL_UNSORTED: &str = include_str!("../english_words_10_unsorted.txt");
+#[cfg(feature = "fs")]
+const EMBEDDED_GOOD_WORDS: &str = include_str!("../test_good_words.txt");
+#[cfg(feature = "fs")]
+const EMBEDDED_NON_WORDS: &str = include_str!("../test_non_words.txt");
+
+// What to do about a missing word file, for the errors that say one isn't there.
+#[cfg(feature = "fs")]
+const DATA_DIR_HINT: &str = "The word files are looked for in the current directory, or in the directory named by \
+    the LETTER_TRIE_DATA_DIR environment variable if it's set. Dataset::EmbeddedSmall works without them.";
+
+// The word length assumed by CapacityHints::from_file_len(), which with the newline is a little under the bytes
+// per line of the large datasets.
+#[cfg(feature = "std")]
+const HINT_WORD_LEN: usize = 9;
+
+// The most that CapacityHints makes room for, which is several times the largest word lists around.
+#[cfg(feature = "std")]
+const MAX_HINTED_WORDS: usize = 1 << 22;
+#[cfg(feature = "std")]
+const MAX_HINTED_WORD_LEN: usize = 64;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+#[cfg(feature = "std")]
 const LABEL_STEP_OVERALL: &str = "overall load";
+#[cfg(feature = "std")]
 const LABEL_STEP_READ_FILE: &str = "read file";
+#[cfg(feature = "std")]
 const LABEL_STEP_MAKE_VECTOR: &str = "make_vector";
+#[cfg(feature = "parallel")]
 const LABEL_STEP_SORT_VECTOR: &str = "sort_vector";
+#[cfg(feature = "std")]
 const LABEL_STEP_LOAD_FROM_VEC: &str = "load from vector";
+#[cfg(feature = "std")]
 const LABEL_STEP_READ_AND_VECTOR: &str = "make vector from file";
+#[cfg(feature = "parallel")]
+const LABEL_STEP_BUILD_FROM_LINES: &str = "build from lines";
+#[cfg(feature = "std")]
+const LABEL_STEP_INSERT_IN_ORDER: &str = "insert in source order";
+#[cfg(feature = "fs")]
+const LABEL_STEP_WRITE_CHUNKS: &str = "write chunks";
+#[cfg(feature = "fs")]
+const LABEL_STEP_MERGE_CHUNKS: &str = "merge chunks";
+#[cfg(feature = "fs")]
+const LABEL_STEP_LOAD_MERGED_CHUNKS: &str = "load merged chunks";
 
-/// A letter trie (https://www.geeksforgeeks.org/trie-insert-and-search) with implementations that use different
-/// approaches for parent and child links but otherwise work the same.
+/// The queries of a trie, which `LetterTrie` has as a supertrait and which `TrieView` has on its own, so that a
+/// read-only view answers them with the same code as the trie it's borrowed from. Each implementation gives the
+/// required methods, and the rest come from the default bodies here unless an implementation has a faster way.
 ///
-/// One use of such a trie is to play millions of rounds of a word game like Scrabble or Boggle. In either game there
-/// is a branching set of possible letter sequences given a set of seven tiles or sixteen dice. The trie allows one
-/// to work letter-by-letter through the possible sequences while stepping node-by-node through the trie at the same
-/// time. It may be possible to cut the process short without either running out of tiles/dice or reaching a leaf
-/// node in the trie. For instance, we may be tracking the best score reached so far and a given node could be
-/// annotated with the highest score that could possibly be reached in its subtree. If this highest possible score
-/// is less than or equal to the best score we've already found, there's no point in going into that subtree or
-/// (which is saying the same thing) in trying to add another tile or die to the word we've been forming.
+/// This trait is sealed: it's implemented by the tries in this crate and by `TrieView`, and can't be implemented
+/// outside of it. Bring it into scope with `use letter_trie::*;` or the prelude to call the queries on either.
 ///
-/// A trie of even half a million words in a given language (with probably fewer than 1.5 million nodes) is still going
-/// to be orders of magnitude smaller than the possible sequences of letters found in one throw of the Boggle dice.
-/// This means that even if we don't cut the search short because of the best possible score in a subtree, we're
-/// still in most cases going to run out of trie before we run out of sequences of dice.
-pub trait LetterTrie {
-    /// Create a trie from words in a text file.
-    ///
-    /// The text file may contain up to one word per line. The words may be upper- or lowercase and
-    /// blank lines and whitespace before or after the words will be ignored. Duplicate words will also be
-    /// ignored.
-    ///
-    /// # Errors
+/// # Case and whitespace
+///
+/// A word or a prefix given to a query as a string is trimmed and lowercased before it's looked for, the same way
+/// `LetterTrie::insert()` stores words, so `contains(" Cross ")` is true once "cross" is in the trie. Whitespace
+/// inside a word is kept. The queries that take characters one at a time, such as `LetterTrie::contains_chars()`
+/// and `TrieCursor::step()`, lowercase them but take any whitespace as a letter like any other.
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut trie = NoParentLetterTrie::from_words(vec!["Cross "]);
+/// trie.insert_with_tag("crossed", 7);
+/// assert!(trie.contains(" CROSS"));
+/// assert_eq!(Some(7), trie.tag(" Crossed\n"));
+/// assert_eq!(vec!["cross", "crossed"], trie.words_with_prefix(" cr"));
+/// assert!(!trie.contains_chars(" cross".chars()));
+/// ```
+pub trait TrieQuery: view::sealed::Sealed {
+    /// Get the tag attached to `word` with `insert_with_tag()`, or None if it isn't in the trie or has no tag.
+    fn tag(&self, word: &str) -> Option<u64>;
+
+    /// Get the sources recorded for `word` in ascending order without repeats, or None if it isn't in the trie or
+    /// has no sources.
+    fn sources(&self, word: &str) -> Option<&[u16]>;
+
+    /// Get the spelling kept for `word` by `insert_preserving_case()` or `insert_with_spelling()`, or None if it
+    /// isn't in the trie or has no spelling.
+    fn spelling(&self, word: &str) -> Option<&str>;
+
+    /// Get the score given to `word` by `set_scores()` or `set_score()`, or None if it isn't in the trie or has no
+    /// score.
+    fn score(&self, word: &str) -> Option<f64>;
+
+    /// Get the rank given to `word` by `set_rank()` or a `SourceOrder::FrequencyRanked` load, or None if it isn't
+    /// in the trie or has no rank.
+    fn rank_of(&self, word: &str) -> Option<u32>;
+
+    /// Given a word or a partial word, find the corresponding node in the trie if it exists.
+    fn find(&self, prefix: &str) -> Option<FixedNode>;
+
+    /// Look up a word or a partial word and say whether it's a word, only the start of longer words, or not in
+    /// the trie at all. The empty string is `PrefixOnly` with the root node since it starts every word.
     ///
-    /// This will produce an incorrect trie if the file contains lines with more than one word.
+    /// # Examples
     ///
-    /// This may crash or produce an incorrect trie if all three of these conditions are met:
-    /// - The words in the file are not sorted at least by their first letter (subsequent letters don't matter).
-    /// - `is_sorted` is incorrectly set to `true`.
-    /// - The load method uses an optimization that relies on the words being sorted by their first letter. Currently the only such load method is `LoadMethod::ContinuousParallel`.
+    /// ```rust
+    /// use letter_trie::*;
     ///
-    /// # Panics
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossing"]);
+    /// assert!(matches!(trie.lookup("Cross"), Lookup::Word(node) if node.word_count == 2));
+    /// assert!(matches!(trie.lookup("cros"), Lookup::PrefixOnly(node) if node.child_count == 1));
+    /// assert_eq!(Lookup::NotFound, trie.lookup("crosses"));
+    /// ```
+    fn lookup(&self, s: &str) -> Lookup {
+        if s.trim().is_empty() {
+            return Lookup::PrefixOnly(self.to_fixed_node());
+        }
+        match self.find(s) {
+            Some(node) if node.is_word => Lookup::Word(node),
+            Some(node) => Lookup::PrefixOnly(node),
+            None => Lookup::NotFound,
+        }
+    }
+
+    /// For testing or debugging, create a FixedNode from the root node of a trie.
+    fn to_fixed_node(&self) -> FixedNode;
+
+    /// Walk the subtree starting at the node for `prefix`, depth-first and in character order, calling `f` once
+    /// for each node.
     ///
-    /// Panics if the file does not exist or can't be opened for reading.
-    fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self;
+    /// The first node visited is the one for `prefix` itself (the root if `prefix` is empty). If `f` returns
+    /// false the children of that node are skipped, which is what allows a search to cut a branch short. Nothing
+    /// is visited if `prefix` is not in the trie. `NodeView::prefix` is borrowed from a buffer that's reused as
+    /// the walk goes up and down the trie so building a word doesn't allocate unless `f` keeps a copy.
+    fn visit(&self, prefix: &str, f: &mut dyn FnMut(&NodeView) -> bool);
 
-    /// Create a trie from words in a text file, optionally displaying elapsed time for each step.
+    /// Walk the subtree for `prefix` as `visit()` does but with each node's children in reverse character order.
+    /// A node is still visited before its children, so the words come in reverse order if each one is taken once
+    /// its children are done, which is how `neighbors()` finds the words before a string.
     ///
-    /// The text file may contain up to one word per line. The words may be upper- or lowercase and
-    /// blank lines and whitespace before or after the words will be ignored. Duplicate words will also be
-    /// ignored.
+    /// # Examples
     ///
-    /// # Errors
+    /// ```rust
+    /// use letter_trie::*;
     ///
-    /// This will produce an incorrect trie if the file contains lines with more than one word.
+    /// let trie = NoParentLetterTrie::from_words(vec!["an", "and", "cross"]);
+    /// let mut letters = String::new();
+    /// trie.visit_rev("", &mut |node| {
+    ///     letters.push(node.c);
+    ///     true
+    /// });
+    /// assert_eq!(" crossand", letters);
+    /// ```
+    fn visit_rev(&self, prefix: &str, f: &mut dyn FnMut(&NodeView) -> bool);
+
+    /// Get a cursor at the root for walking down the trie a letter at a time, without going back over the letters
+    /// already matched as `contains_prefix()` on each longer prefix would. See the `cursor` module.
     ///
-    /// This may crash or produce an incorrect trie if all three of these conditions are met:
-    /// - The words in the file are not sorted at least by their first letter (subsequent letters don't matter).
-    /// - `is_sorted` is incorrectly set to `true`.
-    /// - The load method uses an optimization that relies on the words being sorted by their first letter. Currently the only such load method is `LoadMethod::ContinuousParallel`.
+    /// # Examples
     ///
-    /// # Panics
+    /// ```rust
+    /// use letter_trie::*;
     ///
-    /// Panics if the file does not exist or can't be opened for reading.
-    fn from_file_test(
-        filename: &str,
-        is_sorted: bool,
-        load_method: &LoadMethod,
-        opt: &DisplayDetailOptions,
-        expected_word_count: Option<usize>,
-    ) -> Self;
-
-    /// Given a word or a partial word, find the corresponding node in the trie if it exists.
-    fn find(&self, prefix: &str) -> Option<FixedNode>;
-
-    /// For testing or debugging, create a FixedNode from the root node of a trie.
-    fn to_fixed_node(&self) -> FixedNode;
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// let mut cursor = trie.cursor();
+    /// assert!(cursor.step_chars(['C', 'r', 'o', 's', 's']));
+    /// assert!(cursor.is_word());
+    /// assert_eq!(1, cursor.child_count());
+    /// ```
+    fn cursor(&self) -> TrieCursor<'_>;
 
-    /// Print one line of information about the root node of a trie.
+    /// Get the characters of the children of the node for `prefix` in character order, or an empty list if
+    /// `prefix` isn't in the trie. The children of the root are the first letters of the words.
     ///
-    /// This includes things like the number of nodes and words in the trie and the maximum height.
-    fn print_root(&self) {
-        println!("{:?}", self.to_fixed_node());
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "an", "crease", "crossed"]);
+    /// assert_eq!(vec!['a', 'c'], trie.children(""));
+    /// assert_eq!(vec!['e', 'o'], trie.children("CR"));
+    /// assert!(trie.children("q").is_empty());
+    /// ```
+    fn children(&self, prefix: &str) -> Vec<char> {
+        let mut children = vec![];
+        let mut depth = None;
+        self.visit(prefix, &mut |node| match depth {
+            None => {
+                depth = Some(node.depth);
+                true
+            }
+            Some(_) => {
+                children.push(node.c);
+                false
+            }
+        });
+        children
     }
 
-    /// Print information about the root node of a trie over multiple lines.
+    /// Get the letters that can follow `prefix`, each with whether adding it to `prefix` makes a word, in
+    /// character order, or an empty list if `prefix` isn't in the trie. Only the children of the prefix's node are
+    /// looked at, not anything below them.
     ///
-    /// This includes things like the number of nodes and words in the trie and the maximum height.
-    fn print_root_alt(&self) {
-        println!("{:#?}", self.to_fixed_node());
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// // a, an, and, azure, crease, creative, creator, creature, cross, crossed
+    /// let trie = NoParentLetterTrie::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap();
+    /// assert_eq!(vec![('a', true), ('c', false)], trie.next_letters(""));
+    /// assert_eq!(vec![('n', true), ('z', false)], trie.next_letters("a"));
+    /// assert_eq!(vec![('a', false)], trie.next_letters("cre"));
+    /// assert_eq!(vec![('s', true)], trie.next_letters("CROS"));
+    /// assert!(trie.next_letters("crossed").is_empty());
+    /// assert!(trie.next_letters("q").is_empty());
+    /// ```
+    fn next_letters(&self, prefix: &str) -> Vec<(char, bool)> {
+        let mut letters = vec![];
+        let mut depth = None;
+        self.visit(prefix, &mut |node| match depth {
+            None => {
+                depth = Some(node.depth);
+                true
+            }
+            Some(_) => {
+                letters.push((node.c, node.is_word));
+                false
+            }
+        });
+        letters
     }
-}
 
-/// Choice of the collection of words to load in the letter trie.
-///
-/// Whether the words are sorted in the collection may affect the speed of loading the trie depending on the
-/// chosen LoadMethod but the resulting trie will be identical either way.
-#[derive(Debug)]
-pub enum Dataset {
-    /// Small file with nine sorted English words leading to a trie with 26 nodes and a maximum height of 9.
-    TestSmallSorted,
-    /// Small file with nine unsorted English words leading to a trie with 26 nodes and a maximum height of 9.
-    TestSmallUnsorted,
-    /// Medium file with 10,000 sorted non-English words leading to a trie with 19,089 nodes and a maximum height of 16.
-    TestMediumSorted,
-    /// Medium file with 10,000 unsorted non-English words leading to a trie with 19,089 nodes and a maximum height of 16.
-    TestMediumUnsorted,
-    /// Large file with 584,983 sorted non-English words leading to a trie with 1,143,413 nodes and a maximum height of 16.
-    TestLargeSorted,
-    /// Large file with 584,983 unsorted non-English words leading to a trie with 1,143,413 nodes and a maximum height of 16.
-    TestLargeUnsorted,
-}
-
-impl Dataset {
-    /// Get the path to a file with a set of words for testing.
+    /// Returns true if `word` was added to the trie as a whole word, as opposed to only being the start of longer
+    /// words.
     ///
     /// # Examples
     ///
-    /// Get the path to a file that has 10,000 words.
-    ///
     /// ```rust
     /// use letter_trie::*;
     ///
-    /// let filename: &str = Dataset::TestMediumSorted.filename();
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossing"]);
+    /// assert!(trie.contains("Cross"));
+    /// assert!(!trie.contains("cros"));
     /// ```
-    pub fn filename(&self) -> &str {
-        match self {
-            Dataset::TestSmallSorted => FILENAME_SMALL_SORTED,
-            Dataset::TestSmallUnsorted => FILENAME_SMALL_UNSORTED,
-            Dataset::TestMediumSorted => FILENAME_MEDIUM_SORTED,
-            Dataset::TestMediumUnsorted => FILENAME_MEDIUM_UNSORTED,
-            Dataset::TestLargeSorted => FILENAME_LARGE_SORTED,
-            Dataset::TestLargeUnsorted => FILENAME_LARGE_UNSORTED,
-        }
+    fn contains(&self, word: &str) -> bool {
+        trace::traced(
+            || trace::TraceOp::Contains(word.to_owned()),
+            || self.lookup(word).is_word(),
+        )
     }
 
-    /// Returns true if the dataset is supposed to be already in alphabetical order at least by the first character
-    /// of each word.
+    /// Returns true if `prefix` is a word or the start of a word in the trie. The empty string is the start of
+    /// every word, so it's a prefix of any trie.
     ///
     /// # Examples
     ///
-    /// Get the path to a file that has 10,000 words.
-    ///
     /// ```rust
     /// use letter_trie::*;
     ///
-    /// let is_sorted: bool = Dataset::TestLargeUnsorted.is_sorted();
-    /// assert_eq!(false, is_sorted);
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossing"]);
+    /// assert!(trie.contains_prefix("CROS"));
+    /// assert!(trie.contains_prefix("crossing"));
+    /// assert!(!trie.contains_prefix("crossings"));
     /// ```
-    pub fn is_sorted(&self) -> bool {
-        match self {
-            Dataset::TestSmallSorted | Dataset::TestMediumSorted | Dataset::TestLargeSorted => true,
-            Dataset::TestSmallUnsorted
-            | Dataset::TestMediumUnsorted
-            | Dataset::TestLargeUnsorted => false,
-        }
+    fn contains_prefix(&self, prefix: &str) -> bool {
+        trace::traced(
+            || trace::TraceOp::ContainsPrefix(prefix.to_owned()),
+            || self.lookup(prefix) != Lookup::NotFound,
+        )
     }
 
-    /// Get the number of words in a dataset. This is used in assertions to confirm that the various methods of
-    /// loading the words from the file really did get all of the words and properly ignored blank lines.
-    pub fn word_count(&self) -> usize {
-        match self {
-            Dataset::TestSmallSorted | Dataset::TestSmallUnsorted => WORD_COUNT_SMALL,
-            Dataset::TestMediumSorted | Dataset::TestMediumUnsorted => WORD_COUNT_MEDIUM,
-            Dataset::TestLargeSorted | Dataset::TestLargeUnsorted => WORD_COUNT_LARGE,
-        }
+    /// Get all of the words that start with `prefix` in alphabetical order, including `prefix` itself if it's a
+    /// word. A word with a spelling from `insert_preserving_case()` is given in that spelling, in the place of the
+    /// word as it's stored.
+    fn words_with_prefix(&self, prefix: &str) -> Vec<String> {
+        trace::traced(
+            || trace::TraceOp::WordsWithPrefix(prefix.to_owned()),
+            || {
+                let mut words = vec![];
+                self.visit(prefix, &mut |node| {
+                    if node.is_word {
+                        words.push(node.word().to_owned());
+                    }
+                    true
+                });
+                words
+            },
+        )
     }
-}
-
-/// The choice of implementation of LetterTrie.
-#[derive(Debug)]
-pub enum LetterTrieType {
-    /// The baseline implementation using Rc<RefCell<Node>> for child links and Weak<RefCell<Node>> for parent links.
-    Base,
-    /// A stripped-down implementation with no parent links and with direct ownership of child nodes.
-    NoParent,
-}
-
-/// The method the LetterTrie will use to load words from a text file.
-#[derive(Debug, PartialEq)]
-pub enum LoadMethod {
-    /// Read the whole file into memory, create a vector of words, then fill the trie.
-    ReadVecFill,
-    /// Read the file into a vector in one step, then fill the trie.
-    VecFill,
-    /// Build the trie while reading lines from the file.
-    Continuous,
-    /// Build the trie by evaluating the set of words for each starting letter in its own thread.
-    ///
-    /// Read lines from the file, and as soon as all of the words for each starting letter have been read spawn affect
-    /// thread to build a trie for that starting letter while continuing to read from the file in the first thread.
-    /// As each thread finishes building its trie, merge that trie into the main trie.
-    ContinuousParallel,
-}
-
-/// Options for the amount of detail to display while building a trie.
-pub struct DisplayDetailOptions {
-    /// If true, print the elapsed time for the whole trie build including reading the file.
-    pub print_overall_time: bool,
-    /// If true, print the elapsed time for each step. The particular steps depend on the chosen LoadMethod.
-    pub print_step_time: bool,
-    /// The amount of debugging information to print about the trie after it's been built:
-    /// - 0: Print nothing
-    /// - 1: Print a single line for the trie, the equivalent of `println!("{:?}", trie.to_fixed_node());`.
-    /// - 2: Print a multiple lines for the trie, the equivalent of `println!("{:#?}", trie.to_fixed_node());`.
-    pub object_detail_level: usize,
-    /// The label to be displayed with any debugging information. One easy way to create this string is with a
-    /// call to `DisplayDetailOptions::get_test_label()`.
-    pub label: String,
-}
 
-impl DisplayDetailOptions {
-    /// Create a set of options that display nothing while building the trie.
+    /// Get up to `before` words just before `probe` and up to `after` just after it in sorted order, whether or
+    /// not `probe` is a word, as for showing the part of a dictionary around what's been typed. The probe itself
+    /// is in neither list, and `Neighbors::is_word` says whether it's a word. Near either end of the trie a list
+    /// has fewer words than asked for. Each list is found in one walk as described in the `neighbors` module.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use letter_trie::*;
     ///
-    /// let display_opt: DisplayDetailOptions = DisplayDetailOptions::make_no_display();
+    /// let trie = NoParentLetterTrie::from_words(vec!["quiet", "quill", "quit", "quiz", "quote"]);
+    /// let around = trie.neighbors("Quix", 2, 2);
+    /// assert_eq!(vec!["quill", "quit"], around.before);
+    /// assert_eq!(vec!["quiz", "quote"], around.after);
+    /// assert!(!around.is_word);
+    ///
+    /// let around = trie.neighbors("quiet", 2, 1);
+    /// assert!(around.before.is_empty());
+    /// assert_eq!((true, vec!["quill".to_owned()]), (around.is_word, around.after));
     /// ```
-    pub fn make_no_display() -> Self {
-        Self {
-            print_overall_time: false,
-            print_step_time: false,
-            object_detail_level: 0,
-            label: "".to_owned(),
-        }
+    fn neighbors(&self, probe: &str, before: usize, after: usize) -> Neighbors {
+        neighbors::neighbors(self, probe, before, after)
     }
 
-    /// Create a set of options that display only the overall time to build the trie.
+    /// Get every word in the trie in alphabetical order.
+    ///
+    /// The words are collected when this is called, so the iterator doesn't borrow the trie. The trie can be
+    /// changed while the iterator is in use, and the iterator goes on giving the words as they were when it was
+    /// made, leaving out words added since and still giving words removed since. The same goes for
+    /// `words_with_tags()` and `words_with_sources()`. The iterators over nodes such as
+    /// `BaseLetterTrie::iter_breadth_first()` do borrow the trie, so it can't be changed while one is in use.
+    ///
     /// # Examples
     ///
     /// ```rust
     /// use letter_trie::*;
     ///
-    /// let dataset = Dataset::TestMediumUnsorted;
-    /// let load_method = LoadMethod::Continuous;
-    /// let letter_trie_type = LetterTrieType::NoParent;
-    ///
-    /// let display_opt: DisplayDetailOptions = DisplayDetailOptions::make_overall_time(
-    ///     &dataset,
-    ///     &load_method,
-    ///     &letter_trie_type);
+    /// let mut trie = BaseLetterTrie::from_words(vec!["cross", "an", "crossed", "an"]);
+    /// let words: Vec<String> = trie.words().collect();
+    /// assert_eq!(vec!["an", "cross", "crossed"], words);
     ///
-    /// let mut trie: BaseLetterTrie = BaseLetterTrie::from_file_test(
-    ///     &dataset.filename(),
-    ///     dataset.is_sorted(),
-    ///     &load_method,
-    ///     &display_opt,
-    ///     Some(dataset.word_count()));
+    /// let mut words = trie.words();
+    /// assert_eq!(Some("an".to_owned()), words.next());
+    /// trie.insert("crease");
+    /// trie.remove("crossed");
+    /// assert_eq!(vec!["cross", "crossed"], words.collect::<Vec<_>>());
     /// ```
-    pub fn make_overall_time(
-        dataset: &Dataset,
-        load_method: &LoadMethod,
-        letter_trie_type: &LetterTrieType,
-    ) -> Self {
-        Self {
-            print_overall_time: true,
-            print_step_time: false,
-            object_detail_level: 0,
-            label: Self::get_test_label(&dataset, &load_method, &letter_trie_type),
+    fn words(&self) -> Words {
+        Words {
+            inner: self.words_with_prefix("").into_iter(),
         }
     }
 
-    /// Create a set of options that display the overall time to build the trie as well as the time for each step.
+    /// Get the number of words that start with `prefix`, including `prefix` itself if it's a word. This is the
+    /// number of words in the trie if `prefix` is empty.
     ///
-    /// At the end, if the trie is small enough it will be displayed in its entirety, otherwise only the root node.
     /// # Examples
     ///
     /// ```rust
     /// use letter_trie::*;
     ///
-    /// let dataset = Dataset::TestLargeSorted;
-    /// let load_method = LoadMethod::ContinuousParallel;
-    /// let letter_trie_type = LetterTrieType::Base;
-    ///
-    /// let display_opt: DisplayDetailOptions = DisplayDetailOptions::make_overall_time(
-    ///     &dataset,
-    ///     &load_method,
-    ///     &letter_trie_type);
-    ///
-    /// let mut trie: BaseLetterTrie = BaseLetterTrie::from_file_test(
-    ///     &dataset.filename(),
-    ///     dataset.is_sorted(),
-    ///     &load_method,
-    ///     &display_opt,
-    ///     Some(dataset.word_count()));
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crease", "an"]);
+    /// assert_eq!(2, trie.count_with_prefix("Cross"));
+    /// assert_eq!(3, trie.count_with_prefix("cr"));
+    /// assert_eq!(4, trie.count_with_prefix(""));
+    /// assert_eq!(0, trie.count_with_prefix("q"));
     /// ```
-    pub fn make_moderate(
-        dataset: &Dataset,
-        load_method: &LoadMethod,
-        letter_trie_type: &LetterTrieType,
-    ) -> Self {
-        Self {
-            print_overall_time: true,
-            print_step_time: true,
-            object_detail_level: match dataset {
-                Dataset::TestSmallSorted | Dataset::TestSmallUnsorted => 2,
-                _ => 1,
+    fn count_with_prefix(&self, prefix: &str) -> usize {
+        trace::traced(
+            || trace::TraceOp::CountWithPrefix(prefix.to_owned()),
+            || {
+                let mut count = 0;
+                self.visit(prefix, &mut |node| {
+                    if node.is_word {
+                        count += 1;
+                    }
+                    true
+                });
+                count
             },
-            label: Self::get_test_label(&dataset, &load_method, &letter_trie_type),
-        }
+        )
+    }
+
+    /// Get up to `max_count` completions of `prefix` for something like an autocomplete box, shortest words first
+    /// and alphabetically among words of the same length.
+    ///
+    /// Only as much of the subtree is walked as is needed, since once `max_count` words have been found no deeper
+    /// node can hold a shorter word.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["crossing", "creature", "cross", "crate"]);
+    /// assert_eq!(vec!["crate", "cross", "creature"], trie.suggest("cr", 3));
+    /// ```
+    fn suggest(&self, prefix: &str, max_count: usize) -> Vec<String> {
+        trace::traced(
+            || trace::TraceOp::Suggest(prefix.to_owned(), max_count),
+            || {
+                // Kept sorted by (length, word) so the last entry is the one to drop when a better word turns up. The
+                // spelling to give for each word is on the end.
+                let mut best: Vec<(usize, String, String)> = vec![];
+                if max_count == 0 {
+                    return vec![];
+                }
+                self.visit(prefix, &mut |node| {
+                    let is_full = best.len() == max_count;
+                    if is_full && node.depth >= best[max_count - 1].0 {
+                        // The walk is in alphabetical order so a later word of the same length can't win a tie either.
+                        return false;
+                    }
+                    if node.is_word {
+                        let entry = (node.depth, node.prefix.to_owned(), node.word().to_owned());
+                        let index = best.binary_search(&entry).unwrap_or_else(|x| x);
+                        best.insert(index, entry);
+                        best.truncate(max_count);
+                    }
+                    true
+                });
+                best.into_iter().map(|(_, _, spelling)| spelling).collect()
+            },
+        )
+    }
+
+    /// Get up to `limit` words that `input` might be a misspelling of, best first, ranked with the default
+    /// `SuggestionConfig` as described in the `suggestions` module. A word that's in the trie is its own best
+    /// suggestion.
+    ///
+    /// Suggestions with the same score are in order of distance and then of the words, so the result only depends
+    /// on the words in the trie and their tags.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["the", "ate", "tee", "receive", "relieve"]);
+    /// let suggestions = trie.did_you_mean("hte", 2);
+    /// assert_eq!(vec!["the", "ate"], suggestions.iter().map(|s| s.word.as_str()).collect::<Vec<_>>());
+    /// assert_eq!(1, suggestions[0].distance);
+    /// assert_eq!("receive", trie.did_you_mean("Recieve", 1)[0].word);
+    /// ```
+    #[cfg(feature = "std")]
+    fn did_you_mean(&self, input: &str, limit: usize) -> Vec<Suggestion> {
+        trace::traced(
+            || trace::TraceOp::DidYouMean(input.to_owned(), limit),
+            || suggestions::did_you_mean(self, input, limit, &SuggestionConfig::default()),
+        )
+    }
+
+    /// Get a 64-bit hash of the shape and words of the trie that's the same for any two tries with the same words,
+    /// whatever the implementation or load method. It's meant for quickly checking that two tries agree, not for
+    /// security.
+    ///
+    /// The hash is FNV-1a over each node's character, word flag and number of children in `visit()` order, so it
+    /// won't change between runs or platforms.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie_1 = BaseLetterTrie::from_words(vec!["cross", "an", "crossed"]);
+    /// let trie_2 = NoParentLetterTrie::from_words(vec!["crossed", "cross", "an"]);
+    /// assert_eq!(trie_1.fingerprint(), trie_2.fingerprint());
+    ///
+    /// let trie_3 = NoParentLetterTrie::from_words(vec!["crossed", "an"]);
+    /// assert_ne!(trie_1.fingerprint(), trie_3.fingerprint());
+    /// ```
+    fn fingerprint(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        self.visit("", &mut |node| {
+            hash = fingerprint_node(hash, node.c, node.is_word, node.child_count);
+            true
+        });
+        hash
+    }
+
+    /// Check the trie against what's expected of it, such as words that have to be there and a range for the
+    /// number of words, and report each expectation it doesn't meet. This is meant as a quick check that a
+    /// dictionary loaded as expected, as after a deploy. See the `self_test` module, and `assert_self_test!` for
+    /// tests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// let spec = SelfTestSpec {
+    ///     present: vec!["cross".to_owned()],
+    ///     absent: vec!["crosses".to_owned()],
+    ///     fingerprint: Some(NoParentLetterTrie::from_words(vec!["crossed", "cross"]).fingerprint()),
+    ///     ..SelfTestSpec::default()
+    /// };
+    /// assert!(trie.self_test(&spec).passed());
+    /// ```
+    fn self_test(&self, spec: &SelfTestSpec) -> SelfTestReport {
+        self_test::self_test(self, spec)
+    }
+}
+
+/// A letter trie (https://www.geeksforgeeks.org/trie-insert-and-search) with implementations that use different
+/// approaches for parent and child links but otherwise work the same.
+///
+/// One use of such a trie is to play millions of rounds of a word game like Scrabble or Boggle. In either game there
+/// is a branching set of possible letter sequences given a set of seven tiles or sixteen dice. The trie allows one
+/// to work letter-by-letter through the possible sequences while stepping node-by-node through the trie at the same
+/// time. It may be possible to cut the process short without either running out of tiles/dice or reaching a leaf
+/// node in the trie. For instance, we may be tracking the best score reached so far and a given node could be
+/// annotated with the highest score that could possibly be reached in its subtree. If this highest possible score
+/// is less than or equal to the best score we've already found, there's no point in going into that subtree or
+/// (which is saying the same thing) in trying to add another tile or die to the word we've been forming.
+///
+/// A trie of even half a million words in a given language (with probably fewer than 1.5 million nodes) is still going
+/// to be orders of magnitude smaller than the possible sequences of letters found in one throw of the Boggle dice.
+/// This means that even if we don't cut the search short because of the best possible score in a subtree, we're
+/// still in most cases going to run out of trie before we run out of sequences of dice.
+///
+/// # Ordering
+///
+/// The children of every node are kept in character order, by Unicode scalar value, in every implementation and
+/// whatever the load method or the order the words were added in. Everything that walks the trie follows that
+/// order, so `visit()`, `children()`, `words()`, `words_with_prefix()`, `words_with_tags()`, `fingerprint()`, the
+/// binary format and the `Debug` output are the same for the same set of words however the trie was built.
+/// `verify_integrity()` checks this.
+///
+/// "Alphabetical order" everywhere in this crate means exactly this order: words are compared one `char` at a
+/// time by scalar value, as stored (so lowercased), and a word comes before the longer words it starts. It's the
+/// order of `Ord` for `str` and `[char]`, and it doesn't depend on the locale. Every letter outside ASCII sorts
+/// after "z", so "strasse" comes before "straße", and "zebra" before "éclair", which comes before "ñu". The
+/// words from `words()`, `words_with_prefix()` and the other word lists, ties between words of the same length in
+/// `suggest()`, the words in the binary and front-coded formats, and the `SourcedWord` lists that the `serde`
+/// feature serializes are all in this order.
+///
+/// # Characters
+///
+/// Each node holds one `char`, so a letter is a Unicode scalar value rather than a byte or what a reader would see
+/// as one character. A CJK character or an emoji past the Basic Multilingual Plane such as "🦀" is one node, while
+/// a flag, an emoji with a skin tone, or a family of emoji joined with zero-width joiners is several. Words aren't
+/// normalized, so "café" with "é" and "café" written as "e" and a combining accent are two words, and the accent is
+/// a node of its own. Words and queries are lowercased with `str::to_lowercase()`, which can lengthen a word, as "İ"
+/// becomes "i" and a combining dot, and which gives a final sigma at the end of a Greek word.
+///
+/// Anything measured in letters counts `char`s: `FixedNode::depth`, `FixedNode::height`, `TrieCursor::depth()`,
+/// the lengths that `suggest()` orders by, and the lengths in `completions_of_exact_length()`,
+/// `has_completion_of_length()` and `split_points_with_min_len()`. Anything that points into a text, such as `split_points()` and the matches from
+/// `TrieMatcher::scan()`, gives byte offsets on `char` boundaries, so the text can be sliced with them.
+///
+/// # Trait objects
+///
+/// The methods that build a trie or take a generic parameter, such as `from_file()`, `from_words()`,
+/// `find_chars()`, `subtrie()` and `diff()`, require `Self: Sized`, which keeps the trait object-safe. An
+/// implementation chosen at run time can be held as a `Box<dyn LetterTrie>`, most easily from `build_trie()`, and
+/// everything else is called on it as on a concrete trie. The generic methods still take a trait object as the
+/// other trie, as in `trie.diff(&*boxed)`.
+///
+/// # Queries
+///
+/// The queries that a read-only `TrieView` also has, such as `find()`, `contains()`, `words_with_prefix()` and
+/// `cursor()`, are in the supertrait `TrieQuery`, which has to be in scope along with this trait to call them.
+///
+/// The queries for the optional features and the tables kept beside the nodes are in extension traits with blanket
+/// implementations, so an implementation only gives the methods here: `LimitedQuery` for the `_limited` queries,
+/// `ScoredQuery` for completions by score or rank, `TaggedLetterTrie` for tags and sources, and `RegexQuery` with
+/// the `regex` feature. These are in the prelude too.
+pub trait LetterTrie: TrieQuery {
+    /// Create a trie from words in a text file.
+    ///
+    /// The text file may contain up to one word per line. The words may be upper- or lowercase and
+    /// blank lines and whitespace before or after the words will be ignored. Duplicate words will also be
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the file's path if the file doesn't exist or can't be opened or read,
+    /// including when a line is not valid UTF-8.
+    ///
+    /// This will produce an incorrect trie if the file contains lines with more than one word.
+    ///
+    /// This may crash or produce an incorrect trie if all three of these conditions are met:
+    /// - The words in the file are not sorted at least by their first letter (subsequent letters don't matter).
+    /// - `is_sorted` is incorrectly set to `true`.
+    /// - The load method uses an optimization that relies on the words being sorted by their first letter. Currently the only such load method is `LoadMethod::ContinuousParallel`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let result = NoParentLetterTrie::from_file("no_such_words.txt", false, &LoadMethod::Continuous);
+    /// assert!(matches!(result, Err(LetterTrieError::File { .. })));
+    /// ```
+    #[cfg(feature = "fs")]
+    fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        trace::traced(
+            || trace::TraceOp::FromFile {
+                filename: filename.to_owned(),
+                is_sorted,
+                load_method: load_method.to_string(),
+            },
+            || {
+                let opt = DisplayDetailOptions::make_no_display();
+                Self::from_file_test(filename, is_sorted, load_method, &opt, None)
+            },
+        )
+    }
+
+    /// Create a trie from words in a text file, optionally displaying elapsed time for each step.
+    ///
+    /// The text file may contain up to one word per line. The words may be upper- or lowercase and
+    /// blank lines and whitespace before or after the words will be ignored. Duplicate words will also be
+    /// ignored unless `DisplayDetailOptions::duplicate_policy` says otherwise.
+    ///
+    /// # Errors
+    ///
+    /// As for `from_file()`, and as for `from_reader_test()` with the file's path added where there's a place for
+    /// it.
+    ///
+    /// This will produce an incorrect trie if the file contains lines with more than one word.
+    ///
+    /// This may crash or produce an incorrect trie if all three of these conditions are met:
+    /// - The words in the file are not sorted at least by their first letter (subsequent letters don't matter).
+    /// - `is_sorted` is incorrectly set to `true`.
+    /// - The load method uses an optimization that relies on the words being sorted by their first letter. Currently the only such load method is `LoadMethod::ContinuousParallel`.
+    #[cfg(feature = "fs")]
+    fn from_file_test(
+        filename: &str,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let path = Path::new(filename);
+        let file = File::open(path).map_err(in_file(path))?;
+        opt.with_file_hints(&file, || {
+            Self::from_reader_test(
+                BufReader::new(&file),
+                is_sorted,
+                load_method,
+                opt,
+                expected_word_count,
+            )
+        })
+        .map_err(in_file(path))
+    }
+
+    /// Create a trie from one of the word files in `Dataset`, with the file found by `Dataset::path()` and with
+    /// `is_sorted` taken from `Dataset::is_sorted()` so that the two can't be mismatched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::MissingDataset` if the dataset's file isn't there, and `LetterTrieError::File`
+    /// with the dataset's path if `Dataset::materialize()` can't write the file or it can't be opened or read for
+    /// any other reason. `Dataset::EmbeddedSmall` is read from memory so it has neither.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap();
+    /// assert_small_root(&trie.to_fixed_node());
+    /// ```
+    #[cfg(feature = "fs")]
+    fn from_dataset(dataset: &Dataset, load_method: &LoadMethod) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        trace::traced(
+            || trace::TraceOp::FromDataset {
+                dataset: dataset.clone(),
+                load_method: load_method.to_string(),
+            },
+            || {
+                let opt = DisplayDetailOptions::make_no_display();
+                Self::from_dataset_with_options(dataset, load_method, &opt)
+            },
+        )
+    }
+
+    /// Create a trie from one of the word files in `Dataset` as `from_dataset()` does, optionally displaying
+    /// elapsed time for each step.
+    ///
+    /// # Errors
+    ///
+    /// As for `from_dataset()`, and `LetterTrieError::DuplicateWord` if a word is repeated when
+    /// `DisplayDetailOptions::duplicate_policy` is `DuplicatePolicy::Error`.
+    #[cfg(feature = "fs")]
+    fn from_dataset_with_options(
+        dataset: &Dataset,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if let Some(text) = dataset.embedded_text() {
+            return Self::from_reader_test(
+                text.as_bytes(),
+                dataset.is_sorted(),
+                load_method,
+                opt,
+                None,
+            );
+        }
+        let path = dataset.path();
+        dataset.materialize().map_err(in_file(&path))?;
+        let file = File::open(&path).map_err(in_dataset_file(&path))?;
+        opt.with_file_hints(&file, || {
+            Self::from_reader_test(
+                BufReader::new(&file),
+                dataset.is_sorted(),
+                load_method,
+                opt,
+                None,
+            )
+        })
+        .map_err(in_file(&path))
+    }
+
+    /// Create a trie from words read from any buffered source such as an open file, a network stream, or a
+    /// string in memory.
+    ///
+    /// The same rules apply as for `from_file()`: up to one word per line, case and surrounding whitespace are
+    /// ignored, and duplicate words are ignored. This constructor doesn't need a filesystem so it's the one to
+    /// use when building for WebAssembly.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if reading from the source fails, including when a line is not valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let source = "cross\ncrossing\nan\n";
+    /// let trie = NoParentLetterTrie::from_reader(source.as_bytes(), true, &LoadMethod::Continuous).unwrap();
+    /// assert!(trie.contains("crossing"));
+    /// ```
+    #[cfg(feature = "std")]
+    fn from_reader<R: BufRead>(reader: R, is_sorted: bool, load_method: &LoadMethod) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let opt = DisplayDetailOptions::make_no_display();
+        Self::from_reader_test(reader, is_sorted, load_method, &opt, None)
+    }
+
+    /// Create a trie from words read from any buffered source, optionally displaying elapsed time for each step.
+    ///
+    /// See `from_reader()` and `from_file_test()`.
+    ///
+    /// # Errors
+    ///
+    /// As for `from_reader()`, and `LetterTrieError::DuplicateWord` if a word is repeated when
+    /// `DisplayDetailOptions::duplicate_policy` is `DuplicatePolicy::Error`.
+    #[cfg(feature = "std")]
+    fn from_reader_test<R: BufRead>(
+        reader: R,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// Create a trie from words read from any buffered source in the given order. `SourceOrder::Alphabetical` and
+    /// `SourceOrder::Unsorted` load as `from_reader()` does, while `SourceOrder::FrequencyRanked` inserts the words
+    /// as they're read without sorting them, whatever `load_method` is, and can give each word its rank for
+    /// `rank_of()`. See the `source_order` module.
+    ///
+    /// # Errors
+    ///
+    /// As for `from_reader()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let source = "the\nof\nto\ntoward\ntone\n";
+    /// let order = SourceOrder::FrequencyRanked { record_ranks: true };
+    /// let trie = NoParentLetterTrie::from_reader_in_order(source.as_bytes(), &order, &LoadMethod::Continuous)?;
+    /// assert_eq!(Some(3), trie.rank_of("to"));
+    /// assert_eq!(vec!["to", "tone", "toward"], trie.suggest("to", 3));
+    /// assert_eq!(
+    ///     vec![("to".to_owned(), 3), ("toward".to_owned(), 4), ("tone".to_owned(), 5)],
+    ///     trie.suggest_ranked("to", 3)
+    /// );
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    #[cfg(feature = "std")]
+    fn from_reader_in_order<R: BufRead>(
+        reader: R,
+        order: &SourceOrder,
+        load_method: &LoadMethod,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let opt = DisplayDetailOptions::make_no_display();
+        Self::from_reader_in_order_test(reader, order, load_method, &opt)
+    }
+
+    /// Create a trie from words read from any buffered source in the given order, optionally displaying elapsed
+    /// time for each step. A `SourceOrder::FrequencyRanked` load has the one step "insert in source order" before
+    /// "overall load".
+    ///
+    /// See `from_reader_in_order()` and `from_reader_test()`.
+    ///
+    /// # Errors
+    ///
+    /// As for `from_reader_test()`.
+    #[cfg(feature = "std")]
+    fn from_reader_in_order_test<R: BufRead>(
+        reader: R,
+        order: &SourceOrder,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        source_order::from_reader_in_order(reader, order, load_method, opt)
+    }
+
+    /// Create a trie from lines that hold more than a word, such as a TSV file with a part of speech and a
+    /// frequency after each word. `parser` is given each line that isn't blank, without its line ending, and
+    /// returns the word in it along with a tag or a count if there are any, or `None` to leave the line out.
+    ///
+    /// The words are loaded as with `from_reader()` using `load_method`, with the parsing done as the lines are
+    /// read. A tag is then given to its word as with `insert_with_tag()` and a count is given as its score for
+    /// `suggest_scored()`, so for a word on more than one line the last tag and count win. The report counts the
+    /// words in `words_added` and the lines the parser turned down in `skipped_unparsed`. See the `line_parser`
+    /// module for parsers of the common layouts.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if reading from the source fails, including when a line is not valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let text = "word\tfrequency\ncross\t40\ncrease\t12\ncrossed\n";
+    /// let (trie, report) = NoParentLetterTrie::from_lines_parsed(
+    ///     text.as_bytes(),
+    ///     |line| {
+    ///         let mut columns = line.split('\t');
+    ///         let word = columns.next()?.to_owned();
+    ///         let count = columns.next().map(str::parse).transpose().ok()?;
+    ///         Some(ParsedWord { word, count, ..ParsedWord::default() })
+    ///     },
+    ///     &LoadMethod::ContinuousParallel,
+    /// )?;
+    /// assert_eq!(3, report.words_added);
+    /// assert_eq!(1, report.skipped_unparsed);
+    /// assert_eq!(vec![("cross".to_owned(), 40.0), ("crease".to_owned(), 12.0)], trie.suggest_scored("cr", 2));
+    /// assert!(trie.contains("crossed"));
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    #[cfg(feature = "std")]
+    fn from_lines_parsed<R: BufRead, F: FnMut(&str) -> Option<ParsedWord>>(
+        reader: R,
+        parser: F,
+        load_method: &LoadMethod,
+    ) -> Result<(Self, LoadReport)>
+    where
+        Self: Sized,
+    {
+        line_parser::from_lines_parsed(reader, parser, load_method)
+    }
+
+    /// Create a trie from words that are already in memory.
+    ///
+    /// Each item is treated like a line of a word file so case and surrounding whitespace are ignored, and empty
+    /// items and duplicates are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "Crossing", " an "]);
+    /// assert_eq!(3, trie.to_fixed_node().word_count);
+    /// ```
+    fn from_words<I, S>(words: I) -> Self
+    where
+        Self: Sized,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>;
+
+    /// Build a trie from words that are already grouped by their first letter, as when they come from one file
+    /// for each letter. Each group is built into a subtrie of its own and the subtries are put together under the
+    /// root, so none of the partitioning that `LoadMethod::ContinuousParallel` does for a whole file is repeated.
+    /// If `parallel` is true each group is built on a thread of its own, as in that load method, unless the
+    /// `parallel` feature is off.
+    ///
+    /// Letters and words are compared in lowercase, and as in `from_words()` the whitespace around a word is
+    /// ignored, as are blank words. Two groups for the same letter are put together.
+    ///
+    /// # Errors
+    ///
+    /// `LetterTrieError::MisfiledWord` for the first word, in the order the groups were given, that doesn't start
+    /// with the letter of its group. Nothing is built in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let groups = vec![
+    ///     ('c', vec!["cross".to_owned(), "Crossed".to_owned()]),
+    ///     ('a', vec!["an".to_owned(), "and".to_owned()]),
+    /// ];
+    /// let trie = BaseLetterTrie::from_letter_groups(groups, true)?;
+    /// assert_eq!(vec!["an", "and", "cross", "crossed"], trie.words().collect::<Vec<_>>());
+    ///
+    /// let groups = vec![('a', vec!["an".to_owned(), "cross".to_owned()])];
+    /// match NoParentLetterTrie::from_letter_groups(groups, false) {
+    ///     Err(LetterTrieError::MisfiledWord { letter: 'a', word }) => assert_eq!("cross", word),
+    ///     _ => unreachable!(),
+    /// }
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    fn from_letter_groups<I>(groups: I, parallel: bool) -> Result<Self>
+    where
+        Self: Sized,
+        I: IntoIterator<Item = (char, Vec<String>)>,
+    {
+        // Building the groups one after another into the same trie gives the same trie.
+        let _ = parallel;
+        let groups = check_letter_groups(groups)?;
+        Ok(Self::from_words(
+            groups.into_iter().flat_map(|(_, words)| words),
+        ))
+    }
+
+    /// Add one word to an existing trie, with the same rules as `from_words()`. Adding a word that's already
+    /// present changes nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["cross"]);
+    /// trie.insert("Crossed");
+    /// assert!(trie.contains("crossed"));
+    /// ```
+    fn insert(&mut self, word: &str);
+
+    /// Remove one word, with the same rules for case and surrounding whitespace as `insert()`, along with any
+    /// nodes that were only there for that word. Returns true if the word was in the trie.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// assert!(trie.remove("Crossed"));
+    /// assert!(!trie.remove("crossed"));
+    /// assert!(trie.contains("cross"));
+    /// assert_eq!(6, trie.to_fixed_node().node_count);
+    /// ```
+    fn remove(&mut self, word: &str) -> bool;
+
+    /// Remove every word in a word file, such as a list of banned words, along with any nodes that were only
+    /// there for those words. The file is read with the same rules for case, surrounding whitespace and blank lines
+    /// as `from_file()`, and the words are sorted and passed to `remove_sorted_words()` so that words with a
+    /// prefix in common share the walk down to it. Removing the same file again removes nothing more.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the path if the file can't be opened or read, in which case nothing has
+    /// been removed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let path = std::env::temp_dir().join(format!("letter_trie_banned_{}.txt", std::process::id()));
+    /// std::fs::write(&path, "Crossed\n\n  crosses\ncross\n").unwrap();
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crossing"]);
+    /// let report = trie.remove_words_from_file(&path)?;
+    /// assert_eq!((2, 1, 2), (report.removed, report.not_found, report.nodes_pruned));
+    /// assert_eq!(vec!["crossing"], trie.words().collect::<Vec<_>>());
+    /// assert_eq!(0, trie.remove_words_from_file(&path)?.removed);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    #[cfg(feature = "fs")]
+    fn remove_words_from_file(&mut self, path: impl AsRef<Path>) -> Result<RemovalReport>
+    where
+        Self: Sized,
+    {
+        let words = removal::read_sorted_words(path.as_ref())?;
+        Ok(self.remove_sorted_words(&words))
+    }
+
+    /// Remove each of `words`, which must already be lowercase, sorted and without duplicates, in one walk that
+    /// only goes back up as far as each word's prefix differs from the one before. A word that isn't in the trie,
+    /// including one that's only the start of other words, is counted in `RemovalReport::not_found`. The tags,
+    /// sources, spellings and scores of the words that are removed go with them, as in `remove()`.
+    fn remove_sorted_words(&mut self, words: &[Vec<char>]) -> RemovalReport;
+
+    /// Add a word as `insert()` does and attach `tag` to it, replacing any tag it already had. A tag is whatever
+    /// `u64` the caller wants to keep with the word, such as the offset of its definition in another file.
+    ///
+    /// Tags are kept in a table beside the nodes rather than in them, so a trie that never has a tag doesn't pay
+    /// for one in every node. Adding the word again with `insert()` keeps its tag and removing it drops the tag.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["cross"]);
+    /// trie.insert_with_tag("Crossed", 1_024);
+    /// assert_eq!(Some(1_024), trie.tag("crossed"));
+    /// assert_eq!(None, trie.tag("cross"));
+    /// assert_eq!(None, trie.tag("cro"));
+    /// ```
+    fn insert_with_tag(&mut self, word: &str, tag: u64);
+
+    /// Add a word as `insert()` does and record that it came from the source with the number `source_id`, such
+    /// as one of several word lists being merged. A word can have any number of sources.
+    ///
+    /// Like tags, sources are kept in a table beside the nodes that stays empty until a source is recorded, so a
+    /// trie that never uses them takes no more memory than before. Removing the word drops its sources.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["cross"]);
+    /// trie.insert_with_source("Crossed", 3);
+    /// trie.insert_with_source("crossed", 1);
+    /// trie.insert_with_source("crossed", 3);
+    /// assert_eq!(Some(&[1, 3][..]), trie.sources("crossed"));
+    /// assert_eq!(None, trie.sources("cross"));
+    /// ```
+    fn insert_with_source(&mut self, word: &str, source_id: u16);
+
+    /// Add a word as `insert()` does and keep its spelling as it was given, without the surrounding whitespace.
+    /// The word is still stored and looked up in lower case, so "MacDonald" is found by `contains("macdonald")`,
+    /// but `words()`, `words_with_prefix()` and `suggest()` give "MacDonald". If the word already has a spelling,
+    /// `preference` decides which one it keeps.
+    ///
+    /// Spellings are kept in a table beside the nodes like tags, so a trie that never keeps one takes no more
+    /// memory than before. Adding the word again with `insert()` keeps its spelling and removing it drops the
+    /// spelling.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["mac"]);
+    /// trie.insert_preserving_case("MacDonald", CasePreference::FirstSeen);
+    /// trie.insert_preserving_case("MACDONALD", CasePreference::FirstSeen);
+    /// assert!(trie.contains("macdonald"));
+    /// assert_eq!(vec!["mac", "MacDonald"], trie.words_with_prefix("MAC"));
+    /// assert_eq!(Some("MacDonald"), trie.spelling("macDONALD"));
+    /// assert_eq!(None, trie.spelling("mac"));
+    /// ```
+    fn insert_preserving_case(&mut self, word: &str, preference: CasePreference) {
+        self.insert_with_spelling(word, word, preference);
+    }
+
+    /// Add a word as `insert()` does and keep `spelling` as the form it's shown in, as `insert_preserving_case()`
+    /// does with the word itself. This is for a word that's been changed in more ways than case before it's
+    /// stored, such as having its diacritics folded. A spelling that's blank after it's trimmed is ignored.
+    fn insert_with_spelling(&mut self, word: &str, spelling: &str, preference: CasePreference);
+
+    /// Give each word in `scores` its score for `suggest_scored()`, replacing any score it had. The words are
+    /// matched as in `contains()`, and a word that isn't in the trie is skipped, as is a score that's NaN or
+    /// negative infinity. A word keeps its score until it's removed, and a word added later has none until it's
+    /// given one.
+    ///
+    /// Like tags, scores are kept in a table beside the nodes, but each node also keeps the highest score below
+    /// it, which is worked out again along a word's path whenever its score changes or it's removed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crate"]);
+    /// let scores: HashMap<String, f64> =
+    ///     vec![("Cross".to_owned(), 0.25), ("crate".to_owned(), 0.5), ("cr".to_owned(), 1.0)]
+    ///         .into_iter()
+    ///         .collect();
+    /// trie.set_scores(&scores);
+    /// assert_eq!(Some(0.25), trie.score("cross"));
+    /// assert_eq!(None, trie.score("crossed"));
+    /// assert_eq!(None, trie.score("cr"));
+    /// ```
+    #[cfg(feature = "std")]
+    fn set_scores(&mut self, scores: &HashMap<String, f64>) {
+        for (word, score) in scores {
+            self.set_score(word, *score);
+        }
+    }
+
+    /// Give one word its score for `suggest_scored()` as `set_scores()` does, returning false if the word isn't in
+    /// the trie or the score was skipped.
+    fn set_score(&mut self, word: &str, score: f64) -> bool;
+
+    /// Give `word` its rank in a list of words ordered from most to least common, as a
+    /// `SourceOrder::FrequencyRanked` load does, returning false if the word isn't in the trie. The rank goes when
+    /// the word is removed.
+    fn set_rank(&mut self, word: &str, rank: u32) -> bool;
+
+    /// Keep the nodes `find()` returns for up to `max_entries` of the most recently found prefixes of three
+    /// letters or less, so that a query load dominated by a few hot prefixes doesn't keep making the same
+    /// `FixedNode`. Any change to the words empties the cache. Turning the cache on again starts it over with new
+    /// counts, and a `max_entries` of 0 turns it off. See the `prefix_cache` module.
+    #[cfg(feature = "std")]
+    fn enable_prefix_cache(&mut self, max_entries: usize);
+
+    /// Get the hits and misses of the cache from `enable_prefix_cache()`, which are all 0 if it's off.
+    #[cfg(feature = "std")]
+    fn cache_stats(&self) -> CacheStats;
+
+    /// Find the node for a word or partial word that's already split into characters, such as a `Vec<char>` or a
+    /// `Chars` iterator, without building a string first. As with `find()` there's no node for no characters.
+    ///
+    /// Each character is lowercased on its own. That only differs from `str::to_lowercase()`, which is how the
+    /// words are stored, for a capital sigma at the end of a word, so pass the characters of a lowercased string
+    /// if that matters. In `BaseLetterTrie` and `NoParentLetterTrie` the walk down the trie doesn't allocate;
+    /// building the `FixedNode` for a node that's found does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossing"]);
+    /// let tokens: Vec<char> = "CROSS".chars().collect();
+    /// assert_eq!(trie.find("cross"), trie.find_chars(tokens.iter().copied()));
+    /// assert_eq!(None, trie.find_chars("crosses".chars()));
+    /// ```
+    /// Find the node for `prefix` as `find()` does, along with what the search for it did: the nodes it reached,
+    /// the letters it compared, the depth where it ended and whether that was a word, a prefix or a miss. The
+    /// counts are kept for this call alone, so `find()` itself counts nothing. See the `metrics` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// let (node, metrics) = trie.find_with_metrics("crosx");
+    /// assert_eq!(None, node);
+    /// assert_eq!(FindMetrics { nodes_visited: 5, comparisons: 5, terminal_depth: 4, outcome: FindOutcome::NotFound }, metrics);
+    /// ```
+    fn find_with_metrics(&self, prefix: &str) -> (Option<FixedNode>, FindMetrics);
+
+    fn find_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> Option<FixedNode>
+    where
+        Self: Sized,
+    {
+        let prefix: String = chars.into_iter().flat_map(char::to_lowercase).collect();
+        self.find(&prefix)
+    }
+
+    /// Find the node for `prefix` as in `find()`, along with the first `sample_count` words under it in
+    /// alphabetical order and the letters of its children, so that a surprising `word_count` can be explained
+    /// without another lookup. The empty string gives the root node, as in `lookup()`. Only as much of the subtree
+    /// is walked as is needed to find the samples.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crease", "crossed", "creature", "an"]);
+    /// let result = trie.find_with_samples("CR", 2).unwrap();
+    /// assert_eq!(4, result.node.word_count);
+    /// assert_eq!(vec!["crease", "creature"], result.samples);
+    /// assert_eq!(vec!['e', 'o'], result.child_letters);
+    /// assert_eq!(None, trie.find_with_samples("crosses", 2));
+    /// ```
+    fn find_with_samples(&self, prefix: &str, sample_count: usize) -> Option<FindResult> {
+        let node = if prefix.is_empty() {
+            self.to_fixed_node()
+        } else {
+            self.find(prefix)?
+        };
+        let mut samples: Vec<String> = vec![];
+        if sample_count > 0 {
+            self.visit(prefix, &mut |node| {
+                if samples.len() == sample_count {
+                    return false;
+                }
+                if node.is_word {
+                    samples.push(node.prefix.to_owned());
+                }
+                true
+            });
+        }
+        Some(FindResult {
+            node,
+            samples,
+            child_letters: self.children(prefix),
+        })
+    }
+
+    /// Returns true if the characters are a word in the trie, lowercasing them as in `find_chars()`. This doesn't
+    /// allocate in `BaseLetterTrie` or `NoParentLetterTrie`, and neither does `contains()` for an ASCII word.
+    /// Check whether `word` is in the trie as `contains()` does, along with what the check did, counted as in
+    /// `find_with_metrics()`. An ASCII word longer than any stored word is turned away at the root.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// let (is_word, metrics) = trie.contains_with_metrics("cros");
+    /// assert!(!is_word);
+    /// assert_eq!(FindOutcome::PrefixOnly, metrics.outcome);
+    /// assert_eq!(5, metrics.nodes_visited);
+    /// ```
+    fn contains_with_metrics(&self, word: &str) -> (bool, FindMetrics);
+
+    fn contains_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> bool
+    where
+        Self: Sized,
+    {
+        let word: String = chars.into_iter().flat_map(char::to_lowercase).collect();
+        self.contains(&word)
+    }
+
+    /// Split a list of tokens such as those of a document into the ones that are words in the trie and the ones
+    /// that aren't. Each token is trimmed and lowercased the way a word is when it's added, so "The" and " the"
+    /// always land on the same side as "the", and a blank token is unknown. A token that's given more than once
+    /// is looked up only the first time but is listed each time, so both lists keep the order of `words`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["the", "cross", "an"]);
+    /// let tokens: Vec<String> = ["The", "crosss", "an", "the", " "].iter().map(|s| s.to_string()).collect();
+    /// let classification = trie.classify(&tokens);
+    /// assert_eq!(vec!["The", "an", "the"], classification.known);
+    /// assert_eq!(vec!["crosss", " "], classification.unknown);
+    /// ```
+    fn classify<'a>(&self, words: &'a [String]) -> Classification<'a> {
+        let mut seen: BTreeMap<&str, bool> = BTreeMap::new();
+        let mut classification = Classification::default();
+        for word in words {
+            let is_known = *seen.entry(word.as_str()).or_insert_with(|| {
+                let trimmed = word.trim();
+                !trimmed.is_empty() && self.contains(trimmed)
+            });
+            if is_known {
+                classification.known.push(word);
+            } else {
+                classification.unknown.push(word);
+            }
+        }
+        classification
+    }
+
+    /// Returns true if the characters are a word or the start of a word in the trie, as in `contains_chars()`.
+    fn contains_prefix_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> bool
+    where
+        Self: Sized,
+    {
+        let prefix: String = chars.into_iter().flat_map(char::to_lowercase).collect();
+        self.contains_prefix(&prefix)
+    }
+
+    /// Get the longest prefix shared by every word in the trie, lowercased, or an empty string if the trie is
+    /// empty or its words don't all start with the same letter. See `longest_common_prefix_under()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["Crossing", "crossed", "crossbow"]);
+    /// assert_eq!("cross", trie.longest_common_prefix());
+    /// assert_eq!("", BaseLetterTrie::from_words(vec!["cross", "an"]).longest_common_prefix());
+    /// ```
+    fn longest_common_prefix(&self) -> String {
+        self.longest_common_prefix_under("").unwrap_or_default()
+    }
+
+    /// Get the longest string, starting with `prefix`, that every word starting with `prefix` also starts with,
+    /// lowercased. This is how far a completion box can fill in what's been typed without having to choose. It
+    /// returns None if no word starts with `prefix`.
+    ///
+    /// The string is extended from `prefix` one letter at a time for as long as there's only one letter to take,
+    /// and it stops at a word, since the word itself is one of the words with the prefix. So with "an" and "and"
+    /// in the trie the longest common prefix under "a" is "an".
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["xenon", "xenons", "xenonless", "xylem"]);
+    /// assert_eq!(Some("xenon".to_owned()), trie.longest_common_prefix_under("XEN"));
+    /// assert_eq!(Some("x".to_owned()), trie.longest_common_prefix_under("x"));
+    /// assert_eq!(Some("xylem".to_owned()), trie.longest_common_prefix_under("xylem"));
+    /// assert_eq!(None, trie.longest_common_prefix_under("xa"));
+    /// ```
+    fn longest_common_prefix_under(&self, prefix: &str) -> Option<String> {
+        let mut longest = None;
+        // There's only one node to go to at each step, so the walk is a single path that ends where the node
+        // returns false.
+        self.visit(prefix, &mut |node| {
+            longest = Some(node.prefix.to_owned());
+            !node.is_word && node.child_count == 1
+        });
+        longest
+    }
+
+    /// Returns true if there's a word that starts with `prefix`, counting `prefix` itself, with at least
+    /// `min_total_len` letters in all. This is the question in the game Ghost of whether a prefix can still be
+    /// carried on far enough, along with `contains()` for whether it's already a word.
+    ///
+    /// Each node keeps the length of the longest word below it, so this only has to find the node for `prefix`
+    /// rather than look through everything under it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "creature", "an"]);
+    /// assert!(trie.has_completion_of_length("cr", 8));
+    /// assert!(!trie.has_completion_of_length("cr", 9));
+    /// assert!(trie.has_completion_of_length("an", 2));
+    /// assert!(!trie.has_completion_of_length("q", 0));
+    /// ```
+    fn has_completion_of_length(&self, prefix: &str, min_total_len: usize) -> bool;
+
+    /// Get the words that start with `prefix`, counting `prefix` itself, that have exactly `total_len` letters, in
+    /// alphabetical order. Branches whose longest word is too short are skipped without being walked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crush", "crossed", "creature", "cr"]);
+    /// assert_eq!(vec!["cross", "crush"], trie.completions_of_exact_length("CR", 5));
+    /// assert_eq!(vec!["cr"], trie.completions_of_exact_length("cr", 2));
+    /// assert!(trie.completions_of_exact_length("cr", 1).is_empty());
+    /// ```
+    fn completions_of_exact_length(&self, prefix: &str, total_len: usize) -> Vec<String>;
+
+    /// Get the words with as many letters as `word` that differ from it in no more than `k` places, in
+    /// alphabetical order, which is a Hamming distance of at most `k`. Only letters in the same place are compared,
+    /// so unlike `did_you_mean()` a word with a letter added or left out never matches. A `k` of 0 finds `word`
+    /// itself if it's in the trie, and a `k` of at least the length of `word` finds every word of that length. As
+    /// in `completions_of_exact_length()` the words are given as they're stored, without spellings.
+    ///
+    /// The walk down the trie stops at any branch that already differs in more than `k` places or whose longest
+    /// word is too short, so a small `k` only looks at a small part of the trie.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crass", "cress", "gross", "cros", "crossed"]);
+    /// assert_eq!(vec!["cross"], trie.find_hamming("Cross", 0));
+    /// assert_eq!(vec!["crass", "cress", "cross", "gross"], trie.find_hamming("cross", 1));
+    /// assert_eq!(vec!["crass", "cress", "cross"], trie.find_hamming("cxess", 2));
+    /// assert_eq!(vec!["crossed"], trie.find_hamming("zzzzzzz", 7));
+    /// assert!(trie.find_hamming("crosse", 6).is_empty());
+    /// ```
+    fn find_hamming(&self, word: &str, k: usize) -> Vec<String>;
+
+    /// Call `f` with each word that starts with `prefix` in alphabetical order, including `prefix` itself if it's
+    /// a word, without allocating a `String` for each one as `words_with_prefix()` does. As there, a word with a
+    /// spelling is given in that spelling.
+    ///
+    /// The `&str` passed to `f` is borrowed from one buffer that grows and shrinks as the walk goes down and up
+    /// the trie, so it's only valid for that call. Keep a copy with `to_owned()` if it's needed afterward.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed", "creature", "an"]);
+    /// let mut total_len = 0;
+    /// trie.for_each_word_with_prefix("cr", |word| total_len += word.len());
+    /// assert_eq!(20, total_len);
+    /// ```
+    fn for_each_word_with_prefix<F: FnMut(&str)>(&self, prefix: &str, mut f: F)
+    where
+        Self: Sized,
+    {
+        self.visit(prefix, &mut |node| {
+            if node.is_word {
+                f(node.word());
+            }
+            true
+        });
+    }
+
+    /// Call `f` with every word in the trie in alphabetical order, as in `for_each_word_with_prefix()`.
+    fn for_each_word<F: FnMut(&str)>(&self, f: F)
+    where
+        Self: Sized,
+    {
+        self.for_each_word_with_prefix("", f);
+    }
+
+    /// Add every word from `other`, which may be a different implementation, so that the trie holds the union of
+    /// the two sets of words. Tags come along with their words, replacing the tag of a word that's in both, and so
+    /// do sources, which are added to those the word already has.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["cross", "an"]);
+    /// trie.merge_from(&NoParentLetterTrie::from_words(vec!["crossed", "an"]));
+    /// assert_eq!(3, trie.count_with_prefix(""));
+    /// ```
+    fn merge_from<T: LetterTrie + ?Sized>(&mut self, other: &T)
+    where
+        Self: Sized,
+    {
+        for word in other.words_with_sources() {
+            self.insert_sourced_word(&word);
+        }
+    }
+
+    /// Compare the words with those of `other`, which may be a different implementation, giving the words that
+    /// `other` adds and the words it doesn't have. The two tries are walked side by side and any subtree that's the
+    /// same in both is skipped, so the work is mostly in walking each trie once rather than in building and
+    /// comparing two sets of words. Subtrees are matched by a 64-bit hash of their nodes along with their size.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let old = BaseLetterTrie::from_words(vec!["cross", "crossed", "an"]);
+    /// let new = NoParentLetterTrie::from_words(vec!["cross", "an", "and"]);
+    /// let diff = old.diff(&new);
+    /// assert_eq!(vec!["and"], diff.added);
+    /// assert_eq!(vec!["crossed"], diff.removed);
+    /// ```
+    fn diff<T: LetterTrie + ?Sized>(&self, other: &T) -> diff::TrieDiff
+    where
+        Self: Sized,
+    {
+        diff::diff(self, other)
+    }
+
+    /// Find out how much smaller the trie would be if every set of identical subtrees were merged into one, as in a
+    /// DAWG, along with the shared subtrees that occur most often. The trie is walked once and isn't changed. See
+    /// `SuffixReport` for what's counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["tested", "rested", "rest"]);
+    /// let report = trie.suffix_sharing_report();
+    /// // The "ested" under "t" and "r" differ because "rest" is a word, but their "ed" is shared.
+    /// assert_eq!(vec!["ed"], report.most_common[0].endings);
+    /// assert_eq!(2, report.nodes_saved());
+    /// ```
+    fn suffix_sharing_report(&self) -> SuffixReport {
+        suffix_sharing::report(self)
+    }
+
+    /// Work out a value for every node from the bottom up, each from the node's `NodeView` and the values already
+    /// worked out for its children in character order, and keep them in an `AnnotatedTrie` that can be looked up
+    /// by prefix or by `NodeId`. See the `annotate` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// // The fewest letters left to type after each prefix to finish a word.
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crate"]);
+    /// let keystrokes = trie.annotate(|node, children: &[usize]| {
+    ///     if node.is_word {
+    ///         0
+    ///     } else {
+    ///         children.iter().min().map_or(0, |n| n + 1)
+    ///     }
+    /// });
+    /// assert_eq!(Some(&3), keystrokes.get("cr"));
+    /// assert_eq!(Some(&1), keystrokes.get("Cros"));
+    /// assert_eq!(Some(&0), keystrokes.get("cross"));
+    /// assert_eq!(None, keystrokes.get("crux"));
+    /// ```
+    fn annotate<T, F>(&self, f: F) -> AnnotatedTrie<T>
+    where
+        F: Fn(&NodeView, &[T]) -> T,
+        Self: Sized,
+    {
+        annotate::annotate(self, f)
+    }
+
+    /// Borrow the trie as a `TrieView`, which has the queries but nothing that changes the words, for handing the
+    /// trie to code that should only read it. The view is of this type of trie. For a trie held as a
+    /// `Box<dyn LetterTrie>`, use `TrieView::new()`. See the `view` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// fn longest<T: LetterTrie + ?Sized>(view: TrieView<T>, prefix: &str) -> Option<String> {
+    ///     view.words_with_prefix(prefix).into_iter().max_by_key(|word| word.len())
+    /// }
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed", "crease"]);
+    /// assert_eq!(Some("crossed".to_owned()), longest(trie.view(), "cr"));
+    /// assert_eq!(None, longest(trie.view(), "an"));
+    /// ```
+    fn view(&self) -> TrieView<'_, Self>
+    where
+        Self: Sized,
+    {
+        TrieView::new(self)
+    }
+
+    /// Create a new trie holding only the words that start with `prefix`. The words are kept whole rather than
+    /// having the prefix cut off, so the new trie answers `contains()` the same way for those words.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crease", "an"]);
+    /// let sub = trie.subtrie("cro");
+    /// assert!(sub.contains("crossed"));
+    /// assert!(!sub.contains("an"));
+    /// assert_eq!(2, sub.count_with_prefix(""));
+    /// ```
+    fn subtrie(&self, prefix: &str) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_words(self.words_with_prefix(prefix))
+    }
+
+    /// Get up to `limit` words that `input` might be a misspelling of as in `did_you_mean()`, ranked with the
+    /// weights in `config`.
+    #[cfg(feature = "std")]
+    fn did_you_mean_with(
+        &self,
+        input: &str,
+        limit: usize,
+        config: &SuggestionConfig,
+    ) -> Vec<Suggestion> {
+        suggestions::did_you_mean(self, input, limit, config)
+    }
+
+    /// Get the words typed by `digits` on a phone keypad, where 2 is "abc", 3 is "def" and so on up to 9 for
+    /// "wxyz", in alphabetical order. Only words with exactly one letter per digit are returned.
+    ///
+    /// The result is empty if `digits` is empty or has anything other than the digits 2 through 9, since 0 and 1
+    /// have no letters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["good", "home", "gone", "hood", "goner"]);
+    /// assert_eq!(vec!["gone", "good", "home", "hood"], trie.words_for_digits("4663"));
+    /// assert!(trie.words_for_digits("4660").is_empty());
+    /// ```
+    fn words_for_digits(&self, digits: &str) -> Vec<String> {
+        let keys = match keypad_letters(digits) {
+            Some(keys) => keys,
+            None => return vec![],
+        };
+        let mut words = vec![];
+        self.visit("", &mut |node| {
+            if node.depth == 0 {
+                return true;
+            }
+            if !keys[node.depth - 1].contains(node.c) {
+                return false;
+            }
+            if node.depth < keys.len() {
+                return true;
+            }
+            if node.is_word {
+                words.push(node.prefix.to_owned());
+            }
+            false
+        });
+        words
+    }
+
+    /// Get up to `max_count` words that start with the letters typed by `digits` on a phone keypad, for
+    /// predictive text. The possible starts are ranked by how many words begin with them, most first, and the
+    /// words for each start are shortest first and then alphabetical as in `suggest()`.
+    ///
+    /// The result is empty for the same inputs as `words_for_digits()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["good", "gone", "goner", "home", "homer", "homes"]);
+    /// // There are three words starting with "hom" and two with "gon".
+    /// assert_eq!(
+    ///     vec!["home", "homer", "homes", "gone"],
+    ///     trie.completions_for_digits("466", 4)
+    /// );
+    /// ```
+    fn completions_for_digits(&self, digits: &str, max_count: usize) -> Vec<String> {
+        let keys = match keypad_letters(digits) {
+            Some(keys) => keys,
+            None => return vec![],
+        };
+        // The words under each node whose prefix matches the digits, as (length, word).
+        let mut starts: Vec<Vec<(usize, String)>> = vec![];
+        self.visit("", &mut |node| {
+            if node.depth == 0 {
+                return true;
+            }
+            if node.depth <= keys.len() && !keys[node.depth - 1].contains(node.c) {
+                return false;
+            }
+            if node.depth == keys.len() {
+                starts.push(vec![]);
+            }
+            // A start was pushed on the way down to any node this deep.
+            if let (true, Some(words)) =
+                (node.depth >= keys.len() && node.is_word, starts.last_mut())
+            {
+                words.push((node.depth, node.prefix.to_owned()));
+            }
+            true
+        });
+        // A stable sort so that starts with the same number of words stay in alphabetical order.
+        starts.sort_by_key(|words| core::cmp::Reverse(words.len()));
+        starts
+            .into_iter()
+            .flat_map(|mut words| {
+                words.sort_unstable();
+                words
+            })
+            .take(max_count)
+            .map(|(_, word)| word)
+            .collect()
+    }
+
+    /// Get every place a compound word like "Arbeitsplatz" can be split into two stored words, as the byte
+    /// indices `i` where `word[..i]` and `word[i..]` are both words, in order. Each part has to be at least two
+    /// characters long; use `split_points_with_min_len()` for another limit. The word being stored itself doesn't
+    /// count as a split.
+    ///
+    /// The words the token starts with are found in one walk down the trie, and the rest of the token after each
+    /// of them is looked up with `contains()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["haus", "tür", "haustür", "fiets", "pad"]);
+    /// assert_eq!(vec![4], trie.split_points("Haustür"));
+    /// assert_eq!(vec![5], trie.split_points("fietspad"));
+    /// assert!(trie.split_points("fietsen").is_empty());
+    /// ```
+    fn split_points(&self, word: &str) -> Vec<usize> {
+        compounds::split_points(self, word, compounds::DEFAULT_MIN_PART_LEN)
+    }
+
+    /// Get the places `word` can be split into two stored words as `split_points()` does, with each part at least
+    /// `min_part_len` characters long.
+    fn split_points_with_min_len(&self, word: &str, min_part_len: usize) -> Vec<usize> {
+        compounds::split_points(self, word, min_part_len)
+    }
+
+    /// Get every way of splitting `word` into between two and `max_parts` stored words of at least two characters
+    /// each. The parts are slices of `word` as it was given, and the decompositions are in order of the length of
+    /// the first part, then the second, and so on. The word being stored itself isn't a decomposition.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["ziek", "zieken", "en", "huis"]);
+    /// assert_eq!(
+    ///     vec![vec!["Ziek", "en", "huis"], vec!["Zieken", "huis"]],
+    ///     trie.compound_decompositions("Ziekenhuis", 3)
+    /// );
+    /// assert_eq!(vec![vec!["Zieken", "huis"]], trie.compound_decompositions("Ziekenhuis", 2));
+    /// ```
+    fn compound_decompositions(&self, word: &str, max_parts: usize) -> Vec<Vec<String>> {
+        compounds::compound_decompositions(self, word, max_parts, compounds::DEFAULT_MIN_PART_LEN)
+    }
+
+    /// Get the ways of splitting `word` into stored words as `compound_decompositions()` does, with each part at
+    /// least `min_part_len` characters long.
+    fn compound_decompositions_with_min_len(
+        &self,
+        word: &str,
+        max_parts: usize,
+        min_part_len: usize,
+    ) -> Vec<Vec<String>> {
+        compounds::compound_decompositions(self, word, max_parts, min_part_len)
+    }
+
+    /// Compile the words into a `TrieMatcher` for finding every word that appears in a text in a single pass.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let matcher = BaseLetterTrie::from_words(vec!["cross", "crossed", "rose"]).compile_matcher();
+    /// let words: Vec<&str> = matcher.scan("Crossed").map(|m| m.word).collect();
+    /// assert_eq!(vec!["cross", "crossed"], words);
+    /// ```
+    fn compile_matcher(&self) -> matcher::TrieMatcher {
+        matcher::TrieMatcher::from_trie(self)
+    }
+
+    /// Count each of the letters a to z across all of the words, so a letter that's in a word twice counts twice.
+    /// Other characters aren't counted. The counts are gathered in one walk of the trie.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "rose"]);
+    /// let counts = trie.letter_frequencies();
+    /// assert_eq!(5, counts[(b's' - b'a') as usize]);
+    /// assert_eq!(2, counts[(b'e' - b'a') as usize]);
+    /// ```
+    fn letter_frequencies(&self) -> [usize; 26] {
+        frequency::letter_frequencies(self)
+    }
+
+    /// Count the words that have each of the letters a to z at least once, so a letter that's in a word twice
+    /// counts once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "rose"]);
+    /// assert_eq!(3, trie.letter_frequencies_by_word()[(b's' - b'a') as usize]);
+    /// ```
+    fn letter_frequencies_by_word(&self) -> [usize; 26] {
+        frequency::letter_frequencies_by_word(self)
+    }
+
+    /// Count the letters a to z at each position among the words that are `word_len` characters long. The walk
+    /// doesn't go below that depth.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crest", "rose"]);
+    /// let counts = trie.positional_frequencies(5);
+    /// assert_eq!(5, counts.len());
+    /// assert_eq!(2, counts[0][(b'c' - b'a') as usize]);
+    /// assert_eq!(1, counts[2][(b'e' - b'a') as usize]);
+    /// ```
+    fn positional_frequencies(&self, word_len: usize) -> Vec<[usize; 26]> {
+        frequency::positional_frequencies(self, word_len)
+    }
+
+    /// Count which letter follows which across all of the words, for making up words spelled like them with
+    /// `TransitionModel::generate()`. The counts are the same for every position in a word, and are gathered in
+    /// one walk of the trie. See the `transitions` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "rose"]);
+    /// let model = trie.transition_model();
+    /// assert_eq!(3, model.count(0, Some('o'), Some('s')));
+    /// assert_eq!(1, model.count(0, None, Some('r')));
+    /// ```
+    #[cfg(feature = "std")]
+    fn transition_model(&self) -> TransitionModel {
+        transitions::transition_model(self, false)
+    }
+
+    /// Count which letter follows which as `transition_model()` does but separately for each position in a word,
+    /// which makes words closer to the originals at the cost of needing more words to learn from.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "rose"]);
+    /// let model = trie.transition_model_by_position();
+    /// assert_eq!(2, model.count(3, Some('o'), Some('s')));
+    /// assert_eq!(1, model.count(2, Some('o'), Some('s')));
+    /// ```
+    #[cfg(feature = "std")]
+    fn transition_model_by_position(&self) -> TransitionModel {
+        transitions::transition_model(self, true)
+    }
+
+    /// Pick the word to guess next in a game like hangman. `pattern` has the known letters in place and `?` for
+    /// each unknown one, and no letter of `excluded` can be anywhere in the word. Of the words that fit, this gives
+    /// the one whose letters are shared by the most of the others, counting each of its letters once, with ties
+    /// going to the first in alphabetical order. It returns None if no word fits.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crest", "crust", "rose"]);
+    /// assert_eq!(Some("crest".to_owned()), trie.best_guess("cr?s?", ""));
+    /// assert_eq!(Some("crust".to_owned()), trie.best_guess("cr?s?", "e"));
+    /// assert_eq!(Some("cross".to_owned()), trie.best_guess("cr?s?", "t"));
+    /// assert_eq!(None, trie.best_guess("cr?s?", "ot"));
+    /// ```
+    fn best_guess(&self, pattern: &str, excluded: &str) -> Option<String> {
+        let candidates = frequency::pattern_candidates(
+            self,
+            pattern,
+            excluded,
+            &mut limits::Budget::unlimited(),
+            None,
+        );
+        frequency::best_guess(candidates)
+    }
+
+    /// Get the guess `best_guess()` would make along with what the search for it did. The metrics count each node
+    /// the walk reaches and one comparison for each letter checked against the pattern, and the terminal depth
+    /// is that of the deepest node that fit. See the `metrics` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crest", "rose"]);
+    /// let (guess, metrics) = trie.best_guess_with_metrics("cr?s?", "");
+    /// assert_eq!(trie.best_guess("cr?s?", ""), guess);
+    /// assert_eq!(FindOutcome::Word, metrics.outcome);
+    /// assert_eq!(5, metrics.terminal_depth);
+    /// ```
+    fn best_guess_with_metrics(
+        &self,
+        pattern: &str,
+        excluded: &str,
+    ) -> (Option<String>, FindMetrics) {
+        let mut metrics = FindMetrics::default();
+        let candidates = frequency::pattern_candidates(
+            self,
+            pattern,
+            excluded,
+            &mut limits::Budget::unlimited(),
+            Some(&mut metrics),
+        );
+        (frequency::best_guess(candidates), metrics)
+    }
+
+    /// Get the words that fit `constraints`, in alphabetical order, for a game like Wordle. The walk skips any
+    /// branch with a letter out of place or a letter more often than it can be, or without room left for the
+    /// letters still needed, and goes no deeper than the length of the word.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use letter_trie::LetterFeedback::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["crane", "crate", "grate", "irate", "trace", "slate"]);
+    /// let constraints = LetterConstraints::new(5).with_guess("trace", &[Yellow, Green, Green, Gray, Green]);
+    /// assert_eq!(vec!["grate", "irate"], trie.filter_candidates(&constraints));
+    /// ```
+    fn filter_candidates(&self, constraints: &LetterConstraints) -> Vec<String> {
+        let mut words = vec![];
+        constraints::for_each_candidate(
+            self,
+            constraints,
+            &mut limits::Budget::unlimited(),
+            |word| words.push(word.to_owned()),
+        );
+        words
+    }
+
+    /// Count the words that `filter_candidates()` would return without putting any of them together, for scoring
+    /// possible guesses by how many words they'd leave.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["crane", "crate", "grate", "irate", "trace", "slate"]);
+    /// assert_eq!(6, trie.count_candidates(&LetterConstraints::new(5)));
+    /// assert_eq!(2, trie.count_candidates(&LetterConstraints::new(5).with_fixed(0, 'c')));
+    /// ```
+    fn count_candidates(&self, constraints: &LetterConstraints) -> usize {
+        let mut count = 0;
+        constraints::for_each_candidate(
+            self,
+            constraints,
+            &mut limits::Budget::unlimited(),
+            |_| count += 1,
+        );
+        count
+    }
+
+    /// Check that the trie is consistent with itself, for use in tests and fuzzing.
+    ///
+    /// This walks the whole trie and confirms that every node's depth and prefix agree with its place in the trie,
+    /// that children come in strictly increasing character order and match each parent's child count, that
+    /// `children()` agrees with the walk for the root, that every leaf other than the root ends a word, that the
+    /// counts in `to_fixed_node()` match the nodes found, and that only words have scores and each node's
+    /// `NodeView::max_score` is the highest score at or below it. An implementation may check more, such as
+    /// `BaseLetterTrie` checking its parent links.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Inconsistent` describing the first problem found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// assert!(trie.verify_integrity().is_ok());
+    /// ```
+    fn verify_integrity(&self) -> Result<()> {
+        verify_structure(self)
+    }
+
+    /// Save the trie in the binary format described in the `binary` module.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if writing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// let mut bytes: Vec<u8> = vec![];
+    /// trie.write_binary(&mut bytes).unwrap();
+    ///
+    /// let copy = BaseLetterTrie::read_binary(bytes.as_slice()).unwrap();
+    /// assert_eq!(trie.to_fixed_node(), copy.to_fixed_node());
+    /// ```
+    #[cfg(feature = "std")]
+    fn write_binary<W: io::Write>(&self, writer: W) -> Result<()>
+    where
+        Self: Sized,
+    {
+        binary::write_trie(self, writer)
+    }
+
+    /// Save the words in sorted word files in `dir`, one for each shard chosen by `shard_by`, along with a manifest
+    /// giving the number of words in each. The directory is created if it isn't there, and files already in it
+    /// with the same names are replaced. See the `sharding` module for the file names and the manifest.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the path if a file can't be written, and
+    /// `LetterTrieError::InvalidFormat` for a word with a line break in it. The shards before the one that failed
+    /// have already been written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("letter_trie_sharded_{}", std::process::id()));
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crate", "an", "and", "ñu"]);
+    /// let manifest = trie.save_sharded(&dir, ShardBy::FirstLetter)?;
+    /// let shards: Vec<(&str, usize)> = manifest
+    ///     .shards
+    ///     .iter()
+    ///     .map(|shard| (shard.file_name.as_str(), shard.word_count))
+    ///     .collect();
+    /// assert_eq!(vec![("a.txt", 2), ("c.txt", 2), ("_f1_.txt", 1)], shards);
+    /// assert_eq!("crate\ncross\n", std::fs::read_to_string(dir.join("c.txt"))?);
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    #[cfg(feature = "fs")]
+    fn save_sharded(&self, dir: impl AsRef<Path>, shard_by: ShardBy) -> Result<ShardManifest>
+    where
+        Self: Sized,
+    {
+        sharding::save_sharded(self, dir.as_ref(), shard_by)
+    }
+
+    /// Load a trie saved with `write_binary()` by any implementation of `LetterTrie`, or with
+    /// `ConfiguredLetterTrie::write_binary()`, in which case the words are as they were stored and the options are
+    /// left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::InvalidFormat` if the data isn't a saved trie or is inconsistent, and
+    /// `LetterTrieError::Io` if reading fails, including when the data ends early.
+    #[cfg(feature = "std")]
+    fn read_binary<R: io::Read>(reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        binary::read_trie(reader)
+    }
+
+    /// Save the words in the front-coded format described in the `front_coded` module, which is smaller than a
+    /// plain word list and simple to read without this crate. Tags, sources and spellings aren't saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if writing fails or a word has a control character such as a tab in it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed", "crossing"]);
+    /// let mut bytes: Vec<u8> = vec![];
+    /// trie.save_front_coded(&mut bytes).unwrap();
+    ///
+    /// let copy = NoParentLetterTrie::from_front_coded(bytes.as_slice(), &LoadMethod::Continuous).unwrap();
+    /// assert_eq!(trie.fingerprint(), copy.fingerprint());
+    /// ```
+    #[cfg(feature = "std")]
+    fn save_front_coded<W: io::Write>(&self, writer: W) -> Result<()>
+    where
+        Self: Sized,
+    {
+        front_coded::write_words(self, writer)
+    }
+
+    /// The structure of the trie as nested JSON objects, one for each node, as described in the `json_tree`
+    /// module. This is for handing the trie to a tree viewer, not for saving it.
+    ///
+    /// Only the nodes down to `max_depth` letters are shown, and only the first `max_children` children of each
+    /// node in character order. The nodes left out are still counted in the word and node counts of the nodes
+    /// above them, and each node with children left out says how many. None means no limit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["an", "and", "ant"]);
+    /// assert_eq!(
+    ///     concat!(
+    ///         r#"{"c":"","word":false,"children":["#,
+    ///         r#"{"c":"a","word":false,"children":["#,
+    ///         r#"{"c":"n","word":true,"children":["#,
+    ///         r#"{"c":"d","word":true,"children":[],"n":1,"nodes":1,"height":1}"#,
+    ///         r#"],"n":3,"nodes":3,"height":2,"truncated":1}"#,
+    ///         r#"],"n":3,"nodes":4,"height":3}"#,
+    ///         r#"],"n":3,"nodes":5,"height":4}"#,
+    ///     ),
+    ///     trie.to_json_tree(None, Some(1))
+    /// );
+    /// ```
+    fn to_json_tree(&self, max_depth: Option<usize>, max_children: Option<usize>) -> String {
+        let mut json = String::new();
+        // Writing to a String never fails.
+        let _ = json_tree::write_tree(self, max_depth, max_children, &mut json);
+        json
+    }
+
+    /// Write the same JSON as `to_json_tree()` to `writer` as the trie is walked, without building the whole
+    /// string first, which matters for a large trie with no limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if writing fails.
+    #[cfg(feature = "std")]
+    fn write_json_tree<W: io::Write>(
+        &self,
+        writer: W,
+        max_depth: Option<usize>,
+        max_children: Option<usize>,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let mut out = json_tree::IoWriter {
+            inner: io::BufWriter::new(writer),
+            error: None,
+        };
+        if json_tree::write_tree(self, max_depth, max_children, &mut out).is_err() {
+            // The writer keeps the error that stopped it, but the formatter can also fail on its own.
+            let err = out
+                .error
+                .take()
+                .unwrap_or_else(|| io::Error::other("the JSON tree couldn't be formatted"));
+            return Err(err.into());
+        }
+        io::Write::flush(&mut out.inner)?;
+        Ok(())
+    }
+
+    /// Load a trie from words saved with `save_front_coded()`. The words come out in order, so they're loaded as
+    /// sorted words with `load_method`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::InvalidFormat` if the header isn't right, and `LetterTrieError::Io` if reading
+    /// fails, including when a word's shared byte count is longer than the word before it.
+    #[cfg(feature = "std")]
+    fn from_front_coded<R: io::Read>(reader: R, load_method: &LoadMethod) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        front_coded::read_trie(reader, load_method)
+    }
+
+    /// Describe the trie one node to a line, each letter indented under the one before it and marked "(word)" if
+    /// it ends a word. Below `max_depth` letters, a node's children are replaced by a line such as "… subtree of
+    /// 4,812 words", and after the first `max_children` children of a node the rest are replaced by a line such as
+    /// "… 23 more children". With None for both limits the whole trie is described.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let t = NoParentLetterTrie::from_words(vec!["an", "and", "ant", "cross"]);
+    /// assert_eq!("a\n    n (word)\n        … subtree of 2 words\n… 1 more child\n", t.describe_tree(Some(2), Some(1)));
+    /// ```
+    fn describe_tree(&self, max_depth: Option<usize>, max_children: Option<usize>) -> String {
+        debug_tree::describe_tree(self, max_depth, max_children)
+    }
+
+    /// Print one line of information about the root node of a trie.
+    ///
+    /// This includes things like the number of nodes and words in the trie and the maximum height.
+    #[cfg(feature = "std")]
+    fn print_root(&self) {
+        println!("{:?}", self.to_fixed_node());
+    }
+
+    /// Print information about the root node of a trie over multiple lines.
+    ///
+    /// This includes things like the number of nodes and words in the trie and the maximum height.
+    #[cfg(feature = "std")]
+    fn print_root_alt(&self) {
+        println!("{:#?}", self.to_fixed_node());
+    }
+
+    /// Print one line for each letter that starts a word, with the number of words under it and the first few of
+    /// them, rather than every word.
+    #[cfg(feature = "std")]
+    fn print_branches(&self) {
+        for c in self.children("") {
+            if let Some(branch) = self.find_with_samples(&c.to_string(), DEBUG_BRANCH_SAMPLE_COUNT)
+            {
+                println!(
+                    "{}: {} words, e.g. {}",
+                    c,
+                    branch.node.word_count,
+                    branch.samples.join(", ")
+                );
+            }
+        }
+    }
+}
+
+// The Debug output shared by the tries: a few counts and the first few words, so that it stays short however big
+// the trie is. It only goes through visit() and to_fixed_node() so nothing can follow a parent link.
+pub(crate) fn fmt_debug_summary<T: LetterTrie + ?Sized>(
+    trie: &T,
+    type_name: &str,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let root = trie.to_fixed_node();
+    let mut first_words: Vec<String> = vec![];
+    trie.visit("", &mut |node| {
+        if first_words.len() == DEBUG_TRIE_FIRST_WORD_COUNT {
+            return false;
+        }
+        if node.is_word {
+            first_words.push(node.prefix.to_owned());
+        }
+        true
+    });
+    f.debug_struct(type_name)
+        .field("word_count", &root.word_count)
+        .field("node_count", &root.node_count)
+        .field("height", &root.height)
+        .field("first_words", &first_words)
+        .finish()
+}
+
+// The Display output shared by the tries.
+pub(crate) fn fmt_display_summary<T: LetterTrie + ?Sized>(
+    trie: &T,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let root = trie.to_fixed_node();
+    write!(
+        f,
+        "LetterTrie({} words, height {})",
+        root.word_count, root.height
+    )
+}
+
+// The form a word is stored in, which is also the key for its tag, or None if there's nothing left of it once it's
+// trimmed.
+pub(crate) fn word_key(word: &str) -> Option<String> {
+    let word = word.trim();
+    if word.is_empty() {
+        None
+    } else {
+        Some(word.to_lowercase())
+    }
+}
+
+// Call f with the characters of s for one of the *_chars() queries, which lowercase each character on their own.
+// ASCII is passed straight through so that the query doesn't allocate, but anything else is lowercased as a
+// string first so that a final sigma comes out the way the words were stored.
+pub(crate) fn with_lowercase_chars<R>(
+    s: &str,
+    f: impl FnOnce(&mut dyn Iterator<Item = char>) -> R,
+) -> R {
+    if s.is_ascii() {
+        f(&mut s.chars())
+    } else {
+        f(&mut s.to_lowercase().chars())
+    }
+}
+
+// The characters of an ASCII query, lowercased a byte at a time. The tries walk these directly rather than through
+// with_lowercase_chars() since a miss deep in a long shared prefix otherwise spends most of its time on the
+// dynamic dispatch and char::to_lowercase() at each level.
+pub(crate) fn ascii_lowercase_chars(s: &str) -> impl Iterator<Item = char> + '_ {
+    debug_assert!(s.is_ascii());
+    s.bytes().map(|b| char::from(b.to_ascii_lowercase()))
+}
+
+// The letters for each digit in `digits` on a phone keypad, or None if it's empty or has a character other than
+// the digits 2 through 9.
+fn keypad_letters(digits: &str) -> Option<Vec<&'static str>> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits
+        .chars()
+        .map(|digit| match digit {
+            '2' => Some("abc"),
+            '3' => Some("def"),
+            '4' => Some("ghi"),
+            '5' => Some("jkl"),
+            '6' => Some("mno"),
+            '7' => Some("pqrs"),
+            '8' => Some("tuv"),
+            '9' => Some("wxyz"),
+            _ => None,
+        })
+        .collect()
+}
+
+// Add one node to the hash behind LetterTrie::fingerprint().
+pub(crate) fn fingerprint_node(mut hash: u64, c: char, is_word: bool, child_count: usize) -> u64 {
+    let mut bytes = [0u8; 9];
+    bytes[..4].copy_from_slice(&(c as u32).to_le_bytes());
+    bytes[4] = is_word as u8;
+    bytes[5..].copy_from_slice(&(child_count as u32).to_le_bytes());
+    for byte in &bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+// The checks behind LetterTrie::verify_integrity() that only need visit() and to_fixed_node(), so that an
+// implementation with checks of its own can run these too.
+pub(crate) fn verify_structure<T: LetterTrie + ?Sized>(trie: &T) -> Result<()> {
+    // Each entry is a node on the path to the current node: its number of children not yet visited, the
+    // character of the last child visited, and the length of its prefix in bytes.
+    let mut path: Vec<(usize, Option<char>, usize)> = vec![];
+    let mut problem: Option<String> = None;
+    let mut is_root = true;
+    let mut root_children: Vec<char> = vec![];
+    trie.visit("", &mut |node| {
+        if problem.is_some() {
+            return false;
+        }
+        if node.depth == 1 {
+            root_children.push(node.c);
+        }
+        problem = check_visited_node(node, is_root, &mut path).err();
+        is_root = false;
+        path.push((node.child_count, None, node.prefix.len()));
+        problem.is_none()
+    });
+    if problem.is_none() && path.iter().any(|(remaining, _, _)| *remaining > 0) {
+        problem = Some("the last nodes are missing children".to_owned());
+    }
+    if problem.is_none() && trie.children("") != root_children {
+        problem = Some(format!(
+            "children() gives {:?} for the root but the walk found {:?}",
+            trie.children(""),
+            root_children
+        ));
+    }
+    if problem.is_none() {
+        let root = trie.to_fixed_node();
+        let counts = *annotate::subtree_counts(trie).root();
+        if (root.node_count, root.word_count, root.height)
+            != (counts.node_count, counts.word_count, counts.height)
+        {
+            problem = Some(format!(
+                "the root reports {} nodes, {} words and a height of {} but there are {} nodes, {} words and a \
+                height of {}",
+                root.node_count,
+                root.word_count,
+                root.height,
+                counts.node_count,
+                counts.word_count,
+                counts.height
+            ));
+        }
+    }
+    if problem.is_none() {
+        problem = scores::check_max_scores(trie).err();
+    }
+    match problem {
+        Some(msg) => Err(LetterTrieError::Inconsistent(msg)),
+        None => Ok(()),
+    }
+}
+
+// Check one node from the walk in verify_structure() against the path of nodes above it.
+fn check_visited_node(
+    node: &NodeView,
+    is_root: bool,
+    path: &mut Vec<(usize, Option<char>, usize)>,
+) -> Result<(), String> {
+    if is_root {
+        if node.depth != 0 || !node.prefix.is_empty() || node.is_word {
+            return Err(format!("the root is {:?}", node));
+        }
+        return Ok(());
+    }
+    if node.depth == 0 || node.depth > path.len() {
+        return Err(format!("{:?} is at the wrong depth", node));
+    }
+    while path.len() > node.depth {
+        if let Some((remaining, _, _)) = path.pop() {
+            if remaining > 0 {
+                return Err(format!("a node before {:?} is missing children", node));
+            }
+        }
+    }
+    let (remaining, last_c, parent_prefix_len) = &mut path[node.depth - 1];
+    if *remaining == 0 {
+        return Err(format!("{:?} is an extra child", node));
+    }
+    if last_c.map(|c| c >= node.c).unwrap_or(false) {
+        return Err(format!("{:?} is out of order", node));
+    }
+    *remaining -= 1;
+    *last_c = Some(node.c);
+    if node.prefix.len() != *parent_prefix_len + node.c.len_utf8() || !node.prefix.ends_with(node.c)
+    {
+        return Err(format!("{:?} has the wrong prefix", node));
+    }
+    if node.child_count == 0 && !node.is_word {
+        return Err(format!("{:?} is a leaf but not a word", node));
+    }
+    Ok(())
+}
+
+/// An iterator over the words of a trie in alphabetical order, returned by `LetterTrie::words()`. It holds the
+/// words as they were when it was made rather than borrowing the trie.
+#[derive(Debug)]
+pub struct Words {
+    inner: alloc::vec::IntoIter<String>,
+}
+
+impl Iterator for Words {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Words {
+    fn next_back(&mut self) -> Option<String> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for Words {}
+
+// Add source_id to a word's sources, keeping them in order without repeats.
+pub(crate) fn add_source(sources: &mut Vec<u16>, source_id: u16) {
+    if let Err(index) = sources.binary_search(&source_id) {
+        sources.insert(index, source_id);
+    }
+}
+
+// Keep spelling for the word stored as key unless the word already has a spelling that the preference says to keep.
+// A blank spelling changes nothing.
+pub(crate) fn set_spelling(
+    spellings: &mut BTreeMap<String, String>,
+    key: String,
+    spelling: &str,
+    preference: CasePreference,
+) {
+    let spelling = spelling.trim();
+    if spelling.is_empty() {
+        return;
+    }
+    let old = spellings.entry(key).or_insert_with(|| spelling.to_owned());
+    if old != spelling && preference.prefers(spelling, old) {
+        *old = spelling.to_owned();
+    }
+}
+
+/// A borrowed description of one node passed to the callback in `LetterTrie::visit()`.
+#[derive(Debug)]
+pub struct NodeView<'a> {
+    /// The node's character, or a space for the root.
+    pub c: char,
+    /// The letters from the root down to and including this node, which is the word if `is_word` is true.
+    pub prefix: &'a str,
+    /// The number of letters in `prefix`, so the root has a depth of 0.
+    pub depth: usize,
+    pub is_word: bool,
+    pub child_count: usize,
+    /// The spelling kept for the word by `LetterTrie::insert_preserving_case()`, or None if the node isn't a word
+    /// or the word has no spelling.
+    pub spelling: Option<&'a str>,
+    /// The score given to the word by `LetterTrie::set_scores()`, or None if the node isn't a word or the word has
+    /// no score.
+    pub score: Option<f64>,
+    /// The highest score of the words at or below this node, or None if none of them has a score. This is what
+    /// lets `ScoredQuery::suggest_scored()` leave out branches that can't beat the words it already has.
+    pub max_score: Option<f64>,
+}
+
+impl<'a> NodeView<'a> {
+    /// The word as it should be shown, which is its spelling if it has one and otherwise `prefix`.
+    pub fn word(&self) -> &'a str {
+        self.spelling.unwrap_or(self.prefix)
+    }
+}
+
+/// The result of the fallible operations in this crate, with `LetterTrieError` as the error unless another is given.
+pub type Result<T, E = LetterTrieError> = core::result::Result<T, E>;
+
+/// An error from building, loading, saving or checking a trie.
+///
+/// More variants may be added, so a `match` outside this crate needs a wildcard arm. The variants for a problem
+/// with a file or a line in it carry the path, the line counting from 1, and the word where they're known, and
+/// `Display` includes them so that they can be found in a log.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+/// use std::error::Error;
+///
+/// let options = TrieOptions::new().with_tag_separator('\t');
+/// let err = ConfiguredLetterTrie::<NoParentLetterTrie>::from_file("no_such_words.txt", options).err().unwrap();
+/// assert!(err.to_string().contains("no_such_words.txt"));
+/// let io_err = err.source().unwrap().downcast_ref::<std::io::Error>().unwrap();
+/// assert_eq!(std::io::ErrorKind::NotFound, io_err.kind());
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LetterTrieError {
+    /// Reading or writing failed, either because of an I/O error or because the source wasn't valid UTF-8, where
+    /// there's no file path to give, as when reading from a `BufRead`.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// A file couldn't be opened, read or written.
+    #[cfg(feature = "std")]
+    File { path: PathBuf, source: io::Error },
+    /// The data passed to `LetterTrie::read_binary()` or another loader of a saved trie isn't in the expected
+    /// format, or a word can't be saved in one.
+    InvalidFormat(String),
+    /// A line of a word file or a journal isn't what it should be, such as one whose tag isn't a number. The path
+    /// is None if the lines came from a reader.
+    #[cfg(feature = "std")]
+    InvalidLine {
+        path: Option<PathBuf>,
+        line: usize,
+        message: String,
+    },
+    /// `LetterTrie::verify_integrity()` found a problem with the trie's nodes.
+    Inconsistent(String),
+    /// A word went over one of the limits in `TrieOptions` whose policy is `LimitPolicy::Abort`. `limit` is the name
+    /// of the option, "max_word_len" or "max_words", and `max` is its value. The line is the word's position when
+    /// the words weren't read as lines, and the word is None if its line was too long to be read whole.
+    LimitExceeded {
+        limit: &'static str,
+        max: usize,
+        line: usize,
+        word: Option<String>,
+    },
+    /// A word was given a second time under `DuplicatePolicy::Error`. The word is as it would be stored, and the
+    /// lines count from 1.
+    DuplicateWord {
+        word: String,
+        first_line: usize,
+        second_line: usize,
+    },
+    /// A word given to `LetterTrie::from_letter_groups()` doesn't start with the letter of its group. The letter
+    /// is as it was given and the word is without the whitespace around it.
+    MisfiledWord { letter: char, word: String },
+    /// The file of a `Dataset` given to `LetterTrie::from_dataset()` isn't there. `Display` says where the files
+    /// are looked for.
+    #[cfg(feature = "fs")]
+    MissingDataset { path: PathBuf },
+    /// `record_trace()` was called while a trace was already being recorded on the same thread. The path is the
+    /// file of the trace that's being recorded.
+    #[cfg(feature = "fs")]
+    TraceInProgress { path: PathBuf },
+    /// `ConfiguredLetterTrie::read_binary_with_options()` was asked for options that would look words up
+    /// differently from how they were stored. `option` is the name of the option in `TrieOptions`, and the values
+    /// are as shown by `Debug`.
+    IncompatibleOptions {
+        option: &'static str,
+        saved: String,
+        requested: String,
+    },
+}
+
+impl LetterTrieError {
+    // The same error from working with the file at path, with the path added where there's a place for it.
+    #[cfg(any(feature = "fs", feature = "async"))]
+    pub(crate) fn with_path(self, path: &std::path::Path) -> Self {
+        match self {
+            LetterTrieError::Io(source) => LetterTrieError::File {
+                path: path.to_owned(),
+                source,
+            },
+            LetterTrieError::InvalidLine {
+                path: None,
+                line,
+                message,
+            } => LetterTrieError::InvalidLine {
+                path: Some(path.to_owned()),
+                line,
+                message,
+            },
+            err => err,
+        }
+    }
+}
+
+// For map_err() on anything done with the file at path, so that the error says which file it was.
+#[cfg(any(feature = "fs", feature = "async"))]
+pub(crate) fn in_file<E: Into<LetterTrieError>>(
+    path: &std::path::Path,
+) -> impl FnOnce(E) -> LetterTrieError + '_ {
+    move |err| err.into().with_path(path)
+}
+
+// Lock a mutex whose contents are still good after a panic on another thread that held it, which is true of the
+// records kept by DisplayDetailOptions and of the prefix cache since each is only changed a whole value at a time.
+// Without this one panic in a load would make every later use of the same options panic too.
+#[cfg(feature = "std")]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// The error for a load whose worker thread panicked, which leaves nothing to build the trie from.
+#[cfg(feature = "parallel")]
+pub(crate) fn worker_panicked() -> LetterTrieError {
+    io::Error::new(io::ErrorKind::BrokenPipe, "a loader worker thread panicked").into()
+}
+
+// Like in_file() for the file of a dataset, except that a file that isn't there is LetterTrieError::MissingDataset.
+#[cfg(feature = "fs")]
+pub(crate) fn in_dataset_file(path: &Path) -> impl FnOnce(io::Error) -> LetterTrieError + '_ {
+    move |err| {
+        if err.kind() == io::ErrorKind::NotFound {
+            LetterTrieError::MissingDataset {
+                path: path.to_owned(),
+            }
+        } else {
+            in_file(path)(err)
+        }
+    }
+}
+
+impl fmt::Display for LetterTrieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            LetterTrieError::Io(err) => write!(f, "Error reading words: {}", err),
+            #[cfg(feature = "std")]
+            LetterTrieError::File { path, source } => {
+                write!(f, "Error with the file \"{}\": {}", path.display(), source)
+            }
+            LetterTrieError::InvalidFormat(msg) => write!(f, "Invalid trie data: {}", msg),
+            #[cfg(feature = "std")]
+            LetterTrieError::InvalidLine {
+                path,
+                line,
+                message,
+            } => match path {
+                Some(path) => write!(
+                    f,
+                    "Invalid line {} of \"{}\": {}",
+                    line,
+                    path.display(),
+                    message
+                ),
+                None => write!(f, "Invalid line {}: {}", line, message),
+            },
+            LetterTrieError::Inconsistent(msg) => write!(f, "Inconsistent trie: {}", msg),
+            LetterTrieError::LimitExceeded {
+                limit,
+                max,
+                line,
+                word,
+            } => match word {
+                Some(word) => write!(
+                    f,
+                    "Limit exceeded: \"{}\" on line {} goes over {} ({})",
+                    word, line, limit, max
+                ),
+                None => write!(
+                    f,
+                    "Limit exceeded: line {} is too long to read under {} ({})",
+                    line, limit, max
+                ),
+            },
+            LetterTrieError::DuplicateWord {
+                word,
+                first_line,
+                second_line,
+            } => write!(
+                f,
+                "Duplicate word: \"{}\" on line {} and again on line {}",
+                word, first_line, second_line
+            ),
+            LetterTrieError::MisfiledWord { letter, word } => write!(
+                f,
+                "Misfiled word: \"{}\" is in the group for '{}'",
+                word, letter
+            ),
+            #[cfg(feature = "fs")]
+            LetterTrieError::MissingDataset { path } => write!(
+                f,
+                "Missing dataset: \"{}\" isn't there. {}",
+                path.display(),
+                DATA_DIR_HINT
+            ),
+            #[cfg(feature = "fs")]
+            LetterTrieError::TraceInProgress { path } => write!(
+                f,
+                "Trace in progress: this thread is already recording a trace to \"{}\"",
+                path.display()
+            ),
+            LetterTrieError::IncompatibleOptions {
+                option,
+                saved,
+                requested,
+            } => write!(
+                f,
+                "Incompatible options: the trie was saved with {} = {} but {} was asked for",
+                option, saved, requested
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for LetterTrieError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LetterTrieError::Io(err) => Some(err),
+            LetterTrieError::File { source, .. } => Some(source),
+            LetterTrieError::InvalidFormat(_)
+            | LetterTrieError::InvalidLine { .. }
+            | LetterTrieError::Inconsistent(_)
+            | LetterTrieError::LimitExceeded { .. }
+            | LetterTrieError::DuplicateWord { .. }
+            | LetterTrieError::MisfiledWord { .. }
+            | LetterTrieError::IncompatibleOptions { .. } => None,
+            #[cfg(feature = "fs")]
+            LetterTrieError::MissingDataset { .. } | LetterTrieError::TraceInProgress { .. } => {
+                None
+            }
+        }
+    }
+}
+
+// An io::Error on its own says nothing about where it came from, so it's only turned into LetterTrieError::Io.
+// Errors from a file go through in_file() to become LetterTrieError::File instead.
+#[cfg(feature = "std")]
+impl From<io::Error> for LetterTrieError {
+    fn from(err: io::Error) -> Self {
+        LetterTrieError::Io(err)
+    }
+}
+
+/// A problem with a dataset's file found by `Dataset::verify()`.
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub enum DatasetError {
+    /// The file isn't there, which usually means the word files weren't copied along with the crate or
+    /// `LETTER_TRIE_DATA_DIR` doesn't point at them. `Display` says where the files are looked for.
+    Missing { filename: String },
+    /// The file couldn't be opened or read.
+    Io { filename: String, error: io::Error },
+    /// The file is a Git LFS pointer rather than the words, which happens when the repository was cloned without
+    /// LFS installed.
+    LfsPointer { filename: String },
+    /// The file doesn't have the expected number of words, so it's probably cut short or from another version.
+    WordCount {
+        filename: String,
+        expected: usize,
+        found: usize,
+    },
+    /// The file has the right number of words but not the right ones.
+    Fingerprint {
+        filename: String,
+        expected: u64,
+        found: u64,
+    },
+}
+
+#[cfg(feature = "fs")]
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Missing { filename } => write!(
+                f,
+                "The dataset \"{}\" isn't there. {}",
+                filename, DATA_DIR_HINT
+            ),
+            DatasetError::Io { filename, error } => {
+                write!(f, "Error reading the dataset \"{}\": {}", filename, error)
+            }
+            DatasetError::LfsPointer { filename } => write!(
+                f,
+                "The dataset \"{}\" is a Git LFS pointer, not the words. Install Git LFS and run \"git lfs pull\".",
+                filename
+            ),
+            DatasetError::WordCount {
+                filename,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The dataset \"{}\" has {} words rather than {}. The file may be damaged or out of date.",
+                filename,
+                format_count(*found),
+                format_count(*expected)
+            ),
+            DatasetError::Fingerprint {
+                filename,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The words in the dataset \"{}\" have the fingerprint {:#018x} rather than {:#018x}. The file may \
+                be damaged or out of date.",
+                filename, found, expected
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl error::Error for DatasetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            DatasetError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// A name given to `FromStr` for `LetterTrieType`, `LoadMethod` or `Dataset` that isn't one of the names their
+/// `Display` forms use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownNameError {
+    /// What was being parsed, such as "load method".
+    pub kind: &'static str,
+    /// The name as it was given.
+    pub name: String,
+    /// The names that would have been accepted, separated by commas.
+    pub expected: String,
+}
+
+impl UnknownNameError {
+    fn new<'a>(
+        kind: &'static str,
+        name: &str,
+        expected: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        Self {
+            kind,
+            name: name.to_owned(),
+            expected: expected.into_iter().collect::<Vec<_>>().join(", "),
+        }
+    }
+}
+
+impl fmt::Display for UnknownNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown {} \"{}\". Expected one of: {}",
+            self.kind, self.name, self.expected
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for UnknownNameError {}
+
+// The first line of a Git LFS pointer file.
+#[cfg(feature = "fs")]
+const LFS_POINTER_START: &str = "version https://git-lfs.github.com/spec/";
+
+// FNV-1a over each word the way the loaders see it, trimmed and lowercased, followed by a newline, in the order
+// the words come. Line endings and blank lines don't change it.
+#[cfg(feature = "std")]
+fn words_fingerprint<I, S>(words: I) -> u64
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut hash = FNV_OFFSET_BASIS;
+    for word in words {
+        let word = word.as_ref().trim();
+        if word.is_empty() {
+            continue;
+        }
+        for byte in word.to_lowercase().bytes().chain(Some(b'\n')) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    hash
+}
+
+/// Choice of the collection of words to load in the letter trie.
+///
+/// Whether the words are sorted in the collection may affect the speed of loading the trie depending on the
+/// chosen LoadMethod but the resulting trie will be identical either way.
+///
+/// # Examples
+///
+/// Load a trie from a dataset, which finds the file and passes along whether it's sorted.
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap();
+/// assert_eq!(Dataset::EmbeddedSmall.word_count(), trie.to_fixed_node().word_count);
+/// ```
+///
+/// More datasets may be added without a major version, so a match on this outside the crate needs a wildcard arm.
+/// `Dataset::all()` lists the ones with files in the repository, and the `Display` and `FromStr` forms are names
+/// such as "medium-unsorted", "generated-50000-7" and "realistic-50000-7".
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Dataset {
+    /// Small file with 10 sorted English words leading to a trie with 28 nodes and a maximum height of 9.
+    TestSmallSorted,
+    /// Small file with 10 unsorted English words leading to a trie with 28 nodes and a maximum height of 9.
+    TestSmallUnsorted,
+    /// Medium file with 20,000 sorted non-English words leading to a trie with 99,851 nodes and a maximum height of 17.
+    TestMediumSorted,
+    /// Medium file with 20,000 unsorted non-English words leading to a trie with 99,851 nodes and a maximum height of
+    /// 17.
+    TestMediumUnsorted,
+    /// Large file with 400,000 sorted non-English words leading to a trie with 1,083,388 nodes and a maximum height of
+    /// 17.
+    TestLargeSorted,
+    /// Large file with 400,000 unsorted non-English words leading to a trie with 1,083,388 nodes and a maximum height
+    /// of 17.
+    TestLargeUnsorted,
+    /// Unsorted words from `generator::generate_words()` with lengths from 2 to 12 letters, for benchmarking on
+    /// any amount of data. The file is in the system temp directory, where `materialize()` writes it, and
+    /// `LetterTrie::from_dataset()` and `verify()` call that first.
+    Generated { count: usize, seed: u64 },
+    /// Unsorted words with lengths from 2 to 12 letters made up from the letter pairs of `TestMediumUnsorted` by
+    /// `TransitionModel::generate()`, so that they share prefixes the way real words do. The file is written as
+    /// for `Generated`, reading `TestMediumUnsorted` to build the model.
+    Realistic { count: usize, seed: u64 },
+    /// The same words as `TestSmallUnsorted` built into the crate, to use where the word files might not be there.
+    /// `LetterTrie::from_dataset()`, `verify()` and the conformance checks read the words from memory, and
+    /// `materialize()` writes them to the file in the system temp directory for anything that needs a file.
+    EmbeddedSmall,
+}
+
+#[cfg(feature = "std")]
+impl Dataset {
+    /// Every dataset with a file in the repository, in the order they're declared. `Dataset::Generated` and
+    /// `Dataset::Realistic` aren't included since they need a count and a seed, and neither is
+    /// `Dataset::EmbeddedSmall`, which has the same words as `Dataset::TestSmallUnsorted`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let sorted: Vec<String> = Dataset::all().iter().filter(|d| d.is_sorted()).map(|d| d.to_string()).collect();
+    /// assert_eq!(vec!["small-sorted", "medium-sorted", "large-sorted"], sorted);
+    /// ```
+    pub fn all() -> &'static [Dataset] {
+        &ALL_DATASETS
+    }
+
+    /// Get the path to a file with a set of words for testing. This only names the file. For the datasets that
+    /// aren't in the repository the file may not be there until `materialize()` is called.
+    ///
+    /// # Examples
+    ///
+    /// Get the path to a file that has 20,000 words.
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let filename: &str = Dataset::TestMediumSorted.filename();
+    /// ```
+    pub fn filename(&self) -> &str {
+        match self {
+            Dataset::TestSmallSorted => FILENAME_SMALL_SORTED,
+            Dataset::TestSmallUnsorted => FILENAME_SMALL_UNSORTED,
+            Dataset::TestMediumSorted => FILENAME_MEDIUM_SORTED,
+            Dataset::TestMediumUnsorted => FILENAME_MEDIUM_UNSORTED,
+            Dataset::TestLargeSorted => FILENAME_LARGE_SORTED,
+            Dataset::TestLargeUnsorted => FILENAME_LARGE_UNSORTED,
+            Dataset::Generated { count, seed } => {
+                generator::generated_dataset_filename(*count, *seed)
+            }
+            Dataset::Realistic { count, seed } => {
+                generator::realistic_dataset_filename(*count, *seed)
+            }
+            Dataset::EmbeddedSmall => generator::embedded_dataset_filename(),
+        }
+    }
+
+    /// Write the file for `Dataset::Generated`, `Dataset::Realistic` or `Dataset::EmbeddedSmall` to the system temp
+    /// directory so that `filename()` names a file that's there. A file that's already there, such as one from an
+    /// earlier run, is kept only if it has the dataset's words, and a new one is written under another name and
+    /// renamed into place so that no reader sees part of it. The datasets with files in the repository have nothing
+    /// to write, so for them this does nothing; `verify()` checks those.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing the file, or from reading `TestMediumUnsorted` for `Dataset::Realistic`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::Generated { count: 100, seed: 7 };
+    /// dataset.materialize()?;
+    /// assert_eq!(100, words_from_file(dataset.filename())?.len());
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn materialize(&self) -> io::Result<()> {
+        match self {
+            Dataset::Generated { count, seed } => generator::materialize_generated(*count, *seed),
+            Dataset::Realistic { count, seed } => generator::materialize_realistic(*count, *seed),
+            Dataset::EmbeddedSmall => generator::materialize_embedded(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the path to the dataset's file, which is `filename()` looked for with `data_path()` so that the
+    /// `LETTER_TRIE_DATA_DIR` environment variable is taken into account. This is the file that
+    /// `LetterTrie::from_dataset()` and `verify()` read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// assert!(Dataset::TestSmallSorted.path().ends_with(Dataset::TestSmallSorted.filename()));
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn path(&self) -> PathBuf {
+        data_path(self.filename(), None)
+    }
+
+    /// Returns true if the dataset is supposed to be already in alphabetical order at least by the first character
+    /// of each word.
+    ///
+    /// # Examples
+    ///
+    /// The large unsorted dataset isn't sorted.
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let is_sorted: bool = Dataset::TestLargeUnsorted.is_sorted();
+    /// assert_eq!(false, is_sorted);
+    /// ```
+    pub fn is_sorted(&self) -> bool {
+        match self {
+            Dataset::TestSmallSorted | Dataset::TestMediumSorted | Dataset::TestLargeSorted => true,
+            Dataset::TestSmallUnsorted
+            | Dataset::TestMediumUnsorted
+            | Dataset::TestLargeUnsorted
+            | Dataset::Generated { .. }
+            | Dataset::Realistic { .. }
+            | Dataset::EmbeddedSmall => false,
+        }
+    }
+
+    /// Get the number of words in a dataset. This is used in assertions to confirm that the various methods of
+    /// loading the words from the file really did get all of the words and properly ignored blank lines.
+    ///
+    /// This is the same as `expected_word_count()`.
+    pub fn word_count(&self) -> usize {
+        self.expected_word_count()
+    }
+
+    /// Get the number of words in the dataset's file, not counting blank lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// assert_eq!(400_000, Dataset::TestLargeSorted.expected_word_count());
+    /// ```
+    pub fn expected_word_count(&self) -> usize {
+        match self {
+            Dataset::TestSmallSorted | Dataset::TestSmallUnsorted | Dataset::EmbeddedSmall => {
+                WORD_COUNT_SMALL
+            }
+            Dataset::TestMediumSorted | Dataset::TestMediumUnsorted => WORD_COUNT_MEDIUM,
+            Dataset::TestLargeSorted | Dataset::TestLargeUnsorted => WORD_COUNT_LARGE,
+            Dataset::Generated { count, .. } | Dataset::Realistic { count, .. } => *count,
+        }
+    }
+
+    /// Get the fingerprint of the words in the dataset's file, in the order they're in the file. Each word is
+    /// trimmed and lowercased as the loaders do, so a checkout that changed the line endings still matches.
+    ///
+    /// The values for the files in the repository were worked out once and are kept here. For
+    /// `Dataset::Generated` and `Dataset::Realistic` the words are generated again to work it out, and for
+    /// `Dataset::Realistic` it's 0 if the medium dataset's file that its words are made from can't be read.
+    pub fn expected_fingerprint(&self) -> u64 {
+        match self {
+            Dataset::TestSmallSorted => FINGERPRINT_SMALL_SORTED,
+            Dataset::TestSmallUnsorted | Dataset::EmbeddedSmall => FINGERPRINT_SMALL_UNSORTED,
+            Dataset::TestMediumSorted => FINGERPRINT_MEDIUM_SORTED,
+            Dataset::TestMediumUnsorted => FINGERPRINT_MEDIUM_UNSORTED,
+            Dataset::TestLargeSorted => FINGERPRINT_LARGE_SORTED,
+            Dataset::TestLargeUnsorted => FINGERPRINT_LARGE_UNSORTED,
+            Dataset::Generated { count, seed } => {
+                words_fingerprint(generator::generated_dataset_words(*count, *seed))
+            }
+            Dataset::Realistic { count, seed } => {
+                generator::realistic_dataset_words(*count, *seed).map_or(0, words_fingerprint)
+            }
+        }
+    }
+
+    /// Check that the dataset's file has the expected number of words and fingerprint, so that a damaged or
+    /// missing file shows up as one clear error rather than as a confusing test failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DatasetError::Missing` if the file isn't there, `DatasetError::LfsPointer` if it's a Git LFS
+    /// pointer, `DatasetError::Io` if it can't be read for any other reason, and `DatasetError::WordCount` or
+    /// `DatasetError::Fingerprint` if the words aren't the expected ones.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// Dataset::EmbeddedSmall.verify().unwrap();
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn verify(&self) -> Result<(), DatasetError> {
+        match self.embedded_text() {
+            Some(text) => verify_words(
+                &self.to_string(),
+                text,
+                self.expected_word_count(),
+                self.expected_fingerprint(),
+            ),
+            None => {
+                let path = self.path();
+                self.materialize().map_err(|error| DatasetError::Io {
+                    filename: path.to_string_lossy().into_owned(),
+                    error,
+                })?;
+                verify_word_file(
+                    &path.to_string_lossy(),
+                    self.expected_word_count(),
+                    self.expected_fingerprint(),
+                )
+            }
+        }
+    }
+
+    // The words of a dataset that's built into the crate, as they'd be in its file.
+    #[cfg(feature = "fs")]
+    pub(crate) fn embedded_text(&self) -> Option<&'static str> {
+        match self {
+            Dataset::EmbeddedSmall => Some(EMBEDDED_SMALL_UNSORTED),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+const ALL_DATASETS: [Dataset; 6] = [
+    Dataset::TestSmallSorted,
+    Dataset::TestSmallUnsorted,
+    Dataset::TestMediumSorted,
+    Dataset::TestMediumUnsorted,
+    Dataset::TestLargeSorted,
+    Dataset::TestLargeUnsorted,
+];
+
+#[cfg(feature = "std")]
+impl fmt::Display for Dataset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dataset::TestSmallSorted => write!(f, "small-sorted"),
+            Dataset::TestSmallUnsorted => write!(f, "small-unsorted"),
+            Dataset::TestMediumSorted => write!(f, "medium-sorted"),
+            Dataset::TestMediumUnsorted => write!(f, "medium-unsorted"),
+            Dataset::TestLargeSorted => write!(f, "large-sorted"),
+            Dataset::TestLargeUnsorted => write!(f, "large-unsorted"),
+            Dataset::Generated { count, seed } => write!(f, "generated-{}-{}", count, seed),
+            Dataset::Realistic { count, seed } => write!(f, "realistic-{}-{}", count, seed),
+            Dataset::EmbeddedSmall => write!(f, "embedded-small"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Dataset {
+    type Err = UnknownNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let count_and_seed = |prefix: &str| {
+            s.strip_prefix(prefix)
+                .and_then(|rest| rest.split_once('-'))
+                .and_then(|(count, seed)| Some((count.parse().ok()?, seed.parse().ok()?)))
+        };
+        if let Some((count, seed)) = count_and_seed("generated-") {
+            return Ok(Dataset::Generated { count, seed });
+        }
+        if let Some((count, seed)) = count_and_seed("realistic-") {
+            return Ok(Dataset::Realistic { count, seed });
+        }
+        match s {
+            "small-sorted" => Ok(Dataset::TestSmallSorted),
+            "small-unsorted" => Ok(Dataset::TestSmallUnsorted),
+            "medium-sorted" => Ok(Dataset::TestMediumSorted),
+            "medium-unsorted" => Ok(Dataset::TestMediumUnsorted),
+            "large-sorted" => Ok(Dataset::TestLargeSorted),
+            "large-unsorted" => Ok(Dataset::TestLargeUnsorted),
+            "embedded-small" => Ok(Dataset::EmbeddedSmall),
+            _ => {
+                let names: Vec<String> = Dataset::all().iter().map(|d| d.to_string()).collect();
+                Err(UnknownNameError::new(
+                    "dataset",
+                    s,
+                    names
+                        .iter()
+                        .map(String::as_str)
+                        .chain(Some("generated-<count>-<seed>")),
+                ))
+            }
+        }
+    }
+}
+
+// The checks behind Dataset::verify() for any file.
+#[cfg(feature = "fs")]
+pub(crate) fn verify_word_file(
+    filename: &str,
+    expected_word_count: usize,
+    expected_fingerprint: u64,
+) -> Result<(), DatasetError> {
+    let io_error = |error: io::Error| match error.kind() {
+        io::ErrorKind::NotFound => DatasetError::Missing {
+            filename: filename.to_owned(),
+        },
+        _ => DatasetError::Io {
+            filename: filename.to_owned(),
+            error,
+        },
+    };
+    let bytes = std::fs::read(filename).map_err(io_error)?;
+    if bytes.starts_with(LFS_POINTER_START.as_bytes()) {
+        return Err(DatasetError::LfsPointer {
+            filename: filename.to_owned(),
+        });
+    }
+    let content = String::from_utf8(bytes)
+        .map_err(|err| io_error(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+    verify_words(
+        filename,
+        &content,
+        expected_word_count,
+        expected_fingerprint,
+    )
+}
+
+// The checks on the words themselves, from a file or built into the crate.
+#[cfg(feature = "fs")]
+fn verify_words(
+    filename: &str,
+    content: &str,
+    expected_word_count: usize,
+    expected_fingerprint: u64,
+) -> Result<(), DatasetError> {
+    let word_count = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count();
+    if word_count != expected_word_count {
+        return Err(DatasetError::WordCount {
+            filename: filename.to_owned(),
+            expected: expected_word_count,
+            found: word_count,
+        });
+    }
+    let fingerprint = words_fingerprint(content.lines());
+    if fingerprint != expected_fingerprint {
+        return Err(DatasetError::Fingerprint {
+            filename: filename.to_owned(),
+            expected: expected_fingerprint,
+            found: fingerprint,
+        });
+    }
+    Ok(())
+}
+
+/// The choice of implementation of LetterTrie.
+///
+/// More implementations may be added without a major version, so a match on this outside the crate needs a
+/// wildcard arm. `LetterTrieType::all()` lists every one, and the `Display` and `FromStr` forms are the names
+/// "base" and "no-parent".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LetterTrieType {
+    /// The baseline implementation using Rc<RefCell<Node>> for child links and Weak<RefCell<Node>> for parent links.
+    Base,
+    /// A stripped-down implementation with no parent links and with direct ownership of child nodes.
+    NoParent,
+}
+
+impl LetterTrieType {
+    /// Every implementation, in the order they're declared.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// for letter_trie_type in LetterTrieType::all() {
+    ///     let trie = build_trie(letter_trie_type, TrieSource::Words(&["cross".to_owned()]), &LoadMethod::Continuous)?;
+    ///     assert!(trie.contains("cross"), "{}", letter_trie_type);
+    /// }
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    pub fn all() -> &'static [LetterTrieType] {
+        &[LetterTrieType::Base, LetterTrieType::NoParent]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LetterTrieType::Base => "base",
+            LetterTrieType::NoParent => "no-parent",
+        }
+    }
+}
+
+impl fmt::Display for LetterTrieType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LetterTrieType {
+    type Err = UnknownNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let all = LetterTrieType::all();
+        all.iter()
+            .find(|letter_trie_type| letter_trie_type.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                UnknownNameError::new("trie type", s, all.iter().map(LetterTrieType::name))
+            })
+    }
+}
+
+/// Where `build_trie()` gets its words.
+#[derive(Clone, Copy, Debug)]
+pub enum TrieSource<'a> {
+    /// A text file with up to one word per line, loaded as in `LetterTrie::from_file()`.
+    #[cfg(feature = "fs")]
+    File { filename: &'a str, is_sorted: bool },
+    /// A list of words, added as in `LetterTrie::from_words()`.
+    Words(&'a [String]),
+}
+
+/// Build a trie of the type chosen at run time, so that the code using it doesn't need a match arm for each
+/// implementation. `load_method` is only used for `TrieSource::File`.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::File` if a file can't be read, as in `LetterTrie::from_file()`. A list of words
+/// always builds.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let words = vec!["cross".to_owned(), "crossed".to_owned(), "an".to_owned()];
+/// for letter_trie_type in &[LetterTrieType::Base, LetterTrieType::NoParent] {
+///     let mut trie: Box<dyn LetterTrie> = build_trie(letter_trie_type, TrieSource::Words(&words), &LoadMethod::Continuous)?;
+///     trie.insert("crossing");
+///     assert_eq!(vec!["cross", "crossed", "crossing"], trie.words_with_prefix("CROSS"));
+/// }
+/// # Ok::<(), LetterTrieError>(())
+/// ```
+pub fn build_trie(
+    letter_trie_type: &LetterTrieType,
+    source: TrieSource,
+    load_method: &LoadMethod,
+) -> Result<Box<dyn LetterTrie>> {
+    fn build<T: LetterTrie + 'static>(
+        source: TrieSource,
+        _load_method: &LoadMethod,
+    ) -> Result<Box<dyn LetterTrie>> {
+        Ok(match source {
+            #[cfg(feature = "fs")]
+            TrieSource::File {
+                filename,
+                is_sorted,
+            } => Box::new(T::from_file(filename, is_sorted, _load_method)?),
+            TrieSource::Words(words) => Box::new(T::from_words(words)),
+        })
+    }
+    match letter_trie_type {
+        LetterTrieType::Base => build::<BaseLetterTrie>(source, load_method),
+        LetterTrieType::NoParent => build::<NoParentLetterTrie>(source, load_method),
+    }
+}
+
+/// The method the LetterTrie will use to load words from a text file.
+///
+/// # Choosing a method
+///
+/// `Continuous` is the one to use unless there's a reason not to. In a release build on Linux loading
+/// `Dataset::TestLargeUnsorted` into a `BaseLetterTrie`, every method took about 0.4 to 0.5 seconds and finished
+/// with the process using about 255 MB, almost all of it the trie itself. `Continuous` was the fastest and held
+/// nothing besides the current line. `ReadVecFill` also held the 12 MB file, `VecFill` held 35 MB of words split
+/// into characters, and `ContinuousParallel` held the same 35 MB for its sort and used 285 MB at its peak from the
+/// tries being built in each thread, without finishing any sooner. The figures come from `run_matrix()` with
+/// `LoadStats::peak_resident_bytes()` and `LoadStats::peak_intermediate_bytes()`, and a `NoParentLetterTrie` came
+/// out the same way apart from a trie of about 440 MB.
+///
+/// More load methods may be added without a major version, so a match on this outside the crate needs a wildcard
+/// arm. `LoadMethod::all()` lists every one that can be used without setting anything up, and the `Display` and
+/// `FromStr` forms are names such as "continuous-parallel".
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LoadMethod {
+    /// Read the whole file into memory, create a vector of words, then fill the trie.
+    ReadVecFill,
+    /// Read the file into a vector in one step, then fill the trie.
+    VecFill,
+    /// Build the trie while reading lines from the file.
+    Continuous,
+    /// Build the trie by evaluating the set of words for each starting letter in its own thread.
+    ///
+    /// Read lines from the file, and as soon as all of the words for each starting letter have been read spawn affect
+    /// thread to build a trie for that starting letter while continuing to read from the file in the first thread.
+    /// As each thread finishes building its trie, merge that trie into the main trie.
+    ContinuousParallel,
+    /// Build the trie from a file too big to hold in memory alongside it.
+    ///
+    /// Read `chunk_words` words at a time, build each chunk into a small trie and save it in the binary format to
+    /// a file in `temp_dir`, then merge the files two at a time as sorted streams of words until one is left and
+    /// load that one. Besides the trie itself only one chunk is in memory at a time. The chunk files are deleted
+    /// as they're merged, and any that are left are deleted if the load fails. A `chunk_words` of 0 is taken as 1.
+    #[cfg(feature = "fs")]
+    External {
+        temp_dir: PathBuf,
+        chunk_words: usize,
+    },
+}
+
+/// Every load method, in the order they're declared. `LoadMethod::External` isn't included since it needs a
+/// directory to work in.
+pub const ALL_LOAD_METHODS: [LoadMethod; 4] = [
+    LoadMethod::ReadVecFill,
+    LoadMethod::VecFill,
+    LoadMethod::Continuous,
+    LoadMethod::ContinuousParallel,
+];
+
+/// The number of words in each chunk of a `LoadMethod::External` parsed from "external".
+#[cfg(feature = "fs")]
+pub const DEFAULT_EXTERNAL_CHUNK_WORDS: usize = 100_000;
+
+impl LoadMethod {
+    /// Every load method that can be used without setting anything up, which is `ALL_LOAD_METHODS`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let names: Vec<String> = LoadMethod::all().iter().map(|method| method.to_string()).collect();
+    /// assert_eq!(vec!["read-vec-fill", "vec-fill", "continuous", "continuous-parallel"], names);
+    /// ```
+    pub fn all() -> &'static [LoadMethod] {
+        &ALL_LOAD_METHODS
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LoadMethod::ReadVecFill => "read-vec-fill",
+            LoadMethod::VecFill => "vec-fill",
+            LoadMethod::Continuous => "continuous",
+            LoadMethod::ContinuousParallel => "continuous-parallel",
+            #[cfg(feature = "fs")]
+            LoadMethod::External { .. } => "external",
+        }
+    }
+}
+
+impl fmt::Display for LoadMethod {
+    /// `LoadMethod::External` is shown as "external" without its directory or chunk size.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LoadMethod {
+    type Err = UnknownNameError;
+
+    /// "external" is `LoadMethod::External` in the system temp directory with `DEFAULT_EXTERNAL_CHUNK_WORDS`
+    /// words to a chunk.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[cfg(feature = "fs")]
+        if s == "external" {
+            return Ok(LoadMethod::External {
+                temp_dir: env::temp_dir(),
+                chunk_words: DEFAULT_EXTERNAL_CHUNK_WORDS,
+            });
+        }
+        match s {
+            "read-vec-fill" => Ok(LoadMethod::ReadVecFill),
+            "vec-fill" => Ok(LoadMethod::VecFill),
+            "continuous" => Ok(LoadMethod::Continuous),
+            "continuous-parallel" => Ok(LoadMethod::ContinuousParallel),
+            _ => {
+                let external = if cfg!(feature = "fs") {
+                    Some("external")
+                } else {
+                    None
+                };
+                Err(UnknownNameError::new(
+                    "load method",
+                    s,
+                    LoadMethod::all()
+                        .iter()
+                        .map(LoadMethod::name)
+                        .chain(external),
+                ))
+            }
+        }
+    }
+}
+
+// Keeps the tracing span for a build open while it's in scope. Without the tracing feature there's nothing to keep.
+#[cfg(feature = "tracing")]
+pub(crate) type LoadSpanGuard = Option<tracing::span::EnteredSpan>;
+#[cfg(all(feature = "std", not(feature = "tracing")))]
+pub(crate) struct LoadSpanGuard;
+
+/// Where `DisplayDetailOptions` sends the timings and other details of a trie build.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayTarget {
+    /// Print to stdout with `println!()`.
+    Stdout,
+    /// Emit spans and events through the `tracing` facade so they can be filtered, timestamped and collected by
+    /// whatever subscriber the application has installed. Each build is an INFO span named "load" with the fields
+    /// `label`, `dataset`, `load_method` and `trie_type`. Within it each step is a DEBUG event with `step` and
+    /// `elapsed_micros` fields and the overall time is an INFO event with the same fields.
+    #[cfg(feature = "tracing")]
+    Tracing,
+}
+
+/// Options for the amount of detail to display while building a trie.
+#[cfg(feature = "std")]
+pub struct DisplayDetailOptions {
+    /// If true, print the elapsed time for the whole trie build including reading the file.
+    pub print_overall_time: bool,
+    /// If true, print the elapsed time for each step. The particular steps depend on the chosen LoadMethod.
+    pub print_step_time: bool,
+    /// The amount of debugging information to print about the trie after it's been built:
+    /// - 0: Print nothing
+    /// - 1: Print a single line for the trie, the equivalent of `println!("{:?}", trie.to_fixed_node());`.
+    /// - 2: Print a multiple lines for the trie, the equivalent of `println!("{:#?}", trie.to_fixed_node());`.
+    pub object_detail_level: usize,
+    /// The label to be displayed with any debugging information. One easy way to create this string is with a
+    /// call to `DisplayDetailOptions::get_test_label()`.
+    pub label: String,
+    /// The name of the dataset being loaded, or an empty string if the words didn't come from a `Dataset`. This
+    /// is only used as a field of the tracing span since `label` already includes it.
+    pub dataset: String,
+    /// Whether to print the details or send them to the `tracing` facade.
+    pub target: DisplayTarget,
+    /// The thousands separator for counts such as the number of words read.
+    pub number_style: NumberStyle,
+    /// If true, read the memory used by the process at the end of each step with `resident_memory_bytes()`,
+    /// keep it for `step_memory()`, and print it after each step's time. Reading it takes some microseconds, which
+    /// is counted in the next step.
+    pub sample_memory: bool,
+    /// For `LoadMethod::Continuous` and `LoadMethod::ContinuousParallel`, how many lines may be read ahead of the
+    /// build, or 0 to read and build one after the other. With read-ahead the calling thread reads the lines and a
+    /// second thread builds the trie from them, so a slow reader such as a pipe or a network stream is read while
+    /// the trie is being built. The steps are "read file" and "build from lines", each counting only the time
+    /// spent working rather than waiting on the other, so their sum is more than the overall time by however much
+    /// they overlapped. Read-ahead needs the `parallel` feature and is ignored without it.
+    pub read_ahead_lines: usize,
+    /// What every load method does with a word that's in the source more than once. Under
+    /// `DuplicatePolicy::Count` each word's tag is the number of times it was read, and under
+    /// `DuplicatePolicy::Error` the load stops with `LetterTrieError::DuplicateWord` and the line numbers of both
+    /// copies, counting blank lines. The check is made on the lines as they're read, before any threads or chunks
+    /// see them, so it's the same for every load method. It keeps each word that's been read along with its
+    /// first line, so it costs about as much memory again as `LoadMethod::VecFill` holds, and nothing under
+    /// `DuplicatePolicy::Ignore`.
+    pub duplicate_policy: DuplicatePolicy,
+    /// When `object_detail_level` is 2 or more and either of these is set, the trie is printed with
+    /// `LetterTrie::describe_tree()` down to `debug_max_depth` letters and with at most `debug_max_children`
+    /// children of each node. If both are None the output is the root and a line for each first letter as before.
+    pub debug_max_depth: Option<usize>,
+    /// See `debug_max_depth`.
+    pub debug_max_children: Option<usize>,
+    /// If set, how many words the load methods should expect, so that the buffers they read into and
+    /// `DuplicatePolicy`'s record of the words can be sized once rather than grown as the words come in. When it's
+    /// None and the words come from a file through `from_file_test()` or `from_dataset_with_options()`, the hints
+    /// are worked out from the size of the file with `CapacityHints::from_file_len()`. The nodes themselves are
+    /// allocated one at a time whatever the hints say. Hints that are far off cost memory or reallocations but
+    /// never change the trie.
+    pub capacity_hints: Option<CapacityHints>,
+    // The hints from the size of the file being loaded, which only last for that load.
+    file_capacity_hints: Mutex<Option<CapacityHints>>,
+    // Every step is timed whether or not it's displayed so that the times can be collected afterward.
+    step_times: Mutex<Vec<(String, Duration)>>,
+    step_memory: Mutex<Vec<StepMemory>>,
+    parallel_load_stats: Mutex<Option<ParallelLoadStats>>,
+}
+
+/// Estimates of how many words a load will read and how long they are on average, from
+/// `DisplayDetailOptions::capacity_hints`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityHints {
+    /// The number of words, counting any that are repeated.
+    pub word_count: usize,
+    /// The average number of bytes in a word, not counting the end of the line.
+    pub avg_word_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl CapacityHints {
+    pub fn new(word_count: usize, avg_word_len: usize) -> Self {
+        Self {
+            word_count,
+            avg_word_len,
+        }
+    }
+
+    /// Guess the hints for a word file of `len` bytes, taking the words to be about as long as those in the
+    /// large datasets. A file of longer words gets a word count that's too high, which costs some unused capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// assert_eq!(CapacityHints::new(100, 9), CapacityHints::from_file_len(1_000));
+    /// ```
+    pub fn from_file_len(len: u64) -> Self {
+        let word_count = (len / (HINT_WORD_LEN as u64 + 1)).min(usize::MAX as u64) as usize;
+        Self::new(word_count, HINT_WORD_LEN)
+    }
+
+    // The number of words to make room for. It's capped so that an absurd hint can't take up more memory than a
+    // much larger word list would need.
+    pub(crate) fn words(&self) -> usize {
+        self.word_count.min(MAX_HINTED_WORDS)
+    }
+
+    // The number of bytes of text to make room for, with the same cap.
+    pub(crate) fn bytes(&self) -> usize {
+        self.words()
+            .saturating_mul(self.avg_word_len.min(MAX_HINTED_WORD_LEN) + 1)
+    }
+}
+
+/// The memory in use at the end of one step of a trie build, from `DisplayDetailOptions::step_memory()`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StepMemory {
+    /// The step's label, the same as in `DisplayDetailOptions::step_times()`.
+    pub step: String,
+    /// The resident set size of the process when the step finished, or None if `sample_memory` was off or it
+    /// can't be read on this platform.
+    pub resident_bytes: Option<usize>,
+    /// An estimate of the bytes held by the data the step built up along the way, such as the whole file for
+    /// `LoadMethod::ReadVecFill`, or None for a step that doesn't build up anything that can be measured.
+    pub intermediate_bytes: Option<usize>,
+}
+
+#[cfg(feature = "std")]
+impl DisplayDetailOptions {
+    /// Create a set of options that display nothing while building the trie.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let display_opt: DisplayDetailOptions = DisplayDetailOptions::make_no_display();
+    /// ```
+    pub fn make_no_display() -> Self {
+        Self {
+            print_overall_time: false,
+            print_step_time: false,
+            object_detail_level: 0,
+            label: "".to_owned(),
+            dataset: "".to_owned(),
+            target: DisplayTarget::Stdout,
+            number_style: NumberStyle::Comma,
+            sample_memory: false,
+            read_ahead_lines: 0,
+            duplicate_policy: DuplicatePolicy::Ignore,
+            debug_max_depth: None,
+            debug_max_children: None,
+            capacity_hints: None,
+            file_capacity_hints: Mutex::new(None),
+            step_times: Mutex::new(vec![]),
+            step_memory: Mutex::new(vec![]),
+            parallel_load_stats: Mutex::new(None),
+        }
+    }
+
+    /// Create a set of options that display only the overall time to build the trie.
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::EmbeddedSmall;
+    /// let load_method = LoadMethod::Continuous;
+    /// let letter_trie_type = LetterTrieType::NoParent;
+    ///
+    /// let display_opt: DisplayDetailOptions = DisplayDetailOptions::make_overall_time(
+    ///     &dataset,
+    ///     &load_method,
+    ///     &letter_trie_type);
+    ///
+    /// let trie: BaseLetterTrie = BaseLetterTrie::from_dataset_with_options(
+    ///     &dataset,
+    ///     &load_method,
+    ///     &display_opt).unwrap();
+    /// ```
+    pub fn make_overall_time(
+        dataset: &Dataset,
+        load_method: &LoadMethod,
+        letter_trie_type: &LetterTrieType,
+    ) -> Self {
+        Self {
+            print_overall_time: true,
+            print_step_time: false,
+            object_detail_level: 0,
+            label: Self::get_test_label(dataset, load_method, letter_trie_type),
+            dataset: format!("{:?}", dataset),
+            target: DisplayTarget::Stdout,
+            number_style: NumberStyle::Comma,
+            sample_memory: false,
+            read_ahead_lines: 0,
+            duplicate_policy: DuplicatePolicy::Ignore,
+            debug_max_depth: None,
+            debug_max_children: None,
+            capacity_hints: None,
+            file_capacity_hints: Mutex::new(None),
+            step_times: Mutex::new(vec![]),
+            step_memory: Mutex::new(vec![]),
+            parallel_load_stats: Mutex::new(None),
+        }
+    }
+
+    /// Create a set of options that display the overall time to build the trie as well as the time and memory use
+    /// for each step.
+    ///
+    /// At the end, if the trie is small enough it will be displayed in its entirety, otherwise only the root node.
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::EmbeddedSmall;
+    /// let load_method = LoadMethod::ContinuousParallel;
+    /// let letter_trie_type = LetterTrieType::Base;
+    ///
+    /// let display_opt: DisplayDetailOptions = DisplayDetailOptions::make_moderate(
+    ///     &dataset,
+    ///     &load_method,
+    ///     &letter_trie_type);
+    ///
+    /// let trie: BaseLetterTrie = BaseLetterTrie::from_dataset_with_options(
+    ///     &dataset,
+    ///     &load_method,
+    ///     &display_opt).unwrap();
+    /// ```
+    pub fn make_moderate(
+        dataset: &Dataset,
+        load_method: &LoadMethod,
+        letter_trie_type: &LetterTrieType,
+    ) -> Self {
+        Self {
+            print_overall_time: true,
+            print_step_time: true,
+            object_detail_level: match dataset {
+                Dataset::TestSmallSorted | Dataset::TestSmallUnsorted | Dataset::EmbeddedSmall => 2,
+                _ => 1,
+            },
+            label: Self::get_test_label(dataset, load_method, letter_trie_type),
+            dataset: format!("{:?}", dataset),
+            target: DisplayTarget::Stdout,
+            number_style: NumberStyle::Comma,
+            sample_memory: true,
+            read_ahead_lines: 0,
+            duplicate_policy: DuplicatePolicy::Ignore,
+            debug_max_depth: None,
+            debug_max_children: None,
+            capacity_hints: None,
+            file_capacity_hints: Mutex::new(None),
+            step_times: Mutex::new(vec![]),
+            step_memory: Mutex::new(vec![]),
+            parallel_load_stats: Mutex::new(None),
+        }
+    }
+
+    /// Turn on reading the memory used at the end of each step, as described under `sample_memory`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let opt = DisplayDetailOptions::make_no_display().with_memory_sampling(true);
+    /// NoParentLetterTrie::from_reader_test("cross\n".as_bytes(), true, &LoadMethod::ReadVecFill, &opt, None)
+    ///     .unwrap();
+    ///
+    /// let memory = opt.step_memory();
+    /// assert_eq!("read file", memory[0].step);
+    /// // The whole file was held as one string.
+    /// assert!(memory[0].intermediate_bytes.unwrap() >= "cross\n".len());
+    /// if cfg!(target_os = "linux") {
+    ///     assert!(memory[0].resident_bytes.is_some());
+    /// }
+    /// ```
+    pub fn with_memory_sampling(mut self, sample_memory: bool) -> Self {
+        self.sample_memory = sample_memory;
+        self
+    }
+
+    /// Read up to this many lines ahead of the build in the continuous loads, as described under
+    /// `read_ahead_lines`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let opt = DisplayDetailOptions::make_no_display().with_read_ahead_lines(1_000);
+    /// let t = BaseLetterTrie::from_reader_test("cross\ncrossed\n".as_bytes(), true, &LoadMethod::Continuous, &opt, None)
+    ///     .unwrap();
+    /// assert_eq!(2, t.to_fixed_node().word_count);
+    /// ```
+    pub fn with_read_ahead_lines(mut self, read_ahead_lines: usize) -> Self {
+        self.read_ahead_lines = read_ahead_lines;
+        self
+    }
+
+    /// Choose what the load methods do with a word that's read more than once, as described under
+    /// `duplicate_policy`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let source = "cross\nan\n\nCROSS\n";
+    /// let opt = DisplayDetailOptions::make_no_display().with_duplicate_policy(DuplicatePolicy::Error);
+    /// let result = BaseLetterTrie::from_reader_test(source.as_bytes(), false, &LoadMethod::Continuous, &opt, None);
+    /// match result {
+    ///     Err(LetterTrieError::DuplicateWord { word, first_line, second_line }) => {
+    ///         assert_eq!(("cross", 1, 4), (word.as_str(), first_line, second_line));
+    ///     }
+    ///     _ => panic!("the duplicate wasn't found"),
+    /// }
+    /// ```
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Tell the load methods roughly how many words to expect and how long they are, as described under
+    /// `capacity_hints`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let opt = DisplayDetailOptions::make_no_display().with_capacity_hints(400_000, 10);
+    /// let t = NoParentLetterTrie::from_reader_test("cross\ncrossed\n".as_bytes(), true, &LoadMethod::VecFill, &opt, None)
+    ///     .unwrap();
+    /// assert_eq!(2, t.to_fixed_node().word_count);
+    /// ```
+    pub fn with_capacity_hints(mut self, word_count: usize, avg_word_len: usize) -> Self {
+        self.capacity_hints = Some(CapacityHints::new(word_count, avg_word_len));
+        self
+    }
+
+    // The hints for the current load, if there are any.
+    pub(crate) fn capacity_hints(&self) -> Option<CapacityHints> {
+        self.capacity_hints
+            .or_else(|| *lock(&self.file_capacity_hints))
+    }
+
+    // Run a load of the words in file with hints from its size unless there are hints already.
+    #[cfg(feature = "fs")]
+    fn with_file_hints<T>(&self, file: &File, load: impl FnOnce() -> T) -> T {
+        let hints = match file.metadata() {
+            Ok(metadata) if self.capacity_hints.is_none() => {
+                Some(CapacityHints::from_file_len(metadata.len()))
+            }
+            _ => None,
+        };
+        *lock(&self.file_capacity_hints) = hints;
+        let result = load();
+        *lock(&self.file_capacity_hints) = None;
+        result
+    }
+
+    /// Limit the tree printed at an `object_detail_level` of 2, as described under `debug_max_depth`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut opt = DisplayDetailOptions::make_no_display().with_debug_limits(Some(2), Some(3));
+    /// opt.object_detail_level = 2;
+    /// ```
+    pub fn with_debug_limits(
+        mut self,
+        max_depth: Option<usize>,
+        max_children: Option<usize>,
+    ) -> Self {
+        self.debug_max_depth = max_depth;
+        self.debug_max_children = max_children;
+        self
+    }
+
+    /// Send the output somewhere other than stdout.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let display_opt = DisplayDetailOptions::make_no_display().with_target(DisplayTarget::Stdout);
+    /// ```
+    pub fn with_target(mut self, target: DisplayTarget) -> Self {
+        self.target = target;
+        self
     }
 
     /// Create the label to be displayed during the trie build process.
@@ -368,19 +4204,258 @@ impl DisplayDetailOptions {
     ///     &dataset,
     ///     &load_method,
     ///     &letter_trie_type);
+    /// assert_eq!("small-sorted (10 words); continuous; base", label);
     /// ```
     pub fn get_test_label(
         dataset: &Dataset,
         load_method: &LoadMethod,
         letter_trie_type: &LetterTrieType,
     ) -> String {
-        let word_count = format!("({} words)", dataset.word_count());
         format!(
-            "{:?} {}; {:?}; {:?}",
-            dataset, word_count, load_method, letter_trie_type
+            "{} ({} words); {}; {}",
+            dataset,
+            dataset.word_count(),
+            load_method,
+            letter_trie_type
         )
-        .to_owned()
     }
+
+    /// Start the span that the rest of a build's events belong to. The span lasts until the guard is dropped.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn enter_load_span(
+        &self,
+        load_method: &LoadMethod,
+        letter_trie_type: LetterTrieType,
+    ) -> LoadSpanGuard {
+        match self.target {
+            DisplayTarget::Stdout => None,
+            DisplayTarget::Tracing => Some(
+                tracing::info_span!(
+                    "load",
+                    label = %self.label,
+                    dataset = %self.dataset,
+                    load_method = ?load_method,
+                    trie_type = ?letter_trie_type,
+                )
+                .entered(),
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn enter_load_span(
+        &self,
+        _load_method: &LoadMethod,
+        _letter_trie_type: LetterTrieType,
+    ) -> LoadSpanGuard {
+        LoadSpanGuard
+    }
+
+    /// Record the time taken by one step of a build along with the memory in use, and report them if
+    /// `print_step_time` is set. If `is_overall` is true the time is for the whole build and `print_overall_time`
+    /// is checked instead.
+    pub(crate) fn report_elapsed(
+        &self,
+        step_label: &str,
+        elapsed: Duration,
+        is_overall: bool,
+        intermediate_bytes: Option<usize>,
+    ) {
+        self.record_step_time(step_label, elapsed);
+        let memory = StepMemory {
+            step: step_label.to_owned(),
+            resident_bytes: if self.sample_memory {
+                resident_memory_bytes()
+            } else {
+                None
+            },
+            intermediate_bytes,
+        };
+        let display = if is_overall {
+            self.print_overall_time
+        } else {
+            self.print_step_time
+        };
+        let memory_column = format_memory_column(&memory);
+        lock(&self.step_memory).push(memory);
+        if !display {
+            return;
+        }
+        match self.target {
+            DisplayTarget::Stdout if memory_column.is_empty() => {
+                print_duration(&self.label, step_label, elapsed, None)
+            }
+            DisplayTarget::Stdout => println!(
+                "\n{}: {} = {}{}",
+                self.label,
+                step_label,
+                format_duration(elapsed),
+                memory_column
+            ),
+            #[cfg(feature = "tracing")]
+            DisplayTarget::Tracing => {
+                let elapsed_micros = elapsed.as_micros() as u64;
+                if is_overall {
+                    tracing::info!(step = step_label, elapsed_micros);
+                } else {
+                    tracing::debug!(step = step_label, elapsed_micros);
+                }
+            }
+        }
+    }
+
+    fn record_step_time(&self, step_label: &str, elapsed: Duration) {
+        lock(&self.step_times).push((step_label.to_owned(), elapsed));
+    }
+
+    /// Get the time taken by each step of the builds that used these options, in the order the steps finished.
+    /// The time for a whole build is the step "overall load".
+    ///
+    /// The times are recorded even if `print_step_time` and `print_overall_time` are false.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let opt = DisplayDetailOptions::make_no_display();
+    /// let words = "cross\ncrossed\n";
+    /// NoParentLetterTrie::from_reader_test(words.as_bytes(), true, &LoadMethod::VecFill, &opt, None).unwrap();
+    ///
+    /// let steps: Vec<String> = opt.step_times().into_iter().map(|(step, _)| step).collect();
+    /// assert_eq!(steps, vec!["make vector from file", "load from vector", "overall load"]);
+    /// ```
+    pub fn step_times(&self) -> Vec<(String, Duration)> {
+        lock(&self.step_times).clone()
+    }
+
+    /// Get the memory in use at the end of each step, in the same order as `step_times()`. The resident size is
+    /// only read if `sample_memory` is set, but the estimates of intermediate data are always kept.
+    pub fn step_memory(&self) -> Vec<StepMemory> {
+        lock(&self.step_memory).clone()
+    }
+
+    /// Get the breakdown of the last `LoadMethod::ContinuousParallel` build of a `BaseLetterTrie` that used these
+    /// options, or None if there hasn't been one. Like the step times it's kept whether or not it's displayed.
+    pub fn parallel_load_stats(&self) -> Option<ParallelLoadStats> {
+        lock(&self.parallel_load_stats).clone()
+    }
+
+    /// Keep the breakdown of a parallel build, and report it if `print_step_time` is set.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn report_parallel_load(&self, stats: ParallelLoadStats) {
+        if self.print_step_time {
+            match self.target {
+                DisplayTarget::Stdout => println!(
+                    "\n{}: parallel load\n{}",
+                    self.label,
+                    stats.table(self.number_style)
+                ),
+                #[cfg(feature = "tracing")]
+                DisplayTarget::Tracing => {
+                    for letter in &stats.letters {
+                        tracing::debug!(
+                            letter = %letter.letter,
+                            word_count = letter.word_count,
+                            build_micros = letter.build_time.as_micros() as u64,
+                            queue_wait_micros = letter.queue_wait.as_micros() as u64,
+                            merge_micros = letter.merge_time.as_micros() as u64,
+                        );
+                    }
+                    tracing::debug!(
+                        dispatch_micros = stats.dispatch_time.as_micros() as u64,
+                        worker_wait_micros = stats.worker_wait.as_micros() as u64,
+                        merge_micros = stats.merge_time().as_micros() as u64,
+                        "parallel load"
+                    );
+                }
+            }
+        }
+        *lock(&self.parallel_load_stats) = Some(stats);
+    }
+
+    /// Report the number of words read if `object_detail_level` is at least 1.
+    pub(crate) fn print_word_count(&self, word_count: usize) {
+        if self.object_detail_level >= 1 {
+            match self.target {
+                DisplayTarget::Stdout => println!(
+                    "\nWord count = {}",
+                    format_count_with(word_count, self.number_style)
+                ),
+                #[cfg(feature = "tracing")]
+                DisplayTarget::Tracing => tracing::debug!(word_count),
+            }
+        }
+    }
+
+    /// Describe the finished trie at the level set by `object_detail_level`.
+    // The limited tree for an object_detail_level of 2, or None if no limits are set.
+    fn debug_tree<T: LetterTrie>(&self, trie: &T) -> Option<String> {
+        if self.debug_max_depth.is_none() && self.debug_max_children.is_none() {
+            return None;
+        }
+        Some(trie.describe_tree(self.debug_max_depth, self.debug_max_children))
+    }
+
+    pub(crate) fn print_trie<T: LetterTrie>(&self, trie: &T) {
+        if self.object_detail_level == 0 {
+            return;
+        }
+        match self.target {
+            DisplayTarget::Stdout => match self.object_detail_level {
+                1 => trie.print_root(),
+                _ => {
+                    trie.print_root_alt();
+                    match self.debug_tree(trie) {
+                        Some(tree) => print!("{}", tree),
+                        None => trie.print_branches(),
+                    }
+                }
+            },
+            #[cfg(feature = "tracing")]
+            DisplayTarget::Tracing => {
+                let root = trie.to_fixed_node();
+                tracing::debug!(
+                    node_count = root.node_count,
+                    word_count = root.word_count,
+                    height = root.height,
+                );
+                if self.object_detail_level < 2 {
+                    return;
+                }
+                if let Some(tree) = self.debug_tree(trie) {
+                    for line in tree.lines() {
+                        tracing::debug!(tree_line = line);
+                    }
+                } else {
+                    for c in trie.children("") {
+                        if let Some(branch) =
+                            trie.find_with_samples(&c.to_string(), DEBUG_BRANCH_SAMPLE_COUNT)
+                        {
+                            tracing::debug!(
+                                letter = %c,
+                                word_count = branch.node.word_count,
+                                samples = ?branch.samples,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The text printed after a step's time for the memory in use, or an empty string if nothing was measured.
+#[cfg(feature = "std")]
+fn format_memory_column(memory: &StepMemory) -> String {
+    let mut column = String::new();
+    if let Some(bytes) = memory.resident_bytes {
+        column.push_str(&format!("; memory = {}", format_bytes(bytes)));
+    }
+    if let Some(bytes) = memory.intermediate_bytes {
+        column.push_str(&format!("; intermediate data = {}", format_bytes(bytes)));
+    }
+    column
 }
 
 /// A concrete copy of a real trie node with all owned data, used for testing and debugging.
@@ -391,18 +4466,14 @@ impl DisplayDetailOptions {
 /// implementations of the trie, or tries built with different approaches, ultimately resolve to the same values.
 /// # Examples
 ///
-/// In a unit test, create a large trie using a particular implementation of LetterTrie and a particular building method,
+/// In a unit test, create a trie using a particular implementation of LetterTrie and a particular building method,
 /// then confirm that the root node has all of the expected values.
 ///
 /// ```rust
 /// use letter_trie::*;
 ///
-/// let dataset = Dataset::TestLargeUnsorted;
-/// let trie: BaseLetterTrie = BaseLetterTrie::from_file(
-///     &dataset.filename(),
-///     dataset.is_sorted(),
-///     &LoadMethod::Continuous,
-/// );
+/// let dataset = Dataset::EmbeddedSmall;
+/// let trie: BaseLetterTrie = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
 ///
 /// assert_eq!(
 ///     trie.to_fixed_node(),
@@ -411,10 +4482,10 @@ impl DisplayDetailOptions {
 ///         prefix: "".to_owned(),
 ///         depth: 0,
 ///         is_word: false,
-///         child_count: 25,
-///         node_count: 1_083_388,
-///         word_count: 400_000,
-///         height: 17,
+///         child_count: 2,
+///         node_count: 28,
+///         word_count: 10,
+///         height: 9,
 ///     }
 /// );
 /// ```
@@ -425,19 +4496,13 @@ impl DisplayDetailOptions {
 /// ```rust
 /// use letter_trie::*;
 ///
-/// let dataset = Dataset::TestSmallSorted;
-/// let trie_1: BaseLetterTrie = BaseLetterTrie::from_file(
-///     &dataset.filename(),
-///     dataset.is_sorted(),
-///     &LoadMethod::ContinuousParallel,
-/// );
+/// let sorted = "a\nan\nand\nazure\ncrease\ncreative\ncreator\ncreature\ncross\ncrossed\n";
+/// let opt = DisplayDetailOptions::make_no_display();
+/// let trie_1: BaseLetterTrie =
+///     BaseLetterTrie::from_reader_test(sorted.as_bytes(), true, &LoadMethod::ContinuousParallel, &opt, None).unwrap();
 ///
-/// let dataset = Dataset::TestSmallUnsorted;
-/// let trie_2: BaseLetterTrie = BaseLetterTrie::from_file(
-///     &dataset.filename(),
-///     dataset.is_sorted(),
-///     &LoadMethod::ReadVecFill,
-/// );
+/// let dataset = Dataset::EmbeddedSmall;
+/// let trie_2: BaseLetterTrie = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::ReadVecFill).unwrap();
 ///
 /// // Confirm that the tries' root nodes are equivalent.
 /// assert_eq!(trie_1.to_fixed_node(), trie_2.to_fixed_node());
@@ -458,70 +4523,360 @@ impl DisplayDetailOptions {
 ///     assert_eq!(fixed_node_1, fixed_node_2);
 /// }
 /// ```
-#[derive(Debug, PartialEq)]
+///
+/// # Contract
+///
+/// Every implementation fills in the fields the same way, so that the same words give the same `FixedNode` for
+/// each node no matter which implementation or load method built the trie. The conformance checks in the
+/// `conformance` module compare every node against these rules. For a trie of the words "a", "ab" and "abc":
+///
+/// | Node  | `c`   | `prefix` | `depth` | `is_word` | `child_count` | `node_count` | `word_count` | `height` |
+/// |-------|-------|----------|---------|-----------|---------------|--------------|--------------|----------|
+/// | root  | `' '` | `""`     | 0       | false     | 1             | 4            | 3            | 4        |
+/// | a     | `'a'` | `"a"`    | 1       | true      | 1             | 3            | 3            | 3        |
+/// | ab    | `'b'` | `"ab"`   | 2       | true      | 1             | 2            | 2            | 2        |
+/// | abc   | `'c'` | `"abc"`  | 3       | true      | 0             | 1            | 1            | 1        |
+#[derive(Clone, Debug, PartialEq)]
 pub struct FixedNode {
+    /// The last letter of the prefix, or a space for the root, which doesn't stand for a letter.
     pub c: char,
+    /// The letters from the root down to and including this node, lowercased, or empty for the root.
     pub prefix: String,
+    /// The number of letters in the prefix, so 0 for the root.
     pub depth: usize,
+    /// Whether the prefix is a word in the trie. It's always false for the root.
     pub is_word: bool,
+    /// The number of nodes directly below this one.
     pub child_count: usize,
+    /// The number of nodes in the subtree including this one, so a node without children has 1.
     pub node_count: usize,
+    /// The number of words in the subtree including this node's own prefix if it's a word.
     pub word_count: usize,
+    /// The number of levels in the subtree counting this node's own level, so a node without children has a
+    /// height of 1 and a trie's height is one more than the length of its longest word.
     pub height: usize,
 }
 
-//
-lazy_static! {
-    static ref CHAR_GET_COUNTER: Mutex<CharGetCounter> = Mutex::new(CharGetCounter {
-        hit_count: 0,
-        miss_count: 0
-    });
+/// The tokens given to `LetterTrie::classify()` split by whether they're words in the trie. Each token is listed
+/// once for each time it was given, in the order it was given.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Classification<'a> {
+    /// The tokens that are words in the trie.
+    pub known: Vec<&'a str>,
+    /// The tokens that aren't.
+    pub unknown: Vec<&'a str>,
+}
+
+/// The result of `LetterTrie::find_with_samples()`: a node with a few of the words under it.
+#[derive(Debug, PartialEq)]
+pub struct FindResult {
+    /// The node, as `LetterTrie::find()` gives it.
+    pub node: FixedNode,
+    /// Up to the requested number of words under the node in alphabetical order, including the node's own prefix
+    /// if it's a word.
+    pub samples: Vec<String>,
+    /// The letters of the node's children in character order.
+    pub child_letters: Vec<char>,
+}
+
+/// The result of `LetterTrie::lookup()`. A word that also starts longer words is a `Word`, and its node's
+/// `child_count` says whether there's more to type. `PrefixOnly` is what a spell checker sees partway through a
+/// word: not a word yet, but some word starts this way.
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+    /// The string is a word in the trie.
+    Word(FixedNode),
+    /// The string is the start of at least one word but isn't a word itself.
+    PrefixOnly(FixedNode),
+    /// No word in the trie starts with the string.
+    NotFound,
+}
+
+impl Lookup {
+    /// Returns true for `Lookup::Word`.
+    pub fn is_word(&self) -> bool {
+        matches!(self, Lookup::Word(_))
+    }
+
+    /// The node that was found, or None for `Lookup::NotFound`.
+    pub fn node(&self) -> Option<&FixedNode> {
+        match self {
+            Lookup::Word(node) | Lookup::PrefixOnly(node) => Some(node),
+            Lookup::NotFound => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    // Each thread keeps its own counts so that the threads loading parts of a trie in parallel never wait on each
+    // other. A worker's counts are added to the loading thread's when its part is merged.
+    static CHAR_GET_COUNTS: RefCell<CharGetCounts> = const { RefCell::new(CharGetCounts::new()) };
+    static CHAR_GET_COUNTER_ENABLED: Cell<bool> = const { Cell::new(USE_CHAR_GET_COUNTER) };
 }
 
 /// A counter to keep track of the node hits and misses while building a trie from a list of words.
 /// - hit = Starting from a given node, we found a child node corresponding to the next letter of the word.
 /// - miss = We didn't find such a child node and thus created one.
 ///
-/// These results can influence how we go about speeding up the build. In the large word list with 584,983 words
-/// leading to 1,143,413 nodes we get a hit about 82% of the time.
+/// These results can influence how we go about speeding up the build. In the large word list with 400,000 words
+/// leading to 1,083,388 nodes we get a hit about 74% of the time.
+///
+/// Counting is turned on for a thread with `set_enabled()`, and the counts belong to the thread that loads the
+/// trie. The worker threads of `LoadMethod::ContinuousParallel` count on their own and hand their counts back with
+/// the part of the trie they built, so counting adds no locking to a parallel load and two loads on different
+/// threads don't mix their counts. Only `BaseLetterTrie` counts, both while it's loaded and in the lookups done by
+/// `find()`, `contains()` and `contains_prefix()`, where a miss is where the lookup stopped.
+///
+/// Each hit or miss is also counted by the depth of the node the lookup was at, so 0 for the first letter of a
+/// word, to show whether misses happen near the root or deep in the trie. See `histogram()`.
+#[cfg(feature = "std")]
 #[derive(Debug)]
-pub struct CharGetCounter {
-    hit_count: usize,
-    miss_count: usize,
+pub struct CharGetCounter;
+
+/// The deepest slot in `CharGetCounter::histogram()`, which also counts the lookups from any deeper node.
+#[cfg(feature = "std")]
+pub const CHAR_GET_COUNTER_MAX_DEPTH: usize = 64;
+
+/// The hits and misses at one depth in `CharGetCounter::histogram()`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepthCounts {
+    /// The depth of the node that a letter was looked up from, where the root is 0. The last entry of a histogram
+    /// can be `CHAR_GET_COUNTER_MAX_DEPTH`, which also holds everything deeper.
+    pub depth: usize,
+    pub hit_count: usize,
+    pub miss_count: usize,
 }
 
-impl CharGetCounter {
-    /// Set the counters to zero at the start of a trie build.
-    pub fn reset() {
-        let mut counter = CHAR_GET_COUNTER.lock().unwrap();
-        counter.hit_count = 0;
-        counter.miss_count = 0;
+// The counts kept by each thread: the totals and the hits and misses at each depth up to
+// CHAR_GET_COUNTER_MAX_DEPTH, with anything deeper in the last slot.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub(crate) struct CharGetCounts {
+    totals: CharGetCounterSnapshot,
+    by_depth: Vec<(usize, usize)>,
+}
+
+#[cfg(feature = "std")]
+impl CharGetCounts {
+    const fn new() -> Self {
+        Self {
+            totals: CharGetCounterSnapshot {
+                hit_count: 0,
+                miss_count: 0,
+            },
+            by_depth: Vec::new(),
+        }
     }
 
-    /// Record a single hit or miss.
-    pub fn record(is_hit: bool) {
-        let mut counter = CHAR_GET_COUNTER.lock().unwrap();
+    fn record_at(&mut self, depth: usize, is_hit: bool) {
+        let depth = depth.min(CHAR_GET_COUNTER_MAX_DEPTH);
+        if self.by_depth.len() <= depth {
+            self.by_depth.resize(depth + 1, (0, 0));
+        }
+        let slot = &mut self.by_depth[depth];
         if is_hit {
-            counter.hit_count += 1;
+            self.totals.hit_count += 1;
+            slot.0 += 1;
+        } else {
+            self.totals.miss_count += 1;
+            slot.1 += 1;
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn add(&mut self, other: &CharGetCounts) {
+        self.totals.add(other.totals);
+        if self.by_depth.len() < other.by_depth.len() {
+            self.by_depth.resize(other.by_depth.len(), (0, 0));
+        }
+        for (slot, (hit_count, miss_count)) in self.by_depth.iter_mut().zip(&other.by_depth) {
+            slot.0 += hit_count;
+            slot.1 += miss_count;
+        }
+    }
+}
+
+/// The hits and misses counted by `CharGetCounter` at one moment.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CharGetCounterSnapshot {
+    pub hit_count: usize,
+    pub miss_count: usize,
+}
+
+#[cfg(feature = "std")]
+impl CharGetCounterSnapshot {
+    /// The fraction of lookups that found a child node, from 0.0 to 1.0, or 0.0 if nothing was counted.
+    pub fn hit_pct(&self) -> f64 {
+        let total_count = self.hit_count + self.miss_count;
+        if total_count == 0 {
+            0.0
         } else {
-            counter.miss_count += 1;
+            self.hit_count as f64 / total_count as f64
         }
     }
 
+    #[cfg(feature = "parallel")]
+    fn add(&mut self, other: CharGetCounterSnapshot) {
+        self.hit_count += other.hit_count;
+        self.miss_count += other.miss_count;
+    }
+}
+
+#[cfg(feature = "std")]
+impl CharGetCounter {
+    /// Turn counting on or off for this thread and the worker threads of the loads started on it. It starts out
+    /// off unless `USE_CHAR_GET_COUNTER` in lib.rs is turned on.
+    pub fn set_enabled(enabled: bool) {
+        CHAR_GET_COUNTER_ENABLED.with(|cell| cell.set(enabled));
+    }
+
+    /// Returns true if counting is on for this thread.
+    pub fn is_enabled() -> bool {
+        CHAR_GET_COUNTER_ENABLED.with(Cell::get)
+    }
+
+    /// Set this thread's counts to zero at the start of a trie build.
+    pub fn reset() {
+        Self::take();
+    }
+
+    /// Record a single hit or miss. It's only in the totals, since there's no depth to put it under.
+    pub fn record(is_hit: bool) {
+        CHAR_GET_COUNTS.with(|cell| {
+            let mut counts = cell.borrow_mut();
+            if is_hit {
+                counts.totals.hit_count += 1;
+            } else {
+                counts.totals.miss_count += 1;
+            }
+        });
+    }
+
+    /// Record a single hit or miss for a letter looked up from a node at `depth`.
+    pub fn record_at(depth: usize, is_hit: bool) {
+        CHAR_GET_COUNTS.with(|cell| cell.borrow_mut().record_at(depth, is_hit));
+    }
+
+    // Take this thread's counts, leaving them at zero, such as to send them back from a worker thread.
+    pub(crate) fn take() -> CharGetCounts {
+        CHAR_GET_COUNTS.with(|cell| core::mem::take(&mut *cell.borrow_mut()))
+    }
+
+    // Add counts made elsewhere, such as on a worker thread, to this thread's counts.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn add(counts: &CharGetCounts) {
+        CHAR_GET_COUNTS.with(|cell| cell.borrow_mut().add(counts));
+    }
+
+    /// Get this thread's counts so far.
+    pub fn snapshot() -> CharGetCounterSnapshot {
+        CHAR_GET_COUNTS.with(|cell| cell.borrow().totals)
+    }
+
+    /// Get this thread's hits and misses by the depth of the node each letter was looked up from, with an entry for
+    /// every depth from 0 to the deepest one counted, or None if nothing has been counted by depth. Everything from
+    /// `CHAR_GET_COUNTER_MAX_DEPTH` down is in one last entry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed"]);
+    /// CharGetCounter::set_enabled(true);
+    /// CharGetCounter::reset();
+    /// assert!(trie.contains("cross"));
+    /// assert!(!trie.contains("cat"));
+    /// let histogram = CharGetCounter::histogram().unwrap();
+    /// assert_eq!(DepthCounts { depth: 0, hit_count: 2, miss_count: 0 }, histogram[0]);
+    /// assert_eq!(DepthCounts { depth: 1, hit_count: 1, miss_count: 1 }, histogram[1]);
+    /// assert_eq!(5, histogram.len());
+    /// CharGetCounter::set_enabled(false);
+    /// ```
+    pub fn histogram() -> Option<Vec<DepthCounts>> {
+        CHAR_GET_COUNTS.with(|cell| {
+            let counts = cell.borrow();
+            if counts.by_depth.is_empty() {
+                return None;
+            }
+            Some(
+                counts
+                    .by_depth
+                    .iter()
+                    .enumerate()
+                    .map(|(depth, (hit_count, miss_count))| DepthCounts {
+                        depth,
+                        hit_count: *hit_count,
+                        miss_count: *miss_count,
+                    })
+                    .collect(),
+            )
+        })
+    }
+
     /// View the results.
     pub fn print() {
-        let counter = CHAR_GET_COUNTER.lock().unwrap();
-        let total_count = counter.hit_count + counter.miss_count;
-        if total_count == 0 {
+        Self::print_with_style(NumberStyle::Comma);
+    }
+
+    fn print_with_style(style: NumberStyle) {
+        let counter = Self::snapshot();
+        if counter.hit_count + counter.miss_count == 0 {
             println!("CharGetCounter: nothing recorded");
         } else {
-            let hit_pct = counter.hit_count as f64 / total_count as f64;
             println!(
                 "CharGetCounter: hit count = {}; miss count = {}, hit pct = {}",
-                format_count(counter.hit_count),
-                format_count(counter.miss_count),
-                hit_pct
+                format_count_with(counter.hit_count, style),
+                format_count_with(counter.miss_count, style),
+                counter.hit_pct()
             );
+            for counts in Self::histogram().unwrap_or_default() {
+                let plus = if counts.depth == CHAR_GET_COUNTER_MAX_DEPTH {
+                    "+"
+                } else {
+                    ""
+                };
+                println!(
+                    "    depth {}{}: hit count = {}; miss count = {}",
+                    counts.depth,
+                    plus,
+                    format_count_with(counts.hit_count, style),
+                    format_count_with(counts.miss_count, style)
+                );
+            }
+        }
+    }
+
+    /// Report the results through the target chosen in `opt`, if there are any.
+    ///
+    /// With `DisplayTarget::Stdout` this is the same as `CharGetCounter::print_optional()`. With
+    /// `DisplayTarget::Tracing` it's a DEBUG event with `hit_count` and `miss_count` fields, followed by one with a
+    /// `depth` field as well for each entry of `histogram()`.
+    pub fn report(opt: &DisplayDetailOptions) {
+        match opt.target {
+            DisplayTarget::Stdout => Self::print_optional_with_style(opt.number_style),
+            #[cfg(feature = "tracing")]
+            DisplayTarget::Tracing => {
+                let counter = Self::snapshot();
+                if counter.hit_count + counter.miss_count > 0 {
+                    tracing::debug!(
+                        hit_count = counter.hit_count,
+                        miss_count = counter.miss_count,
+                        "CharGetCounter"
+                    );
+                    for counts in Self::histogram().unwrap_or_default() {
+                        tracing::debug!(
+                            depth = counts.depth,
+                            hit_count = counts.hit_count,
+                            miss_count = counts.miss_count,
+                            "CharGetCounter depth"
+                        );
+                    }
+                }
+            }
         }
     }
 
@@ -530,130 +4885,562 @@ impl CharGetCounter {
     /// This allows us to turn counting on or off for a particular build process without the calling code
     /// having to know whether it's enabled.
     pub fn print_optional() {
-        let total_count: usize;
-        {
-            // Lock the counter and get the total count in a separate scope so that the counter is unlocked
-            // before we call Self::print(). If we didn't do this, we'd still have a lock on CHAR_GET_COUNTER
-            // when calling Self::print(). That function would try to get a lock and wait forever.
-            let counter = CHAR_GET_COUNTER.lock().unwrap();
-            total_count = counter.hit_count + counter.miss_count;
+        Self::print_optional_with_style(NumberStyle::Comma);
+    }
+
+    fn print_optional_with_style(style: NumberStyle) {
+        let counter = Self::snapshot();
+        if counter.hit_count + counter.miss_count > 0 {
+            Self::print_with_style(style);
+        }
+    }
+}
+
+/// The environment variable that `data_path()` checks for the directory holding the word files when no directory
+/// is given. Without it the files are looked for in the current directory, which is the crate's own directory when
+/// its tests are run.
+#[cfg(feature = "fs")]
+pub const DATA_DIR_ENV_VAR: &str = "LETTER_TRIE_DATA_DIR";
+
+/// Get the path to one of the word files such as `Dataset::filename()` or `VerificationSet::filename()`. The file
+/// is looked for in `base_dir` if there is one, then in the directory named by the `LETTER_TRIE_DATA_DIR`
+/// environment variable if it's set, and otherwise in the current directory. A filename that's already absolute is
+/// returned as is.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::path::Path;
+/// use letter_trie::*;
+///
+/// let path = data_path("test_good_words.txt", Some(Path::new("/data/words")));
+/// assert_eq!(Path::new("/data/words/test_good_words.txt"), path);
+/// ```
+#[cfg(feature = "fs")]
+pub fn data_path(filename: &str, base_dir: Option<&Path>) -> PathBuf {
+    match base_dir {
+        Some(dir) => dir.join(filename),
+        None => match env::var_os(DATA_DIR_ENV_VAR) {
+            Some(dir) => Path::new(&dir).join(filename),
+            None => PathBuf::from(filename),
+        },
+    }
+}
+
+/// The lists of words for checking a trie built from the large dataset, `Dataset::TestLargeSorted` or
+/// `Dataset::TestLargeUnsorted`.
+#[cfg(feature = "fs")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationSet {
+    /// 1,000 words that are in the large dataset, so `LetterTrie::find()` should give a node that's a word and
+    /// `LetterTrie::contains()` should be true for each of them.
+    GoodWords,
+    /// 1,000 words that are not in the large dataset, so `LetterTrie::contains()` should be false for each of
+    /// them.
+    NonWords,
+}
+
+#[cfg(feature = "fs")]
+impl VerificationSet {
+    /// Get the name of the file with the words, which `data_path()` turns into a path.
+    pub fn filename(&self) -> &'static str {
+        match self {
+            VerificationSet::GoodWords => FILENAME_GOOD_WORDS,
+            VerificationSet::NonWords => FILENAME_NON_WORDS,
         }
-        if total_count > 0 {
-            Self::print();
+    }
+
+    /// Get the number of words in the crate's copy of the file.
+    pub fn word_count(&self) -> usize {
+        match self {
+            VerificationSet::GoodWords => WORD_COUNT_GOOD,
+            VerificationSet::NonWords => WORD_COUNT_NON,
         }
     }
 }
 
-/// For testing, create a vector of 1,000 words that are known to be in the large word list.
+/// Read one of the lists of words for checking a trie built from the large dataset, from `base_dir` or the default
+/// location as described in `data_path()`. Each line is trimmed and blank lines are skipped.
 ///
-/// The large word list is the one corresponding to Dataset::TestLargeSorted or Dataset::TestLargeUnsorted.
-/// If a trie is built correctly from one of those datasets, it should be possible to find each of these words
-/// in the trie by calling either:
-/// - LetterTrie::find() which should return Some(FixedNode).
-/// - LetterTrie::is_word() which should return true.
+/// # Errors
 ///
-/// # Panics
+/// Returns any error from opening or reading the file, including one for a line that isn't valid UTF-8.
 ///
-/// Panics if the file does not exist or can't be opened for reading.
-pub fn good_words() -> Vec<String> {
-    words_from_file_test(FILENAME_GOOD_WORDS, Some(WORD_COUNT_GOOD))
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dir = std::env::temp_dir().join(format!("letter_trie_verification_doc_{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join(VerificationSet::NonWords.filename()), "Qzx\n\n  zzyq \n").unwrap();
+/// let words = verification_words(VerificationSet::NonWords, Some(&dir)).unwrap();
+/// assert_eq!(vec!["Qzx", "zzyq"], words);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[cfg(feature = "fs")]
+pub fn verification_words(
+    kind: VerificationSet,
+    base_dir: Option<&Path>,
+) -> io::Result<Vec<String>> {
+    read_word_file(&data_path(kind.filename(), base_dir))
 }
 
-/// For testing, create a vector of 1,000 words that are known NOT to be in the large word list.
+/// Get one of the lists of words for checking a trie built from the large dataset from the copy built into the
+/// crate, which is the same as what `verification_words()` reads from the crate's own file but never touches the
+/// disk.
 ///
-/// The large word list is the one corresponding to Dataset::TestLargeSorted or Dataset::TestLargeUnsorted.
-/// If a trie is built correctly from one of those datasets, it should NOT be possible to find each of these
-/// words in the trie by calling either:
-/// - LetterTrie::find() which should return None.
-/// - LetterTrie::is_word() which should return false.
+/// # Examples
 ///
-/// # Panics
+/// ```rust
+/// use letter_trie::*;
 ///
-/// Panics if the file does not exist or can't be opened for reading.
+/// let words = verification_words_embedded(VerificationSet::GoodWords);
+/// assert_eq!(VerificationSet::GoodWords.word_count(), words.len());
+/// ```
+#[cfg(feature = "fs")]
+pub fn verification_words_embedded(kind: VerificationSet) -> Vec<String> {
+    let text = match kind {
+        VerificationSet::GoodWords => EMBEDDED_GOOD_WORDS,
+        VerificationSet::NonWords => EMBEDDED_NON_WORDS,
+    };
+    text_lines(text)
+}
+
+/// Indent `s` by four spaces for each level of `depth`.
+#[deprecated(note = "use util::format_indent(), which was only exported here by accident")]
+pub fn format_indent(depth: usize, s: &str) -> String {
+    util::format_indent(depth, s)
+}
+
+/// Print `s` indented by four spaces for each level of `depth`.
+#[cfg(feature = "std")]
+#[deprecated(note = "use util::print_indent(), which was only exported here by accident")]
+pub fn print_indent(depth: usize, s: &str) {
+    util::print_indent(depth, s)
+}
+
+/// For testing, create a vector of 1,000 words that are known to be in the large word list. They come from the
+/// copy built into the crate, so this works without the word files.
+#[cfg(feature = "fs")]
+#[deprecated(
+    note = "use verification_words_embedded(VerificationSet::GoodWords), or verification_words() to read the file"
+)]
+pub fn good_words() -> Vec<String> {
+    verification_words_embedded(VerificationSet::GoodWords)
+}
+
+/// For testing, create a vector of 1,000 words that are known NOT to be in the large word list. They come from the
+/// copy built into the crate, so this works without the word files.
+#[cfg(feature = "fs")]
+#[deprecated(
+    note = "use verification_words_embedded(VerificationSet::NonWords), or verification_words() to read the file"
+)]
 pub fn non_words() -> Vec<String> {
-    words_from_file_test(FILENAME_NON_WORDS, Some(WORD_COUNT_NON))
+    verification_words_embedded(VerificationSet::NonWords)
 }
 
-/// For testing, create a HashSet containing all of the words in the large dataset.
+/// For testing, make up to `max_count` non-words that each share every letter but the last with a word of the
+/// dataset at least `min_len` letters long. These are the worst case for a lookup that misses, since it gets all
+/// the way down the word before it finds out, unlike the `VerificationSet::NonWords` list where most words go wrong
+/// within a few letters. The words are taken at even steps through the file, and the last letter of each is moved
+/// on through the alphabet until it isn't a word.
+///
+/// # Errors
+///
+/// Returns any error from opening or reading the dataset's file.
 ///
-/// This is the list of 584,983 non-English words corresponding to Dataset::TestLargeSorted or
-/// Dataset::TestLargeUnsorted.
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let probes = shared_prefix_probes(&Dataset::EmbeddedSmall, 5, 10).unwrap();
+/// let trie = NoParentLetterTrie::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap();
+/// assert!(!probes.is_empty());
+/// for probe in &probes {
+///     assert!(!trie.contains(probe));
+///     assert!(trie.contains_prefix(&probe[..probe.len() - 1]));
+/// }
+/// ```
+#[cfg(feature = "fs")]
+pub fn shared_prefix_probes(
+    dataset: &Dataset,
+    min_len: usize,
+    max_count: usize,
+) -> io::Result<Vec<String>> {
+    let words = read_dataset_words(dataset)?;
+    let words: HashSet<String> = words.into_iter().map(|word| word.to_lowercase()).collect();
+    let mut long_words: Vec<&String> = words
+        .iter()
+        .filter(|word| word.chars().count() >= min_len)
+        .collect();
+    long_words.sort_unstable();
+    let step = (long_words.len() / max_count.max(1)).max(1);
+    let mut probes = vec![];
+    for word in long_words.into_iter().step_by(step).take(max_count) {
+        let mut chars: Vec<char> = word.chars().collect();
+        let last = chars.len() - 1;
+        let original = chars[last];
+        for offset in 0..25u8 {
+            chars[last] = match original {
+                'a'..='z' => char::from(b'a' + (original as u8 - b'a' + 1 + offset) % 26),
+                _ => char::from(b'a' + offset),
+            };
+            let probe: String = chars.iter().collect();
+            if !words.contains(&probe) {
+                probes.push(probe);
+                break;
+            }
+        }
+    }
+    Ok(probes)
+}
+
+/// For testing, create a HashSet containing all of the words in a dataset, found as described in `data_path()`.
 ///
-/// We can use this to create a baseline benchmark fer finding our 1,000 known good words and our 1,000 known
-/// non-words using only the HashSet. This is the test called "base_letter_trie::tests::bench_is_word_hash_set".
-/// We can compare the performance of the HashSet to that of searching for the same words in a BaseLetterTrie and
-/// a NoParentLetterTrie.
+/// We can use this with the large dataset to create a baseline benchmark for finding our 1,000 known good words
+/// and our 1,000 known non-words using only the HashSet. This is the test called
+/// "base_letter_trie::tests::bench_is_word_hash_set". We can compare the performance of the HashSet to that of
+/// searching for the same words in a BaseLetterTrie and a NoParentLetterTrie.
 ///
 /// This is not really a fair test because these letter tries are not intended for fast searching of whole
 /// words. If that's all we wanted to do the HashSet would work fine. Instead the idea is to be able to
 /// step letter-by-letter through the trie while following some set of possible letter sequences one letter
 /// at a time in parallel (see the comments on letter_trie::LetterTrie).
 ///
+/// If there are any duplicate words in the file the set will have fewer entries than `Dataset::word_count()`.
+///
+/// # Errors
+///
+/// Returns any error from opening or reading the dataset's file.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let hash_set = words_hash_set(&Dataset::EmbeddedSmall).unwrap();
+/// assert!(hash_set.contains("azure"));
+/// ```
+#[cfg(feature = "fs")]
+pub fn words_hash_set(dataset: &Dataset) -> io::Result<HashSet<String>> {
+    Ok(read_dataset_words(dataset)?.into_iter().collect())
+}
+
+/// For testing, create a HashSet containing all of the words in the large dataset.
+///
 /// # Panics
 ///
 /// Panics if the file for the Dataset::TestLargeSorted dataset does not exist or can't be opened for reading.
+#[cfg(feature = "fs")]
+#[deprecated(
+    note = "use words_hash_set(&Dataset::TestLargeSorted), which returns an error instead"
+)]
 pub fn large_dataset_words_hash_set() -> HashSet<String> {
-    let mut hash_set = HashSet::new();
-    for word in words_from_file_test(Dataset::TestLargeSorted.filename(), Some(WORD_COUNT_LARGE)) {
-        hash_set.insert(word);
+    words_hash_set(&Dataset::TestLargeSorted)
+        .unwrap_or_else(|err| panic!("Error reading {}: {}", FILENAME_LARGE_SORTED, err))
+}
+
+// The trimmed, nonblank lines of a word file.
+#[cfg(feature = "fs")]
+fn read_word_file(path: &Path) -> io::Result<Vec<String>> {
+    let mut words = vec![];
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() {
+            words.push(line.to_owned());
+        }
+    }
+    Ok(words)
+}
+
+// The words of a dataset as read_word_file() gives them, from memory for a dataset that's built into the crate.
+#[cfg(feature = "fs")]
+pub(crate) fn read_dataset_words(dataset: &Dataset) -> io::Result<Vec<String>> {
+    match dataset.embedded_text() {
+        Some(text) => Ok(text_lines(text)),
+        None => {
+            dataset.materialize()?;
+            read_word_file(&dataset.path())
+        }
     }
-    // We've confirmed that the number of words in the source file was equal to WORD_COUNT_LARGE. However, if there
-    // were any duplicate words in that file they won't be included in the hash set so hash_set.len() may be
-    // slightly lower than WORD_COUNT_LARGE.
-    hash_set
+}
+
+// The trimmed, nonblank lines of text built into the crate.
+#[cfg(feature = "fs")]
+fn text_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect()
 }
 
 /// Given a filename, create a Vec<Vec<char>> which is the most convenient starting point for building a trie
 /// from a list of words. This assumes that there is at most one word per line in the file.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::File` with the file's path if the file can't be opened or read.
+#[cfg(feature = "fs")]
 pub fn make_vec_char_test(
     filename: &str,
     opt: &DisplayDetailOptions,
     expected_word_count: Option<usize>,
-) -> Vec<Vec<char>> {
+) -> Result<Vec<Vec<char>>> {
+    let path = Path::new(filename);
+    let file = File::open(path).map_err(in_file(path))?;
+    make_vec_char_from_reader_test(BufReader::new(file), opt, expected_word_count)
+        .map_err(in_file(path))
+}
+
+/// Like `make_vec_char_test()` but reading the words from any buffered source rather than a named file.
+#[cfg(feature = "std")]
+pub fn make_vec_char_from_reader_test<R: BufRead>(
+    reader: R,
+    opt: &DisplayDetailOptions,
+    expected_word_count: Option<usize>,
+) -> Result<Vec<Vec<char>>> {
+    let mut timer = Timer::new(LABEL_STEP_READ_AND_VECTOR, opt);
+    let mut v: Vec<Vec<char>> =
+        Vec::with_capacity(opt.capacity_hints().map_or(0, |hints| hints.words()));
+    for_each_trimmed_line(reader, |line| v.push(lowercase_vec_chars(line)))?;
+    timer.set_intermediate_bytes(vec_char_bytes(&v));
+    timer.stop();
+    opt.print_word_count(v.len());
+
+    if let Some(exp_word_count) = expected_word_count {
+        assert_eq!(v.len(), exp_word_count);
+    }
+
+    Ok(v)
+}
+
+// Call f with each line of reader trimmed, skipping blank lines, and return how many lines were passed to f. Every
+// line is read into the same buffer rather than into a new String for each one as lines() would do. The last line
+// is passed to f the same way whether or not it ends in a newline, and blank or whitespace-only lines at the end are
+// skipped like any others, so a loader only has to finish its work when this returns.
+#[cfg(feature = "std")]
+pub(crate) fn for_each_trimmed_line<R: BufRead>(
+    mut reader: R,
+    mut f: impl FnMut(&str),
+) -> Result<usize> {
+    let mut line_count = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(line_count);
+        }
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            f(trimmed);
+            line_count += 1;
+        }
+    }
+}
+
+// The lowercase letters of a trimmed word. An ASCII word is lowercased a character at a time, so the Vec is the
+// only allocation, while anything else goes through to_lowercase() so that a final sigma comes out right.
+#[cfg(feature = "std")]
+pub(crate) fn lowercase_vec_chars(word: &str) -> Vec<char> {
+    if word.is_ascii() {
+        word.bytes()
+            .map(|b| char::from(b.to_ascii_lowercase()))
+            .collect()
+    } else {
+        word.to_lowercase().chars().collect()
+    }
+}
+
+// The groups for LetterTrie::from_letter_groups() in the order of their letters, lowercased, with any groups for
+// the same letter put together and the words trimmed and without the blank ones. The first word that doesn't
+// start with the letter of its group is an error.
+pub(crate) fn check_letter_groups<I>(groups: I) -> Result<Vec<(char, Vec<String>)>>
+where
+    I: IntoIterator<Item = (char, Vec<String>)>,
+{
+    let first_lowercase = |s: &str| s.chars().next().and_then(|c| c.to_lowercase().next());
+    let mut by_letter: BTreeMap<char, Vec<String>> = BTreeMap::new();
+    for (letter, words) in groups {
+        let lower_letter = letter.to_lowercase().next().unwrap_or(letter);
+        let group = by_letter.entry(lower_letter).or_default();
+        group.reserve(words.len());
+        for word in words {
+            let trimmed = word.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if first_lowercase(trimmed) != Some(lower_letter) {
+                return Err(LetterTrieError::MisfiledWord {
+                    letter,
+                    word: trimmed.to_owned(),
+                });
+            }
+            group.push(if trimmed.len() == word.len() {
+                word
+            } else {
+                trimmed.to_owned()
+            });
+        }
+    }
+    Ok(by_letter
+        .into_iter()
+        .filter(|(_, words)| !words.is_empty())
+        .collect())
+}
+
+// The trie types go through read-ahead in their continuous loads if it's on.
+#[cfg(feature = "parallel")]
+pub(crate) fn uses_read_ahead(load_method: &LoadMethod, opt: &DisplayDetailOptions) -> bool {
+    opt.read_ahead_lines > 0
+        && matches!(
+            load_method,
+            LoadMethod::Continuous | LoadMethod::ContinuousParallel
+        )
+}
+
+// Read the lines on this thread and build the trie from them on another, with at most opt.read_ahead_lines lines
+// waiting between the two. The build is on one thread even for LoadMethod::ContinuousParallel, since splitting the
+// words up by first letter would mean waiting for the whole file. Each side only times its own work, and the
+// overall time against the sum of the two shows how much they overlapped.
+#[cfg(feature = "parallel")]
+pub(crate) fn load_with_read_ahead<T, R>(
+    mut reader: R,
+    opt: &DisplayDetailOptions,
+    expected_word_count: Option<usize>,
+) -> Result<T>
+where
+    T: LetterTrie + Send,
+    R: BufRead,
+{
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Instant;
+
     let start = Instant::now();
-    let file = File::open(filename).unwrap();
-    let mut v: Vec<Vec<char>> = vec![];
-    for line in BufReader::new(file).lines() {
-        let line = line.unwrap();
-        let line = line.trim();
-        if !line.is_empty() {
-            let vec_char: Vec<char> = line.to_lowercase().chars().collect();
-            v.push(vec_char);
+    let (tx, rx) = mpsc::sync_channel::<String>(opt.read_ahead_lines);
+    let counting = CharGetCounter::is_enabled();
+    let (read_result, read_time, built) = thread::scope(|scope| {
+        let builder = scope.spawn(move || {
+            CharGetCounter::set_enabled(counting);
+            let mut t = T::from_words(Vec::<String>::new());
+            let mut build_time = Duration::ZERO;
+            for line in rx {
+                let step_start = Instant::now();
+                t.insert(&line);
+                build_time += step_start.elapsed();
+            }
+            (t, build_time, CharGetCounter::take())
+        });
+        let mut read_time = Duration::ZERO;
+        let read_result = send_lines(&mut reader, &tx, &mut read_time);
+        // Closing the channel lets the builder finish.
+        drop(tx);
+        (read_result, read_time, builder.join())
+    });
+    let (t, build_time, counts) = built.map_err(|_| worker_panicked())?;
+    CharGetCounter::add(&counts);
+    let word_count = read_result?;
+    opt.report_elapsed(LABEL_STEP_READ_FILE, read_time, false, None);
+    opt.report_elapsed(LABEL_STEP_BUILD_FROM_LINES, build_time, false, None);
+    opt.print_word_count(word_count);
+    if let Some(exp_word_count) = expected_word_count {
+        assert_eq!(word_count, exp_word_count);
+    }
+    if opt.print_step_time {
+        let wall = start.elapsed();
+        let step_sum = read_time + build_time;
+        match opt.target {
+            DisplayTarget::Stdout => println!(
+                "\n{}: read-ahead wall time = {}, sum of steps = {}",
+                opt.label,
+                format_duration(wall),
+                format_duration(step_sum)
+            ),
+            #[cfg(feature = "tracing")]
+            DisplayTarget::Tracing => tracing::debug!(
+                wall_micros = wall.as_micros() as u64,
+                step_sum_micros = step_sum.as_micros() as u64,
+                "read-ahead"
+            ),
         }
     }
-    print_elapsed_from_start(
-        opt.print_step_time,
-        &opt.label,
-        LABEL_STEP_READ_AND_VECTOR,
-        start,
-    );
+    Ok(t)
+}
 
-    if opt.object_detail_level >= 1 {
-        println!("\nWord count = {}", v.len());
+// Send the trimmed lines that aren't empty and return how many there were, adding the time spent reading them to
+// read_time.
+#[cfg(feature = "parallel")]
+fn send_lines<R: BufRead>(
+    reader: &mut R,
+    tx: &std::sync::mpsc::SyncSender<String>,
+    read_time: &mut Duration,
+) -> Result<usize> {
+    let mut word_count = 0;
+    let mut line = String::new();
+    loop {
+        let step_start = std::time::Instant::now();
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            *read_time += step_start.elapsed();
+            return Ok(word_count);
+        }
+        let word = line.trim();
+        let word = (!word.is_empty()).then(|| word.to_owned());
+        *read_time += step_start.elapsed();
+        if let Some(word) = word {
+            word_count += 1;
+            // The builder only stops taking lines if it panicked, which joining it reports.
+            if tx.send(word).is_err() {
+                return Ok(word_count);
+            }
+        }
     }
+}
 
-    if let Some(exp_word_count) = expected_word_count {
-        assert_eq!(v.len(), exp_word_count);
+// Group words split into characters by their first character, which is all that LoadMethod::ContinuousParallel
+// needs. Only the vector headers move, not the characters. With the rayon feature the sort is spread across a thread
+// pool.
+#[cfg(feature = "parallel")]
+pub(crate) fn sort_by_first_char(v: &mut [Vec<char>]) {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::slice::ParallelSliceMut;
+        v.par_sort_unstable_by_key(|vec_char| vec_char[0]);
     }
+    #[cfg(not(feature = "rayon"))]
+    v.sort_unstable_by_key(|vec_char| vec_char[0]);
+}
+
+// The bytes allocated for a file's contents and the words sliced out of it.
+#[cfg(feature = "std")]
+pub(crate) fn str_vec_bytes(content: &String, words: &Vec<&str>) -> usize {
+    content.capacity() + words.capacity() * core::mem::size_of::<&str>()
+}
 
-    v
+// The bytes allocated for words split into characters.
+#[cfg(feature = "std")]
+pub(crate) fn vec_char_bytes(v: &Vec<Vec<char>>) -> usize {
+    v.capacity() * core::mem::size_of::<Vec<char>>()
+        + v.iter()
+            .map(|vec_char| vec_char.capacity() * core::mem::size_of::<char>())
+            .sum::<usize>()
 }
 
 /// Confirm that a trie created from the small dataset has the right summary data no matter how the trie was built.
 ///
-/// The small datasets are Dataset::TestSmallSorted and Dataset::TestSmallUnsorted.
+/// The small datasets are Dataset::TestSmallSorted, Dataset::TestSmallUnsorted and Dataset::EmbeddedSmall.
 ///
 /// # Examples
 ///
 /// ```rust
 /// use letter_trie::*;
 ///
-/// let dataset = Dataset::TestSmallUnsorted;
-/// let t = NoParentLetterTrie::from_file(
-///     &dataset.filename(),
-///     dataset.is_sorted(),
-///     &LoadMethod::Continuous,
-/// );
+/// let dataset = Dataset::EmbeddedSmall;
+/// let t = NoParentLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
 ///
 /// assert_small_root(&t.to_fixed_node());
 /// ```
@@ -683,13 +5470,12 @@ pub fn assert_small_root(node: &FixedNode) {
 /// use letter_trie::*;
 ///
 /// let dataset = Dataset::TestLargeSorted;
-/// let t = BaseLetterTrie::from_file(
-///     &dataset.filename(),
-///     dataset.is_sorted(),
-///     &LoadMethod::ContinuousParallel,
-/// );
-///
-/// assert_large_root(&t.to_fixed_node());
+/// match BaseLetterTrie::from_dataset(&dataset, &LoadMethod::ContinuousParallel) {
+///     Ok(t) => assert_large_root(&t.to_fixed_node()),
+///     // The large word files aren't always there, such as when the crate is a Git dependency.
+///     Err(LetterTrieError::MissingDataset { .. }) => {}
+///     Err(err) => panic!("{}", err),
+/// }
 /// ```
 pub fn assert_large_root(node: &FixedNode) {
     assert_eq!(
@@ -706,3 +5492,1573 @@ pub fn assert_large_root(node: &FixedNode) {
         }
     );
 }
+
+// A path in the system temp directory for a test's file or directory, named for the tag and the process so that
+// tests running at the same time don't share files. Nothing is there to begin with, and whatever the test leaves
+// there is removed when the TempPath is dropped.
+#[cfg(all(test, feature = "std"))]
+pub(crate) fn temp_path(tag: &str) -> TempPath {
+    let path = std::env::temp_dir().join(format!("letter_trie_{}_{}", tag, std::process::id()));
+    let temp_path = TempPath(path);
+    temp_path.remove();
+    temp_path
+}
+
+#[cfg(all(test, feature = "std"))]
+#[derive(Debug)]
+pub(crate) struct TempPath(PathBuf);
+
+#[cfg(all(test, feature = "std"))]
+impl TempPath {
+    fn remove(&self) {
+        let _ = if self.0.is_dir() {
+            std::fs::remove_dir_all(&self.0)
+        } else {
+            std::fs::remove_file(&self.0)
+        };
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+impl core::ops::Deref for TempPath {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+impl AsRef<std::path::Path> for TempPath {
+    fn as_ref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::thread;
+
+    #[test]
+    fn verification_words_from_dir() {
+        let dir = temp_path("verification_words");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(FILENAME_GOOD_WORDS), "  Alpha \n\nbeta\r\n   \n").unwrap();
+        assert_eq!(
+            vec!["Alpha", "beta"],
+            verification_words(VerificationSet::GoodWords, Some(&dir)).unwrap()
+        );
+
+        let err = verification_words(VerificationSet::NonWords, Some(&dir)).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+
+        fs::write(dir.join(FILENAME_NON_WORDS), b"good\n\xff\xfe\n").unwrap();
+        let err = verification_words(VerificationSet::NonWords, Some(&dir)).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn crate_verification_words() {
+        for kind in &[VerificationSet::GoodWords, VerificationSet::NonWords] {
+            let words = verification_words(*kind, None).unwrap();
+            assert_eq!(kind.word_count(), words.len());
+            assert_eq!(words, verification_words_embedded(*kind));
+            #[allow(deprecated)]
+            let old = match kind {
+                VerificationSet::GoodWords => good_words(),
+                VerificationSet::NonWords => non_words(),
+            };
+            assert_eq!(words, old);
+        }
+    }
+
+    #[test]
+    fn hash_set_for_dataset() {
+        let hash_set = words_hash_set(&Dataset::TestSmallUnsorted).unwrap();
+        assert_eq!(Dataset::TestSmallUnsorted.word_count(), hash_set.len());
+        assert!(hash_set.contains("creature"));
+
+        // The generated datasets live in the temp directory and have absolute paths.
+        let dataset = Dataset::Generated { count: 50, seed: 7 };
+        assert!(Path::new(dataset.filename()).is_absolute());
+        // Reading the words writes the file first.
+        let hash_set = words_hash_set(&dataset).unwrap();
+        assert!(!hash_set.is_empty() && hash_set.len() <= 50);
+    }
+
+    #[test]
+    fn data_paths() {
+        let dir = temp_path("data_paths");
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(dir.join("words.txt"), data_path("words.txt", Some(&dir)));
+        let absolute = dir.join("other.txt");
+        assert_eq!(
+            absolute,
+            data_path(absolute.to_str().unwrap(), Some(Path::new("elsewhere")))
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn sort_medium_by_first_char() {
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let mut v: Vec<Vec<char>> = words.iter().map(|word| word.chars().collect()).collect();
+        sort_by_first_char(&mut v);
+        assert!(v.windows(2).all(|pair| pair[0][0] <= pair[1][0]));
+        let mut sorted: Vec<String> = v.iter().map(|vec_char| vec_char.iter().collect()).collect();
+        sorted.sort_unstable();
+        let mut expected = words.clone();
+        expected.sort_unstable();
+        assert_eq!(expected, sorted);
+    }
+
+    // Each word is seen with exactly its own letters, so the buffer is cut back properly when the walk goes up from
+    // a long word to a shorter one.
+    fn check_for_each_word<T: LetterTrie>() {
+        let words = vec!["crossed", "crow", "c", "an", "anteater", "ant", "b"];
+        let t = T::from_words(&words);
+        let mut seen: Vec<String> = vec![];
+        t.for_each_word(|word| {
+            assert!(t.contains(word), "{:?}", word);
+            seen.push(word.to_owned());
+        });
+        assert_eq!(
+            vec!["an", "ant", "anteater", "b", "c", "crossed", "crow"],
+            seen
+        );
+
+        seen.clear();
+        t.for_each_word_with_prefix("cro", |word| seen.push(word.to_owned()));
+        assert_eq!(vec!["crossed", "crow"], seen);
+        t.for_each_word_with_prefix("q", |_| panic!());
+
+        let t = T::from_words(words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap());
+        let mut count = 0;
+        t.for_each_word_with_prefix("ba", |word| {
+            assert!(word.starts_with("ba"));
+            count += 1;
+        });
+        assert_eq!(t.words_with_prefix("ba").len(), count);
+    }
+
+    #[test]
+    fn for_each_word() {
+        check_for_each_word::<BaseLetterTrie>();
+        check_for_each_word::<NoParentLetterTrie>();
+    }
+
+    fn check_classify<T: LetterTrie>() {
+        let t = T::from_words(words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap());
+        let mut tokens: Vec<String> = vec![];
+        for word in t.words().step_by(50) {
+            tokens.push(word.to_uppercase());
+            tokens.push(format!(" {}", word));
+            tokens.push(format!("{}qq", word));
+            tokens.push(word);
+        }
+        tokens.push(String::new());
+        let classification = t.classify(&tokens);
+        let known: Vec<&str> = tokens
+            .iter()
+            .map(String::as_str)
+            .filter(|token| t.contains(token.trim()) && !token.trim().is_empty())
+            .collect();
+        let unknown: Vec<&str> = tokens
+            .iter()
+            .map(String::as_str)
+            .filter(|token| !known.contains(token))
+            .collect();
+        assert_eq!(known, classification.known);
+        assert_eq!(unknown, classification.unknown);
+        assert_eq!(tokens.len() / 4 * 3, known.len());
+        // Each word is on the same side however it's written.
+        for chunk in tokens.chunks(4).take(tokens.len() / 4) {
+            assert!(chunk[..2]
+                .iter()
+                .chain(&chunk[3..])
+                .all(|token| classification.known.contains(&token.as_str())));
+        }
+        assert!(T::from_words(Vec::<String>::new())
+            .classify(&tokens)
+            .known
+            .is_empty());
+    }
+
+    #[test]
+    fn classify() {
+        check_classify::<BaseLetterTrie>();
+        check_classify::<NoParentLetterTrie>();
+    }
+
+    // A query trims what it's given the same way insert() does, whether it's answered from the nodes or from the
+    // tables of tags, sources, spellings, scores and ranks.
+    fn check_queries_trim<T: LetterTrie>() {
+        let mut t = T::from_words(vec!["cross", "crossed", "an"]);
+        t.insert_with_tag("cross", 7);
+        t.insert_with_source("cross", 2);
+        t.insert_with_spelling("cross", "Cross", CasePreference::FirstSeen);
+        t.set_score("cross", 0.5);
+        t.set_rank("cross", 3);
+        for padded in &[" cross", "cross ", "\tCROSS\n"] {
+            assert!(t.contains(padded), "{:?}", padded);
+            assert!(t.contains_prefix(padded));
+            assert!(t.lookup(padded).is_word());
+            assert_eq!(t.find("cross"), t.find(padded));
+            assert_eq!(t.children("cross"), t.children(padded));
+            assert_eq!(t.next_letters("cross"), t.next_letters(padded));
+            assert_eq!(t.words_with_prefix("cross"), t.words_with_prefix(padded));
+            assert_eq!(2, t.count_with_prefix(padded));
+            assert_eq!(t.suggest("cross", 5), t.suggest(padded, 5));
+            assert_eq!(t.neighbors("cross", 1, 1), t.neighbors(padded, 1, 1));
+            assert_eq!(t.find_hamming("cross", 1), t.find_hamming(padded, 1));
+            assert_eq!(vec!["crossed"], t.completions_of_exact_length(padded, 7));
+            assert!(t.contains_with_metrics(padded).0);
+            assert_eq!(t.find("cross"), t.find_with_metrics(padded).0);
+            assert_eq!(Some(7), t.tag(padded));
+            assert_eq!(Some(&[2][..]), t.sources(padded));
+            assert_eq!(Some("Cross"), t.spelling(padded));
+            assert_eq!(Some(0.5), t.score(padded));
+            assert_eq!(Some(3), t.rank_of(padded));
+        }
+        assert_eq!(Lookup::PrefixOnly(t.to_fixed_node()), t.lookup("  "));
+        assert!(!t.contains("cr oss"));
+    }
+
+    #[test]
+    fn queries_trim() {
+        check_queries_trim::<BaseLetterTrie>();
+        check_queries_trim::<NoParentLetterTrie>();
+    }
+
+    // Names and other words whose case matters, with some of them given again in another case.
+    const MIXED_CASE: [&str; 9] = [
+        "MacDonald",
+        "iPhone",
+        "macdonald",
+        " NASA ",
+        "Nasal",
+        "apple",
+        "MACDONALD",
+        "nasa",
+        "Apple",
+    ];
+
+    fn mixed_case<T: LetterTrie>(preference: CasePreference) -> T {
+        let mut t = T::from_words(vec!["mac"]);
+        for word in &MIXED_CASE {
+            t.insert_preserving_case(word, preference);
+        }
+        t
+    }
+
+    fn check_preserved_case<T: LetterTrie>() {
+        let t = mixed_case::<T>(CasePreference::FirstSeen);
+        for query in &[
+            "macdonald",
+            "MACDONALD",
+            "mAcDoNaLd",
+            "iphone",
+            "IPHONE",
+            "Nasa",
+        ] {
+            assert!(t.contains(query), "{:?}", query);
+        }
+        assert_eq!(Some("MacDonald"), t.spelling("MACDONALD"));
+        assert_eq!(None, t.spelling("mac"));
+        assert_eq!(None, t.spelling("macdonal"));
+        let first_seen = vec!["apple", "iPhone", "mac", "MacDonald", "NASA", "Nasal"];
+        assert_eq!(first_seen, t.words().collect::<Vec<String>>());
+        assert_eq!(vec!["mac", "MacDonald"], t.words_with_prefix("MAC"));
+        assert_eq!(vec!["NASA", "Nasal"], t.suggest("na", 5));
+        assert_eq!(vec!["mac"], t.suggest("m", 1));
+        let mut seen: Vec<String> = vec![];
+        t.for_each_word(|word| seen.push(word.to_owned()));
+        assert_eq!(first_seen, seen);
+        assert_eq!(6, t.count_with_prefix(""));
+
+        let t = mixed_case::<T>(CasePreference::LastSeen);
+        assert_eq!(
+            vec!["Apple", "iPhone", "mac", "MACDONALD", "nasa", "Nasal"],
+            t.words().collect::<Vec<String>>()
+        );
+        // The spelling that sorts first is the same whatever order the words came in.
+        let sorts_first = vec!["Apple", "iPhone", "mac", "MACDONALD", "NASA", "Nasal"];
+        let t = mixed_case::<T>(CasePreference::SortsFirst);
+        assert_eq!(sorts_first, t.words().collect::<Vec<String>>());
+        let mut reversed = T::from_words(vec!["mac"]);
+        for word in MIXED_CASE.iter().rev() {
+            reversed.insert_preserving_case(word, CasePreference::SortsFirst);
+        }
+        assert_eq!(sorts_first, reversed.words().collect::<Vec<String>>());
+
+        // Adding a word again without a spelling or with a tag keeps the spelling, and removing it drops it.
+        let mut t = mixed_case::<T>(CasePreference::FirstSeen);
+        t.insert("macdonald");
+        t.insert_with_tag("NASAL", 3);
+        assert_eq!(Some("MacDonald"), t.spelling("macdonald"));
+        assert_eq!(
+            Some(&("Nasal".to_owned(), Some(3))),
+            t.words_with_tags().collect::<Vec<_>>().last()
+        );
+        assert!(t.remove("MACDONALD"));
+        assert_eq!(None, t.spelling("macdonald"));
+        t.insert("macdonald");
+        assert_eq!(vec!["mac", "macdonald"], t.words_with_prefix("mac"));
+
+        // The spellings come along when the words are copied into another trie.
+        let t = mixed_case::<T>(CasePreference::FirstSeen);
+        let words: Vec<SourcedWord> = t.words_with_sources().collect();
+        assert_eq!("macdonald", words[3].word);
+        assert_eq!(Some("MacDonald"), words[3].spelling.as_deref());
+        assert_eq!(None, words[2].spelling);
+        let copy = T::from_sourced_words(words);
+        assert_eq!(first_seen, copy.words().collect::<Vec<String>>());
+        let mut merged = T::from_words(vec!["macdonald"]);
+        merged.merge_tagged(t, 0);
+        assert_eq!(first_seen, merged.words().collect::<Vec<String>>());
+    }
+
+    #[test]
+    fn preserved_case() {
+        check_preserved_case::<BaseLetterTrie>();
+        check_preserved_case::<NoParentLetterTrie>();
+    }
+
+    // Compare the cached lengths with a walk through the words, for assorted prefixes and lengths and again after
+    // removing the longest words under some of the prefixes.
+    fn check_completion_lengths<T: LetterTrie>() {
+        let mut t = T::from_words(words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap());
+        let prefixes = ["", "a", "ba", "cre", "zz", "q", "str", "un"];
+        let check = |t: &T| {
+            for prefix in &prefixes {
+                let words = t.words_with_prefix(prefix);
+                for len in 0..20 {
+                    assert_eq!(
+                        words.iter().any(|word| word.chars().count() >= len),
+                        t.has_completion_of_length(prefix, len),
+                        "{:?} {}",
+                        prefix,
+                        len
+                    );
+                    let exact: Vec<String> = words
+                        .iter()
+                        .filter(|word| word.chars().count() == len)
+                        .cloned()
+                        .collect();
+                    assert_eq!(exact, t.completions_of_exact_length(prefix, len));
+                }
+            }
+        };
+        check(&t);
+        for prefix in &prefixes[..5] {
+            for _ in 0..3 {
+                let longest = t
+                    .words_with_prefix(prefix)
+                    .into_iter()
+                    .max_by_key(|word| word.chars().count());
+                if let Some(longest) = longest {
+                    assert!(t.remove(&longest));
+                }
+            }
+        }
+        check(&t);
+
+        // A word that's a prefix of a longer one keeps its node when the longer one goes.
+        let mut t = T::from_words(vec!["cross", "crossed"]);
+        assert!(t.remove("cross"));
+        assert!(t.has_completion_of_length("cross", 7));
+        assert!(t.completions_of_exact_length("cro", 5).is_empty());
+        t.insert("cross");
+        assert!(t.has_completion_of_length("cro", 7));
+        assert!(t.remove("crossed"));
+        assert!(!t.has_completion_of_length("cro", 6));
+        assert!(t.has_completion_of_length("cro", 5));
+        assert!(t.remove("cross"));
+        assert!(!t.has_completion_of_length("", 0));
+        assert!(t.completions_of_exact_length("", 0).is_empty());
+    }
+
+    #[test]
+    fn completion_lengths() {
+        check_completion_lengths::<BaseLetterTrie>();
+        check_completion_lengths::<NoParentLetterTrie>();
+    }
+
+    // The parallel loads merge parts of the trie built on other threads, which have to bring their lengths along.
+    #[test]
+    fn completion_lengths_after_merge() {
+        for dataset in &[Dataset::TestMediumSorted, Dataset::TestMediumUnsorted] {
+            let t = BaseLetterTrie::from_dataset(dataset, &LoadMethod::ContinuousParallel).unwrap();
+            let longest = t.words().map(|word| word.chars().count()).max().unwrap();
+            assert!(t.has_completion_of_length("", longest));
+            assert!(!t.has_completion_of_length("", longest + 1));
+        }
+    }
+
+    // Compare the matches with a check of every word of the same length, for words in the trie, the same words
+    // with a letter changed and words that aren't close to anything.
+    fn check_hamming<T: LetterTrie>() {
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let t = T::from_words(&words);
+        let stored: Vec<String> = t.words().collect();
+        let brute_force = |probe: &str, k: usize| -> Vec<String> {
+            let probe: Vec<char> = probe.to_lowercase().chars().collect();
+            stored
+                .iter()
+                .filter(|word| {
+                    let chars: Vec<char> = word.chars().collect();
+                    chars.len() == probe.len()
+                        && chars.iter().zip(&probe).filter(|(a, b)| a != b).count() <= k
+                })
+                .cloned()
+                .collect()
+        };
+        let mut probes: Vec<String> = words.iter().step_by(1_009).cloned().collect();
+        probes.extend(words.iter().step_by(997).map(|word| {
+            let mut chars: Vec<char> = word.chars().collect();
+            let middle = chars.len() / 2;
+            chars[middle] = if chars[middle] == 'q' { 'z' } else { 'q' };
+            chars.into_iter().collect::<String>()
+        }));
+        probes.extend(vec!["Qzxqzx".to_owned(), "a".to_owned(), "".to_owned()]);
+        for probe in &probes {
+            for k in 0..=3 {
+                assert_eq!(
+                    brute_force(probe, k),
+                    t.find_hamming(probe, k),
+                    "{:?} {}",
+                    probe,
+                    k
+                );
+            }
+            let everything = brute_force(probe, usize::MAX);
+            assert_eq!(everything, t.find_hamming(probe, probe.chars().count()));
+            assert_eq!(everything, t.find_hamming(probe, usize::MAX));
+            let itself = t.find_hamming(probe, 0);
+            assert_eq!(t.contains(probe), itself.len() == 1, "{:?}", probe);
+        }
+        assert_eq!(
+            vec!["crossed"],
+            T::from_words(vec!["crossed"]).find_hamming("CROSSED", 0)
+        );
+        assert!(T::from_words(Vec::<String>::new())
+            .find_hamming("an", 2)
+            .is_empty());
+    }
+
+    #[test]
+    fn hamming() {
+        check_hamming::<BaseLetterTrie>();
+        check_hamming::<NoParentLetterTrie>();
+    }
+
+    // Change the trie at every step of each of the word iterators, which go on giving the words as they were.
+    fn check_mutation_during_iteration<T: LetterTrie>() {
+        let mut t = T::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap();
+        t.insert_with_tag("cross", 3);
+        t.insert_with_source("an", 1);
+        let before: Vec<String> = t.words().collect();
+        let before_tags: Vec<(String, Option<u64>)> = t.words_with_tags().collect();
+        let before_sources: Vec<SourcedWord> = t.words_with_sources().collect();
+
+        let mut words = t.words();
+        let mut tags = t.words_with_tags();
+        let mut sources = t.words_with_sources();
+        let mut seen = vec![];
+        let mut seen_tags = vec![];
+        let mut seen_sources = vec![];
+        while let Some(word) = words.next() {
+            seen_tags.push(tags.next().unwrap());
+            seen_sources.push(sources.next().unwrap());
+            assert!(t.remove(&word), "{}", word);
+            t.insert_with_tag(&format!("{}zz", word), 9);
+            t.insert_with_source("cross", 2);
+            assert_eq!(before.len() - seen.len() - 1, words.len());
+            seen.push(word);
+        }
+        assert_eq!(None, tags.next());
+        assert_eq!(None, sources.next());
+        assert_eq!(before, seen);
+        assert_eq!(before_tags, seen_tags);
+        assert_eq!(before_sources, seen_sources);
+        t.verify_integrity().unwrap();
+        let after: Vec<String> = t.words().collect();
+        assert_eq!(before.len() + 1, after.len());
+        assert!(after
+            .iter()
+            .all(|word| word.ends_with("zz") || word == "cross"));
+
+        // Dropping an iterator partway through leaves nothing behind.
+        let mut words = t.words_with_sources();
+        words.next();
+        for word in &after {
+            t.remove(word);
+        }
+        drop(words);
+        t.verify_integrity().unwrap();
+        assert_eq!(0, t.words().len());
+    }
+
+    #[test]
+    fn mutation_during_iteration() {
+        check_mutation_during_iteration::<BaseLetterTrie>();
+        check_mutation_during_iteration::<NoParentLetterTrie>();
+    }
+
+    // The longest prefix shared by the words found by brute force, or None if there are none.
+    fn brute_force_common_prefix(words: &[String]) -> Option<String> {
+        let (first, rest) = words.split_first()?;
+        let mut common: Vec<char> = first.chars().collect();
+        for word in rest {
+            let shared = common
+                .iter()
+                .zip(word.chars())
+                .take_while(|(a, b)| **a == *b)
+                .count();
+            common.truncate(shared);
+        }
+        Some(common.into_iter().collect())
+    }
+
+    // Random runs of nearby words from the sorted medium dataset, so that they often share long prefixes, checked
+    // under the empty prefix and under every prefix of each word in the run.
+    fn check_longest_common_prefix<T: LetterTrie>() {
+        let all = words_from_file(Dataset::TestMediumSorted.filename()).unwrap();
+        let mut rng = generator::SplitMix64(17);
+        for run in 0..200 {
+            let span = [1, 2, 3, 8, 40, 500][run % 6];
+            let start = rng.below((all.len() - span) as u64) as usize;
+            let words: Vec<String> = all[start..start + span]
+                .iter()
+                .filter(|_| span == 1 || rng.below(3) > 0)
+                .cloned()
+                .collect();
+            let t = T::from_words(&words);
+            assert_eq!(
+                brute_force_common_prefix(&words).unwrap_or_default(),
+                t.longest_common_prefix()
+            );
+            for word in words.iter().take(3) {
+                let prefixes = word
+                    .char_indices()
+                    .map(|(i, _)| &word[..i])
+                    .chain(Some(word.as_str()));
+                for prefix in prefixes {
+                    let under: Vec<String> = words
+                        .iter()
+                        .filter(|word| word.starts_with(prefix))
+                        .cloned()
+                        .collect();
+                    assert_eq!(
+                        brute_force_common_prefix(&under),
+                        t.longest_common_prefix_under(prefix),
+                        "{:?} in {:?}",
+                        prefix,
+                        words
+                    );
+                }
+            }
+            assert_eq!(None, t.longest_common_prefix_under("qqx"));
+        }
+
+        assert_eq!(
+            "",
+            T::from_words(Vec::<&str>::new()).longest_common_prefix()
+        );
+        assert_eq!(
+            None,
+            T::from_words(Vec::<&str>::new()).longest_common_prefix_under("a")
+        );
+        assert_eq!(
+            "crossing",
+            T::from_words(vec!["Crossing"]).longest_common_prefix()
+        );
+        // A word that every other word starts with ends the common prefix, and so does a branch.
+        let t = T::from_words(vec!["an", "and", "andes"]);
+        assert_eq!("an", t.longest_common_prefix());
+        assert_eq!(Some("and".to_owned()), t.longest_common_prefix_under("AND"));
+        assert_eq!(
+            Some("andes".to_owned()),
+            t.longest_common_prefix_under("ande")
+        );
+        let t = T::from_words(vec!["crease", "creature", "creator"]);
+        assert_eq!("crea", t.longest_common_prefix());
+        assert_eq!(
+            Some("creat".to_owned()),
+            t.longest_common_prefix_under("creat")
+        );
+    }
+
+    #[test]
+    fn longest_common_prefix() {
+        check_longest_common_prefix::<BaseLetterTrie>();
+        check_longest_common_prefix::<NoParentLetterTrie>();
+    }
+
+    fn check_medium_samples<T: LetterTrie>() {
+        let t = T::from_words(words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap());
+        let root = t.find_with_samples("", 4).unwrap();
+        assert_eq!(t.to_fixed_node(), root.node);
+        assert_eq!(t.children(""), root.child_letters);
+        let mut prefixes: Vec<String> = root.child_letters.iter().map(char::to_string).collect();
+        prefixes.extend(
+            ["", "co", "sta", "Un"]
+                .iter()
+                .map(|prefix| (*prefix).to_owned()),
+        );
+        for prefix in &prefixes {
+            let all = t.words_with_prefix(prefix);
+            for sample_count in [0, 1, 5, all.len() + 10] {
+                let result = t.find_with_samples(prefix, sample_count).unwrap();
+                assert_eq!(&all[..sample_count.min(all.len())], &result.samples[..]);
+                assert_eq!(all.len(), result.node.word_count);
+                assert_eq!(t.children(prefix), result.child_letters);
+                if !prefix.is_empty() {
+                    assert_eq!(t.find(prefix), Some(result.node));
+                }
+            }
+        }
+        assert_eq!(None, t.find_with_samples("qqq", 3));
+    }
+
+    #[test]
+    fn medium_samples() {
+        check_medium_samples::<BaseLetterTrie>();
+        check_medium_samples::<NoParentLetterTrie>();
+    }
+
+    // Every method that a trait object has, called through one and checked against the concrete trie.
+    fn check_trait_object<T: LetterTrie>(letter_trie_type: &LetterTrieType) {
+        let dataset = Dataset::TestSmallUnsorted;
+        let words = words_from_file(dataset.filename()).unwrap();
+        let concrete = T::from_words(&words);
+        let from_file = build_trie(
+            letter_trie_type,
+            TrieSource::File {
+                filename: dataset.filename(),
+                is_sorted: dataset.is_sorted(),
+            },
+            &LoadMethod::ReadVecFill,
+        )
+        .unwrap();
+        let mut t: Box<dyn LetterTrie> = build_trie(
+            letter_trie_type,
+            TrieSource::Words(&words),
+            &LoadMethod::Continuous,
+        )
+        .unwrap();
+        assert!(concrete.diff(&*from_file).is_empty());
+        assert_eq!(concrete.to_fixed_node(), t.to_fixed_node());
+        for prefix in &["", "a", "Cre", "creature", "cx"] {
+            assert_eq!(concrete.find(prefix), t.find(prefix));
+            assert_eq!(concrete.lookup(prefix), t.lookup(prefix));
+            assert_eq!(
+                concrete.find_with_samples(prefix, 2),
+                t.find_with_samples(prefix, 2)
+            );
+            assert_eq!(concrete.children(prefix), t.children(prefix));
+            assert_eq!(concrete.next_letters(prefix), t.next_letters(prefix));
+            assert_eq!(concrete.contains(prefix), t.contains(prefix));
+            assert_eq!(concrete.contains_prefix(prefix), t.contains_prefix(prefix));
+            assert_eq!(
+                concrete.words_with_prefix(prefix),
+                t.words_with_prefix(prefix)
+            );
+            assert_eq!(
+                concrete.count_with_prefix(prefix),
+                t.count_with_prefix(prefix)
+            );
+            assert_eq!(concrete.suggest(prefix, 3), t.suggest(prefix, 3));
+        }
+        let mut visited = vec![];
+        t.visit("cr", &mut |node| {
+            visited.push(node.prefix.to_owned());
+            node.depth < 3
+        });
+        assert_eq!(vec!["cr", "cre", "cro"], visited);
+        assert_eq!(
+            concrete.words().collect::<Vec<_>>(),
+            t.words().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            concrete.words_for_digits("2739"),
+            t.words_for_digits("2739")
+        );
+        assert_eq!(
+            concrete.completions_for_digits("27", 3),
+            t.completions_for_digits("27", 3)
+        );
+        assert_eq!(concrete.letter_frequencies(), t.letter_frequencies());
+        assert_eq!(
+            concrete.letter_frequencies_by_word(),
+            t.letter_frequencies_by_word()
+        );
+        assert_eq!(
+            concrete.positional_frequencies(5),
+            t.positional_frequencies(5)
+        );
+        assert_eq!(
+            concrete.best_guess("cre?????", ""),
+            t.best_guess("cre?????", "")
+        );
+        assert_eq!(concrete.fingerprint(), t.fingerprint());
+        assert!(t.verify_integrity().is_ok());
+        assert_eq!(
+            concrete.compile_matcher().word_count(),
+            t.compile_matcher().word_count()
+        );
+        #[cfg(feature = "regex")]
+        assert_eq!(
+            vec!["crease", "creative", "creature"],
+            t.words_matching(&regex::Regex::new("^cre.*e$").unwrap(), 10)
+        );
+
+        t.insert("Crossing");
+        assert!(t.remove("an"));
+        assert!(!t.remove("an"));
+        t.insert_with_tag("azure", 7);
+        assert_eq!(Some(7), t.tag("AZURE"));
+        assert_eq!(
+            Some(("azure".to_owned(), Some(7))),
+            t.words_with_tags().find(|(word, _)| word == "azure")
+        );
+        let diff = concrete.diff(&*t);
+        assert_eq!(vec!["crossing"], diff.added);
+        assert_eq!(vec!["an"], diff.removed);
+        let mut merged = T::from_words(vec!["zebra"]);
+        merged.merge_from(&*t);
+        assert_eq!(t.count_with_prefix("") + 1, merged.count_with_prefix(""));
+    }
+
+    #[test]
+    fn trait_objects() {
+        check_trait_object::<BaseLetterTrie>(&LetterTrieType::Base);
+        check_trait_object::<NoParentLetterTrie>(&LetterTrieType::NoParent);
+        let mut tries: Vec<Box<dyn LetterTrie>> = [LetterTrieType::Base, LetterTrieType::NoParent]
+            .iter()
+            .map(|letter_trie_type| {
+                build_trie(
+                    letter_trie_type,
+                    TrieSource::Words(&[]),
+                    &LoadMethod::Continuous,
+                )
+                .unwrap()
+            })
+            .collect();
+        for t in &mut tries {
+            assert_eq!(0, t.to_fixed_node().word_count);
+            t.insert("cross");
+            assert_eq!(vec!["cross"], t.words().collect::<Vec<_>>());
+        }
+    }
+
+    // Hands out one line per read with a pause before each, like a slow pipe.
+    #[cfg(feature = "parallel")]
+    struct ThrottledReader {
+        lines: Vec<String>,
+        next: usize,
+        pause: Duration,
+    }
+
+    #[cfg(feature = "parallel")]
+    impl io::Read for ThrottledReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some(line) = self.lines.get(self.next) else {
+                return Ok(0);
+            };
+            std::thread::sleep(self.pause);
+            self.next += 1;
+            buf[..line.len()].copy_from_slice(line.as_bytes());
+            Ok(line.len())
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn check_read_ahead<T: LetterTrie>(load_method: &LoadMethod) {
+        // Long words with nothing in common past the first few letters, so that building takes about as long as
+        // reading.
+        let words: Vec<String> = (0..300)
+            .map(|i| format!("{:03}{}", i, "abcdefghij".repeat(100)))
+            .collect();
+        let reader = ThrottledReader {
+            lines: words.iter().map(|word| format!("{}\n", word)).collect(),
+            next: 0,
+            pause: Duration::from_micros(300),
+        };
+        let opt = DisplayDetailOptions::make_no_display().with_read_ahead_lines(16);
+        let t = T::from_reader_test(
+            BufReader::new(reader),
+            true,
+            load_method,
+            &opt,
+            Some(words.len()),
+        )
+        .unwrap();
+        assert!(t.diff(&T::from_words(&words)).is_empty());
+
+        let steps = opt.step_times();
+        let labels: Vec<&str> = steps.iter().map(|(step, _)| step.as_str()).collect();
+        assert_eq!(
+            vec![
+                LABEL_STEP_READ_FILE,
+                LABEL_STEP_BUILD_FROM_LINES,
+                LABEL_STEP_OVERALL
+            ],
+            labels
+        );
+        let (read, build, wall) = (steps[0].1, steps[1].1, steps[2].1);
+        // Both sides did real work, and at least half of the shorter one was hidden behind the other.
+        assert!(read >= Duration::from_micros(300 * 300));
+        assert!(build > Duration::ZERO);
+        assert!(wall >= read.max(build));
+        assert!(
+            wall + read.min(build) / 2 < read + build,
+            "read {:?}, build {:?}, wall {:?}",
+            read,
+            build,
+            wall
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn read_ahead_overlaps_read_and_build() {
+        check_read_ahead::<BaseLetterTrie>(&LoadMethod::Continuous);
+        check_read_ahead::<BaseLetterTrie>(&LoadMethod::ContinuousParallel);
+        check_read_ahead::<NoParentLetterTrie>(&LoadMethod::Continuous);
+    }
+
+    // The medium dataset split up by first letter, with the letters in reverse order and each letter's words in
+    // the order they're in the file.
+    fn medium_letter_groups() -> Vec<(char, Vec<String>)> {
+        let mut groups: BTreeMap<char, Vec<String>> = BTreeMap::new();
+        for word in words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap() {
+            let letter = word.to_lowercase().chars().next().unwrap();
+            groups.entry(letter).or_default().push(word);
+        }
+        groups.into_iter().rev().collect()
+    }
+
+    fn check_letter_groups<T: LetterTrie>() {
+        let direct =
+            T::from_dataset(&Dataset::TestMediumUnsorted, &LoadMethod::Continuous).unwrap();
+        for parallel in [false, true] {
+            let t = T::from_letter_groups(medium_letter_groups(), parallel).unwrap();
+            t.verify_integrity().unwrap();
+            assert_eq!(direct.to_fixed_node(), t.to_fixed_node());
+            assert_eq!(direct.fingerprint(), t.fingerprint());
+            assert!(direct.diff(&t).is_empty());
+        }
+
+        // Case, whitespace, blank words and a letter given twice.
+        let groups = vec![
+            (
+                'C',
+                vec!["Cross".to_owned(), " crossed ".to_owned(), "".to_owned()],
+            ),
+            ('a', vec!["an".to_owned()]),
+            ('b', vec![]),
+            ('c', vec!["crate".to_owned()]),
+        ];
+        for parallel in [false, true] {
+            let t = T::from_letter_groups(groups.clone(), parallel).unwrap();
+            assert_eq!(
+                vec!["an", "crate", "cross", "crossed"],
+                t.words().collect::<Vec<_>>()
+            );
+            assert_eq!(vec!['a', 'c'], t.children(""));
+        }
+        assert_eq!(
+            0,
+            T::from_letter_groups(vec![], true)
+                .unwrap()
+                .to_fixed_node()
+                .word_count
+        );
+
+        // One word in the wrong group, with the rest all in the right ones.
+        let mut groups = medium_letter_groups();
+        let misfiled = groups[3].1.remove(10);
+        groups[5].1.insert(20, format!("  {}", misfiled));
+        let letter = groups[5].0;
+        for parallel in [false, true] {
+            match T::from_letter_groups(groups.clone(), parallel) {
+                Err(err @ LetterTrieError::MisfiledWord { .. }) => {
+                    assert_eq!(
+                        format!(
+                            "Misfiled word: \"{}\" is in the group for '{}'",
+                            misfiled, letter
+                        ),
+                        err.to_string()
+                    );
+                    assert!(matches!(
+                        err,
+                        LetterTrieError::MisfiledWord { letter: l, word } if l == letter && word == misfiled
+                    ));
+                }
+                other => panic!("{:?}", other.map(|t| t.to_fixed_node())),
+            }
+        }
+    }
+
+    #[test]
+    fn letter_groups() {
+        check_letter_groups::<BaseLetterTrie>();
+        check_letter_groups::<NoParentLetterTrie>();
+    }
+
+    // Read-ahead only changes the continuous loads.
+    #[test]
+    fn read_ahead_other_load_methods() {
+        let opt = DisplayDetailOptions::make_no_display().with_read_ahead_lines(16);
+        NoParentLetterTrie::from_reader_test(
+            "cross\n".as_bytes(),
+            true,
+            &LoadMethod::VecFill,
+            &opt,
+            None,
+        )
+        .unwrap();
+        assert_eq!(LABEL_STEP_READ_AND_VECTOR, opt.step_times()[0].0);
+    }
+
+    fn check_dataset(dataset: &Dataset) {
+        dataset.verify().unwrap();
+        assert_eq!(
+            dataset.expected_word_count(),
+            words_from_file(dataset.filename()).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn datasets_verify() {
+        for dataset in &[
+            Dataset::TestSmallSorted,
+            Dataset::TestSmallUnsorted,
+            Dataset::TestMediumSorted,
+            Dataset::TestMediumUnsorted,
+            Dataset::Generated {
+                count: 1_000,
+                seed: 7,
+            },
+            Dataset::Realistic {
+                count: 1_000,
+                seed: 7,
+            },
+            Dataset::EmbeddedSmall,
+        ] {
+            check_dataset(dataset);
+        }
+        // The order of the words matters but the line endings don't.
+        assert_ne!(
+            Dataset::TestMediumSorted.expected_fingerprint(),
+            Dataset::TestMediumUnsorted.expected_fingerprint()
+        );
+        assert_eq!(
+            words_fingerprint(vec!["cross", "an"]),
+            words_fingerprint("Cross\r\n\r\nan \r\n".lines())
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the large dataset files"]
+    fn large_datasets_verify() {
+        check_dataset(&Dataset::TestLargeSorted);
+        check_dataset(&Dataset::TestLargeUnsorted);
+    }
+
+    #[test]
+    fn dataset_trie_sizes() {
+        // The sizes given in the docs for the Dataset variants. The large files give 1,083,388 nodes and a height of
+        // 17, which takes too long to check here.
+        for (dataset, node_count, height) in &[
+            (Dataset::TestSmallSorted, 28, 9),
+            (Dataset::TestSmallUnsorted, 28, 9),
+            (Dataset::TestMediumSorted, 99_851, 17),
+            (Dataset::TestMediumUnsorted, 99_851, 17),
+        ] {
+            let fixed_node = NoParentLetterTrie::from_dataset(dataset, &LoadMethod::Continuous)
+                .unwrap()
+                .to_fixed_node();
+            assert_eq!(dataset.expected_word_count(), fixed_node.word_count);
+            assert_eq!(*node_count, fixed_node.node_count);
+            assert_eq!(*height, fixed_node.height);
+        }
+    }
+
+    #[test]
+    fn damaged_word_files() {
+        let dir = temp_path("damaged_word_files");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("words.txt");
+        let filename = path.to_str().unwrap();
+        let fingerprint = words_fingerprint(vec!["an", "cross"]);
+
+        fs::write(&path, "an\r\ncross\r\n").unwrap();
+        verify_word_file(filename, 2, fingerprint).unwrap();
+
+        fs::write(&path, "an\n").unwrap();
+        let err = verify_word_file(filename, 2, fingerprint).unwrap_err();
+        assert!(matches!(
+            err,
+            DatasetError::WordCount {
+                expected: 2,
+                found: 1,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("has 1 words rather than 2"));
+
+        fs::write(&path, "an\ncrass\n").unwrap();
+        let err = verify_word_file(filename, 2, fingerprint).unwrap_err();
+        assert!(
+            matches!(err, DatasetError::Fingerprint { expected, .. } if expected == fingerprint)
+        );
+
+        fs::write(
+            &path,
+            "version https://git-lfs.github.com/spec/v1\noid sha256:4d7a\nsize 4\n",
+        )
+        .unwrap();
+        let err = verify_word_file(filename, 2, fingerprint).unwrap_err();
+        assert!(matches!(err, DatasetError::LfsPointer { .. }));
+        assert!(err.to_string().contains("git lfs pull"));
+
+        let err = verify_word_file(dir.to_str().unwrap(), 2, fingerprint).unwrap_err();
+        assert!(matches!(err, DatasetError::Io { .. }));
+        assert!(error::Error::source(&err).is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+        let err = verify_word_file(filename, 2, fingerprint).unwrap_err();
+        assert!(matches!(err, DatasetError::Missing { .. }));
+        assert!(err.to_string().contains(filename), "{}", err);
+        assert!(err.to_string().contains(DATA_DIR_ENV_VAR), "{}", err);
+        assert!(error::Error::source(&err).is_none());
+    }
+
+    #[test]
+    fn missing_dataset_file() {
+        let path = Path::new("no_such_dir").join(FILENAME_LARGE_SORTED);
+        let err = in_dataset_file(&path)(io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(&err, LetterTrieError::MissingDataset { path: p } if *p == path));
+        let message = err.to_string();
+        assert!(message.contains(&path.display().to_string()), "{}", message);
+        assert!(message.contains(DATA_DIR_ENV_VAR), "{}", message);
+        assert!(message.contains("Dataset::EmbeddedSmall"), "{}", message);
+        assert!(error::Error::source(&err).is_none());
+
+        let err = in_dataset_file(&path)(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(err, LetterTrieError::File { .. }));
+    }
+
+    #[test]
+    fn embedded_small_dataset() {
+        let dataset = Dataset::EmbeddedSmall;
+        assert_eq!(dataset, "embedded-small".parse::<Dataset>().unwrap());
+        assert_eq!("embedded-small", dataset.to_string());
+        assert!(!Dataset::all().contains(&dataset));
+        dataset.verify().unwrap();
+
+        // The words are the same as the small unsorted file's, in the same order, wherever they're read from.
+        let words = read_dataset_words(&dataset).unwrap();
+        assert_eq!(
+            read_dataset_words(&Dataset::TestSmallUnsorted).unwrap(),
+            words
+        );
+        dataset.materialize().unwrap();
+        assert_eq!(words, words_from_file(dataset.filename()).unwrap());
+        assert!(Path::new(dataset.filename()).is_absolute());
+        for load_method in LoadMethod::all() {
+            let t = BaseLetterTrie::from_dataset(&dataset, load_method).unwrap();
+            assert_small_root(&t.to_fixed_node());
+        }
+    }
+
+    // The position of each variant in all(), or None for one that all() leaves out. These matches have no wildcard
+    // arm so a new variant doesn't compile until it's given a place here, and the tests below then fail until
+    // all() has it too.
+    fn letter_trie_type_index(letter_trie_type: &LetterTrieType) -> Option<usize> {
+        match letter_trie_type {
+            LetterTrieType::Base => Some(0),
+            LetterTrieType::NoParent => Some(1),
+        }
+    }
+
+    fn load_method_index(load_method: &LoadMethod) -> Option<usize> {
+        match load_method {
+            LoadMethod::ReadVecFill => Some(0),
+            LoadMethod::VecFill => Some(1),
+            LoadMethod::Continuous => Some(2),
+            LoadMethod::ContinuousParallel => Some(3),
+            LoadMethod::External { .. } => None,
+        }
+    }
+
+    fn dataset_index(dataset: &Dataset) -> Option<usize> {
+        match dataset {
+            Dataset::TestSmallSorted => Some(0),
+            Dataset::TestSmallUnsorted => Some(1),
+            Dataset::TestMediumSorted => Some(2),
+            Dataset::TestMediumUnsorted => Some(3),
+            Dataset::TestLargeSorted => Some(4),
+            Dataset::TestLargeUnsorted => Some(5),
+            Dataset::Generated { .. } | Dataset::Realistic { .. } | Dataset::EmbeddedSmall => None,
+        }
+    }
+
+    fn check_all_in_order<T>(all: &[T], index: fn(&T) -> Option<usize>, variant_count: usize) {
+        let indexes: Vec<Option<usize>> = all.iter().map(index).collect();
+        let expected: Vec<Option<usize>> = (0..variant_count).map(Some).collect();
+        assert_eq!(expected, indexes);
+    }
+
+    fn check_round_trip<T>(value: &T)
+    where
+        T: fmt::Display + FromStr<Err = UnknownNameError> + PartialEq + Debug,
+    {
+        let name = value.to_string();
+        assert_eq!(Ok(value), name.parse::<T>().as_ref(), "{}", name);
+    }
+
+    #[test]
+    fn all_lists_every_variant() {
+        check_all_in_order(LetterTrieType::all(), letter_trie_type_index, 2);
+        check_all_in_order(LoadMethod::all(), load_method_index, 4);
+        check_all_in_order(Dataset::all(), dataset_index, 6);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        LetterTrieType::all().iter().for_each(check_round_trip);
+        LoadMethod::all().iter().for_each(check_round_trip);
+        Dataset::all().iter().for_each(check_round_trip);
+        check_round_trip(&Dataset::Generated {
+            count: 50_000,
+            seed: 7,
+        });
+        assert_eq!(
+            "generated-50000-7",
+            Dataset::Generated {
+                count: 50_000,
+                seed: 7
+            }
+            .to_string()
+        );
+        check_round_trip(&Dataset::Realistic {
+            count: 50_000,
+            seed: 7,
+        });
+        check_round_trip(&LoadMethod::External {
+            temp_dir: env::temp_dir(),
+            chunk_words: DEFAULT_EXTERNAL_CHUNK_WORDS,
+        });
+
+        let err = "continuous_parallel".parse::<LoadMethod>().unwrap_err();
+        assert_eq!("load method", err.kind);
+        assert_eq!(
+            "Unknown load method \"continuous_parallel\". Expected one of: read-vec-fill, vec-fill, continuous, \
+             continuous-parallel, external",
+            err.to_string()
+        );
+        assert!("Base".parse::<LetterTrieType>().is_err());
+        assert!("generated-50000".parse::<Dataset>().is_err());
+        assert!("generated-x-7".parse::<Dataset>().is_err());
+        let err = "huge-sorted".parse::<Dataset>().unwrap_err();
+        assert!(err
+            .expected
+            .ends_with("large-unsorted, generated-<count>-<seed>"));
+    }
+
+    // Hints that are far too small or far too large have to give the same trie as no hints at all, for every
+    // load method and with the duplicate check's map sized from them as well.
+    fn check_absurd_hints<T: LetterTrie>() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let expected = T::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        let hints = [
+            (0, 0),
+            (1, 1),
+            (usize::MAX, usize::MAX),
+            (usize::MAX, 0),
+            (0, usize::MAX),
+        ];
+        for (word_count, avg_word_len) in hints {
+            for policy in [DuplicatePolicy::Ignore, DuplicatePolicy::Count] {
+                for load_method in ALL_LOAD_METHODS.iter() {
+                    let opt = DisplayDetailOptions::make_no_display()
+                        .with_capacity_hints(word_count, avg_word_len)
+                        .with_duplicate_policy(policy);
+                    let t = T::from_dataset_with_options(&dataset, load_method, &opt).unwrap();
+                    assert_eq!(expected.to_fixed_node(), t.to_fixed_node());
+                    assert_eq!(expected.fingerprint(), t.fingerprint());
+                }
+            }
+        }
+        // The hints from a file's size only last for its load.
+        let opt = DisplayDetailOptions::make_no_display();
+        T::from_dataset_with_options(&dataset, &LoadMethod::VecFill, &opt).unwrap();
+        assert_eq!(None, opt.capacity_hints());
+    }
+
+    #[test]
+    fn absurd_hints_base() {
+        check_absurd_hints::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn absurd_hints_no_parent() {
+        check_absurd_hints::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn hints_from_file_len() {
+        let hints = CapacityHints::from_file_len(u64::MAX);
+        assert_eq!(MAX_HINTED_WORDS, hints.words());
+        assert_eq!(MAX_HINTED_WORDS * (HINT_WORD_LEN + 1), hints.bytes());
+        let len = fs::metadata(Dataset::TestMediumSorted.path())
+            .unwrap()
+            .len();
+        let hints = CapacityHints::from_file_len(len);
+        // The estimate should err on the high side so that nothing needs to grow.
+        let word_count = Dataset::TestMediumSorted.word_count();
+        assert!(hints.word_count >= word_count && hints.word_count < word_count * 3 / 2);
+    }
+
+    // Letters outside ASCII come after "z" by scalar value, "ß" (U+00DF) before "é" (U+00E9) and "ñ" (U+00F1).
+    const UNICODE_ORDER_SOURCE: &str =
+        "Éclair\nzebra\nñu\nstraße\nnu\nstrasse\némile\neve\nstrass\n";
+    const UNICODE_ORDER: [&str; 9] = [
+        "eve", "nu", "strass", "strasse", "straße", "zebra", "éclair", "émile", "ñu",
+    ];
+
+    // Every list of words from T in each load method, for the words given in any order.
+    fn unicode_order_lists<T: LetterTrie>() -> Vec<Vec<String>> {
+        let sorted = UNICODE_ORDER.join("\n");
+        let mut lists = vec![];
+        for load_method in ALL_LOAD_METHODS.iter() {
+            for (source, is_sorted) in [(UNICODE_ORDER_SOURCE, false), (sorted.as_str(), true)] {
+                let t = T::from_reader(source.as_bytes(), is_sorted, load_method).unwrap();
+                lists.push(t.words().collect());
+                let mut words = vec![];
+                t.for_each_word(|word| words.push(word.to_owned()));
+                lists.push(words);
+                lists.push(t.words_with_sources().map(|sourced| sourced.word).collect());
+                lists.push(
+                    ["e", "n", "s", "z", "é", "ñ"]
+                        .iter()
+                        .flat_map(|prefix| t.words_with_prefix(prefix))
+                        .collect(),
+                );
+
+                let mut bytes: Vec<u8> = vec![];
+                t.save_front_coded(&mut bytes).unwrap();
+                let copy = BaseLetterTrie::from_front_coded(bytes.as_slice(), load_method).unwrap();
+                lists.push(copy.words().collect());
+                let mut bytes: Vec<u8> = vec![];
+                t.write_binary(&mut bytes).unwrap();
+                let copy = NoParentLetterTrie::read_binary(bytes.as_slice()).unwrap();
+                lists.push(copy.words().collect());
+
+                // Ties in length are broken in the same order.
+                assert_eq!(vec!["nu", "ñu", "eve", "zebra", "émile"], t.suggest("", 5));
+                assert_eq!(vec!["strass", "straße", "strasse"], t.suggest("stra", 3));
+            }
+        }
+        lists
+    }
+
+    #[test]
+    fn unicode_order_is_the_same_everywhere() {
+        let mut sorted = UNICODE_ORDER.to_vec();
+        sorted.sort();
+        assert_eq!(UNICODE_ORDER.to_vec(), sorted);
+        for pair in UNICODE_ORDER.windows(2) {
+            assert!(pair[0].chars().lt(pair[1].chars()));
+        }
+
+        let base = unicode_order_lists::<BaseLetterTrie>();
+        let no_parent = unicode_order_lists::<NoParentLetterTrie>();
+        assert_eq!(base, no_parent);
+        for words in base {
+            assert_eq!(UNICODE_ORDER.to_vec(), words);
+        }
+    }
+
+    // The words of tests/fixtures/unicode/words.txt as they're stored, in order: a combining accent after "cafe",
+    // "İ" lowercased to "i" and a combining dot, a final sigma, letters whose UTF-8 forms start with the same byte
+    // ("é" and "ê"), CJK words, and emoji made of one character or of several, such as a flag, a skin tone and a
+    // family joined with zero-width joiners.
+    const MULTI_BYTE_WORDS: [&str; 15] = [
+        "cafe\u{301}",
+        "café",
+        "i\u{307}stanbul",
+        "rust\u{1f980}",
+        "été",
+        "être",
+        "οδος",
+        "中文",
+        "日本",
+        "日本語",
+        "\u{1f1ef}\u{1f1f5}",
+        "\u{1f44d}\u{1f3fd}",
+        "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}",
+        "\u{1f980}",
+        "\u{1f980}rust",
+    ];
+
+    fn check_multi_byte<T: LetterTrie>(t: &T, label: &str) {
+        t.verify_integrity().unwrap();
+        assert_eq!(
+            MULTI_BYTE_WORDS.to_vec(),
+            t.words_with_prefix(""),
+            "{}",
+            label
+        );
+        assert_eq!(vec!["日本", "日本語"], t.words_with_prefix("日本"));
+        assert_eq!(
+            vec!["\u{1f980}", "\u{1f980}rust"],
+            t.words_with_prefix("\u{1f980}")
+        );
+        assert_eq!(vec!["cafe\u{301}"], t.words_with_prefix("cafe"));
+        assert_eq!(2, t.count_with_prefix("caf"));
+        assert_eq!(
+            vec!["\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}"],
+            t.words_with_prefix("\u{1f468}\u{200d}")
+        );
+        assert!(t.contains("ΟΔΟΣ") && t.contains("οδος"));
+        assert!(t.contains("İstanbul") && !t.contains("istanbul"));
+        assert!(!t.contains("\u{1f44d}") && t.contains_prefix("\u{1f44d}"));
+        assert!(!t.contains("日") && !t.contains("本"));
+
+        // Depths and heights count characters, not bytes.
+        let root = t.to_fixed_node();
+        assert_eq!((MULTI_BYTE_WORDS.len(), 10), (root.word_count, root.height));
+        for word in MULTI_BYTE_WORDS {
+            let node = t.find(word).unwrap();
+            assert_eq!(word.chars().count(), node.depth, "{}", word);
+            assert_eq!((word, true), (node.prefix.as_str(), node.is_word));
+        }
+        let node = t.find("日本").unwrap();
+        assert_eq!(
+            (2, 1, 2, 2),
+            (node.depth, node.child_count, node.word_count, node.height)
+        );
+        assert_eq!('本', node.c);
+        assert_eq!(1, t.find("\u{1f44d}").unwrap().depth);
+        assert_eq!(
+            vec!["中文", "日本", "\u{1f1ef}\u{1f1f5}", "\u{1f44d}\u{1f3fd}"],
+            t.completions_of_exact_length("", 2)
+        );
+        assert_eq!(vec!["\u{1f980}", "中文", "日本"], t.suggest("", 3));
+
+        // Stepping down one character at a time.
+        assert_eq!(
+            vec![
+                'c',
+                'i',
+                'r',
+                'é',
+                'ê',
+                'ο',
+                '中',
+                '日',
+                '\u{1f1ef}',
+                '\u{1f44d}',
+                '\u{1f468}',
+                '\u{1f980}'
+            ],
+            t.children("")
+        );
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let mut prefix = "\u{1f468}".to_owned();
+        for c in family.chars().skip(1) {
+            let next = t.next_letters(&prefix);
+            assert_eq!(
+                vec![(c, prefix.len() + c.len_utf8() == family.len())],
+                next,
+                "{:?}",
+                prefix
+            );
+            prefix.push(c);
+        }
+        assert!(t.next_letters(family).is_empty());
+        assert_eq!(vec![('\u{301}', true)], t.next_letters("cafe"));
+
+        // The same with a cursor, where a letter whose lowercase form is two characters takes two steps.
+        for word in MULTI_BYTE_WORDS {
+            let mut cursor = t.cursor();
+            for (i, c) in word.chars().enumerate() {
+                assert!(cursor.step(c), "{} at {}", word, i);
+                assert_eq!(i + 1, cursor.depth());
+            }
+            assert!(cursor.is_word(), "{}", word);
+        }
+        let mut cursor = t.cursor();
+        assert!(cursor.step('İ'));
+        assert_eq!(2, cursor.depth());
+        assert!(cursor.step_chars("STANBUL".chars()) && cursor.is_word());
+        cursor.reset();
+        assert!(cursor.step_chars("日本".chars()));
+        assert_eq!((2, 1), (cursor.depth(), cursor.child_count()));
+        assert!(!cursor.step('本'));
+        assert!(cursor.step_chars("語".chars()) && cursor.is_word());
+        cursor.reset();
+        assert!(cursor.step_chars("\u{1f468}\u{200d}\u{1f469}".chars()));
+        assert_eq!(3, cursor.depth());
+        // A capital sigma on its own is a plain sigma, so the last letter has to be given as a final one.
+        cursor.reset();
+        assert!(!cursor.step_chars("ΟΔΟΣ".chars()));
+        assert!(cursor.step_chars("ΟΔΟς".chars()) && cursor.is_word());
+
+        // Offsets into a text are bytes, and always on character boundaries.
+        assert_eq!(vec![6], t.split_points("日本中文"));
+        assert_eq!(
+            vec![4, 8],
+            t.split_points_with_min_len("\u{1f980}rust\u{1f980}", 1)
+        );
+        let text = "I saw 日本語 and ÉTÉ, İstanbul, rust\u{1f980}\u{1f980}rust, \u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467} and ΟΔΟΣ.";
+        let matcher = t.compile_matcher();
+        let mut found = vec![];
+        for m in matcher.scan(text) {
+            assert!(
+                text.is_char_boundary(m.start) && text.is_char_boundary(m.end),
+                "{:?}",
+                m
+            );
+            assert_eq!(m.word, text[m.start..m.end].to_lowercase(), "{:?}", m);
+            found.push(m.word);
+        }
+        found.sort_unstable();
+        assert_eq!(
+            vec![
+                "i\u{307}stanbul",
+                "rust\u{1f980}",
+                "été",
+                "οδος",
+                "日本",
+                "日本語",
+                "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}",
+                "\u{1f980}",
+                "\u{1f980}",
+                "\u{1f980}rust",
+            ],
+            found
+        );
+
+        // The saved forms round-trip.
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        let copy = BaseLetterTrie::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(t.fingerprint(), copy.fingerprint());
+        assert_eq!(MULTI_BYTE_WORDS.to_vec(), copy.words_with_prefix(""));
+        let mut bytes: Vec<u8> = vec![];
+        t.save_front_coded(&mut bytes).unwrap();
+        let copy = NoParentLetterTrie::from_front_coded(bytes.as_slice(), &LoadMethod::Continuous)
+            .unwrap();
+        assert_eq!(MULTI_BYTE_WORDS.to_vec(), copy.words_with_prefix(""));
+        // The JSON tree escapes a character past the Basic Multilingual Plane as a UTF-16 pair.
+        let json = t.to_json_tree(None, None);
+        assert!(
+            json.contains(r#""c":"\ud83e\udd80","word":true"#),
+            "{}",
+            json
+        );
+        assert!(json.contains(r#""c":"\u65e5""#), "{}", json);
+    }
+
+    fn check_multi_byte_loads<T: LetterTrie>() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/unicode/words.txt");
+        let sorted = MULTI_BYTE_WORDS.join("\n");
+        for load_method in ALL_LOAD_METHODS.iter() {
+            let t = T::from_file(path.to_str().unwrap(), false, load_method).unwrap();
+            check_multi_byte(&t, &format!("unsorted, {}", load_method));
+            let t = T::from_reader(sorted.as_bytes(), true, load_method).unwrap();
+            check_multi_byte(&t, &format!("sorted, {}", load_method));
+        }
+        let mut t = T::from_words(MULTI_BYTE_WORDS);
+        check_multi_byte(&t, "from_words()");
+        assert!(t.remove("日本"));
+        t.insert("日本");
+        check_multi_byte(&t, "after remove() and insert()");
+    }
+
+    #[test]
+    fn multi_byte_base() {
+        check_multi_byte_loads::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn multi_byte_no_parent() {
+        check_multi_byte_loads::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn unreadable_files_are_errors() {
+        let dir = temp_path("unreadable_files");
+        fs::create_dir_all(&dir).unwrap();
+        let missing = dir.join("missing.txt");
+        let not_utf8 = dir.join("not_utf8.txt");
+        fs::write(&not_utf8, b"cross\n\xff\xfe\n").unwrap();
+        // The directory itself can be opened on some platforms, but not read.
+        for path in [&missing, &not_utf8, &dir.to_path_buf()] {
+            let filename = path.to_str().unwrap();
+            let results = [
+                BaseLetterTrie::from_file(filename, true, &LoadMethod::Continuous).map(|_| ()),
+                NoParentLetterTrie::from_file(filename, true, &LoadMethod::ContinuousParallel)
+                    .map(|_| ()),
+                BaseLetterTrie::from_file_tagged(filename, true, &LoadMethod::ReadVecFill, 1)
+                    .map(|_| ()),
+                words_from_file(filename).map(|_| ()),
+                make_vec_char_test(filename, &DisplayDetailOptions::make_no_display(), None)
+                    .map(|_| ()),
+            ];
+            for result in results {
+                match result {
+                    Err(LetterTrieError::File { path: p, .. }) => assert_eq!(path, &p),
+                    other => panic!("{}: {:?}", filename, other),
+                }
+            }
+        }
+        match BaseLetterTrie::from_file(missing.to_str().unwrap(), true, &LoadMethod::VecFill) {
+            Err(LetterTrieError::File { source, .. }) => {
+                assert_eq!(io::ErrorKind::NotFound, source.kind())
+            }
+            other => panic!("{:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn poisoned_options_still_record() {
+        let opt = DisplayDetailOptions::make_no_display();
+        // A thread that panics while holding the lock on the step times, as a panic in the middle of a timed step
+        // would, poisons it.
+        let _ = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _steps = opt.step_times.lock();
+                    panic!("poisoning the step times");
+                })
+                .join()
+        });
+        assert!(opt.step_times.is_poisoned());
+        let t = NoParentLetterTrie::from_reader_test(
+            "cross\nan\n".as_bytes(),
+            false,
+            &LoadMethod::ContinuousParallel,
+            &opt,
+            Some(2),
+        )
+        .unwrap();
+        assert!(t.contains("cross"));
+        let labels: Vec<String> = opt.step_times().into_iter().map(|(step, _)| step).collect();
+        assert_eq!(Some(&LABEL_STEP_OVERALL.to_owned()), labels.last());
+    }
+}
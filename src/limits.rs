@@ -0,0 +1,433 @@
+//! Limits on how much work a query does, for tries that answer queries from input that can't be trusted.
+//!
+//! A prefix of "" asks for every word in the trie, and a pattern or set of constraints that rules little out can
+//! walk most of it. The `_limited` variants of the expensive queries, such as
+//! `LimitedQuery::words_with_prefix_limited()`, take a `QueryLimits` and stop once either limit is reached, and the
+//! `Limited` they return says whether the results are all there are.
+//!
+//! The nodes are counted as the walk reaches them, so the count costs one comparison and one addition per node.
+
+use crate::*;
+
+/// The most a limited query may return and the most nodes it may look at before it stops.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crossing", "crate"]);
+/// let limited = trie.words_with_prefix_limited("cross", &QueryLimits::new(2, 100));
+/// assert_eq!(vec!["cross", "crossed"], limited.results);
+/// assert_eq!(Completeness::Truncated, limited.completeness);
+///
+/// let limited = trie.words_with_prefix_limited("cr", &QueryLimits::new(10, 100));
+/// assert!(limited.is_complete());
+/// assert_eq!(4, limited.results.len());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// The most results to return.
+    pub max_results: usize,
+    /// The most nodes to look at, counting each node the walk reaches whether or not it's a result.
+    pub max_nodes_visited: usize,
+}
+
+impl QueryLimits {
+    pub fn new(max_results: usize, max_nodes_visited: usize) -> Self {
+        Self {
+            max_results,
+            max_nodes_visited,
+        }
+    }
+
+    /// Limits that are never reached, so a limited query gives the same results as the one without limits.
+    pub fn unlimited() -> Self {
+        Self::new(usize::MAX, usize::MAX)
+    }
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// Whether a limited query found everything it was asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Completeness {
+    /// The query finished within its limits, so the results are the same as without limits.
+    Complete,
+    /// The query stopped at one of its limits, either because there were more results than `max_results` or
+    /// because it ran out of nodes to visit. The results are the ones found before it stopped.
+    Truncated,
+}
+
+/// The results of a limited query, along with whether they're all there are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Limited<T> {
+    pub results: T,
+    pub completeness: Completeness,
+}
+
+impl<T> Limited<T> {
+    pub fn is_complete(&self) -> bool {
+        self.completeness == Completeness::Complete
+    }
+}
+
+/// The `_limited` queries, which stop once either of the `QueryLimits` is reached, for any trie or view.
+///
+/// This is implemented automatically for everything that implements `TrieQuery`.
+pub trait LimitedQuery: TrieQuery {
+    /// Get the words that start with `prefix` as `TrieQuery::words_with_prefix()` does, but stop after
+    /// `limits.max_results` words or `limits.max_nodes_visited` nodes, for a prefix that comes from input that can't
+    /// be trusted. The result says whether the words are all of them. See the `limits` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["an", "and", "azure", "cross"]);
+    /// let limited = trie.words_with_prefix_limited("", &QueryLimits::new(2, 1_000));
+    /// assert_eq!(vec!["an", "and"], limited.results);
+    /// assert_eq!(Completeness::Truncated, limited.completeness);
+    /// assert!(trie.words_with_prefix_limited("a", &QueryLimits::new(3, 1_000)).is_complete());
+    /// ```
+    fn words_with_prefix_limited(
+        &self,
+        prefix: &str,
+        limits: &QueryLimits,
+    ) -> Limited<Vec<String>> {
+        limits::words_with_prefix(self, prefix, limits)
+    }
+
+    /// Get the guess `LetterTrie::best_guess()` would make, but look at no more than `limits.max_results` of the
+    /// words that fit the pattern and `limits.max_nodes_visited` nodes. When the result is truncated the guess is
+    /// the best of the words found before the walk stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["cross", "crest", "crust", "rose"]);
+    /// let limited = trie.best_guess_limited("cr?s?", "", &QueryLimits::unlimited());
+    /// assert_eq!(Some("crest".to_owned()), limited.results);
+    /// assert!(limited.is_complete());
+    /// let limited = trie.best_guess_limited("cr?s?", "", &QueryLimits::new(usize::MAX, 3));
+    /// assert_eq!(None, limited.results);
+    /// assert_eq!(Completeness::Truncated, limited.completeness);
+    /// ```
+    fn best_guess_limited(
+        &self,
+        pattern: &str,
+        excluded: &str,
+        limits: &QueryLimits,
+    ) -> Limited<Option<String>> {
+        let mut budget = limits::Budget::for_limits(limits);
+        let candidates = frequency::pattern_candidates(self, pattern, excluded, &mut budget, None);
+        let Limited {
+            results,
+            completeness,
+        } = budget.finish(candidates, limits);
+        Limited {
+            results: frequency::best_guess(results),
+            completeness,
+        }
+    }
+
+    /// Get the words that fit `constraints` as `LetterTrie::filter_candidates()` does, but stop after
+    /// `limits.max_results` words or `limits.max_nodes_visited` nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["crane", "crate", "grate", "irate", "trace", "slate"]);
+    /// let limited = trie.filter_candidates_limited(&LetterConstraints::new(5), &QueryLimits::new(2, 1_000));
+    /// assert_eq!(vec!["crane", "crate"], limited.results);
+    /// assert!(!limited.is_complete());
+    /// ```
+    fn filter_candidates_limited(
+        &self,
+        constraints: &LetterConstraints,
+        limits: &QueryLimits,
+    ) -> Limited<Vec<String>> {
+        let mut budget = limits::Budget::for_limits(limits);
+        let mut words = vec![];
+        constraints::for_each_candidate(self, constraints, &mut budget, |word| {
+            words.push(word.to_owned())
+        });
+        budget.finish(words, limits)
+    }
+}
+
+impl<T: TrieQuery + ?Sized> LimitedQuery for T {}
+
+// The count of nodes and results for one walk. A walk calls visit_node() at the start of each visit() callback
+// and prunes the node if it returns false, which it does for every node once either limit is reached so that the
+// rest of the walk only goes back up the path.
+#[derive(Debug)]
+pub(crate) struct Budget {
+    max_results: usize,
+    max_nodes_visited: usize,
+    results: usize,
+    nodes_visited: usize,
+    is_out_of_nodes: bool,
+}
+
+impl Budget {
+    pub(crate) fn new(max_results: usize, max_nodes_visited: usize) -> Self {
+        Self {
+            max_results,
+            max_nodes_visited,
+            results: 0,
+            nodes_visited: 0,
+            is_out_of_nodes: false,
+        }
+    }
+
+    pub(crate) fn unlimited() -> Self {
+        Self::new(usize::MAX, usize::MAX)
+    }
+
+    // One result more than the limits allow is looked for, so that finding it shows the results were cut short.
+    pub(crate) fn for_limits(limits: &QueryLimits) -> Self {
+        Self::new(
+            limits.max_results.saturating_add(1),
+            limits.max_nodes_visited,
+        )
+    }
+
+    pub(crate) fn visit_node(&mut self) -> bool {
+        if self.is_spent() {
+            return false;
+        }
+        if self.nodes_visited == self.max_nodes_visited {
+            self.is_out_of_nodes = true;
+            return false;
+        }
+        self.nodes_visited += 1;
+        true
+    }
+
+    pub(crate) fn add_result(&mut self) {
+        self.results += 1;
+    }
+
+    pub(crate) fn is_spent(&self) -> bool {
+        self.is_out_of_nodes || self.results >= self.max_results
+    }
+
+    // The results of a walk with for_limits(), without the extra one if it was found.
+    pub(crate) fn finish<T>(self, mut results: Vec<T>, limits: &QueryLimits) -> Limited<Vec<T>> {
+        let is_cut_short = results.len() > limits.max_results;
+        results.truncate(limits.max_results);
+        let completeness = if self.is_out_of_nodes || is_cut_short {
+            Completeness::Truncated
+        } else {
+            Completeness::Complete
+        };
+        Limited {
+            results,
+            completeness,
+        }
+    }
+}
+
+pub(crate) fn words_with_prefix<T: TrieQuery + ?Sized>(
+    trie: &T,
+    prefix: &str,
+    limits: &QueryLimits,
+) -> Limited<Vec<String>> {
+    let mut budget = Budget::for_limits(limits);
+    let mut words = vec![];
+    trie.visit(prefix, &mut |node| {
+        if !budget.visit_node() {
+            return false;
+        }
+        if node.is_word {
+            words.push(node.word().to_owned());
+            budget.add_result();
+        }
+        true
+    });
+    budget.finish(words, limits)
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    // A query with a tiny node limit has to stop right away however much of the trie it could have walked.
+    const PROMPT: Duration = Duration::from_millis(250);
+
+    fn medium<T: LetterTrie>() -> T {
+        T::from_words(words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap())
+    }
+
+    fn assert_prompt<R>(f: impl FnOnce() -> Limited<R>) -> Limited<R> {
+        let start = Instant::now();
+        let limited = f();
+        let elapsed = start.elapsed();
+        assert!(elapsed < PROMPT, "took {:?}", elapsed);
+        assert_eq!(Completeness::Truncated, limited.completeness);
+        limited
+    }
+
+    fn check_prefix_limits<T: LetterTrie>() {
+        let t: T = medium();
+        let all = t.words_with_prefix("");
+
+        let limited = t.words_with_prefix_limited("", &QueryLimits::unlimited());
+        assert!(limited.is_complete());
+        assert_eq!(all, limited.results);
+        let limited = t.words_with_prefix_limited("", &QueryLimits::new(all.len(), usize::MAX));
+        assert!(limited.is_complete());
+        assert_eq!(all, limited.results);
+
+        // Cut short by the results, and by the nodes.
+        let limited =
+            assert_prompt(|| t.words_with_prefix_limited("", &QueryLimits::new(5, usize::MAX)));
+        assert_eq!(all[..5], limited.results[..]);
+        let limited =
+            assert_prompt(|| t.words_with_prefix_limited("", &QueryLimits::new(usize::MAX, 50)));
+        assert!(limited.results.len() < 50);
+        assert_eq!(all[..limited.results.len()], limited.results[..]);
+        let limited = assert_prompt(|| t.words_with_prefix_limited("", &QueryLimits::new(0, 0)));
+        assert!(limited.results.is_empty());
+
+        let under = t.words_with_prefix("cross");
+        let limited = t.words_with_prefix_limited("cross", &QueryLimits::new(1_000, 10_000));
+        assert!(limited.is_complete());
+        assert_eq!(under, limited.results);
+        assert!(t
+            .words_with_prefix_limited("qqq", &QueryLimits::new(0, 0))
+            .is_complete());
+    }
+
+    #[test]
+    fn prefix_limits() {
+        check_prefix_limits::<BaseLetterTrie>();
+        check_prefix_limits::<NoParentLetterTrie>();
+    }
+
+    fn check_candidate_limits<T: LetterTrie>() {
+        let t: T = medium();
+        let everything = LetterConstraints::new(7);
+        let all = t.filter_candidates(&everything);
+        assert!(all.len() > 100);
+        let limited = t.filter_candidates_limited(&everything, &QueryLimits::unlimited());
+        assert!(limited.is_complete());
+        assert_eq!(all, limited.results);
+        let limited = assert_prompt(|| {
+            t.filter_candidates_limited(&everything, &QueryLimits::new(3, usize::MAX))
+        });
+        assert_eq!(all[..3], limited.results[..]);
+        let limited = assert_prompt(|| {
+            t.filter_candidates_limited(&everything, &QueryLimits::new(usize::MAX, 100))
+        });
+        assert_eq!(all[..limited.results.len()], limited.results[..]);
+
+        let narrow = LetterConstraints::new(5)
+            .with_fixed(0, 'c')
+            .with_fixed(1, 'r');
+        let limited = t.filter_candidates_limited(&narrow, &QueryLimits::new(1_000, 100_000));
+        assert!(limited.is_complete());
+        assert_eq!(t.filter_candidates(&narrow), limited.results);
+    }
+
+    #[test]
+    fn candidate_limits() {
+        check_candidate_limits::<BaseLetterTrie>();
+        check_candidate_limits::<NoParentLetterTrie>();
+    }
+
+    fn check_pattern_limits<T: LetterTrie>() {
+        let t: T = medium();
+        for (pattern, excluded) in [("cre?????", ""), ("?????", "e"), ("??a??", "st")] {
+            let limited = t.best_guess_limited(pattern, excluded, &QueryLimits::unlimited());
+            assert!(limited.is_complete());
+            assert_eq!(t.best_guess(pattern, excluded), limited.results);
+        }
+        // With room for only a few candidates the guess is the best of those.
+        let limited =
+            assert_prompt(|| t.best_guess_limited("?????", "", &QueryLimits::new(1, usize::MAX)));
+        let first = t
+            .filter_candidates(&LetterConstraints::new(5))
+            .into_iter()
+            .next();
+        assert_eq!(first, limited.results);
+        let limited = assert_prompt(|| {
+            t.best_guess_limited("??????????", "", &QueryLimits::new(usize::MAX, 20))
+        });
+        assert!(limited.results.is_none());
+    }
+
+    #[test]
+    fn pattern_limits() {
+        check_pattern_limits::<BaseLetterTrie>();
+        check_pattern_limits::<NoParentLetterTrie>();
+    }
+
+    #[cfg(feature = "regex")]
+    fn check_regex_limits<T: LetterTrie>() {
+        let t: T = medium();
+        for pattern in ["^cre.*e$", "^(cross|crea).*", "e.*e.*e", "^.*q.*z"] {
+            let re = regex::Regex::new(pattern).unwrap();
+            let limited = t.words_matching_limited(&re, &QueryLimits::unlimited());
+            assert!(limited.is_complete());
+            assert_eq!(t.words_matching(&re, usize::MAX), limited.results);
+        }
+        // A pattern the DFA can't prune with walks the whole trie unless it's stopped.
+        let re = regex::Regex::new("e.*e.*e.*e").unwrap();
+        let all = t.words_matching(&re, usize::MAX);
+        let limited =
+            assert_prompt(|| t.words_matching_limited(&re, &QueryLimits::new(usize::MAX, 200)));
+        assert_eq!(all[..limited.results.len()], limited.results[..]);
+        let limited =
+            assert_prompt(|| t.words_matching_limited(&re, &QueryLimits::new(2, usize::MAX)));
+        assert_eq!(all[..2], limited.results[..]);
+        let limited = t.words_matching_limited(&re, &QueryLimits::new(all.len(), usize::MAX));
+        assert!(limited.is_complete());
+        assert_eq!(all, limited.results);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_limits() {
+        check_regex_limits::<BaseLetterTrie>();
+        check_regex_limits::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn budget_counts_nodes_and_results() {
+        let mut budget = Budget::new(2, 3);
+        assert!(budget.visit_node());
+        budget.add_result();
+        assert!(budget.visit_node());
+        assert!(budget.visit_node());
+        assert!(!budget.visit_node());
+        assert!(budget.is_spent());
+        let limits = QueryLimits::new(2, 3);
+        assert_eq!(
+            Completeness::Truncated,
+            budget.finish(vec!["a"], &limits).completeness
+        );
+
+        let mut budget = Budget::for_limits(&limits);
+        budget.visit_node();
+        budget.add_result();
+        budget.visit_node();
+        budget.add_result();
+        assert!(!budget.is_spent());
+        let limited = budget.finish(vec!["a", "b"], &limits);
+        assert!(limited.is_complete());
+        assert_eq!(vec!["a", "b"], limited.results);
+        assert_eq!(QueryLimits::unlimited(), QueryLimits::default());
+    }
+}
@@ -0,0 +1,307 @@
+//! Loading a trie from lines that hold more than a word, such as a TSV export with a part of speech and a
+//! frequency after each word, behind `LetterTrie::from_lines_parsed()`.
+//!
+//! A parser turns each line into a `ParsedWord` or turns it down. The lines go through the parser as the load
+//! reads them, so the words reach the trie the same way the lines of a plain word file do, with whichever
+//! `LoadMethod` is asked for. For `LoadMethod::ContinuousParallel` that means the parsing happens on the thread
+//! that reads, while the tries for each letter are built on their own threads. The tags and counts wait until the
+//! load is done and are then given to their words.
+//!
+//! `first_column()` and `column()` are parsers for the common layouts that only pick out the word. A closure can
+//! be written for anything else, such as one that also reads a tag or a count from another column.
+
+use core::mem;
+use std::io::{self, BufRead, Read};
+
+use crate::*;
+
+/// A word from one line, as given by the parser passed to `LetterTrie::from_lines_parsed()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParsedWord {
+    /// The word, which is trimmed and lowercased as with `LetterTrie::insert()`. A blank word leaves the line out
+    /// without counting it anywhere, as a blank line is.
+    pub word: String,
+    /// The tag to give the word as with `LetterTrie::insert_with_tag()`, if any.
+    pub tag: Option<u64>,
+    /// How often the word occurs, if that's known, which is given to the word as its score for
+    /// `ScoredQuery::suggest_scored()`.
+    pub count: Option<u32>,
+}
+
+impl ParsedWord {
+    /// A word without a tag or a count.
+    pub fn new(word: impl Into<String>) -> Self {
+        Self {
+            word: word.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A parser that takes the first whitespace-separated column of a line as the word, such as "cross" from
+/// "cross noun 1024".
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let text = "cross noun 1024\ncrossed\tverb\n\n  an  \n";
+/// let (trie, report) = NoParentLetterTrie::from_lines_parsed(
+///     text.as_bytes(),
+///     line_parser::first_column,
+///     &LoadMethod::Continuous,
+/// )?;
+/// assert_eq!(vec!["an", "cross", "crossed"], trie.words_with_prefix(""));
+/// assert_eq!(3, report.words_added);
+/// # Ok::<(), LetterTrieError>(())
+/// ```
+pub fn first_column(line: &str) -> Option<ParsedWord> {
+    line.split_whitespace().next().map(ParsedWord::new)
+}
+
+/// A parser that takes column `index` of a line split at each `delimiter` as the word, counting from 0, such as
+/// `column('\t', 0)` for a TSV file with the word first or `column(',', 1)` for a CSV file with the word second.
+/// A line that doesn't have that column, or where it's blank, is turned down. Quoting isn't understood, so a
+/// quoted column with the delimiter in it is split like any other.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let text = "id,word\n1,Cross\n2\n3,an\n";
+/// let (trie, report) = BaseLetterTrie::from_lines_parsed(
+///     text.as_bytes(),
+///     line_parser::column(',', 1),
+///     &LoadMethod::Continuous,
+/// )?;
+/// // The header is taken as a word, since the parser can't tell it from one.
+/// assert_eq!(vec!["an", "cross", "word"], trie.words_with_prefix(""));
+/// assert_eq!(1, report.skipped_unparsed);
+/// # Ok::<(), LetterTrieError>(())
+/// ```
+pub fn column(delimiter: char, index: usize) -> impl FnMut(&str) -> Option<ParsedWord> + Clone {
+    move |line| {
+        line.split(delimiter)
+            .nth(index)
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(ParsedWord::new)
+    }
+}
+
+pub(crate) fn from_lines_parsed<T, R, F>(
+    reader: R,
+    parser: F,
+    load_method: &LoadMethod,
+) -> Result<(T, LoadReport)>
+where
+    T: LetterTrie,
+    R: BufRead,
+    F: FnMut(&str) -> Option<ParsedWord>,
+{
+    let mut lines = ParsedLines {
+        reader,
+        parser,
+        line: String::new(),
+        out: vec![],
+        pos: 0,
+        extras: vec![],
+        report: LoadReport::default(),
+    };
+    let mut t = T::from_reader(&mut lines, false, load_method)?;
+    for parsed in mem::take(&mut lines.extras) {
+        if let Some(tag) = parsed.tag {
+            t.insert_with_tag(&parsed.word, tag);
+        }
+        if let Some(count) = parsed.count {
+            t.set_score(&parsed.word, f64::from(count));
+        }
+    }
+    Ok((t, lines.report))
+}
+
+// The lines of a reader as a plain word file, with each line replaced by the word the parser finds in it. The
+// parsed words that have a tag or a count are kept in extras for after the load.
+struct ParsedLines<R, F> {
+    reader: R,
+    parser: F,
+    // The line being parsed.
+    line: String,
+    // The word from the last line along with a newline, and how much of it has been read.
+    out: Vec<u8>,
+    pos: usize,
+    extras: Vec<ParsedWord>,
+    report: LoadReport,
+}
+
+impl<R: BufRead, F: FnMut(&str) -> Option<ParsedWord>> Read for ParsedLines<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead, F: FnMut(&str) -> Option<ParsedWord>> BufRead for ParsedLines<R, F> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.out.len() {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(&[]);
+            }
+            let line = self.line.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = match (self.parser)(line) {
+                Some(parsed) => parsed,
+                None => {
+                    self.report.skipped_unparsed += 1;
+                    continue;
+                }
+            };
+            let word = parsed.word.trim();
+            if word.is_empty() {
+                continue;
+            }
+            self.out.clear();
+            self.out.extend_from_slice(word.as_bytes());
+            self.out.push(b'\n');
+            self.pos = 0;
+            self.report.words_added += 1;
+            if parsed.tag.is_some() || parsed.count.is_some() {
+                self.extras.push(parsed);
+            }
+        }
+        Ok(&self.out[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.out.len());
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn fixture() -> File {
+        File::open(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/line_parser/words.tsv"),
+        )
+        .unwrap()
+    }
+
+    // The word from the first column and the frequency, if there is one, from the third as both the tag and the
+    // count. A line without a part of speech or with a frequency that isn't a number, such as the header, is
+    // turned down.
+    fn word_and_frequency(line: &str) -> Option<ParsedWord> {
+        let columns: Vec<&str> = line.split('\t').collect();
+        if columns.len() < 2 {
+            return None;
+        }
+        let frequency = match columns.get(2) {
+            Some(frequency) => Some(frequency.trim().parse::<u32>().ok()?),
+            None => None,
+        };
+        Some(ParsedWord {
+            word: columns[0].to_owned(),
+            tag: frequency.map(u64::from),
+            count: frequency,
+        })
+    }
+
+    fn check_from_lines_parsed<T: LetterTrie>() {
+        for load_method in &ALL_LOAD_METHODS {
+            let (t, report) =
+                T::from_lines_parsed(BufReader::new(fixture()), word_and_frequency, load_method)
+                    .unwrap();
+            t.verify_integrity().unwrap();
+            assert_eq!(
+                vec!["an", "and", "azure", "crease", "cross", "crossed", "ñu"],
+                t.words_with_prefix(""),
+                "{}",
+                load_method
+            );
+            assert_eq!(
+                LoadReport {
+                    words_added: 8,
+                    skipped_unparsed: 3,
+                    ..LoadReport::default()
+                },
+                report,
+                "{}",
+                load_method
+            );
+            // "Cross" is on two lines, and the later one wins.
+            assert_eq!(Some(40), t.tag("cross"));
+            assert_eq!(Some(40.0), t.score("cross"));
+            assert_eq!(Some(7), t.tag("ñu"));
+            assert_eq!(None, t.tag("azure"));
+            assert_eq!(
+                vec![("cross".to_owned(), 40.0), ("crease".to_owned(), 12.0)],
+                t.suggest_scored("cr", 2)
+            );
+
+            let (t, report) =
+                T::from_lines_parsed(BufReader::new(fixture()), column('\t', 1), load_method)
+                    .unwrap();
+            assert_eq!(
+                vec!["adjective", "article", "conjunction", "noun", "pos", "verb"],
+                t.words_with_prefix("")
+            );
+            assert_eq!(1, report.skipped_unparsed);
+            assert_eq!(None, t.tag("noun"));
+        }
+    }
+
+    #[test]
+    fn from_lines_parsed_base() {
+        check_from_lines_parsed::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn from_lines_parsed_no_parent() {
+        check_from_lines_parsed::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn built_in_parsers() {
+        assert_eq!(
+            Some(ParsedWord::new("cross")),
+            first_column("  cross\tnoun 3")
+        );
+        assert_eq!(None, first_column(" \t "));
+        let mut third = column(';', 2);
+        assert_eq!(Some(ParsedWord::new("an")), third("1;x; an ;y"));
+        assert_eq!(None, third("1;x"));
+        assert_eq!(None, third("1;x; ;y"));
+        assert_eq!(Some(ParsedWord::new("y")), column(';', 3)("1;x; ;y"));
+
+        // A parser that gives a blank word leaves the line out without counting it, and one that fails a line
+        // on bad input stops the load with the reader's error.
+        let (t, report) = NoParentLetterTrie::from_lines_parsed(
+            "cross\n#\nan\n".as_bytes(),
+            |line: &str| Some(ParsedWord::new(line.trim_start_matches('#'))),
+            &LoadMethod::Continuous,
+        )
+        .unwrap();
+        assert_eq!(vec!["an", "cross"], t.words_with_prefix(""));
+        assert_eq!(2, report.words_added);
+        assert_eq!(0, report.skipped_unparsed);
+        let result = NoParentLetterTrie::from_lines_parsed(
+            &b"cross\n\xff\xfe\n"[..],
+            first_column,
+            &LoadMethod::ContinuousParallel,
+        );
+        assert!(
+            matches!(result, Err(LetterTrieError::Io(_))),
+            "{:?}",
+            result
+        );
+    }
+}
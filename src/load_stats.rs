@@ -0,0 +1,486 @@
+//! Step timings from trie builds in formats that other tools can read, for charting load times across commits.
+//!
+//! The JSON object and the CSV columns are meant to stay stable so that anything reading them keeps working. A
+//! CSV file has one row per step with these columns:
+//!
+//! ```text
+//! dataset,load_method,trie_type,step,millis,word_count,node_count,resident_bytes,intermediate_bytes
+//! ```
+//!
+//! The two memory columns are empty for a step where they weren't measured.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::*;
+
+/// The CSV header written by `LoadStats::write_csv_row()`.
+pub const LOAD_STATS_CSV_HEADER: &str =
+    "dataset,load_method,trie_type,step,millis,word_count,node_count,resident_bytes,intermediate_bytes";
+
+/// The step timings and resulting size of one trie build.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadStats {
+    /// The `Debug` name of the dataset such as "TestMediumSorted".
+    pub dataset: String,
+    /// The `Debug` name of the load method such as "ContinuousParallel".
+    pub load_method: String,
+    /// The `Debug` name of the trie type such as "NoParent".
+    pub trie_type: String,
+    /// Each step and its elapsed time in the order the steps finished, ending with "overall load".
+    pub steps: Vec<(String, Duration)>,
+    /// The memory in use at the end of each step, in the same order as `steps`.
+    pub memory: Vec<StepMemory>,
+    pub word_count: usize,
+    pub node_count: usize,
+    /// How the worker threads and the merges spent their time, for a `BaseLetterTrie` built with
+    /// `LoadMethod::ContinuousParallel`, or None for any other build. It's not part of the JSON or the CSV rows.
+    pub parallel: Option<ParallelLoadStats>,
+}
+
+impl LoadStats {
+    /// Format the stats as a single JSON object.
+    ///
+    /// The object has the string fields `dataset`, `load_method` and `trie_type`, the integer fields
+    /// `word_count` and `node_count`, and `steps`, an array of objects with a string `step`, a number `millis`,
+    /// and the integers `resident_bytes` and `intermediate_bytes`, either of which is null if it wasn't measured.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use std::time::Duration;
+    ///
+    /// let stats = LoadStats {
+    ///     dataset: "TestSmallSorted".to_owned(),
+    ///     load_method: "Continuous".to_owned(),
+    ///     trie_type: "Base".to_owned(),
+    ///     steps: vec![("overall load".to_owned(), Duration::from_micros(1_500))],
+    ///     memory: vec![StepMemory {
+    ///         step: "overall load".to_owned(),
+    ///         resident_bytes: Some(4_096),
+    ///         intermediate_bytes: None,
+    ///     }],
+    ///     word_count: 10,
+    ///     node_count: 28,
+    ///     parallel: None,
+    /// };
+    /// assert_eq!(
+    ///     stats.to_json(),
+    ///     "{\"dataset\":\"TestSmallSorted\",\"load_method\":\"Continuous\",\"trie_type\":\"Base\",\
+    ///     \"word_count\":10,\"node_count\":28,\"steps\":[{\"step\":\"overall load\",\"millis\":1.5,\
+    ///     \"resident_bytes\":4096,\"intermediate_bytes\":null}]}"
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(index, (step, elapsed))| {
+                let memory = self.memory.get(index);
+                let json_bytes = |bytes: Option<usize>| match bytes {
+                    Some(bytes) => bytes.to_string(),
+                    None => "null".to_owned(),
+                };
+                format!(
+                    "{{\"step\":{},\"millis\":{},\"resident_bytes\":{},\"intermediate_bytes\":{}}}",
+                    json_string(step),
+                    millis(*elapsed),
+                    json_bytes(memory.and_then(|memory| memory.resident_bytes)),
+                    json_bytes(memory.and_then(|memory| memory.intermediate_bytes))
+                )
+            })
+            .collect();
+        format!(
+            "{{\"dataset\":{},\"load_method\":{},\"trie_type\":{},\"word_count\":{},\"node_count\":{},\"steps\":[{}]}}",
+            json_string(&self.dataset),
+            json_string(&self.load_method),
+            json_string(&self.trie_type),
+            self.word_count,
+            self.node_count,
+            steps.join(",")
+        )
+    }
+
+    /// Write one CSV row for each step, preceded by `LOAD_STATS_CSV_HEADER` if `with_header` is true.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `w`.
+    pub fn write_csv_row<W: Write>(&self, mut w: W, with_header: bool) -> io::Result<()> {
+        if with_header {
+            writeln!(w, "{}", LOAD_STATS_CSV_HEADER)?;
+        }
+        for (index, (step, elapsed)) in self.steps.iter().enumerate() {
+            let memory = self.memory.get(index);
+            let csv_bytes = |bytes: Option<usize>| bytes.map(|b| b.to_string()).unwrap_or_default();
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(&self.dataset),
+                csv_field(&self.load_method),
+                csv_field(&self.trie_type),
+                csv_field(step),
+                millis(*elapsed),
+                self.word_count,
+                self.node_count,
+                csv_bytes(memory.and_then(|memory| memory.resident_bytes)),
+                csv_bytes(memory.and_then(|memory| memory.intermediate_bytes))
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The most memory the process was seen to use at the end of any step, or None if it wasn't measured.
+    pub fn peak_resident_bytes(&self) -> Option<usize> {
+        self.memory
+            .iter()
+            .filter_map(|memory| memory.resident_bytes)
+            .max()
+    }
+
+    /// The largest estimate of a step's intermediate data, or None for a load method that doesn't build any up.
+    pub fn peak_intermediate_bytes(&self) -> Option<usize> {
+        self.memory
+            .iter()
+            .filter_map(|memory| memory.intermediate_bytes)
+            .max()
+    }
+}
+
+/// How one worker thread of a parallel load spent its time. There's a worker for each first letter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LetterLoadStats {
+    /// The first letter of every word the worker was given.
+    pub letter: char,
+    pub word_count: usize,
+    /// The time the worker took to build its part of the trie.
+    pub build_time: Duration,
+    /// The time the finished part waited in the channel before the loading thread took it. This grows when the
+    /// merges can't keep up with the workers.
+    pub queue_wait: Duration,
+    /// The time taken to merge the part into the trie on the loading thread.
+    pub merge_time: Duration,
+}
+
+/// Where the time went in a `LoadMethod::ContinuousParallel` build of a `BaseLetterTrie`, from
+/// `DisplayDetailOptions::parallel_load_stats()`. It's printed as a table along with the step times when
+/// `print_step_time` is set.
+///
+/// A long `dispatch_time` means the build is waiting on the file, a long `worker_wait` with one letter's
+/// `build_time` far ahead of the rest means a straggler, and a long `merge_time()` or `queue_wait` means the
+/// merges on the single loading thread are the bottleneck.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let opt = DisplayDetailOptions::make_no_display();
+/// let words = "ant\nape\nbee\n";
+/// BaseLetterTrie::from_reader_test(words.as_bytes(), true, &LoadMethod::ContinuousParallel, &opt, None).unwrap();
+///
+/// let stats = opt.parallel_load_stats().unwrap();
+/// let letters: Vec<(char, usize)> = stats.letters.iter().map(|l| (l.letter, l.word_count)).collect();
+/// assert_eq!(vec![('a', 2), ('b', 1)], letters);
+/// assert_eq!(3, stats.word_count());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParallelLoadStats {
+    /// One entry for each first letter, in order of the letters.
+    pub letters: Vec<LetterLoadStats>,
+    /// The time the loading thread took to split the words up by first letter and hand them to the workers. For
+    /// a sorted file this includes reading the file, since each letter goes to its worker as soon as it's read.
+    pub dispatch_time: Duration,
+    /// The time the loading thread spent waiting for the workers once everything had been handed out.
+    pub worker_wait: Duration,
+}
+
+impl ParallelLoadStats {
+    /// The total number of words across the workers.
+    pub fn word_count(&self) -> usize {
+        self.letters.iter().map(|letter| letter.word_count).sum()
+    }
+
+    /// The total time spent merging the workers' parts into the trie.
+    pub fn merge_time(&self) -> Duration {
+        self.letters.iter().map(|letter| letter.merge_time).sum()
+    }
+
+    /// Format the stats as a table with a row for each letter followed by the totals.
+    pub fn table(&self, number_style: NumberStyle) -> String {
+        let mut table = format!(
+            "{:<8}{:>12}{:>14}{:>14}{:>14}\n",
+            "letter", "words", "build", "queue wait", "merge"
+        );
+        for letter in &self.letters {
+            table.push_str(&format!(
+                "{:<8}{:>12}{:>14}{:>14}{:>14}\n",
+                letter.letter,
+                format_count_with(letter.word_count, number_style),
+                format_duration(letter.build_time),
+                format_duration(letter.queue_wait),
+                format_duration(letter.merge_time)
+            ));
+        }
+        table.push_str(&format!(
+            "{:<8}{:>12}{:>14}{:>14}{:>14}\n",
+            "total",
+            format_count_with(self.word_count(), number_style),
+            "",
+            "",
+            format_duration(self.merge_time())
+        ));
+        table.push_str(&format!(
+            "dispatch = {}; waiting on workers = {}\n",
+            format_duration(self.dispatch_time),
+            format_duration(self.worker_wait)
+        ));
+        table
+    }
+}
+
+/// Build a trie from each combination of dataset, load method and trie type and return the stats for each build
+/// in the same order as the nested loops over `datasets`, then `load_methods`, then `letter_trie_types`.
+///
+/// Memory sampling is turned on for each build, and nothing is printed along the way.
+///
+/// # Errors
+///
+/// Returns the first error from a build, such as `LetterTrieError::File` if a dataset's file can't be read.
+#[cfg(feature = "fs")]
+pub fn run_matrix(
+    datasets: &[Dataset],
+    load_methods: &[LoadMethod],
+    letter_trie_types: &[LetterTrieType],
+) -> Result<Vec<LoadStats>> {
+    let mut v = vec![];
+    for dataset in datasets {
+        for load_method in load_methods {
+            for letter_trie_type in letter_trie_types {
+                let stats = match letter_trie_type {
+                    LetterTrieType::Base => {
+                        run_one::<BaseLetterTrie>(dataset, load_method, letter_trie_type)?
+                    }
+                    LetterTrieType::NoParent => {
+                        run_one::<NoParentLetterTrie>(dataset, load_method, letter_trie_type)?
+                    }
+                };
+                v.push(stats);
+            }
+        }
+    }
+    Ok(v)
+}
+
+#[cfg(feature = "fs")]
+fn run_one<T: LetterTrie>(
+    dataset: &Dataset,
+    load_method: &LoadMethod,
+    letter_trie_type: &LetterTrieType,
+) -> Result<LoadStats> {
+    let mut opt = DisplayDetailOptions::make_no_display().with_memory_sampling(true);
+    opt.label = DisplayDetailOptions::get_test_label(dataset, load_method, letter_trie_type);
+    opt.dataset = format!("{:?}", dataset);
+    dataset.materialize().map_err(in_file(&dataset.path()))?;
+    let trie = T::from_file_test(
+        dataset.filename(),
+        dataset.is_sorted(),
+        load_method,
+        &opt,
+        Some(dataset.word_count()),
+    )?;
+    let root = trie.to_fixed_node();
+    Ok(LoadStats {
+        dataset: opt.dataset.clone(),
+        load_method: format!("{:?}", load_method),
+        trie_type: format!("{:?}", letter_trie_type),
+        steps: opt.step_times(),
+        memory: opt.step_memory(),
+        word_count: root.word_count,
+        node_count: root.node_count,
+        parallel: opt.parallel_load_stats(),
+    })
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1_000.0
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn small_matrix() -> Vec<LoadStats> {
+        run_matrix(
+            &[Dataset::TestSmallSorted, Dataset::TestSmallUnsorted],
+            &[
+                LoadMethod::ReadVecFill,
+                LoadMethod::VecFill,
+                LoadMethod::Continuous,
+                LoadMethod::ContinuousParallel,
+            ],
+            &[LetterTrieType::Base, LetterTrieType::NoParent],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn matrix_covers_every_combination() {
+        let v = small_matrix();
+        assert_eq!(16, v.len());
+        assert_eq!("TestSmallSorted", v[0].dataset);
+        assert_eq!("ReadVecFill", v[0].load_method);
+        assert_eq!("Base", v[0].trie_type);
+        assert_eq!("NoParent", v[1].trie_type);
+        assert_eq!("TestSmallUnsorted", v[15].dataset);
+        assert_eq!("ContinuousParallel", v[15].load_method);
+        for stats in &v {
+            assert_eq!(10, stats.word_count);
+            assert_eq!(28, stats.node_count);
+            assert_eq!(
+                Some(LABEL_STEP_OVERALL),
+                stats.steps.last().map(|(step, _)| step.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn json_schema_is_stable() {
+        for stats in small_matrix() {
+            let value: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+            let object = value.as_object().unwrap();
+            let keys: Vec<&str> = object.keys().map(|key| key.as_str()).collect();
+            let mut expected = vec![
+                "dataset",
+                "load_method",
+                "trie_type",
+                "word_count",
+                "node_count",
+                "steps",
+            ];
+            expected.sort_unstable();
+            let mut keys_sorted = keys.clone();
+            keys_sorted.sort_unstable();
+            assert_eq!(expected, keys_sorted);
+
+            assert_eq!(stats.dataset, object["dataset"].as_str().unwrap());
+            assert_eq!(stats.load_method, object["load_method"].as_str().unwrap());
+            assert_eq!(stats.trie_type, object["trie_type"].as_str().unwrap());
+            assert_eq!(10, object["word_count"].as_u64().unwrap());
+            assert_eq!(28, object["node_count"].as_u64().unwrap());
+            let steps = object["steps"].as_array().unwrap();
+            assert_eq!(stats.steps.len(), steps.len());
+            for ((step, (label, elapsed)), memory) in
+                steps.iter().zip(&stats.steps).zip(&stats.memory)
+            {
+                let step = step.as_object().unwrap();
+                assert_eq!(4, step.len());
+                assert_eq!(label, step["step"].as_str().unwrap());
+                assert!((millis(*elapsed) - step["millis"].as_f64().unwrap()).abs() < 1e-9);
+                let bytes = |key: &str| step[key].as_u64().map(|bytes| bytes as usize);
+                assert_eq!(memory.resident_bytes, bytes("resident_bytes"));
+                assert_eq!(memory.intermediate_bytes, bytes("intermediate_bytes"));
+            }
+        }
+    }
+
+    #[test]
+    fn csv_rows() {
+        let stats = LoadStats {
+            dataset: "TestSmallSorted".to_owned(),
+            load_method: "VecFill".to_owned(),
+            trie_type: "NoParent".to_owned(),
+            steps: vec![
+                (
+                    "make vector, then load".to_owned(),
+                    Duration::from_millis(2),
+                ),
+                (LABEL_STEP_OVERALL.to_owned(), Duration::from_micros(2_500)),
+            ],
+            memory: vec![
+                StepMemory {
+                    step: "make vector, then load".to_owned(),
+                    resident_bytes: Some(8_192),
+                    intermediate_bytes: Some(320),
+                },
+                StepMemory {
+                    step: LABEL_STEP_OVERALL.to_owned(),
+                    resident_bytes: None,
+                    intermediate_bytes: None,
+                },
+            ],
+            word_count: 10,
+            node_count: 28,
+            parallel: None,
+        };
+        let mut out: Vec<u8> = vec![];
+        stats.write_csv_row(&mut out, true).unwrap();
+        stats.write_csv_row(&mut out, false).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(5, lines.len());
+        assert_eq!(
+            "dataset,load_method,trie_type,step,millis,word_count,node_count,resident_bytes,intermediate_bytes",
+            lines[0]
+        );
+        assert_eq!(
+            "TestSmallSorted,VecFill,NoParent,\"make vector, then load\",2,10,28,8192,320",
+            lines[1]
+        );
+        assert_eq!(
+            "TestSmallSorted,VecFill,NoParent,overall load,2.5,10,28,,",
+            lines[2]
+        );
+        assert_eq!(lines[1..3], lines[3..5]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn memory_is_sampled() {
+        for stats in small_matrix() {
+            assert_eq!(stats.steps.len(), stats.memory.len());
+            for ((step, _), memory) in stats.steps.iter().zip(&stats.memory) {
+                assert_eq!(step, &memory.step);
+                assert!(memory.resident_bytes.unwrap() > 0);
+            }
+            assert!(stats.peak_resident_bytes().unwrap() > 0);
+            // The vector load methods hold every word in memory before adding them.
+            match stats.load_method.as_str() {
+                "ReadVecFill" | "VecFill" => assert!(stats.peak_intermediate_bytes().unwrap() > 0),
+                "Continuous" => assert_eq!(None, stats.peak_intermediate_bytes()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn json_escaping() {
+        assert_eq!(r#""a\"b\\c\n\u0001""#, json_string("a\"b\\c\n\u{1}"));
+    }
+}
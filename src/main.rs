@@ -43,11 +43,11 @@ fn main() {
     // let all_datasets = vec![Dataset::TestSmallSorted, Dataset::TestSmallUnsorted,
     //	Dataset::TestMediumSorted, Dataset::TestMediumUnsorted,
     //	Dataset::TestLargeSorted, Dataset::TestLargeUnsorted];
-    let all_datasets = vec![Dataset::TestLargeSorted, Dataset::TestLargeUnsorted];
+    let all_datasets = [Dataset::TestLargeSorted, Dataset::TestLargeUnsorted];
     // let all_methods = vec![LoadMethod::ReadVecFill, LoadMethod::VecFill, LoadMethod::Continuous, LoadMethod::ContinuousParallel];
-    let all_methods = vec![LoadMethod::Continuous, LoadMethod::ContinuousParallel];
+    let all_methods = [LoadMethod::Continuous, LoadMethod::ContinuousParallel];
     // let all_types = vec![LetterTrieType::Base, LetterTrieType::NoParent, LetterTrieType::MinStruct];
-    let all_types = vec![LetterTrieType::Base];
+    let all_types = [LetterTrieType::Base];
 
     // try_combinations(&all_datasets, &all_methods, &all_types);
     // display_small_trie();
@@ -63,7 +63,7 @@ fn main() {
     // print_node_counts();
     // make_test_files(&FILENAME_ENGLISH_3_000, 3_000, 3);
     // make_test_files(&FILENAME_ENGLISH_30, 5);
-    examine_generated_sequences(&FILENAME_ENGLISH_3_000, 3_000, 3);
+    examine_generated_sequences(FILENAME_ENGLISH_3_000, 3_000, 3);
 }
 
 fn examine_generated_sequences(
@@ -71,14 +71,14 @@ fn examine_generated_sequences(
     expected_source_word_count: usize,
     max_depth: usize,
 ) {
-    let example_sequences = words_from_file(&source_filename);
+    let example_sequences = words_from_file(source_filename).expect("Error reading file.");
     assert_eq!(example_sequences.len(), expected_source_word_count);
 
     let generated_words = generate_words(&example_sequences, 10, max_depth);
 }
 
 fn make_test_files(source_filename: &str, expected_source_word_count: usize, max_depth: usize) {
-    let example_sequences = words_from_file(&source_filename);
+    let example_sequences = words_from_file(source_filename).expect("Error reading file.");
     assert_eq!(example_sequences.len(), expected_source_word_count);
 
     let mut words_large = generate_words(&example_sequences, WORD_COUNT_LARGE, max_depth);
@@ -87,13 +87,13 @@ fn make_test_files(source_filename: &str, expected_source_word_count: usize, max
     let mut words_medium = Vec::from_iter(words_large[..WORD_COUNT_MEDIUM].iter().cloned());
     assert_eq!(words_medium.len(), WORD_COUNT_MEDIUM);
 
-    file_from_lines(FILENAME_LARGE_UNSORTED, &words_large);
+    file_from_lines(FILENAME_LARGE_UNSORTED, &words_large).expect("Error writing file.");
     words_large.sort_unstable();
-    file_from_lines(FILENAME_LARGE_SORTED, &words_large);
+    file_from_lines(FILENAME_LARGE_SORTED, &words_large).expect("Error writing file.");
 
-    file_from_lines(FILENAME_MEDIUM_UNSORTED, &words_medium);
+    file_from_lines(FILENAME_MEDIUM_UNSORTED, &words_medium).expect("Error writing file.");
     words_medium.sort_unstable();
-    file_from_lines(FILENAME_MEDIUM_SORTED, &words_medium);
+    file_from_lines(FILENAME_MEDIUM_SORTED, &words_medium).expect("Error writing file.");
 }
 
 /*
@@ -138,39 +138,46 @@ fn print_node_counts() {
 }
 
 fn try_load_words() {
-    dbg!(good_words().len());
-    dbg!(non_words().len());
-    dbg!(large_dataset_words_hash_set().len());
+    dbg!(verification_words(VerificationSet::GoodWords, None)
+        .unwrap()
+        .len());
+    dbg!(verification_words(VerificationSet::NonWords, None)
+        .unwrap()
+        .len());
+    dbg!(words_hash_set(&Dataset::TestLargeSorted).unwrap().len());
 }
 
 fn small_trie() -> BaseLetterTrie {
     BaseLetterTrie::from_file_test(
-        &Dataset::TestSmallSorted.filename(),
+        Dataset::TestSmallSorted.filename(),
         true,
         &LoadMethod::Continuous,
         &DisplayDetailOptions::make_no_display(),
         Some(WORD_COUNT_SMALL),
     )
+    .expect("Error loading file.")
 }
 
 fn medium_trie() -> BaseLetterTrie {
     BaseLetterTrie::from_file_test(
-        &Dataset::TestMediumSorted.filename(),
+        Dataset::TestMediumSorted.filename(),
         true,
         &LoadMethod::Continuous,
         &DisplayDetailOptions::make_no_display(),
         Some(WORD_COUNT_MEDIUM),
     )
+    .expect("Error loading file.")
 }
 
 fn large_trie() -> BaseLetterTrie {
     BaseLetterTrie::from_file_test(
-        &Dataset::TestLargeSorted.filename(),
+        Dataset::TestLargeSorted.filename(),
         true,
         &LoadMethod::ContinuousParallel,
         &DisplayDetailOptions::make_no_display(),
         Some(WORD_COUNT_LARGE),
     )
+    .expect("Error loading file.")
 }
 
 fn try_large_trie() {
@@ -179,13 +186,14 @@ fn try_large_trie() {
     let letter_trie_type = LetterTrieType::Base;
     let opt = DisplayDetailOptions::make_moderate(&dataset, &load_method, &letter_trie_type);
     let t = BaseLetterTrie::from_file_test(
-        &dataset.filename(),
+        dataset.filename(),
         dataset.is_sorted(),
         &load_method,
         &opt,
         Some(WORD_COUNT_LARGE),
-    );
-    println!("{:#?}", &t.to_fixed_node());
+    )
+    .expect("Error loading file.");
+    println!("{:#?}", t.to_fixed_node());
 }
 
 fn try_find_loop() {
@@ -223,27 +231,27 @@ fn try_find_loop_like_iterator() {
 fn try_freeze() {
     let fn_name = "try_freeze()";
     let mut t = large_trie();
-    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, || t.print_root_alt());
+    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, None, || t.print_root_alt());
     assert_large_root(&t.to_fixed_node());
 
-    print_elapsed(true, fn_name, LABEL_FREEZE, || t.freeze());
-    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, || t.print_root_alt());
+    print_elapsed(true, fn_name, LABEL_FREEZE, None, || t.freeze());
+    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, None, || t.print_root_alt());
     assert_large_root(&t.to_fixed_node());
 
-    print_elapsed(true, fn_name, LABEL_UNFREEZE, || t.unfreeze());
-    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, || t.print_root_alt());
+    print_elapsed(true, fn_name, LABEL_UNFREEZE, None, || t.unfreeze());
+    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, None, || t.print_root_alt());
     assert_large_root(&t.to_fixed_node());
 }
 
 fn display_small_trie() {
-    println!("{:#?}", &small_trie());
+    println!("{:#?}", small_trie());
 }
 
 fn try_combinations(datasets: &[Dataset], methods: &[LoadMethod], types: &[LetterTrieType]) {
     for one_dataset in datasets {
         for one_method in methods {
             for one_type in types {
-                try_one_combination(&one_dataset, &one_method, &one_type);
+                try_one_combination(one_dataset, one_method, one_type);
             }
         }
     }
@@ -260,6 +268,7 @@ fn try_one_combination(
     let opt = DisplayDetailOptions::make_moderate(dataset, load_method, letter_trie_type);
     let expected_word_count = dataset.word_count();
     if USE_CHAR_GET_COUNTER {
+        CharGetCounter::set_enabled(true);
         CharGetCounter::reset();
     }
     match letter_trie_type {
@@ -267,25 +276,28 @@ fn try_one_combination(
             BaseLetterTrie::from_file_test(
                 filename,
                 is_sorted,
-                &load_method,
+                load_method,
                 &opt,
                 Some(expected_word_count),
-            );
+            )
+            .expect("Error loading file.");
         }
         LetterTrieType::NoParent => {
             if is_sorted || *load_method != LoadMethod::ContinuousParallel {
                 NoParentLetterTrie::from_file_test(
                     filename,
                     is_sorted,
-                    &load_method,
+                    load_method,
                     &opt,
                     Some(expected_word_count),
-                );
+                )
+                .expect("Error loading file.");
             }
         }
+        other => println!("No combinations are tried for {}.", other),
     };
     if USE_CHAR_GET_COUNTER {
-        CharGetCounter::print_optional();
+        CharGetCounter::report(&opt);
     }
 }
 
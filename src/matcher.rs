@@ -0,0 +1,567 @@
+//! Finding every word of a trie that appears in a text in one pass, using the Aho–Corasick algorithm.
+//!
+//! Walking the trie from each position of the text takes time proportional to the length of the text times the
+//! length of the average word. A `TrieMatcher` adds a failure link to each node, pointing to the node for the
+//! longest proper suffix of its prefix that's also in the trie, so that the scan never has to back up and takes
+//! time proportional to the length of the text plus the number of matches.
+
+use alloc::collections::VecDeque;
+use core::cmp::Reverse;
+use core::iter::FusedIterator;
+use core::str::CharIndices;
+
+use crate::*;
+
+// A state index that means there isn't one.
+const NO_STATE: u32 = u32::MAX;
+
+struct State {
+    // The transitions to child states, ordered by character so they can be binary searched.
+    transitions: Vec<(char, u32)>,
+    // The state for the longest proper suffix of this state's prefix that's also a prefix in the trie.
+    fail: u32,
+    // The index in TrieMatcher::words of the word ending at this state, or NO_STATE.
+    word: u32,
+    // The nearest state along the failure links that has a word, or NO_STATE.
+    output: u32,
+    // The number of characters in this state's prefix.
+    depth: usize,
+}
+
+impl State {
+    fn next(&self, c: char) -> Option<u32> {
+        self.transitions
+            .binary_search_by_key(&c, |(c, _)| *c)
+            .ok()
+            .map(|i| self.transitions[i].1)
+    }
+}
+
+/// A word found by `TrieMatcher::scan()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match<'m> {
+    /// The byte offset in the text where the match starts.
+    pub start: usize,
+    /// The byte offset in the text just past the end of the match.
+    pub end: usize,
+    /// The word as it's stored in the trie, which is in lower case even if the text isn't.
+    pub word: &'m str,
+}
+
+/// The words of a trie compiled into an automaton for scanning text, created with
+/// `LetterTrie::compile_matcher()`. It's a copy, so later changes to the trie don't affect it.
+pub struct TrieMatcher {
+    states: Vec<State>,
+    words: Vec<String>,
+    // The length in characters of the longest word.
+    max_depth: usize,
+}
+
+impl TrieMatcher {
+    /// Compile the words of `trie` into a matcher.
+    pub fn from_trie<T: LetterTrie + ?Sized>(trie: &T) -> Self {
+        let mut states: Vec<State> = vec![];
+        let mut words: Vec<String> = vec![];
+        // The state for each node on the path from the root to the node being visited.
+        let mut path: Vec<u32> = vec![];
+        trie.visit("", &mut |node| {
+            let id = states.len() as u32;
+            path.truncate(node.depth);
+            if let Some(parent) = path.last() {
+                // Children are visited in character order so the transitions stay sorted.
+                states[*parent as usize].transitions.push((node.c, id));
+            }
+            let word = if node.is_word {
+                words.push(node.prefix.to_owned());
+                (words.len() - 1) as u32
+            } else {
+                NO_STATE
+            };
+            states.push(State {
+                transitions: vec![],
+                fail: 0,
+                word,
+                output: NO_STATE,
+                depth: node.depth,
+            });
+            path.push(id);
+            true
+        });
+        let max_depth = states.iter().map(|state| state.depth).max().unwrap_or(0);
+        let mut matcher = Self {
+            states,
+            words,
+            max_depth,
+        };
+        matcher.set_failure_links();
+        matcher
+    }
+
+    // Breadth first, so that the failure link of every shallower state is set before it's needed.
+    fn set_failure_links(&mut self) {
+        let mut queue: VecDeque<u32> = VecDeque::new();
+        queue.push_back(0);
+        while let Some(id) = queue.pop_front() {
+            for i in 0..self.states[id as usize].transitions.len() {
+                let (c, child) = self.states[id as usize].transitions[i];
+                let fail = if id == 0 {
+                    0
+                } else {
+                    self.advance(self.states[id as usize].fail, c)
+                };
+                let fail_state = &self.states[fail as usize];
+                let output = if fail_state.word != NO_STATE {
+                    fail
+                } else {
+                    fail_state.output
+                };
+                let child_state = &mut self.states[child as usize];
+                child_state.fail = fail;
+                child_state.output = output;
+                queue.push_back(child);
+            }
+        }
+    }
+
+    // The state after reading c in state id, following failure links as needed.
+    fn advance(&self, mut id: u32, c: char) -> u32 {
+        loop {
+            if let Some(next) = self.states[id as usize].next(c) {
+                return next;
+            }
+            if id == 0 {
+                return 0;
+            }
+            id = self.states[id as usize].fail;
+        }
+    }
+
+    /// The number of words the matcher looks for.
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Find every occurrence of every word in `text` in a single pass, including matches that overlap or are
+    /// inside other matches. Matches come in order of where they end, and longest first among those that end at
+    /// the same place.
+    ///
+    /// The text is lowercased a character at a time to match the way words are stored, except that a capital
+    /// sigma at the end of a word becomes a final sigma as it does in `str::to_lowercase()`. The offsets of each
+    /// match are byte offsets in the original text, and they're always on character boundaries, so
+    /// `&text[m.start..m.end]` is the text that matched even where one of its characters lowercases to more than
+    /// one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let matcher = NoParentLetterTrie::from_words(vec!["he", "she", "hers", "his"]).compile_matcher();
+    /// let matches: Vec<(usize, usize, &str)> = matcher
+    ///     .scan("Ushers")
+    ///     .map(|m| (m.start, m.end, m.word))
+    ///     .collect();
+    /// assert_eq!(vec![(1, 4, "she"), (2, 4, "he"), (2, 6, "hers")], matches);
+    /// ```
+    pub fn scan<'m, 't>(&'m self, text: &'t str) -> Matches<'m, 't> {
+        Matches {
+            matcher: self,
+            text,
+            chars: text.char_indices(),
+            lowercase: None,
+            state: 0,
+            output: NO_STATE,
+            starts: VecDeque::with_capacity(self.max_depth),
+            offset: 0,
+            end: 0,
+        }
+    }
+
+    /// Find the matches that a left to right search would pick if it always took the longest word starting at
+    /// the leftmost position and then moved on past it, so that no two matches overlap. Unlike `scan()` this
+    /// collects every match before returning.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let matcher = NoParentLetterTrie::from_words(vec!["he", "she", "hers", "his"]).compile_matcher();
+    /// let words: Vec<&str> = matcher
+    ///     .scan_leftmost_longest("ushers and his")
+    ///     .map(|m| m.word)
+    ///     .collect();
+    /// assert_eq!(vec!["she", "his"], words);
+    /// ```
+    pub fn scan_leftmost_longest<'m>(&'m self, text: &str) -> alloc::vec::IntoIter<Match<'m>> {
+        let mut matches: Vec<Match> = self.scan(text).collect();
+        matches.sort_unstable_by_key(|m| (m.start, Reverse(m.end)));
+        let mut chosen: Vec<Match> = vec![];
+        for m in matches {
+            if chosen
+                .last()
+                .map(|last| m.start >= last.end)
+                .unwrap_or(true)
+            {
+                chosen.push(m);
+            }
+        }
+        chosen.into_iter()
+    }
+}
+
+/// The iterator returned by `TrieMatcher::scan()`.
+pub struct Matches<'m, 't> {
+    matcher: &'m TrieMatcher,
+    text: &'t str,
+    chars: CharIndices<'t>,
+    // The rest of the lowercase form of the current character of the text, which can be more than one character.
+    lowercase: Option<core::char::ToLowercase>,
+    state: u32,
+    // The next state whose word is still to be returned for the current position, or NO_STATE.
+    output: u32,
+    // The byte offset in the text of the character behind each of the most recent characters read, enough of
+    // them to find the start of the longest word. All of the characters in the lowercase form of one character
+    // of the text have that character's offset.
+    starts: VecDeque<usize>,
+    // The byte offsets of the start and just past the end of the text character that the last character read
+    // came from.
+    offset: usize,
+    end: usize,
+}
+
+impl<'m, 't> Iterator for Matches<'m, 't> {
+    type Item = Match<'m>;
+
+    fn next(&mut self) -> Option<Match<'m>> {
+        let states = &self.matcher.states;
+        loop {
+            if self.output != NO_STATE {
+                let state = &states[self.output as usize];
+                let start = self.starts[self.starts.len() - state.depth];
+                self.output = state.output;
+                return Some(Match {
+                    start,
+                    end: self.end,
+                    word: &self.matcher.words[state.word as usize],
+                });
+            }
+            let c = match self
+                .lowercase
+                .as_mut()
+                .and_then(|lowercase| lowercase.next())
+            {
+                Some(c) => c,
+                None => {
+                    let (offset, text_c) = self.chars.next()?;
+                    self.offset = offset;
+                    self.end = offset + text_c.len_utf8();
+                    if text_c == 'Σ' {
+                        self.lowercase = None;
+                        lowercase_sigma(self.text, offset)
+                    } else {
+                        let mut lowercase = text_c.to_lowercase();
+                        // The lowercase form of a character is never empty, so the fallback is never used.
+                        let c = lowercase.next().unwrap_or(text_c);
+                        self.lowercase = Some(lowercase);
+                        c
+                    }
+                }
+            };
+            if self.matcher.max_depth > 0 {
+                if self.starts.len() == self.matcher.max_depth {
+                    self.starts.pop_front();
+                }
+                self.starts.push_back(self.offset);
+            }
+            self.state = self.matcher.advance(self.state, c);
+            self.output = first_output(states, self.state);
+        }
+    }
+}
+
+impl<'m, 't> FusedIterator for Matches<'m, 't> {}
+
+// The lowercase form of the capital sigma at offset in text. It's a final sigma at the end of a word, as
+// str::to_lowercase() makes it and so as the trie stores it, rather than the plain sigma that lowercasing the letter
+// on its own gives. That's decided by the nearest letter on each side that isn't case-ignorable: the one before has
+// to be cased and the one after mustn't be. Each side only looks as far as that letter, and a sigma is one itself,
+// so no character is looked at for more than one sigma on each side and the scan stays linear in the text.
+fn lowercase_sigma(text: &str, offset: usize) -> char {
+    let is_cased = |c: Option<char>| c.is_some_and(|c| sigma_is_final(&[c]));
+    let before = text[..offset]
+        .chars()
+        .rev()
+        .find(|c| !is_case_ignorable(*c));
+    let after = text[offset + 'Σ'.len_utf8()..]
+        .chars()
+        .find(|c| !is_case_ignorable(*c));
+    if is_cased(before) && !is_cased(after) {
+        'ς'
+    } else {
+        'σ'
+    }
+}
+
+// Whether str::to_lowercase() makes a capital sigma after these characters a final sigma. This is the only stable
+// way to get at the Cased and Case_Ignorable properties it uses, so it's only called for the letters next to a
+// sigma.
+fn sigma_is_final(chars: &[char]) -> bool {
+    let text: String = chars.iter().chain(Some(&'Σ')).collect();
+    text.to_lowercase().ends_with('ς')
+}
+
+// A case-ignorable character such as an apostrophe or a combining accent is skipped when looking for the letter
+// before or after a sigma. It's one that lets a sigma after a capital be final but isn't cased on its own once
+// skipped.
+fn is_case_ignorable(c: char) -> bool {
+    if c.is_ascii_alphanumeric() || c.is_whitespace() {
+        return false;
+    }
+    sigma_is_final(&['Α', c]) && !sigma_is_final(&[c])
+}
+
+// The state itself if it has a word, otherwise the nearest one along its failure links that does.
+fn first_output(states: &[State], id: u32) -> u32 {
+    let state = &states[id as usize];
+    if state.word != NO_STATE {
+        id
+    } else {
+        state.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use test::Bencher;
+
+    // The text's characters in lowercase, each with the offsets of the text character it came from.
+    fn lowercase_chars(text: &str) -> Vec<(char, usize, usize)> {
+        text.char_indices()
+            .flat_map(|(offset, c)| {
+                c.to_lowercase()
+                    .map(move |lower| (lower, offset, offset + c.len_utf8()))
+            })
+            .collect()
+    }
+
+    // Walk the trie from every position without using the failure links, as (start, end, word).
+    fn naive_scan<'m>(matcher: &'m TrieMatcher, text: &str) -> Vec<(usize, usize, &'m str)> {
+        let chars = lowercase_chars(text);
+        let mut matches = vec![];
+        for i in 0..chars.len() {
+            let mut id = 0;
+            for (c, _, end) in &chars[i..] {
+                id = match matcher.states[id as usize].next(*c) {
+                    Some(next) => next,
+                    None => break,
+                };
+                let word = matcher.states[id as usize].word;
+                if word != NO_STATE {
+                    matches.push((chars[i].1, *end, matcher.words[word as usize].as_str()));
+                }
+            }
+        }
+        matches
+    }
+
+    fn scan_sorted<'m>(matcher: &'m TrieMatcher, text: &str) -> Vec<(usize, usize, &'m str)> {
+        let mut matches: Vec<(usize, usize, &str)> = matcher
+            .scan(text)
+            .map(|m| (m.start, m.end, m.word))
+            .collect();
+        matches.sort_unstable();
+        matches
+    }
+
+    // Words from the trie run together with random letters between them, so there are plenty of matches that
+    // overlap.
+    fn medium_text<T: LetterTrie>(t: &T) -> String {
+        let noise = generator::generate_words(2_000, 1, 4, 11);
+        let mut text: String = t
+            .words()
+            .step_by(10)
+            .zip(noise)
+            .flat_map(|(word, noise)| vec![word.to_uppercase(), noise])
+            .collect();
+        text.push_str(" The ÉTÉ crossed İstanbul, STRASSE");
+        text
+    }
+
+    #[test]
+    fn matches_naive_scan() {
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous)
+                .unwrap();
+        let matcher = t.compile_matcher();
+        assert_eq!(t.count_with_prefix(""), matcher.word_count());
+        let text = medium_text(&t);
+        let mut expected = naive_scan(&matcher, &text);
+        expected.sort_unstable();
+        assert!(expected.len() > 2_000);
+        assert_eq!(expected, scan_sorted(&matcher, &text));
+        for m in matcher.scan(&text) {
+            assert_eq!(m.word, text[m.start..m.end].to_lowercase());
+        }
+    }
+
+    #[test]
+    fn overlapping_and_case() {
+        let matcher =
+            BaseLetterTrie::from_words(vec!["a", "ab", "bab", "bc", "bca", "c", "caa", "é", "i"])
+                .compile_matcher();
+        assert_eq!(
+            vec![
+                (0, 1, "a"),
+                (0, 2, "ab"),
+                (1, 3, "bc"),
+                (1, 4, "bca"),
+                (2, 3, "c"),
+                (3, 4, "a"),
+                (4, 6, "é"),
+                (6, 8, "i")
+            ],
+            scan_sorted(&matcher, "ABcA\u{c9}\u{130}")
+        );
+        let words: Vec<&str> = matcher
+            .scan_leftmost_longest("abcaabab")
+            .map(|m| m.word)
+            .collect();
+        assert_eq!(vec!["ab", "caa", "bab"], words);
+    }
+
+    #[test]
+    fn final_sigma() {
+        let t = NoParentLetterTrie::from_words(vec!["ΟΔΟΣ", "σοφια", "σ"]);
+        assert!(t.contains("οδος"));
+        let matcher = t.compile_matcher();
+        // A capital sigma at the end of a word is lowercased to a final sigma as the word was when it was stored,
+        // but not one at the start or in the middle.
+        assert_eq!(
+            vec![
+                (0, 8, "οδος"),
+                (10, 12, "σ"),
+                (10, 20, "σοφια"),
+                (31, 33, "σ")
+            ],
+            scan_sorted(&matcher, "ΟΔΟΣ, ΣΟΦΙΑ and ΟΔΟΣΟ")
+        );
+        assert_eq!(vec![(2, 4, "σ")], scan_sorted(&matcher, "a Σ"));
+
+        // Each sigma is lowercased as str::to_lowercase() lowercases it in the whole text, including where an
+        // apostrophe or a combining accent comes between it and the letter next to it.
+        let texts = [
+            "ΟΔΟΣ'",
+            "ΟΔΟ'Σ",
+            "Ο\u{301}ΔΟΣ\u{301}",
+            "ΟΔΟΣ'Α",
+            "ΑΣ.",
+            "ΟΔΟΣ123",
+            "Σ",
+            "ΣΣΣ",
+            "ǅΣ",
+            "ʰΣ x",
+        ];
+        for text in &texts {
+            let lowercase: Vec<char> = text.to_lowercase().chars().collect();
+            let mut index = 0;
+            for (offset, c) in text.char_indices() {
+                if c == 'Σ' {
+                    assert_eq!(
+                        lowercase[index],
+                        lowercase_sigma(text, offset),
+                        "{} {}",
+                        text,
+                        offset
+                    );
+                }
+                index += c.to_lowercase().count();
+            }
+        }
+    }
+
+    // Each sigma only looks at the letters next to it, so a megabyte of capital sigmas with no space between them
+    // takes no longer than any other text of that size rather than going back over the text for each one.
+    #[test]
+    fn many_sigmas() {
+        let matcher = NoParentLetterTrie::from_words(vec!["σσ", "σς"]).compile_matcher();
+        let text = "Σ".repeat(500_000);
+        let start = std::time::Instant::now();
+        let mut words: BTreeMap<&str, usize> = BTreeMap::new();
+        for m in matcher.scan(&text) {
+            *words.entry(m.word).or_insert(0) += 1;
+        }
+        let elapsed = start.elapsed();
+        assert_eq!(Some(&499_998), words.get("σσ"));
+        assert_eq!(Some(&1), words.get("σς"));
+        assert!(elapsed.as_secs() < 10, "{:?}", elapsed);
+    }
+
+    #[test]
+    fn leftmost_longest_matches_naive() {
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous)
+                .unwrap();
+        let matcher = t.compile_matcher();
+        let text = medium_text(&t);
+        // Take the longest match at each place a match starts and skip the places inside it.
+        let mut longest: BTreeMap<usize, (usize, &str)> = BTreeMap::new();
+        for (start, end, word) in naive_scan(&matcher, &text) {
+            let entry = longest.entry(start).or_insert((end, word));
+            if end > entry.0 {
+                *entry = (end, word);
+            }
+        }
+        let mut expected = vec![];
+        let mut position = 0;
+        for (start, (end, word)) in longest {
+            if start >= position {
+                expected.push((start, end, word));
+                position = end;
+            }
+        }
+        let found: Vec<(usize, usize, &str)> = matcher
+            .scan_leftmost_longest(&text)
+            .map(|m| (m.start, m.end, m.word))
+            .collect();
+        assert_eq!(expected, found);
+    }
+
+    #[test]
+    fn empty_inputs() {
+        let empty = NoParentLetterTrie::new().compile_matcher();
+        assert_eq!(0, empty.scan("some text").count());
+        let matcher = NoParentLetterTrie::from_words(vec!["a"]).compile_matcher();
+        assert_eq!(0, matcher.scan("").count());
+        assert_eq!(3, matcher.scan("aAa").count());
+    }
+
+    fn large_text() -> String {
+        // About a megabyte.
+        generator::generate_words(150_000, 1, 11, 5).join(" ")
+    }
+
+    fn large_matcher() -> TrieMatcher {
+        NoParentLetterTrie::from_dataset(&Dataset::TestLargeSorted, &LoadMethod::ContinuousParallel)
+            .unwrap()
+            .compile_matcher()
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_scan_matcher(b: &mut Bencher) {
+        let matcher = large_matcher();
+        let text = large_text();
+        b.iter(|| matcher.scan(&text).count());
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_scan_naive(b: &mut Bencher) {
+        let matcher = large_matcher();
+        let text = large_text();
+        b.iter(|| naive_scan(&matcher, &text).len());
+    }
+}
@@ -0,0 +1,258 @@
+//! The counts behind `LetterTrie::find_with_metrics()` and the other `_with_metrics` queries, for finding out what
+//! one query cost without turning on `CharGetCounter` for every query.
+//!
+//! The counts are kept in a `FindMetrics` on the stack of the query that fills it in, so nothing is shared and
+//! nothing is locked, and the queries without metrics don't count anything.
+
+use crate::*;
+
+/// What one query did to get its answer.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = BaseLetterTrie::from_words(vec!["cat", "car", "cart", "dog"]);
+/// let (node, metrics) = trie.find_with_metrics("cart");
+/// assert_eq!(trie.find("cart"), node);
+/// assert_eq!(5, metrics.nodes_visited);
+/// assert_eq!(4, metrics.terminal_depth);
+/// assert_eq!(FindOutcome::Word, metrics.outcome);
+///
+/// let (_, metrics) = trie.find_with_metrics("cab");
+/// assert_eq!(2, metrics.terminal_depth);
+/// assert_eq!(FindOutcome::NotFound, metrics.outcome);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FindMetrics {
+    /// The nodes the query reached, counting the root.
+    pub nodes_visited: usize,
+    /// The letters of the trie compared with a letter of the query. A `NoParentLetterTrie` searches a node's
+    /// children by binary search. A `BaseLetterTrie` keeps them in a `BTreeMap`, and its comparisons are counted
+    /// as a scan through the letters in order up to the first that isn't less than the one wanted, which is how
+    /// the map searches each of its nodes.
+    pub comparisons: usize,
+    /// The depth of the deepest node that matched, so 0 if not even the first letter did.
+    pub terminal_depth: usize,
+    /// Whether the query found a word, only a prefix, or nothing.
+    pub outcome: FindOutcome,
+}
+
+/// How a query with metrics ended.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FindOutcome {
+    /// The query ended at a word, or for a search, found at least one.
+    Word,
+    /// The query ended at a node that's only the start of words, or for a search, matched some letters without
+    /// finding a word.
+    PrefixOnly,
+    /// The query ran out of trie before it ran out of letters, or for a search, matched nothing. As in `find()`,
+    /// the empty prefix isn't found.
+    #[default]
+    NotFound,
+}
+
+impl FindMetrics {
+    // The counts at the start of a walk down from the root.
+    pub(crate) fn at_root() -> Self {
+        Self {
+            nodes_visited: 1,
+            ..Default::default()
+        }
+    }
+
+    // Count one letter of the query, which took comparisons to look for among the children of the current node.
+    pub(crate) fn step(&mut self, comparisons: usize, is_found: bool) {
+        self.comparisons += comparisons;
+        if is_found {
+            self.nodes_visited += 1;
+            self.terminal_depth += 1;
+        }
+    }
+
+    // Set the outcome from whether the node at the end of the walk is a word, or None if it wasn't found or was
+    // the root.
+    pub(crate) fn finish(&mut self, is_word: Option<bool>) {
+        self.outcome = match is_word {
+            Some(true) => FindOutcome::Word,
+            Some(false) => FindOutcome::PrefixOnly,
+            None => FindOutcome::NotFound,
+        };
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn metrics(
+        nodes_visited: usize,
+        comparisons: usize,
+        terminal_depth: usize,
+        outcome: FindOutcome,
+    ) -> FindMetrics {
+        FindMetrics {
+            nodes_visited,
+            comparisons,
+            terminal_depth,
+            outcome,
+        }
+    }
+
+    // In a trie where no node has more than one child, each letter that's found or not takes one comparison
+    // whatever the children are kept in.
+    fn check_chain<T: LetterTrie>() {
+        use FindOutcome::*;
+        let t = T::from_words(vec!["cross", "crossed"]);
+        assert_eq!(metrics(6, 5, 5, Word), t.find_with_metrics("Cross").1);
+        assert_eq!(metrics(4, 3, 3, PrefixOnly), t.find_with_metrics("cro").1);
+        assert_eq!(metrics(8, 7, 7, Word), t.find_with_metrics("crossed").1);
+        // The miss is found when the one child of "cro" isn't an "x", and nothing below it is looked at.
+        assert_eq!(metrics(4, 4, 3, NotFound), t.find_with_metrics("croxxxx").1);
+        // A node without children has nothing to compare.
+        assert_eq!(
+            metrics(8, 7, 7, NotFound),
+            t.find_with_metrics("crossedly").1
+        );
+        assert_eq!(metrics(1, 0, 0, NotFound), t.find_with_metrics("").1);
+
+        assert_eq!(
+            (true, metrics(6, 5, 5, Word)),
+            t.contains_with_metrics("cross")
+        );
+        assert_eq!(
+            (false, metrics(4, 3, 3, PrefixOnly)),
+            t.contains_with_metrics("cro")
+        );
+        assert_eq!(
+            (false, metrics(3, 3, 2, NotFound)),
+            t.contains_with_metrics("cry")
+        );
+        // An ASCII word longer than any stored word is turned away at the root.
+        assert_eq!(
+            (false, metrics(1, 0, 0, NotFound)),
+            t.contains_with_metrics("crossedness")
+        );
+        assert_eq!(
+            (false, metrics(1, 0, 0, NotFound)),
+            t.contains_with_metrics("")
+        );
+
+        let t = T::from_words(Vec::<String>::new());
+        assert_eq!((None, metrics(1, 0, 0, NotFound)), t.find_with_metrics("a"));
+    }
+
+    #[test]
+    fn chain() {
+        check_chain::<BaseLetterTrie>();
+        check_chain::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn base_scans_in_order() {
+        use FindOutcome::*;
+        let t = BaseLetterTrie::from_words(vec!["cat", "car", "cart", "dog", "bee"]);
+        // The root's letters are b, c and d, so "c" is the second; "ca" has r and t.
+        assert_eq!(
+            metrics(5, 2 + 1 + 1 + 1, 4, Word),
+            t.find_with_metrics("cart").1
+        );
+        assert_eq!(metrics(4, 2 + 1 + 2, 3, Word), t.find_with_metrics("cat").1);
+        // The scan stops at "t" since it's past "s".
+        assert_eq!(
+            metrics(3, 2 + 1 + 2, 2, NotFound),
+            t.find_with_metrics("cas").1
+        );
+        // Past every letter of the root.
+        assert_eq!(metrics(1, 3, 0, NotFound), t.find_with_metrics("e").1);
+        assert_eq!(metrics(1, 1, 0, NotFound), t.find_with_metrics("a").1);
+    }
+
+    #[test]
+    fn no_parent_searches_by_halves() {
+        use FindOutcome::*;
+        let t = NoParentLetterTrie::from_words(vec!["cat", "car", "cart", "dog", "bee"]);
+        let (node, m) = t.find_with_metrics("cart");
+        assert!(node.unwrap().is_word);
+        assert_eq!((5, 4, Word), (m.nodes_visited, m.terminal_depth, m.outcome));
+        // The binary searches of the root's three letters and the two of "ca" take a few comparisons each, and
+        // the single children one each.
+        assert!((1 + 1 + 1 + 1..=3 + 1 + 2 + 1).contains(&m.comparisons));
+        let (_, m) = t.find_with_metrics("e");
+        assert_eq!(
+            (1, 0, NotFound),
+            (m.nodes_visited, m.terminal_depth, m.outcome)
+        );
+        assert!((1..=3).contains(&m.comparisons));
+    }
+
+    fn check_agrees_with_find<T: LetterTrie>() {
+        let t = T::from_dataset(&Dataset::TestMediumUnsorted, &LoadMethod::Continuous).unwrap();
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let mut probes: Vec<String> = words.iter().step_by(97).cloned().collect();
+        for word in words.iter().step_by(101) {
+            probes.push(word.to_uppercase());
+            probes.push(format!("{}q", word));
+            let prefix: String = word.chars().take(word.chars().count() / 2).collect();
+            probes.push(prefix);
+        }
+        probes.extend(verification_words(VerificationSet::NonWords, None).unwrap());
+        probes.extend(
+            ["", "straße", "STRASSE", "ÉCLAIR", "Σ"]
+                .iter()
+                .map(|s| s.to_string()),
+        );
+        for probe in &probes {
+            let (node, m) = t.find_with_metrics(probe);
+            let expected = t.find(probe);
+            assert_eq!(expected, node, "{}", probe);
+            let expected_outcome = match &expected {
+                Some(node) if node.is_word => FindOutcome::Word,
+                Some(_) => FindOutcome::PrefixOnly,
+                None => FindOutcome::NotFound,
+            };
+            assert_eq!(expected_outcome, m.outcome, "{}", probe);
+            assert_eq!(m.nodes_visited, m.terminal_depth + 1, "{}", probe);
+            if let Some(node) = expected {
+                assert_eq!(node.depth, m.terminal_depth, "{}", probe);
+            }
+            let (is_word, _) = t.contains_with_metrics(probe);
+            assert_eq!(t.contains(probe), is_word, "{}", probe);
+        }
+    }
+
+    #[test]
+    fn agrees_with_find() {
+        check_agrees_with_find::<BaseLetterTrie>();
+        check_agrees_with_find::<NoParentLetterTrie>();
+    }
+
+    fn check_pattern_metrics<T: LetterTrie>() {
+        use FindOutcome::*;
+        let t = T::from_words(vec!["cross", "crest", "crust", "rose"]);
+        let (guess, m) = t.best_guess_with_metrics("cr?s?", "");
+        assert_eq!(t.best_guess("cr?s?", ""), guess);
+        // The root, c and r, then r, then e, o and u, each with one letter below and one below that. The r of
+        // "rose" is compared and ruled out.
+        assert_eq!(
+            metrics(1 + 2 + 1 + 3 + 3 + 3, 2 + 1 + 3 + 3 + 3, 5, Word),
+            m
+        );
+        let (guess, m) = t.best_guess_with_metrics("cr?x?", "");
+        assert_eq!(None, guess);
+        assert_eq!(metrics(1 + 2 + 1 + 3 + 3, 2 + 1 + 3 + 3, 3, PrefixOnly), m);
+        let (_, m) = t.best_guess_with_metrics("q????", "");
+        assert_eq!(metrics(3, 2, 0, NotFound), m);
+        assert_eq!(
+            (None, metrics(0, 0, 0, NotFound)),
+            t.best_guess_with_metrics("", "")
+        );
+    }
+
+    #[test]
+    fn pattern_metrics() {
+        check_pattern_metrics::<BaseLetterTrie>();
+        check_pattern_metrics::<NoParentLetterTrie>();
+    }
+}
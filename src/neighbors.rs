@@ -0,0 +1,210 @@
+//! The words on either side of a string in sorted order, for `LetterTrie::neighbors()`, as when a dictionary
+//! browser shows the part of the list around what's been typed.
+//!
+//! The words after the probe come from one `visit()` that goes down the probe's letters and then on through the
+//! nodes past it, skipping each branch that only has words before the probe and stopping once it has enough. The
+//! words before come the same way from one `visit_rev()`. That walk reaches a node before the longer words under
+//! it, which come after it in sorted order, so each word waits on a stack until the walk leaves its node.
+//!
+//! Each walk keeps its own stack of the nodes it still has to visit, so neither goes back to the root for the
+//! next word.
+
+use crate::*;
+
+/// The words around a string in sorted order, from `LetterTrie::neighbors()`.
+///
+/// The probe itself is in neither list. Whether it's a word is in `is_word`, so a browser can show it between
+/// the two lists or mark that it's missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Neighbors {
+    /// The words just before the probe, in sorted order, so the nearest is last.
+    pub before: Vec<String>,
+    /// Whether the probe is a word in the trie.
+    pub is_word: bool,
+    /// The words just after the probe, in sorted order, so the nearest is first.
+    pub after: Vec<String>,
+}
+
+pub(crate) fn neighbors<T: TrieQuery + ?Sized>(
+    trie: &T,
+    probe: &str,
+    before: usize,
+    after: usize,
+) -> Neighbors {
+    // The trie's letters are lowercase, so the order is that of the lowercased strings.
+    let probe = probe.trim().to_lowercase();
+    let mut neighbors = Neighbors {
+        before: Vec::with_capacity(before),
+        after: Vec::with_capacity(after),
+        ..Default::default()
+    };
+
+    // The walk goes down the probe's letters while the prefix is the start of the probe, and anything to one side
+    // of that path is either all before the probe or all after it.
+    trie.visit("", &mut |node| {
+        if probe.starts_with(node.prefix) {
+            if node.prefix.len() < probe.len() {
+                return true;
+            }
+            neighbors.is_word = node.is_word;
+            after > 0
+        } else if node.prefix > probe.as_str() && neighbors.after.len() < after {
+            if node.is_word {
+                neighbors.after.push(node.word().to_owned());
+            }
+            true
+        } else {
+            false
+        }
+    });
+
+    // The words whose nodes have been reached but not left, with their depths. A word is taken once the walk gets
+    // to a node no deeper than it, which means there's nothing more under it.
+    let mut waiting: Vec<(usize, String)> = vec![];
+    let words = &mut neighbors.before;
+    let mut take_finished = |waiting: &mut Vec<(usize, String)>, depth: usize| {
+        while let Some((_, word)) = waiting.pop_if(|(d, _)| *d >= depth) {
+            if words.len() < before {
+                words.push(word);
+            }
+        }
+        words.len() < before
+    };
+    trie.visit_rev("", &mut |node| {
+        if !take_finished(&mut waiting, node.depth) {
+            return false;
+        }
+        let is_before = node.prefix < probe.as_str();
+        if is_before && node.is_word {
+            waiting.push((node.depth, node.word().to_owned()));
+        }
+        // The start of the probe is before it but may have words after it underneath.
+        is_before
+    });
+    take_finished(&mut waiting, 0);
+    neighbors.before.reverse();
+    neighbors
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use crate::generator::SplitMix64;
+
+    // The answer from a sorted, deduplicated list of the lowercased words.
+    fn expected(words: &[String], probe: &str, before: usize, after: usize) -> Neighbors {
+        let probe = probe.to_lowercase();
+        let index = words.partition_point(|word| *word < probe);
+        let is_word = words.get(index) == Some(&probe);
+        let after_start = index + is_word as usize;
+        Neighbors {
+            before: words[index.saturating_sub(before)..index].to_vec(),
+            is_word,
+            after: words[after_start..(after_start + after).min(words.len())].to_vec(),
+        }
+    }
+
+    fn check_random_probes<T: LetterTrie>() {
+        let t = T::from_dataset(&Dataset::TestMediumUnsorted, &LoadMethod::Continuous).unwrap();
+        let mut words: Vec<String> = words_from_file(Dataset::TestMediumSorted.filename())
+            .unwrap()
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect();
+        words.sort();
+        words.dedup();
+        let mut rng = SplitMix64(182);
+        for i in 0..500 {
+            let word = &words[rng.below(words.len() as u64) as usize];
+            // Words, prefixes of words, words with a letter changed or added, and made-up strings.
+            let probe: String = match i % 5 {
+                0 => word.clone(),
+                1 => word.chars().take(2).collect(),
+                2 => {
+                    let mut chars: Vec<char> = word.chars().collect();
+                    let last = chars.len() - 1;
+                    chars[last] = char::from(b'a' + rng.below(26) as u8);
+                    chars.into_iter().collect()
+                }
+                3 => format!("{}{}", word, char::from(b'a' + rng.below(26) as u8)),
+                _ => (0..1 + rng.below(4))
+                    .map(|_| char::from(b'a' + rng.below(26) as u8))
+                    .collect(),
+            };
+            let (before, after) = (rng.below(8) as usize, rng.below(8) as usize);
+            assert_eq!(
+                expected(&words, &probe, before, after),
+                t.neighbors(&probe, before, after),
+                "{:?} {} {}",
+                probe,
+                before,
+                after
+            );
+        }
+    }
+
+    #[test]
+    fn random_probes() {
+        check_random_probes::<BaseLetterTrie>();
+        check_random_probes::<NoParentLetterTrie>();
+    }
+
+    fn check_edges<T: LetterTrie>() {
+        let t = T::from_words(vec!["an", "and", "ant", "cross", "crossed", "crate"]);
+        let words: Vec<String> = t.words().collect();
+        for probe in [
+            "", "a", "an", "ANT", "az", "cr", "cross", "crosses", "zebra", "\u{f1}u",
+        ] {
+            for (before, after) in [(0, 0), (1, 1), (2, 3), (10, 10)] {
+                assert_eq!(
+                    expected(&words, probe, before, after),
+                    t.neighbors(probe, before, after),
+                    "{:?} {} {}",
+                    probe,
+                    before,
+                    after
+                );
+            }
+        }
+        // Before the first word and after the last.
+        assert_eq!(
+            Neighbors {
+                before: vec![],
+                is_word: false,
+                after: vec!["an".to_owned(), "and".to_owned()],
+            },
+            t.neighbors("a", 3, 2)
+        );
+        let last = t.neighbors("zebra", 2, 2);
+        assert_eq!(vec!["cross", "crossed"], last.before);
+        assert!(last.after.is_empty());
+        // The probe itself is only flagged.
+        let word = t.neighbors("cross", 1, 1);
+        assert_eq!(
+            (vec!["crate".to_owned()], true),
+            (word.before, word.is_word)
+        );
+        assert_eq!(vec!["crossed"], word.after);
+        assert_eq!(
+            Neighbors::default(),
+            T::from_words(Vec::<String>::new()).neighbors("an", 5, 5)
+        );
+    }
+
+    #[test]
+    fn edges() {
+        check_edges::<BaseLetterTrie>();
+        check_edges::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn keeps_spellings() {
+        let mut t = NoParentLetterTrie::new();
+        for word in ["Paris", "Pasta", "paste"] {
+            t.insert_preserving_case(word, CasePreference::FirstSeen);
+        }
+        let neighbors = t.neighbors("pas", 1, 1);
+        assert_eq!(vec!["Paris"], neighbors.before);
+        assert_eq!(vec!["Pasta"], neighbors.after);
+    }
+}
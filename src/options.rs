@@ -0,0 +1,1584 @@
+//! Options that change how words are stored and looked up, and a wrapper that applies them to any `LetterTrie`.
+
+#[cfg(feature = "std")]
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::BufReader;
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
+
+#[cfg(feature = "std")]
+use crate::binary::{self, SavedOptions};
+use crate::diacritics::{compose_diacritics, fold_diacritics};
+use crate::*;
+
+// The bytes allowed on a line beyond four for each character of TrieOptions::max_word_len, for the spaces around
+// the word and a tag.
+#[cfg(feature = "std")]
+const LINE_SLACK_BYTES: usize = 64;
+
+/// What to do with a word that goes over one of the limits in `TrieOptions`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Leave the word out, count it in the `LoadReport`, and carry on.
+    #[default]
+    Skip,
+    /// Stop with `LetterTrieError::LimitExceeded`.
+    Abort,
+}
+
+impl LimitPolicy {
+    // Skip or abort for a word over a limit, with the error from error().
+    fn apply(self, skipped: &mut usize, error: impl FnOnce() -> LetterTrieError) -> Result<bool> {
+        match self {
+            LimitPolicy::Skip => {
+                *skipped += 1;
+                Ok(false)
+            }
+            LimitPolicy::Abort => Err(error()),
+        }
+    }
+}
+
+/// What to do with a word that's given more than once, which `TrieOptions::duplicate_policy` sets for a
+/// `ConfiguredLetterTrie` and `DisplayDetailOptions::duplicate_policy` sets for the load methods. Words are the
+/// same if they're the same after they're trimmed and lowercased, and for a `ConfiguredLetterTrie` after the rest
+/// of the options are applied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Keep the word once and carry on, as the tries always have.
+    #[default]
+    Ignore,
+    /// Keep the word once with the number of times it was given as its tag, so that a word list with repeats
+    /// gives each word's frequency through `LetterTrie::tag()`. Where a line has a tag of its own, as with
+    /// `TrieOptions::tag_separator`, that tag is added in place of 1.
+    Count,
+    /// Stop at the second copy with `LetterTrieError::DuplicateWord`, giving the line numbers of both copies.
+    /// Outside of a file the line numbers are the positions of the words among those given, counting from 1.
+    Error,
+}
+
+/// Which spelling a word keeps when it's given in more than one case, with
+/// `LetterTrie::insert_preserving_case()` or under `TrieOptions::case_preference`. "Foo" and "foo" are always
+/// one word, and this only decides how that word is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CasePreference {
+    /// Keep the spelling the word was first given with.
+    #[default]
+    FirstSeen,
+    /// Take the spelling each time the word is given again.
+    LastSeen,
+    /// Keep whichever spelling sorts first by character, which puts capitals ahead of small letters, so the
+    /// spelling doesn't depend on the order the words came in.
+    SortsFirst,
+}
+
+impl CasePreference {
+    // Returns true if the word should be shown as new_spelling rather than the spelling it already has.
+    pub(crate) fn prefers(self, new_spelling: &str, old_spelling: &str) -> bool {
+        match self {
+            CasePreference::FirstSeen => false,
+            CasePreference::LastSeen => true,
+            CasePreference::SortsFirst => new_spelling < old_spelling,
+        }
+    }
+}
+
+/// What `ConfiguredLetterTrie::from_reader()` and `from_file()` do with a line that isn't valid UTF-8.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Utf8Policy {
+    /// Stop with `LetterTrieError::Io` of kind `io::ErrorKind::InvalidData`, giving the line number.
+    #[default]
+    Strict,
+    /// Replace each invalid sequence with U+FFFD as `String::from_utf8_lossy()` does, add the word, and count the
+    /// line in the `LoadReport`.
+    Lossy,
+    /// Leave the line out and count it in the `LoadReport`.
+    Skip,
+}
+
+/// Counts of the words given to a `ConfiguredLetterTrie`, to `BaseLetterTrie::loader_channel_with_limits()` or to
+/// `AsyncLetterTrie::from_async_reader_with_options()`, including those left out by the limits in `TrieOptions`.
+/// Blank words aren't counted anywhere.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// The words that were added, counting a word that was added more than once each time.
+    pub words_added: usize,
+    /// The words that were skipped for being longer than `TrieOptions::max_word_len`.
+    pub skipped_too_long: usize,
+    /// The words that were skipped because `TrieOptions::max_words` had already been added.
+    pub skipped_over_max_words: usize,
+    /// The words that were given to a `ConfiguredLetterTrie` after they'd already been added, which are also
+    /// counted in `words_added`. The loader channel and the async loads don't look for these and leave it at 0.
+    pub duplicates: usize,
+    /// The lines that weren't valid UTF-8 and were either decoded lossily or skipped, as
+    /// `TrieOptions::utf8_policy` says. A line decoded lossily is also counted in `words_added`. The loader
+    /// channel is given strings and leaves it at 0.
+    pub invalid_utf8_lines: usize,
+    /// The lines that the parser given to `LetterTrie::from_lines_parsed()` turned down. Other loads leave it at
+    /// 0.
+    pub skipped_unparsed: usize,
+}
+
+/// Options for how a `ConfiguredLetterTrie` stores and looks up words. The defaults give the same behavior as the
+/// trie on its own.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrieOptions {
+    /// If true, letters with diacritics are folded onto their base letters both when words are added and when
+    /// they're looked up, so that "jalapeno" finds "jalapeño". See `diacritics::fold_diacritics()`.
+    pub fold_diacritics: bool,
+    /// If true along with `fold_diacritics`, the original spelling of each word is kept and returned in place of
+    /// the folded word by `words_with_prefix()` and `words()`. Spellings are stored trimmed, in lower case, and in
+    /// composed form so that composed and decomposed input give the same results.
+    pub keep_original_spelling: bool,
+    /// If set, each line read by `ConfiguredLetterTrie::from_reader()` or `from_file()` may be a word followed by
+    /// this separator and a tag, such as `"cross\t1024"` with a tab. The tag is attached to the word as with
+    /// `LetterTrie::insert_with_tag()`. A line without the separator is a word without a tag.
+    pub tag_separator: Option<char>,
+    /// If set, a word with more characters than this after it's trimmed goes over the limit and is handled as
+    /// `word_len_policy` says. When reading lines, a line that's longer than four bytes for each of these
+    /// characters plus some room for spaces and a tag also goes over the limit, without ever being held in memory
+    /// as a whole, so a corrupt file with one enormous line can't use up the memory.
+    pub max_word_len: Option<usize>,
+    /// What to do with a word over `max_word_len`.
+    pub word_len_policy: LimitPolicy,
+    /// If set, once this many words have been added every later word goes over the limit and is handled as
+    /// `max_words_policy` says. A word that's added more than once counts each time.
+    pub max_words: Option<usize>,
+    /// What to do with a word after `max_words`.
+    pub max_words_policy: LimitPolicy,
+    /// What to do with a word that's already been added.
+    pub duplicate_policy: DuplicatePolicy,
+    /// If set, each word is still stored and looked up as the other options say, but it's shown in the spelling
+    /// it was given in, trimmed and in composed form, as with `LetterTrie::insert_preserving_case()`. When a word
+    /// is given in more than one spelling this says which one it keeps. Under `keep_original_spelling` the
+    /// spellings listed for each word stay in lower case.
+    pub case_preference: Option<CasePreference>,
+    /// What to do with a line of a word file that isn't valid UTF-8.
+    pub utf8_policy: Utf8Policy,
+}
+
+impl TrieOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold diacritics when words are added and looked up.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new().with_fold_diacritics(true);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_words(vec!["jalapeño"], options);
+    /// assert!(trie.contains("jalapeno"));
+    /// assert_eq!(vec!["jalapeno"], trie.words_with_prefix(""));
+    /// ```
+    pub fn with_fold_diacritics(mut self, fold_diacritics: bool) -> Self {
+        self.fold_diacritics = fold_diacritics;
+        self
+    }
+
+    /// Keep the original spelling of each word when diacritics are folded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new()
+    ///     .with_fold_diacritics(true)
+    ///     .with_original_spelling(true);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_words(vec!["niño", "nine"], options);
+    /// assert_eq!(vec!["nine", "niño"], trie.words_with_prefix("nin"));
+    /// ```
+    pub fn with_original_spelling(mut self, keep_original_spelling: bool) -> Self {
+        self.keep_original_spelling = keep_original_spelling;
+        self
+    }
+
+    /// Read a tag after `separator` on each line of a word file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let source = "cross\t1024\ncrossed\n";
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_reader(source.as_bytes(), TrieOptions::new().with_tag_separator('\t'))
+    ///         .unwrap();
+    /// assert_eq!(Some(1024), trie.tag("cross"));
+    /// assert_eq!(None, trie.tag("crossed"));
+    /// ```
+    pub fn with_tag_separator(mut self, separator: char) -> Self {
+        self.tag_separator = Some(separator);
+        self
+    }
+
+    /// Limit the number of characters in a word.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new().with_max_word_len(5, LimitPolicy::Skip);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_reader("cross\ncrossed\n".as_bytes(), options).unwrap();
+    /// assert_eq!(vec!["cross"], trie.words_with_prefix(""));
+    /// assert_eq!(1, trie.load_report().skipped_too_long);
+    ///
+    /// let options = TrieOptions::new().with_max_word_len(5, LimitPolicy::Abort);
+    /// let result: Result<ConfiguredLetterTrie<NoParentLetterTrie>, _> =
+    ///     ConfiguredLetterTrie::from_reader("cross\ncrossed\n".as_bytes(), options);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(LetterTrieError::LimitExceeded { limit: "max_word_len", max: 5, line: 2, .. })
+    /// ));
+    /// ```
+    pub fn with_max_word_len(mut self, max_word_len: usize, policy: LimitPolicy) -> Self {
+        self.max_word_len = Some(max_word_len);
+        self.word_len_policy = policy;
+        self
+    }
+
+    /// Limit the number of words that can be added.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new().with_max_words(2, LimitPolicy::Skip);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_words(vec!["cross", "an", "crossed"], options);
+    /// assert_eq!(vec!["an", "cross"], trie.words_with_prefix(""));
+    /// assert_eq!(1, trie.load_report().skipped_over_max_words);
+    /// ```
+    pub fn with_max_words(mut self, max_words: usize, policy: LimitPolicy) -> Self {
+        self.max_words = Some(max_words);
+        self.max_words_policy = policy;
+        self
+    }
+
+    /// Choose what to do with a word that's given more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let source = "cross\ncrossed\nCross\n";
+    /// let options = TrieOptions::new().with_duplicate_policy(DuplicatePolicy::Count);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_reader(source.as_bytes(), options).unwrap();
+    /// assert_eq!(Some(2), trie.tag("cross"));
+    /// assert_eq!(Some(1), trie.tag("crossed"));
+    ///
+    /// let options = TrieOptions::new().with_duplicate_policy(DuplicatePolicy::Error);
+    /// let result: Result<ConfiguredLetterTrie<NoParentLetterTrie>, _> =
+    ///     ConfiguredLetterTrie::from_reader(source.as_bytes(), options);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(LetterTrieError::DuplicateWord { first_line: 1, second_line: 3, .. })
+    /// ));
+    /// ```
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Show each word in the spelling it was given in, choosing between spellings with `preference`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new().with_case_preference(CasePreference::LastSeen);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_words(vec!["macdonald", "MacDonald", "Mac"], options);
+    /// assert!(trie.contains("MACDONALD"));
+    /// assert_eq!(vec!["Mac", "MacDonald"], trie.words_with_prefix("mac"));
+    /// ```
+    pub fn with_case_preference(mut self, preference: CasePreference) -> Self {
+        self.case_preference = Some(preference);
+        self
+    }
+
+    /// Choose what to do with a line of a word file that isn't valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let source: &[u8] = b"cross\ncr\xffss\ncrossed\n";
+    /// let options = TrieOptions::new().with_utf8_policy(Utf8Policy::Skip);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_reader(source, options).unwrap();
+    /// assert_eq!(vec!["cross", "crossed"], trie.words_with_prefix(""));
+    /// assert_eq!(1, trie.load_report().invalid_utf8_lines);
+    ///
+    /// let options = TrieOptions::new().with_utf8_policy(Utf8Policy::Lossy);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_reader(source, options).unwrap();
+    /// assert!(trie.contains("cr\u{fffd}ss"));
+    /// ```
+    pub fn with_utf8_policy(mut self, policy: Utf8Policy) -> Self {
+        self.utf8_policy = policy;
+        self
+    }
+
+    /// Lowercase `text` and fold its diacritics if the options say to, which is what's done to a word before
+    /// it's stored or looked up.
+    pub fn normalize(&self, text: &str) -> String {
+        let text = text.to_lowercase();
+        if self.fold_diacritics {
+            fold_diacritics(&text)
+        } else {
+            text
+        }
+    }
+
+    fn keeps_spellings(&self) -> bool {
+        self.fold_diacritics && self.keep_original_spelling
+    }
+
+    // Decide whether word, given on line or at that position, can be added under the limits, counting it in report
+    // either way.
+    pub(crate) fn check_limits(
+        &self,
+        word: &str,
+        line: usize,
+        report: &mut LoadReport,
+    ) -> Result<bool> {
+        if let Some(max_word_len) = self.max_word_len {
+            if word.chars().count() > max_word_len {
+                return self
+                    .word_len_policy
+                    .apply(&mut report.skipped_too_long, || {
+                        LetterTrieError::LimitExceeded {
+                            limit: "max_word_len",
+                            max: max_word_len,
+                            line,
+                            word: Some(word.to_owned()),
+                        }
+                    });
+            }
+        }
+        if let Some(max_words) = self.max_words {
+            if report.words_added >= max_words {
+                return self
+                    .max_words_policy
+                    .apply(&mut report.skipped_over_max_words, || {
+                        LetterTrieError::LimitExceeded {
+                            limit: "max_words",
+                            max: max_words,
+                            line,
+                            word: Some(word.to_owned()),
+                        }
+                    });
+            }
+        }
+        report.words_added += 1;
+        Ok(true)
+    }
+
+    // The most bytes of a line to keep when reading, or None if lines are read whole.
+    #[cfg(feature = "std")]
+    pub(crate) fn max_line_bytes(&self) -> Option<usize> {
+        self.max_word_len
+            .map(|len| len.saturating_mul(4).saturating_add(LINE_SLACK_BYTES))
+    }
+
+    // Skip or abort for the line numbered line that was cut off at max_line_bytes(), which is too long for
+    // max_word_len whatever the word on it is.
+    #[cfg(feature = "std")]
+    pub(crate) fn skip_cut_line(&self, line: usize, report: &mut LoadReport) -> Result<()> {
+        let max_word_len = self.max_word_len.unwrap_or_default();
+        self.word_len_policy
+            .apply(&mut report.skipped_too_long, || {
+                LetterTrieError::LimitExceeded {
+                    limit: "max_word_len",
+                    max: max_word_len,
+                    line,
+                    word: None,
+                }
+            })
+            .map(|_| ())
+    }
+
+    // The text of the line numbered line read into buf, or None if it isn't valid UTF-8 and utf8_policy says to
+    // skip it.
+    #[cfg(feature = "std")]
+    pub(crate) fn decode_line<'a>(
+        &self,
+        buf: &'a [u8],
+        line: usize,
+        report: &mut LoadReport,
+    ) -> Result<Option<Cow<'a, str>>> {
+        match core::str::from_utf8(buf) {
+            Ok(text) => Ok(Some(Cow::Borrowed(text))),
+            Err(_) => match self.utf8_policy {
+                Utf8Policy::Strict => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} is not valid UTF-8", line),
+                )
+                .into()),
+                Utf8Policy::Lossy => {
+                    report.invalid_utf8_lines += 1;
+                    Ok(Some(String::from_utf8_lossy(buf)))
+                }
+                Utf8Policy::Skip => {
+                    report.invalid_utf8_lines += 1;
+                    Ok(None)
+                }
+            },
+        }
+    }
+}
+
+/// A `LetterTrie` along with the `TrieOptions` that are applied to every word added to it and every query made of
+/// it.
+pub struct ConfiguredLetterTrie<T: LetterTrie> {
+    trie: T,
+    options: TrieOptions,
+    // The original spellings of each word, keyed by the word as it's stored in the trie. This is empty unless the
+    // options say to keep them.
+    spellings: BTreeMap<String, BTreeSet<String>>,
+    // The line each word was first given on, which is only kept under DuplicatePolicy::Error.
+    first_lines: BTreeMap<String, usize>,
+    // The number of words given one at a time rather than as lines of a reader, for their positions.
+    words_given: usize,
+    report: LoadReport,
+}
+
+impl<T: LetterTrie> ConfiguredLetterTrie<T> {
+    /// Create an empty trie with the given options.
+    pub fn new(options: TrieOptions) -> Self {
+        Self {
+            trie: T::from_words(Vec::<String>::new()),
+            options,
+            spellings: BTreeMap::new(),
+            first_lines: BTreeMap::new(),
+            words_given: 0,
+            report: LoadReport::default(),
+        }
+    }
+
+    /// Create a trie from a list of words with the given options.
+    pub fn from_words<I, S>(words: I, options: TrieOptions) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut t = Self::new(options);
+        for word in words {
+            t.insert(word.as_ref());
+        }
+        t
+    }
+
+    /// Create a trie from any source of lines, one word per line, with the given options. If
+    /// `TrieOptions::tag_separator` is set a line may also have a tag after the word.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if reading a line fails, including when it isn't valid UTF-8 under
+    /// `Utf8Policy::Strict`,
+    /// `LetterTrieError::InvalidLine` if the text after a tag separator isn't a `u64`,
+    /// `LetterTrieError::LimitExceeded` if a word goes over a limit whose policy is `LimitPolicy::Abort`, and
+    /// `LetterTrieError::DuplicateWord` if a word is repeated under `DuplicatePolicy::Error`.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: BufRead>(mut reader: R, options: TrieOptions) -> Result<Self> {
+        let mut t = Self::new(options);
+        let max_line_bytes = t.options.max_line_bytes();
+        let mut buf: Vec<u8> = vec![];
+        let mut index = 0;
+        while let Some(is_cut) = read_line(&mut reader, &mut buf, max_line_bytes)? {
+            index += 1;
+            if is_cut {
+                t.options.skip_cut_line(index, &mut t.report)?;
+                continue;
+            }
+            let line = match t.options.decode_line(&buf, index, &mut t.report)? {
+                Some(line) => line,
+                None => continue,
+            };
+            let line = line.as_ref();
+            let separator = t.options.tag_separator;
+            match separator.and_then(|separator| line.split_once(separator)) {
+                Some((word, tag)) => {
+                    let tag =
+                        tag.trim()
+                            .parse::<u64>()
+                            .map_err(|_| LetterTrieError::InvalidLine {
+                                path: None,
+                                line: index,
+                                message: format!("{:?} where a tag should be", tag),
+                            })?;
+                    t.add_at(word, Some(tag), index)?;
+                }
+                None => {
+                    t.add_at(line, None, index)?;
+                }
+            }
+        }
+        Ok(t)
+    }
+
+    /// Create a trie from a file with one word per line, with the given options.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the path if the file can't be opened or read, and otherwise the same
+    /// errors as `from_reader()`, with the path in `LetterTrieError::InvalidLine`.
+    #[cfg(feature = "fs")]
+    pub fn from_file(filename: &str, options: TrieOptions) -> Result<Self> {
+        let path = Path::new(filename);
+        let file = File::open(path).map_err(in_file(path))?;
+        Self::from_reader(BufReader::new(file), options).map_err(in_file(path))
+    }
+
+    /// Save the trie in the binary format along with its options and the original spellings it keeps, so that
+    /// `read_binary()` gives back a trie that looks words up the same way. See the `binary` module.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if writing fails.
+    #[cfg(feature = "std")]
+    pub fn write_binary<W: io::Write>(&self, writer: W) -> Result<()> {
+        binary::write_trie_with_options(&self.trie, writer, Some((&self.options, &self.spellings)))
+    }
+
+    /// Load a trie saved with `write_binary()` along with the options it was saved with, which are then applied
+    /// to every query whatever options the program would otherwise use. A file saved by
+    /// `LetterTrie::write_binary()` has no options and gets the defaults. The load report starts over, and
+    /// `DuplicatePolicy::Error` gives 0 as the first line of a word that was already saved.
+    ///
+    /// # Errors
+    ///
+    /// The same as for `LetterTrie::read_binary()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new().with_fold_diacritics(true);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_words(vec!["jalapeño"], options);
+    /// let mut bytes: Vec<u8> = vec![];
+    /// trie.write_binary(&mut bytes)?;
+    ///
+    /// let copy: ConfiguredLetterTrie<BaseLetterTrie> = ConfiguredLetterTrie::read_binary(bytes.as_slice())?;
+    /// assert!(copy.options().fold_diacritics);
+    /// assert!(copy.contains("JALAPEÑO"));
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_binary<R: io::Read>(reader: R) -> Result<Self> {
+        let (trie, saved) = read_binary_parts(reader)?;
+        Ok(Self::loaded(trie, saved))
+    }
+
+    /// Load a trie as `read_binary()` does but with `options`. The options that decide how the stored words are
+    /// looked up, `fold_diacritics` and `keep_original_spelling`, have to be the same as the ones the trie was
+    /// saved with, which are the defaults for a file without options, and the rest only apply to words added
+    /// later so they're taken from `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::IncompatibleOptions` if `options` differs from the saved options in
+    /// `fold_diacritics` or `keep_original_spelling`, and otherwise the same errors as `read_binary()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new().with_fold_diacritics(true);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_words(vec!["jalapeño"], options.clone());
+    /// let mut bytes: Vec<u8> = vec![];
+    /// trie.write_binary(&mut bytes)?;
+    ///
+    /// let copy: ConfiguredLetterTrie<NoParentLetterTrie> = ConfiguredLetterTrie::read_binary_with_options(
+    ///     bytes.as_slice(),
+    ///     options.with_max_words(2, LimitPolicy::Skip),
+    /// )?;
+    /// assert_eq!(Some(2), copy.options().max_words);
+    ///
+    /// let result: Result<ConfiguredLetterTrie<NoParentLetterTrie>, _> =
+    ///     ConfiguredLetterTrie::read_binary_with_options(bytes.as_slice(), TrieOptions::new());
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(LetterTrieError::IncompatibleOptions { option: "fold_diacritics", .. })
+    /// ));
+    /// # Ok::<(), LetterTrieError>(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_binary_with_options<R: io::Read>(reader: R, options: TrieOptions) -> Result<Self> {
+        let (trie, saved) = read_binary_parts(reader)?;
+        check_option(
+            "fold_diacritics",
+            saved.options.fold_diacritics,
+            options.fold_diacritics,
+        )?;
+        check_option(
+            "keep_original_spelling",
+            saved.options.keep_original_spelling,
+            options.keep_original_spelling,
+        )?;
+        Ok(Self::loaded(
+            trie,
+            SavedOptions {
+                options,
+                spellings: saved.spellings,
+            },
+        ))
+    }
+
+    #[cfg(feature = "std")]
+    fn loaded(trie: T, saved: SavedOptions) -> Self {
+        Self {
+            trie,
+            options: saved.options,
+            spellings: saved.spellings,
+            first_lines: BTreeMap::new(),
+            words_given: 0,
+            report: LoadReport::default(),
+        }
+    }
+
+    /// The options used by this trie.
+    pub fn options(&self) -> &TrieOptions {
+        &self.options
+    }
+
+    /// The underlying trie, which holds the words as they were stored after the options were applied.
+    pub fn trie(&self) -> &T {
+        &self.trie
+    }
+
+    /// The counts of the words added and skipped so far.
+    pub fn load_report(&self) -> &LoadReport {
+        &self.report
+    }
+
+    /// Apply the options to `text` the same way they're applied to words and queries.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie: ConfiguredLetterTrie<BaseLetterTrie> =
+    ///     ConfiguredLetterTrie::new(TrieOptions::new().with_fold_diacritics(true));
+    /// assert_eq!("nino", trie.normalize("NIÑO"));
+    /// ```
+    pub fn normalize(&self, text: &str) -> String {
+        self.options.normalize(text)
+    }
+
+    /// Add a word. As with `LetterTrie::insert()` the word is trimmed and lowercased, and a word that's empty
+    /// after that is ignored. A word over one of the limits in the options is left out whatever the policy, so
+    /// use `try_insert()` to find out about it.
+    pub fn insert(&mut self, word: &str) {
+        let _ = self.try_insert(word);
+    }
+
+    /// Add a word as `insert()` does, returning true if it was added and false if it was blank or skipped for
+    /// going over a limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::LimitExceeded` if the word goes over a limit whose policy is
+    /// `LimitPolicy::Abort`, and `LetterTrieError::DuplicateWord` if it's already been added under
+    /// `DuplicatePolicy::Error`.
+    pub fn try_insert(&mut self, word: &str) -> Result<bool> {
+        self.words_given += 1;
+        self.add_at(word, None, self.words_given)
+    }
+
+    /// Add a word as `insert()` does and attach `tag` to it as with `LetterTrie::insert_with_tag()`. Spellings
+    /// that are the same word under the options share one tag.
+    pub fn insert_with_tag(&mut self, word: &str, tag: u64) {
+        let _ = self.try_insert_with_tag(word, tag);
+    }
+
+    /// Add a word with a tag as `insert_with_tag()` does, with the same result as `try_insert()`.
+    ///
+    /// # Errors
+    ///
+    /// The same as for `try_insert()`.
+    pub fn try_insert_with_tag(&mut self, word: &str, tag: u64) -> Result<bool> {
+        self.words_given += 1;
+        self.add_at(word, Some(tag), self.words_given)
+    }
+
+    // Add a word given on the line or at the position `line` after checking it against the limits and the
+    // duplicate policy and recording its spelling if the options say to. Returns false if it's empty or skipped.
+    fn add_at(&mut self, word: &str, tag: Option<u64>, line: usize) -> Result<bool> {
+        let word = word.trim();
+        let key = self.normalize(word);
+        if key.is_empty() {
+            return Ok(false);
+        }
+        let is_duplicate = self.trie.contains(&key);
+        let policy = self.options.duplicate_policy;
+        if is_duplicate && policy == DuplicatePolicy::Error {
+            return Err(LetterTrieError::DuplicateWord {
+                first_line: self.first_lines.get(&key).copied().unwrap_or_default(),
+                word: key,
+                second_line: line,
+            });
+        }
+        if !self.options.check_limits(word, line, &mut self.report)? {
+            return Ok(false);
+        }
+        if is_duplicate {
+            self.report.duplicates += 1;
+        }
+        if self.options.keeps_spellings() {
+            self.spellings
+                .entry(key.clone())
+                .or_default()
+                .insert(compose_diacritics(&word.to_lowercase()));
+        }
+        let tag = match policy {
+            DuplicatePolicy::Count => {
+                let count = if is_duplicate {
+                    self.trie.tag(&key)
+                } else {
+                    None
+                };
+                Some(tag.unwrap_or(1).saturating_add(count.unwrap_or_default()))
+            }
+            DuplicatePolicy::Ignore => tag,
+            DuplicatePolicy::Error => {
+                self.first_lines.insert(key.clone(), line);
+                tag
+            }
+        };
+        match tag {
+            Some(tag) => self.trie.insert_with_tag(&key, tag),
+            None => self.trie.insert(&key),
+        }
+        if let Some(preference) = self.options.case_preference {
+            self.trie
+                .insert_with_spelling(&key, &compose_diacritics(word), preference);
+        }
+        Ok(true)
+    }
+
+    /// Get the tag of the word that `word` is the same as under the options.
+    pub fn tag(&self, word: &str) -> Option<u64> {
+        self.trie.tag(&self.normalize(word.trim()))
+    }
+
+    /// Remove a word, returning true if it was in the trie. When diacritics are folded this removes every
+    /// spelling that folds to the same word, so removing "ano" also removes "año".
+    pub fn remove(&mut self, word: &str) -> bool {
+        let key = self.normalize(word.trim());
+        self.spellings.remove(&key);
+        self.first_lines.remove(&key);
+        self.trie.remove(&key)
+    }
+
+    /// Returns true if `word` or a word that it's the same as under the options is in the trie.
+    pub fn contains(&self, word: &str) -> bool {
+        self.trie.contains(&self.normalize(word))
+    }
+
+    /// Look up `s` as in `LetterTrie::lookup()` after the options are applied to it.
+    pub fn lookup(&self, s: &str) -> Lookup {
+        self.trie.lookup(&self.normalize(s))
+    }
+
+    /// Returns true if `prefix` after the options are applied to it starts a word in the trie.
+    pub fn contains_prefix(&self, prefix: &str) -> bool {
+        self.trie.contains_prefix(&self.normalize(prefix))
+    }
+
+    /// Find the node for `prefix` after the options are applied to it. The node's prefix is the stored form, so
+    /// it has no diacritics when they're folded.
+    pub fn find(&self, prefix: &str) -> Option<FixedNode> {
+        self.trie.find(&self.normalize(prefix))
+    }
+
+    /// Get the words starting with `prefix` after the options are applied to it, in alphabetical order of the
+    /// stored words. When original spellings are kept they're returned instead, and a stored word with more than
+    /// one spelling gives each of them in alphabetical order. Otherwise under `case_preference` each word is in
+    /// the spelling it keeps.
+    pub fn words_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let words = self.trie.words_with_prefix(&self.normalize(prefix));
+        if !self.options.keeps_spellings() {
+            return words;
+        }
+        words
+            .into_iter()
+            .flat_map(|word| match self.spellings.get(&self.normalize(&word)) {
+                Some(spellings) => spellings.iter().cloned().collect(),
+                None => vec![word],
+            })
+            .collect()
+    }
+
+    /// Get up to `max_count` of the shortest words starting with `prefix` after the options are applied to it,
+    /// as in `LetterTrie::suggest()`. Under `case_preference` each word is in the spelling it keeps.
+    pub fn suggest(&self, prefix: &str, max_count: usize) -> Vec<String> {
+        self.trie.suggest(&self.normalize(prefix), max_count)
+    }
+
+    /// Iterate over every word in the same order as `words_with_prefix("")`.
+    pub fn words(&self) -> Words {
+        Words {
+            inner: self.words_with_prefix("").into_iter(),
+        }
+    }
+
+    /// Iterate over every word in the same order as `words()` along with its tag, if it has one.
+    pub fn words_with_tags(&self) -> WordsWithTags {
+        let words: Vec<(String, Option<u64>)> = self
+            .words_with_prefix("")
+            .into_iter()
+            .map(|word| {
+                let tag = self.tag(&word);
+                (word, tag)
+            })
+            .collect();
+        WordsWithTags {
+            inner: words.into_iter(),
+        }
+    }
+
+    /// Get the original spellings of the word that `word` is the same as under the options, or an empty list if
+    /// it's not in the trie or spellings aren't being kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new()
+    ///     .with_fold_diacritics(true)
+    ///     .with_original_spelling(true);
+    /// let trie: ConfiguredLetterTrie<NoParentLetterTrie> =
+    ///     ConfiguredLetterTrie::from_words(vec!["año", "ano"], options);
+    /// assert_eq!(vec!["ano", "año"], trie.spellings("ANO"));
+    /// ```
+    pub fn spellings(&self, word: &str) -> Vec<String> {
+        self.spellings
+            .get(&self.normalize(word.trim()))
+            .map(|spellings| spellings.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+// Read a saved trie and its options, which are the defaults for a file without them.
+#[cfg(feature = "std")]
+fn read_binary_parts<T: LetterTrie, R: io::Read>(reader: R) -> Result<(T, SavedOptions)> {
+    let (trie, saved) = binary::read_trie_with_options(reader)?;
+    let saved = saved.unwrap_or_else(|| SavedOptions {
+        options: TrieOptions::new(),
+        spellings: BTreeMap::new(),
+    });
+    Ok((trie, saved))
+}
+
+// Fail with LetterTrieError::IncompatibleOptions for an option that was asked for with a different value from the
+// one a trie was saved with.
+#[cfg(feature = "std")]
+fn check_option<V: PartialEq + fmt::Debug>(
+    option: &'static str,
+    saved: V,
+    requested: V,
+) -> Result<()> {
+    if saved == requested {
+        Ok(())
+    } else {
+        Err(LetterTrieError::IncompatibleOptions {
+            option,
+            saved: format!("{:?}", saved),
+            requested: format!("{:?}", requested),
+        })
+    }
+}
+
+// Read one line into buf without its line ending, keeping at most max_bytes of it but reading to the end of the
+// line either way. Returns None at the end of the input, or whether the line was cut short.
+#[cfg(feature = "std")]
+fn read_line<R: BufRead>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    max_bytes: Option<usize>,
+) -> io::Result<Option<bool>> {
+    let max_bytes = max_bytes.unwrap_or(usize::MAX);
+    buf.clear();
+    let mut read_any = false;
+    let mut is_cut = false;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if available.is_empty() {
+            break;
+        }
+        read_any = true;
+        let newline = available.iter().position(|byte| *byte == b'\n');
+        let line_part = &available[..newline.unwrap_or(available.len())];
+        let room = max_bytes - buf.len().min(max_bytes);
+        if line_part.len() > room {
+            is_cut = true;
+        }
+        buf.extend_from_slice(&line_part[..line_part.len().min(room)]);
+        let used = newline.map(|i| i + 1).unwrap_or(available.len());
+        reader.consume(used);
+        if newline.is_some() {
+            break;
+        }
+    }
+    if !read_any {
+        return Ok(None);
+    }
+    if !is_cut && buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+    Ok(Some(is_cut))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    // The same Spanish words composed (NFC) and decomposed (NFD), with the case and spacing varied.
+    const COMPOSED: &str =
+        "jalape\u{f1}o\nNI\u{d1}O\n  ni\u{f1}a\nnine\nacci\u{f3}n\n\u{c1}rbol\nano\na\u{f1}o\n";
+    const DECOMPOSED: &str =
+        "jalapen\u{303}o\nNIN\u{303}O\n  nin\u{303}a\nnine\naccio\u{301}n\nA\u{301}rbol\nano\nan\u{303}o\n";
+
+    fn folding() -> TrieOptions {
+        TrieOptions::new().with_fold_diacritics(true)
+    }
+
+    fn check_folded<T: LetterTrie>() {
+        let composed =
+            ConfiguredLetterTrie::<T>::from_reader(COMPOSED.as_bytes(), folding()).unwrap();
+        let decomposed =
+            ConfiguredLetterTrie::<T>::from_reader(DECOMPOSED.as_bytes(), folding()).unwrap();
+        for t in &[&composed, &decomposed] {
+            assert!(t.find("jalapeno").unwrap().is_word);
+            assert!(t.contains("jalape\u{f1}o"));
+            assert!(t.contains("jalapen\u{303}o"));
+            assert!(t.contains("ARBOL"));
+            assert!(!t.contains("jalapen"));
+            assert_eq!(vec!["nina", "nine", "nino"], t.words_with_prefix("nin"));
+            assert_eq!(
+                vec!["nina", "nine", "nino"],
+                t.words_with_prefix("ni\u{f1}")
+            );
+            assert!(t.trie().verify_integrity().is_ok());
+        }
+        assert_eq!(
+            composed.trie().fingerprint(),
+            decomposed.trie().fingerprint()
+        );
+        assert_eq!(7, composed.words().len());
+    }
+
+    fn check_spellings<T: LetterTrie>() {
+        let options = folding().with_original_spelling(true);
+        let composed =
+            ConfiguredLetterTrie::<T>::from_reader(COMPOSED.as_bytes(), options.clone()).unwrap();
+        let decomposed =
+            ConfiguredLetterTrie::<T>::from_reader(DECOMPOSED.as_bytes(), options).unwrap();
+        let expected = vec![
+            "acci\u{f3}n",
+            "ano",
+            "a\u{f1}o",
+            "\u{e1}rbol",
+            "jalape\u{f1}o",
+            "ni\u{f1}a",
+            "nine",
+            "ni\u{f1}o",
+        ];
+        for t in &[&composed, &decomposed] {
+            assert_eq!(expected, t.words().collect::<Vec<String>>());
+            assert_eq!(
+                vec!["ni\u{f1}a", "nine", "ni\u{f1}o"],
+                t.words_with_prefix("nin")
+            );
+            assert_eq!(vec!["ano", "a\u{f1}o"], t.spellings("an\u{303}o"));
+            assert!(t.spellings("nada").is_empty());
+        }
+    }
+
+    #[test]
+    fn folded_base() {
+        check_folded::<BaseLetterTrie>();
+        check_spellings::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn folded_no_parent() {
+        check_folded::<NoParentLetterTrie>();
+        check_spellings::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn default_options_match_plain_trie() {
+        let t = ConfiguredLetterTrie::<NoParentLetterTrie>::from_reader(
+            COMPOSED.as_bytes(),
+            TrieOptions::new(),
+        )
+        .unwrap();
+        let plain =
+            NoParentLetterTrie::from_reader(COMPOSED.as_bytes(), false, &LoadMethod::Continuous)
+                .unwrap();
+        assert_eq!(plain.fingerprint(), t.trie().fingerprint());
+        assert!(t.contains("ni\u{f1}o"));
+        assert!(!t.contains("nino"));
+        assert!(t.spellings("ni\u{f1}o").is_empty());
+    }
+
+    #[test]
+    fn remove_takes_every_spelling() {
+        let mut t = ConfiguredLetterTrie::<BaseLetterTrie>::from_words(
+            vec!["ano", "a\u{f1}o", "anos"],
+            folding().with_original_spelling(true),
+        );
+        assert!(t.remove("A\u{d1}O"));
+        assert!(!t.remove("ano"));
+        assert_eq!(vec!["anos"], t.words().collect::<Vec<String>>());
+        assert!(t.spellings("ano").is_empty());
+        // A word that's nothing but a combining mark folds to nothing and is ignored.
+        t.insert("\u{303}");
+        assert_eq!(1, t.words().len());
+    }
+
+    #[test]
+    fn tagged_lines() {
+        let source = "jalape\u{f1}o\t10\n  ni\u{f1}o \t 20\r\nnino\nano\t\n";
+        let options = folding().with_tag_separator('\t');
+        let result = ConfiguredLetterTrie::<NoParentLetterTrie>::from_reader(
+            source.as_bytes(),
+            options.clone(),
+        );
+        match result {
+            Err(err @ LetterTrieError::InvalidLine { line: 4, .. }) => {
+                assert_eq!(
+                    "Invalid line 4: \"\" where a tag should be",
+                    err.to_string()
+                )
+            }
+            other => panic!("{:?}", other.err()),
+        }
+
+        let source = &source[..source.len() - "ano\t\n".len()];
+        let mut t =
+            ConfiguredLetterTrie::<NoParentLetterTrie>::from_reader(source.as_bytes(), options)
+                .unwrap();
+        assert_eq!(Some(10), t.tag("JALAPENO"));
+        // "nino" on its own line keeps the tag that "niño" gave the folded word.
+        assert_eq!(Some(20), t.tag("ni\u{f1}o"));
+        assert_eq!(
+            vec![
+                ("jalapeno".to_owned(), Some(10)),
+                ("nino".to_owned(), Some(20))
+            ],
+            t.words_with_tags().collect::<Vec<_>>()
+        );
+        t.remove("nino");
+        assert_eq!(None, t.tag("nino"));
+
+        // Without a separator a tab is just more of the word.
+        let t = ConfiguredLetterTrie::<BaseLetterTrie>::from_reader(
+            "a\t1\n".as_bytes(),
+            TrieOptions::new(),
+        )
+        .unwrap();
+        assert!(t.contains("a\t1"));
+        assert_eq!(None, t.tag("a"));
+    }
+
+    // A 10 MB line with no line break in it, followed by two good lines.
+    fn huge_line() -> impl BufRead {
+        use std::io::Read;
+
+        std::io::BufReader::new(
+            std::io::repeat(b'a')
+                .take(10_000_000)
+                .chain("\nshort\r\nworded\n".as_bytes()),
+        )
+    }
+
+    #[test]
+    fn huge_line_is_skipped_or_aborts() {
+        let options = TrieOptions::new().with_max_word_len(6, LimitPolicy::Skip);
+        let t =
+            ConfiguredLetterTrie::<NoParentLetterTrie>::from_reader(huge_line(), options).unwrap();
+        assert_eq!(vec!["short", "worded"], t.words_with_prefix(""));
+        assert_eq!(
+            &LoadReport {
+                words_added: 2,
+                skipped_too_long: 1,
+                skipped_over_max_words: 0,
+                duplicates: 0,
+                invalid_utf8_lines: 0,
+                skipped_unparsed: 0,
+            },
+            t.load_report()
+        );
+        assert_eq!(7, t.trie().to_fixed_node().height);
+
+        let options = TrieOptions::new().with_max_word_len(6, LimitPolicy::Abort);
+        let result = ConfiguredLetterTrie::<BaseLetterTrie>::from_reader(huge_line(), options);
+        match result {
+            Err(err @ LetterTrieError::LimitExceeded { word: None, .. }) => assert_eq!(
+                "Limit exceeded: line 1 is too long to read under max_word_len (6)",
+                err.to_string()
+            ),
+            _ => panic!("expected LimitExceeded"),
+        }
+
+        // A line that fits in the bytes read but has too many characters once it's trimmed.
+        let options = TrieOptions::new().with_max_word_len(5, LimitPolicy::Skip);
+        let t = ConfiguredLetterTrie::<BaseLetterTrie>::from_reader(huge_line(), options).unwrap();
+        assert_eq!(vec!["short"], t.words_with_prefix(""));
+        assert_eq!(2, t.load_report().skipped_too_long);
+    }
+
+    #[test]
+    fn max_words() {
+        let words = vec!["cross", "", "an", "cross", "azure", "crossed"];
+        let options = TrieOptions::new().with_max_words(3, LimitPolicy::Skip);
+        let t = ConfiguredLetterTrie::<BaseLetterTrie>::from_words(words.clone(), options);
+        // The second "cross" counts toward the limit.
+        assert_eq!(vec!["an", "cross"], t.words_with_prefix(""));
+        assert_eq!(
+            &LoadReport {
+                words_added: 3,
+                skipped_too_long: 0,
+                skipped_over_max_words: 2,
+                duplicates: 1,
+                invalid_utf8_lines: 0,
+                skipped_unparsed: 0,
+            },
+            t.load_report()
+        );
+
+        let options = TrieOptions::new()
+            .with_max_words(3, LimitPolicy::Abort)
+            .with_max_word_len(5, LimitPolicy::Skip);
+        let mut t = ConfiguredLetterTrie::<NoParentLetterTrie>::new(options);
+        for word in &words[..4] {
+            assert_eq!(!word.is_empty(), t.try_insert(word).unwrap());
+        }
+        assert!(!t.try_insert("crossed").unwrap());
+        assert!(matches!(
+            t.try_insert_with_tag("azure", 1),
+            Err(LetterTrieError::LimitExceeded { .. })
+        ));
+        // insert() leaves the word out without an error.
+        t.insert("azure");
+        assert_eq!(vec!["an", "cross"], t.words_with_prefix(""));
+        assert_eq!(1, t.load_report().skipped_too_long);
+        assert_eq!(0, t.load_report().skipped_over_max_words);
+
+        let source = "cross\nan\nazure\n";
+        let options = TrieOptions::new().with_max_words(2, LimitPolicy::Abort);
+        let result =
+            ConfiguredLetterTrie::<BaseLetterTrie>::from_reader(source.as_bytes(), options);
+        assert!(matches!(result, Err(LetterTrieError::LimitExceeded { .. })));
+    }
+
+    #[test]
+    fn lines_without_limits() {
+        let source = "cross\r\n\n  an  \r\nazure";
+        let t = ConfiguredLetterTrie::<BaseLetterTrie>::from_reader(
+            source.as_bytes(),
+            TrieOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(vec!["an", "azure", "cross"], t.words_with_prefix(""));
+        assert_eq!(3, t.load_report().words_added);
+
+        let result = ConfiguredLetterTrie::<BaseLetterTrie>::from_reader(
+            &b"cross\n\xff\n"[..],
+            TrieOptions::new(),
+        );
+        assert!(matches!(result, Err(LetterTrieError::Io(_))));
+    }
+
+    // "cross" is on lines 1 and 4 spelled differently, "ano" and "año" are the same word when folded, and "an"
+    // has tags on lines 6 and 8.
+    const DUPLICATES: &str = "cross\nan\t2\nano\n  CROSS\na\u{f1}o\nAn\t5\n\nan\n";
+
+    fn check_duplicate_policies<T: LetterTrie>() {
+        let options = folding().with_tag_separator('\t');
+        let ignore =
+            ConfiguredLetterTrie::<T>::from_reader(DUPLICATES.as_bytes(), options.clone()).unwrap();
+        assert_eq!(vec!["an", "ano", "cross"], ignore.words_with_prefix(""));
+        assert_eq!(Some(5), ignore.tag("an"));
+        assert_eq!(7, ignore.load_report().words_added);
+        assert_eq!(4, ignore.load_report().duplicates);
+
+        let count = ConfiguredLetterTrie::<T>::from_reader(
+            DUPLICATES.as_bytes(),
+            options
+                .clone()
+                .with_duplicate_policy(DuplicatePolicy::Count),
+        )
+        .unwrap();
+        assert_eq!(ignore.words_with_prefix(""), count.words_with_prefix(""));
+        assert_eq!(Some(8), count.tag("an"));
+        assert_eq!(Some(2), count.tag("año"));
+        assert_eq!(Some(2), count.tag("cross"));
+        assert_eq!(ignore.load_report(), count.load_report());
+
+        let result = ConfiguredLetterTrie::<T>::from_reader(
+            DUPLICATES.as_bytes(),
+            options.with_duplicate_policy(DuplicatePolicy::Error),
+        );
+        match result {
+            Err(LetterTrieError::DuplicateWord {
+                word,
+                first_line,
+                second_line,
+            }) => assert_eq!(("cross", 1, 4), (word.as_str(), first_line, second_line)),
+            _ => panic!("the duplicate wasn't found"),
+        }
+
+        // Words given one at a time are numbered by position, and a removed word can be added again.
+        let mut t = ConfiguredLetterTrie::<T>::new(
+            TrieOptions::new().with_duplicate_policy(DuplicatePolicy::Error),
+        );
+        assert!(t.try_insert("cross").unwrap());
+        assert!(t.try_insert("an").unwrap());
+        assert!(matches!(
+            t.try_insert("Cross"),
+            Err(LetterTrieError::DuplicateWord {
+                first_line: 1,
+                second_line: 3,
+                ..
+            })
+        ));
+        assert!(t.remove("cross"));
+        assert!(t.try_insert("cross").unwrap());
+        assert!(matches!(
+            t.try_insert_with_tag("an", 3),
+            Err(LetterTrieError::DuplicateWord {
+                first_line: 2,
+                second_line: 5,
+                ..
+            })
+        ));
+        assert_eq!(0, t.load_report().duplicates);
+    }
+
+    #[test]
+    fn duplicate_policies_base() {
+        check_duplicate_policies::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn duplicate_policies_no_parent() {
+        check_duplicate_policies::<NoParentLetterTrie>();
+    }
+
+    // Line 3 has a byte that can't start a character, and line 5 is cut off partway through "é", with a tag after
+    // it.
+    const INVALID_UTF8: &[u8] = b"cross\nan\ncr\xffss\ncrossed\ncaf\xc3\t7\nazure\n";
+
+    fn check_utf8_policies<T: LetterTrie>() {
+        let options = TrieOptions::new().with_tag_separator('\t');
+        let result = ConfiguredLetterTrie::<T>::from_reader(INVALID_UTF8, options.clone());
+        match result {
+            Err(LetterTrieError::Io(err)) => {
+                assert_eq!(io::ErrorKind::InvalidData, err.kind());
+                assert!(err.to_string().contains("line 3 "), "{}", err);
+            }
+            _ => panic!("expected an error for line 3"),
+        }
+
+        let lossy = ConfiguredLetterTrie::<T>::from_reader(
+            INVALID_UTF8,
+            options.clone().with_utf8_policy(Utf8Policy::Lossy),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                "an",
+                "azure",
+                "caf\u{fffd}",
+                "cross",
+                "crossed",
+                "cr\u{fffd}ss"
+            ],
+            lossy.words_with_prefix("")
+        );
+        assert_eq!(Some(7), lossy.tag("caf\u{fffd}"));
+        assert_eq!(
+            &LoadReport {
+                words_added: 6,
+                invalid_utf8_lines: 2,
+                ..LoadReport::default()
+            },
+            lossy.load_report()
+        );
+
+        let skip = ConfiguredLetterTrie::<T>::from_reader(
+            INVALID_UTF8,
+            options.with_utf8_policy(Utf8Policy::Skip),
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["an", "azure", "cross", "crossed"],
+            skip.words_with_prefix("")
+        );
+        assert_eq!(
+            &LoadReport {
+                words_added: 4,
+                invalid_utf8_lines: 2,
+                ..LoadReport::default()
+            },
+            skip.load_report()
+        );
+    }
+
+    #[test]
+    fn utf8_policies_base() {
+        check_utf8_policies::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn utf8_policies_no_parent() {
+        check_utf8_policies::<NoParentLetterTrie>();
+    }
+
+    // Spellings in mixed case that fold to the same words, one of them decomposed.
+    const MIXED_CASE: &str = "MacDonald\nmacdonald\nJos\u{e9}\nJOSE\nJose\u{301}\nMAC\n";
+
+    fn check_case_preference<T: LetterTrie>() {
+        let options = folding()
+            .with_case_preference(CasePreference::FirstSeen)
+            .with_duplicate_policy(DuplicatePolicy::Count);
+        let t = ConfiguredLetterTrie::<T>::from_reader(MIXED_CASE.as_bytes(), options).unwrap();
+        for query in &["MACDONALD", "macDonald", "jose", "JOS\u{c9}", "Mac"] {
+            assert!(t.contains(query), "{:?}", query);
+        }
+        assert_eq!(
+            vec!["Jos\u{e9}", "MAC", "MacDonald"],
+            t.words().collect::<Vec<String>>()
+        );
+        assert_eq!(vec!["MAC", "MacDonald"], t.words_with_prefix("mac"));
+        assert_eq!(vec!["MAC"], t.suggest("M", 1));
+        assert_eq!(Some(3), t.tag("jose"));
+        assert_eq!(Some(2), t.tag("MacDonald"));
+        assert_eq!(3, t.load_report().duplicates);
+        assert_eq!(
+            vec![
+                ("Jos\u{e9}".to_owned(), Some(3)),
+                ("MAC".to_owned(), Some(1)),
+                ("MacDonald".to_owned(), Some(2))
+            ],
+            t.words_with_tags().collect::<Vec<_>>()
+        );
+
+        // The composed form of the last spelling, which was decomposed.
+        let options = folding().with_case_preference(CasePreference::LastSeen);
+        let t = ConfiguredLetterTrie::<T>::from_reader(MIXED_CASE.as_bytes(), options).unwrap();
+        assert_eq!(vec!["Jos\u{e9}"], t.words_with_prefix("j"));
+
+        // The spellings listed for each word stay in lower case.
+        let options = folding()
+            .with_original_spelling(true)
+            .with_case_preference(CasePreference::FirstSeen);
+        let t = ConfiguredLetterTrie::<T>::from_reader(MIXED_CASE.as_bytes(), options).unwrap();
+        assert_eq!(vec!["jose", "jos\u{e9}"], t.words_with_prefix("jo"));
+    }
+
+    #[test]
+    fn case_preference_base() {
+        check_case_preference::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn case_preference_no_parent() {
+        check_case_preference::<NoParentLetterTrie>();
+    }
+
+    // Saved with folding on and read back in a program whose own options are the defaults.
+    fn check_saved_options<T: LetterTrie>() {
+        let options = folding()
+            .with_original_spelling(true)
+            .with_max_words(100, LimitPolicy::Abort)
+            .with_tag_separator('\t')
+            .with_duplicate_policy(DuplicatePolicy::Count);
+        let t =
+            ConfiguredLetterTrie::<T>::from_reader(COMPOSED.as_bytes(), options.clone()).unwrap();
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        assert_eq!(binary::BINARY_OPTIONS_VERSION, bytes[6]);
+
+        let loaded =
+            ConfiguredLetterTrie::<NoParentLetterTrie>::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(&options, loaded.options());
+        assert!(loaded.find("jalapeno").unwrap().is_word);
+        assert!(loaded.find("jalape\u{f1}o").unwrap().is_word);
+        assert!(loaded.contains("ARBOL"));
+        assert_eq!(
+            t.words().collect::<Vec<_>>(),
+            loaded.words().collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["ano", "a\u{f1}o"], loaded.spellings("ano"));
+        assert_eq!(Some(2), loaded.tag("a\u{f1}o"));
+        assert_eq!(t.trie().fingerprint(), loaded.trie().fingerprint());
+
+        // A plain trie reads the words as they were stored.
+        let plain = T::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(
+            t.trie().words().collect::<Vec<_>>(),
+            plain.words().collect::<Vec<_>>()
+        );
+
+        // The rest of the options can be changed at load time but not the ones that decide how words are looked up.
+        let loaded = ConfiguredLetterTrie::<T>::read_binary_with_options(
+            bytes.as_slice(),
+            folding().with_original_spelling(true),
+        )
+        .unwrap();
+        assert_eq!(None, loaded.options().max_words);
+        assert_eq!(vec!["ano", "a\u{f1}o"], loaded.spellings("ano"));
+        let err = ConfiguredLetterTrie::<T>::read_binary_with_options(bytes.as_slice(), folding())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            LetterTrieError::IncompatibleOptions {
+                option: "keep_original_spelling",
+                ..
+            }
+        ));
+        assert_eq!(
+            "Incompatible options: the trie was saved with keep_original_spelling = true but false was asked for",
+            err.to_string()
+        );
+
+        // The options block ending early or holding a value that isn't one of the choices.
+        let block_start = 15 + 8;
+        let result = ConfiguredLetterTrie::<T>::read_binary(&bytes[..block_start + 10]);
+        assert!(matches!(result, Err(LetterTrieError::Io(_))));
+        let mut bad_policy = bytes.clone();
+        bad_policy[block_start + 2] = 9;
+        let result = ConfiguredLetterTrie::<T>::read_binary(bad_policy.as_slice());
+        assert!(matches!(result, Err(LetterTrieError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn saved_options_base() {
+        check_saved_options::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn saved_options_no_parent() {
+        check_saved_options::<NoParentLetterTrie>();
+    }
+
+    // A file from before the options block was saved has the default options.
+    #[test]
+    fn file_without_options_has_defaults() {
+        let t = NoParentLetterTrie::from_words(vec!["jalape\u{f1}o", "an"]);
+        let mut bytes: Vec<u8> = vec![];
+        t.write_binary(&mut bytes).unwrap();
+        assert_eq!(binary::BINARY_VERSION, bytes[6]);
+
+        let loaded = ConfiguredLetterTrie::<BaseLetterTrie>::read_binary(bytes.as_slice()).unwrap();
+        assert_eq!(&TrieOptions::new(), loaded.options());
+        assert!(loaded.contains("JALAPE\u{d1}O"));
+        assert!(!loaded.contains("jalapeno"));
+        assert_eq!(
+            vec!["an", "jalape\u{f1}o"],
+            loaded.words().collect::<Vec<_>>()
+        );
+
+        let options = TrieOptions::new().with_max_word_len(5, LimitPolicy::Skip);
+        let loaded = ConfiguredLetterTrie::<BaseLetterTrie>::read_binary_with_options(
+            bytes.as_slice(),
+            options.clone(),
+        )
+        .unwrap();
+        assert_eq!(&options, loaded.options());
+        let result = ConfiguredLetterTrie::<BaseLetterTrie>::read_binary_with_options(
+            bytes.as_slice(),
+            folding(),
+        );
+        assert!(matches!(
+            result,
+            Err(LetterTrieError::IncompatibleOptions {
+                option: "fold_diacritics",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn file_errors_have_context() {
+        let path = temp_path("options_errors.txt");
+        let filename = path.to_str().unwrap();
+        let options = TrieOptions::new().with_tag_separator('\t');
+        let err = ConfiguredLetterTrie::<NoParentLetterTrie>::from_file(filename, options.clone())
+            .err()
+            .unwrap();
+        assert!(matches!(&err, LetterTrieError::File { path: p, .. } if *p == *path));
+        assert!(err.to_string().contains(filename), "{}", err);
+        let source = std::error::Error::source(&err)
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .unwrap();
+        assert_eq!(io::ErrorKind::NotFound, source.kind());
+
+        std::fs::write(&path, "cross\t1\nan\tmany\n").unwrap();
+        let err = ConfiguredLetterTrie::<NoParentLetterTrie>::from_file(filename, options.clone())
+            .err()
+            .unwrap();
+        assert!(
+            matches!(&err, LetterTrieError::InvalidLine { path: Some(p), line: 2, .. } if *p == *path)
+        );
+        assert_eq!(
+            format!(
+                "Invalid line 2 of \"{}\": \"many\" where a tag should be",
+                filename
+            ),
+            err.to_string()
+        );
+        assert!(std::error::Error::source(&err).is_none());
+
+        std::fs::write(&path, "cross\nan\nazure\n").unwrap();
+        let options = TrieOptions::new().with_max_words(2, LimitPolicy::Abort);
+        let err = ConfiguredLetterTrie::<NoParentLetterTrie>::from_file(filename, options)
+            .err()
+            .unwrap();
+        match &err {
+            LetterTrieError::LimitExceeded {
+                limit: "max_words",
+                max: 2,
+                line: 3,
+                word: Some(word),
+            } => assert_eq!("azure", word),
+            other => panic!("{:?}", other),
+        }
+        assert!(err.to_string().contains("line 3"), "{}", err);
+    }
+}
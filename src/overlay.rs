@@ -0,0 +1,358 @@
+//! A view of a large shared trie with a few words added and removed, for something like a dictionary of hundreds
+//! of thousands of words shared by every user along with each user's own words, without a copy of the large trie
+//! for each user.
+//!
+//! `OverlayTrie` borrows the shared trie, called the base, and never changes it. Words added through the overlay
+//! go into a small `NoParentLetterTrie` of its own, and words removed through it that are in the base are kept in
+//! a set of tombstones. Each query answers for the base's words, less the tombstones, along with the added words.
+//! An added word is only put in the small trie if the base doesn't already have it, and a tombstone is only kept
+//! for a word in the base, so no word is on both sides and a word the base has is never given twice.
+//!
+//! The queries for the words under a prefix, such as `words_with_prefix()` and `count_with_prefix()`, walk the
+//! base's words under the prefix as the base's own queries do, merging in the added words in order. `find()` is
+//! passed straight to the base when nothing under the prefix has been added or removed, and otherwise puts the
+//! node together from the words under the prefix, which takes as long as `words_with_prefix()`.
+
+use alloc::collections::BTreeSet;
+use core::ops::Bound;
+
+use crate::*;
+
+/// A trie that borrows a base trie and keeps its own words added to and removed from it. See the `overlay`
+/// module.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let base = BaseLetterTrie::from_words(vec!["cross", "crossed", "crease", "an"]);
+/// let mut user = OverlayTrie::new(&base);
+/// user.insert("Crossbow");
+/// assert!(user.remove("crease"));
+/// assert_eq!(vec!["cross", "crossbow", "crossed"], user.words_with_prefix("cr"));
+/// assert_eq!(3, user.count_with_prefix("cr"));
+/// assert_eq!(vec!["an", "cross"], user.suggest("", 2));
+///
+/// // The base is as it was.
+/// assert!(base.contains("crease"));
+/// assert!(!base.contains("crossbow"));
+/// ```
+pub struct OverlayTrie<'a> {
+    base: &'a dyn LetterTrie,
+    // The added words, none of which are in the base.
+    added: NoParentLetterTrie,
+    // The removed words, all of which are in the base, lowercased as they're stored.
+    removed: BTreeSet<String>,
+}
+
+impl<'a> OverlayTrie<'a> {
+    /// Make an overlay on `base` with nothing added or removed yet.
+    pub fn new(base: &'a dyn LetterTrie) -> Self {
+        Self {
+            base,
+            added: NoParentLetterTrie::new(),
+            removed: BTreeSet::new(),
+        }
+    }
+
+    /// The trie the overlay is on.
+    pub fn base(&self) -> &'a dyn LetterTrie {
+        self.base
+    }
+
+    /// The words added through the overlay that aren't in the base, in alphabetical order.
+    pub fn added(&self) -> Vec<String> {
+        self.added.words_with_prefix("")
+    }
+
+    /// The words of the base removed through the overlay, in alphabetical order.
+    pub fn removed(&self) -> Vec<String> {
+        self.removed.iter().cloned().collect()
+    }
+
+    /// Add a word as `LetterTrie::insert()` does. A word the base has is only taken off the tombstones, if it was
+    /// removed, rather than being added again.
+    pub fn insert(&mut self, word: &str) {
+        if let Some(key) = word_key(word) {
+            if self.base.contains(&key) {
+                self.removed.remove(&key);
+            } else {
+                self.added.insert(&key);
+            }
+        }
+    }
+
+    /// Remove a word as `LetterTrie::remove()` does, returning whether it was there. A word of the base is left in
+    /// the base and only hidden.
+    pub fn remove(&mut self, word: &str) -> bool {
+        match word_key(word) {
+            Some(key) if self.base.contains(&key) => self.removed.insert(key),
+            Some(key) => self.added.remove(&key),
+            None => false,
+        }
+    }
+
+    /// Whether `word` is in the trie, as in `LetterTrie::contains()`.
+    pub fn contains(&self, word: &str) -> bool {
+        match word_key(word) {
+            Some(key) if self.base.contains(&key) => !self.removed.contains(&key),
+            Some(key) => self.added.contains(&key),
+            None => false,
+        }
+    }
+
+    /// Whether `prefix` is a word or the start of a word in the trie, as in `LetterTrie::contains_prefix()`.
+    pub fn contains_prefix(&self, prefix: &str) -> bool {
+        let prefix = prefix.trim();
+        if prefix.is_empty() || self.added.contains_prefix(prefix) {
+            return true;
+        }
+        let prefix = prefix.to_lowercase();
+        if self.removed_with_prefix(&prefix).next().is_none() {
+            return self.base.contains_prefix(&prefix);
+        }
+        let mut found = false;
+        self.base.visit(&prefix, &mut |node| {
+            found = found || (node.is_word && !self.removed.contains(node.prefix));
+            !found
+        });
+        found
+    }
+
+    /// Find the node for a word or a partial word as in `LetterTrie::find()`, giving the node the trie would have
+    /// if it held the words it answers for. If anything under the prefix has been added or removed this walks
+    /// every word under it.
+    pub fn find(&self, prefix: &str) -> Option<FixedNode> {
+        let prefix = prefix.trim().to_lowercase();
+        if self.removed_with_prefix(&prefix).next().is_none()
+            && !self.added.contains_prefix(&prefix)
+        {
+            return self.base.find(&prefix);
+        }
+        let words = self.entries(&prefix).into_iter().map(|(word, _)| word);
+        NoParentLetterTrie::from_words(words).find(&prefix)
+    }
+
+    /// Get all of the words that start with `prefix` in alphabetical order, as in
+    /// `LetterTrie::words_with_prefix()`. A word of the base with a spelling from
+    /// `LetterTrie::insert_preserving_case()` is given in that spelling.
+    pub fn words_with_prefix(&self, prefix: &str) -> Vec<String> {
+        self.entries(&prefix.trim().to_lowercase())
+            .into_iter()
+            .map(|(_, shown)| shown)
+            .collect()
+    }
+
+    /// Iterate over all of the words in alphabetical order, as in `LetterTrie::words()`.
+    pub fn words(&self) -> Words {
+        Words {
+            inner: self.words_with_prefix("").into_iter(),
+        }
+    }
+
+    /// Get the number of words that start with `prefix`, as in `LetterTrie::count_with_prefix()`. This counts the
+    /// base's words under the prefix as `LetterTrie::count_with_prefix()` does and then makes up for the words
+    /// added and removed there, so it's no slower than asking the base.
+    pub fn count_with_prefix(&self, prefix: &str) -> usize {
+        let prefix = prefix.trim().to_lowercase();
+        self.base.count_with_prefix(&prefix) - self.removed_with_prefix(&prefix).count()
+            + self.added.count_with_prefix(&prefix)
+    }
+
+    /// Get the number of words in the trie.
+    pub fn word_count(&self) -> usize {
+        self.count_with_prefix("")
+    }
+
+    /// Get up to `max_count` of the shortest words that start with `prefix`, alphabetically among words of the
+    /// same length, as in `LetterTrie::suggest()`.
+    pub fn suggest(&self, prefix: &str, max_count: usize) -> Vec<String> {
+        let prefix = prefix.trim().to_lowercase();
+        // Each removed word under the prefix could push one of the base's suggestions out, so asking for that
+        // many more is enough to be left with max_count of them.
+        let from_base = self.base.suggest(
+            &prefix,
+            max_count + self.removed_with_prefix(&prefix).count(),
+        );
+        let mut best: Vec<(usize, String, String)> = from_base
+            .into_iter()
+            .map(|shown| (shown.to_lowercase(), shown))
+            .filter(|(word, _)| !self.removed.contains(word))
+            .chain(
+                self.added
+                    .suggest(&prefix, max_count)
+                    .into_iter()
+                    .map(|word| (word.clone(), word)),
+            )
+            .map(|(word, shown)| (word.chars().count(), word, shown))
+            .collect();
+        best.sort();
+        best.truncate(max_count);
+        best.into_iter().map(|(_, _, shown)| shown).collect()
+    }
+
+    // The removed words that start with prefix, in order.
+    fn removed_with_prefix<'b>(&'b self, prefix: &'b str) -> impl Iterator<Item = &'b String> + 'b {
+        self.removed
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |word| word.starts_with(prefix))
+    }
+
+    // The words under a lowercased prefix in alphabetical order, each as it's stored and as it's shown: the base's
+    // words other than the removed ones, merged with the added words.
+    fn entries(&self, prefix: &str) -> Vec<(String, String)> {
+        let mut added = self.added.words_with_prefix(prefix).into_iter().peekable();
+        let mut entries = vec![];
+        self.base.visit(prefix, &mut |node| {
+            if node.is_word && !self.removed.contains(node.prefix) {
+                while let Some(word) = added.next_if(|word| word.as_str() < node.prefix) {
+                    entries.push((word.clone(), word));
+                }
+                // The added words aren't in the base, but a word in both would only be given once.
+                added.next_if(|word| word == node.prefix);
+                entries.push((node.prefix.to_owned(), node.word().to_owned()));
+            }
+            true
+        });
+        entries.extend(added.map(|word| (word.clone(), word)));
+        entries
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    // The overlay answers every query the way a trie of the words it stands for does.
+    fn check_like_trie(overlay: &OverlayTrie, expected: &NoParentLetterTrie) {
+        let mut probes = vec![
+            String::new(),
+            "CR".to_owned(),
+            "xyzzy".to_owned(),
+            "crossbowss".to_owned(),
+        ];
+        probes.extend(expected.words_with_prefix(""));
+        probes.extend(overlay.removed());
+        for word in expected.words_with_prefix("") {
+            let chars: Vec<char> = word.chars().collect();
+            probes.extend((1..chars.len()).map(|len| chars[..len].iter().collect::<String>()));
+        }
+        for probe in &probes {
+            assert_eq!(
+                expected.contains(probe),
+                overlay.contains(probe),
+                "{}",
+                probe
+            );
+            assert_eq!(
+                expected.contains_prefix(probe),
+                overlay.contains_prefix(probe)
+            );
+            assert_eq!(expected.find(probe), overlay.find(probe), "{}", probe);
+            assert_eq!(
+                expected.words_with_prefix(probe),
+                overlay.words_with_prefix(probe)
+            );
+            assert_eq!(
+                expected.count_with_prefix(probe),
+                overlay.count_with_prefix(probe)
+            );
+            for max_count in 0..4 {
+                assert_eq!(
+                    expected.suggest(probe, max_count),
+                    overlay.suggest(probe, max_count),
+                    "{} {}",
+                    probe,
+                    max_count
+                );
+            }
+        }
+        assert_eq!(
+            expected.words().collect::<Vec<_>>(),
+            overlay.words().collect::<Vec<_>>()
+        );
+        assert_eq!(expected.count_with_prefix(""), overlay.word_count());
+    }
+
+    fn check_overlay<T: LetterTrie>() {
+        let base = T::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap();
+        let base_words: Vec<String> = base.words().collect();
+        let fingerprint = base.fingerprint();
+
+        let mut overlay = OverlayTrie::new(&base);
+        let mut expected = NoParentLetterTrie::from_words(&base_words);
+        check_like_trie(&overlay, &expected);
+
+        for word in &[
+            "Crossbow",
+            " zebra ",
+            "cross",
+            "aardvark",
+            "ñu",
+            "crossbowman",
+        ] {
+            overlay.insert(word);
+            expected.insert(word);
+        }
+        // Every third word of the base along with one of the added ones, and words that aren't there.
+        for word in base_words
+            .iter()
+            .step_by(3)
+            .map(String::as_str)
+            .chain(vec!["zebra"])
+        {
+            assert!(overlay.remove(word), "{}", word);
+            assert!(expected.remove(word));
+        }
+        assert!(!overlay.remove("zebra"));
+        assert!(!overlay.remove("qqq"));
+        assert!(!overlay.remove(""));
+        check_like_trie(&overlay, &expected);
+        assert_eq!(
+            vec!["aardvark", "crossbow", "crossbowman", "ñu"],
+            overlay.added()
+        );
+        assert_eq!(base_words.len().div_ceil(3), overlay.removed().len());
+
+        // A removed word of the base comes back when it's added again, and isn't added to the overlay's own words.
+        let back = &base_words[3];
+        overlay.insert(&back.to_uppercase());
+        expected.insert(back);
+        assert!(overlay.contains(back));
+        assert!(!overlay.added().contains(back));
+        check_like_trie(&overlay, &expected);
+
+        assert_eq!(fingerprint, base.fingerprint());
+        assert_eq!(base_words, base.words().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn overlay_base() {
+        check_overlay::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn overlay_no_parent() {
+        check_overlay::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn overlay_keeps_spellings() {
+        let mut base = NoParentLetterTrie::from_words(vec!["cross", "an"]);
+        base.insert_preserving_case("Creator", CasePreference::FirstSeen);
+        let boxed: Box<dyn LetterTrie> = Box::new(base);
+        let mut overlay = OverlayTrie::new(&*boxed);
+        overlay.insert("crate");
+        overlay.insert("Creatures");
+        assert_eq!(
+            vec!["crate", "Creator", "creatures", "cross"],
+            overlay.words_with_prefix("CR")
+        );
+        assert_eq!(vec!["Creator", "creatures"], overlay.suggest("crea", 2));
+        assert!(overlay.remove("CREATOR"));
+        assert_eq!(vec!["creatures"], overlay.suggest("crea", 2));
+        assert_eq!(vec!["crate", "cross"], overlay.suggest("cr", 2));
+        assert!(boxed.contains("creator"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Finding words that sound alike using [Soundex](https://en.wikipedia.org/wiki/Soundex) codes.
+//!
+//! `soundex()` gives the four-character American Soundex code for a word, and a `PhoneticIndex` built from a trie
+//! finds the words in that trie with the same code as a given word.
+
+use crate::*;
+
+// Separates the code from the word in the keys of the index's trie. Codes are only letters and digits so this
+// can't appear in one.
+const INDEX_SEPARATOR: char = ':';
+
+/// Get the American Soundex code for `word`: its first letter in upper case followed by three digits for the
+/// consonant sounds that come after it, padded with zeros.
+///
+/// Only the ASCII letters in `word` are used, in either case. Anything else, including letters with diacritics,
+/// is skipped as if it weren't there. A word with no ASCII letters, such as an empty string, has an empty code.
+/// A word whose letters after the first are all vowels has a code ending in "000".
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::phonetic::soundex;
+///
+/// assert_eq!("R163", soundex("Robert"));
+/// assert_eq!("R163", soundex("Rupert"));
+/// assert_eq!("A261", soundex("Ashcraft"));
+/// assert_eq!("A000", soundex("aeiou"));
+/// assert_eq!("", soundex(""));
+/// ```
+pub fn soundex(word: &str) -> String {
+    let mut letters = word
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase());
+    let first = match letters.next() {
+        Some(c) => c,
+        None => return String::new(),
+    };
+    let mut code = String::with_capacity(4);
+    code.push(first);
+    // A letter with the same digit as the one before it isn't coded again, which includes the first letter.
+    let mut last_digit = soundex_digit(first);
+    for c in letters {
+        if code.len() == 4 {
+            break;
+        }
+        // H and W are skipped without separating the letters on either side of them, while vowels do separate
+        // them so that the same digit can appear twice.
+        if c != 'H' && c != 'W' {
+            let digit = soundex_digit(c);
+            if let Some(d) = digit {
+                if digit != last_digit {
+                    code.push(d);
+                }
+            }
+            last_digit = digit;
+        }
+    }
+    while code.len() < 4 {
+        code.push('0');
+    }
+    code
+}
+
+// The digit for an upper case consonant, or None for a vowel, H, W or Y.
+fn soundex_digit(c: char) -> Option<char> {
+    match c {
+        'B' | 'F' | 'P' | 'V' => Some('1'),
+        'C' | 'G' | 'J' | 'K' | 'Q' | 'S' | 'X' | 'Z' => Some('2'),
+        'D' | 'T' => Some('3'),
+        'L' => Some('4'),
+        'M' | 'N' => Some('5'),
+        'R' => Some('6'),
+        _ => None,
+    }
+}
+
+/// An index from Soundex codes to the words of a trie that have them.
+///
+/// The index is itself a trie holding each word behind its code, so the words for a code are found with a
+/// prefix search.
+pub struct PhoneticIndex {
+    trie: NoParentLetterTrie,
+}
+
+impl PhoneticIndex {
+    /// Build an index of every word in `trie` that has a Soundex code. Words with no ASCII letters have no code
+    /// and are left out.
+    pub fn from_trie<T: LetterTrie + ?Sized>(trie: &T) -> Self {
+        let mut index = NoParentLetterTrie::new();
+        for word in trie.words() {
+            let code = soundex(&word);
+            if !code.is_empty() {
+                index.insert(&format!("{}{}{}", code, INDEX_SEPARATOR, word));
+            }
+        }
+        Self { trie: index }
+    }
+
+    /// Get the words in the index with the same Soundex code as `word`, in alphabetical order. `word` itself is
+    /// included if it's in the index. Nothing is found for a word with no code.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use letter_trie::phonetic::PhoneticIndex;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["robert", "rupert", "rubin", "ashcraft"]);
+    /// let index = PhoneticIndex::from_trie(&trie);
+    /// assert_eq!(vec!["robert", "rupert"], index.similar("Robbert"));
+    /// assert!(index.similar("").is_empty());
+    /// ```
+    pub fn similar(&self, word: &str) -> Vec<String> {
+        let code = soundex(word);
+        if code.is_empty() {
+            return vec![];
+        }
+        let prefix = format!("{}{}", code, INDEX_SEPARATOR);
+        self.trie
+            .words_with_prefix(&prefix)
+            .into_iter()
+            .map(|key| key[prefix.len()..].to_owned())
+            .collect()
+    }
+
+    /// The number of words in the index.
+    pub fn word_count(&self) -> usize {
+        self.trie.count_with_prefix("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_codes() {
+        for (word, code) in &[
+            ("Robert", "R163"),
+            ("Rupert", "R163"),
+            ("Rubin", "R150"),
+            ("Ashcraft", "A261"),
+            ("Ashcroft", "A261"),
+            ("Tymczak", "T522"),
+            ("Pfister", "P236"),
+            ("Honeyman", "H555"),
+            ("Lee", "L000"),
+        ] {
+            assert_eq!(*code, soundex(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        assert_eq!("", soundex(""));
+        assert_eq!("", soundex("123 -"));
+        assert_eq!("", soundex("ßé"));
+        assert_eq!("C300", soundex("écoute"));
+        assert_eq!("A000", soundex("aeiou"));
+        assert_eq!("Y000", soundex("y"));
+        // The non-ASCII letters are dropped, leaving "Mller".
+        assert_eq!("M460", soundex("Müller"));
+        assert_eq!(soundex("robert"), soundex("ROBERT"));
+        assert_eq!(soundex("o'brien"), soundex("obrien"));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn small_dataset_lookup() {
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous)
+                .unwrap();
+        let index = PhoneticIndex::from_trie(&t);
+        assert_eq!(10, index.word_count());
+        assert_eq!(vec!["creator", "creature"], index.similar("creater"));
+        assert_eq!(vec!["crease", "cross"], index.similar("Cros"));
+        assert_eq!(vec!["crossed"], index.similar("crossed"));
+        assert!(index.similar("xylophone").is_empty());
+    }
+}
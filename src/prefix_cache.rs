@@ -0,0 +1,323 @@
+//! A small cache of the nodes `LetterTrie::find()` returns for short prefixes, for query loads where most
+//! prefixes start with a few hot letter combinations.
+//!
+//! The cache is off until `LetterTrie::enable_prefix_cache()` turns it on. It holds the `FixedNode` for up to
+//! `max_entries` of the most recently found prefixes of no more than `MAX_CACHED_PREFIX_LEN` letters, including the
+//! prefixes that weren't found, and forgets the one used longest ago when it's full. A `FixedNode` for a short
+//! prefix is the expensive kind to make, since its counts cover most of the trie below the first letters.
+//!
+//! Everything that changes the words of the trie empties the cache, so a cached node is always the one `find()`
+//! would give now. The trie's own methods do this, including `BaseLetterTrie::merge()` and the others that change
+//! the trie through a shared reference.
+//!
+//! The entries and counts are behind a `Mutex` so that a trie with a cache can still be shared between threads.
+
+use std::sync::Mutex;
+
+use crate::*;
+
+/// The longest prefix, in letters, that's kept in the cache.
+pub(crate) const MAX_CACHED_PREFIX_LEN: usize = 3;
+
+/// How the cache from `LetterTrie::enable_prefix_cache()` has done since it was turned on. The counts only
+/// include the queries short enough to be cached, and keep going when the cache is emptied by a change.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "crate"]);
+/// trie.enable_prefix_cache(16);
+/// assert_eq!(3, trie.find("cr").unwrap().word_count);
+/// assert_eq!(3, trie.find("CR").unwrap().word_count);
+/// trie.insert("crab");
+/// assert_eq!(4, trie.find("cr").unwrap().word_count);
+/// let stats = trie.cache_stats();
+/// assert_eq!((1, 2), (stats.hits, stats.misses));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The queries answered from the cache.
+    pub hits: u64,
+    /// The queries that had to walk the trie, after which their nodes were kept.
+    pub misses: u64,
+    /// The prefixes in the cache now.
+    pub entries: usize,
+    /// The most prefixes the cache will hold, or 0 if it's off.
+    pub max_entries: usize,
+}
+
+pub(crate) struct PrefixCache {
+    max_entries: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    // The lowercased prefixes and their nodes, with the most recently used last. The cache is small, so finding a
+    // prefix by looking at each one is quicker than hashing it.
+    entries: Vec<(String, Option<FixedNode>)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl PrefixCache {
+    pub(crate) fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            inner: Mutex::new(Inner {
+                entries: Vec::with_capacity(max_entries),
+                ..Default::default()
+            }),
+        }
+    }
+
+    // The node for prefix from the cache if it's there, and otherwise from find, which is only called for a miss.
+    // A prefix too long to be cached goes straight to find.
+    pub(crate) fn find(
+        &self,
+        prefix: &str,
+        find: impl FnOnce() -> Option<FixedNode>,
+    ) -> Option<FixedNode> {
+        let key = match cache_key(prefix) {
+            Some(key) if self.max_entries > 0 => key,
+            _ => return find(),
+        };
+        let mut inner = lock(&self.inner);
+        if let Some(index) = inner.entries.iter().position(|(k, _)| *k == key) {
+            inner.hits += 1;
+            let entry = inner.entries.remove(index);
+            let node = entry.1.clone();
+            inner.entries.push(entry);
+            return node;
+        }
+        inner.misses += 1;
+        // The lock isn't held during the walk so that other threads can use the cache meanwhile. One of them may
+        // cache the same prefix first, but the trie can't change while it's shared, so its node is the same.
+        drop(inner);
+        let node = find();
+        let mut inner = lock(&self.inner);
+        if !inner.entries.iter().any(|(k, _)| *k == key) {
+            if inner.entries.len() == self.max_entries {
+                inner.entries.remove(0);
+            }
+            inner.entries.push((key, node.clone()));
+        }
+        node
+    }
+
+    pub(crate) fn clear(&self) {
+        lock(&self.inner).entries.clear();
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let inner = lock(&self.inner);
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.entries.len(),
+            max_entries: self.max_entries,
+        }
+    }
+}
+
+// The form of prefix the cache keeps, or None if it's too long to be cached.
+fn cache_key(prefix: &str) -> Option<String> {
+    if prefix.is_ascii() {
+        (prefix.len() <= MAX_CACHED_PREFIX_LEN).then(|| prefix.to_ascii_lowercase())
+    } else {
+        let key = prefix.to_lowercase();
+        (key.chars().count() <= MAX_CACHED_PREFIX_LEN).then_some(key)
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use crate::generator::SplitMix64;
+    use test::Bencher;
+
+    fn check_never_stale<T: LetterTrie>() {
+        let mut t = T::from_words(vec!["cross", "crossed", "crate", "an", "Ñandú"]);
+        t.enable_prefix_cache(4);
+        let probes = [
+            "c", "cr", "cro", "CRO", "a", "an", "q", "ñan", "cros", "crossed",
+        ];
+        let check = |t: &T| {
+            let fresh = T::from_words(t.words());
+            for probe in probes {
+                assert_eq!(fresh.find(probe), t.find(probe), "{}", probe);
+            }
+        };
+        check(&t);
+        check(&t);
+        assert!(t.cache_stats().hits > 0);
+        assert_eq!(4, t.cache_stats().entries);
+
+        // Each change that could alter one of the cached nodes, made after the nodes were cached.
+        t.insert("crossing");
+        check(&t);
+        t.insert("ant");
+        check(&t);
+        t.remove("an");
+        check(&t);
+        t.remove("ant");
+        check(&t);
+        t.insert_with_tag("cry", 1);
+        check(&t);
+        t.insert_with_source("qua", 2);
+        check(&t);
+        t.insert_with_spelling("Crow", "Crow", CasePreference::FirstSeen);
+        check(&t);
+        t.remove_sorted_words(&["cross".chars().collect::<Vec<_>>()]);
+        check(&t);
+        t.merge_from(&NoParentLetterTrie::from_words(vec!["a", "cr"]));
+        check(&t);
+
+        let stats = t.cache_stats();
+        assert!(stats.hits > 0 && stats.misses > 0);
+        assert!(stats.entries <= 4);
+        t.enable_prefix_cache(0);
+        check(&t);
+        assert_eq!(CacheStats::default(), t.cache_stats());
+    }
+
+    #[test]
+    fn never_stale() {
+        check_never_stale::<BaseLetterTrie>();
+        check_never_stale::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn base_merge_empties_cache() {
+        let mut t = BaseLetterTrie::from_words(vec!["cross"]);
+        t.enable_prefix_cache(8);
+        assert_eq!(None, t.find("ap"));
+        assert_eq!(1, t.find("cr").unwrap().word_count);
+        // Both of these change the trie through a shared reference.
+        t.merge(BaseLetterTrie::from_words(vec!["apple"]));
+        assert_eq!(1, t.find("ap").unwrap().word_count);
+        t.add_from_vec_chars(&['c', 'r', 'y'], 3, 0);
+        assert_eq!(2, t.find("cr").unwrap().word_count);
+        t.freeze();
+        assert_eq!(2, t.find("cr").unwrap().word_count);
+        assert_eq!(1, t.cache_stats().hits);
+    }
+
+    #[test]
+    fn least_recently_used_goes_first() {
+        let t = NoParentLetterTrie::from_words(vec!["a", "b", "c"]);
+        let cache = PrefixCache::new(2);
+        let node = |prefix: &str| t.find(prefix);
+        cache.find("a", || node("a"));
+        cache.find("b", || node("b"));
+        cache.find("A", || unreachable!());
+        cache.find("c", || node("c"));
+        // "b" was used longest ago.
+        assert_eq!(node("b"), cache.find("b", || node("b")));
+        cache.find("c", || unreachable!());
+        assert_eq!(
+            CacheStats {
+                hits: 2,
+                misses: 4,
+                entries: 2,
+                max_entries: 2
+            },
+            cache.stats()
+        );
+        // Too long to be cached, so not counted.
+        cache.find("abcd", || None);
+        assert_eq!(6, cache.stats().hits + cache.stats().misses);
+    }
+
+    #[test]
+    fn poisoned_cache_still_answers() {
+        let t = NoParentLetterTrie::from_words(vec!["cross", "an"]);
+        let cache = PrefixCache::new(2);
+        cache.find("cr", || t.find("cr"));
+        // A thread that panics while holding the lock leaves the counts and entries as they were.
+        let _ = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _inner = cache.inner.lock();
+                    panic!("poisoning the prefix cache");
+                })
+                .join()
+        });
+        assert!(cache.inner.is_poisoned());
+        assert_eq!(t.find("cr"), cache.find("cr", || unreachable!()));
+        assert_eq!(t.find("a"), cache.find("a", || t.find("a")));
+        assert_eq!(
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                entries: 2,
+                max_entries: 2
+            },
+            cache.stats()
+        );
+        cache.clear();
+        assert_eq!(0, cache.stats().entries);
+    }
+
+    // A query load over the large trie in which the hot two-letter prefixes come up far more often than the rest,
+    // with the chance of each prefix falling off with its rank as in Zipf's law.
+    fn zipf_queries() -> (NoParentLetterTrie, Vec<String>) {
+        let t = NoParentLetterTrie::from_dataset(
+            &Dataset::TestLargeSorted,
+            &LoadMethod::ContinuousParallel,
+        )
+        .unwrap();
+        let mut prefixes: Vec<(usize, String)> = vec![];
+        for first in t.children("") {
+            for second in t.children(&first.to_string()) {
+                let prefix: String = [first, second].iter().collect();
+                prefixes.push((t.count_with_prefix(&prefix), prefix));
+            }
+        }
+        prefixes.sort_by(|a, b| b.cmp(a));
+        let weights: Vec<f64> = (1..=prefixes.len()).map(|rank| 1.0 / rank as f64).collect();
+        let total: f64 = weights.iter().sum();
+        let mut rng = SplitMix64(175);
+        let queries = (0..200)
+            .map(|_| {
+                let mut target = rng.below(1 << 20) as f64 / (1 << 20) as f64 * total;
+                let index = weights
+                    .iter()
+                    .position(|weight| {
+                        target -= weight;
+                        target < 0.0
+                    })
+                    .unwrap_or(weights.len() - 1);
+                prefixes[index].1.clone()
+            })
+            .collect();
+        (t, queries)
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_zipf_find_uncached(b: &mut Bencher) {
+        let (t, queries) = zipf_queries();
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|prefix| t.find(prefix).unwrap().word_count)
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_zipf_find_cached(b: &mut Bencher) {
+        let (mut t, queries) = zipf_queries();
+        t.enable_prefix_cache(32);
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|prefix| t.find(prefix).unwrap().word_count)
+                .sum::<usize>()
+        });
+    }
+}
@@ -0,0 +1,69 @@
+//! The types and traits that most code using the crate needs, so that one import is enough:
+//!
+//! ```rust
+//! use letter_trie::prelude::*;
+//! ```
+//!
+//! This brings in the `LetterTrie` and `TrieQuery` traits, which have to be in scope to call the queries on any of
+//! the tries or on a `TrieView`, and the extension traits with the limited, scored, tagged and regex queries, along
+//! with the trie types, the loading options and datasets, `TrieOptions` and the `ConfiguredLetterTrie` it builds,
+//! and the error types. Anything more specialized is still under its own module or at the crate root.
+//!
+//! # Examples
+//!
+//! Loading a word list, querying it, and building a configured trie with nothing but the prelude:
+//!
+//! ```rust
+//! use letter_trie::prelude::*;
+//!
+//! let trie = NoParentLetterTrie::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous)?;
+//! assert!(trie.contains("Creature"));
+//! assert_eq!(
+//!     vec!["crease", "creative", "creator", "creature"],
+//!     trie.words_with_prefix("crea")
+//! );
+//! assert_eq!(10, trie.to_fixed_node().word_count);
+//!
+//! let opt = DisplayDetailOptions::make_no_display();
+//! let sorted = "a\nan\nand\nazure\ncrease\ncreative\ncreator\ncreature\ncross\ncrossed\n";
+//! let base = BaseLetterTrie::from_reader_test(
+//!     sorted.as_bytes(),
+//!     true,
+//!     &LoadMethod::VecFill,
+//!     &opt,
+//!     None,
+//! )?;
+//! assert!(trie.diff(&base).is_empty());
+//!
+//! let options = TrieOptions::new()
+//!     .with_fold_diacritics(true)
+//!     .with_max_word_len(8, LimitPolicy::Skip);
+//! let configured: ConfiguredLetterTrie<BaseLetterTrie> =
+//!     ConfiguredLetterTrie::from_reader("jalapeño\ncross\nextraordinary\n".as_bytes(), options)?;
+//! assert!(configured.contains("jalapeno"));
+//! assert_eq!(1, configured.load_report().skipped_too_long);
+//!
+//! match "cottage".parse::<LetterTrieType>() {
+//!     Err(err) => assert_eq!("trie type", err.kind),
+//!     Ok(_) => unreachable!(),
+//! }
+//! # Ok::<(), LetterTrieError>(())
+//! ```
+
+pub use crate::base_letter_trie::BaseLetterTrie;
+pub use crate::no_parent_letter_trie::NoParentLetterTrie;
+pub use crate::options::{
+    CasePreference, ConfiguredLetterTrie, DuplicatePolicy, LimitPolicy, LoadReport, TrieOptions,
+    Utf8Policy,
+};
+pub use crate::static_trie::StaticLetterTrie;
+#[cfg(feature = "fs")]
+pub use crate::DatasetError;
+#[cfg(feature = "regex")]
+pub use crate::RegexQuery;
+#[cfg(feature = "std")]
+pub use crate::{Dataset, DisplayDetailOptions};
+pub use crate::{
+    FixedNode, LetterTrie, LetterTrieError, LetterTrieType, LimitedQuery, LoadMethod, NodeView,
+    ScoredQuery, TaggedLetterTrie, TrieQuery, TrieView, UnknownNameError,
+};
@@ -0,0 +1,185 @@
+// Property-based tests that check every implementation and load path against a BTreeSet<String> model on random
+// sets of words. The strategies lean toward the shapes most likely to trip up a trie: shared prefixes, words that
+// are prefixes of each other, one-letter words, repeated words, and upper case that folds onto lower case.
+
+use std::collections::BTreeSet;
+
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use crate::*;
+
+// A few letters so that random words share prefixes often, plus upper case and a character that's two bytes in
+// UTF-8.
+fn word() -> impl Strategy<Value = String> {
+    "[abcAé]{1,7}"
+}
+
+fn prefix() -> impl Strategy<Value = String> {
+    "[abcAé]{0,3}"
+}
+
+fn word_list() -> impl Strategy<Value = Vec<String>> {
+    let list = prop_oneof![
+        prop::collection::vec(word(), 0..40),
+        // Each word along with every shorter word it starts with.
+        prop::collection::vec(word(), 0..8).prop_map(|words| {
+            words
+                .iter()
+                .flat_map(|word| {
+                    word.char_indices()
+                        .map(|(i, c)| word[..i + c.len_utf8()].to_owned())
+                        .collect::<Vec<String>>()
+                })
+                .collect()
+        }),
+        prop::collection::vec("[a-zA-Z]", 0..40),
+        // A handful of words repeated many times.
+        prop::collection::vec(word(), 1..6)
+            .prop_flat_map(|words| prop::collection::vec(select(words), 0..40)),
+    ];
+    list.prop_shuffle()
+}
+
+// The words the way a trie stores them, sorted and without duplicates.
+fn model(words: &[String]) -> BTreeSet<String> {
+    words
+        .iter()
+        .map(|word| word.trim().to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn model_with_prefix(model: &BTreeSet<String>, prefix: &str) -> BTreeSet<String> {
+    let prefix = prefix.to_lowercase();
+    model
+        .iter()
+        .filter(|word| word.starts_with(&prefix))
+        .cloned()
+        .collect()
+}
+
+fn words_of<T: LetterTrie>(t: &T) -> Vec<String> {
+    t.words().collect()
+}
+
+// Every way of building a trie from a list of words.
+fn build_all<T: LetterTrie>(words: &[String]) -> Vec<(String, T)> {
+    let sorted_words: Vec<String> = model(words).into_iter().collect();
+    let content = words.join("\n");
+    let sorted_content = sorted_words.join("\n");
+    let mut tries = vec![("from_words".to_owned(), T::from_words(words))];
+    for load_method in &ALL_LOAD_METHODS {
+        for (content, is_sorted) in &[(&content, false), (&sorted_content, true)] {
+            let t = T::from_reader(content.as_bytes(), *is_sorted, load_method).unwrap();
+            tries.push((format!("{:?}, is_sorted = {}", load_method, is_sorted), t));
+        }
+    }
+    tries
+}
+
+fn check_round_trip<T: LetterTrie>(words: &[String]) -> Result<(), TestCaseError> {
+    let expected: Vec<String> = model(words).into_iter().collect();
+    for (label, t) in build_all::<T>(words) {
+        prop_assert_eq!(&expected, &words_of(&t), "{}", label);
+        prop_assert!(t.verify_integrity().is_ok(), "{}", label);
+    }
+    Ok(())
+}
+
+fn check_counts<T: LetterTrie>(words: &[String], prefixes: &[String]) -> Result<(), TestCaseError> {
+    let model = model(words);
+    for (label, t) in build_all::<T>(words) {
+        for prefix in prefixes {
+            prop_assert_eq!(
+                model_with_prefix(&model, prefix).len(),
+                t.count_with_prefix(prefix),
+                "{}: count_with_prefix({:?})",
+                label,
+                prefix
+            );
+        }
+    }
+    Ok(())
+}
+
+fn check_membership<T: LetterTrie>(
+    words: &[String],
+    probes: &[String],
+) -> Result<(), TestCaseError> {
+    let model = model(words);
+    let t = T::from_words(words);
+    for probe in words.iter().chain(probes) {
+        let expected = model.contains(&probe.to_lowercase());
+        let found = t.find(probe).map(|node| node.is_word).unwrap_or(false);
+        prop_assert_eq!(expected, found, "find({:?})", probe);
+        prop_assert_eq!(expected, t.contains(probe), "contains({:?})", probe);
+    }
+    Ok(())
+}
+
+fn check_set_algebra<T: LetterTrie>(
+    words: &[String],
+    other_words: &[String],
+    prefix: &str,
+) -> Result<(), TestCaseError> {
+    let model_a = model(words);
+    let model_b = model(other_words);
+
+    let mut t = T::from_words(words);
+    t.merge_from(&T::from_words(other_words));
+    let union: BTreeSet<String> = model_a.union(&model_b).cloned().collect();
+    let expected: Vec<String> = union.iter().cloned().collect();
+    prop_assert_eq!(&expected, &words_of(&t), "merge_from");
+    prop_assert!(t.verify_integrity().is_ok(), "merge_from");
+
+    let sub = t.subtrie(prefix);
+    let expected: Vec<String> = model_with_prefix(&union, prefix).into_iter().collect();
+    prop_assert_eq!(&expected, &words_of(&sub), "subtrie({:?})", prefix);
+    prop_assert!(sub.verify_integrity().is_ok(), "subtrie({:?})", prefix);
+
+    let mut t = T::from_words(words);
+    for word in other_words {
+        t.remove(word);
+    }
+    let difference: Vec<String> = model_a.difference(&model_b).cloned().collect();
+    prop_assert_eq!(&difference, &words_of(&t), "remove");
+    prop_assert!(t.verify_integrity().is_ok(), "remove");
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn words_round_trip(words in word_list()) {
+        check_round_trip::<BaseLetterTrie>(&words)?;
+        check_round_trip::<NoParentLetterTrie>(&words)?;
+    }
+
+    #[test]
+    fn counts_match_model(
+        words in word_list(),
+        prefixes in prop::collection::vec(prefix(), 1..10),
+    ) {
+        check_counts::<BaseLetterTrie>(&words, &prefixes)?;
+        check_counts::<NoParentLetterTrie>(&words, &prefixes)?;
+    }
+
+    #[test]
+    fn find_matches_model(
+        words in word_list(),
+        probes in prop::collection::vec(word(), 0..20),
+    ) {
+        check_membership::<BaseLetterTrie>(&words, &probes)?;
+        check_membership::<NoParentLetterTrie>(&words, &probes)?;
+    }
+
+    #[test]
+    fn set_algebra_matches_model(
+        words in word_list(),
+        other_words in word_list(),
+        prefix in prefix(),
+    ) {
+        check_set_algebra::<BaseLetterTrie>(&words, &other_words, &prefix)?;
+        check_set_algebra::<NoParentLetterTrie>(&words, &other_words, &prefix)?;
+    }
+}
@@ -0,0 +1,161 @@
+//! The report behind `BaseLetterTrie::rc_report()`, which shows the `Rc` and `Weak` counts of the links between
+//! the nodes of a `BaseLetterTrie`.
+//!
+//! Each node of a `BaseLetterTrie` is kept in an `Rc` held by its parent, or by the trie itself for the root, and
+//! each of its children holds a `Weak` link back to it. So with nothing else holding on to a node its strong count
+//! is 1 and its weak count is its number of children. A strong count above 1 means something else shares the node,
+//! as a link cloned by mistake during a merge or a removal would. A weak count that's not the number of children
+//! means a child's parent link is missing or goes to the wrong node, or a node that was cut out of the trie still
+//! links to this one. Either is reported as an `RcAnomaly`, and `LetterTrie::verify_integrity()` fails on the
+//! first one it finds.
+
+use crate::*;
+
+/// The counts for each node of a `BaseLetterTrie` down to a given depth, from `BaseLetterTrie::rc_report()`.
+///
+/// The `Display` output gives one line for each node, indented by its depth, with any anomalies at the end.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = BaseLetterTrie::from_words(vec!["an", "and", "cross"]);
+/// let report = trie.rc_report(1);
+/// assert!(report.is_clean());
+/// // The root and the nodes for "a" and "c".
+/// assert_eq!(3, report.nodes.len());
+/// assert_eq!(RcNodeCounts { prefix: "a".to_owned(), depth: 1, strong_count: 1, weak_count: 1, child_count: 1 }, report.nodes[1]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RcReport {
+    /// The deepest nodes reported, where the root is at depth 0.
+    pub max_depth: usize,
+    /// The counts for each node down to `max_depth`, with every node before its children and the children of a
+    /// node in alphabetical order.
+    pub nodes: Vec<RcNodeCounts>,
+    /// The anomalies among `nodes` in the same order.
+    pub anomalies: Vec<RcAnomaly>,
+}
+
+impl RcReport {
+    /// Whether none of the nodes reported has an anomaly.
+    pub fn is_clean(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+}
+
+/// The counts for one node, from `RcReport::nodes`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RcNodeCounts {
+    /// The letters from the root down to the node, which is "" for the root.
+    pub prefix: String,
+    /// The depth of the node, where the root is at depth 0.
+    pub depth: usize,
+    /// The strong count of the node's `Rc`, not counting the one the report makes while it looks at the node.
+    pub strong_count: usize,
+    /// The weak count of the node's `Rc`, which is the number of parent links to the node.
+    pub weak_count: usize,
+    /// The number of children the node has.
+    pub child_count: usize,
+}
+
+impl RcNodeCounts {
+    /// The anomaly in these counts, if there is one. A node that's shared is reported as that even if its weak
+    /// count is also wrong.
+    pub fn anomaly(&self) -> Option<RcAnomaly> {
+        RcAnomaly::find(self.strong_count, self.weak_count, self.child_count, || {
+            self.prefix.clone()
+        })
+    }
+}
+
+/// A node whose counts aren't what a `BaseLetterTrie` should have, from `RcReport::anomalies`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RcAnomaly {
+    /// The node has more than one strong reference, so something besides its parent holds it.
+    Shared { prefix: String, strong_count: usize },
+    /// The node's weak count isn't its number of children, so the parent links to it are broken.
+    ParentLinks {
+        prefix: String,
+        weak_count: usize,
+        child_count: usize,
+    },
+}
+
+impl RcAnomaly {
+    // prefix is only called if there's an anomaly, since finding a node's prefix from its parent links isn't free.
+    pub(crate) fn find(
+        strong_count: usize,
+        weak_count: usize,
+        child_count: usize,
+        prefix: impl FnOnce() -> String,
+    ) -> Option<Self> {
+        if strong_count > 1 {
+            Some(RcAnomaly::Shared {
+                prefix: prefix(),
+                strong_count,
+            })
+        } else if weak_count != child_count {
+            Some(RcAnomaly::ParentLinks {
+                prefix: prefix(),
+                weak_count,
+                child_count,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for RcAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RcAnomaly::Shared {
+                prefix,
+                strong_count,
+            } => write!(
+                f,
+                "\"{}\" has {} strong references instead of 1",
+                prefix, strong_count
+            ),
+            RcAnomaly::ParentLinks {
+                prefix,
+                weak_count,
+                child_count,
+            } => write!(
+                f,
+                "\"{}\" has {} children but {} weak references",
+                prefix, child_count, weak_count
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RcReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} nodes down to depth {}, {} anomalies",
+            self.nodes.len(),
+            self.max_depth,
+            self.anomalies.len()
+        )?;
+        for node in &self.nodes {
+            writeln!(
+                f,
+                "{:indent$}\"{}\": strong {}, weak {}, children {}",
+                "",
+                node.prefix,
+                node.strong_count,
+                node.weak_count,
+                node.child_count,
+                indent = node.depth * 2
+            )?;
+        }
+        for anomaly in &self.anomalies {
+            writeln!(f, "anomaly: {}", anomaly)?;
+        }
+        Ok(())
+    }
+}
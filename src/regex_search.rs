@@ -0,0 +1,271 @@
+//! The search behind `RegexQuery::words_matching()` and `RegexQuery::words_matching_limited()`.
+//!
+//! Two things keep the search from having to look at every word. If the pattern is anchored at the start, the
+//! literal prefixes it requires are pulled out with `regex_syntax` and only the subtrees for those prefixes are
+//! walked. Within them a DFA built from the pattern is stepped along each branch, and a branch is cut off as soon
+//! as the DFA reaches its dead state without having matched, since no word below it could match. Each word that's reached is then checked
+//! with the `Regex` itself, so the DFA only ever decides what to skip.
+
+use regex::Regex;
+use regex_automata::dfa::{dense, Automaton};
+use regex_automata::util::primitives::StateID;
+use regex_automata::Input;
+use regex_syntax::hir::literal::{ExtractKind, Extractor};
+use regex_syntax::hir::Look;
+
+use crate::limits::Budget;
+use crate::*;
+
+// A DFA bigger than this isn't worth building for one search, so the search falls back to checking every word
+// under the literal prefixes.
+const DFA_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+/// Searching a trie or view for the words a regular expression matches.
+///
+/// This is implemented automatically for everything that implements `TrieQuery`.
+pub trait RegexQuery: TrieQuery {
+    /// Get up to `max_results` words that `re` matches, in alphabetical order. A word matches if `re.is_match()`
+    /// is true for it, so use `^` and `$` to match whole words.
+    ///
+    /// This is much faster than checking every word. When the pattern is anchored at the start only the
+    /// subtrees for its literal prefixes are searched, and a DFA built from the pattern cuts off any branch that
+    /// can't lead to a match. The DFA is built from `re.as_str()`, so set options like case insensitivity with
+    /// inline flags such as `(?i)` rather than through `RegexBuilder`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use regex::Regex;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["bars", "cats", "cat", "bats", "cart", "dots"]);
+    /// let re = Regex::new("^[bc]a[rt].*s$").unwrap();
+    /// assert_eq!(vec!["bars", "bats", "cats"], trie.words_matching(&re, 10));
+    /// assert_eq!(vec!["bars"], trie.words_matching(&re, 1));
+    /// ```
+    #[cfg(feature = "regex")]
+    fn words_matching(&self, re: &regex::Regex, max_results: usize) -> Vec<String> {
+        let mut budget = limits::Budget::new(max_results, usize::MAX);
+        regex_search::words_matching(self, re, &mut budget)
+    }
+
+    /// Get the words that `re` matches as `words_matching()` does, but stop after `limits.max_results` words or
+    /// `limits.max_nodes_visited` nodes, for a pattern that comes from input that can't be trusted. A pattern the
+    /// search can't prune with, like `"e.*e"`, walks the whole trie without a limit on the nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use regex::Regex;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["bars", "cats", "cat", "bats", "cart", "dots"]);
+    /// let re = Regex::new("s$").unwrap();
+    /// let limited = trie.words_matching_limited(&re, &QueryLimits::new(10, 1_000));
+    /// assert_eq!(vec!["bars", "bats", "cats", "dots"], limited.results);
+    /// assert!(limited.is_complete());
+    /// let limited = trie.words_matching_limited(&re, &QueryLimits::new(10, 5));
+    /// assert_eq!(vec!["bars"], limited.results);
+    /// assert!(!limited.is_complete());
+    /// ```
+    #[cfg(feature = "regex")]
+    fn words_matching_limited(
+        &self,
+        re: &regex::Regex,
+        limits: &QueryLimits,
+    ) -> Limited<Vec<String>> {
+        let mut budget = limits::Budget::for_limits(limits);
+        let words = regex_search::words_matching(self, re, &mut budget);
+        budget.finish(words, limits)
+    }
+}
+
+impl<T: TrieQuery + ?Sized> RegexQuery for T {}
+
+// The words that match in alphabetical order, until the budget is spent.
+pub(crate) fn words_matching<T: TrieQuery + ?Sized>(
+    trie: &T,
+    re: &Regex,
+    budget: &mut Budget,
+) -> Vec<String> {
+    let mut words = vec![];
+    if budget.is_spent() {
+        return words;
+    }
+    let dfa = build_dfa(re.as_str());
+    for prefix in start_prefixes(re.as_str()) {
+        // The DFA state after each node on the path from the start of this subtree, or None once the DFA can't
+        // rule anything out on the branch.
+        let mut states: Vec<Option<StateID>> = vec![];
+        let mut start_depth = None;
+        trie.visit(&prefix, &mut |node| {
+            if !budget.visit_node() {
+                return false;
+            }
+            let start_depth = *start_depth.get_or_insert(node.depth);
+            states.truncate(node.depth - start_depth);
+            if let Some(dfa) = &dfa {
+                let state = match states.last() {
+                    Some(state) => {
+                        let mut buffer = [0u8; 4];
+                        state.and_then(|state| step(dfa, state, node.c.encode_utf8(&mut buffer)))
+                    }
+                    None => start_state(dfa).and_then(|state| step(dfa, state, node.prefix)),
+                };
+                if state.map(|state| dfa.is_dead_state(state)).unwrap_or(false) {
+                    return false;
+                }
+                states.push(state);
+            }
+            if node.is_word && re.is_match(node.prefix) {
+                words.push(node.prefix.to_owned());
+                budget.add_result();
+            }
+            true
+        });
+        if budget.is_spent() {
+            break;
+        }
+    }
+    words
+}
+
+// The DFA for the pattern, or None if it's too big or uses something the DFA doesn't support.
+fn build_dfa(pattern: &str) -> Option<dense::DFA<Vec<u32>>> {
+    dense::Builder::new()
+        .configure(
+            dense::Config::new()
+                .dfa_size_limit(Some(DFA_SIZE_LIMIT))
+                .determinize_size_limit(Some(DFA_SIZE_LIMIT))
+                // Allow \b by having the DFA give up on non-ASCII text, which only stops it from pruning.
+                .unicode_word_boundary(true),
+        )
+        .build(pattern)
+        .ok()
+}
+
+fn start_state(dfa: &dense::DFA<Vec<u32>>) -> Option<StateID> {
+    dfa.start_state_forward(&Input::new("")).ok()
+}
+
+// Feed text to the DFA from state, returning None if the DFA gives up. It also returns None once there's been a
+// match, since the DFA goes to its dead state after the match but every longer word has the same match.
+fn step(dfa: &dense::DFA<Vec<u32>>, mut state: StateID, text: &str) -> Option<StateID> {
+    for byte in text.bytes() {
+        state = dfa.next_state(state, byte);
+        if dfa.is_quit_state(state) || dfa.is_match_state(state) {
+            return None;
+        }
+    }
+    Some(state)
+}
+
+// The prefixes of the subtrees that could hold a match, in alphabetical order and with none of them the start of
+// another so that walking them one after another gives the words in order. This is only the empty prefix unless
+// the pattern is anchored at the start and has a finite set of literal prefixes.
+fn start_prefixes(pattern: &str) -> Vec<String> {
+    let everything = vec!["".to_owned()];
+    let hir = match regex_syntax::Parser::new().parse(pattern) {
+        Ok(hir) => hir,
+        Err(_) => return everything,
+    };
+    if !hir.properties().look_set_prefix().contains(Look::Start) {
+        return everything;
+    }
+    let seq = Extractor::new().kind(ExtractKind::Prefix).extract(&hir);
+    let literals = match seq.literals() {
+        Some(literals) if !literals.is_empty() => literals,
+        _ => return everything,
+    };
+    let mut prefixes: Vec<String> = literals
+        .iter()
+        .map(|literal| {
+            // A literal can be cut off partway through a character, so keep only the complete characters.
+            let bytes = literal.as_bytes();
+            let valid_len = match std::str::from_utf8(bytes) {
+                Ok(_) => bytes.len(),
+                Err(err) => err.valid_up_to(),
+            };
+            String::from_utf8_lossy(&bytes[..valid_len]).into_owned()
+        })
+        .collect();
+    prefixes.sort_unstable();
+    prefixes.dedup();
+    let mut start_prefixes: Vec<String> = vec![];
+    for prefix in prefixes {
+        // Sorting puts a prefix right before the longer prefixes that start with it.
+        if !start_prefixes
+            .last()
+            .map(|last| prefix.starts_with(last.as_str()))
+            .unwrap_or(false)
+        {
+            start_prefixes.push(prefix);
+        }
+    }
+    start_prefixes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every word in the trie that matches, found the slow way.
+    fn brute_force<T: LetterTrie>(t: &T, re: &Regex, max_results: usize) -> Vec<String> {
+        t.words()
+            .filter(|word| re.is_match(word))
+            .take(max_results)
+            .collect()
+    }
+
+    #[test]
+    fn prefixes_from_patterns() {
+        assert_eq!(
+            vec!["bar", "bat", "car", "cat"],
+            start_prefixes("^[bc]a[rt].*s$")
+        );
+        assert_eq!(vec!["cr"], start_prefixes("^(cr|cros|cre)"));
+        assert_eq!(vec![""], start_prefixes("cat"));
+        assert_eq!(vec![""], start_prefixes("^.*s$"));
+        assert_eq!(vec![""], start_prefixes("(?m)^cat"));
+    }
+
+    #[test]
+    fn medium_matches_brute_force() {
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous)
+                .unwrap();
+        for pattern in &[
+            "^[bc]a[rt].*s$",
+            "^a",
+            "^st.*ing$",
+            "ing$",
+            "qu",
+            "^.{3}$",
+            "^(re|un)[a-m]+$",
+            "^[^aeiou]+$",
+            r"\bzz",
+            "^zzzzzz",
+            "x.*y",
+        ] {
+            let re = Regex::new(pattern).unwrap();
+            for max_results in &[0, 1, 25, usize::MAX] {
+                assert_eq!(
+                    brute_force(&t, &re, *max_results),
+                    t.words_matching(&re, *max_results),
+                    "{} with max_results = {}",
+                    pattern,
+                    max_results
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn multibyte_prefixes() {
+        let t = BaseLetterTrie::from_words(vec!["éa", "éb", "ê", "e", "xéa"]);
+        let re = Regex::new("^é").unwrap();
+        assert_eq!(vec!["éa", "éb"], t.words_matching(&re, 10));
+        let re = Regex::new("éa$").unwrap();
+        assert_eq!(vec!["xéa", "éa"], t.words_matching(&re, 10));
+    }
+}
@@ -0,0 +1,165 @@
+//! Removing many words at once, as when a list of banned words is taken out of a dictionary.
+//!
+//! `LetterTrie::remove_words_from_file()` reads the words as `from_file()` does, sorts them, and hands them to
+//! `LetterTrie::remove_sorted_words()`. Sorted, the words that share a prefix come one after another, so the walk
+//! only goes back up as far as the next word's prefix differs from the last one's rather than starting again from
+//! the root the way a `remove()` for each word would. Each node on the way is finished when the walk leaves it:
+//! it's cut off if nothing is left below it, and otherwise its longest word is worked out again from its children.
+
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::BufReader;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use crate::*;
+
+/// What `LetterTrie::remove_words_from_file()` or `LetterTrie::remove_sorted_words()` did. A word that appears
+/// more than once in the list is only counted once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemovalReport {
+    /// The words that were in the trie and have been removed.
+    pub removed: usize,
+    /// The words that weren't in the trie, including any that were only the start of a longer word.
+    pub not_found: usize,
+    /// The nodes taken out of the trie because no word was left at or below them.
+    pub nodes_pruned: usize,
+}
+
+// The words in a word file lowercased, sorted and without duplicates, for LetterTrie::remove_sorted_words(). Blank
+// lines and the whitespace around each word are skipped as they are when loading.
+#[cfg(feature = "fs")]
+pub(crate) fn read_sorted_words(path: &Path) -> Result<Vec<Vec<char>>> {
+    let mut words: Vec<Vec<char>> = vec![];
+    let file = File::open(path).map_err(in_file(path))?;
+    for_each_trimmed_line(BufReader::new(file), |word| {
+        words.push(lowercase_vec_chars(word))
+    })
+    .map_err(in_file(path))?;
+    words.sort_unstable();
+    words.dedup();
+    Ok(words)
+}
+
+// The number of letters word shares with the path of letters the walk is on.
+pub(crate) fn shared_len(path: impl Iterator<Item = char>, word: &[char]) -> usize {
+    path.zip(word)
+        .take_while(|(c, word_c)| c == *word_c)
+        .count()
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn check_remove_from_file<T: LetterTrie>(name: &str) {
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let mut t = T::from_words(&words);
+        let node_count = t.to_fixed_node().node_count;
+
+        // Every third word, some of them twice and in other cases, along with words that aren't there and
+        // prefixes that aren't words.
+        let mut banned: Vec<String> = words.iter().step_by(3).cloned().collect();
+        let mut lines = banned.clone();
+        lines.extend(
+            banned
+                .iter()
+                .step_by(5)
+                .map(|word| format!("  {}  ", word.to_uppercase())),
+        );
+        let mut not_words = vec!["zzyzx".to_owned(), "qqqq".to_owned()];
+        let prefix: String = words[1].chars().take(2).collect();
+        if !words.contains(&prefix) {
+            not_words.push(prefix);
+        }
+        lines.extend(not_words.iter().cloned());
+        lines.push(String::new());
+        lines.reverse();
+        let path = temp_path(&format!("removal_{}.txt", name));
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        let report = t.remove_words_from_file(&path).unwrap();
+        t.verify_integrity().unwrap();
+        banned.sort();
+        banned.dedup();
+        assert_eq!(banned.len(), report.removed);
+        assert_eq!(not_words.len(), report.not_found);
+        let mut kept: Vec<String> = words
+            .iter()
+            .filter(|word| banned.binary_search(word).is_err())
+            .cloned()
+            .collect();
+        kept.sort();
+        kept.dedup();
+        assert_eq!(kept, t.words().collect::<Vec<_>>());
+        let expected = T::from_words(&kept);
+        let fixed_node = t.to_fixed_node();
+        assert_eq!(expected.to_fixed_node(), fixed_node);
+        assert_eq!(node_count - fixed_node.node_count, report.nodes_pruned);
+        for word in banned.iter().step_by(7) {
+            assert!(!t.contains(word), "{}", word);
+        }
+        // contains() turns away words longer than the longest one it has been told about.
+        for word in kept.iter().step_by(7) {
+            assert!(t.contains(word), "{}", word);
+        }
+
+        // The second time there's nothing left to remove.
+        let again = t.remove_words_from_file(&path).unwrap();
+        t.verify_integrity().unwrap();
+        assert_eq!(
+            RemovalReport {
+                removed: 0,
+                not_found: report.removed + report.not_found,
+                nodes_pruned: 0,
+            },
+            again
+        );
+        assert_eq!(fixed_node, t.to_fixed_node());
+
+        assert!(matches!(
+            t.remove_words_from_file(temp_path("removal_missing.txt")),
+            Err(LetterTrieError::File { .. })
+        ));
+    }
+
+    #[test]
+    fn remove_from_file() {
+        check_remove_from_file::<BaseLetterTrie>("base");
+        check_remove_from_file::<NoParentLetterTrie>("no_parent");
+    }
+
+    fn check_remove_sorted_words<T: LetterTrie>() {
+        let mut t = T::from_words(vec!["cross", "crossed", "crossing", "crate", "an", "Ñandú"]);
+        t.insert_with_tag("crossed", 7);
+        let words: Vec<Vec<char>> = ["an", "cr", "crossed", "crossing", "ñandú", "zebra"]
+            .iter()
+            .map(|word| word.chars().collect())
+            .collect();
+        let report = t.remove_sorted_words(&words);
+        t.verify_integrity().unwrap();
+        // The two nodes of "an" and the five of "ñandú" go, and "crossed" and "crossing" take the five nodes
+        // after "cross" with them.
+        assert_eq!(
+            RemovalReport {
+                removed: 4,
+                not_found: 2,
+                nodes_pruned: 2 + 5 + 2 + 3,
+            },
+            report
+        );
+        assert_eq!(vec!["crate", "cross"], t.words().collect::<Vec<_>>());
+        assert_eq!(None, t.tag("crossed"));
+        assert!(t.has_completion_of_length("cr", 5));
+        assert!(!t.has_completion_of_length("cr", 7));
+        assert_eq!(RemovalReport::default(), t.remove_sorted_words(&[]));
+    }
+
+    #[test]
+    fn remove_sorted_words() {
+        check_remove_sorted_words::<BaseLetterTrie>();
+        check_remove_sorted_words::<NoParentLetterTrie>();
+    }
+}
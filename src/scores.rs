@@ -0,0 +1,575 @@
+//! Completions ranked by score, for `ScoredQuery::suggest_weighted()` and `ScoredQuery::suggest_scored()`, as when
+//! an autocomplete box puts the words people search for most often at the top.
+//!
+//! `suggest_weighted()` scores every word under the prefix with the caller's function and keeps the best. The scores
+//! from `LetterTrie::set_scores()` are known ahead of time, so each node also keeps the highest score at or below
+//! it, which is `NodeView::max_score`, and `suggest_scored()` uses that for a best-first search. A heap holds the
+//! words and nodes found so far, each at its score or the highest score below it. A word comes off the top of the
+//! heap only once nothing left in it could beat that word, and a node that comes off puts its children in. The
+//! search stops after `n` words, and the branches whose scores are too low are never opened, so it only looks at
+//! the nodes on the way down to the words it gives and the children of those nodes.
+
+use alloc::collections::{BTreeMap, BinaryHeap};
+use core::cmp::Ordering;
+
+use crate::*;
+
+// The highest score below a prefix that has no scored word at or below it, which is also below every score.
+pub(crate) const NO_SCORE: f64 = f64::NEG_INFINITY;
+
+// Whether set_score() keeps a score. NaN can't be ordered, and negative infinity would look like no score at all.
+pub(crate) fn is_valid_score(score: f64) -> bool {
+    !score.is_nan() && score != NO_SCORE
+}
+
+// A node's max_score as it's shown in NodeView.
+pub(crate) fn known_score(max_score: f64) -> Option<f64> {
+    (max_score != NO_SCORE).then_some(max_score)
+}
+
+// Work out the highest score again for each prefix of the word key after the word's score has changed or the word
+// has gone. max_scores has the highest score at or below each prefix that has a scored word at or below it, and
+// nothing for the others, so a trie without scores keeps nothing and the nodes don't carry them. The path has the
+// nodes along the key that are still in the trie, from the root down, each as whether it's a word and the letters
+// of its children. Going up from the deepest one, each node's children already have the right scores.
+pub(crate) fn update_max_scores(
+    max_scores: &mut BTreeMap<String, f64>,
+    scores: &BTreeMap<String, f64>,
+    key: &str,
+    path: &[(bool, Vec<char>)],
+) {
+    // The end in bytes of each prefix of the key, starting with the root's.
+    let mut ends = vec![0];
+    ends.extend(key.char_indices().map(|(i, c)| i + c.len_utf8()));
+    // Nothing is left below the prefixes whose nodes have gone.
+    for end in ends.iter().skip(path.len()) {
+        max_scores.remove(&key[..*end]);
+    }
+    let mut child = String::new();
+    for ((is_word, children), end) in path.iter().zip(&ends).rev() {
+        let prefix = &key[..*end];
+        let own = match scores.get(prefix) {
+            Some(score) if *is_word => *score,
+            _ => NO_SCORE,
+        };
+        let mut max = own;
+        for c in children {
+            child.clear();
+            child.push_str(prefix);
+            child.push(*c);
+            if let Some(score) = max_scores.get(&child) {
+                max = max.max(*score);
+            }
+        }
+        if max == NO_SCORE {
+            max_scores.remove(prefix);
+        } else {
+            max_scores.insert(prefix.to_owned(), max);
+        }
+    }
+}
+
+/// Completions ranked by score or by rank, for any trie or view. See the module documentation.
+///
+/// This is implemented automatically for everything that implements `TrieQuery`.
+pub trait ScoredQuery: TrieQuery {
+    /// Get up to `n` completions of `prefix` with the lowest ranks from `TrieQuery::rank_of()`, most common first and
+    /// alphabetically among words with the same rank, along with their ranks. This is `suggest_weighted()` with the
+    /// rank as the weight, so words without a rank aren't suggested.
+    ///
+    /// See `LetterTrie::from_reader_in_order()` for an example.
+    fn suggest_ranked(&self, prefix: &str, n: usize) -> Vec<(String, u32)> {
+        let weight = |word: &str| self.rank_of(word).map_or(f64::NAN, |rank| -f64::from(rank));
+        scores::suggest_weighted(self, prefix, n, &weight)
+            .into_iter()
+            .map(|(word, weight)| (word, -weight as u32))
+            .collect()
+    }
+
+    /// Get up to `n` completions of `prefix` with the highest scores from `LetterTrie::set_scores()`, best first and
+    /// alphabetically among words with the same score, along with their scores. Words without a score aren't
+    /// suggested.
+    ///
+    /// The search goes best first on the highest score each node keeps for the words below it, so it stops once
+    /// no branch left can beat the `n` words it has, and usually looks at only a small part of the subtree. See
+    /// the `scores` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["the", "then", "there", "these", "theory"]);
+    /// for (word, score) in [("the", 9.0), ("there", 4.0), ("these", 2.5), ("theory", 4.0)] {
+    ///     trie.set_score(word, score);
+    /// }
+    /// let best: Vec<(String, f64)> = trie.suggest_scored("the", 3);
+    /// assert_eq!(
+    ///     vec![("the".to_owned(), 9.0), ("theory".to_owned(), 4.0), ("there".to_owned(), 4.0)],
+    ///     best
+    /// );
+    /// assert!(trie.suggest_scored("then", 1).is_empty());
+    /// ```
+    fn suggest_scored(&self, prefix: &str, n: usize) -> Vec<(String, f64)> {
+        trace::traced(
+            || trace::TraceOp::SuggestScored(prefix.to_owned(), n),
+            || scores::suggest_scored(self, prefix, n, None),
+        )
+    }
+
+    /// Get up to `n` completions of `prefix` with the highest scores from `score`, best first and alphabetically
+    /// among words with the same score, along with their scores. `score` is called with each word under the
+    /// prefix in lowercase as it's stored, and a word it gives NaN is left out. To rank by scores that are known
+    /// ahead of time without scoring every word, use `LetterTrie::set_scores()` and `suggest_scored()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = NoParentLetterTrie::from_words(vec!["crossing", "creature", "cross", "crate"]);
+    /// let vowels = |word: &str| word.chars().filter(|c| "aeiou".contains(*c)).count() as f64;
+    /// assert_eq!(
+    ///     vec![("creature".to_owned(), 4.0), ("crate".to_owned(), 2.0)],
+    ///     trie.suggest_weighted("cr", 2, vowels)
+    /// );
+    /// ```
+    fn suggest_weighted(
+        &self,
+        prefix: &str,
+        n: usize,
+        score: impl Fn(&str) -> f64,
+    ) -> Vec<(String, f64)>
+    where
+        Self: Sized,
+    {
+        scores::suggest_weighted(self, prefix, n, &score)
+    }
+
+    /// Get the completions `suggest_scored()` would give along with what the search for them did. The metrics count
+    /// each node the search looks at, including those it looks at only to find that their scores are too low, and
+    /// no comparisons, since the search compares scores rather than letters. The terminal depth is that of the
+    /// deepest node looked at. See the `metrics` module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(vec!["cat", "car", "cart", "dog"]);
+    /// trie.set_score("cart", 1.0);
+    /// let (best, metrics) = trie.suggest_scored_with_metrics("ca", 1);
+    /// assert_eq!(vec![("cart".to_owned(), 1.0)], best);
+    /// assert_eq!(FindOutcome::Word, metrics.outcome);
+    /// assert_eq!(4, metrics.terminal_depth);
+    /// ```
+    fn suggest_scored_with_metrics(
+        &self,
+        prefix: &str,
+        n: usize,
+    ) -> (Vec<(String, f64)>, FindMetrics) {
+        let mut metrics = FindMetrics::default();
+        let suggestions = scores::suggest_scored(self, prefix, n, Some(&mut metrics));
+        (suggestions, metrics)
+    }
+}
+
+impl<T: TrieQuery + ?Sized> ScoredQuery for T {}
+
+// The order of the results: highest score first and then alphabetical, as stored.
+fn by_rank(a: &(f64, String, String), b: &(f64, String, String)) -> Ordering {
+    b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1))
+}
+
+pub(crate) fn suggest_weighted<T: TrieQuery + ?Sized>(
+    trie: &T,
+    prefix: &str,
+    n: usize,
+    score: &dyn Fn(&str) -> f64,
+) -> Vec<(String, f64)> {
+    if n == 0 {
+        return vec![];
+    }
+    // Kept sorted by rank so the last entry is the one to drop when a better word turns up. The spelling to give
+    // for each word is on the end.
+    let mut best: Vec<(f64, String, String)> = vec![];
+    trie.visit(prefix, &mut |node| {
+        if node.is_word {
+            let score = score(node.prefix);
+            let is_better = best.len() < n || score > best[n - 1].0;
+            if !score.is_nan() && is_better {
+                let entry = (score, node.prefix.to_owned(), node.word().to_owned());
+                let index = best
+                    .binary_search_by(|other| by_rank(other, &entry))
+                    .unwrap_or_else(|x| x);
+                best.insert(index, entry);
+                best.truncate(n);
+            }
+        }
+        true
+    });
+    best.into_iter()
+        .map(|(score, _, spelling)| (spelling, score))
+        .collect()
+}
+
+// An entry in the heap of suggest_scored(): a word with its score, or a node still to be opened with the highest
+// score below it.
+struct Candidate {
+    score: f64,
+    prefix: String,
+    // The spelling to give for a word, or None for a node.
+    word: Option<String>,
+}
+
+// The heap gives the highest score first, then the first prefix in sorted order, then a word before the node it's
+// at. Every word below a node has that node's prefix, so when the scores are the same a word that sorts before the
+// node's prefix sorts before all of its words too, and one that sorts after it and isn't below it sorts after them.
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.prefix.cmp(&self.prefix))
+            .then_with(|| self.word.is_some().cmp(&other.word.is_some()))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+pub(crate) fn suggest_scored<T: TrieQuery + ?Sized>(
+    trie: &T,
+    prefix: &str,
+    n: usize,
+    mut metrics: Option<&mut FindMetrics>,
+) -> Vec<(String, f64)> {
+    let mut suggestions: Vec<(String, f64)> = vec![];
+    let mut heap: BinaryHeap<Candidate> = BinaryHeap::new();
+    let mut is_found = false;
+    // Put the node for prefix's word in the heap along with each child with a score at or below it. The node itself
+    // was counted when it was put in the heap, except for the first.
+    let mut open =
+        |prefix: &str, heap: &mut BinaryHeap<Candidate>, metrics: &mut Option<&mut FindMetrics>| {
+            let mut start_depth = None;
+            trie.visit(prefix, &mut |node| {
+                let is_start = start_depth.is_none();
+                if is_start {
+                    start_depth = Some(node.depth);
+                    if let Some(score) = node.score {
+                        heap.push(Candidate {
+                            score,
+                            prefix: node.prefix.to_owned(),
+                            word: Some(node.word().to_owned()),
+                        });
+                    }
+                } else if let Some(score) = node.max_score {
+                    heap.push(Candidate {
+                        score,
+                        prefix: node.prefix.to_owned(),
+                        word: None,
+                    });
+                }
+                if let Some(metrics) = metrics {
+                    if !is_start || !is_found {
+                        metrics.nodes_visited += 1;
+                        metrics.terminal_depth = metrics.terminal_depth.max(node.depth);
+                    }
+                }
+                is_found = true;
+                is_start
+            });
+        };
+    if n > 0 {
+        open(prefix, &mut heap, &mut metrics);
+    }
+    while suggestions.len() < n {
+        let candidate = match heap.pop() {
+            Some(candidate) => candidate,
+            None => break,
+        };
+        match candidate.word {
+            Some(word) => suggestions.push((word, candidate.score)),
+            None => open(&candidate.prefix, &mut heap, &mut metrics),
+        }
+    }
+    if let Some(metrics) = metrics {
+        metrics.finish(is_found.then_some(!suggestions.is_empty()));
+    }
+    suggestions
+}
+
+// The check behind LetterTrie::verify_integrity() that every node's max_score is the highest of its own score and
+// its children's, and that only words have scores.
+pub(crate) fn check_max_scores<T: LetterTrie + ?Sized>(trie: &T) -> Result<(), String> {
+    // Each entry is a node the walk hasn't left: its depth, its place in the walk, the max_score it reports, and the
+    // highest score found so far at or below it. The prefixes aren't kept since a very deep trie would need a great
+    // many long ones, so a node that's wrong is found again by its place in the walk.
+    type Entry = (usize, usize, f64, f64);
+    let leave = |path: &mut Vec<Entry>, depth: usize| -> Result<(), Entry> {
+        while let Some(entry) = path.pop_if(|(d, _, _, _)| *d >= depth) {
+            if entry.2 != entry.3 {
+                return Err(entry);
+            }
+            if let Some(parent) = path.last_mut() {
+                parent.3 = parent.3.max(entry.3);
+            }
+        }
+        Ok(())
+    };
+    let mut path: Vec<Entry> = vec![];
+    let mut wrong: Option<Entry> = None;
+    let mut problem: Option<String> = None;
+    let mut index = 0;
+    trie.visit("", &mut |node| {
+        wrong = leave(&mut path, node.depth).err();
+        if node.score.is_some() && !node.is_word {
+            problem = Some(format!("{:?} has a score but isn't a word", node));
+        }
+        path.push((
+            node.depth,
+            index,
+            node.max_score.unwrap_or(NO_SCORE),
+            node.score.unwrap_or(NO_SCORE),
+        ));
+        index += 1;
+        wrong.is_none() && problem.is_none()
+    });
+    if let Some(msg) = problem {
+        return Err(msg);
+    }
+    let (_, wrong_index, reported, found) = match wrong.map_or_else(|| leave(&mut path, 0), Err) {
+        Ok(()) => return Ok(()),
+        Err(entry) => entry,
+    };
+    let mut prefix = String::new();
+    let mut index = 0;
+    trie.visit("", &mut |node| {
+        if index == wrong_index {
+            prefix = node.prefix.to_owned();
+        }
+        index += 1;
+        index <= wrong_index
+    });
+    Err(format!(
+        "the node for {:?} reports a highest score of {:?} but the highest at or below it is {:?}",
+        prefix,
+        known_score(reported),
+        known_score(found)
+    ))
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use crate::generator::SplitMix64;
+    use std::collections::HashMap;
+    use test::Bencher;
+
+    // Scores for about half of the words, from few enough values that there are ties.
+    fn random_scores(words: &[String], seed: u64) -> HashMap<String, f64> {
+        let mut rng = SplitMix64(seed);
+        let mut scores = HashMap::new();
+        for word in words {
+            if rng.below(2) == 0 {
+                scores.insert(word.to_lowercase(), rng.below(100) as f64 / 4.0);
+            }
+        }
+        scores
+    }
+
+    fn scored_trie<T: LetterTrie>(dataset: &Dataset, seed: u64) -> (T, HashMap<String, f64>) {
+        let mut t = T::from_dataset(dataset, &LoadMethod::Continuous).unwrap();
+        let scores = random_scores(&t.words().collect::<Vec<_>>(), seed);
+        t.set_scores(&scores);
+        (t, scores)
+    }
+
+    // suggest_weighted() with the scores looked up is the answer from looking at every word.
+    fn check_best_first_matches_every_word<T: LetterTrie>() {
+        let (t, scores) = scored_trie::<T>(&Dataset::TestMediumUnsorted, 183);
+        t.verify_integrity().unwrap();
+        let lookup = |word: &str| scores.get(word).copied().unwrap_or(f64::NAN);
+        for prefix in ["", "a", "co", "st", "pre", "zz", "Re"] {
+            for n in [0, 1, 3, 10, 100] {
+                let expected = t.suggest_weighted(prefix, n, lookup);
+                assert_eq!(expected, t.suggest_scored(prefix, n), "{:?} {}", prefix, n);
+                assert!(expected.len() <= n);
+            }
+        }
+        // Every scored word comes back for a large enough n.
+        let all = t.suggest_scored("", scores.len() + 10);
+        assert_eq!(scores.len(), all.len());
+        assert!(all.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+
+    #[test]
+    fn best_first_matches_every_word() {
+        check_best_first_matches_every_word::<BaseLetterTrie>();
+        check_best_first_matches_every_word::<NoParentLetterTrie>();
+    }
+
+    fn check_max_scores_follow_changes<T: LetterTrie>() {
+        let mut t = T::from_words(vec!["cross", "crossed", "crossing", "crate", "an", "and"]);
+        assert!(t.set_score("crossing", 5.0));
+        assert!(t.set_score("CROSSED ", 3.0));
+        assert!(t.set_score("crate", 4.0));
+        assert!(t.set_score("and", 1.0));
+        assert!(!t.set_score("cro", 9.0));
+        assert!(!t.set_score("zebra", 9.0));
+        assert!(!t.set_score("an", f64::NAN));
+        assert!(!t.set_score("an", f64::NEG_INFINITY));
+        t.verify_integrity().unwrap();
+        let max_score = |t: &T, prefix: &str| {
+            let mut max_score = None;
+            t.visit(prefix, &mut |node| {
+                max_score = node.max_score;
+                false
+            });
+            max_score
+        };
+        assert_eq!(Some(5.0), max_score(&t, ""));
+        assert_eq!(Some(5.0), max_score(&t, "cros"));
+        assert_eq!(Some(1.0), max_score(&t, "a"));
+
+        // Lowering the best score finds the next best, and removing words takes their scores with them.
+        t.set_score("crossing", 2.0);
+        t.verify_integrity().unwrap();
+        assert_eq!(Some(4.0), max_score(&t, ""));
+        assert!(t.remove("crate"));
+        t.verify_integrity().unwrap();
+        assert_eq!(Some(3.0), max_score(&t, "cr"));
+        assert!(t.remove("crossed"));
+        t.verify_integrity().unwrap();
+        assert_eq!(None, t.score("crossed"));
+        assert_eq!(
+            vec![("crossing".to_owned(), 2.0)],
+            t.suggest_scored("cr", 5)
+        );
+        let words: Vec<Vec<char>> = ["an", "and", "crossing"]
+            .iter()
+            .map(|word| word.chars().collect())
+            .collect();
+        assert_eq!(3, t.remove_sorted_words(&words).removed);
+        t.verify_integrity().unwrap();
+        assert_eq!(None, max_score(&t, ""));
+        assert!(t.suggest_scored("", 5).is_empty());
+
+        // A word added again has no score until it's given one.
+        t.insert("crossing");
+        assert_eq!(None, t.score("crossing"));
+        assert!(t.suggest_scored("", 5).is_empty());
+        t.set_score("cross", 1.5);
+        assert_eq!(vec![("cross".to_owned(), 1.5)], t.suggest_scored("c", 5));
+        t.verify_integrity().unwrap();
+    }
+
+    #[test]
+    fn max_scores_follow_changes() {
+        check_max_scores_follow_changes::<BaseLetterTrie>();
+        check_max_scores_follow_changes::<NoParentLetterTrie>();
+    }
+
+    fn check_remove_many_scored_words<T: LetterTrie>() {
+        let (mut t, scores) = scored_trie::<T>(&Dataset::TestMediumSorted, 7);
+        let mut words: Vec<Vec<char>> = t
+            .words()
+            .step_by(3)
+            .map(|word| word.chars().collect())
+            .collect();
+        words.sort();
+        t.remove_sorted_words(&words);
+        t.verify_integrity().unwrap();
+        let lookup = |word: &str| scores.get(word).copied().unwrap_or(f64::NAN);
+        assert_eq!(t.suggest_weighted("", 50, lookup), t.suggest_scored("", 50));
+    }
+
+    #[test]
+    fn remove_many_scored_words() {
+        check_remove_many_scored_words::<BaseLetterTrie>();
+        check_remove_many_scored_words::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn merge_keeps_scores() {
+        let mut t = BaseLetterTrie::from_words(vec!["cross", "crate"]);
+        let mut other = BaseLetterTrie::from_words(vec!["an", "and"]);
+        other.set_score("and", 2.0);
+        t.merge(other);
+        t.verify_integrity().unwrap();
+        assert_eq!(Some(2.0), t.score("and"));
+        assert_eq!(vec![("and".to_owned(), 2.0)], t.suggest_scored("", 3));
+    }
+
+    #[test]
+    fn spellings_and_weights() {
+        let mut t = NoParentLetterTrie::new();
+        for word in ["Paris", "Pasta", "paste"] {
+            t.insert_preserving_case(word, CasePreference::FirstSeen);
+        }
+        t.set_score("paris", 1.0);
+        t.set_score("pasta", 1.0);
+        assert_eq!(
+            vec![("Paris".to_owned(), 1.0), ("Pasta".to_owned(), 1.0)],
+            t.suggest_scored("pa", 5)
+        );
+        // The function is given the stored word and any NaN leaves the word out.
+        let vowels = |word: &str| {
+            if word == "paste" {
+                f64::NAN
+            } else {
+                word.matches('a').count() as f64
+            }
+        };
+        assert_eq!(
+            vec![("Pasta".to_owned(), 2.0), ("Paris".to_owned(), 1.0)],
+            t.suggest_weighted("PA", 5, vowels)
+        );
+    }
+
+    // For n = 10 on a prefix with a large subtree, the best-first search only looks at a small part of it.
+    #[test]
+    fn search_looks_at_little_of_the_subtree() {
+        let (t, _) = scored_trie::<NoParentLetterTrie>(&Dataset::TestMediumSorted, 11);
+        let subtree = t.find("s").unwrap().node_count;
+        let (best, metrics) = t.suggest_scored_with_metrics("s", 10);
+        assert_eq!(10, best.len());
+        assert_eq!(FindOutcome::Word, metrics.outcome);
+        assert!(
+            metrics.nodes_visited * 20 < subtree,
+            "{} of {}",
+            metrics.nodes_visited,
+            subtree
+        );
+        let (_, metrics) = t.suggest_scored_with_metrics("qqqq", 10);
+        assert_eq!(
+            (0, FindOutcome::NotFound),
+            (metrics.nodes_visited, metrics.outcome)
+        );
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_suggest_scored_large_s(b: &mut Bencher) {
+        let (t, _) = scored_trie::<NoParentLetterTrie>(&Dataset::TestLargeSorted, 11);
+        b.iter(|| t.suggest_scored("s", 10));
+    }
+
+    #[bench]
+    #[cfg_attr(debug_assertions, ignore = "needs the large dataset files")]
+    fn bench_suggest_weighted_large_s(b: &mut Bencher) {
+        let (t, scores) = scored_trie::<NoParentLetterTrie>(&Dataset::TestLargeSorted, 11);
+        let lookup = |word: &str| scores.get(word).copied().unwrap_or(f64::NAN);
+        b.iter(|| t.suggest_weighted("s", 10, lookup));
+    }
+}
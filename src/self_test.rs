@@ -0,0 +1,401 @@
+//! A quick check that a loaded dictionary looks right, behind `LetterTrie::self_test()`, such as after a deploy.
+//!
+//! A `SelfTestSpec` lists what's expected of the trie: words that have to be there, words that mustn't be, a range
+//! for the number of words, a fingerprint from `LetterTrie::fingerprint()` and a limit on the height. Only what the
+//! spec asks for is checked. Each word is one lookup and the counts and height come from one walk of the trie, so
+//! the check is cheap unless the spec has a fingerprint, which walks the trie once more.
+//!
+//! This is the same idea as checking a trie built from the large dataset against `VerificationSet::GoodWords` and
+//! `VerificationSet::NonWords`, and `SelfTestSpec::from_files()` reads lists in the same format.
+
+use core::ops::RangeInclusive;
+
+use crate::*;
+
+/// What `LetterTrie::self_test()` expects of a trie. The default expects nothing, so it always passes.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "an"]);
+/// let spec = SelfTestSpec {
+///     present: vec!["Cross".to_owned(), "an".to_owned()],
+///     absent: vec!["cros".to_owned()],
+///     word_count: Some(2..=5),
+///     max_height: Some(8),
+///     ..SelfTestSpec::default()
+/// };
+/// assert!(trie.self_test(&spec).passed());
+/// assert_self_test!(trie, spec);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelfTestSpec {
+    /// Words that have to be in the trie, checked as in `LetterTrie::contains()`.
+    pub present: Vec<String>,
+    /// Words that mustn't be in the trie, checked as in `LetterTrie::contains()`.
+    pub absent: Vec<String>,
+    /// The number of words the trie has to have, if any.
+    pub word_count: Option<RangeInclusive<usize>>,
+    /// The trie's `LetterTrie::fingerprint()`, if it has to have the same words as a known good copy.
+    pub fingerprint: Option<u64>,
+    /// The most the trie's height can be, if any. As in `FixedNode::height` this is one more than the length of the
+    /// longest word.
+    pub max_height: Option<usize>,
+}
+
+impl SelfTestSpec {
+    /// Make a spec from a file of words that have to be in the trie and a file of words that mustn't be, in the
+    /// format of the verification files read by `verification_words()`: one word to a line, with each line
+    /// trimmed and blank lines skipped. Nothing else is expected until it's set on the spec.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from opening or reading either file, including one for a line that isn't valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("letter_trie_self_test_doc_{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("good.txt"), "cross\n\n  An \n").unwrap();
+    /// std::fs::write(dir.join("bad.txt"), "cros\n").unwrap();
+    /// let spec = SelfTestSpec::from_files(&dir.join("good.txt"), &dir.join("bad.txt")).unwrap();
+    /// assert_eq!(vec!["cross", "An"], spec.present);
+    ///
+    /// let trie = BaseLetterTrie::from_words(vec!["cross", "an"]);
+    /// assert_self_test!(trie, spec);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn from_files(good: &Path, bad: &Path) -> io::Result<Self> {
+        Ok(Self {
+            present: read_word_file(good)?,
+            absent: read_word_file(bad)?,
+            ..Self::default()
+        })
+    }
+}
+
+/// The result of `LetterTrie::self_test()`.
+///
+/// The `Display` output says the test passed or lists each failure on its own line.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["cross", "crossed", "an"]);
+/// let spec = SelfTestSpec {
+///     present: vec!["cross".to_owned(), "crease".to_owned()],
+///     word_count: Some(5..=10),
+///     ..SelfTestSpec::default()
+/// };
+/// let report = trie.self_test(&spec);
+/// assert!(!report.passed());
+/// assert_eq!(3, report.checks);
+/// assert_eq!(
+///     vec![
+///         SelfTestFailure::MissingWord("crease".to_owned()),
+///         SelfTestFailure::WordCount { expected: 5..=10, found: 3 },
+///     ],
+///     report.failures
+/// );
+/// assert_eq!(
+///     "\"crease\" should be a word but isn't.\nThere are 3 words rather than 5 to 10.\n",
+///     report.to_string()
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// The number of expectations checked, counting each word as one.
+    pub checks: usize,
+    /// The expectations that weren't met, with the words in the order they're listed in the spec followed by the
+    /// word count, the height and the fingerprint.
+    pub failures: Vec<SelfTestFailure>,
+}
+
+impl SelfTestReport {
+    /// Returns true if every expectation was met.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed() {
+            return writeln!(
+                f,
+                "The self-test passed {} checks.",
+                format_count(self.checks)
+            );
+        }
+        for failure in &self.failures {
+            writeln!(f, "{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// An expectation in a `SelfTestSpec` that the trie didn't meet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelfTestFailure {
+    /// A word that should be in the trie isn't.
+    MissingWord(String),
+    /// A word that shouldn't be in the trie is.
+    UnexpectedWord(String),
+    /// The trie has a number of words outside the expected range.
+    WordCount {
+        expected: RangeInclusive<usize>,
+        found: usize,
+    },
+    /// The trie is taller than expected, so it has a longer word than it should.
+    Height { max: usize, found: usize },
+    /// The trie's fingerprint isn't the expected one, so it doesn't have the expected words.
+    Fingerprint { expected: u64, found: u64 },
+}
+
+impl fmt::Display for SelfTestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelfTestFailure::MissingWord(word) => {
+                write!(f, "\"{}\" should be a word but isn't.", word)
+            }
+            SelfTestFailure::UnexpectedWord(word) => {
+                write!(f, "\"{}\" shouldn't be a word but is.", word)
+            }
+            SelfTestFailure::WordCount { expected, found } => write!(
+                f,
+                "There are {} words rather than {} to {}.",
+                format_count(*found),
+                format_count(*expected.start()),
+                format_count(*expected.end())
+            ),
+            SelfTestFailure::Height { max, found } => {
+                write!(f, "The height is {} rather than at most {}.", found, max)
+            }
+            SelfTestFailure::Fingerprint { expected, found } => write!(
+                f,
+                "The fingerprint is {:#018x} rather than {:#018x}.",
+                found, expected
+            ),
+        }
+    }
+}
+
+/// Panic with the report from `LetterTrie::self_test()` if the trie doesn't meet the spec, for use in tests.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["cross"]);
+/// let spec = SelfTestSpec {
+///     absent: vec!["cross".to_owned()],
+///     ..SelfTestSpec::default()
+/// };
+/// assert_self_test!(trie, spec);
+/// ```
+#[macro_export]
+macro_rules! assert_self_test {
+    ($trie:expr, $spec:expr $(,)?) => {{
+        // For when the trait isn't already in scope, which it is in most tests.
+        #[allow(unused_imports)]
+        use $crate::LetterTrie as _;
+        let report = $trie.self_test(&$spec);
+        if !report.passed() {
+            panic!("The trie failed its self-test:\n{}", report);
+        }
+    }};
+}
+
+pub(crate) fn self_test<T: TrieQuery + ?Sized>(trie: &T, spec: &SelfTestSpec) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    let mut check = |met: bool, failure: &dyn Fn() -> SelfTestFailure| {
+        report.checks += 1;
+        if !met {
+            report.failures.push(failure());
+        }
+    };
+    for word in &spec.present {
+        check(trie.contains(word), &|| {
+            SelfTestFailure::MissingWord(word.clone())
+        });
+    }
+    for word in &spec.absent {
+        check(!trie.contains(word), &|| {
+            SelfTestFailure::UnexpectedWord(word.clone())
+        });
+    }
+    if spec.word_count.is_some() || spec.max_height.is_some() {
+        let root = trie.to_fixed_node();
+        if let Some(expected) = &spec.word_count {
+            check(expected.contains(&root.word_count), &|| {
+                SelfTestFailure::WordCount {
+                    expected: expected.clone(),
+                    found: root.word_count,
+                }
+            });
+        }
+        if let Some(max) = spec.max_height {
+            check(root.height <= max, &|| SelfTestFailure::Height {
+                max,
+                found: root.height,
+            });
+        }
+    }
+    if let Some(expected) = spec.fingerprint {
+        let found = trie.fingerprint();
+        check(found == expected, &|| SelfTestFailure::Fingerprint {
+            expected,
+            found,
+        });
+    }
+    report
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // A spec the medium dataset meets, made from the file rather than from a trie.
+    fn medium_spec() -> SelfTestSpec {
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let lowercase: HashSet<String> = words.iter().map(|word| word.to_lowercase()).collect();
+        let longest = lowercase
+            .iter()
+            .map(|word| word.chars().count())
+            .max()
+            .unwrap();
+        SelfTestSpec {
+            present: words.iter().step_by(97).cloned().collect(),
+            absent: verification_words_embedded(VerificationSet::NonWords)
+                .into_iter()
+                .filter(|word| !lowercase.contains(&word.to_lowercase()))
+                .collect(),
+            word_count: Some(lowercase.len()..=lowercase.len()),
+            fingerprint: Some(BaseLetterTrie::from_words(&words).fingerprint()),
+            max_height: Some(longest + 1),
+        }
+    }
+
+    fn check_self_test<T: LetterTrie>() {
+        let t = T::from_dataset(&Dataset::TestMediumUnsorted, &LoadMethod::Continuous).unwrap();
+        let spec = medium_spec();
+        let report = t.self_test(&spec);
+        assert_eq!(Vec::<SelfTestFailure>::new(), report.failures, "{}", report);
+        assert_eq!(spec.present.len() + spec.absent.len() + 3, report.checks);
+        assert!(report.to_string().starts_with("The self-test passed"));
+        assert_self_test!(t, spec);
+
+        let present = spec.present[3].clone();
+        let spec = SelfTestSpec {
+            present: vec![spec.present[0].clone(), "qqqzz".to_owned()],
+            absent: vec![present.to_uppercase(), "zzqqq".to_owned()],
+            word_count: Some(0..=10),
+            fingerprint: Some(1),
+            max_height: Some(3),
+        };
+        let report = t.self_test(&spec);
+        let root = t.to_fixed_node();
+        assert_eq!(7, report.checks);
+        assert_eq!(
+            vec![
+                SelfTestFailure::MissingWord("qqqzz".to_owned()),
+                SelfTestFailure::UnexpectedWord(present.to_uppercase()),
+                SelfTestFailure::WordCount {
+                    expected: 0..=10,
+                    found: root.word_count,
+                },
+                SelfTestFailure::Height {
+                    max: 3,
+                    found: root.height,
+                },
+                SelfTestFailure::Fingerprint {
+                    expected: 1,
+                    found: t.fingerprint(),
+                },
+            ],
+            report.failures
+        );
+        let message = report.to_string();
+        assert_eq!(5, message.lines().count(), "{}", message);
+        assert!(message.contains("\"qqqzz\" should be a word but isn't."));
+        assert!(message.contains(&format!(
+            "There are {} words rather than 0 to 10.",
+            format_count(root.word_count)
+        )));
+
+        assert_eq!(
+            SelfTestReport::default(),
+            t.self_test(&SelfTestSpec::default())
+        );
+    }
+
+    #[test]
+    fn self_test_base() {
+        check_self_test::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn self_test_no_parent() {
+        check_self_test::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    #[should_panic(expected = "\"zzqqq\" shouldn't be a word but is.")]
+    fn assert_self_test_panics() {
+        let t = NoParentLetterTrie::from_words(vec!["zzqqq"]);
+        let view = t.view();
+        assert_self_test!(
+            view,
+            SelfTestSpec {
+                absent: vec!["zzqqq".to_owned()],
+                ..SelfTestSpec::default()
+            }
+        );
+    }
+
+    #[test]
+    fn spec_from_files() {
+        let dir = temp_path("self_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let good = dir.join(VerificationSet::GoodWords.filename());
+        let bad = dir.join(VerificationSet::NonWords.filename());
+        std::fs::write(&good, "cross\r\n\n crossed \n").unwrap();
+        std::fs::write(&bad, "cros\n").unwrap();
+        let spec = SelfTestSpec::from_files(&good, &bad).unwrap();
+        assert_eq!(
+            SelfTestSpec {
+                present: vec!["cross".to_owned(), "crossed".to_owned()],
+                absent: vec!["cros".to_owned()],
+                ..SelfTestSpec::default()
+            },
+            spec
+        );
+        let err = SelfTestSpec::from_files(&good, &dir.join("none.txt")).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+    }
+
+    // The crate's own verification lists pass against the large dataset.
+    #[test]
+    #[ignore = "needs the large dataset files"]
+    fn spec_from_verification_lists() {
+        let spec = SelfTestSpec::from_files(
+            &data_path(VerificationSet::GoodWords.filename(), None),
+            &data_path(VerificationSet::NonWords.filename(), None),
+        )
+        .unwrap();
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestLargeSorted, &LoadMethod::Continuous)
+                .unwrap();
+        assert_self_test!(t, spec);
+    }
+}
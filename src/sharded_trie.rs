@@ -0,0 +1,435 @@
+//! A trie that threads can change and query at the same time, for a service that adds a few words a second while
+//! answering thousands of lookups.
+//!
+//! `ShardedLetterTrie` keeps the words in a `NoParentLetterTrie` for each first letter from "a" to "z", each behind
+//! its own `RwLock`, along with one shard for the words that start with anything before "a" and one for anything
+//! after "z". A word goes to the shard for its first letter once it's lowercased, so a writer only holds up the
+//! readers of words that start with the same letter. The shards are in the order of their letters, so the words of
+//! one shard after another are in alphabetical order.
+//!
+//! A query for one word or for a prefix that isn't empty locks only the shard it needs. The operations on the whole
+//! trie, such as `words()` and `fingerprint()`, take a read lock on every shard before they start and hold them all
+//! until they're done, so they see the words as they were at one moment. They always lock the shards in order from
+//! the first to the last, and the other operations never hold more than one lock, so no two operations can each be
+//! waiting for a lock the other holds.
+//!
+//! A shard's lock is only poisoned if a thread panics while it's changing that shard, and the methods panic if they
+//! find one poisoned since the shard may be half changed.
+
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::*;
+
+// The number of shards: one for each letter from "a" to "z", one for the first letters before them and one for
+// those after.
+pub(crate) const SHARD_COUNT: usize = 28;
+
+// The shard for a word or prefix whose first letter is c.
+fn shard_index(c: char) -> usize {
+    // A letter can lowercase to more than one character, but the first is the one the stored word starts with.
+    let c = c.to_lowercase().next().unwrap_or(c);
+    match c {
+        'a'..='z' => c as usize - 'a' as usize + 1,
+        _ if c < 'a' => 0,
+        _ => SHARD_COUNT - 1,
+    }
+}
+
+// Lock a shard for reading, panicking if it's poisoned as described in the module documentation.
+#[allow(clippy::unwrap_used)]
+fn read_shard(shard: &RwLock<NoParentLetterTrie>) -> RwLockReadGuard<'_, NoParentLetterTrie> {
+    shard.read().unwrap()
+}
+
+// Lock a shard for writing, panicking if it's poisoned as described in the module documentation.
+#[allow(clippy::unwrap_used)]
+fn write_shard(shard: &RwLock<NoParentLetterTrie>) -> RwLockWriteGuard<'_, NoParentLetterTrie> {
+    shard.write().unwrap()
+}
+
+// The shard for a word or prefix, or None if it's empty once it's trimmed as the shards trim it.
+fn shard_of(s: &str) -> Option<usize> {
+    s.trim().chars().next().map(shard_index)
+}
+
+/// A trie of words split by first letter into shards that are each behind their own lock, so that one thread can
+/// add or remove a word while others look up words that start with other letters. See the `sharded_trie` module.
+///
+/// Every method takes `&self`, so the trie can be shared between threads in an `Arc` or by reference.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = ShardedLetterTrie::from_words(vec!["cross", "crate", "an"]);
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| trie.insert("Crossed"));
+///     scope.spawn(|| assert!(trie.contains("an")));
+/// });
+/// assert_eq!(vec!["cross", "crossed"], trie.words_with_prefix("cro"));
+/// assert!(trie.remove("an"));
+/// assert_eq!(vec!["crate", "cross", "crossed"], trie.words());
+/// ```
+pub struct ShardedLetterTrie {
+    // The shards in the order of their first letters, as given by shard_index().
+    shards: Vec<RwLock<NoParentLetterTrie>>,
+}
+
+impl ShardedLetterTrie {
+    pub fn new() -> Self {
+        Self::from_shards(
+            (0..SHARD_COUNT)
+                .map(|_| NoParentLetterTrie::new())
+                .collect(),
+        )
+    }
+
+    /// Create a trie from a list of words as `LetterTrie::from_words()` does, building each shard from its own
+    /// words before any lock is needed.
+    pub fn from_words<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut groups: Vec<Vec<S>> = (0..SHARD_COUNT).map(|_| vec![]).collect();
+        for word in words {
+            if let Some(index) = shard_of(word.as_ref()) {
+                groups[index].push(word);
+            }
+        }
+        Self::from_shards(
+            groups
+                .into_iter()
+                .map(NoParentLetterTrie::from_words)
+                .collect(),
+        )
+    }
+
+    fn from_shards(shards: Vec<NoParentLetterTrie>) -> Self {
+        debug_assert_eq!(SHARD_COUNT, shards.len());
+        Self {
+            shards: shards.into_iter().map(RwLock::new).collect(),
+        }
+    }
+
+    // Lock every shard for reading, in order, for an operation on the whole trie.
+    fn read_all(&self) -> Vec<RwLockReadGuard<'_, NoParentLetterTrie>> {
+        self.shards.iter().map(read_shard).collect()
+    }
+
+    /// Add a word as `LetterTrie::insert()` does, holding the lock on its shard for only as long as that takes.
+    pub fn insert(&self, word: &str) {
+        if let Some(index) = shard_of(word) {
+            write_shard(&self.shards[index]).insert(word);
+        }
+    }
+
+    /// Remove a word as `LetterTrie::remove()` does, returning whether it was there.
+    pub fn remove(&self, word: &str) -> bool {
+        match shard_of(word) {
+            Some(index) => write_shard(&self.shards[index]).remove(word),
+            None => false,
+        }
+    }
+
+    /// Whether `word` is in the trie, as in `LetterTrie::contains()`.
+    pub fn contains(&self, word: &str) -> bool {
+        match shard_of(word) {
+            Some(index) => read_shard(&self.shards[index]).contains(word),
+            None => false,
+        }
+    }
+
+    /// Get every word that starts with `prefix` in alphabetical order, as in `LetterTrie::words_with_prefix()`. Only
+    /// the prefix's shard is locked unless the prefix is empty.
+    pub fn words_with_prefix(&self, prefix: &str) -> Vec<String> {
+        match shard_of(prefix) {
+            Some(index) => read_shard(&self.shards[index]).words_with_prefix(prefix),
+            None => self.words(),
+        }
+    }
+
+    /// The number of words that start with `prefix`, as in `LetterTrie::count_with_prefix()`. For the empty prefix
+    /// this is the sum of the shards' counts at one moment.
+    pub fn count_with_prefix(&self, prefix: &str) -> usize {
+        match shard_of(prefix) {
+            Some(index) => read_shard(&self.shards[index]).count_with_prefix(prefix),
+            None => self
+                .read_all()
+                .iter()
+                .map(|shard| shard.count_with_prefix(""))
+                .sum(),
+        }
+    }
+
+    /// The number of words in the trie.
+    pub fn word_count(&self) -> usize {
+        self.count_with_prefix("")
+    }
+
+    /// The number of nodes the words would take in one trie, counting a single root rather than one for each
+    /// shard, so it's the `node_count` a `NoParentLetterTrie` of the same words would report.
+    pub fn node_count(&self) -> usize {
+        1 + self
+            .read_all()
+            .iter()
+            .map(|shard| shard.to_fixed_node().node_count - 1)
+            .sum::<usize>()
+    }
+
+    /// Get all of the words in alphabetical order as they were at one moment.
+    pub fn words(&self) -> Vec<String> {
+        let mut words = vec![];
+        for shard in &self.read_all() {
+            words.extend(shard.words());
+        }
+        words
+    }
+
+    /// Get the hash `LetterTrie::fingerprint()` gives for a trie of the same words, as they were at one moment.
+    pub fn fingerprint(&self) -> u64 {
+        let shards = self.read_all();
+        // The shards' roots stand for one root with all of their children, and the rest of each shard's nodes
+        // follow in order.
+        let root_child_count = shards
+            .iter()
+            .map(|shard| shard.to_fixed_node().child_count)
+            .sum();
+        let mut hash = fingerprint_node(FNV_OFFSET_BASIS, ' ', false, root_child_count);
+        for shard in &shards {
+            shard.visit("", &mut |node| {
+                if node.depth > 0 {
+                    hash = fingerprint_node(hash, node.c, node.is_word, node.child_count);
+                }
+                true
+            });
+        }
+        hash
+    }
+
+    /// Check each shard as `LetterTrie::verify_integrity()` does, and that each one only has words that start with
+    /// its letters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Inconsistent` describing the first problem found.
+    pub fn verify_integrity(&self) -> Result<()> {
+        for (index, shard) in self.read_all().iter().enumerate() {
+            shard.verify_integrity()?;
+            if let Some(c) = shard
+                .children("")
+                .into_iter()
+                .find(|c| shard_index(*c) != index)
+            {
+                return Err(LetterTrieError::Inconsistent(format!(
+                    "shard {} has words starting with {:?}, which belong in shard {}",
+                    index,
+                    c,
+                    shard_index(c)
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ShardedLetterTrie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The count rather than the words, which could be hundreds of thousands.
+impl Debug for ShardedLetterTrie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedLetterTrie")
+            .field("word_count", &self.word_count())
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use crate::generator::SplitMix64;
+    use std::collections::BTreeSet;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn matches_one_trie() {
+        assert_send_sync::<ShardedLetterTrie>();
+        let mut words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        words.extend(
+            [
+                "o'clock", "123", "'tis", "ñandú", "Über", "zebra", "Éclair", " padded ",
+            ]
+            .iter()
+            .map(|word| word.to_string()),
+        );
+        let sharded = ShardedLetterTrie::from_words(&words);
+        let t = NoParentLetterTrie::from_words(&words);
+        sharded.verify_integrity().unwrap();
+        assert_eq!(t.words().collect::<Vec<_>>(), sharded.words());
+        assert_eq!(t.fingerprint(), sharded.fingerprint());
+        assert_eq!(t.to_fixed_node().node_count, sharded.node_count());
+        assert_eq!(t.count_with_prefix(""), sharded.word_count());
+        for prefix in ["", "a", "Cr", "ñ", "1", "'", "zz", "éc"] {
+            assert_eq!(
+                t.words_with_prefix(prefix),
+                sharded.words_with_prefix(prefix),
+                "{:?}",
+                prefix
+            );
+            assert_eq!(
+                t.count_with_prefix(prefix),
+                sharded.count_with_prefix(prefix)
+            );
+        }
+        for word in words.iter().step_by(37) {
+            assert!(sharded.contains(word), "{}", word);
+        }
+        assert!(sharded.contains("ÜBER"));
+        assert!(!sharded.contains(""));
+        assert!(!sharded.contains("zzyzx"));
+
+        sharded.insert("   ");
+        assert!(!sharded.remove(" "));
+        assert!(sharded.remove(" Padded"));
+        assert!(!sharded.contains("padded"));
+        assert_eq!(t.count_with_prefix("") - 1, sharded.word_count());
+        assert_eq!(0, ShardedLetterTrie::new().word_count());
+        assert_eq!(
+            NoParentLetterTrie::new().fingerprint(),
+            ShardedLetterTrie::default().fingerprint()
+        );
+    }
+
+    // The padding would otherwise pick the shard before "a" rather than the one the word is in.
+    #[test]
+    fn queries_trim() {
+        let sharded = ShardedLetterTrie::from_words(vec!["cross"]);
+        sharded.insert("crate");
+        let t = NoParentLetterTrie::from_words(vec!["cross", "crate"]);
+        for query in [" cross", "\tCross ", "cross\n"] {
+            assert!(sharded.contains(query), "{:?}", query);
+            assert_eq!(t.contains(query), sharded.contains(query));
+        }
+        for prefix in [" cr", "\tCRO", " cross ", "  "] {
+            assert_eq!(
+                t.words_with_prefix(prefix),
+                sharded.words_with_prefix(prefix),
+                "{:?}",
+                prefix
+            );
+            assert_eq!(
+                t.count_with_prefix(prefix),
+                sharded.count_with_prefix(prefix),
+                "{:?}",
+                prefix
+            );
+        }
+        assert_eq!(vec!["crate", "cross"], sharded.words_with_prefix(" cr"));
+        assert!(sharded.remove(" cross"));
+        assert!(!sharded.contains(" cross"));
+    }
+
+    #[test]
+    fn wrong_shard_is_an_error() {
+        let sharded = ShardedLetterTrie::from_words(vec!["cross", "an"]);
+        sharded.shards[1].write().unwrap().insert("zebra");
+        match sharded.verify_integrity() {
+            Err(LetterTrieError::Inconsistent(msg)) => assert!(msg.contains("'z'"), "{}", msg),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    // Writers add and remove words while readers query them, and at the end the trie has the words a locked set
+    // was given. Each writer has its own words so that the order of the changes to each word is the same in both.
+    #[test]
+    fn writers_and_readers() {
+        const WRITERS: usize = 4;
+        const READERS: usize = 4;
+        const OPERATIONS: usize = 3_000;
+        let words: Vec<String> = words_from_file(Dataset::TestMediumSorted.filename())
+            .unwrap()
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect();
+        // Every fourth word is there from the start and never removed, so a reader can always find it.
+        let kept: Vec<String> = words.iter().step_by(4).cloned().collect();
+        let changing: Vec<&String> = words
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 4 != 0)
+            .map(|(_, word)| word)
+            .collect();
+        let trie = ShardedLetterTrie::from_words(&kept);
+        let model: Mutex<BTreeSet<String>> = Mutex::new(kept.iter().cloned().collect());
+        let writers_done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let writers: Vec<_> = (0..WRITERS)
+                .map(|writer| {
+                    let (trie, model) = (&trie, &model);
+                    let own: Vec<&String> = changing
+                        .iter()
+                        .skip(writer)
+                        .step_by(WRITERS)
+                        .copied()
+                        .collect();
+                    scope.spawn(move || {
+                        let mut rng = SplitMix64(185 + writer as u64);
+                        for _ in 0..OPERATIONS {
+                            let word = own[rng.below(own.len() as u64) as usize];
+                            if rng.below(3) == 0 {
+                                let removed = trie.remove(word);
+                                assert_eq!(model.lock().unwrap().remove(word), removed, "{}", word);
+                            } else {
+                                trie.insert(word);
+                                model.lock().unwrap().insert(word.clone());
+                            }
+                        }
+                    })
+                })
+                .collect();
+            for reader in 0..READERS {
+                let (trie, kept, writers_done) = (&trie, &kept, &writers_done);
+                scope.spawn(move || {
+                    let mut rng = SplitMix64(1_850 + reader as u64);
+                    while !writers_done.load(Ordering::Acquire) {
+                        let word = &kept[rng.below(kept.len() as u64) as usize];
+                        assert!(trie.contains(word), "{}", word);
+                        let prefix: String = word.chars().take(2).collect();
+                        let found = trie.words_with_prefix(&prefix);
+                        assert!(found.contains(word), "{}", word);
+                        assert!(found.iter().all(|found| found.starts_with(&prefix)));
+                        assert!(found.windows(2).all(|pair| pair[0] < pair[1]));
+                        if rng.below(50) == 0 {
+                            let all = trie.words();
+                            assert!(all.len() >= kept.len());
+                            assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+                            assert!(trie.word_count() >= kept.len());
+                        }
+                    }
+                });
+            }
+            // The readers are stopped before a writer's panic is passed on so that the scope can end.
+            let results: Vec<_> = writers.into_iter().map(|writer| writer.join()).collect();
+            writers_done.store(true, Ordering::Release);
+            for result in results {
+                result.unwrap();
+            }
+        });
+        trie.verify_integrity().unwrap();
+        let model: Vec<String> = model.into_inner().unwrap().into_iter().collect();
+        assert_eq!(model, trie.words());
+        assert_eq!(
+            NoParentLetterTrie::from_words(&model).fingerprint(),
+            trie.fingerprint()
+        );
+    }
+}
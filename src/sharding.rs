@@ -0,0 +1,281 @@
+//! Saving the words of a trie in several word files rather than one, as for handing pieces of a dictionary to
+//! different worker processes. `LetterTrie::save_sharded()` writes the files, and `LetterTrie::from_letter_groups()`
+//! is the way back for shards by first letter.
+//!
+//! Each shard is a sorted word file with one word per line, which any of the loaders reads. Alongside them is a
+//! manifest, `manifest.tsv`, with a line for each shard in order giving its file name, a tab, and its number of
+//! words. Shards that would have no words aren't written.
+//!
+//! A shard of words with a prefix in common is named after the prefix. Lowercase ASCII letters and digits are used
+//! as they are, and any other character is written as its code point in hex between underscores, so "ñu" is in
+//! `_f1_u.txt`. A name that Windows keeps for a device, such as `con`, gets an underscore on the end. Shards by
+//! number of words are numbered from 0 with enough leading zeros that the names sort in order.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::*;
+
+// The name of the manifest in the directory with the shards. It doesn't end in .txt, so no shard can have it.
+pub(crate) const MANIFEST_FILE_NAME: &str = "manifest.tsv";
+
+/// How `LetterTrie::save_sharded()` divides up the words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShardBy {
+    /// A shard for each first letter.
+    FirstLetter,
+    /// A shard for each prefix of this many letters. A word shorter than that is in a shard of its own named after
+    /// the whole word, so with `PrefixLen(2)` the word "a" is in `a.txt` and "an" is in `an.txt`. A length of 0 is
+    /// taken as 1.
+    PrefixLen(usize),
+    /// Shards of this many words in order, except that the last may have fewer. A count of 0 is taken as 1.
+    MaxWordsPerFile(usize),
+}
+
+/// One of the files written by `LetterTrie::save_sharded()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// The name of the file in the directory, such as `a.txt`.
+    pub file_name: String,
+    pub word_count: usize,
+}
+
+/// The shards written by `LetterTrie::save_sharded()` in the order of their words, which is also what's in the
+/// manifest file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShardManifest {
+    pub shards: Vec<Shard>,
+}
+
+impl ShardManifest {
+    /// The number of words in all of the shards, which is the number in the trie.
+    pub fn word_count(&self) -> usize {
+        self.shards.iter().map(|shard| shard.word_count).sum()
+    }
+}
+
+pub(crate) fn save_sharded<T: LetterTrie + ?Sized>(
+    trie: &T,
+    dir: &Path,
+    shard_by: ShardBy,
+) -> Result<ShardManifest> {
+    fs::create_dir_all(dir).map_err(in_file(dir))?;
+    // For shards by number of words, the shards' numbers are padded to the width of the last one.
+    let per_file = match shard_by {
+        ShardBy::MaxWordsPerFile(count) => count.max(1),
+        _ => 1,
+    };
+    let width = (trie.count_with_prefix("").saturating_sub(1) / per_file)
+        .to_string()
+        .len();
+    let mut manifest = ShardManifest::default();
+    // The key of the shard being written, and the path and writer of the file it's going to.
+    let mut current_key: Option<String> = None;
+    let mut current: Option<(PathBuf, BufWriter<File>)> = None;
+    for (index, word) in trie.words().enumerate() {
+        if word.contains(['\n', '\r']) {
+            return Err(LetterTrieError::InvalidFormat(format!(
+                "{:?} can't be written to a word file because it has a line break",
+                word
+            )));
+        }
+        let key = match shard_by {
+            ShardBy::FirstLetter => word.chars().take(1).collect(),
+            ShardBy::PrefixLen(len) => word.chars().take(len.max(1)).collect(),
+            ShardBy::MaxWordsPerFile(_) => format!("{:0width$}", index / per_file, width = width),
+        };
+        if current_key.as_ref() != Some(&key) {
+            if let Some((path, mut writer)) = current.take() {
+                writer.flush().map_err(in_file(&path))?;
+            }
+            let file_name = format!("{}.txt", file_stem(&key));
+            let path = dir.join(&file_name);
+            let writer = BufWriter::new(File::create(&path).map_err(in_file(&path))?);
+            manifest.shards.push(Shard {
+                file_name,
+                word_count: 0,
+            });
+            current_key = Some(key);
+            current = Some((path, writer));
+        }
+        // A shard was started above if there wasn't one open.
+        if let (Some((path, writer)), Some(shard)) = (&mut current, manifest.shards.last_mut()) {
+            writeln!(writer, "{}", word).map_err(in_file(path))?;
+            shard.word_count += 1;
+        }
+    }
+    if let Some((path, mut writer)) = current {
+        writer.flush().map_err(in_file(&path))?;
+    }
+    let path = dir.join(MANIFEST_FILE_NAME);
+    let write_manifest = || -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for shard in &manifest.shards {
+            writeln!(writer, "{}\t{}", shard.file_name, shard.word_count)?;
+        }
+        writer.flush()
+    };
+    write_manifest().map_err(in_file(&path))?;
+    Ok(manifest)
+}
+
+// The name of a shard's file without the extension, as described in the module documentation.
+fn file_stem(key: &str) -> String {
+    const WINDOWS_DEVICES: [&str; 22] = [
+        "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+        "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+    ];
+    let mut stem = String::new();
+    for c in key.chars() {
+        if c.is_ascii_lowercase() || c.is_ascii_digit() {
+            stem.push(c);
+        } else {
+            stem.push_str(&format!("_{:x}_", u32::from(c)));
+        }
+    }
+    if WINDOWS_DEVICES.contains(&stem.as_str()) {
+        stem.push('_');
+    }
+    stem
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    // Load every shard in the manifest into one trie, checking each one's count and that its words are sorted.
+    fn reload<T: LetterTrie>(dir: &Path, manifest: &ShardManifest) -> T {
+        let listed = fs::read_to_string(dir.join(MANIFEST_FILE_NAME)).unwrap();
+        let expected: Vec<String> = manifest
+            .shards
+            .iter()
+            .map(|shard| format!("{}\t{}", shard.file_name, shard.word_count))
+            .collect();
+        assert_eq!(expected, listed.lines().collect::<Vec<_>>());
+        let mut all: Vec<String> = vec![];
+        for shard in &manifest.shards {
+            let words = words_from_file(dir.join(&shard.file_name).to_str().unwrap()).unwrap();
+            assert_eq!(shard.word_count, words.len(), "{}", shard.file_name);
+            assert!(words.windows(2).all(|pair| pair[0] < pair[1]));
+            all.extend(words);
+        }
+        assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+        T::from_words(all)
+    }
+
+    fn check_round_trip<T: LetterTrie>(name: &str) {
+        let mut t: T =
+            T::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous).unwrap();
+        for word in ["ñandú", "über", "o'clock", "con", "conch", "a"] {
+            t.insert(word);
+        }
+        let word_count = t.count_with_prefix("");
+        for shard_by in [
+            ShardBy::FirstLetter,
+            ShardBy::PrefixLen(2),
+            ShardBy::PrefixLen(3),
+            ShardBy::MaxWordsPerFile(1_000),
+            ShardBy::MaxWordsPerFile(word_count),
+        ] {
+            let dir = temp_path(&format!("shards_{}_{:?}", name, shard_by));
+            let manifest = t.save_sharded(&dir, shard_by).unwrap();
+            assert_eq!(word_count, manifest.word_count());
+            assert!(manifest.shards.iter().all(|shard| shard.word_count > 0));
+            let copy: T = reload(&dir, &manifest);
+            assert_eq!(t.fingerprint(), copy.fingerprint(), "{:?}", shard_by);
+            let names: Vec<&str> = manifest
+                .shards
+                .iter()
+                .map(|shard| shard.file_name.as_str())
+                .collect();
+            match shard_by {
+                ShardBy::FirstLetter => {
+                    assert!(names.contains(&"a.txt") && names.contains(&"_fc_.txt"));
+                    assert_eq!(t.children("").len(), names.len());
+                }
+                ShardBy::PrefixLen(3) => {
+                    assert!(names.contains(&"con_.txt") && names.contains(&"_f1_an.txt"));
+                    assert!(names.contains(&"a.txt") && names.contains(&"o_27_c.txt"));
+                }
+                ShardBy::MaxWordsPerFile(1_000) => {
+                    assert_eq!(word_count.div_ceil(1_000), names.len());
+                    assert!(manifest.shards[..names.len() - 1]
+                        .iter()
+                        .all(|shard| shard.word_count == 1_000));
+                }
+                ShardBy::MaxWordsPerFile(_) => assert_eq!(1, names.len()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        check_round_trip::<BaseLetterTrie>("base");
+        check_round_trip::<NoParentLetterTrie>("no_parent");
+    }
+
+    #[test]
+    fn first_letter_shards_load_as_groups() {
+        let t = NoParentLetterTrie::from_words(vec!["cross", "crate", "an", "and", "ñu"]);
+        let dir = temp_path("shards_groups");
+        let manifest = t.save_sharded(&dir, ShardBy::FirstLetter).unwrap();
+        let groups: Vec<(char, Vec<String>)> = manifest
+            .shards
+            .iter()
+            .map(|shard| {
+                let words = words_from_file(dir.join(&shard.file_name).to_str().unwrap()).unwrap();
+                (words[0].chars().next().unwrap(), words)
+            })
+            .collect();
+        let copy = BaseLetterTrie::from_letter_groups(groups, true).unwrap();
+        assert_eq!(t.fingerprint(), copy.fingerprint());
+    }
+
+    #[test]
+    fn empty_trie_writes_only_the_manifest() {
+        let t = BaseLetterTrie::new();
+        let dir = temp_path("shards_empty");
+        for shard_by in [ShardBy::FirstLetter, ShardBy::MaxWordsPerFile(0)] {
+            let manifest = t.save_sharded(&dir, shard_by).unwrap();
+            assert!(manifest.shards.is_empty());
+            assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+        }
+    }
+
+    #[test]
+    fn zero_counts_as_one() {
+        let t = NoParentLetterTrie::from_words(vec!["cross", "crate", "an"]);
+        let dir = temp_path("shards_zero");
+        let manifest = t.save_sharded(&dir, ShardBy::PrefixLen(0)).unwrap();
+        assert_eq!(
+            vec!["a.txt", "c.txt"],
+            manifest
+                .shards
+                .iter()
+                .map(|shard| shard.file_name.as_str())
+                .collect::<Vec<_>>()
+        );
+        let manifest = t.save_sharded(&dir, ShardBy::MaxWordsPerFile(0)).unwrap();
+        assert_eq!(
+            vec!["0.txt", "1.txt", "2.txt"],
+            manifest
+                .shards
+                .iter()
+                .map(|shard| shard.file_name.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn stems_are_safe_and_distinct() {
+        assert_eq!("ab9", file_stem("ab9"));
+        assert_eq!("_f1_u", file_stem("ñu"));
+        assert_eq!("o_27_", file_stem("o'"));
+        assert_eq!("_2e__2e_", file_stem(".."));
+        assert_eq!("aux_", file_stem("aux"));
+        assert_eq!("auxi", file_stem("auxi"));
+        assert_eq!("_1f600_", file_stem("😀"));
+    }
+}
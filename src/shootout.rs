@@ -0,0 +1,394 @@
+//! A race between a `NoParentLetterTrie`, a `HashSet<String>` and a `BTreeSet<String>` at the one thing all three
+//! can do, telling whether a word is in the list, for deciding whether a trie is worth it when that's all a program
+//! needs.
+//!
+//! `membership_shootout()` reads the dataset's words once and builds each structure from the same `Vec<String>`,
+//! so the build times leave out reading the file. Each kind of probe in the `ProbeSet` is then looked up in each
+//! structure `ShootoutReport::rounds` times over and the fastest round is kept, which takes out most of the noise
+//! from other work on the machine.
+//!
+//! The sizes are estimates. The trie's comes from `NoParentLetterTrie::estimated_bytes()`. The sets' count each
+//! `String` and its bytes, plus for the hash set the empty slots and a control byte for each slot, and for the
+//! B-tree the room left over in nodes that are only partly full, taken as a third of each node.
+//!
+//! The trie looks up words without regard to case, which costs it some time, while the sets compare the probes as
+//! they are. The datasets and probes are all lowercase, so all three find the same words.
+
+use std::collections::{BTreeSet, HashSet};
+use std::mem;
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+/// The number of times each kind of probe is looked up in each structure, keeping the fastest.
+pub(crate) const SHOOTOUT_ROUNDS: usize = 5;
+
+/// The words to look up in `membership_shootout()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProbeSet {
+    /// Words that are in the dataset.
+    pub hits: Vec<String>,
+    /// Words that aren't, most of which go wrong within a few letters.
+    pub misses: Vec<String>,
+    /// Words that aren't in the dataset but share every letter but the last with one that is, as from
+    /// `shared_prefix_probes()`. These are the worst case for a trie.
+    pub adversarial: Vec<String>,
+}
+
+impl ProbeSet {
+    /// The probes for the large dataset: the 1,000 words of `VerificationSet::GoodWords`, the 1,000 of
+    /// `VerificationSet::NonWords`, and 1,000 from `shared_prefix_probes()` at least six letters long.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from opening or reading the files.
+    pub fn verification() -> io::Result<Self> {
+        Ok(Self {
+            hits: verification_words(VerificationSet::GoodWords, None)?,
+            misses: verification_words(VerificationSet::NonWords, None)?,
+            adversarial: shared_prefix_probes(&Dataset::TestLargeSorted, 6, 1_000)?,
+        })
+    }
+
+    /// Up to `count` probes of each kind for any dataset. The hits are taken at even steps through the dataset's
+    /// words, the misses are the hits with "zq" in front, and the rest come from `shared_prefix_probes()` for words
+    /// of at least four letters.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from opening or reading the dataset's file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let probes = ProbeSet::sampled(&Dataset::EmbeddedSmall, 4)?;
+    /// assert_eq!(4, probes.hits.len());
+    /// assert!(probes.misses.iter().all(|word| word.starts_with("zq")));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn sampled(dataset: &Dataset, count: usize) -> io::Result<Self> {
+        let words = read_dataset_words(dataset)?;
+        let step = (words.len() / count.max(1)).max(1);
+        let hits: Vec<String> = words.into_iter().step_by(step).take(count).collect();
+        let misses = hits.iter().map(|word| format!("zq{}", word)).collect();
+        Ok(Self {
+            hits,
+            misses,
+            adversarial: shared_prefix_probes(dataset, 4, count)?,
+        })
+    }
+}
+
+/// The structures compared by `membership_shootout()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Contender {
+    Trie,
+    HashSet,
+    BTreeSet,
+}
+
+impl fmt::Display for Contender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Contender::Trie => write!(f, "NoParentLetterTrie"),
+            Contender::HashSet => write!(f, "HashSet<String>"),
+            Contender::BTreeSet => write!(f, "BTreeSet<String>"),
+        }
+    }
+}
+
+/// How one structure did in `membership_shootout()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContenderResult {
+    pub contender: Contender,
+    pub build_time: Duration,
+    /// An estimate of the memory the structure holds, as described in the module documentation.
+    pub estimated_bytes: usize,
+    /// Lookups a second for `ProbeSet::hits`, from the fastest round.
+    pub hits_per_sec: f64,
+    /// Lookups a second for `ProbeSet::misses`.
+    pub misses_per_sec: f64,
+    /// Lookups a second for `ProbeSet::adversarial`.
+    pub adversarial_per_sec: f64,
+    /// The probes of all three kinds that were found, which is the same for every structure.
+    pub found: usize,
+}
+
+/// What `membership_shootout()` found, with a row for each structure in the order of `Contender`. Its `Display`
+/// is a table with the fastest build, the smallest size and the fastest lookups marked with `*`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShootoutReport {
+    pub dataset: Dataset,
+    pub word_count: usize,
+    /// The number of probes of each kind, as hits, misses and adversarial.
+    pub probe_counts: (usize, usize, usize),
+    pub rounds: usize,
+    pub results: Vec<ContenderResult>,
+}
+
+impl ShootoutReport {
+    /// The result for one structure, or None if it isn't in `results`.
+    pub fn result(&self, contender: Contender) -> Option<&ContenderResult> {
+        self.results
+            .iter()
+            .find(|result| result.contender == contender)
+    }
+}
+
+impl fmt::Display for ShootoutReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (hits, misses, adversarial) = self.probe_counts;
+        writeln!(
+            f,
+            "{}: {} words; {} hits, {} misses and {} adversarial probes; best of {} rounds",
+            self.dataset,
+            format_count(self.word_count),
+            format_count(hits),
+            format_count(misses),
+            format_count(adversarial),
+            self.rounds
+        )?;
+        writeln!(
+            f,
+            "{:<20} {:>12} {:>12} {:>14} {:>14} {:>14}",
+            "structure", "build", "memory", "hits/s", "misses/s", "adversarial/s"
+        )?;
+        let best = |value: &dyn Fn(&ContenderResult) -> f64, lowest: bool| {
+            let values = self.results.iter().map(value);
+            if lowest {
+                values.fold(f64::INFINITY, f64::min)
+            } else {
+                values.fold(0.0, f64::max)
+            }
+        };
+        let best_build = best(&|r| r.build_time.as_secs_f64(), true);
+        let best_bytes = best(&|r| r.estimated_bytes as f64, true);
+        let best_hits = best(&|r| r.hits_per_sec, false);
+        let best_misses = best(&|r| r.misses_per_sec, false);
+        let best_adversarial = best(&|r| r.adversarial_per_sec, false);
+        let mark = |value: f64, best: f64| if value == best { "*" } else { " " };
+        let rate = |value: f64| format_count(value.round() as usize);
+        for r in &self.results {
+            writeln!(
+                f,
+                "{:<20} {:>11}{} {:>11}{} {:>13}{} {:>13}{} {:>13}{}",
+                r.contender.to_string(),
+                format_duration(r.build_time),
+                mark(r.build_time.as_secs_f64(), best_build),
+                format_bytes(r.estimated_bytes),
+                mark(r.estimated_bytes as f64, best_bytes),
+                rate(r.hits_per_sec),
+                mark(r.hits_per_sec, best_hits),
+                rate(r.misses_per_sec),
+                mark(r.misses_per_sec, best_misses),
+                rate(r.adversarial_per_sec),
+                mark(r.adversarial_per_sec, best_adversarial),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Build a `NoParentLetterTrie`, a `HashSet<String>` and a `BTreeSet<String>` from the words of the dataset and
+/// time how fast each answers whether the probes are words, as described in the module documentation.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::MissingDataset` if the dataset's file isn't there and `LetterTrieError::File` with
+/// the dataset's path if it can't be opened or read for any other reason.
+///
+/// # Panics
+///
+/// Panics if the structures don't find the same number of probes, which would mean one of them is wrong.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let probes = ProbeSet::sampled(&Dataset::EmbeddedSmall, 5)?;
+/// let report = membership_shootout(&Dataset::EmbeddedSmall, &probes)?;
+/// assert_eq!(10, report.word_count);
+/// assert_eq!(Some(5), report.result(Contender::HashSet).map(|result| result.found));
+/// println!("{}", report);
+/// # Ok::<(), LetterTrieError>(())
+/// ```
+pub fn membership_shootout(dataset: &Dataset, probes: &ProbeSet) -> Result<ShootoutReport> {
+    let path = dataset.path();
+    let words = read_dataset_words(dataset).map_err(in_dataset_file(&path))?;
+
+    let (trie, trie_build) = timed(|| NoParentLetterTrie::from_words(&words));
+    let (hash_set, hash_set_build) = timed(|| words.iter().cloned().collect::<HashSet<String>>());
+    let (btree_set, btree_set_build) =
+        timed(|| words.iter().cloned().collect::<BTreeSet<String>>());
+
+    let results = vec![
+        race(
+            Contender::Trie,
+            trie_build,
+            trie.estimated_bytes(),
+            probes,
+            |word| trie.contains(word),
+        ),
+        race(
+            Contender::HashSet,
+            hash_set_build,
+            hash_set_bytes(&hash_set),
+            probes,
+            |word| hash_set.contains(word),
+        ),
+        race(
+            Contender::BTreeSet,
+            btree_set_build,
+            btree_set_bytes(&btree_set),
+            probes,
+            |word| btree_set.contains(word),
+        ),
+    ];
+    assert!(
+        results
+            .windows(2)
+            .all(|pair| pair[0].found == pair[1].found),
+        "The structures found different numbers of probes: {:?}",
+        results
+    );
+    Ok(ShootoutReport {
+        dataset: dataset.clone(),
+        word_count: hash_set.len(),
+        probe_counts: (
+            probes.hits.len(),
+            probes.misses.len(),
+            probes.adversarial.len(),
+        ),
+        rounds: SHOOTOUT_ROUNDS,
+        results,
+    })
+}
+
+fn timed<R>(f: impl FnOnce() -> R) -> (R, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+// Look up each kind of probe SHOOTOUT_ROUNDS times with contains and keep the fastest round of each.
+fn race(
+    contender: Contender,
+    build_time: Duration,
+    estimated_bytes: usize,
+    probes: &ProbeSet,
+    contains: impl Fn(&str) -> bool,
+) -> ContenderResult {
+    let mut found = 0;
+    let mut per_sec = |words: &[String]| {
+        let mut fastest = Duration::MAX;
+        let mut found_in_round = 0;
+        for _ in 0..SHOOTOUT_ROUNDS {
+            let (count, elapsed) = timed(|| {
+                words
+                    .iter()
+                    .filter(|word| contains(std::hint::black_box(word.as_str())))
+                    .count()
+            });
+            fastest = fastest.min(elapsed);
+            found_in_round = count;
+        }
+        found += found_in_round;
+        words.len() as f64 / fastest.as_secs_f64().max(1e-9)
+    };
+    let hits_per_sec = per_sec(&probes.hits);
+    let misses_per_sec = per_sec(&probes.misses);
+    let adversarial_per_sec = per_sec(&probes.adversarial);
+    ContenderResult {
+        contender,
+        build_time,
+        estimated_bytes,
+        hits_per_sec,
+        misses_per_sec,
+        adversarial_per_sec,
+        found,
+    }
+}
+
+// The bytes of the words themselves.
+fn string_bytes<'a>(words: impl Iterator<Item = &'a String>) -> usize {
+    words.map(|word| word.capacity()).sum()
+}
+
+fn hash_set_bytes(set: &HashSet<String>) -> usize {
+    // The table has a String and a control byte for each slot, full or not.
+    mem::size_of::<HashSet<String>>()
+        + set.capacity() * (mem::size_of::<String>() + 1)
+        + string_bytes(set.iter())
+}
+
+fn btree_set_bytes(set: &BTreeSet<String>) -> usize {
+    // Room for half again as many Strings as there are, for the third of each node that's empty.
+    mem::size_of::<BTreeSet<String>>()
+        + set.len() * 3 / 2 * mem::size_of::<String>()
+        + string_bytes(set.iter())
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use test::Bencher;
+
+    #[test]
+    fn all_agree_on_a_sample() {
+        let probes = ProbeSet::sampled(&Dataset::TestMediumSorted, 200).unwrap();
+        assert_eq!(200, probes.hits.len());
+        assert!(!probes.adversarial.is_empty());
+        let report = membership_shootout(&Dataset::TestMediumSorted, &probes).unwrap();
+        assert_eq!(Dataset::TestMediumSorted.word_count(), report.word_count);
+        assert_eq!(
+            vec![Contender::Trie, Contender::HashSet, Contender::BTreeSet],
+            report
+                .results
+                .iter()
+                .map(|result| result.contender)
+                .collect::<Vec<_>>()
+        );
+        for result in &report.results {
+            // Only the hits are found.
+            assert_eq!(200, result.found, "{}", result.contender);
+            assert!(result.estimated_bytes > 0);
+            assert!(result.hits_per_sec > 0.0 && result.adversarial_per_sec > 0.0);
+        }
+        let table = report.to_string();
+        assert_eq!(5, table.lines().count(), "{}", table);
+        assert!(table.starts_with("medium-sorted: 20,000 words; 200 hits, 200 misses and "));
+        assert!(table.contains("BTreeSet<String>"));
+        // One of each column is best, apart from ties.
+        assert!(table.matches('*').count() >= 5, "{}", table);
+    }
+
+    #[test]
+    #[ignore = "needs the large dataset files"]
+    fn verification_probes() {
+        let probes = ProbeSet::verification().unwrap();
+        let report = membership_shootout(&Dataset::TestLargeSorted, &probes).unwrap();
+        assert_eq!((1_000, 1_000, 1_000), report.probe_counts);
+        assert_eq!(1_000, report.result(Contender::Trie).unwrap().found);
+    }
+
+    // The size of the words themselves is a floor for a set that holds them.
+    #[test]
+    fn set_estimates_cover_the_words() {
+        let words: Vec<String> = ["cross", "crate", "an", "azure"]
+            .iter()
+            .map(|word| word.to_string())
+            .collect();
+        let floor = string_bytes(words.iter());
+        assert!(hash_set_bytes(&words.iter().cloned().collect()) > floor);
+        assert!(btree_set_bytes(&words.iter().cloned().collect()) > floor);
+    }
+
+    #[bench]
+    fn bench_membership_shootout_medium(b: &mut Bencher) {
+        let probes = ProbeSet::sampled(&Dataset::TestMediumSorted, 1_000).unwrap();
+        b.iter(|| membership_shootout(&Dataset::TestMediumSorted, &probes).unwrap());
+    }
+}
@@ -0,0 +1,217 @@
+// Golden-file tests that pin the output people read while debugging: the `{:#?}` rendering of `FixedNode` from
+// print_root_alt(), describe_tree() with and without its limits, the `Display` of SuffixReport, and the LoadStats
+// JSON. Each rendering is compared with a file under tests/snapshots, and both implementations have to give the
+// same output for the same words.
+//
+// When a change to one of these formats is intended, run the tests with UPDATE_SNAPSHOTS=1 to write the files from
+// the current output, then check the diff of tests/snapshots before committing it.
+
+use std::fs;
+use std::time::Duration;
+
+use crate::*;
+
+const SNAPSHOT_DIR: &str = "tests/snapshots";
+const UPDATE_ENV_VAR: &str = "UPDATE_SNAPSHOTS";
+
+// The lines shown on each side of the first difference when a snapshot doesn't match.
+const CONTEXT_LINES: usize = 3;
+
+fn snapshot_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(SNAPSHOT_DIR)
+        .join(name)
+}
+
+fn is_updating() -> bool {
+    env::var_os(UPDATE_ENV_VAR).is_some_and(|value| value == "1")
+}
+
+// Panic unless actual is the same as the snapshot file called name, or write the file if UPDATE_SNAPSHOTS=1.
+pub(crate) fn assert_snapshot(name: &str, actual: &str) {
+    let path = snapshot_path(name);
+    if is_updating() {
+        if fs::read_to_string(&path).ok().as_deref() != Some(actual) {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, actual).unwrap();
+        }
+        return;
+    }
+    let expected = match fs::read_to_string(&path) {
+        // A checkout that turned the line endings into CRLF still matches.
+        Ok(expected) => expected.replace("\r\n", "\n"),
+        Err(err) => panic!(
+            "can't read the snapshot {}: {}. Run the tests with {}=1 to create it.",
+            path.display(),
+            err,
+            UPDATE_ENV_VAR
+        ),
+    };
+    if expected != actual {
+        panic!(
+            "the output doesn't match the snapshot {}.\n{}\nRun the tests with {}=1 to update it if the change is \
+             intended.",
+            path.display(),
+            describe_difference(&expected, actual),
+            UPDATE_ENV_VAR
+        );
+    }
+}
+
+// The renderings from the two implementations have to agree before either is compared with the snapshot.
+fn assert_snapshot_for_both(name: &str, base: String, no_parent: String) {
+    assert_eq!(
+        base, no_parent,
+        "BaseLetterTrie and NoParentLetterTrie differ for the snapshot {}",
+        name
+    );
+    assert_snapshot(name, &base);
+}
+
+// The first line that differs with a few lines around it, "-" for the snapshot and "+" for the output.
+fn describe_difference(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let first = expected_lines
+        .iter()
+        .zip(&actual_lines)
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| expected_lines.len().min(actual_lines.len()));
+    let start = first.saturating_sub(CONTEXT_LINES);
+    let mut s = format!(
+        "The first difference is at line {} ({} lines in the snapshot, {} in the output):\n",
+        first + 1,
+        expected_lines.len(),
+        actual_lines.len()
+    );
+    for line in &expected_lines[start..first] {
+        s.push_str(&format!("  {}\n", line));
+    }
+    for line in expected_lines.iter().skip(first).take(CONTEXT_LINES) {
+        s.push_str(&format!("- {}\n", line));
+    }
+    for line in actual_lines.iter().skip(first).take(CONTEXT_LINES) {
+        s.push_str(&format!("+ {}\n", line));
+    }
+    if first == expected_lines.len().min(actual_lines.len()) && expected != actual {
+        s.push_str("(the lines match as far as they go, so the difference is in the length or a line ending)\n");
+    }
+    s
+}
+
+// The root and every node one and two letters down as print_root_alt() shows them.
+fn fixed_nodes<T: LetterTrie>(t: &T) -> String {
+    let mut s = format!("{:#?}\n", t.to_fixed_node());
+    for c in t.children("") {
+        let prefix = c.to_string();
+        s.push_str(&format!("{:#?}\n", t.find(&prefix).unwrap()));
+        for c in t.children(&prefix) {
+            let prefix = format!("{}{}", prefix, c);
+            s.push_str(&format!("{:#?}\n", t.find(&prefix).unwrap()));
+        }
+    }
+    s
+}
+
+fn render_fixed_nodes<T: LetterTrie>() -> String {
+    let t = T::from_dataset(&Dataset::TestSmallUnsorted, &LoadMethod::VecFill).unwrap();
+    fixed_nodes(&t)
+}
+
+fn render_tree_full<T: LetterTrie>() -> String {
+    let t = T::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous).unwrap();
+    t.describe_tree(None, None)
+}
+
+fn render_tree_truncated<T: LetterTrie>() -> String {
+    let t = T::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous).unwrap();
+    t.describe_tree(Some(3), Some(4))
+}
+
+fn render_suffix_report<T: LetterTrie>() -> String {
+    let t = T::from_dataset(&Dataset::TestMediumUnsorted, &LoadMethod::ReadVecFill).unwrap();
+    t.suffix_sharing_report().to_string()
+}
+
+// One line of JSON for each load method. The times and the resident memory change from run to run, so they're
+// replaced with fixed values, leaving the names of the steps, the counts and the shape of the object.
+fn render_load_stats(letter_trie_type: LetterTrieType) -> String {
+    let mut s = String::new();
+    for mut stats in load_stats::run_matrix(
+        &[Dataset::TestSmallSorted],
+        LoadMethod::all(),
+        &[letter_trie_type],
+    )
+    .unwrap()
+    {
+        for (index, (_, elapsed)) in stats.steps.iter_mut().enumerate() {
+            *elapsed = Duration::from_millis(index as u64 + 1);
+        }
+        for memory in stats.memory.iter_mut() {
+            memory.resident_bytes = memory.resident_bytes.map(|_| 4_096);
+        }
+        // The trie type is in the JSON, so it's the only field left to differ.
+        stats.trie_type = "_".to_owned();
+        s.push_str(&stats.to_json());
+        s.push('\n');
+    }
+    s
+}
+
+#[test]
+fn fixed_node_debug_small() {
+    assert_snapshot_for_both(
+        "fixed_node_debug_small.txt",
+        render_fixed_nodes::<BaseLetterTrie>(),
+        render_fixed_nodes::<NoParentLetterTrie>(),
+    );
+}
+
+#[test]
+fn describe_tree_small() {
+    assert_snapshot_for_both(
+        "describe_tree_small.txt",
+        render_tree_full::<BaseLetterTrie>(),
+        render_tree_full::<NoParentLetterTrie>(),
+    );
+}
+
+#[test]
+fn describe_tree_truncated_medium() {
+    assert_snapshot_for_both(
+        "describe_tree_truncated_medium.txt",
+        render_tree_truncated::<BaseLetterTrie>(),
+        render_tree_truncated::<NoParentLetterTrie>(),
+    );
+}
+
+#[test]
+fn suffix_report_medium() {
+    assert_snapshot_for_both(
+        "suffix_report_medium.txt",
+        render_suffix_report::<BaseLetterTrie>(),
+        render_suffix_report::<NoParentLetterTrie>(),
+    );
+}
+
+#[test]
+fn load_stats_json_small() {
+    assert_snapshot_for_both(
+        "load_stats_small.jsonl",
+        render_load_stats(LetterTrieType::Base),
+        render_load_stats(LetterTrieType::NoParent),
+    );
+}
+
+#[test]
+fn difference_is_described() {
+    let s = describe_difference("a\nb\nc\nd\n", "a\nb\nx\nd\n");
+    assert!(s.starts_with(
+        "The first difference is at line 3 (4 lines in the snapshot, 4 in the output)"
+    ));
+    assert!(s.contains("  b\n- c\n- d\n+ x\n+ d\n"));
+    let s = describe_difference("a\nb\n", "a\nb\nc\n");
+    assert!(s.contains("at line 3"));
+    assert!(s.contains("+ c\n"));
+    assert!(describe_difference("a\n", "a").contains("a line ending"));
+}
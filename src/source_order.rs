@@ -0,0 +1,202 @@
+//! Loading a word list in the order it was published in, behind `LetterTrie::from_reader_in_order()`.
+//!
+//! Many word lists, such as those from a frequency corpus, have the most common word first rather than being in
+//! alphabetical order. Loading one of those with `is_sorted` false works, but some load methods then sort the whole
+//! list for nothing, and the order itself, which says how common each word is, is thrown away.
+//! `SourceOrder::FrequencyRanked` inserts the words one at a time as they're read, never sorting them, and can keep
+//! each word's place in the list as its rank for `LetterTrie::rank_of()` and `ScoredQuery::suggest_ranked()`.
+
+use std::io::BufRead;
+
+use crate::*;
+
+/// The order of the words in a source given to `LetterTrie::from_reader_in_order()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceOrder {
+    /// The words are in alphabetical order, as for `is_sorted` true in `LetterTrie::from_reader()`.
+    Alphabetical,
+    /// The words are in no useful order, as for `is_sorted` false in `LetterTrie::from_reader()`.
+    Unsorted,
+    /// The words are in order of how common they are, most common first. They're inserted as they're read
+    /// whatever the load method, so nothing is sorted. If `record_ranks` is set each word is given its place in
+    /// the list as its rank with `LetterTrie::set_rank()`, counting the lines that aren't blank from 1. A word on
+    /// more than one line keeps the rank of the first, and the later lines still use up a rank.
+    FrequencyRanked {
+        /// Whether to give each word its rank.
+        record_ranks: bool,
+    },
+}
+
+pub(crate) fn from_reader_in_order<T: LetterTrie, R: BufRead>(
+    reader: R,
+    order: &SourceOrder,
+    load_method: &LoadMethod,
+    opt: &DisplayDetailOptions,
+) -> Result<T> {
+    let record_ranks = match *order {
+        SourceOrder::Alphabetical => {
+            return T::from_reader_test(reader, true, load_method, opt, None)
+        }
+        SourceOrder::Unsorted => return T::from_reader_test(reader, false, load_method, opt, None),
+        SourceOrder::FrequencyRanked { record_ranks } => record_ranks,
+    };
+    match opt.duplicate_policy {
+        DuplicatePolicy::Ignore => load_ranked(reader, record_ranks, opt),
+        policy => duplicates::load_checking_duplicates(reader, policy, opt, |reader| {
+            load_ranked(reader, record_ranks, opt)
+        }),
+    }
+}
+
+fn load_ranked<T: LetterTrie, R: BufRead>(
+    reader: R,
+    record_ranks: bool,
+    opt: &DisplayDetailOptions,
+) -> Result<T> {
+    let overall = Timer::overall(opt);
+    let timer = Timer::new(LABEL_STEP_INSERT_IN_ORDER, opt);
+    let mut t = T::from_words(Vec::<String>::new());
+    let mut rank: u32 = 0;
+    for_each_trimmed_line(reader, |word| {
+        rank = rank.saturating_add(1);
+        if record_ranks {
+            if !t.contains(word) {
+                t.insert(word);
+                t.set_rank(word, rank);
+            }
+        } else {
+            t.insert(word);
+        }
+    })?;
+    timer.stop();
+    overall.stop();
+    Ok(t)
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn fixture() -> BufReader<File> {
+        BufReader::new(
+            File::open(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/fixtures/source_order/ranked.txt"),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn check_frequency_ranked<T: LetterTrie>() {
+        for load_method in &ALL_LOAD_METHODS {
+            let opt = DisplayDetailOptions::make_no_display();
+            let t: T = from_reader_in_order(
+                fixture(),
+                &SourceOrder::FrequencyRanked { record_ranks: true },
+                load_method,
+                &opt,
+            )
+            .unwrap();
+            t.verify_integrity().unwrap();
+            assert_eq!(
+                vec![
+                    "a", "and", "in", "is", "of", "that", "the", "to", "tone", "too", "tool",
+                    "toward"
+                ],
+                t.words_with_prefix(""),
+                "{}",
+                load_method
+            );
+            let labels: Vec<String> = opt.step_times().into_iter().map(|(step, _)| step).collect();
+            assert_eq!(
+                vec![LABEL_STEP_INSERT_IN_ORDER, LABEL_STEP_OVERALL],
+                labels,
+                "{}",
+                load_method
+            );
+
+            // "The" on the ninth line is the same word as "the" on the first, and the blank line doesn't count.
+            assert_eq!(Some(1), t.rank_of("THE"));
+            assert_eq!(Some(4), t.rank_of("to"));
+            assert_eq!(Some(7), t.rank_of(" is "));
+            assert_eq!(Some(10), t.rank_of("toward"));
+            assert_eq!(Some(13), t.rank_of("too"));
+            assert_eq!(None, t.rank_of("t"));
+            assert_eq!(None, t.rank_of("cross"));
+
+            // suggest() goes shortest first, while suggest_ranked() goes by rank.
+            assert_eq!(vec!["to", "too", "tone"], t.suggest("to", 3));
+            assert_eq!(
+                vec![
+                    ("to".to_owned(), 4),
+                    ("toward".to_owned(), 10),
+                    ("tone".to_owned(), 11)
+                ],
+                t.suggest_ranked("to", 3)
+            );
+            assert_eq!(
+                vec![("the".to_owned(), 1), ("to".to_owned(), 4)],
+                t.suggest_ranked("t", 2)
+            );
+        }
+    }
+
+    #[test]
+    fn frequency_ranked_base() {
+        check_frequency_ranked::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn frequency_ranked_no_parent() {
+        check_frequency_ranked::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn ranks_are_optional_and_follow_the_words() {
+        let mut t = BaseLetterTrie::from_reader_in_order(
+            fixture(),
+            &SourceOrder::FrequencyRanked {
+                record_ranks: false,
+            },
+            &LoadMethod::ContinuousParallel,
+        )
+        .unwrap();
+        assert_eq!(12, t.count_with_prefix(""));
+        assert_eq!(None, t.rank_of("the"));
+        assert!(t.suggest_ranked("t", 3).is_empty());
+
+        assert!(t.set_rank("tool", 2));
+        assert!(!t.set_rank("cross", 1));
+        assert_eq!(vec![("tool".to_owned(), 2)], t.suggest_ranked("", 3));
+        assert!(t.remove("tool"));
+        t.insert("tool");
+        assert_eq!(None, t.rank_of("tool"));
+
+        let mut t = NoParentLetterTrie::from_reader_in_order(
+            fixture(),
+            &SourceOrder::FrequencyRanked { record_ranks: true },
+            &LoadMethod::Continuous,
+        )
+        .unwrap();
+        let words: Vec<Vec<char>> = ["of", "the"]
+            .iter()
+            .map(|word| word.chars().collect())
+            .collect();
+        assert_eq!(2, t.remove_sorted_words(&words).removed);
+        assert_eq!(None, t.rank_of("the"));
+        assert_eq!(
+            vec![("and".to_owned(), 3), ("to".to_owned(), 4)],
+            t.suggest_ranked("", 2)
+        );
+
+        // The other orders load the same words as from_reader() with no ranks.
+        let t = NoParentLetterTrie::from_reader_in_order(
+            fixture(),
+            &SourceOrder::Unsorted,
+            &LoadMethod::VecFill,
+        )
+        .unwrap();
+        assert_eq!(12, t.count_with_prefix(""));
+        assert_eq!(None, t.rank_of("the"));
+    }
+}
@@ -0,0 +1,208 @@
+//! A read-only trie over a table of nodes that can be a `static`, usually one written by
+//! `codegen::generate_static_trie()` from a build script so that the dictionary is compiled into the program and
+//! there's nothing to load at startup.
+
+use crate::*;
+
+/// One node of a `StaticLetterTrie`. The children of each node are next to each other in the table and in
+/// character order, so a node only needs the index of its first child and the number of children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedNode {
+    pub(crate) c: char,
+    pub(crate) is_word: bool,
+    pub(crate) first_child: u32,
+    pub(crate) child_count: u32,
+}
+
+impl PackedNode {
+    /// Create a node. This is meant for generated code, which gets the indexes right.
+    pub const fn new(c: char, is_word: bool, first_child: u32, child_count: u32) -> Self {
+        Self {
+            c,
+            is_word,
+            first_child,
+            child_count,
+        }
+    }
+
+    fn children(&self) -> core::ops::Range<usize> {
+        let first = self.first_child as usize;
+        first..first + self.child_count as usize
+    }
+}
+
+/// A trie that only answers queries, over a table of nodes with the root first. It has the same query methods as
+/// `LetterTrie` and gives the same answers as a trie built from the same words.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::static_trie::{PackedNode, StaticLetterTrie};
+///
+/// // What codegen::generate_static_trie() writes for "an" and "at".
+/// static NODES: [PackedNode; 4] = [
+///     PackedNode::new(' ', false, 1, 1),
+///     PackedNode::new('a', false, 2, 2),
+///     PackedNode::new('n', true, 4, 0),
+///     PackedNode::new('t', true, 4, 0),
+/// ];
+/// static TRIE: StaticLetterTrie = StaticLetterTrie::new(&NODES);
+///
+/// assert!(TRIE.contains("At"));
+/// assert!(!TRIE.contains("a"));
+/// assert_eq!(vec!["an", "at"], TRIE.words_with_prefix("a"));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct StaticLetterTrie {
+    nodes: &'static [PackedNode],
+}
+
+impl StaticLetterTrie {
+    /// Wrap a table of nodes. The table must have at least the root node.
+    pub const fn new(nodes: &'static [PackedNode]) -> Self {
+        Self { nodes }
+    }
+
+    // The index of the node for word, without allocating. Lowercasing one character at a time isn't quite the
+    // same as str::to_lowercase(), which is what the words were stored with, for a final sigma, so only ASCII
+    // takes the shortcut.
+    fn find_index(&self, word: &str) -> Option<usize> {
+        let word = word.trim();
+        if word.is_ascii() {
+            self.find_index_chars(word.chars().map(|c| c.to_ascii_lowercase()))
+        } else {
+            self.find_index_chars(word.to_lowercase().chars())
+        }
+    }
+
+    fn find_index_chars<I: Iterator<Item = char>>(&self, chars: I) -> Option<usize> {
+        let mut index = 0;
+        for c in chars {
+            let children = self.nodes[index].children();
+            let start = children.start;
+            index = start
+                + self.nodes[children]
+                    .binary_search_by_key(&c, |node| node.c)
+                    .ok()?;
+        }
+        Some(index)
+    }
+
+    /// Returns true if `word` is one of the words in the trie. This doesn't allocate unless `word` has
+    /// characters outside of ASCII.
+    pub fn contains(&self, word: &str) -> bool {
+        self.find_index(word)
+            .map(|index| self.nodes[index].is_word)
+            .unwrap_or(false)
+    }
+
+    /// Find the node for `prefix` as in `LetterTrie::find()`, including being None for an empty prefix.
+    pub fn find(&self, prefix: &str) -> Option<FixedNode> {
+        let prefix = prefix.trim();
+        if prefix.is_empty() {
+            return None;
+        }
+        let prefix = prefix.to_lowercase();
+        self.find_index_chars(prefix.chars())
+            .map(|index| self.fixed_node(index, prefix))
+    }
+
+    /// Describe the root node as in `LetterTrie::to_fixed_node()`.
+    pub fn to_fixed_node(&self) -> FixedNode {
+        self.fixed_node(0, String::new())
+    }
+
+    fn fixed_node(&self, index: usize, mut prefix: String) -> FixedNode {
+        let depth = prefix.chars().count();
+        let mut node_count = 0;
+        let mut word_count = 0;
+        let mut max_depth = 0;
+        self.visit_from(index, &mut prefix, depth, &mut |node| {
+            node_count += 1;
+            if node.is_word {
+                word_count += 1;
+            }
+            max_depth = max_depth.max(node.depth);
+            true
+        });
+        let node = &self.nodes[index];
+        FixedNode {
+            c: node.c,
+            prefix,
+            depth,
+            is_word: node.is_word,
+            child_count: node.child_count as usize,
+            node_count,
+            word_count,
+            height: max_depth - depth + 1,
+        }
+    }
+
+    /// Walk the subtree for `prefix` as in `LetterTrie::visit()`.
+    pub fn visit(&self, prefix: &str, f: &mut dyn FnMut(&NodeView) -> bool) {
+        let mut prefix = prefix.trim().to_lowercase();
+        if let Some(index) = self.find_index_chars(prefix.chars()) {
+            let depth = prefix.chars().count();
+            self.visit_from(index, &mut prefix, depth, f);
+        }
+    }
+
+    // Walk the subtree of the node at index, whose prefix and depth are given. The prefix is put back the way it
+    // was at the end.
+    fn visit_from(
+        &self,
+        index: usize,
+        prefix: &mut String,
+        depth: usize,
+        f: &mut dyn FnMut(&NodeView) -> bool,
+    ) {
+        let start_len = prefix.len();
+        // Each entry is a node to visit, its depth, and the length of its parent's prefix or None for the first
+        // node.
+        let mut stack: Vec<(usize, usize, Option<usize>)> = vec![(index, depth, None)];
+        while let Some((index, depth, parent_len)) = stack.pop() {
+            let node = &self.nodes[index];
+            if let Some(len) = parent_len {
+                prefix.truncate(len);
+                prefix.push(node.c);
+            }
+            let node_view = NodeView {
+                c: node.c,
+                prefix,
+                depth,
+                is_word: node.is_word,
+                child_count: node.child_count as usize,
+                spelling: None,
+                score: None,
+                max_score: None,
+            };
+            if f(&node_view) {
+                let len = prefix.len();
+                stack.extend(
+                    node.children()
+                        .rev()
+                        .map(|child| (child, depth + 1, Some(len))),
+                );
+            }
+        }
+        prefix.truncate(start_len);
+    }
+
+    /// Get all of the words that start with `prefix` in alphabetical order, as in
+    /// `LetterTrie::words_with_prefix()`.
+    pub fn words_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let mut words = vec![];
+        self.visit(prefix, &mut |node| {
+            if node.is_word {
+                words.push(node.prefix.to_owned());
+            }
+            true
+        });
+        words
+    }
+
+    /// The number of nodes in the table, including the root.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
@@ -0,0 +1,277 @@
+//! Building a `BaseLetterTrie` from words that arrive a few at a time, such as from a network stream, with the same
+//! split by first letter that `LoadMethod::ContinuousParallel` uses for a file.
+//!
+//! Words sent through a `WordSender` go to a routing thread that hands each one to a worker thread for its first
+//! letter. Each worker builds its own trie, and once every sender has been dropped the routing thread merges them.
+//! All of the channels are bounded, so if the workers fall behind the senders wait rather than the stream piling up
+//! in memory.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use crate::*;
+
+// The number of words that can be waiting between the senders and the routing thread.
+const STREAM_CHANNEL_WORDS: usize = 4_096;
+// The number of words that can be waiting for each worker.
+const STREAM_WORKER_WORDS: usize = 1_024;
+
+// What the handle from BaseLetterTrie::loader_channel_with_limits() gives.
+type LimitedLoad = Result<(BaseLetterTrie, LoadReport)>;
+
+/// The sending half of `BaseLetterTrie::loader_channel()`. It can be cloned to send from several threads, and the
+/// load finishes once every clone has been dropped.
+#[derive(Clone, Debug)]
+pub struct WordSender {
+    tx: SyncSender<String>,
+}
+
+impl WordSender {
+    /// Send one word to the loader. This blocks while the loader is too far behind. The word is trimmed and
+    /// lowercased as with `LetterTrie::insert()`, and a blank word is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::Io` if the loader has stopped, either because one of its threads panicked or
+    /// because a word went over a limit whose policy is `LimitPolicy::Abort`. Joining the loader's handle gives
+    /// the reason.
+    pub fn send(&self, word: &str) -> Result<()> {
+        self.tx
+            .send(word.to_owned())
+            .map_err(|_| loader_stopped("the loader is no longer receiving words"))
+    }
+}
+
+impl BaseLetterTrie {
+    /// Start a load that takes words from any number of threads through the returned `WordSender`. Drop every
+    /// sender to finish, then join the handle to get the trie.
+    ///
+    /// # Errors
+    ///
+    /// The handle gives `LetterTrieError::Io` if one of the worker threads panicked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let (sender, handle) = BaseLetterTrie::loader_channel();
+    /// let other_sender = sender.clone();
+    /// let producer = std::thread::spawn(move || {
+    ///     for word in &["crease", "cross"] {
+    ///         other_sender.send(word).unwrap();
+    ///     }
+    /// });
+    /// for word in &["an", "azure"] {
+    ///     sender.send(word).unwrap();
+    /// }
+    /// drop(sender);
+    /// producer.join().unwrap();
+    ///
+    /// let trie = handle.join().unwrap().unwrap();
+    /// assert_eq!(vec!["an", "azure", "crease", "cross"], trie.words_with_prefix(""));
+    /// ```
+    pub fn loader_channel() -> (WordSender, JoinHandle<Result<Self>>) {
+        let (tx, rx) = mpsc::sync_channel(STREAM_CHANNEL_WORDS);
+        let handle = thread::spawn(move || route_words(rx, &TrieOptions::new()).map(|(t, _)| t));
+        (WordSender { tx }, handle)
+    }
+
+    /// Start a load as `loader_channel()` does, enforcing `TrieOptions::max_word_len` and
+    /// `TrieOptions::max_words` from the options. The handle gives the trie along with the counts of the words
+    /// added and skipped. The other options don't apply to a `BaseLetterTrie` and are ignored.
+    ///
+    /// # Errors
+    ///
+    /// The handle gives `LetterTrieError::Io` if one of the worker threads panicked, and
+    /// `LetterTrieError::LimitExceeded` if a word went over a limit whose policy is `LimitPolicy::Abort`, in which
+    /// case the load stops there.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let options = TrieOptions::new().with_max_word_len(5, LimitPolicy::Skip);
+    /// let (sender, handle) = BaseLetterTrie::loader_channel_with_limits(&options);
+    /// for word in &["an", "creature", "cross"] {
+    ///     sender.send(word).unwrap();
+    /// }
+    /// drop(sender);
+    ///
+    /// let (trie, report) = handle.join().unwrap().unwrap();
+    /// assert_eq!(vec!["an", "cross"], trie.words_with_prefix(""));
+    /// assert_eq!(2, report.words_added);
+    /// assert_eq!(1, report.skipped_too_long);
+    /// ```
+    pub fn loader_channel_with_limits(
+        options: &TrieOptions,
+    ) -> (WordSender, JoinHandle<LimitedLoad>) {
+        let (tx, rx) = mpsc::sync_channel(STREAM_CHANNEL_WORDS);
+        let options = options.clone();
+        let handle = thread::spawn(move || route_words(rx, &options));
+        (WordSender { tx }, handle)
+    }
+}
+
+// Hand each word that's within the limits to the worker for its first letter, starting workers as new letters show
+// up, and merge their tries once the senders are gone.
+fn route_words(rx: Receiver<String>, options: &TrieOptions) -> LimitedLoad {
+    let mut workers: BTreeMap<char, (SyncSender<String>, JoinHandle<BaseLetterTrie>)> =
+        BTreeMap::new();
+    let mut report = LoadReport::default();
+    let mut limit_error = None;
+    // The position of each word among those sent, counting from 1, for a word over a limit.
+    let mut position = 0;
+    for word in rx {
+        // Route on the first character of the lowercased word, which is the child of the root it will go under.
+        let trimmed = word.trim();
+        let c = match trimmed.chars().next().and_then(|c| c.to_lowercase().next()) {
+            Some(c) => c,
+            None => continue,
+        };
+        position += 1;
+        match options.check_limits(trimmed, position, &mut report) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                // Leaving the loop drops the receiver, so every later send fails.
+                limit_error = Some(err);
+                break;
+            }
+        }
+        let (worker_tx, _) = workers.entry(c).or_insert_with(start_worker);
+        if worker_tx.send(word).is_err() {
+            // The worker has panicked, which joining it below reports.
+            break;
+        }
+    }
+
+    let mut t = BaseLetterTrie::new();
+    let mut panicked = false;
+    for (_, (worker_tx, worker)) in workers {
+        drop(worker_tx);
+        match worker.join() {
+            Ok(part) => t.merge(part),
+            Err(_) => panicked = true,
+        }
+    }
+    if panicked {
+        return Err(worker_panicked());
+    }
+    match limit_error {
+        Some(err) => Err(err),
+        None => Ok((t, report)),
+    }
+}
+
+fn start_worker() -> (SyncSender<String>, JoinHandle<BaseLetterTrie>) {
+    let (tx, rx) = mpsc::sync_channel::<String>(STREAM_WORKER_WORDS);
+    let worker = thread::spawn(move || {
+        let mut t = BaseLetterTrie::new();
+        for word in rx {
+            t.insert(&word);
+        }
+        t
+    });
+    (tx, worker)
+}
+
+fn loader_stopped(msg: &str) -> LetterTrieError {
+    io::Error::new(io::ErrorKind::BrokenPipe, msg).into()
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn medium_from_two_producers() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let words = words_from_file(dataset.filename()).unwrap();
+        let (sender, handle) = BaseLetterTrie::loader_channel();
+        let producers: Vec<_> = words
+            .chunks(words.len() / 2 + 1)
+            .map(|chunk| {
+                let chunk = chunk.to_vec();
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for word in &chunk {
+                        sender.send(word).unwrap();
+                    }
+                })
+            })
+            .collect();
+        assert_eq!(2, producers.len());
+        drop(sender);
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        let t = handle.join().unwrap().unwrap();
+        t.verify_integrity().unwrap();
+
+        let expected = BaseLetterTrie::from_dataset(&dataset, &LoadMethod::Continuous).unwrap();
+        assert_eq!(expected.fingerprint(), t.fingerprint());
+        assert_eq!(expected.to_fixed_node(), t.to_fixed_node());
+    }
+
+    #[test]
+    fn blank_and_empty() {
+        let (sender, handle) = BaseLetterTrie::loader_channel();
+        drop(sender);
+        let t = handle.join().unwrap().unwrap();
+        assert_eq!(1, t.to_fixed_node().node_count);
+
+        let (sender, handle) = BaseLetterTrie::loader_channel();
+        for word in &["", "   ", " Ünïcödé ", "ünïty", "İstanbul", "ice"] {
+            sender.send(word).unwrap();
+        }
+        drop(sender);
+        let t = handle.join().unwrap().unwrap();
+        t.verify_integrity().unwrap();
+        assert_eq!(
+            BaseLetterTrie::from_words(vec!["Ünïcödé", "ünïty", "İstanbul", "ice"]).fingerprint(),
+            t.fingerprint()
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let options = TrieOptions::new()
+            .with_max_word_len(6, LimitPolicy::Skip)
+            .with_max_words(3, LimitPolicy::Skip);
+        let (sender, handle) = BaseLetterTrie::loader_channel_with_limits(&options);
+        let huge = "a".repeat(1_000_000);
+        for word in &["cross", huge.as_str(), " ", "an", "azure", "crease"] {
+            sender.send(word).unwrap();
+        }
+        drop(sender);
+        let (t, report) = handle.join().unwrap().unwrap();
+        assert_eq!(vec!["an", "azure", "cross"], t.words_with_prefix(""));
+        assert_eq!(
+            LoadReport {
+                words_added: 3,
+                skipped_too_long: 1,
+                skipped_over_max_words: 1,
+                duplicates: 0,
+                invalid_utf8_lines: 0,
+                skipped_unparsed: 0,
+            },
+            report
+        );
+
+        let options = TrieOptions::new().with_max_word_len(6, LimitPolicy::Abort);
+        let (sender, handle) = BaseLetterTrie::loader_channel_with_limits(&options);
+        sender.send("cross").unwrap();
+        sender.send(&huge).unwrap();
+        // The loader stops at the long word, after which sending fails.
+        while sender.send("an").is_ok() {}
+        drop(sender);
+        assert!(matches!(
+            handle.join().unwrap(),
+            Err(LetterTrieError::LimitExceeded { .. })
+        ));
+    }
+}
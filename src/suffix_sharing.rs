@@ -0,0 +1,332 @@
+//! The analysis behind `LetterTrie::suffix_sharing_report()`, which finds out how much smaller a trie would be if
+//! every set of identical subtrees were merged into one, as in a DAWG, without changing the trie.
+//!
+//! The trie is walked once with `visit()`. As the walk leaves each node, the node's letter, whether it ends a
+//! word, and the classes of its children make up its signature, and a node whose signature has been seen before
+//! joins that signature's class. Since a node's children already have their classes by then, two nodes are in the
+//! same class exactly when the subtrees under them are the same, so the number of classes is the number of nodes
+//! left after merging. Signatures are looked up by a 64-bit hash and then compared in full, so a hash collision
+//! can't merge two different subtrees.
+
+use crate::*;
+
+// The number of shared subtrees kept in SuffixReport::most_common.
+const MOST_COMMON_LEN: usize = 10;
+
+// The number of a subtree's endings shown for each entry in the Display output.
+const ENDINGS_SHOWN: usize = 6;
+
+/// The statistics from `LetterTrie::suffix_sharing_report()`.
+///
+/// Subtrees are the same only if their top nodes have the same letter, because that's where a letter is kept in
+/// this crate's tries. A DAWG that keeps letters on its edges could merge a little more.
+///
+/// The `Display` output gives the node counts followed by one line for each of the most common shared subtrees.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = BaseLetterTrie::from_words(vec!["cross", "crossed", "crossing", "toss", "tossed", "tossing"]);
+/// let report = trie.suffix_sharing_report();
+/// // "oss" with everything below it is the same under "cr" and "t".
+/// assert_eq!(20, report.node_count);
+/// assert_eq!(12, report.merged_node_count);
+/// assert_eq!(8, report.nodes_saved());
+/// assert_eq!(vec!["oss", "ossed", "ossing"], report.most_common[0].endings);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SuffixReport {
+    /// The number of nodes in the trie, counting the root.
+    pub node_count: usize,
+    /// The number of nodes there would be after merging every set of identical subtrees into one.
+    pub merged_node_count: usize,
+    /// The number of distinct subtrees that occur more than once.
+    pub shared_subtrees: usize,
+    /// The subtrees that occur most often, most common first. Those that occur equally often are in order of
+    /// their size from largest to smallest, then in the order they're first met in the trie.
+    pub most_common: Vec<SharedSubtree>,
+}
+
+impl SuffixReport {
+    /// The number of nodes that merging identical subtrees would remove.
+    pub fn nodes_saved(&self) -> usize {
+        self.node_count - self.merged_node_count
+    }
+}
+
+/// A subtree that occurs more than once in a trie, from `SuffixReport::most_common`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SharedSubtree {
+    /// The word endings the subtree stands for in alphabetical order, each starting with the subtree's top letter.
+    /// For instance the subtree under "cr" in a trie with "cross" and "crossed" has the endings "oss" and "ossed".
+    pub endings: Vec<String>,
+    /// The number of places in the trie where the subtree occurs.
+    pub occurrences: usize,
+    /// The number of nodes in one copy of the subtree.
+    pub node_count: usize,
+}
+
+impl fmt::Display for SuffixReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = if self.node_count == 0 {
+            0.0
+        } else {
+            self.nodes_saved() as f64 * 100.0 / self.node_count as f64
+        };
+        writeln!(
+            f,
+            "{} nodes, {} after merging identical subtrees ({:.1}% fewer)",
+            self.node_count, self.merged_node_count, percent
+        )?;
+        writeln!(f, "{} subtrees occur more than once", self.shared_subtrees)?;
+        for subtree in &self.most_common {
+            write!(
+                f,
+                "{:>8} x {} node{}: ",
+                subtree.occurrences,
+                subtree.node_count,
+                if subtree.node_count == 1 { "" } else { "s" }
+            )?;
+            for (i, ending) in subtree.endings.iter().take(ENDINGS_SHOWN).enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", ending)?;
+            }
+            if subtree.endings.len() > ENDINGS_SHOWN {
+                write!(f, ", and {} more", subtree.endings.len() - ENDINGS_SHOWN)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// One set of identical subtrees.
+struct Class {
+    c: char,
+    is_word: bool,
+    children: Vec<usize>,
+    occurrences: usize,
+    node_count: usize,
+    // The prefix of the first node in the class, to find its endings with.
+    prefix: String,
+}
+
+// A node on the path from the root whose children are still being classified.
+struct Frame {
+    c: char,
+    is_word: bool,
+    children: Vec<usize>,
+    node_count: usize,
+}
+
+fn signature_hash(c: char, is_word: bool, children: &[usize]) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut mix = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+    mix(&(c as u32).to_le_bytes());
+    mix(&[is_word as u8]);
+    for child in children {
+        mix(&(*child as u64).to_le_bytes());
+    }
+    hash
+}
+
+pub(crate) fn report<T: LetterTrie + ?Sized>(trie: &T) -> SuffixReport {
+    let mut classes: Vec<Class> = vec![];
+    // The classes with each signature hash.
+    let mut by_hash: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    let mut path: Vec<Frame> = vec![];
+    let mut leave = |path: &mut Vec<Frame>| {
+        let Some(frame) = path.pop() else {
+            return;
+        };
+        let candidates = by_hash
+            .entry(signature_hash(frame.c, frame.is_word, &frame.children))
+            .or_default();
+        let found = candidates.iter().copied().find(|index| {
+            let class = &classes[*index];
+            class.c == frame.c && class.is_word == frame.is_word && class.children == frame.children
+        });
+        let index = match found {
+            Some(index) => {
+                classes[index].occurrences += 1;
+                index
+            }
+            None => {
+                // The root's letter is a space that isn't part of any prefix.
+                let mut prefix: String = path.iter().skip(1).map(|frame| frame.c).collect();
+                if !path.is_empty() {
+                    prefix.push(frame.c);
+                }
+                candidates.push(classes.len());
+                classes.push(Class {
+                    c: frame.c,
+                    is_word: frame.is_word,
+                    children: frame.children,
+                    occurrences: 1,
+                    node_count: frame.node_count,
+                    prefix,
+                });
+                classes.len() - 1
+            }
+        };
+        if let Some(parent) = path.last_mut() {
+            parent.children.push(index);
+            parent.node_count += frame.node_count;
+        }
+    };
+    let mut node_count = 0;
+    trie.visit("", &mut |node| {
+        while path.len() > node.depth {
+            leave(&mut path);
+        }
+        node_count += 1;
+        path.push(Frame {
+            c: node.c,
+            is_word: node.is_word,
+            children: vec![],
+            node_count: 1,
+        });
+        true
+    });
+    while !path.is_empty() {
+        leave(&mut path);
+    }
+
+    let mut shared: Vec<&Class> = classes
+        .iter()
+        .filter(|class| class.occurrences > 1)
+        .collect();
+    let shared_subtrees = shared.len();
+    // The sort is stable so equal entries stay in the order they were first met.
+    shared.sort_by(|a, b| {
+        b.occurrences
+            .cmp(&a.occurrences)
+            .then(b.node_count.cmp(&a.node_count))
+    });
+    let most_common = shared
+        .into_iter()
+        .take(MOST_COMMON_LEN)
+        .map(|class| {
+            let cut = class.prefix.len() - class.c.len_utf8();
+            let mut endings = vec![];
+            trie.visit(&class.prefix, &mut |node| {
+                if node.is_word {
+                    endings.push(node.prefix[cut..].to_owned());
+                }
+                true
+            });
+            SharedSubtree {
+                endings,
+                occurrences: class.occurrences,
+                node_count: class.node_count,
+            }
+        })
+        .collect();
+    SuffixReport {
+        node_count,
+        merged_node_count: classes.len(),
+        shared_subtrees,
+        most_common,
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn shared(endings: &[&str], occurrences: usize, node_count: usize) -> SharedSubtree {
+        SharedSubtree {
+            endings: endings.iter().map(|ending| ending.to_string()).collect(),
+            occurrences,
+            node_count,
+        }
+    }
+
+    // The small dataset has 28 nodes. Four subtrees are repeated: the final "e" of "azure", "crease", "creative"
+    // and "creature", the "d" of "and" and "crossed", and the "re" and "ure" of "azure" and "creature". Merging
+    // them takes out 3 + 1 + 1 + 1 nodes.
+    fn check_small<T: LetterTrie>() {
+        let t = T::from_dataset(&Dataset::TestSmallSorted, &LoadMethod::Continuous).unwrap();
+        let report = t.suffix_sharing_report();
+        assert_eq!(
+            SuffixReport {
+                node_count: 28,
+                merged_node_count: 22,
+                shared_subtrees: 4,
+                most_common: vec![
+                    shared(&["e"], 4, 1),
+                    shared(&["ure"], 2, 3),
+                    shared(&["re"], 2, 2),
+                    shared(&["d"], 2, 1),
+                ],
+            },
+            report
+        );
+        assert_eq!(
+            "28 nodes, 22 after merging identical subtrees (21.4% fewer)\n\
+             4 subtrees occur more than once\n\
+             \x20      4 x 1 node: e\n\
+             \x20      2 x 3 nodes: ure\n\
+             \x20      2 x 2 nodes: re\n\
+             \x20      2 x 1 node: d\n",
+            report.to_string()
+        );
+    }
+
+    #[test]
+    fn small_base() {
+        check_small::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn small_no_parent() {
+        check_small::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn empty() {
+        let report = NoParentLetterTrie::from_words(Vec::<&str>::new()).suffix_sharing_report();
+        assert_eq!(1, report.node_count);
+        assert_eq!(1, report.merged_node_count);
+        assert!(report.most_common.is_empty());
+    }
+
+    #[test]
+    fn medium() {
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous)
+                .unwrap();
+        let report = t.suffix_sharing_report();
+        assert_eq!(t.to_fixed_node().node_count, report.node_count);
+        assert!(report.merged_node_count < report.node_count / 2);
+        assert_eq!(MOST_COMMON_LEN, report.most_common.len());
+        for pair in report.most_common.windows(2) {
+            assert!(pair[0].occurrences >= pair[1].occurrences);
+        }
+        // Merging takes out all but one node of each class.
+        let repeated: usize = report
+            .most_common
+            .iter()
+            .map(|subtree| subtree.occurrences - 1)
+            .sum();
+        assert!(repeated <= report.nodes_saved());
+
+        let t2 =
+            BaseLetterTrie::from_dataset(&Dataset::TestMediumUnsorted, &LoadMethod::Continuous)
+                .unwrap();
+        assert_eq!(report, t2.suffix_sharing_report());
+        assert_eq!(
+            report.most_common.len() + 2,
+            report.to_string().lines().count()
+        );
+    }
+}
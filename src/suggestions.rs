@@ -0,0 +1,329 @@
+//! Spelling suggestions for `LetterTrie::did_you_mean()`, which ranks the words near a misspelled input by more
+//! than how many edits away they are.
+//!
+//! The candidates are found with one walk down the trie that keeps a row of the edit distance table for each node
+//! on the path, as in a Levenshtein automaton. A branch is left as soon as every entry in its row is over
+//! `SuggestionConfig::max_distance`, since nothing below it can come back under, so only the part of the trie near
+//! the input is looked at. The distance counts swapping two letters next to each other as one edit, which is the
+//! [optimal string alignment](https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance) distance.
+//!
+//! Each candidate's score is worked out from these, with the weights from `SuggestionConfig`:
+//!
+//! - Its edits, each counted by its kind. Typing two letters in the wrong order is the most common typo, so
+//!   `transposition_weight` is the lowest weight and "hte" is closer to "the" than to "ate" even though both are
+//!   one edit away. Leaving a letter out comes next, as `insertion_weight`, then typing one too many, as
+//!   `deletion_weight`, and last typing the wrong letter, as `substitution_weight`. The edits counted are the ones
+//!   with the lowest total weight, which may not be the fewest.
+//! - The letters it has in common with the input at the start, as `prefix_weight` for each one. People rarely get
+//!   the first letters of a word wrong.
+//! - The difference between its length and the input's, as `length_weight` for each letter.
+//! - How often it's used, as `frequency_weight` times the natural log of one more than its tag. This takes a tag to
+//!   be a count, as `DuplicatePolicy::Count` leaves them. A word without a tag counts as never used.
+//!
+//! The score is the prefix and frequency parts less the edit and length parts, so a higher score is a better
+//! suggestion.
+
+use crate::*;
+
+/// A word offered by `LetterTrie::did_you_mean()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Suggestion {
+    /// The word as it should be shown, which is its spelling if it has one.
+    pub word: String,
+    /// The number of edits from the input to the word, counting a swap of two neighboring letters as one.
+    pub distance: usize,
+    /// The score the suggestions are ranked by, as described in the `suggestions` module. Higher is better.
+    pub score: f64,
+}
+
+/// The weights `LetterTrie::did_you_mean_with()` ranks suggestions by, as described in the `suggestions` module.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut trie = BaseLetterTrie::from_words(vec!["the", "ate", "then"]);
+/// assert_eq!("the", trie.did_you_mean("hte", 1)[0].word);
+///
+/// // With the frequency counted, a much more common word can win over one with a better spelling match.
+/// trie.insert_with_tag("ate", 1_000_000);
+/// let config = SuggestionConfig {
+///     frequency_weight: 0.2,
+///     ..Default::default()
+/// };
+/// assert_eq!("ate", trie.did_you_mean_with("hte", 1, &config)[0].word);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuggestionConfig {
+    /// The most edits a suggestion can be from the input. The default is 2.
+    pub max_distance: usize,
+    /// What each letter the input has in place of the word's letter takes off the score. The default is 1.0.
+    pub substitution_weight: f64,
+    /// What each letter missing from the input takes off the score. The default is 0.7.
+    pub insertion_weight: f64,
+    /// What each letter in the input that shouldn't be there takes off the score. The default is 0.8.
+    pub deletion_weight: f64,
+    /// What swapping two neighboring letters takes off the score. The default is 0.6.
+    pub transposition_weight: f64,
+    /// What each letter in common with the input at the start adds to the score. The default is 0.1.
+    pub prefix_weight: f64,
+    /// What each letter of difference in length from the input takes off the score. The default is 0.2.
+    pub length_weight: f64,
+    /// What the natural log of one more than the word's tag is multiplied by and added to the score. The default is
+    /// 0.05, which is only enough to choose between suggestions that are otherwise about as good.
+    pub frequency_weight: f64,
+}
+
+impl Default for SuggestionConfig {
+    fn default() -> Self {
+        Self {
+            max_distance: 2,
+            substitution_weight: 1.0,
+            insertion_weight: 0.7,
+            deletion_weight: 0.8,
+            transposition_weight: 0.6,
+            prefix_weight: 0.1,
+            length_weight: 0.2,
+            frequency_weight: 0.05,
+        }
+    }
+}
+
+pub(crate) fn did_you_mean<T: TrieQuery + ?Sized>(
+    trie: &T,
+    input: &str,
+    limit: usize,
+    config: &SuggestionConfig,
+) -> Vec<Suggestion> {
+    if limit == 0 {
+        return vec![];
+    }
+    let input: Vec<char> = input.trim().to_lowercase().chars().collect();
+    let mut suggestions: Vec<(Suggestion, String)> = vec![];
+    for (word, spelling, distance) in candidates(trie, &input, config.max_distance) {
+        let word_chars: Vec<char> = word.chars().collect();
+        let prefix_len = input
+            .iter()
+            .zip(&word_chars)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let len_diff = input.len().abs_diff(word_chars.len());
+        let frequency = trie.tag(&word).unwrap_or(0) as f64;
+        let score = config.prefix_weight * prefix_len as f64
+            + config.frequency_weight * frequency.ln_1p()
+            - weighted_distance(&input, &word_chars, config)
+            - config.length_weight * len_diff as f64;
+        let suggestion = Suggestion {
+            word: spelling,
+            distance,
+            score,
+        };
+        suggestions.push((suggestion, word));
+    }
+    // The lowercase word breaks ties, so the order doesn't depend on anything but the words and the weights.
+    suggestions.sort_by(|(a, a_word), (b, b_word)| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.distance.cmp(&b.distance))
+            .then_with(|| a_word.cmp(b_word))
+    });
+    suggestions.truncate(limit);
+    suggestions
+        .into_iter()
+        .map(|(suggestion, _)| suggestion)
+        .collect()
+}
+
+// The words within max_distance edits of input, each with its spelling and its distance, found with a walk that
+// keeps a row of the distance table for each node on the path.
+fn candidates<T: TrieQuery + ?Sized>(
+    trie: &T,
+    input: &[char],
+    max_distance: usize,
+) -> Vec<(String, String, usize)> {
+    let mut found = vec![];
+    // The row for the root is the distance from nothing to each prefix of the input.
+    let mut rows: Vec<Vec<usize>> = vec![(0..=input.len()).collect()];
+    let mut letters: Vec<char> = vec![];
+    trie.visit("", &mut |node| {
+        if node.depth == 0 {
+            return true;
+        }
+        rows.truncate(node.depth);
+        letters.truncate(node.depth - 1);
+        letters.push(node.c);
+        let row = next_row(&rows, &letters, input);
+        let distance = row[input.len()];
+        if node.is_word && distance <= max_distance {
+            found.push((node.prefix.to_owned(), node.word().to_owned(), distance));
+        }
+        let is_close = row.iter().any(|d| *d <= max_distance);
+        rows.push(row);
+        is_close
+    });
+    found
+}
+
+// The row of the distance table for the last of letters, from the rows for the letters before it.
+fn next_row(rows: &[Vec<usize>], letters: &[char], input: &[char]) -> Vec<usize> {
+    let i = letters.len();
+    let c = letters[i - 1];
+    let above = &rows[i - 1];
+    let mut row = Vec::with_capacity(input.len() + 1);
+    row.push(i);
+    for j in 1..=input.len() {
+        let cost = usize::from(input[j - 1] != c);
+        let mut d = (above[j] + 1).min(row[j - 1] + 1).min(above[j - 1] + cost);
+        if i > 1 && j > 1 && input[j - 1] == letters[i - 2] && input[j - 2] == c {
+            d = d.min(rows[i - 2][j - 2] + 1);
+        }
+        row.push(d);
+    }
+    row
+}
+
+// The optimal string alignment distance from input to word with each kind of edit weighted as in config.
+fn weighted_distance(input: &[char], word: &[char], config: &SuggestionConfig) -> f64 {
+    let width = word.len() + 1;
+    let mut table = vec![0.0; (input.len() + 1) * width];
+    for i in 0..=input.len() {
+        for j in 0..=word.len() {
+            table[i * width + j] = if i == 0 || j == 0 {
+                config.insertion_weight * j as f64 + config.deletion_weight * i as f64
+            } else {
+                let substitution = if input[i - 1] == word[j - 1] {
+                    0.0
+                } else {
+                    config.substitution_weight
+                };
+                let mut d = (table[(i - 1) * width + j] + config.deletion_weight)
+                    .min(table[i * width + j - 1] + config.insertion_weight)
+                    .min(table[(i - 1) * width + j - 1] + substitution);
+                if i > 1 && j > 1 && input[i - 1] == word[j - 2] && input[i - 2] == word[j - 1] {
+                    d = d.min(table[(i - 2) * width + j - 2] + config.transposition_weight);
+                }
+                d
+            };
+        }
+    }
+    table[input.len() * width + word.len()]
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    // Classic typos whose corrections are in the large dataset, each with the word it should be corrected to. Most
+    // of the dataset's words are made up, so a typo is only here if no made-up word is a closer match for it.
+    const TYPOS: [(&str, &str); 16] = [
+        ("hte", "the"),
+        ("teh", "the"),
+        ("recieve", "receive"),
+        ("beleive", "believe"),
+        ("seperate", "separate"),
+        ("becuase", "because"),
+        ("whcih", "which"),
+        ("freind", "friend"),
+        ("goverment", "government"),
+        ("adress", "address"),
+        ("begining", "beginning"),
+        ("tommorrow", "tomorrow"),
+        ("neccessary", "necessary"),
+        ("foriegn", "foreign"),
+        ("libary", "library"),
+        ("posession", "possession"),
+    ];
+
+    #[test]
+    #[ignore = "needs the large dataset files"]
+    fn classic_typos() {
+        let t =
+            NoParentLetterTrie::from_dataset(&Dataset::TestLargeSorted, &LoadMethod::Continuous)
+                .unwrap();
+        for (typo, expected) in TYPOS {
+            assert!(!t.contains(typo), "{}", typo);
+            let suggestions = t.did_you_mean(typo, 3);
+            assert_eq!(expected, suggestions[0].word, "{} {:?}", typo, suggestions);
+            assert!(suggestions
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score));
+        }
+    }
+
+    // The walk finds the same words as working out the distance to every word in the trie.
+    fn check_candidates<T: LetterTrie>() {
+        let t = T::from_dataset(&Dataset::TestMediumSorted, &LoadMethod::Continuous).unwrap();
+        let words: Vec<Vec<char>> = t.words().map(|word| word.chars().collect()).collect();
+        let config = SuggestionConfig {
+            substitution_weight: 1.0,
+            insertion_weight: 1.0,
+            deletion_weight: 1.0,
+            transposition_weight: 1.0,
+            ..Default::default()
+        };
+        for input in ["ab", "moral", "qqqq", "tsrange", ""] {
+            let input: Vec<char> = input.chars().collect();
+            for max_distance in 0..=2 {
+                let mut expected: Vec<(String, usize)> = words
+                    .iter()
+                    .filter_map(|word| {
+                        let distance = weighted_distance(&input, word, &config) as usize;
+                        (distance <= max_distance).then(|| (word.iter().collect(), distance))
+                    })
+                    .collect();
+                expected.sort();
+                let mut found: Vec<(String, usize)> = candidates(&t, &input, max_distance)
+                    .into_iter()
+                    .map(|(word, _, distance)| (word, distance))
+                    .collect();
+                found.sort();
+                assert_eq!(expected, found, "{:?} {}", input, max_distance);
+            }
+        }
+    }
+
+    #[test]
+    fn candidates_match_a_scan() {
+        check_candidates::<BaseLetterTrie>();
+        check_candidates::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn ties_go_by_distance_then_word() {
+        let t = BaseLetterTrie::from_words(vec!["cot", "cab", "car", "cut", "CAT"]);
+        let config = SuggestionConfig {
+            prefix_weight: 0.0,
+            length_weight: 0.0,
+            ..Default::default()
+        };
+        let words: Vec<String> = t
+            .did_you_mean_with(" Cat ", 10, &config)
+            .into_iter()
+            .map(|suggestion| suggestion.word)
+            .collect();
+        assert_eq!(vec!["cat", "cab", "car", "cot", "cut"], words);
+        assert!(t.did_you_mean("cat", 0).is_empty());
+        assert!(t.did_you_mean("xyzzy", 5).is_empty());
+    }
+
+    #[test]
+    fn spelling_and_frequency() {
+        let mut t = NoParentLetterTrie::from_words(Vec::<String>::new());
+        t.insert_with_spelling("Paris", "Paris", CasePreference::FirstSeen);
+        t.insert("parts");
+        let suggestions = t.did_you_mean("pari", 2);
+        assert_eq!("Paris", suggestions[0].word);
+        assert_eq!(1, suggestions[0].distance);
+
+        // A small count isn't enough to make up for a substitution with the default weights, but it is with more
+        // weight on how often a word is used.
+        t.insert_with_tag("pare", 10);
+        assert_eq!("Paris", t.did_you_mean("pari", 1)[0].word);
+        let config = SuggestionConfig {
+            frequency_weight: 0.5,
+            ..Default::default()
+        };
+        assert_eq!("pare", t.did_you_mean_with("pari", 1, &config)[0].word);
+    }
+}
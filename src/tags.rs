@@ -0,0 +1,228 @@
+//! Listing, loading and merging words along with their tags and sources.
+//!
+//! A tag is a `u64` kept with a word through `LetterTrie::insert_with_tag()`, and a source is the id of a word list
+//! it came from, added with `LetterTrie::insert_with_source()`. The tries keep these and answer `TrieQuery::tag()`
+//! and `TrieQuery::sources()`, and `TaggedLetterTrie` is built on those for any trie.
+
+use crate::*;
+
+/// The words of a trie along with their tags and sources, and tries built or merged from them.
+///
+/// This is implemented automatically for every `LetterTrie`.
+pub trait TaggedLetterTrie: LetterTrie {
+    /// Get every word in the trie in alphabetical order along with its tag, if it has one. As in
+    /// `TrieQuery::words()`, the words and tags are collected when this is called.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["cross", "an"]);
+    /// trie.insert_with_tag("crossed", 7);
+    /// let words: Vec<(String, Option<u64>)> = trie.words_with_tags().collect();
+    /// assert_eq!(
+    ///     vec![("an".to_owned(), None), ("cross".to_owned(), None), ("crossed".to_owned(), Some(7))],
+    ///     words
+    /// );
+    /// ```
+    fn words_with_tags(&self) -> WordsWithTags {
+        let mut words: Vec<(String, Option<u64>)> = vec![];
+        self.visit("", &mut |node| {
+            if node.is_word {
+                words.push((node.word().to_owned(), self.tag(node.prefix)));
+            }
+            true
+        });
+        WordsWithTags {
+            inner: words.into_iter(),
+        }
+    }
+
+    /// Get every word in the trie in alphabetical order along with its tag, its sources and its spelling. Each word
+    /// is as it's stored rather than in its spelling, and a word without sources has an empty list. Collecting these
+    /// and passing them to `from_sourced_words()` gives the same trie back, which with the `serde` feature is a way
+    /// to save a trie with its tags, sources and spellings as JSON. As in `TrieQuery::words()`, they're collected
+    /// when this is called.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = BaseLetterTrie::from_words(vec!["an"]);
+    /// trie.insert_with_source("cross", 2);
+    /// let words: Vec<SourcedWord> = trie.words_with_sources().collect();
+    /// assert!(words[0].sources.is_empty());
+    /// assert_eq!(("cross", vec![2]), (words[1].word.as_str(), words[1].sources.clone()));
+    /// ```
+    fn words_with_sources(&self) -> WordsWithSources {
+        let mut words: Vec<SourcedWord> = vec![];
+        self.visit("", &mut |node| {
+            if node.is_word {
+                words.push(SourcedWord {
+                    word: node.prefix.to_owned(),
+                    tag: self.tag(node.prefix),
+                    sources: self
+                        .sources(node.prefix)
+                        .map(<[u16]>::to_vec)
+                        .unwrap_or_default(),
+                    spelling: node.spelling.map(str::to_owned),
+                });
+            }
+            true
+        });
+        WordsWithSources {
+            inner: words.into_iter(),
+        }
+    }
+
+    /// Create a trie from words along with their tags and sources, such as from `words_with_sources()`.
+    fn from_sourced_words<I: IntoIterator<Item = SourcedWord>>(words: I) -> Self
+    where
+        Self: Sized,
+    {
+        let mut t = Self::from_words(Vec::<String>::new());
+        for word in words {
+            t.insert_sourced_word(&word);
+        }
+        t
+    }
+
+    /// Add a word along with its tag and spelling, if it has them, and each of its sources, keeping the sources
+    /// it already has. A spelling it already has is kept as under `CasePreference::FirstSeen`.
+    fn insert_sourced_word(&mut self, word: &SourcedWord) {
+        match word.tag {
+            Some(tag) => self.insert_with_tag(&word.word, tag),
+            None => self.insert(&word.word),
+        }
+        for source_id in &word.sources {
+            self.insert_with_source(&word.word, *source_id);
+        }
+        if let Some(spelling) = &word.spelling {
+            self.insert_with_spelling(&word.word, spelling, CasePreference::FirstSeen);
+        }
+    }
+
+    /// Create a trie from a word file as `LetterTrie::from_file()` does, recording `source_id` as the source of
+    /// every word.
+    ///
+    /// # Errors
+    ///
+    /// As for `LetterTrie::from_file()`.
+    #[cfg(feature = "fs")]
+    fn from_file_tagged(
+        filename: &str,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        source_id: u16,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut t = Self::from_file(filename, is_sorted, load_method)?;
+        let words: Vec<String> = t.words().collect();
+        for word in words {
+            t.insert_with_source(&word, source_id);
+        }
+        Ok(t)
+    }
+
+    /// Add every word of `other` with `source_id` as one of its sources, keeping the tags and sources it had in
+    /// `other` too. A word already in this trie keeps its own sources along with the new ones, and takes the tag
+    /// from `other` if it has one there.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::from_words(Vec::<String>::new());
+    /// trie.merge_tagged(NoParentLetterTrie::from_words(vec!["cross", "an"]), 0);
+    /// trie.merge_tagged(NoParentLetterTrie::from_words(vec!["crossed", "an"]), 1);
+    /// assert_eq!(Some(&[0, 1][..]), trie.sources("an"));
+    /// assert_eq!(Some(&[1][..]), trie.sources("crossed"));
+    /// ```
+    fn merge_tagged(&mut self, other: Self, source_id: u16)
+    where
+        Self: Sized,
+    {
+        for word in other.words_with_sources() {
+            self.insert_sourced_word(&word);
+            self.insert_with_source(&word.word, source_id);
+        }
+    }
+}
+
+impl<T: LetterTrie + ?Sized> TaggedLetterTrie for T {}
+
+/// An iterator over the words of a trie in alphabetical order along with their tags, returned by
+/// `TaggedLetterTrie::words_with_tags()`.
+#[derive(Debug)]
+pub struct WordsWithTags {
+    pub(crate) inner: alloc::vec::IntoIter<(String, Option<u64>)>,
+}
+
+impl Iterator for WordsWithTags {
+    type Item = (String, Option<u64>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for WordsWithTags {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for WordsWithTags {}
+
+/// A word along with its tag and sources, from `TaggedLetterTrie::words_with_sources()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourcedWord {
+    pub word: String,
+    pub tag: Option<u64>,
+    /// The sources in ascending order, or an empty list if the word has none.
+    pub sources: Vec<u16>,
+    /// The spelling from `LetterTrie::insert_preserving_case()`, if the word has one. It's left out of JSON
+    /// without one, and JSON from before spellings were kept reads as having none.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub spelling: Option<String>,
+}
+
+/// An iterator over the words of a trie in alphabetical order along with their tags and sources, returned by
+/// `TaggedLetterTrie::words_with_sources()`.
+#[derive(Debug)]
+pub struct WordsWithSources {
+    pub(crate) inner: alloc::vec::IntoIter<SourcedWord>,
+}
+
+impl Iterator for WordsWithSources {
+    type Item = SourcedWord;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for WordsWithSources {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for WordsWithSources {}
@@ -5,6 +5,9 @@ use regex::Regex;
 use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use crate::{in_file, Result};
 
 type PrefixEntry = BTreeMap<String, NextStep>;
 type PrefixMap = BTreeMap<String, PrefixEntry>;
@@ -31,7 +34,7 @@ struct NextStep {
 ///
 /// For a valid test the generated words should follow the patterns of the real words. For instance, going by the list of the
 /// 3,000 most common English words:
-///	- About 11.3% of words start with "s" while only 4.8% of words start with "b".
+/// - About 11.3% of words start with "s" while only 4.8% of words start with "b".
 /// - Given only that the last letter in a partially-formed word is "b", about 17% of the time that will be followed by an "e" and about 3% of the time this "b" will be the last letter of the word.
 /// - Given only that the last three letters in a partially-formed word are "ome", 38% of the time that's the end of the word.
 ///
@@ -75,17 +78,15 @@ struct NextStep {
 /// ```rust
 /// use letter_trie::*;
 ///
-/// let source_filename = "english_words_3_000.txt";
-/// let source_word_count = 3_000;
+/// // The words built into the crate for checking tries stand in for a list of real words here, such as
+/// // the 3,000 in "english_words_3_000.txt".
+/// let example_words: Vec<String> = verification_words_embedded(VerificationSet::GoodWords);
 ///
-///	// A million words would be fine but it's a smaller number here since this doc test will be
+/// // A million words would be fine but it's a smaller number here since this doc test will be
 /// // run repeatedly.
 /// let generated_word_count = 50_000;
 /// let depth = 3;
 ///
-/// let example_words: Vec<String> = words_from_file(&source_filename);
-/// assert_eq!(example_words.len(), source_word_count);
-///
 /// let generated_words = generate_words(&example_words, generated_word_count, depth);
 /// assert_eq!(generated_words.len(), generated_word_count);
 /// ```
@@ -100,12 +101,12 @@ pub fn generate_words(
     while set.len() < target_count {
         let mut word = String::from(START);
         while add_to_word(&sequence_map, &mut word) {}
-        let final_word: String = (&word[1..]).to_lowercase().to_owned();
+        let final_word: String = word[1..].to_lowercase().to_owned();
         //if !example_sequences.contains(&final_word)
         if !final_word.is_empty() && final_word.len() <= MAX_WORD_LENGTH {
             let set_len = set.len();
-            if set_len % 1_000 == 0 {
-                println!("[{}] {}", set.len(), &final_word);
+            if set_len.is_multiple_of(1_000) {
+                println!("[{}] {}", set.len(), final_word);
             }
             set.insert(final_word);
         }
@@ -125,11 +126,11 @@ fn add_to_word(sequence_map: &SequenceMap, word: &mut String) -> bool {
         if let Some(prefix_map) = sequence_map.get(&depth) {
             let prefix = &word[word_len - depth..].to_owned();
             if let Some(prefix_entry) = prefix_map.get(prefix) {
-                let next_step_value = random_weighted_value(&prefix_entry);
+                let next_step_value = random_weighted_value(prefix_entry);
                 if next_step_value == END {
                     return false;
                 } else {
-                    *word = format!("{}{}", &word, next_step_value);
+                    *word = format!("{}{}", word, next_step_value);
                     return true;
                 }
             }
@@ -142,14 +143,17 @@ fn add_to_word(sequence_map: &SequenceMap, word: &mut String) -> bool {
 
 fn random_weighted_value(prefix_entry: &PrefixEntry) -> String {
     let r = rand::random::<f64>();
-    let next_step = prefix_entry
+    // The ranges cover 0 to 1 between them, but rounding can leave a sliver at the top, which goes to the last.
+    prefix_entry
         .values()
         .find(|x| r >= x.range_start && r < x.range_end)
-        .unwrap();
-    next_step.value.to_owned()
+        .or_else(|| prefix_entry.values().next_back())
+        .map_or_else(|| END.to_owned(), |next_step| next_step.value.to_owned())
 }
 
 fn make_sequence_map(example_words: &[String], max_depth: usize) -> SequenceMap {
+    // A fixed pattern that's known to be valid.
+    #[allow(clippy::unwrap_used)]
     let regex = Regex::new(r"^[a-z]+$").unwrap();
     // The special characters indicating the beginning and end of a word must not be characters that can be found
     // in a word.
@@ -159,7 +163,7 @@ fn make_sequence_map(example_words: &[String], max_depth: usize) -> SequenceMap
     let mut sequence_map = SequenceMap::new();
 
     for depth in 1..=max_depth {
-        let prefix_map = sequence_map.entry(depth).or_insert_with(PrefixMap::new);
+        let prefix_map = sequence_map.entry(depth).or_default();
         for example in example_words.iter().map(|x| x.trim().to_lowercase()) {
             if regex.is_match(&example) {
                 let word = format!("{}{}{}", START, example, END);
@@ -167,10 +171,9 @@ fn make_sequence_map(example_words: &[String], max_depth: usize) -> SequenceMap
                 if last_i >= 0 {
                     for i in 0..=(last_i as usize) {
                         let prefix = word[i..i + depth].to_owned();
-                        let prefix_entry =
-                            prefix_map.entry(prefix).or_insert_with(PrefixEntry::new);
+                        let prefix_entry = prefix_map.entry(prefix).or_default();
                         let next_step_value = word[i + depth..=i + depth].to_owned();
-                        let mut next_step =
+                        let next_step =
                             prefix_entry
                                 .entry(next_step_value.clone())
                                 .or_insert(NextStep {
@@ -191,7 +194,7 @@ fn make_sequence_map(example_words: &[String], max_depth: usize) -> SequenceMap
                 .map(|next_step| next_step.count as f64)
                 .sum::<f64>();
             let mut range_start = 0.0;
-            for mut next_step in prefix_entry.values_mut() {
+            for next_step in prefix_entry.values_mut() {
                 let share = next_step.count as f64 / count_sum;
                 next_step.share = share;
                 next_step.range_start = range_start;
@@ -220,10 +223,11 @@ fn print_sequence_map(sequence_map: &SequenceMap) {
 /// Given a filename, create a Vec<String> where each entry is one word.
 /// This assumes that there is at most one word per line in the file.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This will fail if the file does not exist or can't be opened for reading.
-pub fn words_from_file(filename: &str) -> Vec<String> {
+/// Returns `LetterTrieError::File` with the file's path if the file does not exist or can't be opened or read,
+/// including when a line is not valid UTF-8.
+pub fn words_from_file(filename: &str) -> Result<Vec<String>> {
     // The None means don't check the number of words found in the file.
     words_from_file_test(filename, None)
 }
@@ -231,17 +235,23 @@ pub fn words_from_file(filename: &str) -> Vec<String> {
 /// Given a filename, create a Vec<String> where each entry is one word.
 /// This assumes that there is at most one word per line in the file.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This will fail if the file does not exist or can't be opened for reading.
+/// As for `words_from_file()`.
 ///
-/// It will also fail with an assertion error if `expected_word_count` has a value and doesn't match the
+/// # Panics
+///
+/// This will fail with an assertion error if `expected_word_count` has a value and doesn't match the
 /// number of words found in the file.
-pub fn words_from_file_test(filename: &str, expected_word_count: Option<usize>) -> Vec<String> {
-    let file = File::open(filename).unwrap();
+pub fn words_from_file_test(
+    filename: &str,
+    expected_word_count: Option<usize>,
+) -> Result<Vec<String>> {
+    let path = Path::new(filename);
+    let file = File::open(path).map_err(in_file(path))?;
     let mut v: Vec<String> = vec![];
     for line in BufReader::new(file).lines() {
-        let line = line.unwrap();
+        let line = line.map_err(in_file(path))?;
         let line = line.trim();
         if !line.is_empty() {
             v.push(line.to_string());
@@ -250,12 +260,19 @@ pub fn words_from_file_test(filename: &str, expected_word_count: Option<usize>)
     if let Some(exp_word_count) = expected_word_count {
         assert_eq!(v.len(), exp_word_count);
     }
-    v
+    Ok(v)
 }
 
-pub fn file_from_lines(filename: &str, lines: &[String]) {
-    let mut file = File::create(filename).expect("Error creating file.");
+/// Write each of `lines` to a new file, replacing any file that's already there.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::File` with the file's path if the file can't be created or written.
+pub fn file_from_lines(filename: &str, lines: &[String]) -> Result<()> {
+    let path = Path::new(filename);
+    let mut file = File::create(path).map_err(in_file(path))?;
     for line in lines {
-        writeln!(file, "{}", line).expect("Error writing a line.");
+        writeln!(file, "{}", line).map_err(in_file(path))?;
     }
+    Ok(())
 }
@@ -0,0 +1,770 @@
+//! Recording what a program does with its tries to a file that can be replayed later, so that a report such as
+//! "loads got slower" comes with a workload that can be timed against each version.
+//!
+//! While the `TraceGuard` from `record_trace()` is alive, the operations made on any trie on the thread that
+//! called it are written to the trace, and `replay_trace()` makes them again on a new trie and times each kind.
+//! The operations recorded are the loads `LetterTrie::from_dataset()`, `LetterTrie::from_file()` and
+//! `LetterTrie::from_words()`, the changes `insert()`, `insert_with_tag()`, `remove()` and `set_score()`, and the
+//! queries `contains()`, `contains_prefix()`, `find()`, `words_with_prefix()`, `count_with_prefix()`,
+//! `suggest()`, `suggest_scored()` and `did_you_mean()`. Only the operation the program called is recorded and
+//! not those it makes along the way, so a load is one line rather than one for each word, and an operation that
+//! isn't recorded shows up as the recorded ones it makes.
+//!
+//! A trace is a text file that starts with `letter_trie trace 1` and then has one operation per line, such as
+//! `insert crossed` or `suggest cr 5`: the operation's name and then its arguments separated by spaces. A load of
+//! a `Dataset` or a file gives its name or path rather than its words, so the trace of a session with a large
+//! trie stays small, but `from_words()` lists its words. The bytes of an argument that are `%`, whitespace or
+//! control characters are written as `%` and two hex digits, so every word fits on its line.
+//!
+//! A trace is replayed onto one trie, and each load starts it over, so it's meant for a session that works with
+//! one trie at a time. `LoadMethod::External` is recorded by name and replayed with its defaults.
+
+#[cfg(feature = "fs")]
+use core::cell::RefCell;
+#[cfg(feature = "fs")]
+use core::hint;
+#[cfg(feature = "fs")]
+use core::marker::PhantomData;
+#[cfg(feature = "fs")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "fs")]
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+// The first line of every trace.
+#[cfg(feature = "fs")]
+const TRACE_HEADER: &str = "letter_trie trace 1";
+
+// One recorded operation. The load methods are kept by name since LoadMethod can't be cloned.
+#[cfg_attr(not(feature = "fs"), allow(dead_code))]
+#[derive(Debug, PartialEq)]
+pub(crate) enum TraceOp {
+    #[cfg(feature = "fs")]
+    FromDataset {
+        dataset: Dataset,
+        load_method: String,
+    },
+    #[cfg(feature = "fs")]
+    FromFile {
+        filename: String,
+        is_sorted: bool,
+        load_method: String,
+    },
+    FromWords(Vec<String>),
+    Insert(String),
+    InsertWithTag(String, u64),
+    Remove(String),
+    SetScore(String, f64),
+    Contains(String),
+    ContainsPrefix(String),
+    Find(String),
+    WordsWithPrefix(String),
+    CountWithPrefix(String),
+    Suggest(String, usize),
+    SuggestScored(String, usize),
+    DidYouMean(String, usize),
+}
+
+// Run f as an operation for record_trace(), first recording op() if the thread is recording and this isn't
+// inside another operation.
+#[inline]
+pub(crate) fn traced<R>(op: impl FnOnce() -> TraceOp, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "fs")]
+    if RECORDING.load(Ordering::Relaxed) > 0 {
+        let (_depth, is_outermost) = Depth::enter();
+        if is_outermost {
+            write_op(&op());
+        }
+        return f();
+    }
+    #[cfg(not(feature = "fs"))]
+    let _ = op;
+    f()
+}
+
+// Like traced() for an operation that's recorded from what it returns, such as a load that's recorded as the
+// words it ended up with.
+#[inline]
+pub(crate) fn traced_with<R>(f: impl FnOnce() -> R, op: impl FnOnce(&R) -> TraceOp) -> R {
+    #[cfg(feature = "fs")]
+    if RECORDING.load(Ordering::Relaxed) > 0 {
+        let (_depth, is_outermost) = Depth::enter();
+        let result = f();
+        if is_outermost {
+            write_op(&op(&result));
+        }
+        return result;
+    }
+    #[cfg(not(feature = "fs"))]
+    let _ = op;
+    f()
+}
+
+// The number of threads recording a trace, so that the operations only look for a recorder when there might be
+// one.
+#[cfg(feature = "fs")]
+static RECORDING: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "fs")]
+std::thread_local! {
+    static RECORDER: RefCell<Recorder> = RefCell::new(Recorder::default());
+}
+
+#[cfg(feature = "fs")]
+#[derive(Default)]
+struct Recorder {
+    // The trace being written and its path, if the thread is recording.
+    writer: Option<BufWriter<File>>,
+    path: Option<PathBuf>,
+    // The first error writing the trace, after which nothing more is written.
+    error: Option<io::Error>,
+    // How many operations the thread is inside, so that only the outermost is recorded.
+    depth: usize,
+}
+
+// Being inside an operation, for as long as this is alive.
+#[cfg(feature = "fs")]
+struct Depth;
+
+#[cfg(feature = "fs")]
+impl Depth {
+    // Start an operation, and say whether it's the outermost one on a thread that's recording.
+    fn enter() -> (Self, bool) {
+        let is_outermost = RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            recorder.depth += 1;
+            recorder.depth == 1 && recorder.writer.is_some()
+        });
+        (Depth, is_outermost)
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Drop for Depth {
+    fn drop(&mut self) {
+        RECORDER.with(|recorder| recorder.borrow_mut().depth -= 1);
+    }
+}
+
+#[cfg(feature = "fs")]
+fn write_op(op: &TraceOp) {
+    RECORDER.with(|recorder| {
+        let recorder = &mut *recorder.borrow_mut();
+        if let (Some(writer), None) = (&mut recorder.writer, &recorder.error) {
+            if let Err(err) = writeln!(writer, "{}", op.to_line()) {
+                recorder.error = Some(err);
+            }
+        }
+    });
+}
+
+/// Start recording the operations made on this thread to a trace at `path`, replacing any file there, until the
+/// guard is dropped or `TraceGuard::finish()` is called. See the `trace` module.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::TraceInProgress` without touching the file if a trace is already being recorded on
+/// this thread, and `LetterTrieError::File` with the path if the file can't be created.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let path = std::env::temp_dir().join(format!("letter_trie_trace_doc_{}.txt", std::process::id()));
+/// let guard = record_trace(&path)?;
+/// let mut trie = NoParentLetterTrie::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous)?;
+/// trie.insert("crossbow");
+/// assert!(trie.contains("cross"));
+/// guard.finish()?;
+///
+/// let stats = replay_trace(&path, &LetterTrieType::Base)?;
+/// assert_eq!(trie.fingerprint(), stats.fingerprint);
+/// assert_eq!(1, stats.operations["insert"].count);
+/// # std::fs::remove_file(&path).unwrap();
+/// # Ok::<(), LetterTrieError>(())
+/// ```
+#[cfg(feature = "fs")]
+pub fn record_trace(path: impl AsRef<Path>) -> Result<TraceGuard> {
+    let path = path.as_ref();
+    if let Some(path) = RECORDER.with(|recorder| recorder.borrow().path.clone()) {
+        return Err(LetterTrieError::TraceInProgress { path });
+    }
+    let mut writer = BufWriter::new(File::create(path).map_err(in_file(path))?);
+    writeln!(writer, "{}", TRACE_HEADER).map_err(in_file(path))?;
+    RECORDER.with(|recorder| {
+        let mut recorder = recorder.borrow_mut();
+        recorder.writer = Some(writer);
+        recorder.path = Some(path.to_owned());
+        recorder.error = None;
+    });
+    RECORDING.fetch_add(1, Ordering::Relaxed);
+    Ok(TraceGuard {
+        path: path.to_owned(),
+        is_recording: true,
+        thread: PhantomData,
+    })
+}
+
+/// The trace being recorded by `record_trace()`, which stops when this is dropped. It can't be sent to another
+/// thread, since it's the thread that made it that's being recorded.
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct TraceGuard {
+    path: PathBuf,
+    is_recording: bool,
+    thread: PhantomData<*const ()>,
+}
+
+#[cfg(feature = "fs")]
+impl TraceGuard {
+    /// The file the trace is going to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stop recording and make sure the whole trace is in the file, which dropping the guard also does without
+    /// saying whether it worked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LetterTrieError::File` with the path if any of the trace couldn't be written.
+    pub fn finish(mut self) -> Result<()> {
+        self.stop()
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        if !self.is_recording {
+            return Ok(());
+        }
+        self.is_recording = false;
+        RECORDING.fetch_sub(1, Ordering::Relaxed);
+        let (writer, error) = RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            recorder.path = None;
+            (recorder.writer.take(), recorder.error.take())
+        });
+        match (writer, error) {
+            (_, Some(err)) => Err(in_file(&self.path)(err)),
+            (Some(mut writer), None) => writer.flush().map_err(in_file(&self.path)),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// What `replay_trace()` found.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayStats {
+    /// The timings for each kind of operation, keyed by its name in the trace such as "insert" or
+    /// "from-dataset".
+    pub operations: BTreeMap<&'static str, OperationStats>,
+    /// The time taken by all of the operations together.
+    pub total: Duration,
+    /// The `LetterTrie::fingerprint()` of the trie at the end of the trace.
+    pub fingerprint: u64,
+    /// The number of words in the trie at the end of the trace.
+    pub word_count: usize,
+}
+
+/// How many times one kind of operation was made during `replay_trace()` and how long it took.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperationStats {
+    pub count: usize,
+    pub total: Duration,
+}
+
+#[cfg(feature = "fs")]
+impl OperationStats {
+    /// The average time for one operation, or zero if there weren't any.
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total.div_f64(self.count as f64)
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl fmt::Display for ReplayStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>10} {:>14} {:>12}",
+            "operation", "count", "total", "mean"
+        )?;
+        for (name, stats) in &self.operations {
+            writeln!(
+                f,
+                "{:<20} {:>10} {:>14?} {:>12?}",
+                name,
+                stats.count,
+                stats.total,
+                stats.mean()
+            )?;
+        }
+        write!(
+            f,
+            "{} words, fingerprint {:016x}, {:?} in all",
+            self.word_count, self.fingerprint, self.total
+        )
+    }
+}
+
+/// Make the operations in the trace at `path` again on a new trie of the type `letter_trie_type`, timing each
+/// kind of operation. The trie starts out empty and each load in the trace replaces it. Nothing replayed is
+/// recorded, even if this thread is recording a trace of its own.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::File` with the path if the trace can't be opened or read,
+/// `LetterTrieError::InvalidLine` with the path and line for a line that isn't an operation, including a first
+/// line that isn't the header, and the error of a load that fails, such as `LetterTrieError::MissingDataset`. The
+/// operations before a bad line have already been made.
+///
+/// # Panics
+///
+/// As for `LetterTrie::from_file()` if the trace loads a file that can't be read.
+#[cfg(feature = "fs")]
+pub fn replay_trace(
+    path: impl AsRef<Path>,
+    letter_trie_type: &LetterTrieType,
+) -> Result<ReplayStats> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(in_file(path))?;
+    let (_depth, _) = Depth::enter();
+    let invalid = |index: usize, message: String| LetterTrieError::InvalidLine {
+        path: Some(path.to_owned()),
+        line: index + 1,
+        message,
+    };
+    let mut trie = build_trie(
+        letter_trie_type,
+        TrieSource::Words(&[]),
+        &LoadMethod::Continuous,
+    )?;
+    let mut stats = ReplayStats::default();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(in_file(path))?;
+        if index == 0 {
+            if line != TRACE_HEADER {
+                return Err(invalid(
+                    index,
+                    format!("{:?} isn't the start of a trace", line),
+                ));
+            }
+            continue;
+        }
+        let op = TraceOp::from_line(&line).map_err(|message| invalid(index, message))?;
+        let start = Instant::now();
+        op.apply(&mut trie, letter_trie_type)?;
+        let elapsed = start.elapsed();
+        let op_stats = stats.operations.entry(op.name()).or_default();
+        op_stats.count += 1;
+        op_stats.total += elapsed;
+        stats.total += elapsed;
+    }
+    stats.fingerprint = trie.fingerprint();
+    stats.word_count = trie.count_with_prefix("");
+    Ok(stats)
+}
+
+#[cfg(feature = "fs")]
+impl TraceOp {
+    fn name(&self) -> &'static str {
+        match self {
+            TraceOp::FromDataset { .. } => "from-dataset",
+            TraceOp::FromFile { .. } => "from-file",
+            TraceOp::FromWords(_) => "from-words",
+            TraceOp::Insert(_) => "insert",
+            TraceOp::InsertWithTag(..) => "insert-with-tag",
+            TraceOp::Remove(_) => "remove",
+            TraceOp::SetScore(..) => "set-score",
+            TraceOp::Contains(_) => "contains",
+            TraceOp::ContainsPrefix(_) => "contains-prefix",
+            TraceOp::Find(_) => "find",
+            TraceOp::WordsWithPrefix(_) => "words-with-prefix",
+            TraceOp::CountWithPrefix(_) => "count-with-prefix",
+            TraceOp::Suggest(..) => "suggest",
+            TraceOp::SuggestScored(..) => "suggest-scored",
+            TraceOp::DidYouMean(..) => "did-you-mean",
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let args: Vec<String> = match self {
+            TraceOp::FromDataset {
+                dataset,
+                load_method,
+            } => vec![escape(&dataset.to_string()), escape(load_method)],
+            TraceOp::FromFile {
+                filename,
+                is_sorted,
+                load_method,
+            } => vec![escape(filename), is_sorted.to_string(), escape(load_method)],
+            TraceOp::FromWords(words) => words.iter().map(|word| escape(word)).collect(),
+            TraceOp::Insert(s)
+            | TraceOp::Remove(s)
+            | TraceOp::Contains(s)
+            | TraceOp::ContainsPrefix(s)
+            | TraceOp::Find(s)
+            | TraceOp::WordsWithPrefix(s)
+            | TraceOp::CountWithPrefix(s) => vec![escape(s)],
+            TraceOp::InsertWithTag(word, tag) => vec![escape(word), tag.to_string()],
+            TraceOp::SetScore(word, score) => vec![escape(word), score.to_string()],
+            TraceOp::Suggest(s, n) | TraceOp::SuggestScored(s, n) | TraceOp::DidYouMean(s, n) => {
+                vec![escape(s), n.to_string()]
+            }
+        };
+        let mut line = self.name().to_owned();
+        for arg in args {
+            line.push(' ');
+            line.push_str(&arg);
+        }
+        line
+    }
+
+    fn from_line(line: &str) -> core::result::Result<Self, String> {
+        let mut parts = line.split(' ');
+        let name = parts.next().unwrap_or_default();
+        let args = parts
+            .map(unescape)
+            .collect::<core::result::Result<Vec<String>, String>>()?;
+        let wrong_args = || format!("{:?} doesn't have the arguments for {}", line, name);
+        let op = match (name, args.as_slice()) {
+            ("from-dataset", [dataset, load_method]) => TraceOp::FromDataset {
+                dataset: dataset.parse().map_err(|err| format!("{}", err))?,
+                load_method: load_method.clone(),
+            },
+            ("from-file", [filename, is_sorted, load_method]) => TraceOp::FromFile {
+                filename: filename.clone(),
+                is_sorted: is_sorted.parse().map_err(|_| wrong_args())?,
+                load_method: load_method.clone(),
+            },
+            ("from-words", words) => TraceOp::FromWords(words.to_vec()),
+            ("insert", [word]) => TraceOp::Insert(word.clone()),
+            ("insert-with-tag", [word, tag]) => {
+                TraceOp::InsertWithTag(word.clone(), tag.parse().map_err(|_| wrong_args())?)
+            }
+            ("remove", [word]) => TraceOp::Remove(word.clone()),
+            ("set-score", [word, score]) => {
+                TraceOp::SetScore(word.clone(), score.parse().map_err(|_| wrong_args())?)
+            }
+            ("contains", [word]) => TraceOp::Contains(word.clone()),
+            ("contains-prefix", [prefix]) => TraceOp::ContainsPrefix(prefix.clone()),
+            ("find", [prefix]) => TraceOp::Find(prefix.clone()),
+            ("words-with-prefix", [prefix]) => TraceOp::WordsWithPrefix(prefix.clone()),
+            ("count-with-prefix", [prefix]) => TraceOp::CountWithPrefix(prefix.clone()),
+            ("suggest", [prefix, n]) => {
+                TraceOp::Suggest(prefix.clone(), n.parse().map_err(|_| wrong_args())?)
+            }
+            ("suggest-scored", [prefix, n]) => {
+                TraceOp::SuggestScored(prefix.clone(), n.parse().map_err(|_| wrong_args())?)
+            }
+            ("did-you-mean", [word, n]) => {
+                TraceOp::DidYouMean(word.clone(), n.parse().map_err(|_| wrong_args())?)
+            }
+            (
+                "from-dataset" | "from-file" | "insert" | "insert-with-tag" | "remove"
+                | "set-score" | "contains" | "contains-prefix" | "find" | "words-with-prefix"
+                | "count-with-prefix" | "suggest" | "suggest-scored" | "did-you-mean",
+                _,
+            ) => return Err(wrong_args()),
+            _ => return Err(format!("{:?} isn't an operation", line)),
+        };
+        Ok(op)
+    }
+
+    // Make the operation on trie, or replace it with a new one for a load. The answers to the queries are only
+    // kept from being optimized away.
+    fn apply(
+        &self,
+        trie: &mut Box<dyn LetterTrie>,
+        letter_trie_type: &LetterTrieType,
+    ) -> Result<()> {
+        fn load<T: LetterTrie + 'static>(op: &TraceOp) -> Result<Box<dyn LetterTrie>> {
+            let load_method = |name: &str| {
+                name.parse::<LoadMethod>()
+                    .map_err(|err| LetterTrieError::InvalidFormat(err.to_string()))
+            };
+            Ok(match op {
+                TraceOp::FromDataset {
+                    dataset,
+                    load_method: name,
+                } => Box::new(T::from_dataset(dataset, &load_method(name)?)?),
+                TraceOp::FromFile {
+                    filename,
+                    is_sorted,
+                    load_method: name,
+                } => Box::new(T::from_file(filename, *is_sorted, &load_method(name)?)?),
+                TraceOp::FromWords(words) => Box::new(T::from_words(words)),
+                _ => unreachable!(),
+            })
+        }
+        match self {
+            TraceOp::FromDataset { .. } | TraceOp::FromFile { .. } | TraceOp::FromWords(_) => {
+                *trie = match letter_trie_type {
+                    LetterTrieType::Base => load::<BaseLetterTrie>(self)?,
+                    LetterTrieType::NoParent => load::<NoParentLetterTrie>(self)?,
+                };
+            }
+            TraceOp::Insert(word) => trie.insert(word),
+            TraceOp::InsertWithTag(word, tag) => trie.insert_with_tag(word, *tag),
+            TraceOp::Remove(word) => {
+                hint::black_box(trie.remove(word));
+            }
+            TraceOp::SetScore(word, score) => {
+                hint::black_box(trie.set_score(word, *score));
+            }
+            TraceOp::Contains(word) => {
+                hint::black_box(trie.contains(word));
+            }
+            TraceOp::ContainsPrefix(prefix) => {
+                hint::black_box(trie.contains_prefix(prefix));
+            }
+            TraceOp::Find(prefix) => {
+                hint::black_box(trie.find(prefix));
+            }
+            TraceOp::WordsWithPrefix(prefix) => {
+                hint::black_box(trie.words_with_prefix(prefix));
+            }
+            TraceOp::CountWithPrefix(prefix) => {
+                hint::black_box(trie.count_with_prefix(prefix));
+            }
+            TraceOp::Suggest(prefix, n) => {
+                hint::black_box(trie.suggest(prefix, *n));
+            }
+            TraceOp::SuggestScored(prefix, n) => {
+                hint::black_box(trie.suggest_scored(prefix, *n));
+            }
+            TraceOp::DidYouMean(word, n) => {
+                hint::black_box(trie.did_you_mean(word, *n));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Write the bytes of s that are '%', whitespace or control characters as '%' and two hex digits.
+#[cfg(feature = "fs")]
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '%' || c.is_whitespace() || c.is_control() {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(feature = "fs")]
+fn unescape(s: &str) -> core::result::Result<String, String> {
+    let bad = || format!("{:?} isn't escaped properly", s);
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = after.get(..2).ok_or_else(bad)?;
+            let hex = core::str::from_utf8(hex).map_err(|_| bad())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| bad())?);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| bad())
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // A session with a trie of type T, returning its fingerprint and word count at the end.
+    fn session<T: LetterTrie>() -> (u64, usize) {
+        let mut t = T::from_words(vec!["cross", "crossed", "an"]);
+        t.insert("azure");
+        t = T::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::ContinuousParallel).unwrap();
+        for word in &[
+            "crossbow",
+            "100%",
+            "two words",
+            "tab\there",
+            "ñu",
+            "line\nbreak",
+        ] {
+            t.insert(word);
+        }
+        t.insert_with_tag("crossbowman", 7);
+        assert!(t.remove("cross"));
+        assert!(!t.remove("cross"));
+        t.set_score("crossed", 1.5);
+        assert!(t.contains("two words"));
+        assert!(t.contains_prefix("cro"));
+        assert!(t.find("cre").is_some());
+        assert!(!t.words_with_prefix("").is_empty());
+        t.count_with_prefix("c");
+        t.suggest("cr", 3);
+        t.suggest_scored("cr", 3);
+        t.did_you_mean("crossd", 2);
+        // These aren't recorded themselves but make recorded operations.
+        assert!(t.words().len() > 10);
+        t.find_hamming("cross", 1);
+        (t.fingerprint(), t.count_with_prefix(""))
+    }
+
+    fn check_record_and_replay<T: LetterTrie>(name: &str) {
+        let path = temp_path(&format!("trace_{}.txt", name));
+        let guard = record_trace(&path).unwrap();
+        assert_eq!(&*path, guard.path());
+        let (fingerprint, word_count) = session::<T>();
+        guard.finish().unwrap();
+
+        let trace = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(TRACE_HEADER, lines[0]);
+        assert!(lines.contains(&"from-words an cross crossed"), "{}", trace);
+        assert!(lines.contains(&"from-dataset embedded-small continuous-parallel"));
+        assert!(lines.contains(&"insert two%20words"));
+        assert!(lines.contains(&"insert tab%09here"));
+        assert!(lines.contains(&"insert line%0Abreak"));
+        assert!(lines.contains(&"insert 100%25"));
+        assert!(lines.contains(&"insert ñu"));
+        assert!(lines.contains(&"insert-with-tag crossbowman 7"));
+        assert!(lines.contains(&"set-score crossed 1.5"));
+        assert!(lines.contains(&"did-you-mean crossd 2"));
+        assert!(lines.contains(&"words-with-prefix "));
+
+        for letter_trie_type in LetterTrieType::all() {
+            let stats = replay_trace(&path, letter_trie_type).unwrap();
+            assert_eq!(fingerprint, stats.fingerprint, "{}", letter_trie_type);
+            assert_eq!(word_count, stats.word_count);
+            assert_eq!(1, stats.operations["from-dataset"].count);
+            assert_eq!(1, stats.operations["from-words"].count);
+            assert_eq!(7, stats.operations["insert"].count);
+            assert_eq!(2, stats.operations["remove"].count);
+            assert_eq!(
+                lines.len() - 1,
+                stats.operations.values().map(|s| s.count).sum::<usize>()
+            );
+            assert!(stats.to_string().contains("from-dataset"));
+        }
+    }
+
+    #[test]
+    fn record_and_replay_base() {
+        check_record_and_replay::<BaseLetterTrie>("base");
+    }
+
+    #[test]
+    fn record_and_replay_no_parent() {
+        check_record_and_replay::<NoParentLetterTrie>("no_parent");
+    }
+
+    #[test]
+    fn only_while_recording() {
+        let path = temp_path("trace_guard.txt");
+        let mut t = NoParentLetterTrie::from_words(vec!["an"]);
+        {
+            let _guard = record_trace(&path).unwrap();
+            t.insert("cross");
+            // Another thread isn't recorded.
+            std::thread::spawn(|| NoParentLetterTrie::new().insert("elsewhere"))
+                .join()
+                .unwrap();
+            // Nor is a replay.
+            let other = temp_path("trace_guard_other.txt");
+            fs::write(&other, format!("{}\ninsert crease\n", TRACE_HEADER)).unwrap();
+            replay_trace(&other, &LetterTrieType::Base).unwrap();
+        }
+        t.insert("crossed");
+        assert_eq!(
+            format!("{}\ninsert cross\n", TRACE_HEADER),
+            fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn one_trace_at_a_time() {
+        let path = temp_path("trace_first.txt");
+        let other = temp_path("trace_second.txt");
+        let guard = record_trace(&path).unwrap();
+        NoParentLetterTrie::new().insert("cross");
+        for second in &[&path, &other] {
+            match record_trace(second) {
+                Err(LetterTrieError::TraceInProgress { path: p }) => assert_eq!(*path, p),
+                result => panic!("{:?}", result),
+            }
+        }
+        assert!(!other.exists());
+        // The trace that was already going is still recorded to the end.
+        NoParentLetterTrie::new().insert("crossed");
+        guard.finish().unwrap();
+        assert_eq!(
+            format!("{}\ninsert cross\ninsert crossed\n", TRACE_HEADER),
+            fs::read_to_string(&path).unwrap()
+        );
+        record_trace(&other).unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn escaping() {
+        for s in &[
+            "", "cross", "100%", " a b ", "tab\t", "\r\n", "ñu", "日本", "%41", "\u{2003}",
+        ] {
+            let escaped = escape(s);
+            assert!(!escaped.contains(char::is_whitespace), "{:?}", escaped);
+            assert_eq!(Ok(s.to_string()), unescape(&escaped));
+        }
+        assert_eq!("%25%20%E2%80%83", escape("% \u{2003}"));
+        assert!(unescape("%4").is_err());
+        assert!(unescape("%zz").is_err());
+        assert!(unescape("%FF").is_err());
+    }
+
+    #[test]
+    fn bad_traces() {
+        let path = temp_path("trace_bad.txt");
+        for (text, line) in &[
+            ("not a trace\n", 1),
+            ("letter_trie trace 1\ninsert a\nfrobnicate x\n", 3),
+            ("letter_trie trace 1\nsuggest cr\n", 2),
+            ("letter_trie trace 1\nsuggest cr many\n", 2),
+            ("letter_trie trace 1\ninsert a%2\n", 2),
+            ("letter_trie trace 1\nfrom-dataset huge continuous\n", 2),
+        ] {
+            fs::write(&path, text).unwrap();
+            match replay_trace(&path, &LetterTrieType::NoParent) {
+                Err(LetterTrieError::InvalidLine { line: found, .. }) => assert_eq!(*line, found),
+                other => panic!("{:?} {:?}", text, other),
+            }
+        }
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            replay_trace(&path, &LetterTrieType::NoParent),
+            Err(LetterTrieError::File { .. })
+        ));
+    }
+}
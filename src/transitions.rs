@@ -0,0 +1,317 @@
+//! A letter-to-letter transition model of the words in a trie, behind `LetterTrie::transition_model()`, for
+//! generating made-up words that are spelled like the real ones.
+//!
+//! Each word is counted as a chain of transitions from a start symbol through its letters to an end symbol, so
+//! "an" is start to 'a', 'a' to 'n' and 'n' to end. The model can keep one set of counts for every position in a
+//! word or a separate set for each position. Counts by position follow the source words more closely, such as in
+//! how long they are, while counts for every position can make words with letter pairs in places they never are
+//! in the source.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use crate::generator::SplitMix64;
+use crate::*;
+
+// The number of words in a row that generate() can fail to make within the lengths before it gives up.
+const MAX_FAILED_WORDS: usize = 1_000;
+
+/// Counts of which letter follows which in the words of a trie, from `LetterTrie::transition_model()` or
+/// `LetterTrie::transition_model_by_position()`. In the counts None stands for the start of a word before a
+/// letter and for the end of a word after one.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["an", "and", "at"]);
+/// let model = trie.transition_model();
+/// assert_eq!(3, model.count(0, None, Some('a')));
+/// assert_eq!(2, model.count(0, Some('a'), Some('n')));
+/// assert_eq!(1, model.count(0, Some('n'), None));
+///
+/// let words = model.generate(7, 5, 2, 3);
+/// assert_eq!(words, model.generate(7, 5, 2, 3));
+/// assert!(words.iter().all(|word| word.starts_with('a')));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransitionModel {
+    by_position: bool,
+    // For each position and the letter before it, or None at the start of a word, the number of times each letter
+    // comes next, or None for the end of the word. The position is always 0 unless the counts are by position.
+    counts: BTreeMap<(usize, Option<char>), BTreeMap<Option<char>, u64>>,
+}
+
+impl TransitionModel {
+    /// Returns true if there are separate counts for each position in a word.
+    pub fn by_position(&self) -> bool {
+        self.by_position
+    }
+
+    /// The number of times `to` comes after `from` at `position`, where the start of a word is at position 0, so
+    /// the first letter of a word is `to` at position 0 and its end is `None` at the position equal to its length.
+    /// The position is ignored unless the counts are by position.
+    pub fn count(&self, position: usize, from: Option<char>, to: Option<char>) -> u64 {
+        self.counts
+            .get(&(self.position(position), from))
+            .and_then(|next| next.get(&to))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The number of words the model was built from.
+    pub fn word_count(&self) -> u64 {
+        self.counts
+            .get(&(0, None))
+            .map(|next| next.values().sum())
+            .unwrap_or(0)
+    }
+
+    /// Every letter in the words the model was built from.
+    pub fn letters(&self) -> BTreeSet<char> {
+        self.counts
+            .values()
+            .flat_map(|next| next.keys().flatten().copied())
+            .collect()
+    }
+
+    /// Make up `count` words from `min_len` to `max_len` characters long, the same words for the same seed. Each
+    /// letter is picked in proportion to how often it follows the letter before it, the end of the word is only
+    /// picked from `min_len` on, and a word stops at `max_len` whether or not the model would end it there. Like
+    /// `generator::generate_words()` this can give the same word more than once.
+    ///
+    /// Fewer words are returned only when the model can't make words within the lengths at all, such as when it
+    /// was built from an empty trie or from words that are all shorter than `min_len` with counts by position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_len` is zero or greater than `max_len`.
+    pub fn generate(
+        &self,
+        rng_seed: u64,
+        count: usize,
+        min_len: usize,
+        max_len: usize,
+    ) -> Vec<String> {
+        assert!(min_len > 0, "min_len must be at least 1.");
+        assert!(min_len <= max_len, "min_len must not be more than max_len.");
+        let mut rng = SplitMix64(rng_seed);
+        let mut words: Vec<String> = Vec::with_capacity(count);
+        let mut failures = 0;
+        while words.len() < count && failures < MAX_FAILED_WORDS {
+            match self.generate_word(&mut rng, min_len, max_len) {
+                Some(word) => {
+                    words.push(word);
+                    failures = 0;
+                }
+                None => failures += 1,
+            }
+        }
+        words
+    }
+
+    // Make up one word, or None if it got to a letter that nothing can follow before it was min_len long.
+    fn generate_word(
+        &self,
+        rng: &mut SplitMix64,
+        min_len: usize,
+        max_len: usize,
+    ) -> Option<String> {
+        let mut word = String::new();
+        let mut len = 0;
+        let mut prev: Option<char> = None;
+        while len < max_len {
+            let next = self.counts.get(&(self.position(len), prev))?;
+            let can_end = len >= min_len;
+            let total: u64 = next
+                .iter()
+                .filter(|(to, _)| can_end || to.is_some())
+                .map(|(_, count)| count)
+                .sum();
+            if total == 0 {
+                return None;
+            }
+            let mut pick = rng.below(total);
+            let mut chosen = None;
+            for (to, count) in next.iter().filter(|(to, _)| can_end || to.is_some()) {
+                if pick < *count {
+                    chosen = Some(*to);
+                    break;
+                }
+                pick -= count;
+            }
+            match chosen? {
+                Some(c) => {
+                    word.push(c);
+                    len += 1;
+                    prev = Some(c);
+                }
+                None => break,
+            }
+        }
+        Some(word)
+    }
+
+    fn position(&self, position: usize) -> usize {
+        if self.by_position {
+            position
+        } else {
+            0
+        }
+    }
+
+    fn add(&mut self, position: usize, from: Option<char>, to: Option<char>, count: u64) {
+        let position = self.position(position);
+        *self
+            .counts
+            .entry((position, from))
+            .or_default()
+            .entry(to)
+            .or_default() += count;
+    }
+}
+
+// Each transition into a node is counted once for every word below the node, found by adding up the words below
+// each node as the walk leaves it as in frequency::letter_frequencies(), so every node is only looked at once.
+pub(crate) fn transition_model<T: LetterTrie + ?Sized>(
+    trie: &T,
+    by_position: bool,
+) -> TransitionModel {
+    let mut model = TransitionModel {
+        by_position,
+        counts: BTreeMap::new(),
+    };
+    // The letter and the number of words so far in the subtree of each node on the path from the root.
+    let mut path: Vec<(char, u64)> = vec![];
+    let leave = |path: &mut Vec<(char, u64)>, model: &mut TransitionModel| {
+        let Some((c, word_count)) = path.pop() else {
+            return;
+        };
+        // The node's depth, which is one more than the position of the transition into it.
+        let depth = path.len();
+        // Leaving the root adds nothing, since nothing leads into it.
+        if let Some((parent_c, parent_word_count)) = path.last_mut() {
+            let from = if depth == 1 { None } else { Some(*parent_c) };
+            if word_count > 0 {
+                model.add(depth - 1, from, Some(c), word_count);
+            }
+            *parent_word_count += word_count;
+        }
+    };
+    trie.visit("", &mut |node| {
+        while path.len() > node.depth {
+            leave(&mut path, &mut model);
+        }
+        if node.is_word {
+            model.add(node.depth, Some(node.c), None, 1);
+        }
+        path.push((node.c, node.is_word as u64));
+        true
+    });
+    while !path.is_empty() {
+        leave(&mut path, &mut model);
+    }
+    model
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    // The counts worked out one word at a time.
+    fn expected_model(words: &[String], by_position: bool) -> TransitionModel {
+        let mut model = TransitionModel {
+            by_position,
+            counts: BTreeMap::new(),
+        };
+        for word in words {
+            let mut prev = None;
+            let mut len = 0;
+            for c in word.chars() {
+                model.add(len, prev, Some(c), 1);
+                prev = Some(c);
+                len += 1;
+            }
+            model.add(len, prev, None, 1);
+        }
+        model
+    }
+
+    fn check_transition_model<T: LetterTrie>() {
+        let t = T::from_words(vec!["an", "and", "at", "ñu", "a"]);
+        let model = t.transition_model_by_position();
+        assert!(model.by_position());
+        assert_eq!(5, model.word_count());
+        assert_eq!(4, model.count(0, None, Some('a')));
+        assert_eq!(1, model.count(1, Some('a'), None));
+        assert_eq!(2, model.count(1, Some('a'), Some('n')));
+        assert_eq!(0, model.count(0, Some('a'), Some('n')));
+        assert_eq!(1, model.count(2, Some('n'), None));
+        assert_eq!(1, model.count(2, Some('u'), None));
+        assert_eq!(
+            "adntuñ".chars().collect::<BTreeSet<char>>(),
+            model.letters()
+        );
+        assert_eq!(
+            TransitionModel::default(),
+            T::from_words(Vec::<String>::new()).transition_model()
+        );
+
+        let words = words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap();
+        let t = T::from_words(&words);
+        let words: Vec<String> = t.words().collect();
+        assert_eq!(expected_model(&words, false), t.transition_model());
+        assert_eq!(
+            expected_model(&words, true),
+            t.transition_model_by_position()
+        );
+    }
+
+    #[test]
+    fn transition_model_base() {
+        check_transition_model::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn transition_model_no_parent() {
+        check_transition_model::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn generated_words() {
+        let t = NoParentLetterTrie::from_words(
+            words_from_file(Dataset::TestMediumUnsorted.filename()).unwrap(),
+        );
+        let letters = t.transition_model().letters();
+        for model in &[t.transition_model(), t.transition_model_by_position()] {
+            let words = model.generate(3, 2_000, 4, 9);
+            assert_eq!(2_000, words.len());
+            assert_eq!(words, model.generate(3, 2_000, 4, 9));
+            assert_ne!(words, model.generate(4, 2_000, 4, 9));
+            for word in &words {
+                assert!((4..=9).contains(&word.chars().count()), "{}", word);
+                assert!(word.chars().all(|c| letters.contains(&c)), "{}", word);
+            }
+        }
+
+        // Every word starts with a letter that words start with and follows a letter pair from the source.
+        let model = t.transition_model_by_position();
+        for word in model.generate(5, 1_000, 1, 20) {
+            let chars: Vec<char> = word.chars().collect();
+            assert!(model.count(0, None, Some(chars[0])) > 0, "{}", word);
+            for (i, pair) in chars.windows(2).enumerate() {
+                assert!(
+                    model.count(i + 1, Some(pair[0]), Some(pair[1])) > 0,
+                    "{}",
+                    word
+                );
+            }
+        }
+
+        // With counts by position nothing can be made longer than the longest source word.
+        let model = NoParentLetterTrie::from_words(vec!["an", "at"]).transition_model_by_position();
+        assert!(model.generate(1, 10, 3, 5).is_empty());
+        assert_eq!(10, model.generate(1, 10, 1, 5).len());
+        assert!(TransitionModel::default().generate(1, 10, 1, 5).is_empty());
+    }
+}
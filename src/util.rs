@@ -1,5 +1,43 @@
-use num_format::{Locale, ToFormattedString};
-use std::time::Instant;
+use alloc::string::{String, ToString};
+#[cfg(feature = "fs")]
+use core::fmt;
+use core::time::Duration;
+#[cfg(feature = "fs")]
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "fs")]
+use std::io::{BufRead, BufReader};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+#[cfg(feature = "fs")]
+use crate::{in_file, Result, TrieOptions};
+#[cfg(feature = "std")]
+use crate::{DisplayDetailOptions, LABEL_STEP_OVERALL};
+#[cfg(feature = "timing")]
+pub use std::time::Instant;
+
+/// A stand-in for `std::time::Instant` used when the `timing` feature is off.
+///
+/// On targets like wasm32-unknown-unknown `Instant::now()` panics, so without the feature every elapsed time is
+/// reported as zero and the load steps don't have to be written differently.
+#[cfg(all(feature = "std", not(feature = "timing")))]
+#[derive(Clone, Copy, Debug)]
+pub struct Instant;
+
+#[cfg(all(feature = "std", not(feature = "timing")))]
+impl Instant {
+    pub fn now() -> Self {
+        Instant
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+}
 
 // type_name_of() seems to dereference automatically so it can't tell the difference between a basic value and a
 // reference to that value.
@@ -75,25 +113,846 @@ pub fn format_indent(depth: usize, s: &str) -> String {
     format!("{}{}", "    ".repeat(depth), s)
 }
 
+#[cfg(feature = "std")]
 pub fn print_indent(depth: usize, s: &str) {
     println!("{}", format_indent(depth, s));
 }
 
-pub fn print_elapsed<F>(display: bool, case_label: &str, step_label: &str, mut f: F)
+/// Run `f`, then print how long it took if `display` is true. The elapsed time is returned either way.
+///
+/// The output goes to `sink` if one is given, otherwise to stdout.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut out: Vec<u8> = vec![];
+/// let elapsed = print_elapsed(true, "example", "sum", Some(&mut out), || {
+///     let _total: u64 = (0..1_000).sum();
+/// });
+/// assert_eq!(String::from_utf8(out).unwrap(), format!("\nexample: sum = {}\n", format_duration(elapsed)));
+/// ```
+#[cfg(feature = "std")]
+pub fn print_elapsed<F>(
+    display: bool,
+    case_label: &str,
+    step_label: &str,
+    sink: Option<&mut dyn Write>,
+    mut f: F,
+) -> Duration
 where
-    F: FnMut() -> (),
+    F: FnMut(),
 {
     let start = Instant::now();
     f();
-    print_elapsed_from_start(display, case_label, step_label, start);
+    print_elapsed_from_start(display, case_label, step_label, start, sink)
 }
 
-pub fn print_elapsed_from_start(display: bool, case_label: &str, step_label: &str, start: Instant) {
+/// Get the time since `start` and print it if `display` is true, to `sink` if one is given or otherwise to stdout.
+#[cfg(feature = "std")]
+pub fn print_elapsed_from_start(
+    display: bool,
+    case_label: &str,
+    step_label: &str,
+    start: Instant,
+    sink: Option<&mut dyn Write>,
+) -> Duration {
+    let elapsed = start.elapsed();
     if display {
-        println!("\n{}: {} = {:?}", case_label, step_label, start.elapsed());
+        print_duration(case_label, step_label, elapsed, sink);
+    }
+    elapsed
+}
+
+/// Print a time that has already been measured in the same format as `print_elapsed_from_start()`, using
+/// `format_duration()`.
+///
+/// Write errors on `sink` are ignored since this is only diagnostic output.
+#[cfg(feature = "std")]
+pub fn print_duration(
+    case_label: &str,
+    step_label: &str,
+    elapsed: Duration,
+    sink: Option<&mut dyn Write>,
+) {
+    match sink {
+        Some(sink) => {
+            let _ = writeln!(
+                sink,
+                "\n{}: {} = {}",
+                case_label,
+                step_label,
+                format_duration(elapsed)
+            );
+        }
+        None => println!(
+            "\n{}: {} = {}",
+            case_label,
+            step_label,
+            format_duration(elapsed)
+        ),
+    }
+}
+
+/// Times one step of a trie build and reports it through a `DisplayDetailOptions` when it's stopped or dropped,
+/// so a load method doesn't need to keep track of an `Instant` for each step.
+///
+/// The time is always recorded in `DisplayDetailOptions::step_times()` and is displayed depending on
+/// `print_step_time`, or `print_overall_time` for a timer created with `Timer::overall()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let opt = DisplayDetailOptions::make_no_display();
+/// {
+///     let _t = Timer::new("sum", &opt);
+///     let _total: u64 = (0..1_000).sum();
+/// }
+/// let elapsed = Timer::new("sum again", &opt).stop();
+///
+/// let step_times = opt.step_times();
+/// assert_eq!(("sum again".to_owned(), elapsed), step_times[1]);
+/// ```
+#[cfg(feature = "std")]
+pub struct Timer<'a> {
+    step_label: &'a str,
+    opt: &'a DisplayDetailOptions,
+    start: Instant,
+    is_overall: bool,
+    is_stopped: bool,
+    intermediate_bytes: Option<usize>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Timer<'a> {
+    /// Start timing a step.
+    pub fn new(step_label: &'a str, opt: &'a DisplayDetailOptions) -> Self {
+        Self {
+            step_label,
+            opt,
+            start: Instant::now(),
+            is_overall: false,
+            is_stopped: false,
+            intermediate_bytes: None,
+        }
+    }
+
+    /// Start timing a whole build, which is reported as the step "overall load".
+    pub fn overall(opt: &'a DisplayDetailOptions) -> Self {
+        Self {
+            step_label: LABEL_STEP_OVERALL,
+            opt,
+            start: Instant::now(),
+            is_overall: true,
+            is_stopped: false,
+            intermediate_bytes: None,
+        }
+    }
+
+    /// Get the time so far without stopping the timer.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Record an estimate of the bytes held by the data the step builds up along the way, such as the file's
+    /// contents or a vector of words, to be reported with the step in `DisplayDetailOptions::step_memory()`.
+    pub fn set_intermediate_bytes(&mut self, bytes: usize) {
+        self.intermediate_bytes = Some(bytes);
+    }
+
+    /// Report the step now rather than when the timer goes out of scope, and return its elapsed time.
+    pub fn stop(mut self) -> Duration {
+        self.report()
+    }
+
+    fn report(&mut self) -> Duration {
+        self.is_stopped = true;
+        let elapsed = self.start.elapsed();
+        self.opt.report_elapsed(
+            self.step_label,
+            elapsed,
+            self.is_overall,
+            self.intermediate_bytes,
+        );
+        elapsed
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+        if !self.is_stopped {
+            self.report();
+        }
     }
 }
 
+/// The thousands separator used when formatting counts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberStyle {
+    /// 1,234,567 as in English.
+    Comma,
+    /// 1.234.567 as in German and many other European languages.
+    Dot,
+    /// Groups separated by a thin space (U+2009), the SI style that avoids either punctuation mark.
+    Thin,
+    /// 1234567 with no separator.
+    Plain,
+}
+
+/// Format a count with commas between groups of three digits.
 pub fn format_count(val: usize) -> String {
-    val.to_formatted_string(&Locale::en)
+    format_count_with(val, NumberStyle::Comma)
+}
+
+/// Format a count with the thousands separator for `style`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// assert_eq!("1.083.388", format_count_with(1_083_388, NumberStyle::Dot));
+/// assert_eq!("1083388", format_count_with(1_083_388, NumberStyle::Plain));
+/// ```
+pub fn format_count_with(val: usize, style: NumberStyle) -> String {
+    let separator = match style {
+        NumberStyle::Comma => ',',
+        NumberStyle::Dot => '.',
+        NumberStyle::Thin => '\u{2009}',
+        NumberStyle::Plain => return val.to_string(),
+    };
+    let digits = val.to_string();
+    let mut s = String::with_capacity(digits.len() + digits.len() / 3 * separator.len_utf8());
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            s.push(separator);
+        }
+        s.push(c);
+    }
+    s
+}
+
+/// Format a duration in whichever of ns, µs, ms or s keeps the number between 1 and 1,000, with two decimal
+/// places for anything from a microsecond up.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+/// use std::time::Duration;
+///
+/// assert_eq!("20.74 µs", format_duration(Duration::from_nanos(20_741)));
+/// assert_eq!("3.50 s", format_duration(Duration::from_millis(3_500)));
+/// ```
+pub fn format_duration(d: Duration) -> String {
+    let nanos = d.as_nanos();
+    if nanos < 1_000 {
+        format!("{} ns", nanos)
+    } else if nanos < 1_000_000 {
+        format!("{:.2} µs", nanos as f64 / 1_000.0)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.2} ms", nanos as f64 / 1_000_000.0)
+    } else {
+        format!("{:.2} s", d.as_secs_f64())
+    }
+}
+
+/// Format a number of bytes in whichever of B, KB, MB or GB keeps the number under 1,024, with two decimal places
+/// for anything from a kilobyte up. The units are powers of 1,024.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// assert_eq!("512 B", format_bytes(512));
+/// assert_eq!("1.50 MB", format_bytes(1_572_864));
+/// ```
+pub fn format_bytes(bytes: usize) -> String {
+    const KB: f64 = 1_024.0;
+    let b = bytes as f64;
+    if b < KB {
+        format!("{} B", bytes)
+    } else if b < KB * KB {
+        format!("{:.2} KB", b / KB)
+    } else if b < KB * KB * KB {
+        format!("{:.2} MB", b / (KB * KB))
+    } else {
+        format!("{:.2} GB", b / (KB * KB * KB))
+    }
+}
+
+/// Get the resident set size of this process, the memory it's using right now, or None if that can't be read on
+/// this platform.
+///
+/// On Linux this is read from `/proc/self/statm` and on Windows it's the working set from
+/// `GetProcessMemoryInfo()`.
+#[cfg(feature = "std")]
+pub fn resident_memory_bytes() -> Option<usize> {
+    platform_memory::resident_bytes()
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+mod platform_memory {
+    use std::ffi::{c_int, c_long};
+    use std::fs;
+
+    // The value of _SC_PAGESIZE in glibc and musl.
+    const SC_PAGESIZE: c_int = 30;
+
+    extern "C" {
+        fn sysconf(name: c_int) -> c_long;
+    }
+
+    pub(super) fn resident_bytes() -> Option<usize> {
+        // The second field is the number of pages that are resident.
+        let statm = fs::read_to_string("/proc/self/statm").ok()?;
+        let pages: usize = statm.split_whitespace().nth(1)?.parse().ok()?;
+        // SAFETY: sysconf() only reads the name it's given.
+        let page_size = unsafe { sysconf(SC_PAGESIZE) };
+        if page_size <= 0 {
+            return None;
+        }
+        pages.checked_mul(page_size as usize)
+    }
+}
+
+#[cfg(all(feature = "std", windows))]
+mod platform_memory {
+    use std::ffi::c_void;
+    use std::mem;
+
+    // PROCESS_MEMORY_COUNTERS from psapi.h. Only the working set is read.
+    #[allow(dead_code)]
+    #[repr(C)]
+    struct ProcessMemoryCounters {
+        cb: u32,
+        page_fault_count: u32,
+        peak_working_set_size: usize,
+        working_set_size: usize,
+        quota_peak_paged_pool_usage: usize,
+        quota_paged_pool_usage: usize,
+        quota_peak_non_paged_pool_usage: usize,
+        quota_non_paged_pool_usage: usize,
+        pagefile_usage: usize,
+        peak_pagefile_usage: usize,
+    }
+
+    // Kernel32 has exported GetProcessMemoryInfo() under this name since Windows 7, so psapi.lib isn't needed.
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> *mut c_void;
+        fn K32GetProcessMemoryInfo(
+            process: *mut c_void,
+            counters: *mut ProcessMemoryCounters,
+            cb: u32,
+        ) -> i32;
+    }
+
+    pub(super) fn resident_bytes() -> Option<usize> {
+        let cb = mem::size_of::<ProcessMemoryCounters>() as u32;
+        // SAFETY: ProcessMemoryCounters matches the C struct, which is plain data that can start out as zeros, and
+        // the pseudo handle from GetCurrentProcess() doesn't need to be closed.
+        unsafe {
+            let mut counters: ProcessMemoryCounters = mem::zeroed();
+            counters.cb = cb;
+            if K32GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, cb) == 0 {
+                return None;
+            }
+            Some(counters.working_set_size)
+        }
+    }
+}
+
+#[cfg(all(feature = "std", not(any(target_os = "linux", windows))))]
+mod platform_memory {
+    pub(super) fn resident_bytes() -> Option<usize> {
+        None
+    }
+}
+
+/// What `validate_word_file()` accepts in a word file.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, Default)]
+pub struct ValidationPolicy {
+    /// The normalization a word should already have, as in `TrieOptions::normalize()`. Only the lowercasing and
+    /// `fold_diacritics` matter here.
+    pub normalization: TrieOptions,
+    /// The characters allowed in a word, or None to allow any character.
+    pub alphabet: Option<BTreeSet<char>>,
+}
+
+#[cfg(feature = "fs")]
+impl ValidationPolicy {
+    /// Create a policy that expects lowercase words and allows any character.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect words to be normalized with these options.
+    pub fn with_normalization(mut self, normalization: TrieOptions) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Only allow the characters in `alphabet`.
+    pub fn with_alphabet(mut self, alphabet: &str) -> Self {
+        self.alphabet = Some(alphabet.chars().collect());
+        self
+    }
+}
+
+/// The number of lines with one kind of problem found by `validate_word_file()`, and the first of them.
+#[cfg(feature = "fs")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineProblem {
+    pub count: usize,
+    /// The line number of the first line with the problem, starting from 1.
+    pub first_line: Option<usize>,
+}
+
+#[cfg(feature = "fs")]
+impl LineProblem {
+    fn add(&mut self, line: usize) {
+        self.count += 1;
+        self.first_line.get_or_insert(line);
+    }
+}
+
+/// The first word in a file that comes before the word on the line above it.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutOfOrder {
+    pub previous_line: usize,
+    pub previous_word: String,
+    pub line: usize,
+    pub word: String,
+}
+
+/// What `validate_word_file()` found. Each problem with a line is counted separately, so a line can count toward
+/// more than one of them, except that a blank line doesn't count toward anything else.
+///
+/// The `Display` output is a short summary for people. For tools, the fields are public and the struct can be
+/// serialized with the `serde` feature.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationReport {
+    pub line_count: usize,
+    /// The number of lines that aren't blank.
+    pub word_count: usize,
+    /// The first place where the words after trimming and normalizing aren't in order, or None if the file is
+    /// sorted.
+    pub out_of_order: Option<OutOfOrder>,
+    /// Lines that are the same word as an earlier line after trimming and normalizing.
+    pub duplicates: LineProblem,
+    /// Lines that are empty or only whitespace.
+    pub blank: LineProblem,
+    /// Lines with whitespace before or after the word.
+    pub untrimmed: LineProblem,
+    /// Lines whose word changes when it's normalized, such as by lowercasing.
+    pub unnormalized: LineProblem,
+    /// Lines with a character that isn't in `ValidationPolicy::alphabet` after normalizing.
+    pub outside_alphabet: LineProblem,
+    /// Each character outside the alphabet and the number of times it appears.
+    pub outside_alphabet_chars: BTreeMap<char, usize>,
+}
+
+#[cfg(feature = "fs")]
+impl ValidationReport {
+    /// Returns true if the words are in order.
+    pub fn is_sorted(&self) -> bool {
+        self.out_of_order.is_none()
+    }
+
+    /// Returns true if nothing is wrong with the file.
+    pub fn is_valid(&self) -> bool {
+        self.is_sorted()
+            && [
+                self.duplicates,
+                self.blank,
+                self.untrimmed,
+                self.unnormalized,
+                self.outside_alphabet,
+            ]
+            .iter()
+            .all(|problem| problem.count == 0)
+    }
+}
+
+#[cfg(feature = "fs")]
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} lines, {} words",
+            format_count(self.line_count),
+            format_count(self.word_count)
+        )?;
+        match &self.out_of_order {
+            None => writeln!(f, "sorted")?,
+            Some(o) => writeln!(
+                f,
+                "not sorted: {:?} on line {} comes before {:?} on line {}",
+                o.word, o.line, o.previous_word, o.previous_line
+            )?,
+        }
+        for (label, problem) in &[
+            ("duplicates", self.duplicates),
+            ("blank lines", self.blank),
+            ("lines needing trimming", self.untrimmed),
+            ("lines needing normalizing", self.unnormalized),
+            (
+                "lines with characters outside the alphabet",
+                self.outside_alphabet,
+            ),
+        ] {
+            if let Some(first_line) = problem.first_line {
+                writeln!(
+                    f,
+                    "{}: {} (first on line {})",
+                    label,
+                    format_count(problem.count),
+                    first_line
+                )?;
+            }
+        }
+        if !self.outside_alphabet_chars.is_empty() {
+            let chars: Vec<String> = self
+                .outside_alphabet_chars
+                .iter()
+                .map(|(c, count)| format!("{:?} x {}", c, count))
+                .collect();
+            writeln!(f, "characters outside the alphabet: {}", chars.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a word file once, one word per line, and report whether it's sorted and how many lines are blank,
+/// duplicated, untrimmed, not normalized, or have characters outside the alphabet, as `policy` defines them.
+///
+/// The order and duplicates are judged on the words as they'd be stored in a trie, after trimming and normalizing.
+///
+/// # Errors
+///
+/// Returns `LetterTrieError::Io` if the file can't be opened or read, including when it isn't valid UTF-8.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// // The same untidy lines as the small sorted test file, with "AN" after "and" among them.
+/// let path = std::env::temp_dir().join(format!("letter_trie_validate_doc_{}.txt", std::process::id()));
+/// let content = "a\nand        \nAN\t\t\nazure\n\ncrease\nCREATIVE\n\ncreator\n\t\tCreature\ncross\ncrossed\n\n";
+/// std::fs::write(&path, content).unwrap();
+/// let policy = ValidationPolicy::new().with_alphabet("abcdefghijklmnopqrstuvwxyz");
+/// let report = validate_word_file(&path, &policy).unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// assert_eq!(10, report.word_count);
+/// assert_eq!(Some(3), report.out_of_order.as_ref().map(|o| o.line));
+/// assert_eq!(3, report.blank.count);
+/// assert_eq!(3, report.untrimmed.count);
+/// assert_eq!(Some(3), report.unnormalized.first_line);
+/// assert_eq!(0, report.outside_alphabet.count);
+/// assert!(!report.is_valid());
+/// ```
+#[cfg(feature = "fs")]
+pub fn validate_word_file(
+    path: impl AsRef<Path>,
+    policy: &ValidationPolicy,
+) -> Result<ValidationReport> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path).map_err(in_file(path))?);
+    let mut report = ValidationReport::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut previous: Option<(usize, String)> = None;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(in_file(path))?;
+        let line_number = index + 1;
+        report.line_count = line_number;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            report.blank.add(line_number);
+            continue;
+        }
+        report.word_count += 1;
+        if trimmed.len() != line.len() {
+            report.untrimmed.add(line_number);
+        }
+        let word = policy.normalization.normalize(trimmed);
+        if word != trimmed {
+            report.unnormalized.add(line_number);
+        }
+        if let Some(alphabet) = &policy.alphabet {
+            let mut is_outside = false;
+            for c in word.chars().filter(|c| !alphabet.contains(c)) {
+                *report.outside_alphabet_chars.entry(c).or_default() += 1;
+                is_outside = true;
+            }
+            if is_outside {
+                report.outside_alphabet.add(line_number);
+            }
+        }
+        if let Some((previous_line, previous_word)) = &previous {
+            if report.out_of_order.is_none() && word < *previous_word {
+                report.out_of_order = Some(OutOfOrder {
+                    previous_line: *previous_line,
+                    previous_word: previous_word.clone(),
+                    line: line_number,
+                    word: word.clone(),
+                });
+            }
+        }
+        if seen.contains(&word) {
+            report.duplicates.add(line_number);
+        } else {
+            seen.insert(word.clone());
+        }
+        previous = Some((line_number, word));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fs")]
+    use crate::{temp_path, LetterTrieError};
+    #[cfg(feature = "timing")]
+    use std::thread;
+
+    #[test]
+    fn format_counts() {
+        let cases: [(usize, [&str; 4]); 4] = [
+            (0, ["0", "0", "0", "0"]),
+            (999, ["999", "999", "999", "999"]),
+            (1000, ["1,000", "1.000", "1\u{2009}000", "1000"]),
+            (
+                1_234_567_890,
+                [
+                    "1,234,567,890",
+                    "1.234.567.890",
+                    "1\u{2009}234\u{2009}567\u{2009}890",
+                    "1234567890",
+                ],
+            ),
+        ];
+        let styles = [
+            NumberStyle::Comma,
+            NumberStyle::Dot,
+            NumberStyle::Thin,
+            NumberStyle::Plain,
+        ];
+        for (val, expected) in &cases {
+            for (style, expected) in styles.iter().zip(expected) {
+                assert_eq!(*expected, format_count_with(*val, *style));
+            }
+        }
+        assert_eq!("1,234,567,890", format_count(1_234_567_890));
+    }
+
+    #[test]
+    fn format_durations() {
+        for (nanos, expected) in &[
+            (0, "0 ns"),
+            (999, "999 ns"),
+            (1000, "1.00 µs"),
+            (999_994, "999.99 µs"),
+            (1_500_000, "1.50 ms"),
+            (1_234_567_890, "1.23 s"),
+            (61_000_000_000, "61.00 s"),
+        ] {
+            assert_eq!(*expected, format_duration(Duration::from_nanos(*nanos)));
+        }
+    }
+
+    #[test]
+    fn format_byte_counts() {
+        for (bytes, expected) in &[
+            (0, "0 B"),
+            (1_023, "1023 B"),
+            (1_024, "1.00 KB"),
+            (12_999_999, "12.40 MB"),
+            (3 << 30, "3.00 GB"),
+        ] {
+            assert_eq!(*expected, format_bytes(*bytes));
+        }
+    }
+
+    #[cfg(feature = "timing")]
+    #[test]
+    fn print_elapsed_to_sink() {
+        let mut inner = Duration::from_secs(0);
+        let mut inner_out: Vec<u8> = vec![];
+        let mut out: Vec<u8> = vec![];
+        let outer = print_elapsed(true, "case", "outer", Some(&mut out), || {
+            inner = print_elapsed(true, "case", "inner", Some(&mut inner_out), || {
+                thread::sleep(Duration::from_millis(2))
+            });
+        });
+        assert!(inner >= Duration::from_millis(2));
+        assert!(outer >= inner);
+        assert_eq!(
+            format!("\ncase: inner = {}\n", format_duration(inner)),
+            String::from_utf8(inner_out).unwrap()
+        );
+        assert_eq!(
+            format!("\ncase: outer = {}\n", format_duration(outer)),
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out: Vec<u8> = vec![];
+        let elapsed = print_elapsed(false, "case", "hidden", Some(&mut out), || {
+            thread::sleep(Duration::from_millis(1))
+        });
+        assert!(elapsed >= Duration::from_millis(1));
+        assert!(out.is_empty());
+    }
+
+    #[cfg(feature = "timing")]
+    #[test]
+    fn timer_reports_on_stop_and_drop() {
+        let opt = DisplayDetailOptions::make_no_display();
+        {
+            let overall = Timer::overall(&opt);
+            {
+                let _step = Timer::new("first", &opt);
+                thread::sleep(Duration::from_millis(1));
+            }
+            let step = Timer::new("second", &opt);
+            thread::sleep(Duration::from_millis(1));
+            assert!(step.elapsed() >= Duration::from_millis(1));
+            step.stop();
+            drop(overall);
+        }
+        let step_times = opt.step_times();
+        let labels: Vec<&str> = step_times.iter().map(|(step, _)| step.as_str()).collect();
+        assert_eq!(labels, vec!["first", "second", LABEL_STEP_OVERALL]);
+        let durations: Vec<Duration> = step_times.iter().map(|(_, elapsed)| *elapsed).collect();
+        assert!(durations[0] >= Duration::from_millis(1));
+        assert!(durations[1] >= Duration::from_millis(1));
+        assert!(durations[2] >= durations[0] + durations[1]);
+    }
+
+    // Validate a fixture file with the given contents.
+    #[cfg(feature = "fs")]
+    fn validate(name: &str, contents: &[u8], policy: &ValidationPolicy) -> ValidationReport {
+        let path = temp_path(&format!("validate_{}.txt", name));
+        std::fs::write(&path, contents).unwrap();
+        validate_word_file(&path, policy).unwrap()
+    }
+
+    #[cfg(feature = "fs")]
+    fn problem(count: usize, first_line: usize) -> LineProblem {
+        LineProblem {
+            count,
+            first_line: Some(first_line),
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn validate_order_and_duplicates() {
+        let policy = ValidationPolicy::new();
+        let report = validate("unsorted", b"an\ncross\nazure\ncrossed\n", &policy);
+        assert_eq!(
+            Some(OutOfOrder {
+                previous_line: 2,
+                previous_word: "cross".to_owned(),
+                line: 3,
+                word: "azure".to_owned(),
+            }),
+            report.out_of_order
+        );
+        assert_eq!(4, report.word_count);
+        assert!(!report.is_valid());
+
+        let report = validate("duplicates", b"an\nan\ncross\nAN\n", &policy);
+        assert_eq!(problem(2, 2), report.duplicates);
+        assert_eq!(problem(1, 4), report.unnormalized);
+        assert_eq!(Some(4), report.out_of_order.map(|o| o.line));
+
+        let report = validate("sorted", b"an\nand\ncross\n", &policy);
+        assert!(report.is_valid());
+        assert_eq!("3 lines, 3 words\nsorted\n", report.to_string());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn validate_lines() {
+        let policy = ValidationPolicy::new();
+        let report = validate("blank", b"an\n\n   \r\ncross", &policy);
+        assert_eq!(4, report.line_count);
+        assert_eq!(2, report.word_count);
+        assert_eq!(problem(2, 2), report.blank);
+        assert_eq!(LineProblem::default(), report.untrimmed);
+
+        let report = validate("untrimmed", b"an\n cross\ncrossed \t\r\n", &policy);
+        assert_eq!(problem(2, 2), report.untrimmed);
+        assert!(report.is_sorted());
+
+        let folding = ValidationPolicy::new()
+            .with_normalization(TrieOptions::new().with_fold_diacritics(true));
+        let report = validate("folding", "ni\u{f1}o\nnino\n".as_bytes(), &folding);
+        assert_eq!(problem(1, 1), report.unnormalized);
+        assert_eq!(problem(1, 2), report.duplicates);
+        let report = validate("not_folding", "ni\u{f1}o\nnino\n".as_bytes(), &policy);
+        // Without folding they're different words, and "niño" comes after "nino".
+        assert_eq!(LineProblem::default(), report.duplicates);
+        assert_eq!(LineProblem::default(), report.unnormalized);
+        assert!(!report.is_sorted());
+        let report = validate("order", "nino\nni\u{f1}o\n".as_bytes(), &policy);
+        assert!(report.is_valid());
+
+        assert!(matches!(
+            validate_word_file(
+                std::env::temp_dir().join("letter_trie_no_such_file.txt"),
+                &policy
+            ),
+            Err(LetterTrieError::File { .. })
+        ));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn validate_alphabet() {
+        let policy = ValidationPolicy::new().with_alphabet("abcdefghijklmnopqrstuvwxyz");
+        let report = validate(
+            "alphabet",
+            "cafe\ncaf\u{e9}\nit's\nits\n".as_bytes(),
+            &policy,
+        );
+        assert_eq!(problem(2, 2), report.outside_alphabet);
+        assert_eq!(
+            vec![('\'', 1), ('\u{e9}', 1)],
+            report
+                .outside_alphabet_chars
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        let report = validate("alphabet_display", b"it's\nIt's\n", &policy);
+        assert_eq!(
+            "2 lines, 2 words\nsorted\nduplicates: 1 (first on line 2)\nlines needing normalizing: 1 (first on \
+            line 2)\nlines with characters outside the alphabet: 2 (first on line 1)\ncharacters outside the \
+            alphabet: '\\'' x 2\n",
+            report.to_string()
+        );
+
+        let path = temp_path("validate_utf8.txt");
+        std::fs::write(&path, b"an\n\xff\n").unwrap();
+        match validate_word_file(&path, &policy) {
+            Err(err @ LetterTrieError::File { .. }) => {
+                assert!(err.to_string().contains(path.to_str().unwrap()), "{}", err)
+            }
+            other => panic!("{:?}", other),
+        }
+    }
 }
@@ -0,0 +1,270 @@
+//! A read-only borrow of a trie, from `LetterTrie::view()`, for handing a trie to code such as a plugin that
+//! should only query it.
+//!
+//! A `TrieView` is a reference to the trie with only the queries on it, so a function that takes one says in its
+//! signature that it won't change the words, and nothing it's given lets it call `insert()`, `remove()` or the other
+//! methods that take `&mut self`. It's the size of that reference and is `Copy`, so it can be passed around as freely
+//! as the reference itself.
+//!
+//! The queries are the methods of `TrieQuery`, which `LetterTrie` has as a supertrait. A view gives the trie's own
+//! `find()`, `visit()`, `cursor()` and the other methods that each trie implements, along with the ones a trie has a
+//! faster way to answer such as `contains()`, and the rest run the same default bodies for the view as for the trie,
+//! so the answers are the same as asking the trie directly.
+//!
+//! A view is generic over the type of trie, so calls through a `TrieView<NoParentLetterTrie>` from
+//! `NoParentLetterTrie::view()` go straight to that trie's methods. The type defaults to `dyn LetterTrie`, so
+//! `TrieView<'a>` can be written in a signature to take a view of any trie, and `into_dyn()` gives one. The
+//! `LetterTrie` methods that are generic, such as `find_chars()` and `for_each_word_with_prefix()`, aren't queries
+//! of a view. `visit()` and `cursor()` cover what they do.
+
+use crate::*;
+
+// Keeps TrieQuery from being implemented outside of the crate, so that methods can be added to it.
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
+
+/// A read-only view of a trie from `LetterTrie::view()` or `TrieView::new()`. See the `view` module.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// fn count_plurals(view: TrieView) -> usize {
+///     view.words().filter(|word| word.ends_with('s') && view.contains(&word[..word.len() - 1])).count()
+/// }
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["cross", "crosses", "crease", "creases", "an"]);
+/// let view = trie.view();
+/// assert_eq!(1, count_plurals(view.into_dyn()));
+/// assert_eq!(5, view.word_count());
+/// assert_eq!(vec!["crease", "creases"], view.words_with_prefix("CREA"));
+///
+/// let mut cursor = view.cursor();
+/// assert!(cursor.step_chars("cross".chars()));
+/// assert!(cursor.is_word());
+/// ```
+///
+/// Nothing that changes the trie can be called through a view:
+///
+/// ```compile_fail,E0599
+/// use letter_trie::*;
+///
+/// let trie = NoParentLetterTrie::from_words(vec!["cross"]);
+/// let view = trie.view();
+/// view.insert("crossed");
+/// ```
+///
+/// cbindgen:ignore
+pub struct TrieView<'a, T: LetterTrie + ?Sized = dyn LetterTrie + 'a> {
+    trie: &'a T,
+}
+
+// Written out rather than derived, since a derive would require T: Clone and T: Copy.
+impl<T: LetterTrie + ?Sized> Clone for TrieView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: LetterTrie + ?Sized> Copy for TrieView<'_, T> {}
+
+impl<'a, T: LetterTrie + ?Sized> TrieView<'a, T> {
+    /// Make a view of a trie, such as one held as a `Box<dyn LetterTrie>`.
+    pub fn new(trie: &'a T) -> Self {
+        Self { trie }
+    }
+
+    /// Get the number of words in the trie.
+    pub fn word_count(&self) -> usize {
+        self.trie.count_with_prefix("")
+    }
+
+    /// Get the number of nodes in the trie, counting the root.
+    pub fn node_count(&self) -> usize {
+        self.trie.to_fixed_node().node_count
+    }
+}
+
+impl<'a, T: LetterTrie + 'a> TrieView<'a, T> {
+    /// Make this into a view of a `dyn LetterTrie`, as for a function that takes a view of any kind of trie.
+    pub fn into_dyn(self) -> TrieView<'a> {
+        TrieView { trie: self.trie }
+    }
+}
+
+impl<T: LetterTrie + ?Sized> sealed::Sealed for TrieView<'_, T> {}
+
+// Only the methods that each trie implements for itself, and those it has a faster way to answer, are passed along
+// to the trie. The rest are the default bodies in TrieQuery, built on these.
+impl<T: LetterTrie + ?Sized> TrieQuery for TrieView<'_, T> {
+    fn tag(&self, word: &str) -> Option<u64> {
+        self.trie.tag(word)
+    }
+
+    fn sources(&self, word: &str) -> Option<&[u16]> {
+        self.trie.sources(word)
+    }
+
+    fn spelling(&self, word: &str) -> Option<&str> {
+        self.trie.spelling(word)
+    }
+
+    fn score(&self, word: &str) -> Option<f64> {
+        self.trie.score(word)
+    }
+
+    fn rank_of(&self, word: &str) -> Option<u32> {
+        self.trie.rank_of(word)
+    }
+
+    fn find(&self, prefix: &str) -> Option<FixedNode> {
+        self.trie.find(prefix)
+    }
+
+    fn to_fixed_node(&self) -> FixedNode {
+        self.trie.to_fixed_node()
+    }
+
+    fn visit(&self, prefix: &str, f: &mut dyn FnMut(&NodeView) -> bool) {
+        self.trie.visit(prefix, f)
+    }
+
+    fn visit_rev(&self, prefix: &str, f: &mut dyn FnMut(&NodeView) -> bool) {
+        self.trie.visit_rev(prefix, f)
+    }
+
+    fn cursor(&self) -> TrieCursor<'_> {
+        self.trie.cursor()
+    }
+
+    fn children(&self, prefix: &str) -> Vec<char> {
+        self.trie.children(prefix)
+    }
+
+    fn next_letters(&self, prefix: &str) -> Vec<(char, bool)> {
+        self.trie.next_letters(prefix)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.trie.contains(word)
+    }
+
+    fn contains_prefix(&self, prefix: &str) -> bool {
+        self.trie.contains_prefix(prefix)
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    // Every query through the view gives what the trie gives, for each prefix in the trie and some that aren't.
+    fn check_view_parity<T: LetterTrie>() {
+        let mut t = T::from_dataset(&Dataset::EmbeddedSmall, &LoadMethod::Continuous).unwrap();
+        t.insert_with_tag("crossed", 7);
+        t.insert_with_source("crease", 3);
+        t.insert_preserving_case("Creator", CasePreference::FirstSeen);
+        t.set_score("cross", 0.5);
+        t.set_rank("crease", 2);
+        let view = t.view();
+        let copy = view;
+        let erased = view.into_dyn();
+
+        let mut probes = vec![
+            String::new(),
+            "CR".to_owned(),
+            "xyzzy".to_owned(),
+            "crossedd".to_owned(),
+        ];
+        probes.extend(t.words_with_prefix("c"));
+        t.visit("cr", &mut |node| {
+            probes.push(node.prefix.to_owned());
+            true
+        });
+        let limits = QueryLimits::new(3, 20);
+        for probe in &probes {
+            assert_eq!(t.find(probe), view.find(probe), "{}", probe);
+            assert_eq!(t.lookup(probe), view.lookup(probe), "{}", probe);
+            assert_eq!(t.contains(probe), view.contains(probe), "{}", probe);
+            assert_eq!(t.contains_prefix(probe), view.contains_prefix(probe));
+            assert_eq!(t.children(probe), view.children(probe), "{}", probe);
+            assert_eq!(t.next_letters(probe), view.next_letters(probe));
+            assert_eq!(t.words_with_prefix(probe), view.words_with_prefix(probe));
+            assert_eq!(
+                t.words_with_prefix_limited(probe, &limits),
+                view.words_with_prefix_limited(probe, &limits)
+            );
+            assert_eq!(t.neighbors(probe, 2, 2), view.neighbors(probe, 2, 2));
+            assert_eq!(t.count_with_prefix(probe), copy.count_with_prefix(probe));
+            assert_eq!(t.suggest(probe, 3), copy.suggest(probe, 3));
+            assert_eq!(t.suggest_scored(probe, 3), copy.suggest_scored(probe, 3));
+            assert_eq!(t.suggest_ranked(probe, 3), copy.suggest_ranked(probe, 3));
+            assert_eq!(t.did_you_mean(probe, 3), copy.did_you_mean(probe, 3));
+            assert_eq!(t.tag(probe), copy.tag(probe), "{}", probe);
+            assert_eq!(t.sources(probe), copy.sources(probe), "{}", probe);
+            assert_eq!(t.spelling(probe), copy.spelling(probe), "{}", probe);
+            assert_eq!(t.score(probe), copy.score(probe), "{}", probe);
+            assert_eq!(t.rank_of(probe), copy.rank_of(probe), "{}", probe);
+            assert_eq!(t.find(probe), erased.find(probe), "{}", probe);
+            assert_eq!(t.lookup(probe), erased.lookup(probe), "{}", probe);
+            assert_eq!(t.suggest(probe, 3), erased.suggest(probe, 3));
+            let mut cursor = view.cursor();
+            assert_eq!(t.contains_prefix(probe), cursor.step_chars(probe.chars()));
+            assert_eq!(t.contains(probe), cursor.is_word(), "{}", probe);
+        }
+        assert_eq!(Some(7), view.tag("crossed"));
+        assert_eq!(Some(&[3][..]), view.sources("crease"));
+        assert_eq!(Some("Creator"), view.spelling("creator"));
+        assert_eq!(Some(0.5), view.score("cross"));
+        assert_eq!(Some(2), view.rank_of("crease"));
+
+        assert_eq!(
+            t.words().collect::<Vec<_>>(),
+            view.words().collect::<Vec<_>>()
+        );
+        assert_eq!(t.count_with_prefix(""), view.word_count());
+        assert_eq!(t.to_fixed_node().node_count, view.node_count());
+        assert_eq!(t.fingerprint(), erased.fingerprint());
+        assert!(erased.self_test(&SelfTestSpec::default()).passed());
+        for prefix in &["", "cr", "q"] {
+            let mut expected = vec![];
+            t.visit_rev(prefix, &mut |node| {
+                expected.push(node.prefix.to_owned());
+                node.depth < 3
+            });
+            let mut seen = vec![];
+            view.visit_rev(prefix, &mut |node| {
+                seen.push(node.prefix.to_owned());
+                node.depth < 3
+            });
+            assert_eq!(expected, seen);
+        }
+    }
+
+    #[test]
+    fn view_parity_base() {
+        check_view_parity::<BaseLetterTrie>();
+    }
+
+    #[test]
+    fn view_parity_no_parent() {
+        check_view_parity::<NoParentLetterTrie>();
+    }
+
+    #[test]
+    fn view_of_boxed_trie() {
+        let words: Vec<String> = vec!["cross".into(), "crossed".into(), "an".into()];
+        let boxed = build_trie(
+            &LetterTrieType::NoParent,
+            TrieSource::Words(&words),
+            &LoadMethod::Continuous,
+        )
+        .unwrap();
+        let view = TrieView::new(&*boxed);
+        assert_eq!(3, view.word_count());
+        assert_eq!(10, view.node_count());
+        assert_eq!(boxed.find("cross"), view.find("cross"));
+    }
+}
@@ -0,0 +1,19 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+const WORD_FILE: &str = "../english_words_10_unsorted.txt";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", WORD_FILE);
+    let words: Vec<String> = fs::read_to_string(WORD_FILE)
+        .expect("Error reading the word file.")
+        .lines()
+        .map(str::to_owned)
+        .collect();
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("small_trie.rs");
+    let mut out = BufWriter::new(File::create(path).expect("Error creating the generated file."));
+    letter_trie::codegen::generate_static_trie(&words, &mut out)
+        .expect("Error writing the generated file.");
+}
@@ -0,0 +1,7 @@
+//! The small dataset compiled into the program by build.rs.
+
+use letter_trie::static_trie::StaticLetterTrie;
+
+include!(concat!(env!("OUT_DIR"), "/small_trie.rs"));
+
+pub static SMALL_TRIE: StaticLetterTrie = StaticLetterTrie::new(&LETTER_TRIE_NODES);
@@ -0,0 +1,50 @@
+use letter_trie::*;
+use static_dictionary::{LETTER_TRIE_NODES, SMALL_TRIE};
+
+#[test]
+fn small_root() {
+    assert_small_root(&SMALL_TRIE.to_fixed_node());
+    assert_eq!(28, LETTER_TRIE_NODES.len());
+}
+
+#[test]
+fn small_queries() {
+    assert!(SMALL_TRIE.contains("azure"));
+    assert!(SMALL_TRIE.contains("Creature"));
+    assert!(!SMALL_TRIE.contains("azur"));
+    assert!(!SMALL_TRIE.contains(""));
+    assert_eq!(
+        vec!["a", "an", "and", "azure"],
+        SMALL_TRIE.words_with_prefix("a")
+    );
+    assert_eq!(
+        vec!["cross", "crossed"],
+        SMALL_TRIE.words_with_prefix("CROSS")
+    );
+    assert!(SMALL_TRIE.words_with_prefix("b").is_empty());
+    let cross = SMALL_TRIE.find("cross").unwrap();
+    assert_eq!(
+        ("cross", true, 2, 3),
+        (
+            cross.prefix.as_str(),
+            cross.is_word,
+            cross.word_count,
+            cross.height
+        )
+    );
+    assert_eq!(None, SMALL_TRIE.find("crox"));
+}
+
+#[test]
+fn matches_loaded_trie() {
+    let loaded = NoParentLetterTrie::from_reader(
+        include_str!("../../english_words_10_unsorted.txt").as_bytes(),
+        false,
+        &LoadMethod::Continuous,
+    )
+    .unwrap();
+    assert_eq!(
+        loaded.words_with_prefix(""),
+        SMALL_TRIE.words_with_prefix("")
+    );
+}
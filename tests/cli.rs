@@ -0,0 +1,36 @@
+//! Runs the letter-trie binary to check what it prints to stderr and the exit codes it gives.
+#![cfg(feature = "cli")]
+
+use std::process::Command;
+
+const EXIT_ERROR: i32 = 2;
+
+#[test]
+fn missing_file_names_path() {
+    let path = "no_such_dir/no_such_words.txt";
+    for args in &[
+        &["stats", path][..],
+        &["lookup", path, "word"],
+        &["build", path, "-o", "no_such_dir/words.bin"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_letter-trie"))
+            .args(*args)
+            .output()
+            .unwrap();
+        assert_eq!(Some(EXIT_ERROR), output.status.code());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(path), "{:?} gave {}", args, stderr);
+    }
+}
+
+#[test]
+fn unwritable_output_names_path() {
+    let output_path = "no_such_dir/words.bin";
+    let output = Command::new(env!("CARGO_BIN_EXE_letter-trie"))
+        .args(["build", "english_words_10_sorted.txt", "-o", output_path])
+        .output()
+        .unwrap();
+    assert_eq!(Some(EXIT_ERROR), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(output_path), "{}", stderr);
+}